@@ -0,0 +1,49 @@
+//! Parses and standardizes a hard-coded address with the USPS.
+//!
+//! `cargo run --example standardize`
+
+use adr::io::{default_headers, new_client};
+use adr::models::{Address, Source};
+use adr::prsr::Prsr;
+use adr::usps::{Standardizer, Usps};
+use anyhow::Result;
+
+const LNES: [&str; 2] = ["1600 Pennsylvania Avenue NW", "Washington, DC 20500"];
+
+#[tokio::main]
+async fn main() -> Result<()> {
+    // Parse lines to addresses.
+    let adrs = prs(&LNES)?;
+
+    // Standardize each address.
+    let usps = Usps::new(new_client(default_headers())?);
+    for mut adr in adrs {
+        usps.standardize(&mut adr).await?;
+        println!("{adr}");
+    }
+
+    Ok(())
+}
+
+/// Edits and parses address lines, as a source does before standardization.
+fn prs(lnes: &[&str]) -> Result<Vec<Address>> {
+    let mut lnes: Vec<String> = lnes.iter().map(|lne| lne.to_uppercase()).collect();
+    let prsr = Prsr::new();
+    prsr.edit_lnes(&mut lnes, Source::Observer);
+    Ok(prsr.prs_adrs(&lnes)?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_prs() {
+        let adrs = prs(&LNES).unwrap();
+        assert_eq!(adrs.len(), 1);
+        assert_eq!(
+            adrs[0].to_string(),
+            "1600 PENNSYLVANIA AVENUE NW,,WASHINGTON,DC,20500,0,"
+        );
+    }
+}
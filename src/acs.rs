@@ -0,0 +1,368 @@
+use crate::io::*;
+use crate::mailing::*;
+use crate::models::*;
+use crate::suppression::*;
+use crate::usps::*;
+use anyhow::{anyhow, Result};
+use serde::Deserialize;
+use std::fmt;
+use std::fs;
+use std::path::Path;
+use std::str::FromStr;
+
+/// How the USPS handled an addressed mailpiece.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Disposition {
+    /// Forwarded or returned with a new address of the addressee.
+    Corrected,
+    /// Returned without a new address, a Nixie.
+    Undeliverable,
+}
+impl fmt::Display for Disposition {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            Disposition::Corrected => write!(f, "corrected"),
+            Disposition::Undeliverable => write!(f, "undeliverable"),
+        }
+    }
+}
+impl FromStr for Disposition {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s.to_lowercase().as_str() {
+            "corrected" => Ok(Disposition::Corrected),
+            "undeliverable" | "nixie" => Ok(Disposition::Undeliverable),
+            _ => Err(anyhow!("unknown disposition '{s}'")),
+        }
+    }
+}
+
+/// A row of an address correction CSV.
+///
+/// Address fields are empty for an undeliverable mailpiece.
+#[derive(Debug, Deserialize)]
+struct CorrectionRow {
+    serial_id: String,
+    disposition: String,
+    #[serde(default)]
+    address1: String,
+    #[serde(default)]
+    address2: String,
+    #[serde(default)]
+    city: String,
+    #[serde(default)]
+    state: String,
+    #[serde(default)]
+    zip5: Option<u32>,
+    #[serde(default)]
+    zip4: Option<u16>,
+}
+
+impl CorrectionRow {
+    /// Corrected address of the row, before standardization.
+    fn adr(&self) -> Result<Address> {
+        let zip5 = self
+            .zip5
+            .ok_or_else(|| anyhow!("corrected address: missing zip5"))?;
+        if self.address1.is_empty() || self.city.is_empty() || self.state.is_empty() {
+            return Err(anyhow!(
+                "corrected address: missing address1, city or state"
+            ));
+        }
+        Ok(Address {
+            address1: self.address1.to_uppercase(),
+            address2: string_to_opt(self.address2.to_uppercase()),
+            city: self.city.to_uppercase(),
+            state: self.state.to_uppercase(),
+            zip5,
+            zip4: self.zip4.unwrap_or_default(),
+            ..Default::default()
+        })
+    }
+}
+
+/// Results of applying address corrections.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct CorrectionStats {
+    /// Addresses replaced by corrected addresses.
+    pub corrected: usize,
+    /// Suppressions of undeliverable addresses.
+    pub undeliverable: Vec<Suppression>,
+    /// Rows matching no mailpiece or person, or failing standardization.
+    pub unmatched: Vec<String>,
+}
+
+impl CorrectionStats {
+    /// Summary lines, such as `2 corrected`.
+    pub fn lnes(&self) -> Vec<String> {
+        let mut lnes = vec![
+            format!("{} corrected", self.corrected),
+            format!("{} undeliverable", self.undeliverable.len()),
+        ];
+        lnes.extend(self.unmatched.iter().cloned());
+        lnes
+    }
+}
+
+/// Applies a CSV of address corrections of a mailing to persons, standardizing with the USPS.
+pub async fn apply_corrections(
+    mailing: &Mailing,
+    pers: &mut [Person],
+    csv_path: &Path,
+) -> Result<CorrectionStats> {
+    let csv = fs::read_to_string(csv_path).map_err(|err| anyhow!("{csv_path:?}: {err}"))?;
+    apply_corrections_with(mailing, pers, &csv, &UspsFallback::default()).await
+}
+
+/// Applies address corrections of `serial_id,disposition,address1,address2,city,state,zip5,zip4` rows.
+///
+/// A serial id is the mailpiece id of the IMb, such as `000123`. A corrected
+/// address replaces the mailed address of its person after standardization;
+/// an undeliverable address is returned as a suppression for the next mailing.
+/// Rows matching nothing are kept in `unmatched`.
+pub async fn apply_corrections_with<S: Standardizer>(
+    mailing: &Mailing,
+    pers: &mut [Person],
+    csv: &str,
+    stdzr: &S,
+) -> Result<CorrectionStats> {
+    let mut rdr = csv::ReaderBuilder::new()
+        .trim(csv::Trim::All)
+        .from_reader(csv.as_bytes());
+    let mut stats = CorrectionStats::default();
+    for (idx, row) in rdr.deserialize::<CorrectionRow>().enumerate() {
+        let row = row?;
+        // Header is line 1.
+        let lne = |err: anyhow::Error| format!("line {} '{}': {err}", idx + 2, row.serial_id);
+        match apply_row(mailing, pers, &row, stdzr).await {
+            Ok(None) => stats.corrected += 1,
+            Ok(Some(sup)) => stats.undeliverable.push(sup),
+            Err(err) => stats.unmatched.push(lne(err)),
+        }
+    }
+    Ok(stats)
+}
+
+/// Applies a correction row to the persons of its mailpiece.
+///
+/// Every matching address is corrected, such as a senator listed again by a committee.
+/// Returns the suppression of an undeliverable address.
+async fn apply_row<S: Standardizer>(
+    mailing: &Mailing,
+    pers: &mut [Person],
+    row: &CorrectionRow,
+    stdzr: &S,
+) -> Result<Option<Suppression>> {
+    let disposition: Disposition = row.disposition.parse()?;
+    let mp = find_mailpiece(mailing, &row.serial_id)?;
+    let adrs: Vec<&mut Address> = pers
+        .iter_mut()
+        .filter(|per| per.name == mp.name)
+        .flat_map(|per| per.adrs.iter_mut().flatten())
+        .filter(|adr| is_mailed_adr(adr, mp))
+        .collect();
+    if adrs.is_empty() {
+        return Err(anyhow!("no person address of mailpiece {}", mp.name));
+    }
+    if disposition == Disposition::Undeliverable {
+        return Ok(Some(Suppression {
+            name: mp.name.clone(),
+            zip5s: vec![mp.zip5],
+        }));
+    }
+
+    // Standardize the corrected address before replacing the mailed address.
+    let mut new = row.adr()?;
+    if !is_dry_run() && !std_sob(&mut new) {
+        stdzr.standardize(&mut new).await?;
+        new.standardized = true;
+    }
+    for adr in adrs {
+        *adr = new.clone();
+    }
+    Ok(None)
+}
+
+/// Returns the mailpiece of a serial id.
+fn find_mailpiece<'a>(mailing: &'a Mailing, serial_id: &str) -> Result<&'a Mailpiece> {
+    let id: u32 = serial_id
+        .trim()
+        .parse()
+        .map_err(|_| anyhow!("invalid serial id"))?;
+    mailing
+        .trays
+        .iter()
+        .flat_map(|tray| tray.mailpieces.iter())
+        .chain(mailing.intl.iter())
+        .find(|mp| mp.id == id)
+        .ok_or_else(|| anyhow!("no mailpiece of serial id"))
+}
+
+/// Applies address corrections to person files, such as `house.json`, and the suppression list.
+///
+/// Files with corrections are rewritten, keeping their other fields.
+/// Missing files are skipped.
+pub async fn apply_correction_files(
+    mailing: &Mailing,
+    pths: &[&str],
+    csv_path: &Path,
+) -> Result<CorrectionStats> {
+    // Persons of all files, as mailpieces may come from any source.
+    let mut per_files: Vec<(&str, serde_json::Value, usize)> = Vec::new();
+    let mut pers: Vec<Person> = Vec::new();
+    for pth in pths.iter().filter(|pth| data_pth(pth).exists()) {
+        let mut per_file: serde_json::Value = read_from_file(pth)?;
+        let file_pers: Vec<Person> = serde_json::from_value(per_file["persons"].take())
+            .map_err(|err| anyhow!("acs: person file '{pth}': {err}"))?;
+        per_files.push((pth, per_file, file_pers.len()));
+        pers.extend(file_pers);
+    }
+    let before = pers.clone();
+    let stats = apply_corrections(mailing, &mut pers, csv_path).await?;
+
+    // Rewrite files with changed persons; persons compare equal by name alone.
+    let mut start = 0;
+    for (pth, mut per_file, cnt) in per_files {
+        let rng = start..start + cnt;
+        start += cnt;
+        let file_pers = serde_json::to_value(&pers[rng.clone()])?;
+        if serde_json::to_value(&before[rng])? != file_pers {
+            per_file["persons"] = file_pers;
+            write_to_file(&per_file, pth)?;
+        }
+    }
+
+    // Suppress undeliverable addresses in the next mailing.
+    if !stats.undeliverable.is_empty() {
+        let mut suppressions = SuppressionList::load(SUPPRESSION_PTH)?;
+        for sup in stats.undeliverable.iter() {
+            suppressions.add(sup);
+        }
+        suppressions.save(SUPPRESSION_PTH)?;
+    }
+
+    Ok(stats)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A standardizer adding a zip4, failing addresses containing "BAD".
+    struct MockStdzr;
+
+    impl Standardizer for MockStdzr {
+        async fn standardize(&self, adr: &mut Address) -> Result<()> {
+            if adr.address1.contains("BAD") {
+                return Err(anyhow!("No address found in the USPS response."));
+            }
+            adr.zip4 = 1234;
+            Ok(())
+        }
+    }
+
+    fn per(name: &str, address1: &str, address2: Option<&str>, zip5: u32) -> Person {
+        Person {
+            name: name.into(),
+            adrs: Some(vec![Address {
+                address1: address1.into(),
+                address2: address2.map(String::from),
+                city: "WASHINGTON".into(),
+                state: "DC".into(),
+                zip5,
+                ..Default::default()
+            }]),
+            ..Default::default()
+        }
+    }
+
+    fn mailing(pers: &[Person]) -> Mailing {
+        let mailpieces = pers
+            .iter()
+            .enumerate()
+            .map(|(idx, per)| {
+                let adr = &per.adrs.as_ref().unwrap()[0];
+                let mut mp = new_mailpiece(per, adr);
+                mp.address1 = adr.merged_address1(ADDRESS1_MAX);
+                mp.id = idx as u32 + 101;
+                mp
+            })
+            .collect();
+        let mut mailing = Mailing::new();
+        mailing.trays.push(MailTray {
+            name: "T1".into(),
+            size: TraySize::OneFoot,
+            barcode_id: BarcodeId::Default,
+            mailpieces,
+        });
+        mailing
+    }
+
+    #[tokio::test]
+    async fn test_apply_corrections() {
+        let mut pers = vec![
+            per("Jane Doe", "100 MAIN ST", Some("STE 2"), 20510),
+            per("John Roe", "200 MAIN ST", None, 20515),
+            per("Pat Poe", "300 MAIN ST", None, 20515),
+        ];
+        let mailing = mailing(&pers);
+        // The same person listed by another source.
+        pers.push(pers[0].clone());
+        let csv = "serial_id,disposition,address1,address2,city,state,zip5,zip4
+            000101,corrected,1 First St,Apt 3,Arlington,va,22201,
+            000102,undeliverable,,,,,,
+            000999,undeliverable,,,,,,
+            000103,corrected,1 Bad St,,Arlington,VA,22201,
+            000103,moved,,,,,,
+            abc,corrected,,,,,,";
+        let stats = apply_corrections_with(&mailing, &mut pers, csv, &MockStdzr)
+            .await
+            .unwrap();
+
+        // A corrected address replaces the mailed address of each listing, standardized.
+        assert_eq!(stats.corrected, 1);
+        for per in [&pers[0], &pers[3]] {
+            let adr = &per.adrs.as_ref().unwrap()[0];
+            assert_eq!(adr.address1, "1 FIRST ST");
+            assert_eq!(adr.address2.as_deref(), Some("APT 3"));
+            assert_eq!(
+                (adr.state.as_str(), adr.zip5, adr.zip4),
+                ("VA", 22201, 1234)
+            );
+            assert!(adr.standardized);
+        }
+
+        // An undeliverable address is suppressed, and kept until then.
+        assert_eq!(
+            stats.undeliverable,
+            vec![Suppression {
+                name: "John Roe".into(),
+                zip5s: vec![20515],
+            }]
+        );
+        assert_eq!(pers[1].adrs.as_ref().unwrap()[0].address1, "200 MAIN ST");
+
+        // Unknown serial ids, failing addresses and unknown dispositions are reported.
+        assert_eq!(pers[2].adrs.as_ref().unwrap()[0].address1, "300 MAIN ST");
+        let lnes: Vec<&str> = stats
+            .unmatched
+            .iter()
+            .map(|lne| lne.split(':').next().unwrap())
+            .collect();
+        assert_eq!(
+            lnes,
+            vec![
+                "line 4 '000999'",
+                "line 5 '000103'",
+                "line 6 '000103'",
+                "line 7 'abc'",
+            ]
+        );
+        assert!(stats.unmatched[0].contains("no mailpiece of serial id"));
+        assert_eq!(
+            stats.lnes()[..2],
+            ["1 corrected".to_string(), "1 undeliverable".to_string()]
+        );
+    }
+}
@@ -0,0 +1,415 @@
+use crate::io::*;
+use crate::mailing::*;
+use crate::manifest::*;
+use crate::models::*;
+use crate::suppression::*;
+use anyhow::{anyhow, Result};
+use serde::Deserialize;
+use std::collections::HashSet;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// Person files of the sources of a mailing, in mailing order.
+pub const AUDIT_PER_PTHS: [&str; 8] = [
+    crate::military::FLE_PTH,
+    crate::nasa::FLE_PTH,
+    crate::executive::FLE_PTH,
+    crate::senate::FLE_PTH,
+    crate::house::FLE_PTH,
+    crate::committee::FLE_PTH,
+    crate::state::FLE_PTH,
+    crate::observer::FLE_PTH,
+];
+
+/// Sources of the person files of `AUDIT_PER_PTHS`, in the same order.
+pub const AUDIT_SOURCES: [Source; 8] = [
+    Source::Military,
+    Source::Nasa,
+    Source::Executive,
+    Source::Senate,
+    Source::House,
+    Source::Committee,
+    Source::State,
+    Source::Observer,
+];
+
+/// Person files of the sources whose role passes the recipient filter of a mailing.
+pub fn audit_per_pths(cfg: &MailingCfg) -> Vec<&'static str> {
+    AUDIT_SOURCES
+        .iter()
+        .zip(AUDIT_PER_PTHS)
+        .filter(|(src, _)| cfg.is_role_allowed(&src.role()))
+        .map(|(_, pth)| pth)
+        .collect()
+}
+
+/// Persons of a person file, ignoring other fields.
+#[derive(Debug, Deserialize)]
+struct PersonFile {
+    persons: Vec<Person>,
+}
+
+/// Reads the persons of person files whose role passes the recipient filter of a mailing.
+pub fn read_persons(pths: &[&str], cfg: &MailingCfg) -> Result<Vec<Person>> {
+    let mut pers = Vec::new();
+    for pth in pths {
+        let per_file = read_from_file::<PersonFile>(pth)
+            .map_err(|err| anyhow!("audit: person file '{pth}': {err}"))?;
+        pers.extend(
+            per_file
+                .persons
+                .into_iter()
+                .filter(|per| cfg.is_role_allowed(&per.role)),
+        );
+    }
+    // Merged and suppressed as for the mailing.
+    let mut pers = dedup_persons(pers);
+    let suppressions = SuppressionList::load(SUPPRESSION_PTH)?;
+    for per in pers.iter_mut() {
+        if let Some(adrs) = per.adrs.as_mut() {
+            adrs.retain(|adr| !suppressions.is_suppressed(&per.name, adr));
+        }
+    }
+    Ok(pers)
+}
+
+/// Discrepancies between the artifacts of a mailing.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct AuditReport {
+    pub mailing: String,
+    pub discrepancies: Vec<String>,
+}
+
+impl AuditReport {
+    pub fn is_pass(&self) -> bool {
+        self.discrepancies.is_empty()
+    }
+
+    /// Lines of the run summary.
+    pub fn lnes(&self) -> Vec<String> {
+        if self.is_pass() {
+            return vec![format!("pass {}", self.mailing)];
+        }
+        let mut lnes = vec![format!(
+            "fail {}: {} discrepancies",
+            self.mailing,
+            self.discrepancies.len()
+        )];
+        lnes.extend(self.discrepancies.iter().cloned());
+        lnes
+    }
+}
+
+/// Recipient key of a mailpiece, excluding the zip4 upgraded during mailing.
+fn recipient_key(name: &str, adr: (&str, &str, &str, u32)) -> String {
+    format!("{name},{},{},{},{:05}", adr.0, adr.1, adr.2, adr.3)
+}
+
+/// Recipient keys of a person address, with address2 merged into address1 or not.
+///
+/// A mailpiece keeps a suite on its delivery line when it fits.
+fn recipient_keys(name: &str, adr: &Address) -> [String; 2] {
+    [
+        recipient_key(name, (&adr.address1, &adr.city, &adr.state, adr.zip5)),
+        recipient_key(
+            name,
+            (
+                &adr.merged_address1(usize::MAX),
+                &adr.city,
+                &adr.state,
+                adr.zip5,
+            ),
+        ),
+    ]
+}
+
+/// Cross-checks a mailing with its persons, manifest and chunk pdfs.
+///
+/// Checks that every mailpiece has a person address and every person address
+/// has a mailpiece, that piece counts of the mailing and manifest agree, that
+/// every chunk pdf of the manifest exists, and that sha256 sidecars match.
+pub fn audit(mailing_pth: &Path, pers: &[Person], dir: &Path) -> Result<AuditReport> {
+    let mailing = read_from_file::<Mailing>(&mailing_pth.to_string_lossy())
+        .map_err(|err| anyhow!("audit: mailing '{}': {err}", mailing_pth.display()))?;
+    let mut rpt = AuditReport {
+        mailing: mailing.name.clone(),
+        ..Default::default()
+    };
+    let mps: Vec<&Mailpiece> = mailing
+        .trays
+        .iter()
+        .flat_map(|tray| tray.mailpieces.iter())
+        .chain(mailing.intl.iter())
+        .collect();
+
+    // Recipients.
+    let per_keys: HashSet<String> = pers
+        .iter()
+        .flat_map(|per| {
+            per.adrs
+                .iter()
+                .flatten()
+                .flat_map(|adr| recipient_keys(&per.name, adr))
+        })
+        .collect();
+    let mp_keys: HashSet<String> = mps
+        .iter()
+        .map(|mp| recipient_key(&mp.name, (&mp.address1, &mp.city, &mp.state, mp.zip5)))
+        .collect();
+    for mp in mps.iter() {
+        let key = recipient_key(&mp.name, (&mp.address1, &mp.city, &mp.state, mp.zip5));
+        if !per_keys.contains(&key) {
+            rpt.discrepancies
+                .push(format!("mailpiece without a person address: {key}"));
+        }
+    }
+    for per in pers.iter() {
+        let Some(adrs) = &per.adrs else {
+            rpt.discrepancies
+                .push(format!("person without an address: {}", per.name));
+            continue;
+        };
+        for adr in adrs {
+            let keys = recipient_keys(&per.name, adr);
+            if !keys.iter().any(|key| mp_keys.contains(key)) {
+                rpt.discrepancies
+                    .push(format!("person address without a mailpiece: {}", keys[0]));
+            }
+        }
+    }
+
+    // Counts.
+    // The postage statement totals the domestic mailpiece count.
+    let dom_cnt = mps.len() - mailing.intl.len();
+    if mailing.mailpiece_cnt as usize != dom_cnt {
+        rpt.discrepancies.push(format!(
+            "statement pieces {} != {dom_cnt} tray pieces",
+            mailing.mailpiece_cnt
+        ));
+    }
+    if mailing.intl_cnt as usize != mailing.intl.len() {
+        rpt.discrepancies.push(format!(
+            "intl pieces {} != {} intl mailpieces",
+            mailing.intl_cnt,
+            mailing.intl.len()
+        ));
+    }
+
+    // Manifest.
+    let manifest_pth = dir.join(FLE_NAME_MANIFEST);
+    match read_from_file::<Manifest>(&manifest_pth.to_string_lossy()) {
+        Err(err) => rpt
+            .discrepancies
+            .push(format!("manifest {}: {err}", manifest_pth.display())),
+        Ok(manifest) => {
+            if manifest.mailing != mailing.name {
+                rpt.discrepancies.push(format!(
+                    "manifest mailing {} != {}",
+                    manifest.mailing, mailing.name
+                ));
+            }
+            if manifest.pieces != mps.len() {
+                rpt.discrepancies.push(format!(
+                    "manifest pieces {} != {} mailing pieces",
+                    manifest.pieces,
+                    mps.len()
+                ));
+            }
+            for file in manifest.files.iter() {
+                match fs::metadata(dir.join(file)) {
+                    Ok(meta) if meta.len() != 0 => {}
+                    Ok(_) => rpt.discrepancies.push(format!("empty chunk file {file}")),
+                    Err(_) => rpt.discrepancies.push(format!("missing chunk file {file}")),
+                }
+            }
+        }
+    }
+
+    // Finalization hashes.
+    let mut artifacts: Vec<(PathBuf, ArtifactStatus)> = Vec::new();
+    if sidecar_pth(mailing_pth).exists() {
+        let status = verify_artifact(mailing_pth, &fs::read(mailing_pth)?);
+        artifacts.push((mailing_pth.to_path_buf(), status));
+    }
+    if dir.exists() {
+        artifacts.extend(verify_artifacts(dir)?);
+    }
+    for (pth, status) in artifacts {
+        if status != ArtifactStatus::Verified {
+            rpt.discrepancies
+                .push(format!("artifact {status} {}", pth.display()));
+        }
+    }
+
+    Ok(rpt)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::*;
+
+    /// Writes a consistent mailing of two persons to a directory.
+    ///
+    /// Returns the mailing path, the persons and the mailing directory.
+    fn setup(root: &Path) -> (PathBuf, Vec<Person>, PathBuf) {
+        let per = |name: &str, address1: &str, zip5: u32| Person {
+            name: name.into(),
+            adrs: Some(vec![Address {
+                address1: address1.into(),
+                city: "ALBANY".into(),
+                state: "NY".into(),
+                zip5,
+                ..Default::default()
+            }]),
+            ..Default::default()
+        };
+        let mut pers = vec![
+            per("Jane Doe", "1 MAIN ST", 12224),
+            per("John Roe", "2 MAIN ST", 12210),
+        ];
+        pers[1].adrs.as_mut().unwrap()[0].address2 = Some("STE 9".into());
+
+        let mut mailing = Mailing::new();
+        mailing.name = "2026-q4".into();
+        let mut tray = MailTray {
+            name: "T1".into(),
+            size: TraySize::OneFoot,
+            barcode_id: BarcodeId::MixedAadc,
+            mailpieces: Vec::new(),
+        };
+        for per in pers.iter() {
+            let adr = &per.adrs.as_ref().unwrap()[0];
+            let mut mp = new_mailpiece(per, adr);
+            // A suite merged into the delivery line doesn't break recipient matching.
+            mp.address1 = adr.merged_address1(ADDRESS1_MAX);
+            mp.address2 = None;
+            // Zip4 upgrades don't break recipient matching.
+            mp.zip4 = 1234;
+            tray.mailpieces.push(mp);
+        }
+        mailing.trays.push(tray);
+        mailing.mailpiece_cnt = 2;
+        let mailing_pth = root.join("mailing.json");
+        write_to_file_checked(&mailing, &mailing_pth.to_string_lossy()).unwrap();
+
+        let dir = root.join(&mailing.name);
+        fs::create_dir_all(&dir).unwrap();
+        let mut manifest = Manifest::new(&mailing.name);
+        for name in ["T1_1of01_cnt2_env.pdf", "T1_1of01_cnt2_ltr.pdf"] {
+            fs::write(dir.join(name), "%PDF-1.4").unwrap();
+            manifest.add_file(Path::new(name));
+        }
+        manifest.add_piece(None, false);
+        manifest.add_piece(None, false);
+        manifest.write(&dir).unwrap();
+
+        (mailing_pth, pers, dir)
+    }
+
+    #[test]
+    fn test_audit_per_pths() {
+        assert_eq!(audit_per_pths(&MailingCfg::default()), AUDIT_PER_PTHS);
+        let cfg = MailingCfg {
+            recipient_filter: vec![Role::Political],
+            ..Default::default()
+        };
+        assert_eq!(
+            audit_per_pths(&cfg),
+            vec![
+                crate::executive::FLE_PTH,
+                crate::senate::FLE_PTH,
+                crate::house::FLE_PTH,
+                crate::committee::FLE_PTH,
+                crate::state::FLE_PTH,
+            ]
+        );
+    }
+
+    #[test]
+    fn test_audit() {
+        let root = TempDir::new("audit");
+        let reset = || {
+            root.clear();
+            setup(&root)
+        };
+
+        // A consistent mailing passes.
+        let (mailing_pth, pers, dir) = reset();
+        let rpt = audit(&mailing_pth, &pers, &dir).unwrap();
+        assert!(rpt.is_pass(), "{:?}", rpt.discrepancies);
+        assert_eq!(rpt.lnes(), vec!["pass 2026-q4"]);
+
+        // Each class of drift is detected.
+        type Drift = fn(&Path, &mut Vec<Person>, &Path);
+        let cases: Vec<(Drift, &str)> = vec![
+            (
+                |_, pers, _| pers[0].adrs.as_mut().unwrap()[0].address1 = "9 ELM ST".into(),
+                "mailpiece without a person address: Jane Doe,1 MAIN ST",
+            ),
+            (
+                |_, pers, _| {
+                    pers.push(Person {
+                        name: "New Member".into(),
+                        adrs: Some(vec![Address {
+                            address1: "3 MAIN ST".into(),
+                            ..Default::default()
+                        }]),
+                        ..Default::default()
+                    })
+                },
+                "person address without a mailpiece: New Member,3 MAIN ST",
+            ),
+            (
+                |mailing_pth, _, _| {
+                    let mut mailing: Mailing =
+                        read_from_file(&mailing_pth.to_string_lossy()).unwrap();
+                    mailing.mailpiece_cnt = 3;
+                    write_to_file_checked(&mailing, &mailing_pth.to_string_lossy()).unwrap();
+                },
+                "statement pieces 3 != 2 tray pieces",
+            ),
+            (
+                |mailing_pth, _, _| {
+                    let mut mailing: Mailing =
+                        read_from_file(&mailing_pth.to_string_lossy()).unwrap();
+                    mailing.trays[0].mailpieces.pop();
+                    mailing.mailpiece_cnt = 1;
+                    write_to_file_checked(&mailing, &mailing_pth.to_string_lossy()).unwrap();
+                },
+                "manifest pieces 2 != 1 mailing pieces",
+            ),
+            (
+                |_, _, dir| fs::remove_file(dir.join("T1_1of01_cnt2_ltr.pdf")).unwrap(),
+                "missing chunk file T1_1of01_cnt2_ltr.pdf",
+            ),
+            (
+                |_, _, dir| fs::write(dir.join("T1_1of01_cnt2_env.pdf"), "").unwrap(),
+                "empty chunk file T1_1of01_cnt2_env.pdf",
+            ),
+            (
+                |mailing_pth, _, _| {
+                    let json = fs::read_to_string(mailing_pth).unwrap();
+                    fs::write(mailing_pth, json.replace("2026-q4", "2026-q3")).unwrap();
+                },
+                "artifact corrupted",
+            ),
+            (
+                |_, _, dir| fs::remove_file(dir.join(FLE_NAME_MANIFEST)).unwrap(),
+                "manifest",
+            ),
+        ];
+        for (drift, expected) in cases {
+            let (mailing_pth, mut pers, dir) = reset();
+            drift(&mailing_pth, &mut pers, &dir);
+            let rpt = audit(&mailing_pth, &pers, &dir).unwrap();
+            assert!(!rpt.is_pass());
+            assert!(
+                rpt.discrepancies.iter().any(|d| d.starts_with(expected)),
+                "Failed to match: {} {:?}",
+                expected,
+                rpt.discrepancies
+            );
+        }
+    }
+}
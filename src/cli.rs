@@ -0,0 +1,508 @@
+use crate::models::*;
+use crate::refresh::*;
+use crate::tracking::*;
+use anyhow::{anyhow, Result};
+use std::path::PathBuf;
+
+pub const USAGE: &str = "usage:
+  adr [--data-dir <dir>] <command>, defaulting to ADR_DATA_DIR or the current directory
+  adr [--dry-run] [--no-barcode-cache] [--only <source>]... [--person <name>]
+      [--refresh-members] [--members-max-days <days>]
+  adr record-fixture <url> <fixture-name> [--source house|senate|state] [--force]
+  adr verify-artifacts [dir]
+  adr refresh --notify <path|stdout> [--source <source>]... [--exit-code] [--force]
+  adr track --events <csv> [--days <days>]
+  adr acs --corrections <csv>
+  adr diff <old-mailing> <new-mailing>
+  adr audit [mailing]
+  adr purge-usps-cache
+  adr invalidate-cache <url-prefix>
+  adr verify [--apply]";
+
+/// A command line command.
+#[derive(Debug, PartialEq)]
+pub enum Cmd {
+    /// Load persons and create the mailing.
+    ///
+    /// A dry run skips USPS standardization and barcode fetches, writing to `dry_run/`.
+    /// Bypassing the barcode cache refetches IMb encodings.
+    /// Selecting sources or a person only loads them, refetching the
+    /// addresses of the person, without creating the mailing.
+    /// Refreshing members refetches the House and Senate member lists of
+    /// cached person files, as does a max age in days of the lists.
+    Run {
+        is_dry_run: bool,
+        is_barcode_cache_bypass: bool,
+        only: Vec<Source>,
+        person: Option<String>,
+        is_refresh_members: bool,
+        members_max_days: Option<u64>,
+    },
+    /// Record an offline html fixture.
+    RecordFixture {
+        url: String,
+        name: String,
+        source: Source,
+        force: bool,
+    },
+    /// Refetch sources and report changes against previous checkpoints.
+    ///
+    /// Forcing refetches cached pages of the sources.
+    Refresh {
+        notify: String,
+        sources: Vec<Source>,
+        is_exit_code: bool,
+        force: bool,
+    },
+    /// Verify checksums of artifacts under a data directory.
+    VerifyArtifacts { dir: String },
+    /// Ingest tracking scan events and report gaps of the mailing.
+    Track { events: String, days: i64 },
+    /// Apply address corrections and undeliverables returned for the mailing.
+    Acs { corrections: String },
+    /// Report recipients added, removed or changed between two mailings.
+    Diff { old: String, new: String },
+    /// Cross-check persons, the mailing, its manifest and pdfs.
+    ///
+    /// Defaults to the mailing of `mailing.json`.
+    Audit { mailing: Option<String> },
+    /// Delete cached USPS standardization results.
+    PurgeUspsCache,
+    /// Delete cached pages of URLs starting with a prefix, such as `*.house.gov`.
+    InvalidateCache { url_prefix: String },
+    /// Re-standardize cached addresses and report differences.
+    ///
+    /// Applying replaces changed addresses in the person files.
+    Verify { is_apply: bool },
+}
+
+/// Parses command line arguments, excluding the program name.
+pub fn prs_args(args: &[String]) -> Result<Cmd> {
+    let Some(cmd) = args.first() else {
+        return Ok(Cmd::Run {
+            is_dry_run: false,
+            is_barcode_cache_bypass: false,
+            only: Vec::new(),
+            person: None,
+            is_refresh_members: false,
+            members_max_days: None,
+        });
+    };
+
+    // Run flags.
+    if cmd.starts_with("--") {
+        let mut args = args.iter();
+        let mut is_dry_run = false;
+        let mut is_barcode_cache_bypass = false;
+        let mut only: Vec<Source> = Vec::new();
+        let mut person: Option<String> = None;
+        let mut is_refresh_members = false;
+        let mut members_max_days: Option<u64> = None;
+        while let Some(arg) = args.next() {
+            match arg.as_str() {
+                "--dry-run" => is_dry_run = true,
+                "--no-barcode-cache" => is_barcode_cache_bypass = true,
+                "--only" => only.push(flag_val(arg, args.next())?.parse()?),
+                "--person" => person = Some(flag_val(arg, args.next())?.clone()),
+                "--refresh-members" => is_refresh_members = true,
+                "--members-max-days" => {
+                    members_max_days = Some(flag_val(arg, args.next())?.parse()?)
+                }
+                _ => return Err(unknown(arg)),
+            }
+        }
+        return Ok(Cmd::Run {
+            is_dry_run,
+            is_barcode_cache_bypass,
+            only,
+            person,
+            is_refresh_members,
+            members_max_days,
+        });
+    }
+    let mut args = args[1..].iter();
+
+    match cmd.as_str() {
+        "record-fixture" => {
+            let mut pos: Vec<String> = Vec::new();
+            let mut source = Source::House;
+            let mut force = false;
+            while let Some(arg) = args.next() {
+                match arg.as_str() {
+                    "--source" => source = flag_val(arg, args.next())?.parse()?,
+                    "--force" => force = true,
+                    _ if arg.starts_with("--") => return Err(unknown(arg)),
+                    _ => pos.push(arg.clone()),
+                }
+            }
+            let [url, name] = <[String; 2]>::try_from(pos)
+                .map_err(|_| anyhow!("record-fixture needs <url> <fixture-name>\n{USAGE}"))?;
+            Ok(Cmd::RecordFixture {
+                url,
+                name,
+                source,
+                force,
+            })
+        }
+        "refresh" => {
+            let mut notify: Option<String> = None;
+            let mut sources: Vec<Source> = Vec::new();
+            let mut is_exit_code = false;
+            let mut force = false;
+            while let Some(arg) = args.next() {
+                match arg.as_str() {
+                    "--notify" => notify = Some(flag_val(arg, args.next())?.clone()),
+                    "--source" => sources.push(flag_val(arg, args.next())?.parse()?),
+                    "--exit-code" => is_exit_code = true,
+                    "--force" => force = true,
+                    _ => return Err(unknown(arg)),
+                }
+            }
+            if sources.is_empty() {
+                sources = REFRESH_SOURCES.to_vec();
+            }
+            Ok(Cmd::Refresh {
+                notify: notify.ok_or_else(|| anyhow!("refresh needs --notify\n{USAGE}"))?,
+                sources,
+                is_exit_code,
+                force,
+            })
+        }
+        "track" => {
+            let mut events: Option<String> = None;
+            let mut days = TRACKING_DAYS;
+            while let Some(arg) = args.next() {
+                match arg.as_str() {
+                    "--events" => events = Some(flag_val(arg, args.next())?.clone()),
+                    "--days" => days = flag_val(arg, args.next())?.parse()?,
+                    _ => return Err(unknown(arg)),
+                }
+            }
+            Ok(Cmd::Track {
+                events: events.ok_or_else(|| anyhow!("track needs --events\n{USAGE}"))?,
+                days,
+            })
+        }
+        "acs" => {
+            let mut corrections: Option<String> = None;
+            while let Some(arg) = args.next() {
+                match arg.as_str() {
+                    "--corrections" => corrections = Some(flag_val(arg, args.next())?.clone()),
+                    _ => return Err(unknown(arg)),
+                }
+            }
+            Ok(Cmd::Acs {
+                corrections: corrections
+                    .ok_or_else(|| anyhow!("acs needs --corrections\n{USAGE}"))?,
+            })
+        }
+        "diff" => {
+            let mut pos: Vec<String> = Vec::new();
+            for arg in args {
+                match arg.as_str() {
+                    _ if arg.starts_with("--") => return Err(unknown(arg)),
+                    _ => pos.push(arg.clone()),
+                }
+            }
+            let [old, new] = <[String; 2]>::try_from(pos)
+                .map_err(|_| anyhow!("diff needs <old-mailing> <new-mailing>\n{USAGE}"))?;
+            Ok(Cmd::Diff { old, new })
+        }
+        "audit" => match (args.next(), args.next()) {
+            (None, _) => Ok(Cmd::Audit { mailing: None }),
+            (Some(mailing), None) if !mailing.starts_with("--") => Ok(Cmd::Audit {
+                mailing: Some(mailing.clone()),
+            }),
+            (Some(arg), None) => Err(unknown(arg)),
+            (_, Some(arg)) => Err(unknown(arg)),
+        },
+        "purge-usps-cache" => match args.next() {
+            None => Ok(Cmd::PurgeUspsCache),
+            Some(arg) => Err(unknown(arg)),
+        },
+        "invalidate-cache" => match (args.next(), args.next()) {
+            (Some(url_prefix), None) if !url_prefix.starts_with("--") => Ok(Cmd::InvalidateCache {
+                url_prefix: url_prefix.clone(),
+            }),
+            (None, _) => Err(anyhow!("invalidate-cache needs <url-prefix>\n{USAGE}")),
+            (Some(arg), None) => Err(unknown(arg)),
+            (_, Some(arg)) => Err(unknown(arg)),
+        },
+        "verify" => match args.next() {
+            None => Ok(Cmd::Verify { is_apply: false }),
+            Some(arg) if arg == "--apply" => match args.next() {
+                None => Ok(Cmd::Verify { is_apply: true }),
+                Some(arg) => Err(unknown(arg)),
+            },
+            Some(arg) => Err(unknown(arg)),
+        },
+        "verify-artifacts" => match (args.next(), args.next()) {
+            (None, _) => Ok(Cmd::VerifyArtifacts { dir: ".".into() }),
+            (Some(dir), None) if !dir.starts_with("--") => {
+                Ok(Cmd::VerifyArtifacts { dir: dir.clone() })
+            }
+            (Some(arg), None) => Err(unknown(arg)),
+            (_, Some(arg)) => Err(unknown(arg)),
+        },
+        _ => Err(unknown(cmd)),
+    }
+}
+
+/// Splits `--data-dir <dir>` from command line arguments, excluding the program name.
+///
+/// Accepted before or after the command.
+pub fn prs_data_dir(args: &[String]) -> Result<(Option<PathBuf>, Vec<String>)> {
+    let mut data_dir: Option<PathBuf> = None;
+    let mut rest: Vec<String> = Vec::with_capacity(args.len());
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        if arg == "--data-dir" {
+            data_dir = Some(flag_val(arg, args.next())?.into());
+        } else {
+            rest.push(arg.clone());
+        }
+    }
+    Ok((data_dir, rest))
+}
+
+/// Returns the value of a flag.
+fn flag_val<'a>(flag: &str, val: Option<&'a String>) -> Result<&'a String> {
+    val.ok_or_else(|| anyhow!("missing value for {flag}\n{USAGE}"))
+}
+
+fn unknown(arg: &str) -> anyhow::Error {
+    anyhow!("unknown argument '{arg}'\n{USAGE}")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn args(s: &str) -> Vec<String> {
+        s.split_whitespace().map(String::from).collect()
+    }
+
+    #[test]
+    fn test_prs_args_valid() {
+        assert_eq!(
+            prs_args(&args("")).unwrap(),
+            Cmd::Run {
+                is_dry_run: false,
+                is_barcode_cache_bypass: false,
+                only: vec![],
+                person: None,
+                is_refresh_members: false,
+                members_max_days: None,
+            }
+        );
+        assert_eq!(
+            prs_args(&args("--dry-run --no-barcode-cache")).unwrap(),
+            Cmd::Run {
+                is_dry_run: true,
+                is_barcode_cache_bypass: true,
+                only: vec![],
+                person: None,
+                is_refresh_members: false,
+                members_max_days: None,
+            }
+        );
+        assert_eq!(
+            prs_args(&[
+                "--only".into(),
+                "house".into(),
+                "--person".into(),
+                "Jared Huffman".into(),
+                "--only".into(),
+                "senate".into(),
+            ])
+            .unwrap(),
+            Cmd::Run {
+                is_dry_run: false,
+                is_barcode_cache_bypass: false,
+                only: vec![Source::House, Source::Senate],
+                person: Some("Jared Huffman".into()),
+                is_refresh_members: false,
+                members_max_days: None,
+            }
+        );
+        assert_eq!(
+            prs_args(&args("--refresh-members --members-max-days 30")).unwrap(),
+            Cmd::Run {
+                is_dry_run: false,
+                is_barcode_cache_bypass: false,
+                only: vec![],
+                person: None,
+                is_refresh_members: true,
+                members_max_days: Some(30),
+            }
+        );
+        assert_eq!(
+            prs_args(&args(
+                "record-fixture https://tuberville.senate.gov tuberville --source senate --force"
+            ))
+            .unwrap(),
+            Cmd::RecordFixture {
+                url: "https://tuberville.senate.gov".into(),
+                name: "tuberville".into(),
+                source: Source::Senate,
+                force: true,
+            }
+        );
+        assert_eq!(
+            prs_args(&args("refresh --notify stdout")).unwrap(),
+            Cmd::Refresh {
+                notify: "stdout".into(),
+                sources: REFRESH_SOURCES.to_vec(),
+                is_exit_code: false,
+                force: false,
+            }
+        );
+        assert_eq!(
+            prs_args(&args(
+                "refresh --source senate --notify changes.txt --source house --exit-code"
+            ))
+            .unwrap(),
+            Cmd::Refresh {
+                notify: "changes.txt".into(),
+                sources: vec![Source::Senate, Source::House],
+                is_exit_code: true,
+                force: false,
+            }
+        );
+        assert_eq!(
+            prs_args(&args("refresh --notify stdout --source house --force")).unwrap(),
+            Cmd::Refresh {
+                notify: "stdout".into(),
+                sources: vec![Source::House],
+                is_exit_code: false,
+                force: true,
+            }
+        );
+        assert_eq!(
+            prs_args(&args("verify-artifacts")).unwrap(),
+            Cmd::VerifyArtifacts { dir: ".".into() }
+        );
+        assert_eq!(
+            prs_args(&args("verify-artifacts data")).unwrap(),
+            Cmd::VerifyArtifacts { dir: "data".into() }
+        );
+        assert_eq!(
+            prs_args(&args("track --events scans.csv")).unwrap(),
+            Cmd::Track {
+                events: "scans.csv".into(),
+                days: TRACKING_DAYS,
+            }
+        );
+        assert_eq!(
+            prs_args(&args("track --days 10 --events scans.csv")).unwrap(),
+            Cmd::Track {
+                events: "scans.csv".into(),
+                days: 10,
+            }
+        );
+        assert_eq!(
+            prs_args(&args("acs --corrections acs.csv")).unwrap(),
+            Cmd::Acs {
+                corrections: "acs.csv".into(),
+            }
+        );
+        assert_eq!(
+            prs_args(&args("diff old.json mailing.json")).unwrap(),
+            Cmd::Diff {
+                old: "old.json".into(),
+                new: "mailing.json".into(),
+            }
+        );
+        assert_eq!(
+            prs_args(&args("audit")).unwrap(),
+            Cmd::Audit { mailing: None }
+        );
+        assert_eq!(
+            prs_args(&args("audit 2026-q4")).unwrap(),
+            Cmd::Audit {
+                mailing: Some("2026-q4".into())
+            }
+        );
+        assert_eq!(
+            prs_args(&args("purge-usps-cache")).unwrap(),
+            Cmd::PurgeUspsCache
+        );
+        assert_eq!(
+            prs_args(&args("invalidate-cache *.house.gov")).unwrap(),
+            Cmd::InvalidateCache {
+                url_prefix: "*.house.gov".into()
+            }
+        );
+        assert_eq!(
+            prs_args(&args("verify")).unwrap(),
+            Cmd::Verify { is_apply: false }
+        );
+        assert_eq!(
+            prs_args(&args("verify --apply")).unwrap(),
+            Cmd::Verify { is_apply: true }
+        );
+    }
+
+    #[test]
+    fn test_prs_data_dir() {
+        let cases = [
+            ("", None, ""),
+            ("--data-dir /srv/adr", Some("/srv/adr"), ""),
+            ("--data-dir data --dry-run", Some("data"), "--dry-run"),
+            ("audit m1 --data-dir data", Some("data"), "audit m1"),
+        ];
+        for (input, dir, rest) in cases {
+            assert_eq!(
+                prs_data_dir(&args(input)).unwrap(),
+                (dir.map(PathBuf::from), args(rest)),
+                "Failed to match: {}",
+                input
+            );
+        }
+        assert!(prs_data_dir(&args("--dry-run --data-dir")).is_err());
+    }
+
+    #[test]
+    fn test_prs_args_invalid() {
+        let cases = vec![
+            "record",
+            "record-fixture https://a.house.gov",
+            "record-fixture https://a.house.gov a --source moon",
+            "record-fixture https://a.house.gov a --source",
+            "record-fixture https://a.house.gov a --frce",
+            "verify-artifacts a b",
+            "audit a b",
+            "--dry-run audit",
+            "--only",
+            "--only moon",
+            "--person",
+            "purge-usps-cache all",
+            "invalidate-cache",
+            "invalidate-cache --all",
+            "invalidate-cache a.house.gov b.house.gov",
+            "audit --force",
+            "refresh",
+            "refresh --notify",
+            "refresh --notify stdout --source moon",
+            "verify-artifacts --all",
+            "track",
+            "acs",
+            "acs --corrections",
+            "acs --corrections acs.csv --apply",
+            "diff",
+            "diff old.json",
+            "diff old.json mailing.json extra.json",
+            "diff old.json --force",
+            "track --events scans.csv --days ten",
+            "verify --force",
+            "verify --apply --apply",
+        ];
+        for input in cases {
+            assert!(
+                prs_args(&args(input)).is_err(),
+                "Failed to match: {}",
+                input
+            );
+        }
+    }
+}
@@ -0,0 +1,344 @@
+use crate::io::*;
+use crate::models::*;
+use crate::prsr::*;
+use crate::report::*;
+use crate::usps::*;
+use anyhow::{anyhow, Result};
+use scraper::{Html, Selector};
+use serde::{Deserialize, Serialize};
+use tracing::{debug, info, trace, warn};
+
+pub const FLE_PTH: &str = "committee.json";
+
+/// Committee lists of each chamber.
+pub const URLS: [(&str, &str); 2] = [
+    ("House", "https://www.house.gov/committees"),
+    ("Senate", "https://www.senate.gov/committees/membership"),
+];
+
+/// Committees whose leaders are mailed, matched against committee link text.
+pub const COMMITTEES: [&str; 5] = [
+    "Armed Services",
+    "Commerce, Science, and Transportation",
+    "Homeland Security",
+    "Intelligence",
+    "Oversight",
+];
+
+/// Chair and ranking member of each committee of each chamber.
+const CAP_PER: usize = COMMITTEES.len() * URLS.len() * 2;
+
+/// Chair of a committee.
+pub const POS_CHAIR: &str = "Chair";
+/// Ranking member of a committee.
+pub const POS_RANKING: &str = "Ranking Member";
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Eq, PartialOrd, Ord)]
+pub struct Committee {
+    pub name: String,
+    pub role: Role,
+    pub persons: Vec<Person>,
+}
+
+impl Default for Committee {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Committee {
+    pub fn new() -> Self {
+        Self {
+            name: "U.S. Congressional Committees".into(),
+            role: Role::Political,
+            persons: Vec::with_capacity(CAP_PER),
+        }
+    }
+
+    pub async fn load() -> Result<Committee> {
+        REPORT.lock().unwrap().begin(Source::Committee);
+
+        // Read file from disk.
+        let committee = match read_from_file::<Committee>(FLE_PTH) {
+            Ok(mut committee_from_disk) => {
+                // Backfill roles of older files.
+                set_roles(&mut committee_from_disk.persons, &committee_from_disk.role);
+                committee_from_disk
+            }
+            Err(err) => {
+                warn!("read file: {err}");
+                let mut committee = Committee::new();
+
+                // Fetch leaders with committee office addresses.
+                committee.persons = committee.fetch_leaders().await?;
+                set_roles(&mut committee.persons, &committee.role);
+
+                // Write file to disk.
+                write_to_file(&committee, FLE_PTH)?;
+
+                committee
+            }
+        };
+
+        info!("{} committee leaders", committee.persons.len());
+
+        Ok(committee)
+    }
+
+    /// Fetch leaders of the committees of each chamber from network.
+    ///
+    /// Committees whose page fails are skipped with a warning.
+    pub async fn fetch_leaders(&self) -> Result<Vec<Person>> {
+        let mut pers = Vec::with_capacity(CAP_PER);
+        for (chamber, url) in URLS {
+            let html = fetch_html(url, FetchOptions::for_source(Source::Committee)).await?;
+            let links = prs_committee_links(chamber, &html);
+            if links.is_empty() {
+                return Err(anyhow!("{url}: no committees"));
+            }
+            for (name, url) in links {
+                match fetch_prs_std_leaders(&name, &url).await {
+                    Ok(cur_pers) => pers.extend(cur_pers),
+                    Err(err) => warn!("{name}: {err}"),
+                }
+            }
+        }
+        Ok(pers)
+    }
+}
+
+/// Parses names and urls of the mailed committees from a committee list.
+///
+/// Names are prefixed with the chamber, such as "House Armed Services".
+pub fn prs_committee_links(chamber: &str, html: &str) -> Vec<(String, String)> {
+    let document = Html::parse_document(html);
+    let link_sel = Selector::parse("a").unwrap();
+    let mut links: Vec<(String, String)> = Vec::new();
+    for elm in document.select(&link_sel) {
+        let txt = elm.text().collect::<Vec<_>>().concat();
+        let txt = txt.split_whitespace().collect::<Vec<_>>().join(" ");
+        let url = elm
+            .value()
+            .attr("href")
+            .unwrap_or_default()
+            .trim_end_matches('/')
+            .to_string();
+        if !url.starts_with("http") || !COMMITTEES.iter().any(|c| txt.contains(c)) {
+            continue;
+        }
+        let name = txt
+            .trim_start_matches("Committee on the ")
+            .trim_start_matches("Committee on ")
+            .trim_start_matches(chamber)
+            .trim();
+        let name = format!("{chamber} {name}");
+        if !links.iter().any(|(o, _)| *o == name) {
+            links.push((name, url));
+        }
+    }
+    links
+}
+
+/// Fetch a committee page, and parse its leaders with the committee office addresses standardized with the USPS.
+pub async fn fetch_prs_std_leaders(name: &str, url: &str) -> Result<Vec<Person>> {
+    let html = fetch_html(url, FetchOptions::for_source(Source::Committee)).await?;
+    let mut pers = prs_leaders(name, url, &html)?;
+    let adrs = standardize_addresses(prs_html_adrs(&html)?).await?;
+    debug!("{name}: {} leaders, {} addresses", pers.len(), adrs.len());
+    for per in pers.iter_mut() {
+        per.adrs = Some(adrs.clone());
+    }
+    Ok(pers)
+}
+
+/// Parses the chair and ranking member of a committee page.
+///
+/// Supports "Chairman Mike Rogers", "Mike Rogers, Chairman", and a position line followed by a name line.
+pub fn prs_leaders(name: &str, url: &str, html: &str) -> Result<Vec<Person>> {
+    let document = Html::parse_document(html);
+    let body_sel = Selector::parse("body").unwrap();
+    let lnes: Vec<String> = document
+        .select(&body_sel)
+        .flat_map(|elm| elm.text())
+        .map(|s| s.split_whitespace().collect::<Vec<_>>().join(" "))
+        .filter(|s| !s.is_empty())
+        .collect();
+
+    let mut pers: Vec<Person> = Vec::with_capacity(2);
+    for (idx, lne) in lnes.iter().enumerate() {
+        let Some((pos, full_name)) = prs_position(lne, lnes.get(idx + 1)) else {
+            continue;
+        };
+        if full_name.is_empty() || pers.iter().any(|per| per.title2 == pos) {
+            continue;
+        }
+        trace!("{name}: {pos} {full_name}");
+        pers.push(Person {
+            name: name_clean(&full_name),
+            title1: name.into(),
+            title2: pos.into(),
+            url: url.into(),
+            role: Role::Political,
+            provenance: Some(Provenance::new(Source::Committee, url)),
+            ..Default::default()
+        });
+    }
+
+    if pers.is_empty() {
+        return Err(anyhow!("{url}: no chair or ranking member"));
+    }
+    Ok(pers)
+}
+
+/// Position and name of a line naming a committee leader, with the next line for a bare position.
+fn prs_position(lne: &str, nxt: Option<&String>) -> Option<(&'static str, String)> {
+    const PREFIXES: [(&str, &str); 4] = [
+        ("CHAIRMAN", POS_CHAIR),
+        ("CHAIRWOMAN", POS_CHAIR),
+        ("CHAIR", POS_CHAIR),
+        ("RANKING MEMBER", POS_RANKING),
+    ];
+    let is_eq = |s: Option<&str>, prefix: &str| s.is_some_and(|s| s.eq_ignore_ascii_case(prefix));
+    for (prefix, pos) in PREFIXES {
+        let pre = format!("{prefix} ");
+        let suf = format!(", {prefix}");
+        // Bare position, such as "Ranking Member" above "Adam Smith".
+        if lne.eq_ignore_ascii_case(prefix) {
+            return Some((pos, nxt.cloned().unwrap_or_default()));
+        }
+        // Position before the name, such as "Chairman Mike Rogers".
+        if is_eq(lne.get(..pre.len()), &pre) {
+            return Some((pos, lne[pre.len()..].trim().to_string()));
+        }
+        // Position after the name, such as "Mike Rogers, Chairman".
+        if lne.len() > suf.len() && is_eq(lne.get(lne.len() - suf.len()..), &suf) {
+            return Some((pos, lne[..lne.len() - suf.len()].trim().to_string()));
+        }
+    }
+    None
+}
+
+/// Parses the committee office addresses of a committee page, before standardization.
+pub fn prs_html_adrs(html: &str) -> Result<Vec<Address>, ParseError> {
+    let adr_lnes = prs_adr_lnes(html).ok_or(ParseError::NoZipFound)?;
+    PRSR.prs_adrs(&adr_lnes)
+}
+
+pub fn prs_adr_lnes(html: &str) -> Option<Vec<String>> {
+    let document = Html::parse_document(html);
+    let mut lnes: Vec<String> = Vec::new();
+    for txt in ["address", "footer", "body"] {
+        let selector = Selector::parse(txt).unwrap();
+        for elm in document.select(&selector) {
+            // Extract lines from html.
+            let cur_lnes = elm
+                .text()
+                .map(|s| s.trim().trim_end_matches(',').to_uppercase().to_string())
+                .filter(|s| PRSR.filter(s))
+                .collect::<Vec<String>>();
+            lnes.extend(cur_lnes);
+        }
+
+        if !lnes.is_empty() {
+            break;
+        }
+    }
+
+    // Edit lines to make it easier to parse.
+    edit_dot(&mut lnes);
+    edit_nbsp_zwsp(&mut lnes);
+    edit_mailing(&mut lnes);
+    PRSR.edit_lnes(&mut lnes, Source::Committee);
+    edit_newline(&mut lnes);
+    edit_hob(&mut lnes);
+    edit_split_comma(&mut lnes);
+    edit_starting_hash(&mut lnes);
+    edit_char_half(&mut lnes);
+    edit_empty(&mut lnes);
+
+    trace!("{lnes:?}");
+
+    if lnes.is_empty() {
+        None
+    } else {
+        Some(lnes)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_prs_committee_links() {
+        let html = r#"<html><body><ul>
+            <li><a href="https://agriculture.house.gov/">Agriculture</a></li>
+            <li><a href="https://armedservices.house.gov/">Armed Services</a></li>
+            <li><a href="https://oversight.house.gov/">Oversight and Accountability</a></li>
+            <li><a href="/committees/oversight">Oversight and Accountability</a></li>
+        </ul></body></html>"#;
+        let links = prs_committee_links("House", html);
+        assert_eq!(
+            links,
+            vec![
+                (
+                    "House Armed Services".to_string(),
+                    "https://armedservices.house.gov".to_string()
+                ),
+                (
+                    "House Oversight and Accountability".to_string(),
+                    "https://oversight.house.gov".to_string()
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_prs_leaders() {
+        let cases = [
+            (
+                "<p>Chairman Mike Rogers</p><p>Ranking Member Adam Smith</p>",
+                "Mike Rogers",
+                "Adam Smith",
+            ),
+            (
+                "<p>Roger Wicker, Chairman</p><p>Jack Reed, Ranking Member</p>",
+                "Roger Wicker",
+                "Jack Reed",
+            ),
+            (
+                "<h3>Chair</h3><p>James Comer</p><h3>Ranking Member</h3><p>Jamie Raskin</p>",
+                "James Comer",
+                "Jamie Raskin",
+            ),
+        ];
+        for (bdy, chair, ranking) in cases {
+            let html = format!("<html><body><p>Vice Chair Jane Doe</p>{bdy}</body></html>");
+            let pers = prs_leaders("House Armed Services", "https://a.gov", &html).unwrap();
+            let names: Vec<(&str, &str)> = pers
+                .iter()
+                .map(|per| (per.title2.as_str(), per.name.as_str()))
+                .collect();
+            assert_eq!(
+                names,
+                vec![(POS_CHAIR, chair), (POS_RANKING, ranking)],
+                "Failed to match: {}",
+                bdy
+            );
+            assert!(pers.iter().all(|per| per.title1 == "House Armed Services"));
+            assert!(pers.iter().all(|per| per.role == Role::Political));
+        }
+        assert!(prs_leaders("House Armed Services", "https://a.gov", "<p>Members</p>").is_err());
+    }
+
+    #[test]
+    fn test_prs_html_adrs() {
+        let html = r#"<html><body><p>Chairman Mike Rogers</p>
+            <footer><address>2216 Rayburn House Office Building<br>Washington, DC 20515</address></footer>
+            </body></html>"#;
+        let adrs = prs_html_adrs(html).unwrap();
+        assert_eq!(adrs.len(), 1);
+        assert_eq!(adrs[0].zip5, 20515);
+        assert_eq!(adrs[0].state, "DC");
+    }
+}
@@ -0,0 +1,327 @@
+use crate::house::*;
+use crate::models::*;
+use crate::prsr::*;
+use crate::senate::*;
+use crate::state::*;
+use std::collections::BTreeMap;
+use std::fmt;
+
+/// Each state is represented by two senators.
+const SENATORS_PER_STATE: usize = 2;
+
+/// House seats per state from the 2020 apportionment, plus one delegate for DC and each territory.
+pub const HOUSE_SEATS: [(&str, usize); 56] = [
+    ("AL", 7),
+    ("AK", 1),
+    ("AZ", 9),
+    ("AR", 4),
+    ("CA", 52),
+    ("CO", 8),
+    ("CT", 5),
+    ("DE", 1),
+    ("FL", 28),
+    ("GA", 14),
+    ("HI", 2),
+    ("ID", 2),
+    ("IL", 17),
+    ("IN", 9),
+    ("IA", 4),
+    ("KS", 4),
+    ("KY", 6),
+    ("LA", 6),
+    ("ME", 2),
+    ("MD", 8),
+    ("MA", 9),
+    ("MI", 13),
+    ("MN", 8),
+    ("MS", 4),
+    ("MO", 8),
+    ("MT", 2),
+    ("NE", 3),
+    ("NV", 4),
+    ("NH", 2),
+    ("NJ", 12),
+    ("NM", 3),
+    ("NY", 26),
+    ("NC", 14),
+    ("ND", 1),
+    ("OH", 15),
+    ("OK", 5),
+    ("OR", 6),
+    ("PA", 17),
+    ("RI", 2),
+    ("SC", 7),
+    ("SD", 1),
+    ("TN", 9),
+    ("TX", 38),
+    ("UT", 4),
+    ("VT", 1),
+    ("VA", 11),
+    ("WA", 10),
+    ("WV", 2),
+    ("WI", 8),
+    ("WY", 1),
+    ("DC", 1),
+    ("AS", 1),
+    ("GU", 1),
+    ("MP", 1),
+    ("PR", 1),
+    ("VI", 1),
+];
+
+/// A state whose member count differs from the expected count.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CoverageGap {
+    pub source: String,
+    pub state: String,
+    pub expected: usize,
+    pub found: usize,
+    /// Names listed for the state, marking members without addresses.
+    pub names: Vec<String>,
+}
+impl fmt::Display for CoverageGap {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let kind = if self.found < self.expected {
+            "missing"
+        } else {
+            "surplus"
+        };
+        write!(
+            f,
+            "{}: {} {}: expected {}, found {} [{}]",
+            self.source,
+            kind,
+            self.state,
+            self.expected,
+            self.found,
+            self.names.join(", ")
+        )
+    }
+}
+
+/// Persons cross-tabulated by state against expected counts.
+#[derive(Debug, Default)]
+pub struct CoverageReport {
+    pub gaps: Vec<CoverageGap>,
+    pub notes: Vec<String>,
+}
+
+impl CoverageReport {
+    pub fn is_complete(&self) -> bool {
+        self.gaps.is_empty()
+    }
+
+    /// Lines for the run summary.
+    pub fn lnes(&self) -> Vec<String> {
+        let mut lnes: Vec<String> = self.gaps.iter().map(|gap| gap.to_string()).collect();
+        lnes.extend(self.notes.iter().cloned());
+        if lnes.is_empty() {
+            lnes.push("complete".into());
+        }
+        lnes
+    }
+}
+
+/// Reports missing or surplus senators, representatives, and governors per state.
+///
+/// Senator and governor coverage is exact.
+/// House coverage compares seat counts per state until district metadata is available.
+/// Vacant House seats are noted rather than reported as gaps.
+pub fn coverage_report(senate: &Senate, house: &House, state: &State) -> CoverageReport {
+    let mut rpt = CoverageReport::default();
+
+    // Senators.
+    let expected: BTreeMap<&str, usize> = STATES
+        .iter()
+        .map(|abbr| (*abbr, SENATORS_PER_STATE))
+        .collect();
+    cover(&mut rpt, &senate.name, &senate.persons, &expected);
+
+    // Representatives.
+    let cnt_no_state = house
+        .persons
+        .iter()
+        .filter(|per| per.state.is_empty())
+        .count();
+    if cnt_no_state == 0 {
+        let mut expected: BTreeMap<&str, usize> = HOUSE_SEATS.into_iter().collect();
+        for vac in house.vacancies.iter() {
+            if let Some(cnt) = expected.get_mut(vac.state.as_str()) {
+                *cnt = cnt.saturating_sub(1);
+            }
+            rpt.notes.push(format!("{}: vacancy {}", house.name, vac));
+        }
+        cover(&mut rpt, &house.name, &house.persons, &expected);
+    } else {
+        rpt.notes.push(format!(
+            "{}: {} members without state, skipped",
+            house.name, cnt_no_state
+        ));
+    }
+
+    // Governors.
+    let expected: BTreeMap<&str, usize> = state_names()
+        .into_iter()
+        .filter_map(state_abbr)
+        .map(|abbr| (abbr, 1))
+        .collect();
+    cover(&mut rpt, &state.name, &state.persons, &expected);
+
+    rpt
+}
+
+/// Adds gaps for states whose count of persons with addresses differs from the expected count.
+fn cover(
+    rpt: &mut CoverageReport,
+    source: &str,
+    pers: &[Person],
+    expected: &BTreeMap<&str, usize>,
+) {
+    // Group persons by state.
+    let mut by_state: BTreeMap<&str, Vec<&Person>> =
+        expected.keys().map(|abbr| (*abbr, Vec::new())).collect();
+    for per in pers {
+        by_state.entry(per.state.as_str()).or_default().push(per);
+    }
+
+    for (abbr, state_pers) in by_state {
+        let expected = expected.get(abbr).copied().unwrap_or(0);
+        let found = state_pers.iter().filter(|per| per.adr_len() != 0).count();
+        if found == expected && state_pers.len() == expected {
+            continue;
+        }
+        rpt.gaps.push(CoverageGap {
+            source: source.into(),
+            state: abbr.into(),
+            expected,
+            found,
+            names: state_pers
+                .iter()
+                .map(|per| {
+                    if per.adr_len() == 0 {
+                        format!("{} (no addresses)", per.name)
+                    } else {
+                        per.name.clone()
+                    }
+                })
+                .collect(),
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::NaiveDate;
+
+    fn per(name: &str, state: &str) -> Person {
+        Person {
+            name: name.into(),
+            state: state.into(),
+            adrs: Some(vec![Address::default()]),
+            ..Default::default()
+        }
+    }
+
+    fn rosters() -> (Senate, House, State) {
+        let mut senate = Senate::new();
+        for abbr in STATES {
+            senate.persons.push(per(&format!("{abbr} Senior"), abbr));
+            senate.persons.push(per(&format!("{abbr} Junior"), abbr));
+        }
+        let mut house = House::new();
+        for (abbr, cnt) in HOUSE_SEATS {
+            for idx in 0..cnt {
+                house.persons.push(per(&format!("{abbr} {idx}"), abbr));
+            }
+        }
+        let mut state = State::new();
+        for abbr in state_names().into_iter().filter_map(state_abbr) {
+            state.persons.push(per(&format!("{abbr} Governor"), abbr));
+        }
+        (senate, house, state)
+    }
+
+    #[test]
+    fn test_coverage_report_complete() {
+        let (senate, house, state) = rosters();
+        let rpt = coverage_report(&senate, &house, &state);
+        assert!(rpt.is_complete(), "Failed to match: {:?}", rpt.gaps);
+        assert_eq!(rpt.lnes(), vec!["complete".to_string()]);
+    }
+
+    #[test]
+    fn test_coverage_report_gaps() {
+        let (mut senate, house, mut state) = rosters();
+        // Missing senator.
+        senate.persons.retain(|per| per.name != "WY Junior");
+        // Senator without addresses.
+        senate.persons[0].adrs = None;
+        // Duplicated governor.
+        state.persons.push(per("Other Governor", "OH"));
+        let rpt = coverage_report(&senate, &house, &state);
+
+        assert_eq!(rpt.gaps.len(), 3, "Failed to match: {:?}", rpt.gaps);
+        assert_eq!(
+            rpt.gaps[0],
+            CoverageGap {
+                source: senate.name.clone(),
+                state: "AL".into(),
+                expected: 2,
+                found: 1,
+                names: vec![
+                    "AL Senior (no addresses)".to_string(),
+                    "AL Junior".to_string()
+                ],
+            }
+        );
+        assert_eq!(
+            rpt.gaps[1],
+            CoverageGap {
+                source: senate.name.clone(),
+                state: "WY".into(),
+                expected: 2,
+                found: 1,
+                names: vec!["WY Senior".to_string()],
+            }
+        );
+        assert_eq!(
+            rpt.gaps[2],
+            CoverageGap {
+                source: state.name.clone(),
+                state: "OH".into(),
+                expected: 1,
+                found: 2,
+                names: vec!["OH Governor".to_string(), "Other Governor".to_string()],
+            }
+        );
+        assert!(rpt.gaps[2].to_string().contains("surplus OH"));
+    }
+
+    #[test]
+    fn test_coverage_report_house_vacancy() {
+        let (senate, mut house, state) = rosters();
+        house.persons.retain(|per| per.name != "WI 7");
+        house.vacancies.push(Vacancy {
+            state: "WI".into(),
+            district: "8th".into(),
+            as_of: NaiveDate::from_ymd_opt(2024, 5, 1).unwrap(),
+            former: None,
+        });
+        let rpt = coverage_report(&senate, &house, &state);
+        assert!(rpt.is_complete(), "Failed to match: {:?}", rpt.gaps);
+        assert_eq!(
+            rpt.lnes(),
+            vec![format!("{}: vacancy WI 8th as of 2024-05-01", house.name)]
+        );
+    }
+
+    #[test]
+    fn test_coverage_report_house_without_state() {
+        let (senate, mut house, state) = rosters();
+        house.persons[0].state.clear();
+        let rpt = coverage_report(&senate, &house, &state);
+        assert!(rpt.is_complete());
+        assert_eq!(rpt.notes.len(), 1);
+    }
+}
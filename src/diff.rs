@@ -0,0 +1,111 @@
+use crate::models::*;
+use std::collections::BTreeMap;
+use std::fmt;
+
+/// A change to a person between two checkpoints.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Change {
+    Added {
+        name: String,
+    },
+    /// A departed member, such as a new vacancy.
+    Removed {
+        name: String,
+    },
+    AdrsChanged {
+        name: String,
+        added: Vec<Address>,
+        removed: Vec<Address>,
+    },
+    /// A person with addresses in the previous checkpoint and none now.
+    ParseFailing {
+        name: String,
+    },
+}
+impl fmt::Display for Change {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Change::Added { name } => write!(f, "added {name}"),
+            Change::Removed { name } => write!(f, "removed {name}"),
+            Change::AdrsChanged {
+                name,
+                added,
+                removed,
+            } => {
+                write!(
+                    f,
+                    "addresses changed {name}: +{} -{}",
+                    added.len(),
+                    removed.len()
+                )?;
+                for adr in added {
+                    write!(f, "\n  + {adr}")?;
+                }
+                for adr in removed {
+                    write!(f, "\n  - {adr}")?;
+                }
+                Ok(())
+            }
+            Change::ParseFailing { name } => write!(f, "parse failing {name}"),
+        }
+    }
+}
+
+/// Compares persons of two checkpoints by name.
+///
+/// Changes are ordered by name.
+pub fn diff_persons(prev: &[Person], cur: &[Person]) -> Vec<Change> {
+    let prev: BTreeMap<&str, &Person> = prev.iter().map(|per| (per.name.as_str(), per)).collect();
+    let cur: BTreeMap<&str, &Person> = cur.iter().map(|per| (per.name.as_str(), per)).collect();
+
+    let mut ret = Vec::new();
+    for (name, per_prev) in prev.iter() {
+        let Some(per_cur) = cur.get(name) else {
+            ret.push(Change::Removed {
+                name: name.to_string(),
+            });
+            continue;
+        };
+        let adrs_prev = per_prev.adrs.as_deref().unwrap_or_default();
+        let adrs_cur = per_cur.adrs.as_deref().unwrap_or_default();
+        if !adrs_prev.is_empty() && adrs_cur.is_empty() {
+            ret.push(Change::ParseFailing {
+                name: name.to_string(),
+            });
+            continue;
+        }
+        let added: Vec<Address> = adrs_cur
+            .iter()
+            .filter(|adr| !adrs_prev.contains(adr))
+            .cloned()
+            .collect();
+        let removed: Vec<Address> = adrs_prev
+            .iter()
+            .filter(|adr| !adrs_cur.contains(adr))
+            .cloned()
+            .collect();
+        if !added.is_empty() || !removed.is_empty() {
+            ret.push(Change::AdrsChanged {
+                name: name.to_string(),
+                added,
+                removed,
+            });
+        }
+    }
+    for name in cur.keys().filter(|name| !prev.contains_key(*name)) {
+        ret.push(Change::Added {
+            name: name.to_string(),
+        });
+    }
+    ret.sort_by(|a, b| change_name(a).cmp(change_name(b)));
+    ret
+}
+
+fn change_name(change: &Change) -> &str {
+    match change {
+        Change::Added { name }
+        | Change::Removed { name }
+        | Change::AdrsChanged { name, .. }
+        | Change::ParseFailing { name } => name,
+    }
+}
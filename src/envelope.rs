@@ -1,32 +1,160 @@
-use crate::core::*;
+use crate::font::*;
+use crate::imb::*;
 use crate::mailing::*;
 use crate::models::*;
-use crate::prsr::*;
-use path::PaintMode;
+use crate::qr::*;
+use anyhow::{anyhow, Result};
 use printpdf::*;
 use serde::Deserialize;
 use serde::Serialize;
 
 const LYR_FROM: &str = "FROM";
-const WIDTH: Mm = Mm(241.3);
-const HEIGHT: Mm = Mm(104.8);
+/// Font size of the address block in points.
+const FNT_SZE_TO: f32 = 12.0;
+/// Font size and line height of the return address in points.
+const FNT_SZE_FROM: f32 = 10.0;
+const LNE_HEIGHT_FROM: f32 = 12.0;
+/// Line height of the address block in points.
+const LNE_HEIGHT_TO: f32 = 18.0;
+/// Address block lines fitting above the barcode without shifting the block up.
+const LNE_CNT_TO: usize = 5;
+/// Margin of the return address from the top left corner.
+const MARGIN_FROM: Mm = Mm(10.0);
+/// Left margin of the address block on a Number 10 envelope.
+const MARGIN_TO_X: Mm = Mm(85.0);
+/// Top margin of the address block on a Number 10 envelope.
+const MARGIN_TO_Y: Mm = Mm(45.0);
+/// Right and top margins of "Return Service Requested" on a Number 10 envelope.
+const MARGIN_RSR_X: Mm = Mm(37.0);
+const MARGIN_RSR_Y: Mm = Mm(30.0);
+/// Left margin of the response QR code in the lower left corner.
+const MARGIN_QR_X: Mm = Mm(10.0);
+/// Baseline of the mailpiece id below the response QR code.
+const MARGIN_QR_Y: Mm = Mm(8.0);
+/// Gap of the barcode baseline below the last address line, 1/8" to 1/2".
+pub const BARCODE_GAP_MIN: Mm = Mm(3.175);
+pub const BARCODE_GAP_MAX: Mm = Mm(12.7);
+/// Height of the barcode clear zone along the bottom edge, 5/8".
+pub const BARCODE_CLEAR_ZONE: Mm = Mm(15.875);
 
-static FNT_IMB: &[u8] = include_bytes!("../fonts/USPSIMBStandard.ttf");
+/// Size of the envelopes of a mailing.
+#[derive(Debug, Default, Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum EnvelopeSize {
+    /// 9 1/2" x 4 1/8", for business correspondence.
+    #[default]
+    Number10,
+    /// 8 7/8" x 3 7/8", fitting in a Number 10 as a reply envelope.
+    Number9,
+    /// 9" x 6", for booklets.
+    SixByNine,
+}
+
+impl EnvelopeSize {
+    pub fn width(&self) -> Mm {
+        match self {
+            EnvelopeSize::Number10 => Mm(241.3),
+            EnvelopeSize::Number9 => Mm(225.4),
+            EnvelopeSize::SixByNine => Mm(228.6),
+        }
+    }
+
+    pub fn height(&self) -> Mm {
+        match self {
+            EnvelopeSize::Number10 => Mm(104.8),
+            EnvelopeSize::Number9 => Mm(98.4),
+            EnvelopeSize::SixByNine => Mm(152.4),
+        }
+    }
+
+    /// Scales a horizontal Number 10 margin to the width.
+    fn scale_x(&self, margin: Mm) -> Mm {
+        margin * (self.width().0 / EnvelopeSize::Number10.width().0)
+    }
+
+    /// Scales a vertical Number 10 margin to the height.
+    fn scale_y(&self, margin: Mm) -> Mm {
+        margin * (self.height().0 / EnvelopeSize::Number10.height().0)
+    }
+
+    /// Cursor of the return address.
+    pub fn from_cursor(&self) -> (Mm, Mm) {
+        (MARGIN_FROM, self.height() - MARGIN_FROM)
+    }
+
+    /// Left edge of the address block.
+    pub fn to_x(&self) -> Mm {
+        self.scale_x(MARGIN_TO_X)
+    }
+
+    /// Width of the address block, from its left edge to the right margin.
+    pub fn to_width(&self) -> Mm {
+        self.width() - self.to_x() - MARGIN_FROM
+    }
+
+    /// Width of the return address, up to a margin before the address block.
+    pub fn from_width(&self) -> Mm {
+        self.to_x() - MARGIN_FROM * 2.0
+    }
+
+    /// Wraps address block lines too wide for the address block.
+    pub fn wrap_to_lines(&self, lnes: Vec<String>) -> Vec<String> {
+        wrap_lines(lnes, FNT_SZE_TO, self.to_width())
+    }
+
+    /// Wraps return address lines too wide for the return address.
+    pub fn wrap_from_lines(&self, lnes: Vec<String>) -> Vec<String> {
+        wrap_lines(lnes, FNT_SZE_FROM, self.from_width())
+    }
+
+    /// Returns the top of an address block of lines.
+    ///
+    /// Blocks taller than `LNE_CNT_TO` lines, such as with an address line 2,
+    /// shift up so the barcode line stays within the OCR read area.
+    pub fn to_top(&self, lne_cnt: usize) -> Mm {
+        let lne_up = lne_cnt.saturating_sub(LNE_CNT_TO);
+        self.height() - self.scale_y(MARGIN_TO_Y) + Mm::from(Pt(LNE_HEIGHT_TO * lne_up as f32))
+    }
+
+    /// Baseline of the barcode, one line below an address block of lines.
+    pub fn barcode_y(&self, lne_cnt: usize) -> Mm {
+        self.to_top(lne_cnt) - Mm::from(Pt(LNE_HEIGHT_TO * lne_cnt as f32))
+    }
+
+    /// Cursor of "Return Service Requested".
+    pub fn rsr_cursor(&self) -> (Mm, Mm) {
+        (
+            self.width() - self.scale_x(MARGIN_RSR_X),
+            self.height() - self.scale_y(MARGIN_RSR_Y),
+        )
+    }
+}
 
 pub struct EnvelopeDocument {
     pub name: String,
+    pub size: EnvelopeSize,
     pub doc: PdfDocumentReference,
     pub font: IndirectFontRef,
-    pub font_barcode: IndirectFontRef,
+    /// IMb font, `None` in vector barcode mode.
+    pub font_barcode: Option<IndirectFontRef>,
     pub pg_idx1: PdfPageIndex,
     pub lyr_idx1: PdfLayerIndex,
 }
 
 impl EnvelopeDocument {
-    pub fn new(name: String) -> Self {
+    /// Creates an envelope document with an embedded TTF font, or builtin Helvetica.
+    ///
+    /// Barcodes are rendered in the IMb font, or as vector bars when the font is unavailable.
+    pub fn new(
+        name: String,
+        size: EnvelopeSize,
+        font_pth: Option<&str>,
+        mode: Option<BarcodeMode>,
+    ) -> Result<Self> {
         // Setup document.
         // A Number 10 envelope, commonly used for business and personal correspondence,
         // has dimensions of 241.3 mm in width, and 104.8 mm in height.
+        // Margins of other sizes scale with their dimensions.
         // Common envelope margins for printing can vary depending on the specific printer
         // and the design requirements, but here are some general guidelines that are
         // typically used:
@@ -35,29 +163,40 @@ impl EnvelopeDocument {
         //  * Left Margin: 10-15 mm
         //  * Right Margin: 10-15 mm
 
-        let (doc, pg_idx1, lyr_idx1) = PdfDocument::new(&name, WIDTH, HEIGHT, LYR_FROM);
+        let (doc, pg_idx1, lyr_idx1) =
+            PdfDocument::new(&name, size.width(), size.height(), LYR_FROM);
+        let doc = with_font_mode(doc, font_pth);
 
         // Setup fonts.
-        let font = doc.add_builtin_font(BuiltinFont::Helvetica).unwrap();
-        let font_barcode = doc.add_external_font(FNT_IMB).unwrap();
+        let font = add_text_font(&doc, font_pth, BuiltinFont::Helvetica)?;
+        let fnt_imb = imb_font();
+        let font_barcode = match barcode_mode(mode, fnt_imb.is_some()) {
+            BarcodeMode::Font => fnt_imb
+                .map(|fnt| doc.add_external_font(fnt.as_ref()))
+                .transpose()
+                .map_err(|err| anyhow!("font IMb: {err}"))?,
+            BarcodeMode::Vector => None,
+        };
 
-        Self {
+        Ok(Self {
             name,
+            size,
             doc,
             font,
             font_barcode,
             pg_idx1,
             lyr_idx1,
-        }
+        })
     }
 
     /// Create one envelope per page.
-    pub fn create_page(&mut self, to: &Mailpiece, is_pg1: bool) {
+    pub fn create_page(&mut self, to: &Mailpiece, is_pg1: bool) -> Result<()> {
         // Create envelope page.
         let (pg_idx, lyr_idx) = if is_pg1 {
             (self.pg_idx1, self.lyr_idx1)
         } else {
-            self.doc.add_page(WIDTH, HEIGHT, LYR_FROM)
+            self.doc
+                .add_page(self.size.width(), self.size.height(), LYR_FROM)
         };
 
         // Offset X for printer quirk.
@@ -72,22 +211,23 @@ impl EnvelopeDocument {
         // upper left corner of the envelope within the area starting:
         //  * 15 mm from the left edge of the envelope.
         //  * 15 mm from the top edge of the envelope.
-        let margin_from = Mm(10.0);
-        lyr_from.begin_text_section();
-        lyr_from.set_font(&self.font, 10.0);
-        lyr_from.set_text_cursor(margin_from + offset, HEIGHT - margin_from);
-        lyr_from.set_line_height(12.0);
-        lyr_from.write_text(CFG.from.name.clone(), &self.font);
-        lyr_from.add_line_break();
-        lyr_from.write_text(CFG.from.address1.clone(), &self.font);
-        lyr_from.add_line_break();
-        lyr_from.write_text(
+        let (from_x, from_y) = self.size.from_cursor();
+        let lnes_from = self.size.wrap_from_lines(vec![
+            CFG.from.name.clone(),
+            CFG.from.address1.clone(),
             format!(
                 "{}  {}  {:05}-{:04}",
                 CFG.from.city, CFG.from.state, CFG.from.zip5, CFG.from.zip4
             ),
-            &self.font,
-        );
+        ]);
+        lyr_from.begin_text_section();
+        lyr_from.set_font(&self.font, FNT_SZE_FROM);
+        lyr_from.set_text_cursor(from_x + offset, from_y);
+        lyr_from.set_line_height(LNE_HEIGHT_FROM);
+        for lne in lnes_from {
+            lyr_from.write_text(lne, &self.font);
+            lyr_from.add_line_break();
+        }
         lyr_from.end_text_section();
 
         // Write "to" address on envelope.
@@ -100,34 +240,36 @@ impl EnvelopeDocument {
         //  * 40 mm from the top edge of the envelope.
         // Add layers for use in Adobe Illustrator.
         let lyr_to = self.doc.get_page(pg_idx).add_layer("TO");
-        let margin_to_x = Mm(85.0) + offset;
-        let margin_to_y = Mm(45.0);
+        let margin_to_x = self.size.to_x() + offset;
+        // Wrapped lines count toward the block height, keeping the barcode line in place.
+        let lnes = self.size.wrap_to_lines(to.to_lines());
+        let lne_cnt = lnes.len();
+        let top_to = self.size.to_top(lne_cnt);
         lyr_to.begin_text_section();
-        lyr_to.set_font(&self.font, 12.0);
-        lyr_to.set_text_cursor(margin_to_x, HEIGHT - margin_to_y);
-        lyr_to.set_line_height(18.0);
-        lyr_to.write_text(dot_remove(to.name.clone()).to_uppercase(), &self.font);
-        lyr_to.add_line_break();
-        if to.title1.is_some() {
-            lyr_to.write_text(to.title1.clone().unwrap(), &self.font);
+        lyr_to.set_font(&self.font, FNT_SZE_TO);
+        lyr_to.set_text_cursor(margin_to_x, top_to);
+        lyr_to.set_line_height(LNE_HEIGHT_TO);
+        for lne in lnes {
+            lyr_to.write_text(lne, &self.font);
             lyr_to.add_line_break();
         }
-        if to.title2.is_some() {
-            lyr_to.write_text(to.title2.clone().unwrap(), &self.font);
-            lyr_to.add_line_break();
-        }
-        lyr_to.write_text(to.address1.clone(), &self.font);
-        lyr_to.add_line_break();
-        lyr_to.write_text(
-            format!("{}  {}  {:05}-{:04}", to.city, to.state, to.zip5, to.zip4),
-            &self.font,
-        );
-        lyr_to.add_line_break();
         // Write barcode.
         // See USPS guidelines https://pe.usps.com/text/qsg300/Q201a.htm.
-        lyr_to.set_font(&self.font_barcode, 16.0);
-        lyr_to.write_text(to.barcode.clone(), &self.font_barcode);
+        // Foreign mailpieces have no barcode.
+        if let (false, Some(font_barcode)) = (to.is_foreign(), &self.font_barcode) {
+            lyr_to.set_font(font_barcode, 16.0);
+            lyr_to.write_text(to.barcode.clone(), font_barcode);
+        }
         lyr_to.end_text_section();
+        // Draw vector barcode on the baseline below the address block.
+        if !to.is_foreign() && self.font_barcode.is_none() {
+            draw_imb(
+                &lyr_to,
+                &to.barcode,
+                margin_to_x,
+                self.size.barcode_y(lne_cnt),
+            )?;
+        }
 
         // // Write a permit indicia.
         // let lyr_indicia = self.doc.get_page(pg_idx).add_layer("INDICIA");
@@ -157,15 +299,36 @@ impl EnvelopeDocument {
         // let rect = Rect::new(ll_x, ll_y, ur_x, ur_y).with_mode(PaintMode::Stroke);
         // lyr_indicia.add_rect(rect);
 
+        // Draw the response QR code above its mailpiece id.
+        if let Some(base) = &CFG.qr_base_url {
+            let lyr_qr = self.doc.get_page(pg_idx).add_layer("QR");
+            draw_qr(
+                &lyr_qr,
+                &qr_url(base, to.id),
+                MARGIN_QR_X,
+                MARGIN_QR_Y + Mm(3.0),
+            )?;
+            lyr_qr.begin_text_section();
+            lyr_qr.set_font(&self.font, 7.0);
+            lyr_qr.set_text_cursor(MARGIN_QR_X, MARGIN_QR_Y);
+            lyr_qr.write_text(format!("{:06}", to.id), &self.font);
+            lyr_qr.end_text_section();
+        }
+
         // Write "Return Service Requested".
+        // Return service is domestic only.
+        if to.is_foreign() {
+            return Ok(());
+        }
         let lyr_rsr = self.doc.get_page(pg_idx).add_layer("RSR");
-        let margin_rsr_x = Mm(37.0);
-        let margin_rsr_y = Mm(30.0);
+        let (rsr_x, rsr_y) = self.size.rsr_cursor();
         lyr_rsr.begin_text_section();
         lyr_rsr.set_font(&self.font, 8.0);
-        lyr_rsr.set_text_cursor(WIDTH - margin_rsr_x, HEIGHT - margin_rsr_y);
+        lyr_rsr.set_text_cursor(rsr_x, rsr_y);
         lyr_rsr.write_text("Return Service Requested", &self.font);
         lyr_rsr.end_text_section();
+
+        Ok(())
     }
 }
 
@@ -175,3 +338,173 @@ pub struct Indicia {
     pub city_state: String,
     pub permit_id: String,
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::*;
+
+    const SIZES: [EnvelopeSize; 3] = [
+        EnvelopeSize::Number10,
+        EnvelopeSize::Number9,
+        EnvelopeSize::SixByNine,
+    ];
+
+    #[test]
+    fn test_to_top() {
+        for size in SIZES {
+            let top = size.height() - size.scale_y(MARGIN_TO_Y);
+
+            // Short blocks keep their top.
+            assert_eq!(size.to_top(3), top);
+            assert_eq!(size.to_top(LNE_CNT_TO), top);
+
+            // Taller blocks shift up, keeping the barcode line in place.
+            assert!(size.to_top(LNE_CNT_TO + 1) > top);
+            let y = size.barcode_y(LNE_CNT_TO);
+            for lne_cnt in LNE_CNT_TO + 1..=LNE_CNT_TO + 2 {
+                assert!(
+                    (size.barcode_y(lne_cnt).0 - y.0).abs() < 0.001,
+                    "Failed to match: {:?} {}",
+                    size,
+                    lne_cnt
+                );
+            }
+        }
+        assert_eq!(EnvelopeSize::Number10.to_top(3), Mm(104.8) - MARGIN_TO_Y);
+    }
+
+    #[test]
+    fn test_envelope_size() {
+        let is_inside = |size: EnvelopeSize, (x, y): (Mm, Mm)| {
+            x > Mm(0.0) && x < size.width() && y > Mm(0.0) && y < size.height()
+        };
+        for size in SIZES {
+            assert!(
+                is_inside(size, size.from_cursor()),
+                "Failed to match: {:?}",
+                size
+            );
+            assert!(
+                is_inside(size, size.rsr_cursor()),
+                "Failed to match: {:?}",
+                size
+            );
+            for lne_cnt in 3..=LNE_CNT_TO + 2 {
+                let top = size.to_top(lne_cnt);
+                let barcode_y = size.barcode_y(lne_cnt);
+                assert!(
+                    is_inside(size, (size.to_x(), top)),
+                    "Failed to match: {:?}",
+                    size
+                );
+
+                // The barcode clears the bottom edge, below the last address line.
+                let last_y = top - Mm::from(Pt(LNE_HEIGHT_TO * (lne_cnt - 1) as f32));
+                let gap = last_y - barcode_y;
+                assert!(
+                    barcode_y >= BARCODE_CLEAR_ZONE,
+                    "Failed to match: {:?}",
+                    size
+                );
+                assert!(
+                    gap >= BARCODE_GAP_MIN && gap <= BARCODE_GAP_MAX,
+                    "Failed to match: {:?}",
+                    size
+                );
+
+                // The block stays below the return address.
+                assert!(top < size.from_cursor().1, "Failed to match: {:?}", size);
+            }
+        }
+
+        // Sizes read from the mailing config.
+        let size: EnvelopeSize = serde_json::from_str(r#""sixbynine""#).unwrap();
+        assert_eq!(size, EnvelopeSize::SixByNine);
+    }
+
+    /// Renders an envelope with a line of text to pdf bytes.
+    fn render(font_pth: Option<&str>) -> String {
+        let env_doc = EnvelopeDocument::new(
+            "test".into(),
+            EnvelopeSize::Number10,
+            font_pth,
+            Some(BarcodeMode::Vector),
+        )
+        .unwrap();
+        env_doc
+            .doc
+            .get_page(env_doc.pg_idx1)
+            .get_layer(env_doc.lyr_idx1)
+            .use_text("JANE DOE", FNT_SZE_TO, Mm(10.0), Mm(10.0), &env_doc.font);
+        String::from_utf8_lossy(&env_doc.doc.save_to_bytes().unwrap()).into_owned()
+    }
+
+    #[test]
+    fn test_envelope_font_mode() {
+        // Letters are rendered by pdf_doc from their templates, without `font_pth`.
+        let pdf = render(Some(TEXT_FONT_PTH));
+        assert!(pdf.contains("/FontFile2"));
+        assert!(pdf.contains("DejaVuSans"));
+        assert!(!pdf.contains("/Helvetica"));
+        assert!(pdf.contains("font-mode:embedded"));
+
+        let pdf = render(None);
+        assert!(!pdf.contains("/FontFile2"));
+        assert!(pdf.contains("/Helvetica"));
+        assert!(pdf.contains("font-mode:builtin"));
+
+        assert!(EnvelopeDocument::new(
+            "test".into(),
+            EnvelopeSize::Number10,
+            Some("fonts/missing.ttf"),
+            None
+        )
+        .is_err());
+    }
+
+    #[test]
+    fn test_wrap_to_lines() {
+        let title = "Deputy Assistant Secretary of Defense for Special Operations and Low Intensity Conflict (SO/LIC)";
+        assert!(title.len() >= 90);
+        let mut mp = Mailpiece {
+            name: "Jane Doe".into(),
+            title1: Some(title.into()),
+            address1: "1000 DEFENSE PENTAGON".into(),
+            city: "WASHINGTON".into(),
+            state: "DC".into(),
+            zip5: 20301,
+            zip4: 1000,
+            ..Default::default()
+        };
+        for size in SIZES {
+            let lnes = size.wrap_to_lines(mp.to_lines());
+            assert!(lnes.len() >= 5, "Failed to match: {:?}", size);
+            assert_eq!(lnes.join(" "), mp.to_lines().join(" "));
+            for lne in &lnes {
+                assert!(
+                    text_width(lne, FNT_SZE_TO) <= size.to_width(),
+                    "Failed to match: {:?} {}",
+                    size,
+                    lne
+                );
+            }
+
+            // The barcode baseline matches an unwrapped block.
+            mp.title1 = Some("Secretary".into());
+            let short = size.wrap_to_lines(mp.to_lines());
+            mp.title1 = Some(title.into());
+            assert_eq!(short.len(), 4);
+            let y = size.barcode_y(short.len().max(LNE_CNT_TO));
+            assert!(
+                (size.barcode_y(lnes.len()).0 - y.0).abs() < 0.001,
+                "Failed to match: {:?}",
+                size
+            );
+
+            // Long return addresses wrap before the address block.
+            let from = size.wrap_from_lines(vec![title.into()]);
+            assert!(from.len() >= 2, "Failed to match: {:?}", size);
+        }
+    }
+}
@@ -1,20 +1,225 @@
-use crate::core::*;
+use crate::io::*;
 use crate::models::*;
 use crate::prsr::*;
+use crate::report::*;
 use crate::usps::*;
 use anyhow::{anyhow, Result};
-use reqwest::Client;
 use scraper::{Html, Selector};
 use serde::{Deserialize, Serialize};
-use std::fs::File;
-use std::io::{BufReader, BufWriter};
-use std::ops::Add;
-use std::path::Path;
+use tracing::{info, trace, warn};
 
-const FLE_PTH: &str = "executive.json";
-const FLE_PTH_URL: &str = "executive.url.json";
+pub const FLE_PTH: &str = "executive.json";
 
-const CAP_PER: usize = 4;
+/// Cabinet list of the administration.
+pub const URL: &str = "https://www.whitehouse.gov/administration/cabinet/";
+
+/// Headquarters mailing address of an executive agency, keyed by the title of its head.
+pub struct Agency {
+    pub title: &'static str,
+    pub name: &'static str,
+    pub url: &'static str,
+    pub address1: &'static str,
+    pub city: &'static str,
+    pub state: &'static str,
+    pub zip5: u32,
+    /// Zero where unknown, filled by standardizing fetched members.
+    pub zip4: u16,
+    pub delivery_point: Option<&'static str>,
+}
+
+/// Agencies of mailed cabinet members.
+///
+/// Defense is mailed by the military source and NASA by the NASA source.
+pub const AGENCIES: [Agency; 17] = [
+    agency(
+        "President",
+        "Office of the President",
+        "https://www.whitehouse.gov",
+        "1600 PENNSYLVANIA AVENUE NW",
+        20500,
+        5,
+        Some("00"),
+    ),
+    agency(
+        "Vice President",
+        "Office of the Vice President",
+        "https://www.whitehouse.gov",
+        "EEOB",
+        20501,
+        1,
+        Some("99"),
+    ),
+    agency(
+        "Secretary of State",
+        "Department of State",
+        "https://www.state.gov",
+        "2201 C STREET NW",
+        20520,
+        1,
+        Some("01"),
+    ),
+    agency(
+        "Secretary of the Treasury",
+        "Department of the Treasury",
+        "https://home.treasury.gov",
+        "1500 PENNSYLVANIA AVENUE NW",
+        20220,
+        0,
+        None,
+    ),
+    agency(
+        "Attorney General",
+        "Department of Justice",
+        "https://www.justice.gov",
+        "950 PENNSYLVANIA AVENUE NW",
+        20530,
+        0,
+        None,
+    ),
+    agency(
+        "Secretary of the Interior",
+        "Department of the Interior",
+        "https://www.doi.gov",
+        "1849 C STREET NW",
+        20240,
+        0,
+        None,
+    ),
+    agency(
+        "Secretary of Agriculture",
+        "Department of Agriculture",
+        "https://www.usda.gov",
+        "1400 INDEPENDENCE AVENUE SW",
+        20250,
+        0,
+        None,
+    ),
+    agency(
+        "Secretary of Commerce",
+        "Department of Commerce",
+        "https://www.commerce.gov",
+        "1401 CONSTITUTION AVENUE NW",
+        20230,
+        0,
+        None,
+    ),
+    agency(
+        "Secretary of Labor",
+        "Department of Labor",
+        "https://www.dol.gov",
+        "200 CONSTITUTION AVENUE NW",
+        20210,
+        0,
+        None,
+    ),
+    agency(
+        "Secretary of Health and Human Services",
+        "Department of Health and Human Services",
+        "https://www.hhs.gov",
+        "200 INDEPENDENCE AVENUE SW",
+        20201,
+        0,
+        None,
+    ),
+    agency(
+        "Secretary of Housing and Urban Development",
+        "Department of Housing and Urban Development",
+        "https://www.hud.gov",
+        "451 7TH STREET SW",
+        20410,
+        0,
+        None,
+    ),
+    agency(
+        "Secretary of Transportation",
+        "Department of Transportation",
+        "https://www.transportation.gov",
+        "1200 NEW JERSEY AVENUE SE",
+        20590,
+        0,
+        None,
+    ),
+    agency(
+        "Secretary of Energy",
+        "Department of Energy",
+        "https://www.energy.gov",
+        "1000 INDEPENDENCE AVENUE SW",
+        20585,
+        0,
+        None,
+    ),
+    agency(
+        "Secretary of Education",
+        "Department of Education",
+        "https://www.ed.gov",
+        "400 MARYLAND AVENUE SW",
+        20202,
+        0,
+        None,
+    ),
+    agency(
+        "Secretary of Veterans Affairs",
+        "Department of Veterans Affairs",
+        "https://www.va.gov",
+        "810 VERMONT AVENUE NW",
+        20420,
+        0,
+        None,
+    ),
+    agency(
+        "Secretary of Homeland Security",
+        "Department of Homeland Security",
+        "https://www.dhs.gov",
+        "2707 MARTIN LUTHER KING JR AVENUE SE",
+        20528,
+        0,
+        None,
+    ),
+    agency(
+        "Director of the National Science Foundation",
+        "National Science Foundation",
+        "https://www.nsf.gov",
+        "2415 EISENHOWER AVENUE",
+        22314,
+        0,
+        None,
+    ),
+];
+
+/// Members written when the cabinet list fails to fetch or parse, by name and title.
+pub const FALLBACK: [(&str, &str); 3] = [
+    ("Joe Biden", "President"),
+    ("Kamala Harris", "Vice President"),
+    ("Antony Blinken", "Secretary of State"),
+];
+
+/// Creates an agency in Washington, DC, or Alexandria, VA for NSF.
+const fn agency(
+    title: &'static str,
+    name: &'static str,
+    url: &'static str,
+    address1: &'static str,
+    zip5: u32,
+    zip4: u16,
+    delivery_point: Option<&'static str>,
+) -> Agency {
+    let (city, state) = if zip5 == 22314 {
+        ("ALEXANDRIA", "VA")
+    } else {
+        ("WASHINGTON", "DC")
+    };
+    Agency {
+        title,
+        name,
+        url,
+        address1,
+        city,
+        state,
+        zip5,
+        zip4,
+        delivery_point,
+    }
+}
 
 #[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Eq, PartialOrd, Ord)]
 pub struct Executive {
@@ -23,6 +228,12 @@ pub struct Executive {
     pub persons: Vec<Person>,
 }
 
+impl Default for Executive {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 impl Executive {
     pub fn new() -> Self {
         Self {
@@ -33,14 +244,29 @@ impl Executive {
     }
 
     pub async fn load() -> Result<Executive> {
+        REPORT.lock().unwrap().begin(Source::Executive);
+
         // Read file from disk.
-        let mut exec = match read_from_file::<Executive>(FLE_PTH) {
-            Ok(exec_from_disk) => exec_from_disk,
-            Err(err) => {
+        let exec = match read_from_file::<Executive>(FLE_PTH) {
+            Ok(mut exec_from_disk) => {
+                // Backfill roles of older files.
+                set_roles(&mut exec_from_disk.persons, &exec_from_disk.role);
+                exec_from_disk
+            }
+            Err(_) => {
                 let mut exec = Executive::new();
 
-                // Set members.
-                exec.persons = exec.set_members();
+                // Fetch members, falling back to built-in members.
+                exec.persons = match exec.fetch_members().await {
+                    Ok(pers) => pers,
+                    Err(err) => {
+                        warn!("{URL}: {err}");
+                        summary_add("Executive fallback", vec![format!("{URL}: {err}")], true);
+                        exec.fallback_members()
+                    }
+                };
+                set_roles(&mut exec.persons, &exec.role);
+                exec.standardize_adrs().await?;
 
                 // Write file to disk.
                 write_to_file(&exec, FLE_PTH)?;
@@ -49,71 +275,182 @@ impl Executive {
             }
         };
 
-        println!("{} executive branch members", exec.persons.len());
+        info!("{} executive branch members", exec.persons.len());
 
         Ok(exec)
     }
 
-    pub fn set_members(&self) -> Vec<Person> {
-        let mut ret = Vec::new();
+    /// Fetch cabinet members from network.
+    pub async fn fetch_members(&self) -> Result<Vec<Person>> {
+        let html = fetch_html(URL, FetchOptions::for_source(Source::Executive)).await?;
+        prs_cabinet(&html)
+    }
 
-        // President
-        let mut per = Person {
-            name: "Joe Biden".into(),
-            title1: "Office of the President".into(),
-            url: "https://www.whitehouse.gov".into(),
-            ..Default::default()
-        };
-        let adr = Address {
-            address1: "1600 PENNSYLVANIA AVENUE NW".into(),
-            city: "WASHINGTON".into(),
-            state: "DC".into(),
-            zip5: 20500,
-            zip4: 5,
-            delivery_point: Some("00".into()),
-            ..Default::default()
-        };
-        per.adrs = Some(vec![adr]);
-        ret.push(per);
-
-        // Vice President
-        let mut per = Person {
-            name: "Kamala Harris".into(),
-            title1: "Office of the Vice President".into(),
-            url: "https://www.whitehouse.gov".into(),
-            ..Default::default()
-        };
-        let adr = Address {
-            address1: "EEOB".into(),
-            city: "WASHINGTON".into(),
-            state: "DC".into(),
-            zip5: 20501,
-            zip4: 1,
-            delivery_point: Some("99".into()),
-            ..Default::default()
-        };
-        per.adrs = Some(vec![adr]);
-        ret.push(per);
-
-        // Department of State
-        let mut per = Person {
-            name: "Antony Blinken".into(),
-            title1: "Department of State".into(),
-            url: "https://www.state.gov".into(),
-            ..Default::default()
-        };
-        let adr = Address {
-            address1: "2201 C STREET NW".into(),
-            city: "WASHINGTON".into(),
-            state: "DC".into(),
-            zip5: 20520,
-            zip4: 1,
-            delivery_point: Some("01".into()),
-            ..Default::default()
+    /// Standardizes member addresses with the USPS, filling unknown zip4s and delivery points.
+    pub async fn standardize_adrs(&mut self) -> Result<()> {
+        for per in self.persons.iter_mut() {
+            if let Some(adrs) = per.adrs.take() {
+                per.adrs = Some(standardize_addresses(adrs).await?);
+            }
+        }
+        Ok(())
+    }
+
+    /// Built-in members, for when the cabinet list is unavailable.
+    pub fn fallback_members(&self) -> Vec<Person> {
+        FALLBACK
+            .iter()
+            .filter_map(|(name, title)| new_member(name, title))
+            .collect()
+    }
+}
+
+/// Returns the agency headed by a title, such as "Secretary of State".
+///
+/// Ignores case and a leading "The".
+pub fn agency_of(title: &str) -> Option<&'static Agency> {
+    let title = title.trim();
+    let title = match title.get(..4) {
+        Some(pre) if pre.eq_ignore_ascii_case("the ") => &title[4..],
+        _ => title,
+    };
+    AGENCIES
+        .iter()
+        .find(|agency| agency.title.eq_ignore_ascii_case(title))
+}
+
+/// Creates a member with the headquarters address of the agency headed by a title.
+pub fn new_member(name: &str, title: &str) -> Option<Person> {
+    let agency = agency_of(title)?;
+    let adr = Address {
+        address1: agency.address1.into(),
+        city: agency.city.into(),
+        state: agency.state.into(),
+        zip5: agency.zip5,
+        zip4: agency.zip4,
+        delivery_point: agency.delivery_point.map(String::from),
+        ..Default::default()
+    };
+    Some(Person {
+        name: name_clean(name),
+        title1: agency.name.into(),
+        url: agency.url.into(),
+        adrs: Some(vec![adr]),
+        provenance: Some(Provenance::new(Source::Executive, URL)),
+        ..Default::default()
+    })
+}
+
+/// Parses cabinet members of a cabinet list.
+///
+/// A member is a name line followed by a title line of a mailed agency, such as
+/// "Marco Rubio" above "Secretary of State". Titles of other agencies are skipped.
+pub fn prs_cabinet(html: &str) -> Result<Vec<Person>> {
+    let document = Html::parse_document(html);
+    let body_sel = Selector::parse("body").unwrap();
+    let lnes: Vec<String> = document
+        .select(&body_sel)
+        .flat_map(|elm| elm.text())
+        .map(|s| s.split_whitespace().collect::<Vec<_>>().join(" "))
+        .filter(|s| !s.is_empty())
+        .collect();
+
+    let mut pers: Vec<Person> = Vec::with_capacity(AGENCIES.len());
+    for (idx, lne) in lnes.iter().enumerate().skip(1) {
+        let name = &lnes[idx - 1];
+        if agency_of(name).is_some() {
+            continue;
+        }
+        let Some(per) = new_member(name, lne) else {
+            continue;
         };
-        per.adrs = Some(vec![adr]);
-        ret.push(per);
+        if pers.iter().any(|cur| cur.title1 == per.title1) {
+            continue;
+        }
+        trace!("{lne}: {name}");
+        pers.push(per);
+    }
+
+    if pers.is_empty() {
+        return Err(anyhow!("no cabinet members"));
+    }
+    Ok(pers)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_agency_of() {
+        let cases = [
+            ("Secretary of State", Some("Department of State")),
+            (
+                "The Secretary of the Treasury",
+                Some("Department of the Treasury"),
+            ),
+            ("vice president", Some("Vice President")),
+            ("Secretary of Defense", None),
+            ("", None),
+        ];
+        for (title, expected) in cases {
+            assert_eq!(
+                agency_of(title).map(|agency| agency.name.replace("Office of the ", "")),
+                expected.map(String::from),
+                "Failed to match: {}",
+                title
+            );
+        }
+    }
+
+    #[test]
+    fn test_prs_cabinet() {
+        let html = r#"<html><body><h1>The Cabinet</h1>
+            <div><h3>Jane Doe</h3><p>The Vice President</p></div>
+            <div><h3>John Roe</h3><p>Secretary of State</p></div>
+            <div><h3>Pat Poe</h3><p>Secretary of Defense</p></div>
+            <div><h3>Sam Moe</h3><p>Secretary of Energy</p></div>
+            <div><h3>Ana Loe</h3><p>Director of the National Science Foundation</p></div>
+        </body></html>"#;
+        let pers = prs_cabinet(html).unwrap();
+        let names: Vec<(&str, &str)> = pers
+            .iter()
+            .map(|per| (per.name.as_str(), per.title1.as_str()))
+            .collect();
+        assert_eq!(
+            names,
+            vec![
+                ("Jane Doe", "Office of the Vice President"),
+                ("John Roe", "Department of State"),
+                ("Sam Moe", "Department of Energy"),
+                ("Ana Loe", "National Science Foundation"),
+            ]
+        );
+        let adr = &pers[2].adrs.as_ref().unwrap()[0];
+        assert_eq!(adr.address1, "1000 INDEPENDENCE AVENUE SW");
+        assert_eq!((adr.state.as_str(), adr.zip5), ("DC", 20585));
+        let adr = &pers[3].adrs.as_ref().unwrap()[0];
+        assert_eq!(
+            (adr.city.as_str(), adr.state.as_str()),
+            ("ALEXANDRIA", "VA")
+        );
+
+        assert_eq!(pers[0].provenance.as_ref().unwrap().url, URL);
+
+        assert!(prs_cabinet("<html><body><p>Cabinet</p></body></html>").is_err());
+    }
 
-        ret
+    #[test]
+    fn test_fallback_members() {
+        let pers = Executive::new().fallback_members();
+        assert_eq!(pers.len(), FALLBACK.len());
+        assert_eq!(pers[0].name, "Joe Biden");
+        assert_eq!(pers[0].title1, "Office of the President");
+        let adr = &pers[0].adrs.as_ref().unwrap()[0];
+        assert_eq!(adr.address1, "1600 PENNSYLVANIA AVENUE NW");
+        assert_eq!((adr.zip5, adr.zip4), (20500, 5));
+        assert_eq!(adr.delivery_point.as_deref(), Some("00"));
+        assert_eq!(pers[2].url, "https://www.state.gov");
+        assert_eq!(pers[2].adrs.as_ref().unwrap()[0].zip4, 1);
     }
 }
@@ -0,0 +1,185 @@
+use crate::models::*;
+use anyhow::{anyhow, Result};
+use csv::Writer;
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+use tracing::debug;
+
+/// Combined person and address list of a run.
+pub const FLE_PTH_CSV: &str = "persons.csv";
+
+/// A row of the person csv, one per address.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Eq)]
+pub struct PersonRow {
+    pub name: String,
+    pub title1: String,
+    pub title2: String,
+    pub source: Source,
+    pub address1: String,
+    pub address2: String,
+    pub city: String,
+    pub state: String,
+    /// Zero-padded, such as `02109`.
+    pub zip5: String,
+    /// Zero-padded, empty when unknown.
+    pub zip4: String,
+    /// Single line, such as `1 STATE ST, STE 2, BOSTON, MA 02109-1234`.
+    pub address: String,
+    pub delivery_point: String,
+    /// Page the addresses were parsed from, empty when unknown.
+    pub provenance_url: String,
+    /// Fetch time of the addresses, such as `2026-10-01T09:30:00`, empty when unknown.
+    pub fetched_at: String,
+}
+
+impl PersonRow {
+    pub fn new(per: &Person, source: Source, adr: &Address) -> Self {
+        Self {
+            name: per.name.clone(),
+            title1: per.title1.clone(),
+            title2: per.title2.clone(),
+            source,
+            address1: adr.address1.clone(),
+            address2: adr.address2.clone().unwrap_or_default(),
+            city: adr.city.clone(),
+            state: adr.state.clone(),
+            zip5: format!("{:05}", adr.zip5),
+            zip4: if adr.zip4 == 0 {
+                String::new()
+            } else {
+                format!("{:04}", adr.zip4)
+            },
+            address: adr.to_single_line(),
+            delivery_point: adr.delivery_point.clone().unwrap_or_default(),
+            provenance_url: per
+                .provenance
+                .as_ref()
+                .map(|prov| prov.url.clone())
+                .unwrap_or_default(),
+            fetched_at: per
+                .provenance
+                .as_ref()
+                .map(|prov| prov.fetched_at.format("%Y-%m-%dT%H:%M:%S").to_string())
+                .unwrap_or_default(),
+        }
+    }
+}
+
+/// Writes persons to a csv file, one row per address.
+///
+/// Sources are parallel to persons.
+pub fn export_csv(pers: &[Person], srcs: &[Source], pth: &Path) -> Result<()> {
+    if pers.len() != srcs.len() {
+        return Err(anyhow!(
+            "export_csv: {} persons with {} sources",
+            pers.len(),
+            srcs.len()
+        ));
+    }
+    debug!("Writing file: {}", pth.display());
+    let mut wtr = Writer::from_path(pth)?;
+    for (per, src) in pers.iter().zip(srcs) {
+        for adr in per.adrs.iter().flatten() {
+            wtr.serialize(PersonRow::new(per, *src, adr))?;
+        }
+    }
+    wtr.flush()?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::*;
+    use chrono::NaiveDate;
+
+    #[test]
+    fn test_export_csv() {
+        let dir = TempDir::new("export");
+        let pth = dir.join("persons.csv");
+
+        let pers = vec![
+            Person {
+                name: "Jerry L. Carl, Jr.".into(),
+                title1: "Representative".into(),
+                title2: "\"District\" 1".into(),
+                adrs: Some(vec![
+                    Address {
+                        address1: "1330 LHOB".into(),
+                        city: "WASHINGTON".into(),
+                        state: "DC".into(),
+                        zip5: 20515,
+                        zip4: 101,
+                        delivery_point: Some("30".into()),
+                        ..Default::default()
+                    },
+                    Address {
+                        address1: "41 W I-65 SERVICE RD N".into(),
+                        address2: Some("STE 2300-A".into()),
+                        city: "MOBILE".into(),
+                        state: "AL".into(),
+                        zip5: 36608,
+                        ..Default::default()
+                    },
+                ]),
+                provenance: Some(Provenance {
+                    source: Source::House,
+                    url: "https://carl.house.gov/contact/offices".into(),
+                    fetched_at: NaiveDate::from_ymd_opt(2026, 10, 1)
+                        .unwrap()
+                        .and_hms_opt(9, 30, 0)
+                        .unwrap(),
+                }),
+                ..Default::default()
+            },
+            Person {
+                name: "Elizabeth Warren".into(),
+                adrs: Some(vec![Address {
+                    address1: "2400 JFK FEDERAL BLDG".into(),
+                    city: "BOSTON".into(),
+                    state: "MA".into(),
+                    zip5: 2203,
+                    ..Default::default()
+                }]),
+                ..Default::default()
+            },
+        ];
+        let srcs = vec![Source::House, Source::Senate];
+        export_csv(&pers, &srcs, &pth).unwrap();
+
+        let mut rdr = csv::Reader::from_path(&pth).unwrap();
+        let rows: Vec<PersonRow> = rdr.deserialize().map(|row| row.unwrap()).collect();
+        assert_eq!(rows.len(), 3);
+        assert_eq!(
+            rows[0],
+            PersonRow::new(&pers[0], Source::House, &pers[0].adrs.as_ref().unwrap()[0])
+        );
+        assert_eq!(rows[0].name, "Jerry L. Carl, Jr.");
+        assert_eq!(rows[0].title2, "\"District\" 1");
+        assert_eq!(rows[0].zip4, "0101");
+        assert_eq!(rows[1].address2, "STE 2300-A");
+        assert_eq!(rows[1].zip4, "");
+        assert_eq!(rows[0].address, "1330 LHOB, WASHINGTON, DC 20515-0101");
+        assert_eq!(
+            rows[1].address,
+            "41 W I-65 SERVICE RD N, STE 2300-A, MOBILE, AL 36608"
+        );
+        assert_eq!(rows[2].source, Source::Senate);
+        assert_eq!(rows[2].zip5, "02203");
+        assert_eq!(rows[2].address, "2400 JFK FEDERAL BLDG, BOSTON, MA 02203");
+        assert_eq!(
+            rows[2].address.parse::<Address>().unwrap(),
+            pers[1].adrs.as_ref().unwrap()[0]
+        );
+        assert_eq!(
+            rows[1].provenance_url,
+            "https://carl.house.gov/contact/offices"
+        );
+        assert_eq!(rows[1].fetched_at, "2026-10-01T09:30:00");
+        assert_eq!(rows[2].provenance_url, "");
+        assert_eq!(rows[2].fetched_at, "");
+
+        // Sources must be parallel to persons.
+        assert!(export_csv(&pers, &srcs[..1], &pth).is_err());
+    }
+}
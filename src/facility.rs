@@ -0,0 +1,209 @@
+use crate::io::*;
+use crate::models::*;
+use crate::prsr::*;
+use crate::report::*;
+use crate::usps::*;
+use anyhow::{anyhow, Result};
+use scraper::{Html, Selector};
+use std::collections::{BTreeMap, HashMap};
+use std::fmt::Debug;
+use std::hash::Hash;
+use strum::IntoEnumIterator;
+use tracing::{debug, trace};
+
+/// A facility whose address is scraped from a web page, such as a NASA center.
+pub trait Facility: Debug + Copy + Eq + Hash + IntoEnumIterator {
+    /// Source of the facility, selecting its shared line edits.
+    const SOURCE: Source;
+    /// Html selectors of address lines, tried in order until one has lines.
+    const SELECTORS: &'static [&'static str];
+
+    /// Url of the page with the facility address; empty to skip the facility.
+    fn url(&self) -> &str;
+
+    /// Key of the facility in its address file, its variant name.
+    fn cache_key(&self) -> String {
+        format!("{self:?}")
+    }
+
+    /// Edits address lines of the facility before parsing.
+    fn edit_lnes(&self, _lnes: &mut Vec<String>) {}
+
+    /// Edits the standardized address of the facility.
+    fn edit_adr(&self, _adr: &mut Address) {}
+
+    /// Provenance of the facility address.
+    fn provenance(&self) -> Provenance {
+        Provenance::new(Self::SOURCE, self.url())
+    }
+}
+
+/// Reads facility addresses from a file, fetching and writing them when unreadable.
+pub async fn fetch_facility_adrs<F: Facility>(pth: &str) -> Result<HashMap<F, Address>> {
+    // Read file from disk.
+    if let Ok(map_adrs) = read_facility_adrs(pth) {
+        return Ok(map_adrs);
+    }
+
+    let mut map_adrs = HashMap::new();
+
+    // Iterate through each facility.
+    for fac in F::iter() {
+        debug!("{:?}", fac);
+
+        // Get url.
+        let url = fac.url();
+        if url.is_empty() {
+            continue;
+        }
+
+        // Fetch, parse, and standardize each address.
+        if let Some(mut adr) = fetch_prs_std_adr(fac, url).await? {
+            fac.edit_adr(&mut adr);
+            map_adrs.insert(fac, adr);
+        }
+    }
+
+    // Write file to disk.
+    let by_key: BTreeMap<String, &Address> = map_adrs
+        .iter()
+        .map(|(fac, adr)| (fac.cache_key(), adr))
+        .collect();
+    write_to_file(&by_key, pth)?;
+
+    Ok(map_adrs)
+}
+
+/// Reads facility addresses keyed by `cache_key`.
+///
+/// An unknown key is an error, so the addresses are refetched.
+fn read_facility_adrs<F: Facility>(pth: &str) -> Result<HashMap<F, Address>> {
+    let by_key = read_from_file::<HashMap<String, Address>>(pth)?;
+    by_key
+        .into_iter()
+        .map(|(key, adr)| {
+            F::iter()
+                .find(|fac| fac.cache_key() == key)
+                .map(|fac| (fac, adr))
+                .ok_or_else(|| anyhow!("unknown facility '{key}' in {pth}"))
+        })
+        .collect()
+}
+
+/// Fetch, parse, and standardize an address.
+pub async fn fetch_prs_std_adr<F: Facility>(fac: F, url: &str) -> Result<Option<Address>> {
+    // Fetch html.
+    let html = fetch_html(url, FetchOptions::for_source(F::SOURCE)).await?;
+
+    // Parse html to address lines.
+    let adr_lnes_o = prs_adr_lnes(fac, &html);
+
+    // Parse lines to addresses.
+    let adrs_o = adr_lnes_o.and_then(|adr_lnes| adrs_or_next(url, PRSR.prs_adrs(&adr_lnes)));
+    let adrs_o = match adrs_o {
+        None => None,
+        Some(mut adrs) => {
+            adrs = standardize_addresses(adrs).await?;
+            if adrs.is_empty() {
+                None
+            } else {
+                Some(adrs.remove(0))
+            }
+        }
+    };
+
+    Ok(adrs_o)
+}
+
+pub fn prs_adr_lnes<F: Facility>(fac: F, html: &str) -> Option<Vec<String>> {
+    let document = Html::parse_document(html);
+    let mut lnes: Vec<String> = Vec::new();
+    for txt in F::SELECTORS {
+        let selector = Selector::parse(txt).unwrap();
+        for elm in document.select(&selector) {
+            // Extract lines from html.
+            let mut cur_lnes = elm
+                .text()
+                .map(|s| s.trim().trim_end_matches(',').to_uppercase().to_string())
+                .collect::<Vec<String>>();
+
+            // eprintln!("--- pre: {cur_lnes:?}");
+
+            // Filter lines.
+            // Filter separately to allow debugging.
+            cur_lnes = cur_lnes
+                .into_iter()
+                .filter(|s| PRSR.filter(s))
+                .collect::<Vec<String>>();
+
+            if !cur_lnes.is_empty() {
+                trace!("{cur_lnes:?}");
+
+                lnes.extend(cur_lnes);
+            }
+        }
+
+        if !lnes.is_empty() {
+            break;
+        }
+    }
+
+    // eprintln!("--- pre: {lnes:?}");
+
+    // Edit lines to make it easier to parse.
+    edit_dot(&mut lnes);
+    edit_nbsp_zwsp(&mut lnes);
+    edit_mailing(&mut lnes);
+    edit_counted(&mut lnes, |lnes| fac.edit_lnes(lnes));
+    PRSR.edit_lnes(&mut lnes, F::SOURCE);
+    edit_newline(&mut lnes);
+    edit_split_comma(&mut lnes);
+    edit_starting_hash(&mut lnes);
+    edit_char_half(&mut lnes);
+    edit_empty(&mut lnes);
+
+    trace!("--- --- --- post: {lnes:?}");
+
+    // Do not check for zip count here.
+
+    Some(lnes)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::military::MilCenter;
+    use crate::nasa::Center;
+    use crate::test_support::*;
+
+    #[test]
+    fn test_read_facility_adrs() {
+        let dir = TempDir::new("facility");
+        let pth = dir.pth("military_adr.json");
+
+        // Address files keyed by variant name still read.
+        let adr = Address {
+            address1: "4251 SUITLAND RD".into(),
+            city: "WASHINGTON".into(),
+            state: "DC".into(),
+            zip5: 20395,
+            ..Default::default()
+        };
+        let legacy = HashMap::from([(MilCenter::Oni, adr.clone())]);
+        write_to_file(&legacy, &pth).unwrap();
+        let map_adrs = read_facility_adrs::<MilCenter>(&pth).unwrap();
+        assert_eq!(map_adrs, legacy);
+
+        // Keys of another facility are refetched.
+        assert!(read_facility_adrs::<Center>(&pth).is_err());
+    }
+
+    #[test]
+    fn test_prs_adr_lnes() {
+        let html = "<html><body><h6>Office of Naval Intelligence</h6>\
+            <h6>4251 Suitland Road</h6><h6>Washington, DC 20395, USA</h6></body></html>";
+        let lnes = prs_adr_lnes(MilCenter::Oni, html).unwrap();
+        assert!(lnes.contains(&"20395".to_string()), "{lnes:?}");
+        assert!(lnes.iter().all(|lne| !lne.contains("USA")), "{lnes:?}");
+    }
+}
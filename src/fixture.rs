@@ -0,0 +1,190 @@
+use crate::io::*;
+use crate::models::*;
+use crate::prsr::*;
+use anyhow::{anyhow, Result};
+use chrono::{Local, NaiveDate};
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::{Path, PathBuf};
+use tracing::info;
+
+/// Directory of offline html fixtures.
+pub const FIXTURES_DIR: &str = "tests/fixtures";
+
+lazy_static! {
+    /// Regexes matching html removed from fixtures.
+    static ref RE_SCRUB: Vec<Regex> = vec![
+        Regex::new(r"(?is)<script\b.*?</script\s*>").unwrap(),
+        Regex::new(r"(?is)<style\b.*?</style\s*>").unwrap(),
+        Regex::new(r"(?is)<noscript\b.*?</noscript\s*>").unwrap(),
+        Regex::new(r"(?is)<svg\b.*?</svg\s*>").unwrap(),
+        Regex::new(r"(?is)<!--.*?-->").unwrap(),
+        Regex::new(r"(?is)<link\b[^>]*>").unwrap(),
+    ];
+    /// A regex matching an inline base64 blob.
+    static ref RE_BLOB: Regex = Regex::new(r"(?i)data:[a-z0-9.+/-]+;base64,[a-z0-9+/=]+").unwrap();
+    /// A regex matching blank lines.
+    static ref RE_BLANK: Regex = Regex::new(r"\n[ \t]*(?:\n[ \t]*)+").unwrap();
+}
+
+/// Expected parser output of a fixture.
+///
+/// Generated from the current parser and reviewed before commit.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct FixtureSidecar {
+    pub url: String,
+    pub source: Source,
+    pub recorded: NaiveDate,
+    pub adrs: Vec<Address>,
+}
+
+/// Removes scripts, styles, comments, and binary blobs from html.
+pub fn scrub_html(html: &str) -> String {
+    let mut ret = html.to_string();
+    for re in RE_SCRUB.iter() {
+        ret = re.replace_all(&ret, "").into_owned();
+    }
+    ret = RE_BLOB.replace_all(&ret, "data:,").into_owned();
+    ret = RE_BLANK.replace_all(&ret, "\n").into_owned();
+    ret
+}
+
+/// Parses addresses of a fixture with the parser of its source.
+///
+/// The fixture name is used as the state of state fixtures.
+pub fn prs_fixture(source: Source, name: &str, html: &str) -> Result<Vec<Address>> {
+    let per = Person::default();
+    let lnes = match source {
+        // Senate lines parse one element at a time.
+        Source::Senate => {
+            return crate::senate::prs_html_adrs(&per, html)
+                .map_err(|err| anyhow!("fixture '{name}' does not parse: {err}"))
+        }
+        Source::House => crate::house::prs_adr_lnes(&per, html),
+        Source::State => crate::state::prs_adr_lnes(name, html),
+        _ => return Err(anyhow!("no fixture parser for source '{source}'")),
+    };
+    let lnes = lnes.ok_or_else(|| anyhow!("fixture '{name}' does not parse: no address lines"))?;
+    PRSR.prs_adrs(&lnes)
+        .map_err(|err| anyhow!("fixture '{name}' does not parse: {err}"))
+}
+
+/// Creates the sidecar of a fixture from the current parser output.
+pub fn fixture_sidecar(
+    url: &str,
+    source: Source,
+    name: &str,
+    html: &str,
+) -> Result<FixtureSidecar> {
+    Ok(FixtureSidecar {
+        url: url.into(),
+        source,
+        recorded: Local::now().date_naive(),
+        adrs: prs_fixture(source, name, html)?,
+    })
+}
+
+/// Writes a fixture and its sidecar to `dir/<source>/<name>.html` and `.json`.
+///
+/// Existing fixtures are only overwritten with `force`.
+pub fn write_fixture(
+    dir: &Path,
+    name: &str,
+    html: &str,
+    sidecar: &FixtureSidecar,
+    force: bool,
+) -> Result<PathBuf> {
+    let mut pth = dir.join(sidecar.source.to_string());
+    fs::create_dir_all(&pth)?;
+    pth.push(name);
+    pth.set_extension("html");
+    if pth.exists() && !force {
+        return Err(anyhow!("fixture {pth:?} exists, use --force to overwrite"));
+    }
+    fs::write(&pth, html)?;
+    write_to_file(sidecar, &pth.with_extension("json").to_string_lossy())?;
+    Ok(pth)
+}
+
+/// Fetches a page live, verifies it parses, and records it as a scrubbed fixture.
+pub async fn record_fixture(url: &str, name: &str, source: Source, force: bool) -> Result<PathBuf> {
+    let html = scrub_html(&fetch_html_live(url).await?);
+    let sidecar = fixture_sidecar(url, source, name, &html)?;
+    info!("{}", AddressList(sidecar.adrs.clone()));
+    let pth = write_fixture(Path::new(FIXTURES_DIR), name, &html, &sidecar, force)?;
+    info!("recorded {pth:?}; review the sidecar before commit");
+    Ok(pth)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::*;
+
+    const HTML_SAMPLE: &str = include_str!("../tests/fixtures/house/sample_offices.html");
+
+    #[test]
+    fn test_scrub_html() {
+        let html = r#"<html><head><script type="text/javascript">var a = "<p>1 MAIN ST</p>";</script>
+<style>.x { color: red; }</style><link rel="stylesheet" href="a.css">
+</head>
+<body><!-- comment --><img src="data:image/png;base64,iVBORw0KGgo=">
+
+
+<p>1 MAIN ST</p><svg><path d="M0"/></svg><noscript>enable js</noscript></body></html>"#;
+        let scrubbed = scrub_html(html);
+        assert_eq!(
+            scrubbed,
+            "<html><head>\n</head>\n<body><img src=\"data:,\">\n<p>1 MAIN ST</p></body></html>"
+        );
+    }
+
+    #[test]
+    fn test_fixture_sidecar() {
+        let html = scrub_html(HTML_SAMPLE);
+        assert!(!html.contains("<script"));
+        assert!(html.contains("2312 Rayburn"));
+
+        let sidecar =
+            fixture_sidecar("https://sample.house.gov", Source::House, "sample", &html).unwrap();
+        assert_eq!(sidecar.source, Source::House);
+        assert_eq!(sidecar.adrs.len(), 2, "Failed to match: {:?}", sidecar.adrs);
+        assert!(sidecar
+            .adrs
+            .iter()
+            .any(|adr| adr.address1 == "2312 RHOB" && adr.zip5 == 20515));
+        assert!(sidecar
+            .adrs
+            .iter()
+            .any(|adr| adr.address1 == "100 MAIN STREET" && adr.city == "DAYTON"));
+
+        // Unparsable pages are rejected.
+        assert!(fixture_sidecar(
+            "https://sample.house.gov",
+            Source::House,
+            "empty",
+            "<p></p>"
+        )
+        .is_err());
+    }
+
+    #[test]
+    fn test_write_fixture_force() {
+        let dir = TempDir::new("fixture");
+        let sidecar = FixtureSidecar {
+            url: "https://sample.house.gov".into(),
+            source: Source::House,
+            recorded: NaiveDate::from_ymd_opt(2024, 1, 1).unwrap(),
+            adrs: Vec::new(),
+        };
+
+        let pth = write_fixture(&dir, "sample", "<p>1</p>", &sidecar, false).unwrap();
+        assert_eq!(pth, dir.join("house").join("sample.html"));
+        assert!(pth.with_extension("json").exists());
+        assert!(write_fixture(&dir, "sample", "<p>2</p>", &sidecar, false).is_err());
+        assert_eq!(fs::read_to_string(&pth).unwrap(), "<p>1</p>");
+        write_fixture(&dir, "sample", "<p>2</p>", &sidecar, true).unwrap();
+        assert_eq!(fs::read_to_string(&pth).unwrap(), "<p>2</p>");
+    }
+}
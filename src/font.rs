@@ -0,0 +1,157 @@
+use anyhow::{anyhow, Result};
+use printpdf::*;
+use std::fmt;
+use std::fs;
+
+/// How text fonts are written to a pdf.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FontMode {
+    /// A builtin font, substituted by the printer.
+    Builtin,
+    /// A TTF embedded in the pdf.
+    Embedded,
+}
+impl fmt::Display for FontMode {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            FontMode::Builtin => write!(f, "builtin"),
+            FontMode::Embedded => write!(f, "embedded"),
+        }
+    }
+}
+
+/// Returns the font mode of a configured font path.
+pub fn font_mode(font_pth: Option<&str>) -> FontMode {
+    match font_pth {
+        Some(_) => FontMode::Embedded,
+        None => FontMode::Builtin,
+    }
+}
+
+/// Notes the font mode in the pdf metadata keywords, such as `font-mode:embedded`.
+pub fn with_font_mode(doc: PdfDocumentReference, font_pth: Option<&str>) -> PdfDocumentReference {
+    doc.with_keywords(vec![format!("font-mode:{}", font_mode(font_pth))])
+}
+
+/// Adds the text font of a document.
+///
+/// Embeds the TTF at the font path once per document, or adds the builtin `fallback`.
+pub fn add_text_font(
+    doc: &PdfDocumentReference,
+    font_pth: Option<&str>,
+    fallback: BuiltinFont,
+) -> Result<IndirectFontRef> {
+    match font_pth {
+        Some(pth) => {
+            let bytes = fs::read(pth).map_err(|err| anyhow!("font {pth}: {err}"))?;
+            doc.add_external_font(bytes.as_slice())
+                .map_err(|err| anyhow!("font {pth}: {err}"))
+        }
+        None => doc
+            .add_builtin_font(fallback)
+            .map_err(|err| anyhow!("font {fallback:?}: {err}")),
+    }
+}
+
+/// Advance of a Helvetica character in thousandths of the font size.
+///
+/// Liberation Sans shares the metrics. Characters outside the table take the digit width.
+fn helvetica_advance(c: char) -> u16 {
+    const UPPER: [u16; 26] = [
+        667, 667, 722, 722, 667, 611, 778, 722, 278, 500, 667, 556, 833, 722, 778, 667, 778, 722,
+        667, 611, 722, 667, 944, 667, 667, 611,
+    ];
+    const LOWER: [u16; 26] = [
+        556, 556, 500, 556, 556, 278, 556, 556, 222, 222, 500, 222, 833, 556, 556, 556, 556, 333,
+        500, 278, 556, 500, 722, 500, 500, 500,
+    ];
+    match c {
+        'A'..='Z' => UPPER[c as usize - 'A' as usize],
+        'a'..='z' => LOWER[c as usize - 'a' as usize],
+        ' ' | '.' | ',' | '/' | ':' | ';' => 278,
+        '-' | '(' | ')' => 333,
+        '\'' => 191,
+        '&' => 667,
+        _ => 556,
+    }
+}
+
+/// Approximate width of Helvetica text at a font size in points.
+pub fn text_width(txt: &str, fnt_sze: f32) -> Mm {
+    let advance: u32 = txt.chars().map(|c| helvetica_advance(c) as u32).sum();
+    Mm::from(Pt(advance as f32 / 1000.0 * fnt_sze))
+}
+
+/// Wraps text on word boundaries to lines fitting a width.
+///
+/// A word wider than the width keeps a line of its own.
+pub fn wrap_text(txt: &str, fnt_sze: f32, width: Mm) -> Vec<String> {
+    let mut lnes: Vec<String> = Vec::new();
+    let mut lne = String::new();
+    for word in txt.split_whitespace() {
+        let cur = if lne.is_empty() {
+            word.to_string()
+        } else {
+            format!("{lne} {word}")
+        };
+        if !lne.is_empty() && text_width(&cur, fnt_sze) > width {
+            lnes.push(std::mem::replace(&mut lne, word.to_string()));
+        } else {
+            lne = cur;
+        }
+    }
+    if !lne.is_empty() || lnes.is_empty() {
+        lnes.push(lne);
+    }
+    lnes
+}
+
+/// Wraps each line too wide for a width, keeping line order.
+pub fn wrap_lines(lnes: Vec<String>, fnt_sze: f32, width: Mm) -> Vec<String> {
+    lnes.into_iter()
+        .flat_map(|lne| {
+            if text_width(&lne, fnt_sze) <= width {
+                vec![lne]
+            } else {
+                wrap_text(&lne, fnt_sze, width)
+            }
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_wrap_text() {
+        // 12pt caps average about 2.7mm.
+        let w = text_width("ASSISTANT SECRETARY", 12.0);
+        assert!(w > Mm(45.0) && w < Mm(55.0), "{w:?}");
+
+        let cases = [
+            ("", vec![""]),
+            ("JANE DOE", vec!["JANE DOE"]),
+            (
+                "ASSISTANT SECRETARY OF DEFENSE FOR SPECIAL OPERATIONS",
+                vec![
+                    "ASSISTANT SECRETARY OF",
+                    "DEFENSE FOR SPECIAL",
+                    "OPERATIONS",
+                ],
+            ),
+            (
+                "SUPERCALIFRAGILISTICEXPIALIDOCIOUS",
+                vec!["SUPERCALIFRAGILISTICEXPIALIDOCIOUS"],
+            ),
+        ];
+        for (txt, expected) in cases {
+            assert_eq!(
+                wrap_text(txt, 12.0, Mm(60.0)),
+                expected,
+                "Failed to match: {}",
+                txt
+            );
+        }
+    }
+}
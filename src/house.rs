@@ -1,26 +1,66 @@
-use crate::core::*;
+use crate::io::*;
+use crate::manual::*;
 use crate::models::*;
+use crate::office_pdf::*;
 use crate::prsr::*;
+use crate::report::*;
 use crate::usps::*;
 use anyhow::{anyhow, Result};
-use reqwest::Client;
-use scraper::{Html, Selector};
+use chrono::{Local, NaiveDate};
+use futures_util::{stream, StreamExt};
+use scraper::{ElementRef, Html, Selector};
 use serde::{Deserialize, Serialize};
-use std::fs::File;
-use std::io::{BufReader, BufWriter};
-use std::ops::Add;
-use std::path::Path;
+use std::fmt;
+use std::sync::Mutex;
+use std::time::Instant;
+use tracing::{debug, info, trace, warn};
 
-const FLE_PTH: &str = "house.json";
+pub const FLE_PTH: &str = "house.json";
 
 /// The total number of members in the U.S. House of Representatives is 441. This includes 435 voting members who represent the 50 states and 6 non-voting members who represent the District of Columbia, Puerto Rico, and four other U.S. territories (American Samoa, Guam, the Northern Mariana Islands, and the U.S. Virgin Islands). Some members may be vacant.
 const CAP_PER: usize = 441;
 
+/// Title line of a representative on the envelope.
+pub const TITLE_REPRESENTATIVE: &str = "Member of Congress";
+
 #[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Eq, PartialOrd, Ord)]
 pub struct House {
     pub name: String,
     pub role: Role,
     pub persons: Vec<Person>,
+    /// Vacant seats, which receive no mailpieces.
+    #[serde(default)]
+    pub vacancies: Vec<Vacancy>,
+    /// Date the member list was fetched; `None` in older files.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub members_on: Option<NaiveDate>,
+}
+
+/// A vacant House seat.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Eq, PartialOrd, Ord)]
+pub struct Vacancy {
+    pub state: String,
+    pub district: String,
+    /// Date the seat was listed as vacant.
+    pub as_of: NaiveDate,
+    /// Former member of a combined row, such as "Gallagher, Mike - Vacancy".
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub former: Option<String>,
+}
+impl fmt::Display for Vacancy {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{} {} as of {}", self.state, self.district, self.as_of)?;
+        if let Some(former) = &self.former {
+            write!(f, " (formerly {former})")?;
+        }
+        Ok(())
+    }
+}
+
+impl Default for House {
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
 impl House {
@@ -29,65 +69,143 @@ impl House {
             name: "U.S. House of Representatives".into(),
             role: Role::Political,
             persons: Vec::new(),
+            vacancies: Vec::new(),
+            members_on: None,
         }
     }
 
     pub async fn load() -> Result<House> {
+        House::load_pth(FLE_PTH).await
+    }
+
+    /// Loads from a person file, fetching and writing it when missing.
+    pub async fn load_pth(pth: &str) -> Result<House> {
+        REPORT.lock().unwrap().begin(Source::House);
+
         // Read file from disk.
-        let mut house = match read_from_file::<House>(FLE_PTH) {
-            Ok(mut house_from_disk) => house_from_disk,
+        let mut house = match read_from_file::<House>(pth) {
+            Ok(mut house_from_disk) => {
+                // Backfill titles of older files.
+                for per in house_from_disk
+                    .persons
+                    .iter_mut()
+                    .filter(|per| per.title1.is_empty())
+                {
+                    per.title1 = TITLE_REPRESENTATIVE.into();
+                }
+                // Backfill roles of older files.
+                set_roles(&mut house_from_disk.persons, &house_from_disk.role);
+                // Refetch members, dropping former members.
+                if is_members_refresh(house_from_disk.members_on) {
+                    house_from_disk.refresh_members(pth).await?;
+                }
+                house_from_disk
+            }
 
             Err(err) => {
-                eprintln!("err: read file: {err}");
+                warn!("read file: {err}");
                 let mut house = House::new();
 
                 // Fetch members.
-                house.persons = house.fetch_members().await?;
+                (house.persons, house.vacancies) = house.fetch_members().await?;
+                house.members_on = Some(Local::now().date_naive());
+                set_roles(&mut house.persons, &house.role);
 
                 // Write file to disk.
-                write_to_file(&house, FLE_PTH)?;
+                write_to_file(&house, pth)?;
 
                 house
             }
         };
 
-        println!("{} representatives", house.persons.len());
+        info!("{} representatives", house.persons.len());
+        if !house.vacancies.is_empty() {
+            let mut lnes = vec![format!("{} house vacancies", house.vacancies.len())];
+            lnes.extend(house.vacancies.iter().map(|vac| vac.to_string()));
+            summary_add("Vacancies", lnes, false);
+        }
 
         // Fetch addresses.
-        house.fetch_adrs().await?;
+        house.fetch_adrs(pth).await?;
 
         Ok(house)
     }
 
-    /// Fetch members from network.
-    pub async fn fetch_members(&self) -> Result<Vec<Person>> {
+    /// Refetch members and vacancies, keeping the addresses of cached members, and write the file.
+    ///
+    /// New members have no addresses, so only they're fetched.
+    pub async fn refresh_members(&mut self, pth: &str) -> Result<()> {
+        let (fresh, vacancies) = self.fetch_members().await?;
+        let (pers, stats) = reconcile_members(std::mem::take(&mut self.persons), fresh);
+        info!("house members: {}", stats.lnes().join("; "));
+        summary_add("House members", stats.lnes(), false);
+        self.persons = pers;
+        self.vacancies = vacancies;
+        self.members_on = Some(Local::now().date_naive());
+        set_roles(&mut self.persons, &self.role);
+        write_to_file(self, pth)
+    }
+
+    /// Fetch members and vacancies from network.
+    pub async fn fetch_members(&self) -> Result<(Vec<Person>, Vec<Vacancy>)> {
         let url = "https://www.house.gov/representatives";
-        let html = fetch_html(url).await?;
-        let document = Html::parse_document(&html);
+        let html = fetch_html(url, FetchOptions::for_source(Source::House)).await?;
+        House::prs_members(&html, Local::now().date_naive())
+    }
+
+    /// Parse members and vacancies from the representatives table.
+    pub fn prs_members(html: &str, as_of: NaiveDate) -> Result<(Vec<Person>, Vec<Vacancy>)> {
+        let document = Html::parse_document(html);
         let mut pers = Vec::with_capacity(CAP_PER);
+        let mut vacancies = Vec::new();
 
         // Define the CSS selector for the members list
         let tbl_sel = Selector::parse("table.table tr").unwrap();
         let name_sel = Selector::parse("td:nth-of-type(1)").unwrap();
         let url_sel = Selector::parse("td:nth-of-type(1) a").unwrap();
+        let district_sel = Selector::parse("td:nth-of-type(2)").unwrap();
+        let cap_sel = Selector::parse("caption").unwrap();
 
         // Iterate over each member entry
         for tbl_elm in document.select(&tbl_sel) {
             if let Some(name_elm) = tbl_elm.select(&name_sel).next() {
-                let mut per = Person::default();
-                if let Some((name_lst, name_fst)) = name_elm
-                    .text()
-                    .collect::<Vec<_>>()
-                    .join(" ")
-                    .split_once(',')
-                {
+                let mut per = Person {
+                    title1: TITLE_REPRESENTATIVE.into(),
+                    ..Default::default()
+                };
+                let name_txt = name_elm.text().collect::<Vec<_>>().join(" ");
+                if let Some((name_lst, name_fst)) = name_txt.split_once(',') {
                     let full_name = format!("{} {}", name_fst.trim(), name_lst.trim());
                     // eprintln!("{}", full_name);
                     per.name = name_clean(&full_name);
                 }
-                // Skip empty or vacancy.
-                // "Mike - Vacancy"
-                if per.name.is_empty() || per.name.contains("Vacancy") {
+                // Select state from the table caption.
+                // "<caption>Alabama</caption>"
+                per.state = tbl_elm
+                    .ancestors()
+                    .filter_map(ElementRef::wrap)
+                    .find(|elm| elm.value().name() == "table")
+                    .and_then(|elm| elm.select(&cap_sel).next())
+                    .and_then(|elm| state_abbr(&elm.text().collect::<String>()))
+                    .unwrap_or_default()
+                    .into();
+                // Record vacancy.
+                // "Vacancy", "Gallagher, Mike - Vacancy"
+                if name_txt.contains("Vacancy") {
+                    vacancies.push(Vacancy {
+                        state: per.state,
+                        district: tbl_elm
+                            .select(&district_sel)
+                            .next()
+                            .map(|elm| elm.text().collect::<String>().trim().to_string())
+                            .unwrap_or_default(),
+                        as_of,
+                        former: prs_former(&name_txt),
+                    });
+                    continue;
+                }
+                // Skip empty.
+                if per.name.is_empty() {
                     continue;
                 }
                 // Edit some names.
@@ -146,114 +264,249 @@ impl House {
             }
         }
 
-        Ok(pers)
+        Ok((pers, vacancies))
     }
 
-    pub async fn fetch_adrs(&mut self) -> Result<()> {
-        // Clone self for file writing.
-        let mut self_clone = self.clone();
-        let per_len = self.persons.len() as f64;
+    /// Fetches addresses of persons without any, checkpointing to a person file.
+    pub async fn fetch_adrs(&mut self, pth: &str) -> Result<()> {
+        // Clone persons for concurrent fetching.
+        let pers = self.persons.clone();
+        let manual = ManualAddresses::load(MANUAL_PTH)?;
+        manual.validate_summary(Source::House, &self.persons);
+        let fetch_cfg = FetchCfg::load(FLE_PTH_FETCH)?;
+        let mut ckpt = Checkpoint::new(CheckpointPolicy::load(FLE_PTH_CHECKPOINT)?, Instant::now());
+        let todo = pers.iter().filter(|per| per.adrs.is_none()).count();
+        let progress = Mutex::new(Progress::new(Some(todo)));
+
+        // Fetch persons concurrently, bounded so USPS calls are bounded too.
+        // Results are stored by index to preserve person order.
+        let mut fetches = stream::iter(
+            pers.iter()
+                .enumerate()
+                .filter(|(_, per)| per.adrs.is_none()),
+        )
+        .map(|(idx, per)| {
+            let manual = &manual;
+            let progress = &progress;
+            async move {
+                let lbl = format!("{} {} {}", idx, per.name, per.url);
+                progress.lock().unwrap().tick(&lbl);
+                (idx, fetch_per_adrs(per, manual).await)
+            }
+        })
+        .buffer_unordered(fetch_cfg.concurrency);
 
-        for (idx, per) in self_clone
-            .persons
-            .iter()
-            .enumerate()
-            .filter(|(_, per)| per.adrs.is_none())
-        // .take(1)
-        {
-            let pct = (((idx as f64 + 1.0) / per_len) * 100.0) as u8;
-            eprintln!("  {}% {} {} {}", pct, idx, per.name, per.url);
-
-            match self.persons[idx].name.as_str() {
-                // Fetch and gather from multiple urls.
-                "Brian Fitzpatrick" => {
-                    let mut adrs = Vec::new();
-                    for url_path in ["washington-d-c-office", "district-office"] {
-                        // Create url.
-                        let mut url = per.url.clone();
-                        if !url_path.is_empty() {
-                            url.push('/');
-                            url.push_str(url_path);
-                        }
-                        // Fetch, parse, standardize.
-                        if let Some(new_adrs) = fetch_prs_std_adrs(per, &url).await? {
-                            adrs.extend(new_adrs);
-                        }
-                    }
+        while let Some((idx, adrs)) = fetches.next().await {
+            match adrs {
+                Ok((adrs, provenance)) => {
                     self.persons[idx].adrs = Some(adrs);
+                    self.persons[idx].provenance = Some(provenance);
                 }
-                _ => {
-                    // Fetch from single unknown url.
-                    let url_paths = [
-                        "contact/offices",
-                        "contact/office-locations",
-                        "district",
-                        "contact",
-                        "offices",
-                        "office-locations",
-                        "office-information",
-                        "",
-                    ];
-                    for url_path in url_paths {
-                        // Create url.
-                        let mut url = per.url.clone();
-                        if !url_path.is_empty() {
-                            url.push('/');
-                            url.push_str(url_path);
-                        }
-                        // Fetch, parse, standardize.
-                        if let Some(adrs) = fetch_prs_std_adrs(per, &url).await? {
-                            if adrs.len() < 2 {
-                                continue;
-                            }
-                            self.persons[idx].adrs = Some(adrs);
-                            break;
-                        }
-                    }
+                Err(err) => {
+                    // Flush completed persons before returning an error.
+                    ckpt.flush(&self, pth, Instant::now())?;
+                    return Err(err);
                 }
             }
 
-            // Check for address parsing error.
-            if self.persons[idx].adrs.is_none() {
-                return Err(anyhow!("no addresses for {}", self.persons[idx]));
-            }
-
             // Checkpoint save.
-            write_to_file(&self, FLE_PTH)?;
+            ckpt.done(&self, pth, Instant::now())?;
         }
+        ckpt.flush(&self, pth, Instant::now())?;
 
         Ok(())
     }
 }
 
+/// Fetches addresses of a person, with the page they were parsed from.
+async fn fetch_per_adrs(
+    per: &Person,
+    manual: &ManualAddresses,
+) -> Result<(Vec<Address>, Provenance)> {
+    // Manual addresses bypass scraping.
+    let entry = manual.get(Source::House, &per.name);
+    if let Some(entry) = entry.filter(|entry| entry.skip_scrape) {
+        let provenance = Provenance::new(Source::House, MANUAL_PTH);
+        return Ok((entry.std_adrs().await?, provenance));
+    }
+
+    let mut per_adrs = None;
+    let mut urls: Vec<String> = Vec::new();
+    let mut pdf = PdfFallback::default();
+    match per.name.as_str() {
+        // Fetch and gather from multiple urls.
+        "Brian Fitzpatrick" => {
+            let mut adrs = Vec::new();
+            for url_path in ["washington-d-c-office", "district-office"] {
+                // Create url.
+                let mut url = per.url.clone();
+                if !url_path.is_empty() {
+                    url.push('/');
+                    url.push_str(url_path);
+                }
+                // Fetch, parse, standardize.
+                if let Some(new_adrs) = fetch_prs_std_adrs(per, &url, &mut pdf).await? {
+                    adrs.extend(new_adrs);
+                    urls.push(url);
+                }
+            }
+            per_adrs = Some(adrs);
+        }
+        _ => {
+            // Fetch from single unknown url.
+            let url_paths = [
+                "contact/offices",
+                "contact/office-locations",
+                "district",
+                "contact",
+                "offices",
+                "office-locations",
+                "office-information",
+                "",
+            ];
+            for url_path in url_paths {
+                // Create url.
+                let mut url = per.url.clone();
+                if !url_path.is_empty() {
+                    url.push('/');
+                    url.push_str(url_path);
+                }
+                // Fetch, parse, standardize.
+                if let Some(adrs) = fetch_prs_std_adrs(per, &url, &mut pdf).await? {
+                    if adrs.len() < 2 {
+                        continue;
+                    }
+                    per_adrs = Some(adrs);
+                    urls.push(url);
+                    break;
+                }
+            }
+        }
+    }
+
+    // Client-rendered pages list addresses only in WordPress endpoints.
+    if per_adrs.is_none() {
+        if let Some((url, adrs)) = fetch_wp_adrs(per).await? {
+            per_adrs = Some(adrs);
+            urls.push(url);
+        }
+    }
+
+    // Manual addresses extend scraped addresses.
+    if let Some(entry) = entry {
+        per_adrs = Some(entry.append_to(per_adrs).await?);
+        if urls.is_empty() {
+            urls.push(MANUAL_PTH.into());
+        }
+    }
+
+    // Check for address parsing error.
+    let per_adrs = per_adrs.ok_or_else(|| anyhow!("no addresses for {}", per))?;
+    Ok((per_adrs, Provenance::new(Source::House, &urls.join(" "))))
+}
+
+/// WordPress endpoints of office locations, relative to a member url.
+const WP_LOCATION_PTHS: [&str; 2] = ["wp-json/wp/v2/office_locations", "wp-json/wp/v2/locations"];
+
+/// Fetches standardized addresses from WordPress office locations, with the endpoint url.
+async fn fetch_wp_adrs(per: &Person) -> Result<Option<(String, Vec<Address>)>> {
+    for pth in WP_LOCATION_PTHS {
+        let url = format!("{}/{pth}", per.url);
+        // Sites without the endpoint respond with an error or an html page.
+        let adrs = match fetch_html(&url, FetchOptions::for_source(Source::House))
+            .await
+            .and_then(|json| prs_locations(&json))
+        {
+            Ok(adrs) => adrs,
+            Err(err) => {
+                debug!("{url}: {err}");
+                continue;
+            }
+        };
+        let adrs = standardize_addresses(adrs).await?;
+        if !adrs.is_empty() {
+            return Ok(Some((url, adrs)));
+        }
+    }
+    Ok(None)
+}
+
+/// Parses the former member of a vacancy row, such as "Gallagher, Mike - Vacancy".
+fn prs_former(name_txt: &str) -> Option<String> {
+    let former = name_txt
+        .replace("Vacancy", "")
+        .trim_matches(|c: char| c == '-' || c.is_whitespace())
+        .to_string();
+    if former.is_empty() {
+        return None;
+    }
+    match former.split_once(',') {
+        Some((name_lst, name_fst)) => Some(name_clean(&format!(
+            "{} {}",
+            name_fst.trim(),
+            name_lst.trim()
+        ))),
+        None => Some(name_clean(&former)),
+    }
+}
+
 /// Fetch and parse addresses and standardize with the USPS.
-pub async fn fetch_prs_std_adrs(per: &Person, url: &str) -> Result<Option<Vec<Address>>> {
+pub async fn fetch_prs_std_adrs(
+    per: &Person,
+    url: &str,
+    pdf: &mut PdfFallback,
+) -> Result<Option<Vec<Address>>> {
     // Fetch html.
-    let html = fetch_html(url).await?;
-
-    // Parse html to address lines.
-    let adr_lnes_o = prs_adr_lnes(per, &html);
+    // A missing page, a 404 or 410, is a candidate url without addresses.
+    // Other failed fetches, such as a 403 block page, are errors.
+    let html = match fetch_html(url, FetchOptions::for_source(Source::House)).await {
+        Ok(html) => html,
+        Err(err) if is_page_missing(&err) => {
+            debug!("{err}");
+            return Ok(None);
+        }
+        Err(err) => return Err(err),
+    };
 
-    // Parse lines to addresses.
-    let adrs_o = match adr_lnes_o {
+    // Parse html to addresses.
+    // Without zips, offices may be in a linked PDF.
+    let adr_lnes = prs_adr_lnes(per, &html).unwrap_or_default();
+    let mut adrs_res = PRSR.prs_adrs(&adr_lnes);
+    if !has_zip(&adr_lnes) {
+        let opts = FetchOptions::for_source(Source::House);
+        if let Some(txt) = pdf.fetch_txt(&html, url, opts).await {
+            adrs_res = prs_pdf_adrs(per, &txt);
+        }
+    }
+    let adrs_o = match adrs_or_next(url, adrs_res) {
         None => None,
-        Some(mut adr_lnes) => match PRSR.prs_adrs(&adr_lnes) {
-            None => None,
-            Some(mut adrs) => {
-                adrs = standardize_addresses(adrs).await?;
-                // Can be called twice for different url
-                if adrs.is_empty() {
-                    None
-                } else {
-                    Some(adrs)
-                }
+        Some(mut adrs) => {
+            adrs = standardize_addresses(adrs).await?;
+            // Can be called twice for different url
+            if adrs.is_empty() {
+                None
+            } else {
+                Some(adrs)
             }
-        },
+        }
     };
 
     Ok(adrs_o)
 }
 
+/// Parses addresses of a person from html, before standardization.
+pub fn prs_html_adrs(per: &Person, html: &str) -> Result<Vec<Address>, ParseError> {
+    let adr_lnes = prs_adr_lnes(per, html).ok_or(ParseError::NoZipFound)?;
+    PRSR.prs_adrs(&adr_lnes)
+}
+
+/// Parses addresses of a person from the text of an office PDF, before standardization.
+pub fn prs_pdf_adrs(per: &Person, txt: &str) -> Result<Vec<Address>, ParseError> {
+    let mut adr_lnes = pdf_lnes(txt);
+    edit_adr_lnes(per, &mut adr_lnes);
+    PRSR.prs_adrs(&adr_lnes)
+}
+
 pub fn prs_adr_lnes(per: &Person, html: &str) -> Option<Vec<String>> {
     let document = Html::parse_document(html);
     let mut lnes: Vec<String> = Vec::new();
@@ -285,7 +538,7 @@ pub fn prs_adr_lnes(per: &Person, html: &str) -> Option<Vec<String>> {
                 .collect::<Vec<String>>();
 
             if !cur_lnes.is_empty() {
-                eprintln!("{cur_lnes:?}");
+                trace!("{cur_lnes:?}");
 
                 lnes.extend(cur_lnes);
             }
@@ -296,28 +549,34 @@ pub fn prs_adr_lnes(per: &Person, html: &str) -> Option<Vec<String>> {
         }
     }
 
-    // eprintln!("--- pre: {lnes:?}");
-
-    // Edit lines to make it easier to parse.
-    edit_dot(&mut lnes);
-    edit_nbsp_zwsp(&mut lnes);
-    edit_mailing(&mut lnes);
-    edit_person_house_lnes(per, &mut lnes);
-    PRSR.edit_lnes(&mut lnes);
-    edit_newline(&mut lnes);
-    edit_hob(&mut lnes);
-    edit_split_comma(&mut lnes);
-    edit_starting_hash(&mut lnes);
-    edit_char_half(&mut lnes);
-    edit_empty(&mut lnes);
-
-    eprintln!("--- --- --- post: {lnes:?}");
+    edit_adr_lnes(per, &mut lnes);
 
     // Do not check for zip count here.
 
     Some(lnes)
 }
 
+/// Edits extracted address lines of a person to make them easier to parse.
+pub fn edit_adr_lnes(per: &Person, lnes: &mut Vec<String>) {
+    // eprintln!("--- pre: {lnes:?}");
+
+    // Edit lines to make it easier to parse.
+    edit_dot(lnes);
+    edit_nbsp_zwsp(lnes);
+    PRSR.edit_counties(lnes);
+    edit_mailing(lnes);
+    edit_counted(lnes, |lnes| edit_person_house_lnes(per, lnes));
+    PRSR.edit_lnes(lnes, Source::House);
+    edit_newline(lnes);
+    edit_hob(lnes);
+    edit_split_comma(lnes);
+    edit_starting_hash(lnes);
+    edit_char_half(lnes);
+    edit_empty(lnes);
+
+    trace!("--- --- --- post: {lnes:?}");
+}
+
 pub fn edit_person_house_lnes(per: &Person, lnes: &mut Vec<String>) {
     match per.name.as_str() {
         "Matthew Rosendale" => {
@@ -348,13 +607,6 @@ pub fn edit_person_house_lnes(per: &Person, lnes: &mut Vec<String>) {
                 }
             }
         }
-        "Andy Biggs" => {
-            for idx in (0..lnes.len()).rev() {
-                if lnes[idx] == "SUPERSTITION PLAZA" {
-                    lnes.remove(idx);
-                }
-            }
-        }
         "John Carter" => {
             for idx in (0..lnes.len()).rev() {
                 if lnes[idx] == "SUITE # I-10" {
@@ -459,12 +711,9 @@ pub fn edit_person_house_lnes(per: &Person, lnes: &mut Vec<String>) {
         }
         "Raul Grijalva" => {
             for idx in (0..lnes.len()).rev() {
-                // "146 N. STATE AVENUE", "SOMERTON AZ 85350"
-                if lnes[idx] == "146 N STATE AVENUE" {
-                    lnes.remove(idx + 1);
-                    lnes.remove(idx);
-                } else if lnes[idx].starts_with("MAILING ADDRESS") {
-                    // "MAILING ADDRESS: PO BOX", "4105, SOMERTON, AZ 85350"
+                if lnes[idx] == "PO BOX" && idx + 1 != lnes.len() {
+                    // "PO BOX", "4105, SOMERTON, AZ 85350"
+                    // edit_mailing trimmed "MAILING ADDRESS:".
                     let mut lne = lnes.remove(idx + 1);
                     lne.insert_str(0, "PO BOX ");
                     lnes[idx] = lne;
@@ -496,3 +745,212 @@ pub fn edit_person_house_lnes(per: &Person, lnes: &mut Vec<String>) {
         _ => {}
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::*;
+
+    const REPRESENTATIVES: &str = include_str!("../tests/fixtures/house/representatives.html");
+
+    #[test]
+    fn test_read_house_without_provenance() {
+        // Written before persons had provenance.
+        let json = r#"{
+            "name": "House",
+            "role": "Political",
+            "persons": [{
+                "name": "Jerry L. Carl, Jr.",
+                "title1": "Representative",
+                "title2": "District 1",
+                "url": "https://carl.house.gov",
+                "state": "AL",
+                "adrs": [{
+                    "address1": "1330 LHOB",
+                    "address2": null,
+                    "city": "WASHINGTON",
+                    "state": "DC",
+                    "zip5": 20515,
+                    "zip4": 101,
+                    "delivery_point": "30"
+                }]
+            }]
+        }"#;
+        let house: House = serde_json::from_str(json).unwrap();
+        assert_eq!(house.persons[0].provenance, None);
+        assert_eq!(house.persons[0].adr_len(), 1);
+
+        // Provenance round trips.
+        let mut house = house;
+        house.persons[0].provenance = Some(Provenance::new(
+            Source::House,
+            "https://carl.house.gov/contact/offices",
+        ));
+        let json = serde_json::to_string(&house).unwrap();
+        let read: House = serde_json::from_str(&json).unwrap();
+        assert_eq!(read.persons[0].provenance, house.persons[0].provenance);
+    }
+
+    #[test]
+    fn test_prs_members_vacancy() {
+        let as_of = NaiveDate::from_ymd_opt(2024, 5, 1).unwrap();
+        let (pers, vacancies) = House::prs_members(REPRESENTATIVES, as_of).unwrap();
+
+        assert_eq!(pers.len(), 1);
+        assert_eq!(pers[0].name, "Greg Landsman");
+        assert_eq!(pers[0].state, "OH");
+        assert_eq!(pers[0].url, "https://landsman.house.gov");
+        assert_eq!(pers[0].title1, "Member of Congress");
+
+        assert_eq!(
+            vacancies,
+            vec![
+                Vacancy {
+                    state: "OH".into(),
+                    district: "6th".into(),
+                    as_of,
+                    former: None,
+                },
+                Vacancy {
+                    state: "WI".into(),
+                    district: "8th".into(),
+                    as_of,
+                    former: Some("Mike Gallagher".into()),
+                },
+            ]
+        );
+        assert_eq!(
+            vacancies[1].to_string(),
+            "WI 8th as of 2024-05-01 (formerly Mike Gallagher)"
+        );
+
+        // Vacancies aren't persons, so no mailpieces are created for them.
+        assert!(pers.iter().all(|per| !per.name.contains("Vacancy")));
+        let mut house = House::new();
+        (house.persons, house.vacancies) = (pers, vacancies);
+        let house: House = serde_json::from_str(&serde_json::to_string(&house).unwrap()).unwrap();
+        assert_eq!(house.persons.len(), 1);
+        assert_eq!(house.vacancies.len(), 2);
+    }
+
+    #[tokio::test]
+    async fn test_load_snapshot() {
+        let dir = TempDir::new("house_snapshot");
+        let pth = dir.pth("house.json");
+        let snapshot = SnapshotFetcher::new("tests/fixtures/snapshot/house");
+        let house = with_snapshot(snapshot, House::load_pth(&pth))
+            .await
+            .unwrap();
+
+        let pers: Vec<(&str, &str)> = house
+            .persons
+            .iter()
+            .map(|per| (per.name.as_str(), per.state.as_str()))
+            .collect();
+        assert_eq!(pers, vec![("Raul Grijalva", "AZ"), ("Jared Huffman", "CA")]);
+        let adrs: Vec<String> = house.persons[1]
+            .adrs
+            .iter()
+            .flatten()
+            .map(|adr| adr.to_string())
+            .collect();
+        assert_eq!(
+            adrs,
+            vec![
+                "2330 RHOB,,WASHINGTON,DC,20515,0,",
+                "430 NORTH FRANKLIN ST,,FORT BRAGG,CA,95437,0,",
+                "999 FIFTH AVENUE,SUITE 290,SAN RAFAEL,CA,94901,0,",
+            ]
+        );
+        assert_eq!(
+            house.persons[0].provenance.as_ref().unwrap().url,
+            "https://grijalva.house.gov/contact/offices"
+        );
+        assert_eq!(house.persons[0].adr_len(), 3);
+
+        // Unrecorded pages are errors.
+        let err = with_snapshot(
+            SnapshotFetcher::new("tests/fixtures/snapshot/none"),
+            House::new().fetch_members(),
+        )
+        .await
+        .unwrap_err();
+        assert!(err.to_string().contains("not in snapshot"), "{err}");
+    }
+
+    #[test]
+    fn test_fixture_grijalva() {
+        assert_eq!(
+            fixture_lnes("Raul Grijalva", "tests/fixtures/house/grijalva.html"),
+            vec![
+                "101 W IRVINGTON ROAD,BUILDING 4 & 5,TUCSON,AZ,85714,0,",
+                "1511 LHOB,,WASHINGTON,DC,20515,0,",
+                "PO BOX 4105,,SOMERTON,AZ,85350,0,",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_fixture_huffman() {
+        assert_eq!(
+            fixture_lnes("Jared Huffman", "tests/fixtures/house/huffman.html"),
+            vec![
+                "2330 RHOB,,WASHINGTON,DC,20515,0,",
+                "430 NORTH FRANKLIN ST,,FORT BRAGG,CA,95437,0,",
+                "999 FIFTH AVENUE,SUITE 290,SAN RAFAEL,CA,94901,0,",
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_fixture_pdf_link() {
+        let per = Person {
+            name: "Jane Doe".into(),
+            ..Default::default()
+        };
+        let html = std::fs::read_to_string("tests/fixtures/house/pdf_link.html").unwrap();
+        let url = "https://doe.house.gov/contact";
+        assert!(!has_zip(&prs_adr_lnes(&per, &html).unwrap()));
+        assert_eq!(
+            prs_pdf_link(&html, url).unwrap(),
+            "https://doe.house.gov/uploads/pdf_link_offices.pdf"
+        );
+
+        // Record a copy of the pages, since the text is cached beside the PDF.
+        let dir = TempDir::new("pdf_link");
+        std::fs::write(dir.join("doe_house_gov_contact"), &html).unwrap();
+        std::fs::write(dir.join("doe_house_gov_offices"), &html).unwrap();
+        let pth = dir.join("doe_house_gov_uploads_pdf_link_offices_pdf");
+        std::fs::copy("tests/fixtures/house/pdf_link_offices.pdf", &pth).unwrap();
+        let snapshot = SnapshotFetcher::new(&*dir);
+
+        // A page without zips falls back to its office PDF.
+        let mut pdf = PdfFallback::default();
+        let adrs = with_snapshot(snapshot.clone(), fetch_prs_std_adrs(&per, url, &mut pdf))
+            .await
+            .unwrap()
+            .unwrap();
+        let mut adrs: Vec<String> = adrs.iter().map(|adr| adr.to_string()).collect();
+        adrs.sort();
+        assert_eq!(
+            adrs,
+            vec![
+                "100 MAIN STREET,SUITE 200,DAYTON,OH,45402,0,",
+                "2312 RHOB,,WASHINGTON,DC,20515,0,",
+            ]
+        );
+        let txt = std::fs::read_to_string(pth.with_extension("txt")).unwrap();
+        assert_eq!(pdf_txt(&pth).unwrap(), txt);
+
+        // A person follows one PDF.
+        let url = "https://doe.house.gov/offices";
+        let adrs = with_snapshot(snapshot, fetch_prs_std_adrs(&per, url, &mut pdf))
+            .await
+            .unwrap();
+        assert_eq!(adrs, None);
+        assert_eq!(
+            pdf.url.as_deref(),
+            Some("https://doe.house.gov/uploads/pdf_link_offices.pdf")
+        );
+    }
+}
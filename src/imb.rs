@@ -0,0 +1,234 @@
+use anyhow::{anyhow, Result};
+use printpdf::*;
+use serde::{Deserialize, Serialize};
+use std::borrow::Cow;
+use std::fmt;
+use tracing::warn;
+
+/// Path of the IMb font loaded at runtime without the `imb-font` feature.
+pub const IMB_FONT_PTH: &str = "fonts/USPSIMBStandard.ttf";
+
+#[cfg(feature = "imb-font")]
+static FNT_IMB: &[u8] = include_bytes!("../fonts/USPSIMBStandard.ttf");
+
+/// Bars of an Intelligent Mail barcode.
+pub const IMB_BAR_CNT: usize = 65;
+
+/// Returns a placeholder IMb of trackers, used by dry runs instead of an encoded barcode.
+pub fn placeholder_barcode() -> String {
+    "T".repeat(IMB_BAR_CNT)
+}
+
+/// Returns whether a barcode is a dry run placeholder.
+pub fn is_placeholder_barcode(barcode: &str) -> bool {
+    barcode == placeholder_barcode()
+}
+
+// Nominal dimensions from USPS-B-3200, "Intelligent Mail Barcode 4-State Specification".
+// See https://postalpro.usps.com/node/221.
+//  * Bar width: 0.015 - 0.025 in.
+//  * Bar pitch: 20 - 24 bars per inch.
+//  * Full bar height: 0.125 - 0.165 in.
+//  * Ascender and descender height: 0.091 - 0.115 in.
+//  * Tracker height: 0.039 - 0.057 in.
+//  * Clear zone: 0.125 in left and right, 0.028 in above and below.
+
+/// Millimeters per inch.
+const MM_IN: f32 = 25.4;
+/// Bar width, 0.020 in.
+pub const IMB_BAR_W: Mm = Mm(0.020 * MM_IN);
+/// Bar pitch, 22 bars per inch.
+pub const IMB_BAR_PITCH: Mm = Mm(MM_IN / 22.0);
+/// Full bar height, 0.145 in.
+pub const IMB_FULL_H: Mm = Mm(0.145 * MM_IN);
+/// Tracker height, 0.048 in.
+pub const IMB_TRACKER_H: Mm = Mm(0.048 * MM_IN);
+/// Ascender and descender height, from the tracker to the full bar extent.
+pub const IMB_ASC_H: Mm = Mm((0.145 + 0.048) / 2.0 * MM_IN);
+
+/// How the IMb of an envelope is rendered.
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum BarcodeMode {
+    /// Text in the USPS IMb font.
+    Font,
+    /// Vector rectangles per bar.
+    Vector,
+}
+impl fmt::Display for BarcodeMode {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            BarcodeMode::Font => write!(f, "font"),
+            BarcodeMode::Vector => write!(f, "vector"),
+        }
+    }
+}
+
+/// Returns the IMb font, bundled with the `imb-font` feature or read at runtime.
+pub fn imb_font() -> Option<Cow<'static, [u8]>> {
+    #[cfg(feature = "imb-font")]
+    return Some(Cow::Borrowed(FNT_IMB));
+    #[cfg(not(feature = "imb-font"))]
+    return std::fs::read(IMB_FONT_PTH).ok().map(Cow::Owned);
+}
+
+/// Returns the barcode mode to render with.
+///
+/// Font mode falls back to vector when the font is unavailable.
+/// Unconfigured, the font is used when available.
+pub fn barcode_mode(cfg: Option<BarcodeMode>, is_font: bool) -> BarcodeMode {
+    match (cfg, is_font) {
+        (Some(BarcodeMode::Vector), _) => BarcodeMode::Vector,
+        (Some(BarcodeMode::Font), false) => {
+            warn!("IMb font unavailable, rendering vector barcodes");
+            BarcodeMode::Vector
+        }
+        (_, true) => BarcodeMode::Font,
+        (None, false) => BarcodeMode::Vector,
+    }
+}
+
+/// A bar of an IMb with its lower left corner and size.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ImbBar {
+    pub x: Mm,
+    pub y: Mm,
+    pub w: Mm,
+    pub h: Mm,
+}
+
+/// Lays out the bars of an encoded IMb, such as `FADT...`, from a lower left corner.
+///
+/// Full bars span the full height, ascenders rise from the tracker bottom,
+/// descenders fall from the tracker top, and trackers are centered.
+pub fn imb_bars(barcode: &str, x: Mm, y: Mm) -> Result<Vec<ImbBar>> {
+    if barcode.chars().count() != IMB_BAR_CNT {
+        return Err(anyhow!(
+            "IMb has {} bars, expected {IMB_BAR_CNT}",
+            barcode.chars().count()
+        ));
+    }
+    let tracker_y = (IMB_FULL_H - IMB_TRACKER_H) / 2.0;
+    barcode
+        .chars()
+        .enumerate()
+        .map(|(idx, c)| {
+            let (dy, h) = match c {
+                'F' => (Mm(0.0), IMB_FULL_H),
+                'A' => (tracker_y, IMB_ASC_H),
+                'D' => (Mm(0.0), IMB_ASC_H),
+                'T' => (tracker_y, IMB_TRACKER_H),
+                _ => return Err(anyhow!("invalid IMb bar '{c}'")),
+            };
+            Ok(ImbBar {
+                x: x + IMB_BAR_PITCH * idx as f32,
+                y: y + dy,
+                w: IMB_BAR_W,
+                h,
+            })
+        })
+        .collect()
+}
+
+/// Draws the bars of an encoded IMb as filled rectangles.
+pub fn draw_imb(lyr: &PdfLayerReference, barcode: &str, x: Mm, y: Mm) -> Result<()> {
+    lyr.set_fill_color(Color::Greyscale(Greyscale::new(0.0, None)));
+    for bar in imb_bars(barcode, x, y)? {
+        let rect =
+            Rect::new(bar.x, bar.y, bar.x + bar.w, bar.y + bar.h).with_mode(path::PaintMode::Fill);
+        lyr.add_rect(rect);
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Example from USPS-B-3200 Appendix C.
+    const FADT: &str = "AADTFFDFTDADTAADAATFDTDDAAADDTDTTDAFADADDDTFFFDDTTTADFAAADFTDAADA";
+
+    #[test]
+    fn test_imb_bars() {
+        let bars = imb_bars(FADT, Mm(10.0), Mm(20.0)).unwrap();
+        assert_eq!(bars.len(), IMB_BAR_CNT);
+
+        let cnt = |h: Mm| bars.iter().filter(|bar| bar.h == h).count();
+        let fadt_cnt = |c: char| FADT.chars().filter(|o| *o == c).count();
+        assert_eq!(cnt(IMB_FULL_H), fadt_cnt('F'));
+        assert_eq!(cnt(IMB_ASC_H), fadt_cnt('A') + fadt_cnt('D'));
+        assert_eq!(cnt(IMB_TRACKER_H), fadt_cnt('T'));
+
+        // Relative heights and vertical extents.
+        let (bottom, top) = (Mm(20.0), Mm(20.0) + IMB_FULL_H);
+        for (bar, c) in bars.iter().zip(FADT.chars()) {
+            let bar_top = bar.y + bar.h;
+            let is_match = match c {
+                'F' => bar.y == bottom && bar_top == top,
+                'A' => bar.y > bottom && (bar_top - top).0.abs() < 1e-4,
+                'D' => bar.y == bottom && bar_top < top,
+                _ => bar.y > bottom && bar_top < top && bar.h < IMB_ASC_H,
+            };
+            assert!(is_match, "Failed to match: {}", c);
+        }
+
+        // Pitch and overall length within spec tolerances.
+        assert_eq!(bars[0].x, Mm(10.0));
+        let len = bars[IMB_BAR_CNT - 1].x + IMB_BAR_W - bars[0].x;
+        assert!(len.0 >= 2.667 * MM_IN && len.0 <= 3.225 * MM_IN);
+        let h_in = |h: Mm| h.0 / MM_IN;
+        assert!((0.125..=0.165).contains(&h_in(IMB_FULL_H)));
+        assert!((0.091..=0.115).contains(&h_in(IMB_ASC_H)));
+        assert!((0.039..=0.057).contains(&h_in(IMB_TRACKER_H)));
+        assert!((0.015..=0.025).contains(&h_in(IMB_BAR_W)));
+        assert!((20.0..=24.0).contains(&(MM_IN / IMB_BAR_PITCH.0)));
+    }
+
+    #[test]
+    fn test_placeholder_barcode() {
+        let barcode = placeholder_barcode();
+        assert!(is_placeholder_barcode(&barcode));
+        assert!(!is_placeholder_barcode(FADT));
+        assert_eq!(
+            imb_bars(&barcode, Mm(0.0), Mm(0.0)).unwrap().len(),
+            IMB_BAR_CNT
+        );
+    }
+
+    #[test]
+    fn test_imb_bars_invalid() {
+        let cases = vec!["", "FADT", &FADT[1..]];
+        for case in cases {
+            assert!(
+                imb_bars(case, Mm(0.0), Mm(0.0)).is_err(),
+                "Failed to match: {}",
+                case
+            );
+        }
+        let case = FADT.replacen('F', "X", 1);
+        assert!(
+            imb_bars(&case, Mm(0.0), Mm(0.0)).is_err(),
+            "Failed to match: {}",
+            case
+        );
+    }
+
+    #[test]
+    fn test_barcode_mode() {
+        // Falls back to vector when no font is present.
+        assert_eq!(
+            barcode_mode(Some(BarcodeMode::Font), false),
+            BarcodeMode::Vector
+        );
+        assert_eq!(barcode_mode(None, false), BarcodeMode::Vector);
+        assert_eq!(barcode_mode(None, true), BarcodeMode::Font);
+        assert_eq!(
+            barcode_mode(Some(BarcodeMode::Font), true),
+            BarcodeMode::Font
+        );
+        assert_eq!(
+            barcode_mode(Some(BarcodeMode::Vector), true),
+            BarcodeMode::Vector
+        );
+    }
+}
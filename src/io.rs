@@ -0,0 +1,1791 @@
+use crate::models::*;
+use anyhow::{anyhow, Result};
+use chrono::{Local, NaiveDate, NaiveDateTime};
+use reqwest::header::{HeaderMap, HeaderValue, USER_AGENT};
+use reqwest::{Client, RequestBuilder, Response, StatusCode};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::collections::{BTreeMap, HashSet};
+use std::fmt;
+use std::fs;
+use std::fs::File;
+use std::future::Future;
+use std::io::{BufReader, Write};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::Mutex;
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+use tracing::{debug, info, warn};
+
+lazy_static! {
+    /// A client shared by fetches, with the default headers.
+    pub static ref CLI: Client = new_client(default_headers()).unwrap();
+}
+
+/// Default headers of fetches.
+pub fn default_headers() -> HeaderMap {
+    // Create a header map and set the User-Agent header.
+    // Set User-Agent to avoid url blocking.
+    let mut headers = HeaderMap::new();
+    headers.insert(USER_AGENT, HeaderValue::from_static("Mozilla/5.0 (Windows NT 10.0; Win64; x64) AppleWebKit/537.36 (KHTML, like Gecko) Chrome/91.0.4472.124 Safari/537.36"));
+    headers
+}
+
+/// Creates a client with custom headers, such as `default_headers` plus an api key.
+pub fn new_client(headers: HeaderMap) -> Result<Client> {
+    Ok(Client::builder().default_headers(headers).build()?)
+}
+
+/// Skips USPS standardization and barcode fetches, set by `--dry-run`.
+pub static DRY_RUN: AtomicBool = AtomicBool::new(false);
+
+/// Returns whether the run skips USPS network calls.
+///
+/// Snapshot runs are offline, so they're dry runs too.
+pub fn is_dry_run() -> bool {
+    DRY_RUN.load(Ordering::Relaxed) || SNAPSHOT.try_with(|_| ()).is_ok()
+}
+
+/// Refetches member lists of cached person files, set by `--refresh-members`.
+pub static REFRESH_MEMBERS: AtomicBool = AtomicBool::new(false);
+
+/// Days after which member lists of cached person files are refetched, set by `--members-max-days`.
+///
+/// Zero never refetches by age.
+pub static MEMBERS_MAX_DAYS: AtomicU64 = AtomicU64::new(0);
+
+/// Returns whether the cached member list of a person file is refetched this run.
+pub fn is_members_refresh(members_on: Option<NaiveDate>) -> bool {
+    REFRESH_MEMBERS.load(Ordering::Relaxed)
+        || is_members_stale(
+            members_on,
+            Local::now().date_naive(),
+            MEMBERS_MAX_DAYS.load(Ordering::Relaxed),
+        )
+}
+
+/// Returns whether a member list fetched on a date is older than a max age in days.
+///
+/// A list of an older file without a date is stale. Zero days is never stale.
+pub fn is_members_stale(members_on: Option<NaiveDate>, today: NaiveDate, max_days: u64) -> bool {
+    max_days != 0 && members_on.is_none_or(|on| (today - on).num_days() > max_days as i64)
+}
+
+/// Exit code of a run which completed with warnings.
+pub const EXIT_WARN: i32 = 2;
+
+/// Exit code of a refresh which detected changes.
+pub const EXIT_CHANGES: i32 = 3;
+
+lazy_static! {
+    /// Summary printed at the end of a run.
+    pub static ref SUMMARY: Mutex<Summary> = Mutex::new(Summary::default());
+}
+
+/// A run summary of titled sections.
+#[derive(Debug, Default)]
+pub struct Summary {
+    pub sections: Vec<(String, Vec<String>)>,
+    pub is_warn: bool,
+}
+
+/// Adds a section to the run summary.
+pub fn summary_add(title: &str, lnes: Vec<String>, is_warn: bool) {
+    let mut summary = SUMMARY.lock().unwrap();
+    summary.sections.push((title.into(), lnes));
+    summary.is_warn |= is_warn;
+}
+
+/// Prints the run summary and returns the exit code.
+pub fn summary_print() -> i32 {
+    let summary = SUMMARY.lock().unwrap();
+    for (title, lnes) in summary.sections.iter() {
+        info!("{title}");
+        for lne in lnes {
+            info!("  {lne}");
+        }
+    }
+    if summary.is_warn {
+        EXIT_WARN
+    } else {
+        0
+    }
+}
+
+/// Environment variable of the data directory.
+pub const DATA_DIR_ENV: &str = "ADR_DATA_DIR";
+
+lazy_static! {
+    /// Data directory set by `--data-dir`, taking precedence over `ADR_DATA_DIR`.
+    pub static ref DATA_DIR: Mutex<Option<PathBuf>> = Mutex::new(None);
+    /// Page cache directory kept by `redirect_dry_run`, `.cache` of the data directory when unset.
+    pub static ref CACHE_DIR: Mutex<Option<PathBuf>> = Mutex::new(None);
+}
+
+/// Scratch data directory of a dry run, under the data directory.
+pub const DRY_RUN_DIR: &str = "dry_run";
+
+/// Redirects the data directory to a scratch copy for a dry run, returning the copy.
+///
+/// Person files, ids and mailings of the run are written to the copy,
+/// leaving the data directory untouched. The page cache stays shared.
+pub fn redirect_dry_run() -> Result<PathBuf> {
+    let src = std::env::current_dir()?.join(data_dir());
+    let dst = copy_dry_run(&src)?;
+    *CACHE_DIR.lock().unwrap() = Some(src.join(".cache"));
+    *DATA_DIR.lock().unwrap() = Some(dst.clone());
+    Ok(dst)
+}
+
+/// Copies the data files of a data directory to its scratch dry run directory.
+///
+/// Directories, such as the page cache and previous mailings, aren't copied.
+/// A previous scratch directory is replaced.
+pub fn copy_dry_run(src: &Path) -> Result<PathBuf> {
+    let dst = src.join(DRY_RUN_DIR);
+    if dst.exists() {
+        fs::remove_dir_all(&dst)?;
+    }
+    fs::create_dir_all(&dst)?;
+    for entry in fs::read_dir(src)? {
+        let entry = entry?;
+        if entry.file_type()?.is_file() {
+            fs::copy(entry.path(), dst.join(entry.file_name()))?;
+        }
+    }
+    Ok(dst)
+}
+
+/// Returns the directory of data files, caches and mailings.
+///
+/// Resolves `--data-dir`, then `ADR_DATA_DIR`, then the current directory.
+pub fn data_dir() -> PathBuf {
+    resolve_data_dir(
+        DATA_DIR.lock().unwrap().clone(),
+        std::env::var_os(DATA_DIR_ENV).map(PathBuf::from),
+    )
+}
+
+/// Returns the data directory of a command line and environment directory.
+///
+/// The current directory is empty, so relative paths stay relative.
+/// Other relative directories are made absolute, so resolving a path
+/// under the data directory again leaves it unchanged.
+pub fn resolve_data_dir(cli_dir: Option<PathBuf>, env_dir: Option<PathBuf>) -> PathBuf {
+    match cli_dir
+        .or(env_dir)
+        .filter(|dir| !dir.as_os_str().is_empty())
+    {
+        Some(dir) => std::path::absolute(&dir).unwrap_or(dir),
+        None => PathBuf::new(),
+    }
+}
+
+/// Returns a path under the data directory, such as `house.json`.
+///
+/// Absolute paths, such as paths already under the data directory, are unchanged.
+pub fn data_pth(file_path: impl AsRef<Path>) -> PathBuf {
+    data_dir().join(file_path)
+}
+
+/// Serializes a JSON struct to a file under the data directory.
+///
+/// Written atomically, so an interrupted checkpoint leaves the previous file intact.
+pub fn write_to_file<T: Serialize>(data: &T, file_path: &str) -> Result<()> {
+    let pth = data_pth(file_path);
+    debug!("Writing file: {}", pth.display());
+    let bytes = serde_json::to_vec_pretty(data)?;
+    write_atomic(&pth, &bytes)
+}
+
+/// Deserializes a JSON struct from a file under the data directory.
+///
+/// A leftover temporary file of an interrupted write is recovered
+/// when the file itself is unreadable, and otherwise removed.
+pub fn read_from_file<T: for<'de> Deserialize<'de>>(file_path: &str) -> Result<T> {
+    let pth = &data_pth(file_path);
+    debug!("Reading file: {}", pth.display());
+    let pth_tmp = tmp_pth(pth);
+    let ret = read_json(pth);
+    if !pth_tmp.exists() {
+        return ret;
+    }
+    match (ret, read_json(&pth_tmp)) {
+        (Ok(data), _) => {
+            warn!("removing leftover {:?}", pth_tmp);
+            fs::remove_file(&pth_tmp)?;
+            Ok(data)
+        }
+        (Err(_), Ok(data)) => {
+            warn!("recovering {:?} from {:?}", pth, pth_tmp);
+            fs::rename(&pth_tmp, pth)?;
+            Ok(data)
+        }
+        (Err(err), Err(err_tmp)) => Err(anyhow!(
+            "{pth:?}: {err}; leftover {pth_tmp:?}: {err_tmp}; delete both to refetch"
+        )),
+    }
+}
+
+/// Deserializes a JSON struct from a path.
+fn read_json<T: for<'de> Deserialize<'de>>(pth: &Path) -> Result<T> {
+    let file = File::open(pth)?;
+    let reader = BufReader::new(file);
+    let data = serde_json::from_reader(reader)?;
+    Ok(data)
+}
+
+/// Checkpoint policy file of the fetch_adrs loops.
+pub const FLE_PTH_CHECKPOINT: &str = "checkpoint_cfg.json";
+
+/// When fetch_adrs loops write their checkpoint file.
+///
+/// Writes every `every` completions or every `secs` seconds, whichever first,
+/// so a crash loses at most `every - 1` persons of work.
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+pub struct CheckpointPolicy {
+    pub every: usize,
+    #[serde(default)]
+    pub secs: Option<u64>,
+}
+
+impl Default for CheckpointPolicy {
+    /// Writes after every completion.
+    fn default() -> Self {
+        Self {
+            every: 1,
+            secs: None,
+        }
+    }
+}
+
+impl CheckpointPolicy {
+    /// Reads the policy from a file; a missing file writes after every completion.
+    pub fn load(pth: &str) -> Result<CheckpointPolicy> {
+        if !data_pth(pth).exists() {
+            return Ok(CheckpointPolicy::default());
+        }
+        let policy: CheckpointPolicy = read_from_file(pth)?;
+        if policy.every == 0 {
+            return Err(anyhow!(
+                "checkpoint policy '{pth}': every must be at least 1"
+            ));
+        }
+        Ok(policy)
+    }
+}
+
+/// Fetch config file of the fetch_adrs loops.
+pub const FLE_PTH_FETCH: &str = "fetch_cfg.json";
+
+/// Persons fetched concurrently, including their USPS standardization calls.
+pub const FETCH_CONCURRENCY: usize = 8;
+
+/// How fetch_adrs loops fetch persons.
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+pub struct FetchCfg {
+    pub concurrency: usize,
+}
+
+impl Default for FetchCfg {
+    fn default() -> Self {
+        Self {
+            concurrency: FETCH_CONCURRENCY,
+        }
+    }
+}
+
+impl FetchCfg {
+    /// Reads the config from a file; a missing file has the default concurrency.
+    pub fn load(pth: &str) -> Result<FetchCfg> {
+        if !data_pth(pth).exists() {
+            return Ok(FetchCfg::default());
+        }
+        let cfg: FetchCfg = read_from_file(pth)?;
+        if cfg.concurrency == 0 {
+            return Err(anyhow!(
+                "fetch config '{pth}': concurrency must be at least 1"
+            ));
+        }
+        Ok(cfg)
+    }
+}
+
+/// Throttled checkpoint writes of a fetch loop.
+#[derive(Debug)]
+pub struct Checkpoint {
+    pub policy: CheckpointPolicy,
+    /// Completions since the last write.
+    pub pending: usize,
+    /// Time of the last write, or of the start.
+    pub last: Instant,
+    pub writes: usize,
+}
+
+impl Checkpoint {
+    pub fn new(policy: CheckpointPolicy, now: Instant) -> Self {
+        Self {
+            policy,
+            pending: 0,
+            last: now,
+            writes: 0,
+        }
+    }
+
+    /// Whether pending completions are due for a write.
+    pub fn is_due(&self, now: Instant) -> bool {
+        self.pending >= self.policy.every
+            || self.policy.secs.is_some_and(|secs| {
+                self.pending > 0 && now.duration_since(self.last) >= Duration::from_secs(secs)
+            })
+    }
+
+    /// Counts a completion, writing the checkpoint when due.
+    pub fn done<T: Serialize>(&mut self, data: &T, file_path: &str, now: Instant) -> Result<()> {
+        self.pending += 1;
+        if self.is_due(now) {
+            self.write(data, file_path, now)?;
+        }
+        Ok(())
+    }
+
+    /// Writes pending completions, such as at loop end or before returning an error.
+    pub fn flush<T: Serialize>(&mut self, data: &T, file_path: &str, now: Instant) -> Result<()> {
+        if self.pending > 0 {
+            self.write(data, file_path, now)?;
+        }
+        Ok(())
+    }
+
+    fn write<T: Serialize>(&mut self, data: &T, file_path: &str, now: Instant) -> Result<()> {
+        write_to_file(data, file_path)?;
+        self.pending = 0;
+        self.last = now;
+        self.writes += 1;
+        Ok(())
+    }
+}
+
+/// Extension of an artifact checksum sidecar.
+pub const SHA256_EXT: &str = "sha256";
+
+lazy_static! {
+    /// Verification status of artifacts touched during a run.
+    pub static ref ARTIFACTS: Mutex<BTreeMap<PathBuf, ArtifactStatus>> = Mutex::new(BTreeMap::new());
+}
+
+/// Verification status of an artifact.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ArtifactStatus {
+    Written,
+    Verified,
+    Corrupted,
+    /// A sidecar without its artifact, or an artifact without its sidecar.
+    Missing,
+}
+impl fmt::Display for ArtifactStatus {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ArtifactStatus::Written => write!(f, "written"),
+            ArtifactStatus::Verified => write!(f, "verified"),
+            ArtifactStatus::Corrupted => write!(f, "corrupted"),
+            ArtifactStatus::Missing => write!(f, "missing"),
+        }
+    }
+}
+
+/// An artifact whose content doesn't match its checksum sidecar.
+#[derive(Debug)]
+pub struct ArtifactCorrupted {
+    pub pth: PathBuf,
+    pub expected: String,
+    pub actual: String,
+}
+impl fmt::Display for ArtifactCorrupted {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "artifact corrupted: {:?}: sha256 {} does not match sidecar {}",
+            self.pth, self.actual, self.expected
+        )
+    }
+}
+impl std::error::Error for ArtifactCorrupted {}
+
+/// Returns the hex sha256 of bytes.
+pub fn sha256_hex(bytes: &[u8]) -> String {
+    Sha256::digest(bytes)
+        .iter()
+        .map(|b| format!("{b:02x}"))
+        .collect()
+}
+
+/// Returns the checksum sidecar path of an artifact, such as `mailing.json.sha256`.
+pub fn sidecar_pth(pth: &Path) -> PathBuf {
+    let mut ret = pth.as_os_str().to_owned();
+    ret.push(".");
+    ret.push(SHA256_EXT);
+    PathBuf::from(ret)
+}
+
+/// Returns the temporary path of an atomic write, such as `senate.json.tmp`.
+pub fn tmp_pth(pth: &Path) -> PathBuf {
+    let mut ret = pth.as_os_str().to_owned();
+    ret.push(".tmp");
+    PathBuf::from(ret)
+}
+
+/// Writes a file atomically through a temporary file and rename.
+///
+/// Creates missing parent directories, such as a new data directory.
+pub fn write_atomic(pth: &Path, bytes: &[u8]) -> Result<()> {
+    if let Some(dir) = pth.parent().filter(|dir| !dir.as_os_str().is_empty()) {
+        fs::create_dir_all(dir)?;
+    }
+    let pth_tmp = tmp_pth(pth);
+    let mut file = File::create(&pth_tmp)?;
+    file.write_all(bytes)?;
+    file.sync_all()?;
+    fs::rename(&pth_tmp, pth)?;
+    Ok(())
+}
+
+/// Records the status of an artifact for the run summary.
+fn artifact_record(pth: &Path, status: ArtifactStatus) {
+    ARTIFACTS.lock().unwrap().insert(pth.to_path_buf(), status);
+}
+
+/// Serializes a critical JSON artifact atomically with a sha256 sidecar.
+pub fn write_to_file_checked<T: Serialize>(data: &T, file_path: &str) -> Result<()> {
+    let pth = &data_pth(file_path);
+    debug!("Writing file: {}", pth.display());
+    let bytes = serde_json::to_vec_pretty(data)?;
+    write_atomic(pth, &bytes)?;
+    let file_name = pth.file_name().unwrap_or_default().to_string_lossy();
+    write_atomic(
+        &sidecar_pth(pth),
+        format!("{}  {}\n", sha256_hex(&bytes), file_name).as_bytes(),
+    )?;
+    artifact_record(pth, ArtifactStatus::Written);
+    Ok(())
+}
+
+/// Deserializes a critical JSON artifact, verifying its sha256 sidecar.
+///
+/// Corruption is an `ArtifactCorrupted` error, distinct from schema errors.
+/// An artifact without a sidecar is read with a warning.
+pub fn read_from_file_checked<T: for<'de> Deserialize<'de>>(file_path: &str) -> Result<T> {
+    let pth = &data_pth(file_path);
+    debug!("Reading file: {}", pth.display());
+    let bytes = fs::read(pth)?;
+    let status = verify_artifact(pth, &bytes);
+    artifact_record(pth, status);
+    match status {
+        // Return the corruption error.
+        ArtifactStatus::Corrupted => check_sha256(pth, &bytes)?,
+        // Artifacts written before sidecars are read unverified.
+        ArtifactStatus::Missing => {
+            warn!("artifact sidecar missing: {:?}", sidecar_pth(pth))
+        }
+        _ => {}
+    }
+    serde_json::from_slice(&bytes).map_err(|err| anyhow!("artifact schema error: {pth:?}: {err}"))
+}
+
+/// Compares bytes of an artifact with its sidecar.
+fn check_sha256(pth: &Path, bytes: &[u8]) -> Result<()> {
+    let expected = fs::read_to_string(sidecar_pth(pth))?
+        .split_whitespace()
+        .next()
+        .unwrap_or_default()
+        .to_string();
+    let actual = sha256_hex(bytes);
+    if expected != actual {
+        return Err(ArtifactCorrupted {
+            pth: pth.to_path_buf(),
+            expected,
+            actual,
+        }
+        .into());
+    }
+    Ok(())
+}
+
+/// Returns the verification status of artifact bytes.
+pub fn verify_artifact(pth: &Path, bytes: &[u8]) -> ArtifactStatus {
+    if !sidecar_pth(pth).exists() {
+        ArtifactStatus::Missing
+    } else if check_sha256(pth, bytes).is_ok() {
+        ArtifactStatus::Verified
+    } else {
+        ArtifactStatus::Corrupted
+    }
+}
+
+/// Verifies every artifact with a sidecar under a directory.
+///
+/// Hidden directories and `target` are skipped.
+pub fn verify_artifacts(dir: &Path) -> Result<Vec<(PathBuf, ArtifactStatus)>> {
+    let mut ret = Vec::new();
+    let mut dirs = vec![dir.to_path_buf()];
+    while let Some(dir) = dirs.pop() {
+        for entry in fs::read_dir(&dir)? {
+            let pth = entry?.path();
+            let name = pth.file_name().unwrap_or_default().to_string_lossy();
+            if pth.is_dir() {
+                if !name.starts_with('.') && name != "target" {
+                    dirs.push(pth);
+                }
+            } else if pth.extension().is_some_and(|ext| ext == SHA256_EXT) {
+                let pth = pth.with_extension("");
+                let status = match fs::read(&pth) {
+                    Ok(bytes) => verify_artifact(&pth, &bytes),
+                    Err(_) => ArtifactStatus::Missing,
+                };
+                ret.push((pth, status));
+            }
+        }
+    }
+    ret.sort_by(|a, b| a.0.cmp(&b.0));
+    Ok(ret)
+}
+
+/// Lines of artifact statuses for the run summary.
+pub fn artifact_lnes(artifacts: &[(PathBuf, ArtifactStatus)]) -> Vec<String> {
+    if artifacts.is_empty() {
+        return vec!["none".into()];
+    }
+    artifacts
+        .iter()
+        .map(|(pth, status)| format!("{status} {}", pth.display()))
+        .collect()
+}
+
+/// Adds artifacts touched during the run to the run summary.
+pub fn summary_add_artifacts() {
+    let artifacts: Vec<(PathBuf, ArtifactStatus)> = ARTIFACTS
+        .lock()
+        .unwrap()
+        .iter()
+        .map(|(pth, status)| (pth.clone(), *status))
+        .collect();
+    let is_warn = artifacts
+        .iter()
+        .any(|(_, status)| matches!(status, ArtifactStatus::Corrupted | ArtifactStatus::Missing));
+    summary_add("Artifacts", artifact_lnes(&artifacts), is_warn);
+}
+
+/// Progress of a long loop, with items per minute and remaining time.
+#[derive(Debug, Clone)]
+pub struct Progress {
+    /// Items of the loop; unknown when `None`.
+    pub total: Option<usize>,
+    /// Items started before the current item.
+    pub completed: usize,
+    pub started_at: Instant,
+}
+
+impl Progress {
+    pub fn new(total: Option<usize>) -> Self {
+        Progress {
+            total,
+            completed: 0,
+            started_at: Instant::now(),
+        }
+    }
+
+    /// Starts the next item, printing its progress line.
+    pub fn tick(&mut self, label: &str) {
+        info!("{}", self.lne(label, Instant::now()));
+        self.completed += 1;
+    }
+
+    /// Completed items per minute, once an item is completed.
+    pub fn per_min(&self, now: Instant) -> Option<f64> {
+        let mins = now.duration_since(self.started_at).as_secs_f64() / 60.0;
+        (self.completed != 0 && mins > 0.0).then(|| self.completed as f64 / mins)
+    }
+
+    /// Estimated time to complete the current and remaining items.
+    pub fn eta(&self, now: Instant) -> Option<Duration> {
+        let per_min = self.per_min(now)?;
+        let remaining = self.total?.saturating_sub(self.completed);
+        Some(Duration::from_secs_f64(remaining as f64 / per_min * 60.0))
+    }
+
+    /// Progress line of the current item, such as `  3/120 2% 4.0/min eta 29m15s label`.
+    pub fn lne(&self, label: &str, now: Instant) -> String {
+        let cur = self.completed + 1;
+        let mut lne = match self.total {
+            Some(total) => format!("  {cur}/{total} {}%", cur * 100 / total.max(1)),
+            None => format!("  {cur}"),
+        };
+        if let Some(per_min) = self.per_min(now) {
+            lne.push_str(&format!(" {per_min:.1}/min"));
+        }
+        if let Some(eta) = self.eta(now) {
+            lne.push_str(&format!(" eta {}", fmt_dur(eta)));
+        }
+        format!("{lne} {label}")
+    }
+}
+
+/// Formats a duration to hours and minutes, or minutes and seconds, such as `1h05m`.
+pub fn fmt_dur(dur: Duration) -> String {
+    let secs = dur.as_secs();
+    match (secs / 3600, secs / 60 % 60, secs % 60) {
+        (0, 0, s) => format!("{s}s"),
+        (0, m, s) => format!("{m}m{s:02}s"),
+        (h, m, _) => format!("{h}h{m:02}m"),
+    }
+}
+
+/// Directory of mailing artifacts under the data directory.
+pub fn mailings_dir() -> PathBuf {
+    data_pth("mailings")
+}
+
+/// Directory of cached pages under the data directory.
+pub fn cache_dir() -> PathBuf {
+    CACHE_DIR
+        .lock()
+        .unwrap()
+        .clone()
+        .unwrap_or_else(|| data_pth(".cache"))
+}
+
+/// Retries of a request after its first attempt.
+pub const RETRY_CNT: u32 = 4;
+
+/// Backoff delay of the first retry, doubled per retry.
+pub const RETRY_BASE: Duration = Duration::from_millis(500);
+
+/// Longest honored `Retry-After` delay.
+pub const RETRY_AFTER_MAX: Duration = Duration::from_secs(60);
+
+/// Retries of idempotent requests with exponential backoff and jitter.
+#[derive(Debug, Clone, Copy)]
+pub struct Retry {
+    pub cnt: u32,
+    pub base: Duration,
+}
+
+/// Retries with the core constants.
+pub const RETRY: Retry = Retry {
+    cnt: RETRY_CNT,
+    base: RETRY_BASE,
+};
+
+impl Retry {
+    /// Backoff delay before a retry, with jitter of up to the base delay.
+    pub fn delay(&self, attempt: u32) -> Duration {
+        let nanos = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default()
+            .subsec_nanos() as u64;
+        let jitter = Duration::from_nanos(nanos % (self.base.as_nanos() as u64).max(1));
+        self.base * 2u32.saturating_pow(attempt) + jitter
+    }
+}
+
+/// Sends an idempotent request, retrying 429s, 5xx and connection errors.
+///
+/// A `Retry-After` delay in seconds replaces the backoff delay. Other
+/// statuses are returned as is.
+pub async fn send_retry<F>(url: &str, retry: Retry, req: F) -> Result<Response>
+where
+    F: Fn() -> RequestBuilder,
+{
+    let mut attempt = 0;
+    loop {
+        let (delay, err) = match req().send().await {
+            Ok(res)
+                if res.status() == StatusCode::TOO_MANY_REQUESTS
+                    || res.status().is_server_error() =>
+            {
+                let retry_after = res
+                    .headers()
+                    .get(reqwest::header::RETRY_AFTER)
+                    .and_then(|val| val.to_str().ok())
+                    .and_then(|val| val.trim().parse::<u64>().ok())
+                    .map(|secs| Duration::from_secs(secs).min(RETRY_AFTER_MAX));
+                (
+                    retry_after.unwrap_or_else(|| retry.delay(attempt)),
+                    format!("status {}", res.status()),
+                )
+            }
+            Ok(res) => return Ok(res),
+            Err(err) if err.is_connect() || err.is_timeout() || err.is_request() => {
+                (retry.delay(attempt), err.to_string())
+            }
+            Err(err) => return Err(anyhow!("{url}: {err}")),
+        };
+        if attempt >= retry.cnt {
+            return Err(anyhow!(
+                "{url}: failed after {} attempts: {err}",
+                attempt + 1
+            ));
+        }
+        warn!("retrying {url:?} in {delay:?}: {err}");
+        tokio::time::sleep(delay).await;
+        attempt += 1;
+    }
+}
+
+/// How a fetch uses its cached response.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct FetchOptions {
+    /// Age after which a cached response is refetched; `None` never expires.
+    pub max_age: Option<Duration>,
+    /// Refetch regardless of the cached response.
+    pub force: bool,
+    /// Bytes under which a body is an error rather than cached; 0 caches any body.
+    pub min_len: usize,
+}
+
+impl Default for FetchOptions {
+    fn default() -> Self {
+        Self {
+            max_age: None,
+            force: false,
+            min_len: FETCH_MIN_LEN,
+        }
+    }
+}
+
+/// Bytes under which a fetched body is likely an error page, such as an empty 200.
+pub const FETCH_MIN_LEN: usize = 512;
+
+/// Days before a cached page of an elected official is refetched.
+pub const FETCH_DAYS_OFFICIAL: u64 = 30;
+
+/// Days before a cached page of a facility or organization is refetched.
+pub const FETCH_DAYS_FACILITY: u64 = 90;
+
+/// Cached USPS forms, refetched yearly.
+pub const FETCH_OPTS_FORM: FetchOptions = FetchOptions {
+    max_age: Some(Duration::from_secs(365 * 24 * 60 * 60)),
+    force: false,
+    min_len: FETCH_MIN_LEN,
+};
+
+lazy_static! {
+    /// Sources whose cached pages are refetched, set by `refresh --force`.
+    pub static ref FORCE_SOURCES: Mutex<HashSet<Source>> = Mutex::new(HashSet::new());
+}
+
+impl FetchOptions {
+    /// Options of the pages of a source.
+    pub fn for_source(source: Source) -> FetchOptions {
+        let days = match source {
+            Source::Senate
+            | Source::House
+            | Source::Committee
+            | Source::State
+            | Source::Executive => FETCH_DAYS_OFFICIAL,
+            Source::Military | Source::Nasa | Source::Observer => FETCH_DAYS_FACILITY,
+        };
+        FetchOptions {
+            max_age: Some(Duration::from_secs(days * 24 * 60 * 60)),
+            force: FORCE_SOURCES.lock().unwrap().contains(&source),
+            min_len: FETCH_MIN_LEN,
+        }
+    }
+}
+
+/// Metadata of a cached response, kept in a `.meta` file beside it.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CacheMeta {
+    pub url: String,
+    pub at: NaiveDateTime,
+    /// URL after redirects, when it differs from `url`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub final_url: Option<String>,
+}
+
+/// A fetched response body with the URL after redirects.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Fetched {
+    pub bdy: Vec<u8>,
+    pub url: String,
+}
+
+/// Metadata file of a cached response.
+fn meta_pth(pth: &Path) -> PathBuf {
+    let mut pth = pth.as_os_str().to_owned();
+    pth.push(".meta");
+    PathBuf::from(pth)
+}
+
+/// Metadata file of the cached response of a URL under the cache directory.
+pub fn cache_meta_pth(url: &str) -> PathBuf {
+    meta_pth(&cache_dir().join(url_to_filename(url)))
+}
+
+/// Fetch time of the cached response of a URL, `None` when uncached or not a URL.
+pub fn cached_at(url: &str) -> Option<NaiveDateTime> {
+    if !url.starts_with("https://") {
+        return None;
+    }
+    let pth = cache_meta_pth(url);
+    if !pth.exists() {
+        return None;
+    }
+    read_from_file::<CacheMeta>(&pth.to_string_lossy())
+        .ok()
+        .map(|meta| meta.at)
+}
+
+/// Whether a cached response is usable.
+///
+/// Responses without metadata predate expiry and are refetched.
+fn is_cache_fresh(pth: &Path, opts: FetchOptions, now: NaiveDateTime) -> bool {
+    if opts.force || !pth.exists() {
+        return false;
+    }
+    let Ok(meta) = read_from_file::<CacheMeta>(&meta_pth(pth).to_string_lossy()) else {
+        return false;
+    };
+    match opts.max_age.map(chrono::Duration::from_std) {
+        None => true,
+        Some(Ok(max_age)) => now - meta.at < max_age,
+        Some(Err(_)) => true,
+    }
+}
+
+/// Returns the cached response of a URL, or fetches and caches it.
+async fn fetch_cached<F, Fut>(
+    dir: &Path,
+    url: &str,
+    opts: FetchOptions,
+    now: NaiveDateTime,
+    fetch: F,
+) -> Result<PathBuf>
+where
+    F: FnOnce() -> Fut,
+    Fut: Future<Output = Result<Fetched>>,
+{
+    // Create the cache directory if it does not exist
+    if !dir.exists() {
+        fs::create_dir_all(dir)?;
+    }
+
+    // Check if the cache file is fresh
+    let pth = dir.join(url_to_filename(url));
+    if is_cache_fresh(&pth, opts, now) {
+        debug!("Loading cached {:?}...", &pth);
+        return Ok(pth);
+    }
+
+    let fetched = fetch().await?;
+
+    // Refuse to cache short bodies, such as an empty page of a blocked request.
+    if fetched.bdy.len() < opts.min_len {
+        return Err(anyhow!(
+            "{url}: body of {} bytes is under {} bytes, not cached",
+            fetched.bdy.len(),
+            opts.min_len
+        ));
+    }
+
+    // Save the fetched body to the cache file
+    let mut file = fs::File::create(&pth)?;
+    file.write_all(&fetched.bdy)?;
+    if fetched.url != url {
+        info!("{url:?} redirected to {:?}", fetched.url);
+    }
+    let meta = CacheMeta {
+        url: url.into(),
+        at: now,
+        final_url: Some(fetched.url).filter(|final_url| final_url != url),
+    };
+    write_to_file(&meta, &meta_pth(&pth).to_string_lossy())?;
+
+    Ok(pth)
+}
+
+/// Fetches pages by URL.
+#[allow(async_fn_in_trait)]
+pub trait Fetcher {
+    async fn get(&self, url: &str) -> Result<String>;
+}
+
+/// Fetches pages from the network, through the cache.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct CachedFetcher {
+    pub opts: FetchOptions,
+}
+
+impl Fetcher for CachedFetcher {
+    async fn get(&self, url: &str) -> Result<String> {
+        let now = Local::now().naive_local();
+        let pth = fetch_cached(&cache_dir(), url, self.opts, now, || {
+            fetch_html_fetched(url)
+        })
+        .await?;
+        Ok(fs::read_to_string(&pth)?)
+    }
+}
+
+/// Fetches pages from a directory of recorded pages, erroring on unrecorded URLs.
+///
+/// Pages are named as in the cache, so a cache directory records a snapshot.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SnapshotFetcher {
+    pub dir: PathBuf,
+}
+
+impl SnapshotFetcher {
+    pub fn new(dir: impl Into<PathBuf>) -> Self {
+        Self { dir: dir.into() }
+    }
+
+    /// Recorded file of a URL.
+    pub fn pth(&self, url: &str) -> PathBuf {
+        self.dir.join(url_to_filename(url))
+    }
+}
+
+impl Fetcher for SnapshotFetcher {
+    async fn get(&self, url: &str) -> Result<String> {
+        let pth = self.pth(url);
+        // An unrecorded page is missing, as the cache records no 404s.
+        fs::read_to_string(&pth).map_err(|err| {
+            anyhow::Error::new(StatusError {
+                url: url.into(),
+                status: StatusCode::NOT_FOUND,
+            })
+            .context(format!(
+                "{url}: not in snapshot {}: {err}",
+                self.dir.display()
+            ))
+        })
+    }
+}
+
+tokio::task_local! {
+    /// Snapshot of the pages of a task, set by `with_snapshot`.
+    static SNAPSHOT: SnapshotFetcher;
+}
+
+/// Runs a future offline, fetching pages from a snapshot rather than the network.
+pub async fn with_snapshot<F: Future>(snapshot: SnapshotFetcher, fut: F) -> F::Output {
+    SNAPSHOT.scope(snapshot, fut).await
+}
+
+/// Fetches HTML from a URL and caches the response body to a local file.
+///
+/// Within `with_snapshot`, the page is read from the snapshot.
+pub async fn fetch_html(url: &str, opts: FetchOptions) -> Result<String> {
+    match SNAPSHOT.try_with(|snapshot| snapshot.clone()) {
+        Ok(snapshot) => snapshot.get(url).await,
+        Err(_) => CachedFetcher { opts }.get(url).await,
+    }
+}
+
+/// Deletes cached responses of URLs starting with a prefix, returning the count.
+///
+/// The scheme is optional, and a leading `*.` matches any subdomain,
+/// such as `*.house.gov`.
+pub fn invalidate_cache(url_prefix: &str) -> Result<usize> {
+    invalidate_cache_in(&cache_dir(), url_prefix)
+}
+
+/// Deletes cached responses of matching URLs in a cache directory.
+fn invalidate_cache_in(dir: &Path, url_prefix: &str) -> Result<usize> {
+    if !dir.exists() {
+        return Ok(0);
+    }
+    let mut cnt = 0;
+    for entry in fs::read_dir(dir)? {
+        let pth = entry?.path();
+        if pth.extension().and_then(|ext| ext.to_str()) != Some("meta") {
+            continue;
+        }
+        let meta = read_from_file::<CacheMeta>(&pth.to_string_lossy())?;
+        if !is_url_match(&meta.url, url_prefix) {
+            continue;
+        }
+        let pth_bdy = pth.with_extension("");
+        if pth_bdy.exists() {
+            fs::remove_file(&pth_bdy)?;
+        }
+        fs::remove_file(&pth)?;
+        cnt += 1;
+    }
+    Ok(cnt)
+}
+
+/// Whether a URL starts with a prefix, ignoring schemes.
+fn is_url_match(url: &str, url_prefix: &str) -> bool {
+    let url = url.split_once("://").map_or(url, |(_, rest)| rest);
+    let url_prefix = url_prefix
+        .split_once("://")
+        .map_or(url_prefix, |(_, rest)| rest);
+    let Some(domain_prefix) = url_prefix.strip_prefix("*.") else {
+        return url.starts_with(url_prefix);
+    };
+    let (host, path) = url.split_once('/').unwrap_or((url, ""));
+    let (domain, path_prefix) = domain_prefix.split_once('/').unwrap_or((domain_prefix, ""));
+    (host == domain || host.ends_with(&format!(".{domain}"))) && path.starts_with(path_prefix)
+}
+
+/// A response with a status other than 2xx.
+#[derive(Debug)]
+pub struct StatusError {
+    pub url: String,
+    pub status: StatusCode,
+}
+impl fmt::Display for StatusError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}: status {}", self.url, self.status)
+    }
+}
+impl std::error::Error for StatusError {}
+
+/// Whether a fetch error is a missing page, a 404 or 410 status.
+///
+/// Other errors, such as a 403 block page or a 5xx after retries, are failed fetches.
+pub fn is_page_missing(err: &anyhow::Error) -> bool {
+    err.chain().any(|cause| {
+        cause
+            .downcast_ref::<StatusError>()
+            .is_some_and(|err| matches!(err.status, StatusCode::NOT_FOUND | StatusCode::GONE))
+    })
+}
+
+/// Fetches a URL with retries, following redirects.
+///
+/// Statuses other than 2xx, such as a 403 "Access denied" page, are errors with the status and URL.
+pub async fn fetch_ok(url: &str) -> Result<Response> {
+    info!("Fetching {url:?}...");
+    let res = send_retry(url, RETRY, || CLI.get(url)).await?;
+    let status = res.status();
+    if !status.is_success() {
+        return Err(StatusError {
+            url: url.into(),
+            status,
+        }
+        .into());
+    }
+    Ok(res)
+}
+
+/// Fetches HTML from a URL, bypassing the cache.
+pub async fn fetch_html_live(url: &str) -> Result<String> {
+    let res = fetch_ok(url).await?;
+    let bdy = res.text().await?;
+    Ok(bdy)
+}
+
+/// Fetches HTML from a URL with the URL after redirects, bypassing the cache.
+async fn fetch_html_fetched(url: &str) -> Result<Fetched> {
+    let res = fetch_ok(url).await?;
+    let final_url = res.url().to_string();
+    Ok(Fetched {
+        bdy: res.text().await?.into_bytes(),
+        url: final_url,
+    })
+}
+
+/// Fetches PDF from a URL and caches the response body to a local file.
+///
+/// Within `with_snapshot`, the PDF is read from the snapshot.
+pub async fn fetch_pdf(url: &str, opts: FetchOptions) -> Result<PathBuf> {
+    if let Ok(snapshot) = SNAPSHOT.try_with(|snapshot| snapshot.clone()) {
+        let pth = snapshot.pth(url);
+        if !pth.exists() {
+            return Err(anyhow!("{url}: not in snapshot {}", snapshot.dir.display()));
+        }
+        return Ok(pth);
+    }
+    let now = Local::now().naive_local();
+    fetch_cached(&cache_dir(), url, opts, now, || async {
+        let res = fetch_ok(url).await?;
+        let final_url = res.url().to_string();
+        Ok(Fetched {
+            bdy: res.bytes().await?.to_vec(),
+            url: final_url,
+        })
+    })
+    .await
+}
+
+/// Converts a URL to a safe filename by replacing non-alphanumeric characters.
+fn url_to_filename(url: &str) -> String {
+    // Skip https://
+    url[8..]
+        .chars()
+        .map(|c| if c.is_alphanumeric() { c } else { '_' })
+        .collect()
+}
+
+/// Transforms a String to Option<String>.
+/// Empty string is None.
+pub fn string_to_opt(s: String) -> Option<String> {
+    if s.is_empty() {
+        None
+    } else {
+        Some(s)
+    }
+}
+
+pub fn yr_qtr(date: NaiveDate) -> String {
+    use chrono::Datelike;
+    let year = date.year();
+    let month = date.month();
+
+    let quarter = match month {
+        1..=3 => 1,
+        4..=6 => 2,
+        7..=9 => 3,
+        10..=12 => 4,
+        _ => unreachable!(),
+    };
+
+    format!("{}-Q{}", year, quarter)
+}
+
+/// Format an integer with commas.
+pub fn numfmt(num: usize) -> String {
+    let mut ret = String::new();
+    for (i, c) in num.to_string().chars().rev().enumerate() {
+        if i != 0 && i % 3 == 0 {
+            ret.push(',');
+        }
+        ret.push(c);
+    }
+    ret.chars().rev().collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::*;
+    use std::fs;
+    use std::sync::atomic::AtomicUsize;
+    use std::sync::Arc;
+    use tokio::runtime::Runtime;
+
+    /// Serves canned responses in order, one per connection, counting requests.
+    fn serve(resps: Vec<&'static str>) -> (String, Arc<AtomicUsize>) {
+        use std::io::Read;
+        let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        let url = format!("http://{}/offices", listener.local_addr().unwrap());
+        let hits = Arc::new(AtomicUsize::new(0));
+        let hits_srv = hits.clone();
+        std::thread::spawn(move || {
+            for resp in resps {
+                let (mut stream, _) = listener.accept().unwrap();
+                let mut buf = [0u8; 4096];
+                let _ = stream.read(&mut buf);
+                hits_srv.fetch_add(1, Ordering::SeqCst);
+                stream.write_all(resp.as_bytes()).unwrap();
+            }
+        });
+        (url, hits)
+    }
+
+    const RESP_503: &str =
+        "HTTP/1.1 503 Service Unavailable\r\nContent-Length: 0\r\nConnection: close\r\n\r\n";
+    const RESP_429: &str = "HTTP/1.1 429 Too Many Requests\r\nRetry-After: 0\r\nContent-Length: 0\r\nConnection: close\r\n\r\n";
+    const RESP_OK: &str = "HTTP/1.1 200 OK\r\nContent-Length: 5\r\nConnection: close\r\n\r\nhello";
+    const RESP_403: &str =
+        "HTTP/1.1 403 Forbidden\r\nContent-Length: 13\r\nConnection: close\r\n\r\naccess denied";
+    const RESP_404: &str =
+        "HTTP/1.1 404 Not Found\r\nContent-Length: 9\r\nConnection: close\r\n\r\nnot found";
+
+    /// Retries without waiting.
+    const RETRY_TEST: Retry = Retry {
+        cnt: 2,
+        base: Duration::from_millis(1),
+    };
+
+    #[tokio::test]
+    async fn test_send_retry() {
+        // Fails twice, then succeeds.
+        let (url, hits) = serve(vec![RESP_503, RESP_429, RESP_OK]);
+        let res = send_retry(&url, RETRY_TEST, || CLI.get(&url))
+            .await
+            .unwrap();
+        assert_eq!(res.text().await.unwrap(), "hello");
+        assert_eq!(hits.load(Ordering::SeqCst), 3);
+
+        // Fails after all retries with the url and final status.
+        let (url, hits) = serve(vec![RESP_503, RESP_503, RESP_503]);
+        let err = send_retry(&url, RETRY_TEST, || CLI.get(&url))
+            .await
+            .unwrap_err()
+            .to_string();
+        assert!(err.contains(&url), "{err}");
+        assert!(err.contains("503"), "{err}");
+        assert_eq!(hits.load(Ordering::SeqCst), 3);
+    }
+
+    #[test]
+    fn test_retry_delay() {
+        let retry = Retry {
+            cnt: 3,
+            base: Duration::from_millis(100),
+        };
+        for attempt in 0..3 {
+            let delay = retry.delay(attempt);
+            let min = Duration::from_millis(100 * 2u64.pow(attempt));
+            assert!(delay >= min && delay < min + retry.base, "{delay:?}");
+        }
+    }
+
+    #[test]
+    fn test_artifact_corrupted() {
+        let dir = TempDir::new("artifact");
+        fs::create_dir_all(dir.join("mailings")).unwrap();
+        let pth = dir.join("mailings").join("mailing.json");
+        let file_path = pth.to_string_lossy().to_string();
+
+        write_to_file_checked(&vec![1, 2, 3], &file_path).unwrap();
+        assert!(sidecar_pth(&pth).exists());
+        assert_eq!(
+            read_from_file_checked::<Vec<u8>>(&file_path).unwrap(),
+            vec![1, 2, 3]
+        );
+        assert_eq!(
+            verify_artifacts(&dir).unwrap(),
+            vec![(pth.clone(), ArtifactStatus::Verified)]
+        );
+
+        // Schema errors are not corruption.
+        let err = read_from_file_checked::<String>(&file_path).unwrap_err();
+        assert!(err.downcast_ref::<ArtifactCorrupted>().is_none());
+        assert!(err.to_string().contains("schema error"));
+
+        // Corrupt a byte.
+        let mut bytes = fs::read(&pth).unwrap();
+        bytes[2] = b'9';
+        fs::write(&pth, bytes).unwrap();
+        let err = read_from_file_checked::<Vec<u8>>(&file_path).unwrap_err();
+        assert!(err.downcast_ref::<ArtifactCorrupted>().is_some());
+        assert!(err.to_string().starts_with("artifact corrupted"));
+        assert_eq!(
+            verify_artifacts(&dir).unwrap(),
+            vec![(pth.clone(), ArtifactStatus::Corrupted)]
+        );
+        assert_eq!(
+            ARTIFACTS.lock().unwrap().get(&pth),
+            Some(&ArtifactStatus::Corrupted)
+        );
+
+        // Sidecar without its artifact.
+        fs::remove_file(&pth).unwrap();
+        assert_eq!(
+            verify_artifacts(&dir).unwrap(),
+            vec![(pth.clone(), ArtifactStatus::Missing)]
+        );
+    }
+
+    #[test]
+    fn test_fetch_cfg() {
+        let dir = TempDir::new("fetch_cfg");
+        let pth = dir.pth("fetch_cfg.json");
+
+        // A missing file has the default concurrency.
+        assert_eq!(FetchCfg::load(&pth).unwrap().concurrency, FETCH_CONCURRENCY);
+
+        fs::write(&pth, r#"{"concurrency": 3}"#).unwrap();
+        assert_eq!(FetchCfg::load(&pth).unwrap().concurrency, 3);
+
+        // Zero would never fetch.
+        fs::write(&pth, r#"{"concurrency": 0}"#).unwrap();
+        assert!(FetchCfg::load(&pth).is_err());
+    }
+
+    #[test]
+    fn test_checkpoint_policy() {
+        let dir = TempDir::new("checkpoint");
+        let file_path = dir.pth("senate.json");
+
+        // Fake clock, one completion per second.
+        let start = Instant::now();
+        let at = |secs: u64| start + Duration::from_secs(secs);
+
+        // Every completion by default.
+        let mut ckpt = Checkpoint::new(CheckpointPolicy::default(), at(0));
+        for idx in 1..=5 {
+            ckpt.done(&idx, &file_path, at(idx)).unwrap();
+        }
+        ckpt.flush(&5, &file_path, at(5)).unwrap();
+        assert_eq!(ckpt.writes, 5);
+
+        // Every 4 completions, with a flush of the remainder at loop end.
+        let policy = CheckpointPolicy {
+            every: 4,
+            secs: None,
+        };
+        let mut ckpt = Checkpoint::new(policy, at(0));
+        for idx in 1..=10 {
+            ckpt.done(&idx, &file_path, at(idx)).unwrap();
+        }
+        assert_eq!(ckpt.writes, 2);
+        ckpt.flush(&10, &file_path, at(10)).unwrap();
+        assert_eq!(ckpt.writes, 3);
+        ckpt.flush(&10, &file_path, at(10)).unwrap();
+        assert_eq!(ckpt.writes, 3);
+
+        // Every 10 seconds comes first.
+        let policy = CheckpointPolicy {
+            every: 100,
+            secs: Some(10),
+        };
+        let mut ckpt = Checkpoint::new(policy, at(0));
+        for idx in 1..=25 {
+            ckpt.done(&idx, &file_path, at(idx)).unwrap();
+        }
+        assert_eq!(ckpt.writes, 2);
+    }
+
+    #[test]
+    fn test_checkpoint_crash() {
+        let dir = TempDir::new("checkpoint_crash");
+        let file_path = dir.pth("house.json");
+        let start = Instant::now();
+
+        // Crash after each completion count, without a flush.
+        let every = 4;
+        let policy = CheckpointPolicy { every, secs: None };
+        for crash in 1..=12 {
+            let _ = fs::remove_file(&file_path);
+            let mut ckpt = Checkpoint::new(policy, start);
+            let mut done: Vec<usize> = Vec::new();
+            for idx in 0..crash {
+                done.push(idx);
+                ckpt.done(&done, &file_path, start).unwrap();
+            }
+            let recovered = read_from_file::<Vec<usize>>(&file_path).unwrap_or_default();
+            assert!(
+                crash - recovered.len() < every,
+                "Failed to match: {}",
+                crash
+            );
+        }
+
+        // Flushing before returning an error loses nothing.
+        let mut ckpt = Checkpoint::new(policy, start);
+        let done: Vec<usize> = (0..7).collect();
+        for idx in 1..=done.len() {
+            ckpt.done(&done[..idx].to_vec(), &file_path, start).unwrap();
+        }
+        ckpt.flush(&done, &file_path, start).unwrap();
+        assert_eq!(read_from_file::<Vec<usize>>(&file_path).unwrap(), done);
+    }
+
+    #[test]
+    fn test_write_to_file_interrupted() {
+        let dir = TempDir::new("interrupted");
+        let pth = dir.join("senate.json");
+        let file_path = pth.to_string_lossy().to_string();
+        let read = || read_from_file::<Vec<u8>>(&file_path);
+
+        write_to_file(&vec![1, 2, 3], &file_path).unwrap();
+        assert!(!tmp_pth(&pth).exists());
+        assert_eq!(read().unwrap(), vec![1, 2, 3]);
+
+        // A partial write is recovered from the temporary file.
+        fs::write(&pth, "[1, 2").unwrap();
+        fs::write(tmp_pth(&pth), "[1, 2, 3, 4]").unwrap();
+        assert_eq!(read().unwrap(), vec![1, 2, 3, 4]);
+        assert!(!tmp_pth(&pth).exists());
+        assert_eq!(read().unwrap(), vec![1, 2, 3, 4]);
+
+        // A stale temporary file is removed.
+        fs::write(tmp_pth(&pth), "[1").unwrap();
+        assert_eq!(read().unwrap(), vec![1, 2, 3, 4]);
+        assert!(!tmp_pth(&pth).exists());
+
+        // Both unreadable is reported.
+        fs::write(&pth, "[1, 2").unwrap();
+        fs::write(tmp_pth(&pth), "[1").unwrap();
+        let err = read().unwrap_err();
+        assert!(err.to_string().contains("delete both to refetch"));
+    }
+
+    #[test]
+    fn test_resolve_data_dir() {
+        let dir = |s: &str| Some(PathBuf::from(s));
+        let cwd = std::env::current_dir().unwrap();
+        let cases = [
+            (dir("cli"), dir("env"), cwd.join("cli")),
+            (None, dir("env"), cwd.join("env")),
+            (None, dir("/data"), PathBuf::from("/data")),
+            (None, dir(""), PathBuf::new()),
+            (None, None, PathBuf::new()),
+        ];
+        for (cli_dir, env_dir, expected) in cases {
+            assert_eq!(
+                resolve_data_dir(cli_dir.clone(), env_dir.clone()),
+                expected,
+                "Failed to match: {:?} {:?}",
+                cli_dir,
+                env_dir
+            );
+        }
+
+        // The current directory keeps relative paths relative.
+        assert_eq!(
+            PathBuf::new().join("house.json"),
+            PathBuf::from("house.json")
+        );
+    }
+
+    #[test]
+    fn test_resolve_data_dir_relative() {
+        // Paths resolved under a relative data directory resolve to themselves,
+        // such as a tracking file, a manifest under the mailings, or a cache sidecar.
+        let dir = resolve_data_dir(Some(PathBuf::from("data")), None);
+        assert!(dir.is_absolute());
+        for file_path in ["tracking.json", "mailings/a/manifest.json", ".cache/a.meta"] {
+            let pth = dir.join(file_path);
+            assert_eq!(dir.join(&pth), pth, "Failed to match: {}", file_path);
+        }
+    }
+
+    #[test]
+    fn test_data_dir_env() {
+        let _lock = data_dir_lock();
+        let dir = TempDir::new("data_dir");
+        let file_path = format!("adr_data_dir_{}.json", std::process::id());
+
+        // A missing data directory is created on the first write.
+        let data_dir = dir.join("data");
+        std::env::set_var(DATA_DIR_ENV, &data_dir);
+        write_to_file(&vec![1, 2, 3], &file_path).unwrap();
+        let data = read_from_file::<Vec<u8>>(&file_path).unwrap();
+        let (pth, mailings, cache) = (data_pth(&file_path), mailings_dir(), cache_dir());
+        std::env::remove_var(DATA_DIR_ENV);
+
+        assert_eq!(data, vec![1, 2, 3]);
+        assert_eq!(pth, data_dir.join(&file_path));
+        assert!(pth.exists());
+        assert!(!Path::new(&file_path).exists());
+        assert_eq!(mailings, data_dir.join("mailings"));
+        assert_eq!(cache, data_dir.join(".cache"));
+
+        // Absolute paths are unchanged.
+        assert_eq!(data_pth(&pth), pth);
+    }
+
+    #[test]
+    fn test_copy_dry_run() {
+        let dir = TempDir::new("dry_run");
+        fs::write(dir.join("mailing_cfg.json"), "{}").unwrap();
+        fs::create_dir_all(dir.join(".cache")).unwrap();
+        fs::create_dir_all(dir.join("mailings")).unwrap();
+
+        // Data files are copied, directories are left behind.
+        let dst = copy_dry_run(&dir).unwrap();
+        assert_eq!(dst, dir.join(DRY_RUN_DIR));
+        assert_eq!(
+            fs::read_to_string(dst.join("mailing_cfg.json")).unwrap(),
+            "{}"
+        );
+        assert!(!dst.join(".cache").exists());
+        assert!(!dst.join("mailings").exists());
+
+        // Writes of a previous dry run are replaced.
+        fs::write(dst.join("mailing.json"), "{}").unwrap();
+        copy_dry_run(&dir).unwrap();
+        assert!(!dst.join("mailing.json").exists());
+    }
+
+    #[test]
+    fn test_is_members_stale() {
+        let today = NaiveDate::from_ymd_opt(2024, 6, 30).unwrap();
+        let on = |day: u32| Some(NaiveDate::from_ymd_opt(2024, 6, day).unwrap());
+        let cases = [
+            (on(30), 30, false),
+            (on(1), 30, false),
+            (on(1), 28, true),
+            (None, 30, true),
+            (on(1), 0, false),
+            (None, 0, false),
+        ];
+        for (members_on, max_days, expected) in cases {
+            assert_eq!(
+                is_members_stale(members_on, today, max_days),
+                expected,
+                "Failed to match: {:?} {}",
+                members_on,
+                max_days
+            );
+        }
+    }
+
+    #[test]
+    fn test_numfmt() {
+        assert_eq!(numfmt(0), "0");
+        assert_eq!(numfmt(100), "100");
+        assert_eq!(numfmt(1000), "1,000");
+        assert_eq!(numfmt(10000), "10,000");
+        assert_eq!(numfmt(100000), "100,000");
+        assert_eq!(numfmt(1000000), "1,000,000");
+        assert_eq!(numfmt(10000000), "10,000,000");
+        assert_eq!(numfmt(100000000), "100,000,000");
+        assert_eq!(numfmt(1000000000), "1,000,000,000");
+    }
+    
+    #[test]
+    fn test_valid_cases() {
+        let test_cases = vec![
+            (
+                NaiveDate::from_ymd_opt(2024, 1, 1).expect("Invalid date"),
+                "2024-Q1",
+            ),
+            (
+                NaiveDate::from_ymd_opt(2024, 4, 1).expect("Invalid date"),
+                "2024-Q2",
+            ),
+            (
+                NaiveDate::from_ymd_opt(2024, 7, 1).expect("Invalid date"),
+                "2024-Q3",
+            ),
+            (
+                NaiveDate::from_ymd_opt(2024, 10, 1).expect("Invalid date"),
+                "2024-Q4",
+            ),
+            (
+                NaiveDate::from_ymd_opt(2024, 12, 31).expect("Invalid date"),
+                "2024-Q4",
+            ),
+        ];
+
+        for (input, expected) in test_cases {
+            assert_eq!(yr_qtr(input), expected);
+        }
+    }
+
+    #[test]
+    fn test_string_to_opt_valid() {
+        assert_eq!(
+            string_to_opt("Hello".to_string()),
+            Some("Hello".to_string())
+        );
+        assert_eq!(
+            string_to_opt("world".to_string()),
+            Some("world".to_string())
+        );
+        assert_eq!(string_to_opt("Rust".to_string()), Some("Rust".to_string()));
+        assert_eq!(string_to_opt("".to_string()), None);
+        assert_eq!(string_to_opt(String::new()), None);
+    }
+
+    #[test]
+    fn test_fetch_html_with_caching() {
+        let _lock = data_dir_lock();
+        let runtime = Runtime::new().unwrap();
+
+        // Replace with a test URL
+        let test_url = "https://www.google.com";
+
+        // First call should fetch and cache the content
+        let result = runtime.block_on(fetch_html(test_url, FetchOptions::default()));
+        assert!(result.is_ok());
+        let body = result.unwrap();
+        assert!(!body.is_empty());
+
+        // Second call should load from cache
+        let result = runtime.block_on(fetch_html(test_url, FetchOptions::default()));
+        assert!(result.is_ok());
+        let cached_body = result.unwrap();
+        assert_eq!(body, cached_body);
+
+        // Clean up cache file
+        let cache_file = Path::new("cache").join(url_to_filename(test_url));
+        fs::remove_file(cache_file).unwrap();
+
+        // Clean up cache directory if empty
+        if fs::read_dir("cache").unwrap().next().is_none() {
+            fs::remove_dir("cache").unwrap();
+        }
+    }
+
+    #[tokio::test]
+    async fn test_fetch_cached() {
+        let dir = TempDir::new("fetch_cache");
+        let url = "https://smith.house.gov/contact";
+        let cnt = AtomicUsize::new(0);
+        let fetch = || async {
+            let n = cnt.fetch_add(1, Ordering::SeqCst) + 1;
+            Ok(Fetched {
+                bdy: format!("body {n}").into_bytes(),
+                url: url.into(),
+            })
+        };
+        let opts = FetchOptions {
+            max_age: Some(Duration::from_secs(24 * 60 * 60)),
+            force: false,
+            min_len: 0,
+        };
+        let now = NaiveDate::from_ymd_opt(2026, 10, 1)
+            .unwrap()
+            .and_hms_opt(0, 0, 0)
+            .unwrap();
+
+        // Miss.
+        let pth = fetch_cached(&dir, url, opts, now, fetch).await.unwrap();
+        assert_eq!(fs::read_to_string(&pth).unwrap(), "body 1");
+
+        // Hit.
+        let later = now + chrono::Duration::hours(23);
+        let pth = fetch_cached(&dir, url, opts, later, fetch).await.unwrap();
+        assert_eq!(fs::read_to_string(&pth).unwrap(), "body 1");
+        assert_eq!(cnt.load(Ordering::SeqCst), 1);
+
+        // Expired hit is refetched.
+        let later = now + chrono::Duration::hours(25);
+        let pth = fetch_cached(&dir, url, opts, later, fetch).await.unwrap();
+        assert_eq!(fs::read_to_string(&pth).unwrap(), "body 2");
+
+        // Forced.
+        let forced = FetchOptions {
+            force: true,
+            ..opts
+        };
+        let pth = fetch_cached(&dir, url, forced, later, fetch).await.unwrap();
+        assert_eq!(fs::read_to_string(&pth).unwrap(), "body 3");
+
+        // Pages without metadata are refetched.
+        fs::remove_file(meta_pth(&pth)).unwrap();
+        let pth = fetch_cached(&dir, url, opts, later, fetch).await.unwrap();
+        assert_eq!(fs::read_to_string(&pth).unwrap(), "body 4");
+
+        // Invalidated pages are refetched.
+        assert_eq!(invalidate_cache_in(&dir, "smith.senate.gov").unwrap(), 0);
+        assert_eq!(invalidate_cache_in(&dir, "*.house.gov").unwrap(), 1);
+        assert!(!pth.exists());
+        let pth = fetch_cached(&dir, url, opts, later, fetch).await.unwrap();
+        assert_eq!(fs::read_to_string(&pth).unwrap(), "body 5");
+    }
+
+    #[tokio::test]
+    async fn test_fetch_cached_status() {
+        let dir = TempDir::new("fetch_status");
+        let now = NaiveDate::from_ymd_opt(2026, 10, 1)
+            .unwrap()
+            .and_hms_opt(0, 0, 0)
+            .unwrap();
+        let opts = FetchOptions {
+            min_len: 0,
+            ..Default::default()
+        };
+
+        // Error pages are errors with the status, and are not cached.
+        let (url, _) = serve(vec![RESP_404]);
+        let err = fetch_cached(&dir, &url, opts, now, || fetch_html_fetched(&url))
+            .await
+            .unwrap_err();
+        assert!(is_page_missing(&err));
+        let err = err.to_string();
+        assert!(err.contains("404"), "{err}");
+        assert!(err.contains(&url), "{err}");
+        assert_eq!(fs::read_dir(&dir).unwrap().count(), 0);
+
+        // Block pages are failed fetches rather than missing pages.
+        let (url, _) = serve(vec![RESP_403]);
+        let err = fetch_cached(&dir, &url, opts, now, || fetch_html_fetched(&url))
+            .await
+            .unwrap_err();
+        assert!(!is_page_missing(&err));
+        assert!(err.to_string().contains("403"), "{err}");
+        assert_eq!(fs::read_dir(&dir).unwrap().count(), 0);
+
+        // Short bodies are not cached unless opted in.
+        let (url, _) = serve(vec![RESP_OK, RESP_OK]);
+        let err = fetch_cached(&dir, &url, FetchOptions::default(), now, || {
+            fetch_html_fetched(&url)
+        })
+        .await
+        .unwrap_err()
+        .to_string();
+        assert!(err.contains("not cached"), "{err}");
+        assert_eq!(fs::read_dir(&dir).unwrap().count(), 0);
+        let pth = fetch_cached(&dir, &url, opts, now, || fetch_html_fetched(&url))
+            .await
+            .unwrap();
+        assert_eq!(fs::read_to_string(&pth).unwrap(), "hello");
+        let meta = read_from_file::<CacheMeta>(&meta_pth(&pth).to_string_lossy()).unwrap();
+        assert_eq!(meta.final_url, None);
+    }
+
+    #[test]
+    fn test_is_url_match() {
+        let cases = [
+            ("https://a.house.gov/contact", "https://a.house.gov", true),
+            ("https://a.house.gov/contact", "a.house.gov/contact", true),
+            ("https://a.house.gov/contact", "*.house.gov", true),
+            ("https://house.gov/contact", "*.house.gov", true),
+            ("https://a.house.gov/contact", "*.house.gov/contact", true),
+            ("https://a.house.gov/offices", "*.house.gov/contact", false),
+            ("https://a.senate.gov", "*.house.gov", false),
+            ("https://greenhouse.gov", "*.house.gov", false),
+            ("https://b.house.gov", "https://a.house.gov", false),
+        ];
+        for (url, url_prefix, expected) in cases {
+            assert_eq!(
+                is_url_match(url, url_prefix),
+                expected,
+                "Failed to match: {}",
+                url
+            );
+        }
+    }
+
+    #[test]
+    fn test_progress() {
+        let started_at = Instant::now();
+        let mut progress = Progress {
+            total: Some(120),
+            completed: 0,
+            started_at,
+        };
+
+        // No rate before an item is completed.
+        assert_eq!(progress.per_min(started_at), None);
+        assert_eq!(progress.eta(started_at), None);
+        assert_eq!(progress.lne("a", started_at), "  1/120 0% a");
+
+        // 4 items per minute leaves 29m15s for 117 items.
+        progress.completed = 3;
+        let now = started_at + Duration::from_secs(45);
+        assert_eq!(progress.per_min(now), Some(4.0));
+        assert_eq!(progress.eta(now), Some(Duration::from_secs(117 * 15)));
+        assert_eq!(progress.lne("b", now), "  4/120 3% 4.0/min eta 29m15s b");
+
+        // Unknown totals have a rate without an eta.
+        progress.total = None;
+        assert_eq!(progress.eta(now), None);
+        assert_eq!(progress.lne("c", now), "  4 4.0/min c");
+
+        // Ticks count items.
+        progress.tick("d");
+        assert_eq!(progress.completed, 4);
+    }
+
+    #[test]
+    fn test_fmt_dur() {
+        let cases = [
+            (0, "0s"),
+            (45, "45s"),
+            (60, "1m00s"),
+            (1755, "29m15s"),
+            (3600, "1h00m"),
+            (3900, "1h05m"),
+            (90061, "25h01m"),
+        ];
+        for (secs, expected) in cases {
+            assert_eq!(
+                fmt_dur(Duration::from_secs(secs)),
+                expected,
+                "Failed to match: {}",
+                secs
+            );
+        }
+    }
+}
@@ -0,0 +1,97 @@
+use crate::font::*;
+use crate::pdfa::*;
+use anyhow::Result;
+use printpdf::*;
+use std::path::Path;
+
+const LYR_LABEL: &str = "LABEL";
+/// A 4x6 inch shipping label.
+const WIDTH: Mm = Mm(101.6);
+const HEIGHT: Mm = Mm(152.4);
+const MARGIN: Mm = Mm(8.0);
+
+/// A pdf document of labels, one label per page.
+pub struct LabelDocument {
+    pub name: String,
+    pub doc: PdfDocumentReference,
+    pub font: IndirectFontRef,
+    pub font_bold: IndirectFontRef,
+    pub pg_cnt: usize,
+}
+
+impl LabelDocument {
+    /// Creates a label document with an embedded TTF font, or builtin Helvetica.
+    pub fn new(name: String, font_pth: Option<&str>) -> Result<Self> {
+        let doc = with_font_mode(PdfDocument::empty(&name), font_pth);
+        let font = add_text_font(&doc, font_pth, BuiltinFont::Helvetica)?;
+        // An embedded font is used for headings too.
+        let font_bold = match font_pth {
+            Some(_) => font.clone(),
+            None => add_text_font(&doc, None, BuiltinFont::HelveticaBold)?,
+        };
+        Ok(Self {
+            name,
+            doc,
+            font,
+            font_bold,
+            pg_cnt: 0,
+        })
+    }
+
+    /// Creates a label page with a bold heading followed by lines.
+    pub fn create_page(&mut self, heading: &str, lnes: &[String]) {
+        let (pg_idx, lyr_idx) = self.doc.add_page(WIDTH, HEIGHT, LYR_LABEL);
+        self.pg_cnt += 1;
+        let lyr = self.doc.get_page(pg_idx).get_layer(lyr_idx);
+
+        lyr.begin_text_section();
+        lyr.set_font(&self.font_bold, 28.0);
+        lyr.set_text_cursor(MARGIN, HEIGHT - MARGIN - Mm(10.0));
+        lyr.set_line_height(34.0);
+        lyr.write_text(heading, &self.font_bold);
+        lyr.add_line_break();
+        lyr.set_font(&self.font, 11.0);
+        lyr.set_line_height(14.0);
+        for lne in lnes {
+            lyr.write_text(lne.clone(), &self.font);
+            lyr.add_line_break();
+        }
+        lyr.end_text_section();
+    }
+
+    /// Saves the labels to `dir/<name>.pdf`.
+    pub fn save(self, dir: &Path, conformance: Conformance) -> Result<()> {
+        let mut pth = dir.join(&self.name);
+        pth.set_extension("pdf");
+        save_doc(self.doc, &pth, conformance)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::*;
+
+    /// Renders a label to pdf bytes.
+    fn render(font_pth: Option<&str>) -> String {
+        let mut lbl_doc = LabelDocument::new("test".into(), font_pth).unwrap();
+        lbl_doc.create_page("BOX 1 OF 1", &["Pieces: 50".to_string()]);
+        String::from_utf8_lossy(&lbl_doc.doc.save_to_bytes().unwrap()).into_owned()
+    }
+
+    #[test]
+    fn test_label_font_mode() {
+        let pdf = render(Some(TEXT_FONT_PTH));
+        assert!(pdf.contains("/FontFile2"));
+        assert!(pdf.contains("DejaVuSans"));
+        assert!(!pdf.contains("/Helvetica"));
+        assert!(pdf.contains("font-mode:embedded"));
+
+        let pdf = render(None);
+        assert!(!pdf.contains("/FontFile2"));
+        assert!(pdf.contains("/Helvetica"));
+        assert!(pdf.contains("font-mode:builtin"));
+
+        assert!(LabelDocument::new("test".into(), Some("fonts/missing.ttf")).is_err());
+    }
+}
@@ -0,0 +1,109 @@
+use crate::io::*;
+use crate::models::*;
+use anyhow::{anyhow, Result};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+/// Letter language overrides by person name, such as `{"Jane Doe": "es"}`.
+pub const LANGUAGE_PTH: &str = "language_overrides.json";
+
+/// Language of mailpieces without a language.
+pub const LANGUAGE_DEFAULT: &str = "en";
+
+/// Letter languages of recipients.
+#[derive(Debug, Default, Serialize, Deserialize, Clone)]
+pub struct LanguageCfg {
+    /// Language by address state, such as `{"PR": "es", "GU": "es"}`.
+    #[serde(default)]
+    pub states: BTreeMap<String, String>,
+    /// Letter template path by language, such as `{"es": "letter-template-es.json"}`.
+    ///
+    /// Other languages fall back to the default template.
+    #[serde(default)]
+    pub templates: BTreeMap<String, String>,
+}
+
+impl LanguageCfg {
+    /// Language of a mailpiece to an address of a person.
+    ///
+    /// An override wins, then the person's language, then the address state.
+    /// `None` is the default language.
+    pub fn language_of(
+        &self,
+        overrides: &LanguageOverrides,
+        per: &Person,
+        adr: &Address,
+    ) -> Option<String> {
+        overrides
+            .persons
+            .get(&per.name)
+            .or(per.language.as_ref())
+            .or(self.states.get(&adr.state))
+            .filter(|lang| lang.as_str() != LANGUAGE_DEFAULT)
+            .cloned()
+    }
+}
+
+/// Letter languages of persons, overriding the state rules.
+#[derive(Debug, Default, Serialize, Deserialize, Clone)]
+pub struct LanguageOverrides {
+    pub persons: BTreeMap<String, String>,
+}
+
+impl LanguageOverrides {
+    /// Reads overrides from a file; a missing file has no overrides.
+    pub fn load(pth: &str) -> Result<LanguageOverrides> {
+        if !data_pth(pth).exists() {
+            return Ok(LanguageOverrides::default());
+        }
+        let persons: BTreeMap<String, String> = read_from_file(pth)
+            .map_err(|err| anyhow!("invalid language overrides '{pth}': {err}"))?;
+        Ok(LanguageOverrides { persons })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_language_of() {
+        let cfg = LanguageCfg {
+            states: BTreeMap::from([("PR".into(), "es".into()), ("GU".into(), "es".into())]),
+            ..Default::default()
+        };
+        let per = |name: &str, language: Option<&str>| Person {
+            name: name.into(),
+            language: language.map(String::from),
+            ..Default::default()
+        };
+        let adr = |state: &str| Address {
+            state: state.into(),
+            ..Default::default()
+        };
+        let overrides = LanguageOverrides {
+            persons: BTreeMap::from([
+                ("Pablo José Hernández".into(), "en".into()),
+                ("Maria Elvira Salazar".into(), "es".into()),
+            ]),
+        };
+
+        let cases = vec![
+            (per("Jenniffer González-Colón", None), "PR", Some("es")),
+            (per("Jenniffer González-Colón", None), "DC", None),
+            (per("Pablo José Hernández", None), "PR", None),
+            (per("Maria Elvira Salazar", None), "FL", Some("es")),
+            (per("Mike Rounds", Some("es")), "SD", Some("es")),
+            (per("Mike Rounds", None), "SD", None),
+        ];
+        for (per, state, expected) in cases {
+            assert_eq!(
+                cfg.language_of(&overrides, &per, &adr(state)).as_deref(),
+                expected,
+                "Failed to match: {} {}",
+                per.name,
+                state
+            );
+        }
+    }
+}
@@ -0,0 +1,366 @@
+use crate::io::*;
+use crate::language::*;
+use crate::models::*;
+use crate::qr::*;
+use anyhow::{anyhow, Result};
+use pdf_doc::*;
+use skia_safe::{
+    pdf,
+    textlayout::{
+        FontCollection, ParagraphBuilder, ParagraphStyle, PlaceholderAlignment, PlaceholderStyle,
+        TextBaseline, TextStyle,
+    },
+    Document, FontMgr, Paint, Point,
+};
+use std::collections::hash_map::Entry::Vacant;
+use std::collections::{BTreeMap, HashMap};
+use std::fs;
+use std::path::Path;
+use tracing::warn;
+
+/// Merge field of the recipient name.
+pub const FLD_NAME: &str = "{{name}}";
+
+/// A span of paragraph text sharing one font style.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Run {
+    pub txt: String,
+    pub sty: Style,
+}
+
+/// Parses inline markup of a paragraph into styled runs.
+///
+/// `**bold**` and `*italic*` spans are supported; `\*` is a literal asterisk.
+/// Nested or unclosed spans are errors.
+pub fn prs_runs(txt: &str) -> Result<Vec<Run>> {
+    let mut runs: Vec<Run> = Vec::new();
+    let mut cur = String::new();
+    let mut is_bold = false;
+    let mut is_italic = false;
+
+    let mut chrs = txt.chars().peekable();
+    while let Some(chr) = chrs.next() {
+        match chr {
+            '\\' if chrs.peek() == Some(&'*') => {
+                cur.push(chrs.next().unwrap());
+            }
+            '*' => {
+                // Close the current run.
+                push_run(&mut runs, &mut cur, sty(is_bold, is_italic));
+                if chrs.peek() == Some(&'*') {
+                    chrs.next();
+                    if is_italic {
+                        return Err(anyhow!("unsupported bold within italic: {txt}"));
+                    }
+                    is_bold = !is_bold;
+                } else {
+                    if is_bold {
+                        return Err(anyhow!("unsupported italic within bold: {txt}"));
+                    }
+                    is_italic = !is_italic;
+                }
+            }
+            _ => cur.push(chr),
+        }
+    }
+
+    if is_bold || is_italic {
+        return Err(anyhow!("unclosed markup: {txt}"));
+    }
+    push_run(&mut runs, &mut cur, Style::Normal);
+
+    Ok(runs)
+}
+
+fn sty(is_bold: bool, is_italic: bool) -> Style {
+    match (is_bold, is_italic) {
+        (true, _) => Style::Bold,
+        (_, true) => Style::Italic,
+        _ => Style::Normal,
+    }
+}
+
+/// Appends a run, merging with a previous run of the same style.
+fn push_run(runs: &mut Vec<Run>, cur: &mut String, sty: Style) {
+    if cur.is_empty() {
+        return;
+    }
+    match runs.last_mut() {
+        Some(run) if run.sty == sty => run.txt.push_str(cur),
+        _ => runs.push(Run {
+            txt: cur.clone(),
+            sty,
+        }),
+    }
+    cur.clear();
+}
+
+/// Escapes asterisks of a merge value so substituted text stays literal.
+pub fn escape_markup(s: &str) -> String {
+    s.replace('*', "\\*")
+}
+
+/// Validates inline markup of every paragraph of a letter template.
+pub fn validate_markup(doc: &Doc) -> Result<()> {
+    for (idx, elm) in doc.elms.iter().enumerate() {
+        if let Elm::Par(par) = elm {
+            prs_runs(&par.txt).map_err(|err| anyhow!("paragraph {idx}: {err}"))?;
+        }
+    }
+    Ok(())
+}
+
+/// Style of a run within a paragraph of a style.
+///
+/// A bold run of an italic paragraph is bold italic.
+pub fn run_sty(par_sty: Style, sty: Style) -> Style {
+    match (par_sty, sty) {
+        (par_sty, Style::Normal) => par_sty,
+        (Style::Normal, sty) => sty,
+        (Style::Italic, Style::Bold) | (Style::Bold, Style::Italic) => Style::BoldItalic,
+        (par_sty, _) => par_sty,
+    }
+}
+
+/// Saves a letter as a pdf, rendering the markup runs of each paragraph in their styles.
+///
+/// Mirrors `Doc::save_pdf`, whose paragraphs are one style, so runs share a line.
+pub fn save_letter_pdf(ltr: &Doc, pth: &Path) -> Result<()> {
+    let mut memory = Vec::new();
+    let mut pdf = pdf::new_document(&mut memory, None);
+    let mut fnts: HashMap<String, FontCollection> = HashMap::new();
+    let font_mgr = FontMgr::new();
+    for pars in ltr.seg_pags() {
+        pdf = wrt_letter_pag(ltr, pars, pdf, &mut fnts, &font_mgr)?;
+    }
+    pdf.close();
+    fs::write(pth.with_extension("pdf"), &memory)?;
+    Ok(())
+}
+
+/// Writes a page of letter paragraphs, one text style per markup run.
+fn wrt_letter_pag<'a>(
+    ltr: &Doc,
+    pars: Vec<Par>,
+    pdf: Document<'a>,
+    fnts: &mut HashMap<String, FontCollection>,
+    font_mgr: &FontMgr,
+) -> Result<Document<'a>> {
+    let mut pdf_pag = pdf.begin_page(ltr.sze.pt(), None);
+    let par_wid = ltr.sze.width - ltr.mrg.width();
+    let mut y: f32 = ltr.mrg.top.pt();
+    for par in pars {
+        // Determine paragraph font collection.
+        let fnt = par.fnt.unwrap_or(ltr.fnt);
+        if let Vacant(entry) = fnts.entry(fnt.to_string()) {
+            entry.insert(create_fnt_col(fnt, font_mgr).map_err(|err| anyhow!("{err:?}"))?);
+        }
+        let fnt_col = fnts[&fnt.to_string()].clone();
+
+        // Determine paragraph text style.
+        let mut par_ts = TextStyle::new();
+        par_ts.set_font_families(&[fnt.to_string()]);
+        par_ts.set_font_size(par.fnt_sze.unwrap_or(ltr.fnt_sze));
+        par_ts.set_height(par.spc_lne.unwrap_or(ltr.spc_lne).val());
+        par_ts.set_height_override(true);
+        par_ts.set_foreground_paint(&Paint::default());
+        let par_sty = par.fnt_sty.unwrap_or(ltr.fnt_sty);
+
+        // Build paragraph.
+        let mut par_sty_bld = ParagraphStyle::new();
+        par.aln.unwrap_or(ltr.aln).set(&mut par_sty_bld);
+        let mut par_bld = ParagraphBuilder::new(&par_sty_bld, &fnt_col);
+        par_bld.push_style(&par_ts);
+        if par.has_ind.unwrap_or(ltr.has_ind) {
+            let ind = par.ind.as_ref().unwrap_or(&ltr.ind);
+            par_bld.add_placeholder(&PlaceholderStyle {
+                width: ind.pt(),
+                height: 0.0,
+                alignment: PlaceholderAlignment::Baseline,
+                baseline_offset: 0.0,
+                baseline: TextBaseline::Alphabetic,
+            });
+        }
+
+        // Add each run in its style.
+        for run in prs_runs(&par.txt)? {
+            let mut ts = par_ts.clone();
+            run_sty(par_sty, run.sty).set(&mut ts);
+            par_bld.push_style(&ts);
+            par_bld.add_text(&run.txt);
+            par_bld.pop();
+        }
+
+        // Layout and paint paragraph.
+        let mut paragraph = par_bld.build();
+        paragraph.layout(par_wid.pt());
+        paragraph.paint(
+            pdf_pag.canvas(),
+            Point {
+                x: ltr.mrg.lft.pt(),
+                y,
+            },
+        );
+
+        // Advance past paragraph and the space after it.
+        let par_spc_aft = par.spc_aft.unwrap_or(ltr.spc_par_aft);
+        let lne_hgt = paragraph
+            .get_line_metrics_at(0)
+            .map_or(0.0, |lne| lne.height);
+        y += lne_hgt as f32 * par_spc_aft.val();
+        y += paragraph.height();
+    }
+    Ok(pdf_pag.end_page())
+}
+
+/// Letter templates by language.
+#[derive(Debug, Clone)]
+pub struct LetterTemplates {
+    pub default: Doc,
+    pub langs: BTreeMap<String, Doc>,
+}
+
+impl LetterTemplates {
+    /// Reads the template of each configured language alongside the default template.
+    pub fn load(default: Doc, pths: &BTreeMap<String, String>) -> Result<LetterTemplates> {
+        validate_markup(&default)?;
+        let mut langs = BTreeMap::new();
+        for (lang, pth) in pths.iter() {
+            let tmpl = read_from_file::<Doc>(pth)?;
+            validate_markup(&tmpl).map_err(|err| anyhow!("{lang} letter template: {err}"))?;
+            langs.insert(lang.clone(), tmpl);
+        }
+        Ok(LetterTemplates { default, langs })
+    }
+
+    /// Template of a language, `None` for the default language.
+    pub fn get(&self, lang: Option<&str>) -> Option<&Doc> {
+        match lang {
+            None => Some(&self.default),
+            Some(lang) if lang == LANGUAGE_DEFAULT => Some(&self.default),
+            Some(lang) => self.langs.get(lang),
+        }
+    }
+}
+
+/// Appends the letter of a mailpiece in its language, followed by a page break.
+///
+/// A language without a template falls back to the default template.
+/// A response url base adds a footer with the mailpiece id and its url.
+/// Returns whether the letter fell back.
+pub fn append_letter(
+    ltr: &mut Doc,
+    tmpls: &LetterTemplates,
+    mp: &Mailpiece,
+    qr_base_url: Option<&str>,
+) -> Result<bool> {
+    let (tmpl, is_fallback) = match tmpls.get(mp.language.as_deref()) {
+        Some(tmpl) => (tmpl, false),
+        None => {
+            warn!(
+                "no {} letter template for {}, using default",
+                mp.language.as_deref().unwrap_or_default(),
+                mp.name
+            );
+            (&tmpls.default, true)
+        }
+    };
+
+    // Substitute before markup so the name is styled by surrounding markup.
+    let mut cur_ltr = tmpl.clone();
+    for elm in cur_ltr.elms.iter_mut() {
+        if let Elm::Par(par) = elm {
+            par.replace(FLD_NAME, &escape_markup(&mp.name));
+        }
+    }
+    validate_markup(&cur_ltr)?;
+
+    // Copy paragraphs to destination letter.
+    ltr.copy_pars(cur_ltr);
+    // Add a footer matching the QR code on the envelope.
+    if let Some(base) = qr_base_url {
+        let txt = format!("Ref {:06}  {}", mp.id, qr_url(base, mp.id));
+        ltr.add_par(par(&escape_markup(&txt)).set_fnt_sze(Some(8.0)));
+    }
+    // Add a page break.
+    ltr.add_pag_brk();
+    Ok(is_fallback)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_prs_runs_mixed() {
+        let tmpl = r"**Dear {{name}}**, please read *UFO Journal* and rate it 5 \* 5.";
+        let txt = tmpl.replace("{{name}}", &escape_markup("Jane *Q* Doe"));
+        let runs = prs_runs(&txt).unwrap();
+        assert_eq!(
+            runs,
+            vec![
+                Run {
+                    txt: "Dear Jane *Q* Doe".into(),
+                    sty: Style::Bold
+                },
+                Run {
+                    txt: ", please read ".into(),
+                    sty: Style::Normal
+                },
+                Run {
+                    txt: "UFO Journal".into(),
+                    sty: Style::Italic
+                },
+                Run {
+                    txt: " and rate it 5 * 5.".into(),
+                    sty: Style::Normal
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_prs_runs_invalid() {
+        let cases = vec![
+            "**bold *italic* bold**",
+            "*italic **bold** italic*",
+            "**unclosed",
+            "*unclosed",
+        ];
+        for input in cases {
+            assert!(prs_runs(input).is_err(), "Failed to match: {}", input);
+        }
+    }
+
+    #[test]
+    fn test_run_sty() {
+        let cases = [
+            (Style::Normal, Style::Normal, Style::Normal),
+            (Style::Normal, Style::Bold, Style::Bold),
+            (Style::Bold, Style::Normal, Style::Bold),
+            (Style::Italic, Style::Bold, Style::BoldItalic),
+            (Style::Bold, Style::Italic, Style::BoldItalic),
+            (Style::Bold, Style::Bold, Style::Bold),
+        ];
+        for (par_sty, sty, expected) in cases {
+            assert_eq!(
+                run_sty(par_sty, sty),
+                expected,
+                "Failed to match: {:?}",
+                (par_sty, sty)
+            );
+        }
+    }
+
+    #[test]
+    fn test_validate_markup() {
+        let mut doc = Doc::default();
+        doc.add_par(par("**Entirely bold**"));
+        doc.add_par(par(r"Partly **bold**, *italic* and 5 \* 5"));
+        validate_markup(&doc).unwrap();
+
+        doc.add_par(par("**unclosed"));
+        let err = validate_markup(&doc).unwrap_err().to_string();
+        assert!(err.starts_with("paragraph 2: unclosed markup"), "{err}");
+    }
+}
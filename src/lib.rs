@@ -0,0 +1,50 @@
+//! Addresses of officials and facilities, and the mailings sent to them.
+//!
+//! The parser, USPS client and models are usable without the `adr` binary,
+//! such as `Prsr::new().prs_adrs(&lnes)` then `Usps::new(cli).standardize(&mut adr)`.
+
+#[macro_use]
+extern crate lazy_static;
+
+pub mod acs;
+pub mod audit;
+pub mod cli;
+pub mod committee;
+pub mod coverage;
+pub mod diff;
+pub mod envelope;
+pub mod executive;
+pub mod export;
+pub mod facility;
+pub mod fixture;
+pub mod font;
+pub mod house;
+pub mod imb;
+pub mod io;
+pub mod label;
+pub mod language;
+pub mod letter;
+pub mod mailing;
+pub mod manifest;
+pub mod manual;
+pub mod military;
+pub mod models;
+pub mod money;
+pub mod nasa;
+pub mod observer;
+pub mod office_pdf;
+pub mod packing;
+pub mod pdfa;
+pub mod postage_statement;
+pub mod prsr;
+pub mod qr;
+pub mod refresh;
+pub mod report;
+pub mod senate;
+pub mod state;
+pub mod suppression;
+#[cfg(test)]
+pub mod test_support;
+pub mod tracking;
+pub mod usps;
+pub mod verify;
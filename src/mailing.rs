@@ -1,8 +1,17 @@
-use crate::core::*;
+use crate::audit::*;
 use crate::envelope::*;
+use crate::imb::*;
+use crate::io::*;
+use crate::label::*;
+use crate::language::*;
+use crate::letter::*;
+use crate::manifest::*;
 use crate::models::*;
+use crate::money::*;
+use crate::packing::*;
+use crate::pdfa::*;
 use crate::postage_statement::*;
-use crate::prsr::*;
+use crate::suppression::*;
 use crate::usps::*;
 use anyhow::{anyhow, Result};
 use chrono::Local;
@@ -10,25 +19,31 @@ use chrono::NaiveDate;
 use itertools::*;
 use pdf_doc::*;
 use serde::{Deserialize, Serialize};
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashSet};
 use std::fmt;
 use std::fs;
-use std::fs::File;
-use std::io::{BufReader, BufWriter, Cursor};
+use std::ops::Range;
 use std::path::Path;
-use std::path::PathBuf;
+use tracing::{debug, info, warn};
 use TraySize::*;
 
-const FLE_PTH: &str = "mailing.json";
-const FLE_PTH_CFG: &str = "mailing_cfg.json";
+pub const FLE_PTH: &str = "mailing.json";
+pub const FLE_PTH_CFG: &str = "mailing_cfg.json";
+/// Differences between two mailings, with a text table beside it.
+pub const FLE_PTH_DIFF: &str = "mailing_diff.json";
+/// Piece-level manifest of a mailing directory, for the mail service provider.
+pub const FLE_NAME_MANIFEST_CSV: &str = "manifest.csv";
 const FLE_PTH_LTR: &str = "letter-template.json";
+const FLE_PTH_LTR_MD: &str = "letter-template.md";
 
-const PRC_FIVE_DIG: f64 = 0.173; // PS Form 3602-N
-const PRC_MIXED_AADC: f64 = 0.208; // PS Form 3602-N
+const PRC_FIVE_DIG: Money = Money::from_mills(173); // PS Form 3602-N
+const PRC_THREE_DIG: Money = Money::from_mills(189); // PS Form 3602-N
+const PRC_AADC: Money = Money::from_mills(195); // PS Form 3602-N
+const PRC_MIXED_AADC: Money = Money::from_mills(208); // PS Form 3602-N
 
 lazy_static! {
     /// A mailing configuration.
-    pub static ref CFG: MailingCfg = read_from_file::<MailingCfg>(FLE_PTH_CFG).unwrap();
+    pub static ref CFG: MailingCfg = MailingCfg::load(FLE_PTH_CFG).unwrap();
 }
 
 // TODO: ADD "Return Service Requested" TO ENVELOPE.
@@ -45,10 +60,36 @@ pub struct Mailing {
     pub tray_1ft_cnt: u8,
     pub tray_2ft_cnt: u8,
     pub five_dig_cnt: u16,
+    #[serde(default)]
+    pub three_dig_cnt: u16,
+    #[serde(default)]
+    pub aadc_cnt: u16,
     pub mixed_aadc_cnt: u16,
-    pub postage_subtotal_five_dig: f64,
-    pub postage_subtotal_mixed_aadc: f64,
-    pub part_a_subtotal: f64,
+    /// Postage of a price category, exact to the mill.
+    pub postage_subtotal_five_dig: Money,
+    #[serde(default)]
+    pub postage_subtotal_three_dig: Money,
+    #[serde(default)]
+    pub postage_subtotal_aadc: Money,
+    pub postage_subtotal_mixed_aadc: Money,
+    /// Postage of the price categories, rounded to the cent.
+    pub part_a_subtotal: Money,
+    /// Foreign mailpieces mailed international First-Class.
+    ///
+    /// Not presorted and without barcodes.
+    #[serde(default)]
+    pub intl: Vec<Mailpiece>,
+    #[serde(default)]
+    pub intl_cnt: u16,
+    /// Postage statement id, reserved once per mailing so reruns reuse it.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub statement_id: Option<u16>,
+}
+
+impl Default for Mailing {
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
 impl Mailing {
@@ -61,159 +102,567 @@ impl Mailing {
             tray_1ft_cnt: 0,
             tray_2ft_cnt: 0,
             five_dig_cnt: 0,
+            three_dig_cnt: 0,
+            aadc_cnt: 0,
             mixed_aadc_cnt: 0,
-            postage_subtotal_five_dig: 0.0,
-            postage_subtotal_mixed_aadc: 0.0,
-            part_a_subtotal: 0.0,
+            postage_subtotal_five_dig: Money::default(),
+            postage_subtotal_three_dig: Money::default(),
+            postage_subtotal_aadc: Money::default(),
+            postage_subtotal_mixed_aadc: Money::default(),
+            part_a_subtotal: Money::default(),
+            intl: Vec::new(),
+            intl_cnt: 0,
+            statement_id: None,
         }
     }
 
-    pub async fn load(pers: &mut [Person]) -> Result<Mailing> {
-        // Read file from disk.
-        let mut mailing = match read_from_file::<Mailing>(FLE_PTH) {
-            Ok(mailing_from_disk) => mailing_from_disk,
-            Err(_) => {
-                let mut mailing = Mailing::new();
-
-                // Create mailpieces for each person.
-                let adr_cnt = pers.iter().map(|p| p.adr_len()).sum::<usize>();
-                let mut mailpieces = Vec::with_capacity(adr_cnt);
-                for per in pers.iter() {
-                    if let Some(adrs) = &per.adrs {
-                        for adr in adrs {
-                            // See guidelines.
-                            // https://about.usps.com/publications/pub28/28c2_007.htm
-                            let mp = Mailpiece {
-                                name: per.name.clone(),
-                                title1: string_to_opt(per.title1.clone()),
-                                title2: string_to_opt(per.title2.clone()),
-                                address1: adr.address1.clone(),
-                                city: adr.city.clone(),
-                                state: adr.state.clone(),
-                                zip5: adr.zip5,
-                                zip4: adr.zip4,
-                                delivery_point: adr.delivery_point.clone(),
-                                ..Default::default()
-                            };
-                            mailpieces.push(mp);
-                        }
-                    } else {
-                        return Err(anyhow!("missing address for {}", per));
-                    }
-                }
+    /// Reserves the postage statement id of the mailing, unless already reserved.
+    ///
+    /// Returns whether an id was reserved, to be committed with the mailing.
+    pub fn reserve_statement_id(&mut self, id_alloc: &mut IdAllocator) -> Result<bool> {
+        if self.statement_id.is_some() {
+            return Ok(false);
+        }
+        self.statement_id = Some(id_alloc.reserve_statement_id()?);
+        Ok(true)
+    }
 
-                // Set mailpiece count.
-                mailing.mailpiece_cnt = mailpieces.len() as u16;
+    /// Checks that tray and piece counts and postage totals agree.
+    ///
+    /// All violations are reported together.
+    pub fn validate(&self) -> Result<()> {
+        let mut errs: Vec<String> = Vec::new();
+
+        // Pieces.
+        let mp_cnt = self.trays.iter().map(|o| o.mailpieces.len()).sum::<usize>();
+        if mp_cnt != self.mailpiece_cnt as usize {
+            errs.push(format!(
+                "mailpiece_cnt {} != {mp_cnt} tray pieces",
+                self.mailpiece_cnt
+            ));
+        }
+        if self.intl.len() != self.intl_cnt as usize {
+            errs.push(format!(
+                "intl_cnt {} != {} intl pieces",
+                self.intl_cnt,
+                self.intl.len()
+            ));
+        }
 
-                // Sort by zip code for id generation.
-                mailpieces.sort_unstable_by_key(|o| format!("{:05}{:04}", o.zip5, o.zip4));
+        // Trays.
+        let tray_cnt = self.tray_1ft_cnt as usize + self.tray_2ft_cnt as usize;
+        if tray_cnt != self.trays.len() {
+            errs.push(format!(
+                "tray_1ft_cnt {} + tray_2ft_cnt {} != {} trays",
+                self.tray_1ft_cnt,
+                self.tray_2ft_cnt,
+                self.trays.len()
+            ));
+        }
+        for tray in self.trays.iter() {
+            if tray.mailpieces.len() > tray.size.cap() {
+                errs.push(format!(
+                    "tray {} has {} pieces over capacity {}",
+                    tray.name,
+                    tray.mailpieces.len(),
+                    tray.size.cap()
+                ));
+            }
+        }
 
-                // Calculate current id based on the previous mailing
-                // and current mailing. Each envelope gets a unique id.
-                // Id is used in the barcode.
-                let mut base_id = CFG.last_mailpiece_id + 1;
-                for (idx, mp) in mailpieces.iter_mut().enumerate() {
-                    mp.id = base_id + idx as u32;
-                }
+        // Mailpieces.
+        let mut ids = HashSet::with_capacity(mp_cnt);
+        for mp in self.trays.iter().flat_map(|o| o.mailpieces.iter()) {
+            if !ids.insert(mp.id) {
+                errs.push(format!("duplicate mailpiece id {}", mp.id));
+            }
+        }
+        for mp in self
+            .trays
+            .iter()
+            .flat_map(|o| o.mailpieces.iter())
+            .chain(self.intl.iter())
+        {
+            if !mp.barcode.is_empty() && mp.barcode.len() != IMB_BAR_CNT {
+                errs.push(format!(
+                    "mailpiece {} barcode has {} bars",
+                    mp.id,
+                    mp.barcode.len()
+                ));
+            }
+        }
 
-                // Pre-sort for USPS discount.
-                mailing.trays = presort_mailpieces(mailpieces);
-                eprintln!("{} trays", mailing.trays.len());
+        // Price categories.
+        let mut priced_cnt = 0;
+        let mut part_a_subtotal = Money::default();
+        for (barcode_id, name, cnt, prc) in self.price_categories() {
+            let tray_cnt = self.barcode_id_cnt(barcode_id);
+            if tray_cnt != cnt as usize {
+                errs.push(format!("{name} {cnt} != {tray_cnt} tray pieces"));
+            }
+            priced_cnt += tray_cnt;
+            part_a_subtotal = part_a_subtotal + prc.times(tray_cnt as u64);
+        }
+        let part_a_subtotal = part_a_subtotal.round_cents();
+        if priced_cnt != mp_cnt {
+            errs.push(format!(
+                "{} tray pieces without a price category",
+                mp_cnt - priced_cnt
+            ));
+        }
 
-                // Determine tray counts.
-                mailing.tray_1ft_cnt = mailing
-                    .trays
-                    .iter()
-                    .filter(|o| o.size == TraySize::OneFoot)
-                    .count() as u8;
-                mailing.tray_2ft_cnt = mailing
-                    .trays
-                    .iter()
-                    .filter(|o| o.size == TraySize::TwoFoot)
-                    .count() as u8;
+        // Postage.
+        if self.part_a_subtotal != part_a_subtotal {
+            errs.push(format!(
+                "part_a_subtotal {} != {part_a_subtotal} recomputed",
+                self.part_a_subtotal
+            ));
+        }
 
-                // Determine price categories.
-                mailing.five_dig_cnt = mailing
-                    .trays
-                    .iter()
-                    .filter(|o| o.barcode_id == BarcodeId::FiveDigit)
-                    .map(|o| o.mailpieces.len())
-                    .sum::<usize>() as u16;
-                mailing.mixed_aadc_cnt = mailing
-                    .trays
-                    .iter()
-                    .filter(|o| o.barcode_id == BarcodeId::MixedAadc)
-                    .map(|o| o.mailpieces.len())
-                    .sum::<usize>() as u16;
+        if !errs.is_empty() {
+            return Err(anyhow!(
+                "invalid mailing {}: {}",
+                self.name,
+                errs.join("; ")
+            ));
+        }
+        Ok(())
+    }
+
+    /// Price categories with their count field name, count and price.
+    pub fn price_categories(&self) -> [(BarcodeId, &'static str, u16, Money); 4] {
+        [
+            (
+                BarcodeId::FiveDigit,
+                "five_dig_cnt",
+                self.five_dig_cnt,
+                PRC_FIVE_DIG,
+            ),
+            (
+                BarcodeId::ThreeDigit,
+                "three_dig_cnt",
+                self.three_dig_cnt,
+                PRC_THREE_DIG,
+            ),
+            (BarcodeId::Aadc, "aadc_cnt", self.aadc_cnt, PRC_AADC),
+            (
+                BarcodeId::MixedAadc,
+                "mixed_aadc_cnt",
+                self.mixed_aadc_cnt,
+                PRC_MIXED_AADC,
+            ),
+        ]
+    }
+
+    /// Number of tray mailpieces of a barcode identifier.
+    pub fn barcode_id_cnt(&self, barcode_id: BarcodeId) -> usize {
+        self.trays
+            .iter()
+            .filter(|o| o.barcode_id == barcode_id)
+            .map(|o| o.mailpieces.len())
+            .sum::<usize>()
+    }
+
+    /// Writes a csv of tray mailpieces in tray and piece order, one row per piece.
+    ///
+    /// International pieces have no barcode and are omitted.
+    pub fn write_manifest(&self, pth: &Path) -> Result<()> {
+        debug!("Writing file: {}", pth.display());
+        let mut wtr = csv::Writer::from_path(pth)?;
+        for tray in self.trays.iter() {
+            for mp in tray.mailpieces.iter() {
+                wtr.serialize(ManifestRow::new(tray, mp))?;
+            }
+        }
+        wtr.flush()?;
+        Ok(())
+    }
+
+    pub async fn load(pers: &mut [Person]) -> Result<Mailing> {
+        // Read file from disk.
+        // A corrupted mailing is an error rather than regenerated.
+        let mut mailing = match read_from_file_checked::<Mailing>(FLE_PTH) {
+            Ok(mut mailing_from_disk) => {
+                // Older files kept the total in dollars, unrounded.
+                mailing_from_disk.part_a_subtotal = mailing_from_disk.part_a_subtotal.round_cents();
+                mailing_from_disk.validate()?;
+
+                // Older mailings reserve their statement id once.
+                if CFG.ps.enabled {
+                    let mut id_alloc = IdAllocator::load(FLE_PTH_CFG)?;
+                    if mailing_from_disk.reserve_statement_id(&mut id_alloc)? {
+                        write_to_file_checked(&mailing_from_disk, FLE_PTH)?;
+                        id_alloc.commit()?;
+                    }
+                }
+                mailing_from_disk
+            }
+            Err(err) if data_pth(FLE_PTH).exists() => return Err(err),
+            Err(_) => {
+                // Skip persons asking not to be mailed, and apply language overrides.
+                let overrides = LanguageOverrides::load(LANGUAGE_PTH)?;
+                let suppressions = SuppressionList::load(SUPPRESSION_PTH)?;
+                let (mailed, suppressed) = mailed_persons(pers, &overrides, &suppressions);
+                info!("{} mailpieces suppressed", suppressed.len());
+                if !suppressed.is_empty() {
+                    summary_add("Suppressed mailpieces", suppressed, false);
+                }
 
-                // Calculate prices.
-                mailing.postage_subtotal_five_dig = mailing.five_dig_cnt as f64 * PRC_FIVE_DIG;
-                mailing.postage_subtotal_mixed_aadc = mailing.mixed_aadc_cnt as f64 * PRC_MIXED_AADC;
-                mailing.part_a_subtotal = mailing.postage_subtotal_five_dig + mailing.postage_subtotal_mixed_aadc;
+                // Reserve ids after the previous mailing.
+                // Each envelope gets a unique id across mailings.
+                let mut id_alloc = IdAllocator::load(FLE_PTH_CFG)?;
+                let mut mailing = Mailing::build(&mailed, &id_alloc.cfg)?;
+                id_alloc.reserve_mailpiece_ids(mailing.mailpiece_cnt as u32)?;
+                if CFG.ps.enabled {
+                    mailing.reserve_statement_id(&mut id_alloc)?;
+                }
+                info!("{} trays", mailing.trays.len());
+                summary_add(
+                    "Mail classes",
+                    vec![
+                        format!("{} domestic marketing", mailing.mailpiece_cnt),
+                        format!("{} international First-Class", mailing.intl_cnt),
+                    ],
+                    false,
+                );
+
+                // Upgrade FiveDigit mailpieces to zip4 for automation discounts.
+                let upgrade =
+                    upgrade_zip4(&mut mailing.trays, pers, &Usps::default(), ZIP4_BUDGET).await;
+                info!("{upgrade}");
+                let mut upgrade_lnes = vec![upgrade.to_string()];
+                if upgrade.upgraded != 0 {
+                    let cnt = write_zip4_files(&AUDIT_PER_PTHS, pers)?;
+                    upgrade_lnes.push(format!("{cnt} person file addresses upgraded"));
+                }
+                summary_add("Zip4 upgrade", upgrade_lnes, false);
+                summary_add(
+                    "Price categories",
+                    mailing
+                        .price_categories()
+                        .iter()
+                        .map(|(barcode_id, _, cnt, _)| format!("{cnt} {barcode_id:?}"))
+                        .collect(),
+                    false,
+                );
 
                 // Write file to disk.
-                write_to_file(&mailing, FLE_PTH)?;
+                mailing.validate()?;
+                write_to_file_checked(&mailing, FLE_PTH)?;
+
+                // Persist reserved ids once the mailing is written.
+                id_alloc.commit()?;
 
                 mailing
             }
         };
 
-        // Create the directory and any necessary parent directories
-        let mut pth = PathBuf::from("mailings");
-        pth.push(&mailing.name);
-        if pth.exists() {
-            // Delete any previous directory.
-            fs::remove_dir_all(&pth)?;
+        // Add barcodes to mailpieces.
+        let is_fetch = mailing.barcode_todo() != 0;
+        mailing.fetch_barcodes(&USPS_CLI).await?;
+        if is_fetch {
+            // Save intermediate.
+            // Write file to disk.
+            mailing.validate()?;
+            write_to_file_checked(&mailing, FLE_PTH)?;
+        }
+
+        // Create envelopes, letters, labels and lists.
+        let pth = mailings_dir().join(&mailing.name);
+        mailing.generate_artifacts(&pth).await?;
+
+        Ok(mailing)
+    }
+
+    /// Builds a presorted and priced mailing of persons, without files or network.
+    ///
+    /// Mailpiece ids follow the last id of the config, in zip order.
+    /// Foreign addresses are mailed international First-Class.
+    pub fn build(pers: &[Person], cfg: &MailingCfg) -> Result<Mailing> {
+        let mut mailing = Mailing::new();
+        cfg.check_roles(pers)?;
+
+        // Create mailpieces for each person.
+        let adr_cnt = pers.iter().map(|p| p.adr_len()).sum::<usize>();
+        let mut mailpieces = Vec::with_capacity(adr_cnt);
+        let overrides = LanguageOverrides::default();
+        for per in pers.iter() {
+            if let Some(adrs) = &per.adrs {
+                for adr in adrs {
+                    let mut mp = new_mailpiece(per, adr);
+                    mp.language = cfg.languages.language_of(&overrides, per, adr);
+                    // Keep a suite on the delivery line when it fits, as the USPS recommends.
+                    let address1 = adr.merged_address1(cfg.address1_max.unwrap_or(ADDRESS1_MAX));
+                    if address1 != mp.address1 {
+                        mp.address1 = address1;
+                        mp.address2 = None;
+                    }
+                    if mp.is_foreign() {
+                        mailing.intl.push(mp);
+                    } else {
+                        mailpieces.push(mp);
+                    }
+                }
+            } else {
+                return Err(anyhow!("missing address for {}", per));
+            }
         }
-        fs::create_dir_all(&pth)?;
 
-        // // Find longest title1.
-        // pers.sort_unstable_by_key(|k| k.title1.len());
-        // eprintln!("title1:{}", pers[pers.len() - 1].title1);
+        // Set mailpiece counts.
+        mailing.mailpiece_cnt = mailpieces.len() as u16;
+        mailing.intl_cnt = mailing.intl.len() as u16;
 
-        // // Find longest address1.
-        // mailpieces.sort_unstable_by_key(|k| k.address1.len());
-        // eprintln!("address1:{}", mailpieces[mailpieces.len() - 1].address1);
+        // Sort by zip code for id generation.
+        mailpieces.sort_unstable_by_key(|o| format!("{:05}{:04}", o.zip5, o.zip4));
+
+        // Id is used in the barcode.
+        let ids = cfg.mailpiece_ids(mailpieces.len() as u32)?;
+        for (mp, id) in mailpieces.iter_mut().zip(ids) {
+            mp.id = id;
+        }
 
-        let mps_len = mailing
+        // Pre-sort for USPS discount.
+        mailing.trays = presort_mailpieces(mailpieces);
+
+        // Determine tray counts.
+        mailing.tray_1ft_cnt = mailing
             .trays
             .iter()
-            .map(|o| o.mailpieces.len())
-            .sum::<usize>() as f64;
+            .filter(|o| o.size == TraySize::OneFoot)
+            .count() as u8;
+        mailing.tray_2ft_cnt = mailing
+            .trays
+            .iter()
+            .filter(|o| o.size == TraySize::TwoFoot)
+            .count() as u8;
+
+        // Determine price categories.
+        mailing.five_dig_cnt = mailing.barcode_id_cnt(BarcodeId::FiveDigit) as u16;
+        mailing.three_dig_cnt = mailing.barcode_id_cnt(BarcodeId::ThreeDigit) as u16;
+        mailing.aadc_cnt = mailing.barcode_id_cnt(BarcodeId::Aadc) as u16;
+        mailing.mixed_aadc_cnt = mailing.barcode_id_cnt(BarcodeId::MixedAadc) as u16;
+
+        // Calculate prices.
+        // Subtotals are exact to the mill; the total rounds to the cent.
+        mailing.postage_subtotal_five_dig = PRC_FIVE_DIG.times(mailing.five_dig_cnt as u64);
+        mailing.postage_subtotal_three_dig = PRC_THREE_DIG.times(mailing.three_dig_cnt as u64);
+        mailing.postage_subtotal_aadc = PRC_AADC.times(mailing.aadc_cnt as u64);
+        mailing.postage_subtotal_mixed_aadc = PRC_MIXED_AADC.times(mailing.mixed_aadc_cnt as u64);
+        mailing.part_a_subtotal = (mailing.postage_subtotal_five_dig
+            + mailing.postage_subtotal_three_dig
+            + mailing.postage_subtotal_aadc
+            + mailing.postage_subtotal_mixed_aadc)
+            .round_cents();
 
-        // Add barcodes to mailpieces.
-        // Mail tray barcode_id is used in the barcode.
-        let mut may_write = false;
-        let mut cur_cnt: usize = 0;
-        for mail_tray in mailing.trays.iter_mut() {
-            if mail_tray.add_barcodes(cur_cnt, mps_len).await? {
-                may_write = true;
+        Ok(mailing)
+    }
+
+    /// Number of tray mailpieces without a barcode.
+    pub fn barcode_todo(&self) -> usize {
+        self.trays
+            .iter()
+            .flat_map(|o| o.mailpieces.iter())
+            .filter(|mp| needs_barcode(mp))
+            .count()
+    }
+
+    /// Adds barcodes to the tray mailpieces without one.
+    ///
+    /// Mail tray barcode_id is used in the barcode.
+    pub async fn fetch_barcodes(&mut self, usps: &UspsClient) -> Result<()> {
+        let mut progress = Progress::new(Some(self.barcode_todo()));
+        let mut res = Ok(());
+        for mail_tray in self.trays.iter_mut() {
+            if let Err(err) = mail_tray.add_barcodes(&mut progress, usps).await {
+                res = Err(err);
+                break;
             }
-            cur_cnt += mail_tray.mailpieces.len();
         }
-        if may_write {
-            // Save intermediate.
-            // Write file to disk.
-            write_to_file(&mailing, FLE_PTH)?;
+        // Keep encodings of a failed run for the next one.
+        BarcodeCache::save_dirty(&BARCODE_CACHE, FLE_PTH_BARCODE_CACHE)?;
+        res
+    }
+
+    /// Creates the mailing directory, replacing a previous one.
+    ///
+    /// Writes the manifest, envelopes, letters, placards, packing plan, box labels and postage statement.
+    pub async fn generate_artifacts(&self, out_dir: &Path) -> Result<()> {
+        // Create the directory and any necessary parent directories
+        if out_dir.exists() {
+            // Delete any previous directory.
+            fs::remove_dir_all(out_dir)?;
         }
+        fs::create_dir_all(out_dir)?;
+
+        let mps_len = self.trays.iter().map(|o| o.mailpieces.len()).sum::<usize>();
+
+        // List pieces with their barcodes for the mail service provider.
+        self.write_manifest(&out_dir.join(FLE_NAME_MANIFEST_CSV))?;
+
+        // Read letter templates from disk.
+        let tmpls = LetterTemplates::load(letter_template()?, &CFG.languages.templates)?;
+        let mut manifest = Manifest::new(&self.name);
 
         // Create envelopes and letters.
-        let mut cur_cnt: usize = 0;
-        for mail_tray in mailing.trays.iter() {
-            mail_tray.create_envelopes_letters(cur_cnt, mps_len, &pth.clone())?;
-            cur_cnt += mail_tray.mailpieces.len();
+        let mut progress = Progress::new(Some(mps_len));
+        for mail_tray in self.trays.iter() {
+            mail_tray.create_envelopes_letters(&mut progress, out_dir, &tmpls, &mut manifest)?;
+            mail_tray.create_placard(&CFG, out_dir)?;
         }
 
-        // // Fill in postage statement pdf.
-        // let mut ps = PostageStatement::load_new().await?;
-        // ps.fill_and_save(&mailing, pth.clone())?;
+        // Create envelopes and letters of international First-Class mail.
+        if !self.intl.is_empty() {
+            let tray = MailTray {
+                name: "INTL".into(),
+                size: OneFoot,
+                barcode_id: BarcodeId::Default,
+                mailpieces: self.intl.clone(),
+            };
+            let mut progress = Progress::new(Some(self.intl.len()));
+            tray.create_envelopes_letters(&mut progress, out_dir, &tmpls, &mut manifest)?;
+        }
 
-        // eprintln!("{} mailpieces", mailing.mailpieces.len());
+        // Report pieces by letter language.
+        manifest.write(out_dir)?;
+        summary_add("Letter languages", manifest.lnes(), manifest.fallbacks != 0);
 
-        Ok(mailing)
+        // Plan boxes for transport to the BMEU.
+        let plan = packing_plan(self, &CFG.packing, CFG.duplex)?;
+        write_packing_plan(&plan, out_dir)?;
+        let font_pth = conformance_font_pth(CFG.conformance, CFG.font_pth.as_deref())?;
+        create_box_labels(&plan, out_dir, font_pth, CFG.conformance)?;
+        info!("{} boxes", plan.boxes.len());
+
+        // Fill in postage statement pdf.
+        if CFG.ps.enabled {
+            create_postage_statement(self, out_dir.to_path_buf()).await?;
+        }
+
+        Ok(())
     }
 }
 
+/// A row of the piece-level manifest.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Eq)]
+pub struct ManifestRow {
+    pub id: u32,
+    pub tray: String,
+    pub tray_size: TraySize,
+    /// Barcode identifier, such as `20`.
+    pub barcode_id: String,
+    /// Presort level, such as `FiveDigit`.
+    pub presort: String,
+    /// Content line of the tray label, such as `LTRS 5D 47547`.
+    pub tray_label: String,
+    pub name: String,
+    pub address1: String,
+    pub address2: String,
+    pub city: String,
+    pub state: String,
+    /// Zero-padded, such as `02109`.
+    pub zip5: String,
+    /// Zero-padded, empty when unknown.
+    pub zip4: String,
+    pub delivery_point: String,
+    /// The 65 bars of the IMb.
+    pub imb: String,
+}
+
+impl ManifestRow {
+    pub fn new(tray: &MailTray, mp: &Mailpiece) -> Self {
+        Self {
+            id: mp.id,
+            tray: tray.name.clone(),
+            tray_size: tray.size.clone(),
+            barcode_id: tray.barcode_id.to_string(),
+            presort: format!("{:?}", tray.barcode_id),
+            tray_label: tray.label_line(),
+            name: mp.name.clone(),
+            address1: mp.address1.clone(),
+            address2: mp.address2.clone().unwrap_or_default(),
+            city: mp.city.clone(),
+            state: mp.state.clone(),
+            zip5: format!("{:05}", mp.zip5),
+            zip4: if mp.zip4 == 0 {
+                String::new()
+            } else {
+                format!("{:04}", mp.zip4)
+            },
+            delivery_point: mp.delivery_point.clone().unwrap_or_default(),
+            imb: mp.barcode.clone(),
+        }
+    }
+}
+
+/// Persons to mail, without suppressed addresses and with overridden languages.
+///
+/// Returns the suppressed pieces, such as `Jane Doe 20510`.
+pub fn mailed_persons(
+    pers: &[Person],
+    overrides: &LanguageOverrides,
+    suppressions: &SuppressionList,
+) -> (Vec<Person>, Vec<String>) {
+    let mut suppressed = Vec::new();
+    let mailed = pers
+        .iter()
+        .map(|per| {
+            let mut per = per.clone();
+            if let Some(lang) = overrides.persons.get(&per.name) {
+                per.language = Some(lang.clone());
+            }
+            if let Some(adrs) = per.adrs.as_mut() {
+                adrs.retain(|adr| {
+                    let is_suppressed = suppressions.is_suppressed(&per.name, adr);
+                    if is_suppressed {
+                        suppressed.push(format!("{} {:05}", per.name, adr.zip5));
+                    }
+                    !is_suppressed
+                });
+            }
+            per
+        })
+        .collect();
+    (mailed, suppressed)
+}
+
+/// Whether a mailpiece was addressed to an address, with address2 merged or not.
+pub fn is_mailed_adr(adr: &Address, mp: &Mailpiece) -> bool {
+    adr.zip5 == mp.zip5
+        && (adr.address1 == mp.address1 || adr.merged_address1(usize::MAX) == mp.address1)
+}
+
+/// Creates a mailpiece for an address of a person.
+///
+/// See guidelines.
+/// https://about.usps.com/publications/pub28/28c2_007.htm
+pub fn new_mailpiece(per: &Person, adr: &Address) -> Mailpiece {
+    let mut mp = Mailpiece {
+        name: per.name.clone(),
+        title1: string_to_opt(per.title1.clone()),
+        title2: string_to_opt(per.title2.clone()),
+        firm: adr.firm.clone(),
+        address1: adr.address1.clone(),
+        address2: adr.address2.clone(),
+        city: adr.city.clone(),
+        state: adr.state.clone(),
+        zip5: adr.zip5,
+        zip4: adr.zip4,
+        delivery_point: adr.delivery_point.clone(),
+        language: per.language.clone(),
+        ..Default::default()
+    };
+    if adr.is_foreign() {
+        mp.country.clone_from(&adr.country);
+        mp.postal_code.clone_from(&adr.postal_code);
+    }
+    // Unstandardized addresses are routed by zip5 alone.
+    if !adr.standardized {
+        mp.zip4 = 0;
+        mp.delivery_point = None;
+    }
+    mp
+}
+
 /// Pre-sort mail.
 ///
 /// Determine barcode_id based on sort level.
@@ -223,94 +672,295 @@ pub fn presort_mailpieces(mut mailpieces: Vec<Mailpiece>) -> Vec<MailTray> {
     // Sort for chunking.
     mailpieces.sort_unstable_by_key(|o| o.zip5);
 
-    let mut mixed_aadcs = Vec::with_capacity(mailpieces.len());
+    let mut three_digs = Vec::with_capacity(mailpieces.len());
     for (key, chunk) in &mailpieces.into_iter().chunk_by(|mp| mp.zip5) {
-        pub const PRESORT_MIN: usize = 200;
         let grp: Vec<Mailpiece> = chunk.collect();
-        if grp.len() >= PRESORT_MIN {
-            eprintln!("{key:05} {}", grp.len());
+        if grp.len() >= PRESORT_MIN_FIVE_DIG {
+            debug!("{key:05} {}", grp.len());
             ret.extend(segment_trays(BarcodeId::FiveDigit, grp));
+        } else {
+            three_digs.extend(grp);
+        }
+    }
+
+    // Remaining pieces stay sorted by zip5, so zip3 groups are contiguous.
+    let mut aadcs: BTreeMap<&str, Vec<Mailpiece>> = BTreeMap::new();
+    let mut mixed_aadcs = Vec::with_capacity(three_digs.len());
+    for (key, chunk) in &three_digs.into_iter().chunk_by(|mp| mp.zip5 / 100) {
+        let grp: Vec<Mailpiece> = chunk.collect();
+        if grp.len() >= PRESORT_MIN_THREE_DIG {
+            debug!("{key:03} {}", grp.len());
+            ret.extend(segment_trays(BarcodeId::ThreeDigit, grp));
+        } else if let Some(aadc) = aadc_of(key) {
+            aadcs.entry(aadc).or_default().extend(grp);
         } else {
             mixed_aadcs.extend(grp);
         }
     }
 
-    eprintln!("mixed aadc {}", mixed_aadcs.len());
-    ret.extend(segment_trays(BarcodeId::MixedAadc, mixed_aadcs));
+    for (aadc, grp) in aadcs {
+        if grp.len() >= PRESORT_MIN_AADC {
+            debug!("{aadc} {}", grp.len());
+            ret.extend(segment_trays(BarcodeId::Aadc, grp));
+        } else {
+            mixed_aadcs.extend(grp);
+        }
+    }
+
+    debug!("mixed aadc {}", mixed_aadcs.len());
+    if !mixed_aadcs.is_empty() {
+        mixed_aadcs.sort_unstable_by_key(|o| o.zip5);
+        ret.extend(segment_trays(BarcodeId::MixedAadc, mixed_aadcs));
+    }
 
     // Set tray names.
-    let mut chr = 'A' as u32;
     for (idx, tray) in ret.iter_mut().enumerate() {
-        tray.name = char::from_u32(chr).expect("Invalid character").into();
-        chr += 1;
+        tray.name = tray_name(idx);
     }
 
     ret
 }
 
+/// Minimum pieces of a zip5 for a FiveDigit tray.
+pub const PRESORT_MIN_FIVE_DIG: usize = 200;
+
+/// Minimum pieces of a zip3 for a ThreeDigit tray of automation letters.
+pub const PRESORT_MIN_THREE_DIG: usize = 150;
+
+/// Minimum pieces of an AADC for an Aadc tray of automation letters.
+pub const PRESORT_MIN_AADC: usize = 150;
+
+/// Excerpt of labeling list L801, zip3 ranges to their AADC.
+///
+/// Zip3s outside the excerpt are presorted as mixed AADC.
+pub const L801: [(u32, u32, &str); 10] = [
+    (120, 123, "AADC ALBANY NY 120"),
+    (128, 129, "AADC ALBANY NY 120"),
+    (206, 212, "AADC BALTIMORE MD 210"),
+    (214, 219, "AADC BALTIMORE MD 210"),
+    (220, 223, "AADC NORTHERN VA 220"),
+    (350, 352, "AADC BIRMINGHAM AL 350"),
+    (354, 359, "AADC BIRMINGHAM AL 350"),
+    (365, 366, "AADC MOBILE AL 365"),
+    (750, 753, "AADC DALLAS TX 750"),
+    (900, 908, "AADC LOS ANGELES CA 900"),
+];
+
+/// AADC of a zip3, from the L801 excerpt.
+pub fn aadc_of(zip3: u32) -> Option<&'static str> {
+    L801.iter()
+        .find(|(start, end, _)| (*start..=*end).contains(&zip3))
+        .map(|(_, _, aadc)| *aadc)
+}
+
+/// USPS call budget of the zip4 upgrade pass.
+pub const ZIP4_BUDGET: CallBudget = CallBudget {
+    max_calls: 500,
+    max_failures: 5,
+};
+
+/// Counts of a zip4 upgrade pass.
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+pub struct Zip4Upgrade {
+    pub eligible: usize,
+    pub tried: usize,
+    pub upgraded: usize,
+}
+impl fmt::Display for Zip4Upgrade {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let pct = if self.eligible == 0 {
+            0.0
+        } else {
+            (self.upgraded as f64 / self.eligible as f64) * 100.0
+        };
+        write!(
+            f,
+            "{} of {} FiveDigit mailpieces upgraded to zip4 ({:.1}%), {} tried",
+            self.upgraded, self.eligible, pct, self.tried
+        )
+    }
+}
+
+/// Re-standardizes FiveDigit mailpieces lacking a zip4.
+///
+/// The USPS usually returns the zip4 when given the cleaned street.
+/// Upgrades are written to the mailpiece and the source address of the person;
+/// `write_zip4_files` writes them to the person files.
+/// Mailpieces with a zip4 are skipped, so the pass is idempotent.
+pub async fn upgrade_zip4<S: Standardizer>(
+    trays: &mut [MailTray],
+    pers: &mut [Person],
+    stdzr: &S,
+    budget: CallBudget,
+) -> Zip4Upgrade {
+    let mut upgrade = Zip4Upgrade::default();
+    let mut failures = 0;
+
+    for mp in trays
+        .iter_mut()
+        .filter(|tray| tray.barcode_id == BarcodeId::FiveDigit)
+        .flat_map(|tray| tray.mailpieces.iter_mut())
+        .filter(|mp| mp.zip4 == 0)
+    {
+        upgrade.eligible += 1;
+        if upgrade.tried == budget.max_calls || failures == budget.max_failures {
+            continue;
+        }
+        upgrade.tried += 1;
+
+        let mut adr = Address {
+            address1: mp.address1.clone(),
+            address2: mp.address2.clone(),
+            city: mp.city.clone(),
+            state: mp.state.clone(),
+            zip5: mp.zip5,
+            ..Default::default()
+        };
+        match stdzr.standardize(&mut adr).await {
+            Ok(_) => failures = 0,
+            Err(err) => {
+                warn!("upgrade_zip4: {}: {err}", mp.name);
+                failures += 1;
+                continue;
+            }
+        }
+        if adr.zip4 == 0 || adr.zip5 != mp.zip5 {
+            continue;
+        }
+
+        // Update source address.
+        if let Some(src) = pers
+            .iter_mut()
+            .filter(|per| per.name == mp.name)
+            .flat_map(|per| per.adrs.iter_mut().flatten())
+            .find(|src| src.zip4 == 0 && is_mailed_adr(src, mp))
+        {
+            src.zip4 = adr.zip4;
+            src.delivery_point.clone_from(&adr.delivery_point);
+        }
+
+        // Update mailpiece.
+        // Clear any barcode encoded without the zip4.
+        mp.zip4 = adr.zip4;
+        mp.delivery_point = adr.delivery_point;
+        mp.barcode.clear();
+        upgrade.upgraded += 1;
+    }
+
+    upgrade
+}
+
+/// Writes zip4 upgrades of merged persons to person files, such as `house.json`.
+///
+/// Files with upgrades are rewritten, keeping their other fields.
+/// Missing files are skipped. Returns the count of upgraded file addresses.
+pub fn write_zip4_files(pths: &[&str], pers: &[Person]) -> Result<usize> {
+    let mut cnt = 0;
+    for pth in pths.iter().filter(|pth| data_pth(pth).exists()) {
+        let mut per_file: serde_json::Value = read_from_file(pth)?;
+        let mut file_pers: Vec<Person> = serde_json::from_value(per_file["persons"].take())
+            .map_err(|err| anyhow!("upgrade_zip4: person file '{pth}': {err}"))?;
+
+        let mut file_cnt = 0;
+        for file_per in file_pers.iter_mut() {
+            let Some(upgraded) = pers.iter().find(|per| per.name == file_per.name) else {
+                continue;
+            };
+            for src in file_per
+                .adrs
+                .iter_mut()
+                .flatten()
+                .filter(|src| src.zip4 == 0)
+            {
+                if let Some(adr) = upgraded.adrs.iter().flatten().find(|adr| {
+                    adr.merged_address1(usize::MAX) == src.merged_address1(usize::MAX)
+                        && adr.zip5 == src.zip5
+                        && adr.zip4 != 0
+                }) {
+                    src.zip4 = adr.zip4;
+                    src.delivery_point.clone_from(&adr.delivery_point);
+                    file_cnt += 1;
+                }
+            }
+        }
+
+        if file_cnt != 0 {
+            per_file["persons"] = serde_json::to_value(&file_pers)?;
+            write_to_file(&per_file, pth)?;
+            cnt += file_cnt;
+        }
+    }
+    Ok(cnt)
+}
+
+// 600 envelopes per 1ft tray.
+// Tray Length: 12 inches
+// Envelope Thickness: Varies, but a standard #10 envelope with a single sheet of paper is approximately 0.02 inches thick.
+// Fit Calculation
+// Number of Envelopes Lengthwise:
+// 12 inches ÷ 0.02 inches/envelope = 600 envelopes 12 inches ÷ 0.02 inches/envelope = 600 envelopes
+pub const CAP_1FOOT: usize = 600;
+pub const CAP_2FOOT: usize = 1200;
+
 /// Segement pre-sorted groups into USPS trays.
+///
+/// Full 2-foot trays are split off first, and the last two trays are balanced,
+/// so 1300 pieces become 650/650 rather than 1200/100. Order is kept.
 pub fn segment_trays(barcode_id: BarcodeId, mailpieces: Vec<Mailpiece>) -> Vec<MailTray> {
-    // 600 envelopes per 1ft tray.
-    // Tray Length: 12 inches
-    // Envelope Thickness: Varies, but a standard #10 envelope with a single sheet of paper is approximately 0.02 inches thick.
-    // Fit Calculation
-    // Number of Envelopes Lengthwise:
-    // 12 inches ÷ 0.02 inches/envelope = 600 envelopes 12 inches ÷ 0.02 inches/envelope = 600 envelopes
-    pub const CAP_1FOOT: usize = 600;
-    pub const CAP_2FOOT: usize = 1200;
-
     // Place all trays in return list for naming "_tray1ofN".
     let mut ret = Vec::new();
-
-    if mailpieces.len() <= CAP_1FOOT {
-        ret.push(MailTray {
-            name: "".into(),
-            size: OneFoot,
-            barcode_id,
-            mailpieces,
-        });
-    } else if mailpieces.len() <= CAP_2FOOT {
+    let mut push_tray = |mailpieces: &[Mailpiece]| {
+        let size = if mailpieces.len() <= CAP_1FOOT {
+            OneFoot
+        } else {
+            TwoFoot
+        };
         ret.push(MailTray {
             name: "".into(),
-            size: TwoFoot,
+            size,
             barcode_id,
-            mailpieces,
+            mailpieces: mailpieces.to_vec(),
         });
-    } else {
-        // Split mailpieces into 2-foot trays and remaining pieces.
-        let mut remaining_pieces = mailpieces.as_slice();
-        while remaining_pieces.len() > CAP_2FOOT {
-            let (left, right) = remaining_pieces.split_at(CAP_2FOOT);
-            ret.push(MailTray {
-                name: "".into(),
-                size: TraySize::TwoFoot,
-                barcode_id,
-                mailpieces: left.to_vec(),
-            });
-            remaining_pieces = right;
-        }
-
-        // Handle remaining pieces.
-        if remaining_pieces.len() > CAP_1FOOT {
-            ret.push(MailTray {
-                name: "".into(),
-                size: TraySize::TwoFoot,
-                barcode_id,
-                mailpieces: remaining_pieces.to_vec(),
-            });
-        } else if !remaining_pieces.is_empty() {
-            ret.push(MailTray {
-                name: "".into(),
-                size: TraySize::OneFoot,
-                barcode_id,
-                mailpieces: remaining_pieces.to_vec(),
-            });
-        }
+    };
+
+    // Split off 2-foot trays while more than two trays remain.
+    let mut remaining_pieces = mailpieces.as_slice();
+    while remaining_pieces.len() > 2 * CAP_2FOOT {
+        let (left, right) = remaining_pieces.split_at(CAP_2FOOT);
+        push_tray(left);
+        remaining_pieces = right;
+    }
+
+    // Balance the remaining pieces over the last two trays.
+    if remaining_pieces.len() > CAP_2FOOT {
+        let (left, right) = remaining_pieces.split_at(remaining_pieces.len().div_ceil(2));
+        push_tray(left);
+        push_tray(right);
+    } else if !remaining_pieces.is_empty() {
+        push_tray(remaining_pieces);
     }
 
     ret
 }
 
+/// Name of a tray by index: "A".."Z", then "AA", "AB".
+pub fn tray_name(idx: usize) -> String {
+    let mut name = Vec::new();
+    let mut rem = idx + 1;
+    while rem > 0 {
+        rem -= 1;
+        name.push(b'A' + (rem % 26) as u8);
+        rem /= 26;
+    }
+    name.reverse();
+    String::from_utf8(name).expect("Invalid tray name")
+}
+
+/// Whether a mailpiece is missing its barcode, or has a placeholder outside a dry run.
+fn needs_barcode(mp: &Mailpiece) -> bool {
+    mp.barcode.is_empty() || (!is_dry_run() && is_placeholder_barcode(&mp.barcode))
+}
+
 /// A tray of mailpieces.
 #[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct MailTray {
@@ -323,9 +973,12 @@ pub struct MailTray {
 impl MailTray {
     // See "Intelligent Mail Barcode Technical Resource Guide" PDF.
     // See https://postalpro.usps.com/node/221.
-    pub async fn add_barcodes(&mut self, cur_cnt: usize, mps_len: f64) -> Result<bool> {
-        let mut self_clone = self.clone();
-        let mp_len = self.mailpieces.len() as f64;
+    pub async fn add_barcodes(
+        &mut self,
+        progress: &mut Progress,
+        usps: &UspsClient,
+    ) -> Result<bool> {
+        let self_clone = self.clone();
 
         // Fetch barcode encoding for each mailpiece.
         let mut did_fetch = false;
@@ -333,61 +986,140 @@ impl MailTray {
             .mailpieces
             .iter()
             .enumerate()
-            .filter(|(_, mp)| mp.barcode.is_empty())
+            .filter(|(_, mp)| needs_barcode(mp))
         // .take(1)
         {
             did_fetch = true;
-            let pct = ((((cur_cnt + idx) as f64 + 1.0) / mps_len) * 100.0) as u8;
-            eprintln!("  {}% {}", pct, mp);
+            progress.tick(&mp.to_string());
 
             // Create routing code (zip + delivery point).
-            // The Routing Code field is an optional field, which may contain a
-            // 5-digit ZIP Code, a 9-digit ZIP+4 code, or an 11-digit delivery
-            // point code. When used on letters for automation-rate eligibility purposes,
-            // the routing code must contain a delivery point code from CASS-certified
-            // software that accurately matches the delivery address.
-            // From "Intelligent Mail Barcode Technical Resource Guide" PDF.
-            // See https://postalpro.usps.com/node/221.
-            let mut routing_code = if mp.zip4 != 0 {
-                format!("{:05}{:04}", mp.zip5, mp.zip4)
-            } else {
-                format!("{:05}", mp.zip5)
-            };
-            if mp.zip4 != 0 {
-                if let Some(delivery_point) = &mp.delivery_point {
-                    routing_code.push_str(delivery_point);
-                }
-            }
+            let routing_code = mp.routing_code();
 
             // eprintln!("  routing_code:{routing_code}");
-            self.mailpieces[idx].barcode = encode_barcode(
-                &format!("{}", self.barcode_id),
-                STID_RSR,
-                &CFG.mailer_id,
-                &format!("{:06}", mp.id),
-                &routing_code,
-            )
-            .await?;
+            let imb = Imb {
+                barcode_id: format!("{}", self.barcode_id),
+                service_type_id: STID_RSR.into(),
+                mailer_id: CFG.mailer_id.clone(),
+                serial: mp.id,
+                routing_code,
+            };
+            // Dry runs use a placeholder, refetched by a later run.
+            self.mailpieces[idx].barcode = if is_dry_run() {
+                placeholder_barcode()
+            } else {
+                let serial_id = format!("{:06}", imb.serial);
+                let barcode = usps
+                    .encode_barcode_cached(
+                        &imb.barcode_id,
+                        &imb.service_type_id,
+                        &imb.mailer_id,
+                        &serial_id,
+                        &imb.routing_code,
+                    )
+                    .await?;
+                // Check the encoder before its bars are printed on a tray of mail.
+                let id = mp.id;
+                validate_imb(&barcode).map_err(|err| anyhow!("mailpiece {id}: {err}"))?;
+                let local = encode_barcode_local(
+                    &imb.barcode_id,
+                    &imb.service_type_id,
+                    &imb.mailer_id,
+                    &serial_id,
+                    &imb.routing_code,
+                )?;
+                if barcode != local {
+                    return Err(anyhow!(
+                        "mailpiece {id}: IMb '{barcode}' differs from local encoding '{local}'"
+                    ));
+                }
+                barcode
+            };
+            self.mailpieces[idx].imb = Some(imb);
         }
 
         Ok(did_fetch)
     }
 
-    pub fn create_envelopes_letters<P>(&self, cur_cnt: usize, mps_len: f64, pth: P) -> Result<()>
+    /// Piece counts of each printing chunk.
+    pub fn chunk_lens(&self) -> Vec<usize> {
+        self.mailpieces
+            .chunks(CHUNK_SIZE)
+            .map(|chunk| chunk.len())
+            .collect()
+    }
+
+    /// Lowest and highest zip5 of the pieces; `(0, 0)` for an empty tray.
+    pub fn zip_range(&self) -> (u32, u32) {
+        let zip5s = self.mailpieces.iter().map(|mp| mp.zip5);
+        match (zip5s.clone().min(), zip5s.max()) {
+            (Some(min), Some(max)) => (min, max),
+            _ => (0, 0),
+        }
+    }
+
+    /// Content line of the tray label, such as `LTRS 5D 47547` or `LTRS MXD AADC`.
+    ///
+    /// A 5-digit tray spanning zips shows the range, such as `LTRS 5D 47546-47547`.
+    pub fn label_line(&self) -> String {
+        let (min, max) = self.zip_range();
+        // The AADC is named by its own zip3, such as `AADC ALBANY NY 120`.
+        let aadc_zip3 = aadc_of(min / 100).and_then(|aadc| aadc.rsplit(' ').next());
+        match self.barcode_id {
+            BarcodeId::FiveDigit if min != max => format!("LTRS 5D {min:05}-{max:05}"),
+            BarcodeId::FiveDigit => format!("LTRS 5D {min:05}"),
+            BarcodeId::ThreeDigit => format!("LTRS 3D {:03}", min / 100),
+            BarcodeId::Aadc => match aadc_zip3 {
+                Some(zip3) => format!("LTRS AADC {zip3}"),
+                None => "LTRS MXD AADC".into(),
+            },
+            BarcodeId::MixedAadc => "LTRS MXD AADC".into(),
+            _ => "LTRS".into(),
+        }
+    }
+
+    /// Creates the tray label pdf `{tray}_placard.pdf`.
+    ///
+    /// Shows the content line, tray size, piece count, mailer and entry office.
+    pub fn create_placard(&self, cfg: &MailingCfg, pth: &Path) -> Result<()> {
+        let font_pth = conformance_font_pth(cfg.conformance, cfg.font_pth.as_deref())?;
+        let mut lbl_doc = LabelDocument::new(format!("{}_placard", self.name), font_pth)?;
+        let size = match self.size {
+            OneFoot => "1 FT",
+            TwoFoot => "2 FT",
+        };
+        lbl_doc.create_page(
+            &self.label_line(),
+            &[
+                format!("Tray {} {size} letter tray", self.name),
+                format!("Pieces: {}", self.mailpieces.len()),
+                String::new(),
+                format!("Mailer: {}", cfg.from.name),
+                format!("Permit: {}", cfg.indicia.permit_id),
+                format!("Entry: {}", cfg.ps.post_office_mailing),
+            ],
+        );
+        lbl_doc.save(pth, cfg.conformance)
+    }
+
+    /// Creates envelope and letter pdfs per chunk, counting pieces in the manifest.
+    ///
+    /// Each letter uses the template of its mailpiece language, in envelope order.
+    pub fn create_envelopes_letters<P>(
+        &self,
+        progress: &mut Progress,
+        pth: P,
+        tmpls: &LetterTemplates,
+        manifest: &mut Manifest,
+    ) -> Result<()>
     where
         P: AsRef<Path>,
     {
-        // Read letter template from disk.
-        let ltr_tmpl = letter_template()?;
+        // Archival conformance requires an embedded font.
+        let font_pth = conformance_font_pth(CFG.conformance, CFG.font_pth.as_deref())?;
 
-        // 50 chunk size is based on capacity of an envelope printer and paper folding machine.
-        const CHUNK_SIZE: usize = 50;
-        let chunk_cnt = self
-            .mailpieces
-            .iter()
-            .chunks(CHUNK_SIZE)
-            .into_iter()
-            .count();
+        let chunk_cnt = self.chunk_lens().len();
+        let mut env_pths = Vec::with_capacity(chunk_cnt);
+        let mut ltr_pths = Vec::with_capacity(chunk_cnt);
         for (chunk_idx, chunk) in (&self.mailpieces.iter().enumerate().chunks(CHUNK_SIZE))
             .into_iter()
             .enumerate()
@@ -397,72 +1129,183 @@ impl MailTray {
             let chunk: Vec<_> = chunk.collect();
             let chunk_len = chunk.len();
 
-            // Create letter name.
-            let ltr_name = format!(
-                "{}_{}of{:02}_cnt{}_ltr",
-                self.name,
-                chunk_idx + 1,
-                chunk_cnt,
-                chunk_len
-            );
-            // Create envelope name.
-            let env_name = format!(
-                "{}_{}of{:02}_cnt{}_env",
-                self.name,
-                chunk_idx + 1,
-                chunk_cnt,
-                chunk_len
-            );
-            eprintln!("creating {}", ltr_name);
-
-            // Create a pdf document for multiple letters.
-            let mut ltr = ltr_tmpl.clone_clear();
+            // Create letter and envelope names.
+            let kind = ltr_kind(CFG.duplex);
+            let ltr_name = chunk_name(&self.name, chunk_idx, chunk_cnt, chunk_len, kind);
+            let env_name = chunk_name(&self.name, chunk_idx, chunk_cnt, chunk_len, "env");
+            info!("creating {}", ltr_name);
 
             // Create a pdf document for multiple envelopes.
-            let mut env_doc = EnvelopeDocument::new(env_name);
+            let mut env_doc =
+                EnvelopeDocument::new(env_name, CFG.envelope_size, font_pth, CFG.barcode_mode)?;
 
             // Iterate through each mailpiece in the current chunk.
-            for (mp_idx, mp) in chunk {
-                let pct = ((((cur_cnt + mp_idx) as f64 + 1.0) / mps_len) * 100.0) as u8;
-                eprintln!("  {}% {}", pct, mp);
+            for (mp_idx, mp) in chunk.iter() {
+                progress.tick(&mp.to_string());
 
                 // Create envelope.
-                env_doc.create_page(mp, mp_idx % CHUNK_SIZE == 0);
-
-                // Create letter.
-                // Clone letter template with text.
-                let mut cur_ltr = ltr_tmpl.clone();
-                // Replace placeholder text with actual name.
-                cur_ltr.replace_par_at(0, "{{name}}", &mp.name);
-                // Copy paragraphs to destination letter.
-                ltr.copy_pars(cur_ltr.clone());
-                // Add a page break.
-                ltr.add_pag_brk();
+                env_doc.create_page(mp, mp_idx % CHUNK_SIZE == 0)?;
             }
 
+            // Create a pdf document for multiple letters, paired with envelopes.
+            let mps: Vec<&Mailpiece> = chunk.iter().map(|(_, mp)| *mp).collect();
+            let ltr = chunk_letter(
+                tmpls,
+                &mps,
+                CFG.duplex,
+                CFG.qr_base_url.as_deref(),
+                manifest,
+            )?;
+
             // Create path.
             let mut pth = pth.as_ref().to_path_buf();
 
             // Save envelope document to disk.
-            pth.push(env_doc.name);
+            pth.push(&env_doc.name);
             pth.set_extension("pdf");
-            env_doc
-                .doc
-                .save(&mut BufWriter::new(File::create(&pth).unwrap()))?;
+            manifest.add_file(&pth);
+            save_doc(env_doc.doc, &pth, CFG.conformance)?;
+            env_pths.push(pth.clone());
 
             // Save letter document to disk.
             pth.pop();
             pth.push(ltr_name);
             pth.set_extension("");
-            ltr.save_pdf(&pth)?;
+            save_letter_pdf(&ltr, &pth)?;
+            pth.set_extension("pdf");
+            manifest.add_file(&pth);
+            convert_file(&pth, CFG.conformance)?;
+            ltr_pths.push(pth);
+        }
+
+        // Merge chunks into one pdf per tray, for the print shop.
+        if CFG.combine_tray_pdfs {
+            for (kind, pths) in [("env", &env_pths), (ltr_kind(CFG.duplex), &ltr_pths)] {
+                let mut pth = pth.as_ref().join(format!("{}_all_{kind}", self.name));
+                pth.set_extension("pdf");
+                let pag_cnt = merge_files(pths, &pth, CFG.conformance)?;
+                info!("merged {} pages into {}", pag_cnt, pth.display());
+                // One envelope page per mailpiece.
+                if kind == "env" && pag_cnt != self.mailpieces.len() {
+                    return Err(anyhow!(
+                        "{}: {pag_cnt} pages != {} mailpieces",
+                        pth.display(),
+                        self.mailpieces.len()
+                    ));
+                }
+                manifest.add_file(&pth);
+            }
         }
 
         Ok(())
     }
 }
 
+/// Creates the letter document of a chunk, one letter per mailpiece in order.
+///
+/// Each letter uses the template of its mailpiece language.
+/// Duplex pads each letter with a blank back page to an even page count.
+/// A response url base adds a footer to each letter.
+pub fn chunk_letter(
+    tmpls: &LetterTemplates,
+    mps: &[&Mailpiece],
+    duplex: bool,
+    qr_base_url: Option<&str>,
+    manifest: &mut Manifest,
+) -> Result<Doc> {
+    let mut ltr = tmpls.default.clone_clear();
+    for mp in mps {
+        let pag_cnt = ltr.seg_pags().len();
+        let is_fallback = append_letter(&mut ltr, tmpls, mp, qr_base_url)?;
+        manifest.add_piece(mp.language.as_deref(), is_fallback);
+
+        // A blank page needs a paragraph, as pdf_doc skips empty pages.
+        if duplex && (ltr.seg_pags().len() - pag_cnt) % 2 == 1 {
+            ltr.add_par(par(" "));
+            ltr.add_pag_brk();
+        }
+    }
+    Ok(ltr)
+}
+
+/// Mailpieces per printing chunk.
+///
+/// 50 chunk size is based on capacity of an envelope printer and paper folding machine.
+pub const CHUNK_SIZE: usize = 50;
+
+/// Kind of a letter chunk name, `ltr_duplex` for duplex letters.
+pub fn ltr_kind(duplex: bool) -> &'static str {
+    if duplex {
+        "ltr_duplex"
+    } else {
+        "ltr"
+    }
+}
+
+/// Creates the file name of a chunk, such as `A_1of03_cnt50_ltr`.
+pub fn chunk_name(
+    tray: &str,
+    chunk_idx: usize,
+    chunk_cnt: usize,
+    chunk_len: usize,
+    kind: &str,
+) -> String {
+    format!(
+        "{}_{}of{:02}_cnt{}_{}",
+        tray,
+        chunk_idx + 1,
+        chunk_cnt,
+        chunk_len,
+        kind
+    )
+}
+
+/// Reads the letter template, preferring markdown over JSON.
+///
+/// A markdown letter takes its layout from the JSON template when present.
 pub fn letter_template() -> Result<Doc> {
-    read_from_file::<Doc>(FLE_PTH_LTR)
+    if !data_pth(FLE_PTH_LTR_MD).exists() {
+        return read_from_file::<Doc>(FLE_PTH_LTR);
+    }
+    let layout = if data_pth(FLE_PTH_LTR).exists() {
+        read_from_file::<Doc>(FLE_PTH_LTR)?
+    } else {
+        new_ansi_letter()
+    };
+    debug!("Reading file: {}", FLE_PTH_LTR_MD);
+    Ok(prs_letter_md(
+        &fs::read_to_string(data_pth(FLE_PTH_LTR_MD))?,
+        &layout,
+    ))
+}
+
+/// Parses a markdown letter into paragraphs with the layout of a document.
+///
+/// Blank lines separate paragraphs, `#` headings are bold paragraphs and
+/// `---` is a page break. `**bold**` and `*italic*` spans are kept for save_letter_pdf.
+pub fn prs_letter_md(md: &str, layout: &Doc) -> Doc {
+    let mut doc = layout.clone_clear();
+    let mut lnes: Vec<&str> = Vec::new();
+    for lne in md.lines().map(str::trim).chain([""]) {
+        let is_heading = lne.starts_with('#');
+        let is_pag_brk = lne == "---";
+        if lne.is_empty() || is_heading || is_pag_brk {
+            // Join the lines of the current paragraph.
+            if !lnes.is_empty() {
+                doc.add_par(par(&lnes.join(" ")));
+                lnes.clear();
+            }
+        }
+        if is_heading {
+            let heading = lne.trim_start_matches('#').trim();
+            doc.add_par(par(heading).set_fnt_sty(Some(Style::Bold)));
+        } else if is_pag_brk {
+            doc.add_pag_brk();
+        } else if !lne.is_empty() {
+            lnes.push(lne);
+        }
+    }
+    doc
 }
 
 pub fn mailing_cfg() -> Result<MailingCfg> {
@@ -503,6 +1346,96 @@ pub struct MailingCfg {
     pub indicia: Indicia,
     pub from: Mailpiece,
     pub ps: PostageStatementCfg,
+    #[serde(default)]
+    pub packing: PackingCfg,
+    /// Path of a TTF embedded for envelope and label text, such as Liberation Sans.
+    ///
+    /// Builtin Helvetica when unset. Letters are rendered by pdf_doc, which embeds its fonts.
+    #[serde(default)]
+    pub font_pth: Option<String>,
+    /// IMb rendering, `font` or `vector`.
+    ///
+    /// Vector when unset and the IMb font is unavailable.
+    #[serde(default)]
+    pub barcode_mode: Option<BarcodeMode>,
+    /// Pdf conformance of envelopes, letters and labels, `print` or `pdfa2b`.
+    ///
+    /// PDF/A-2b for archival copies requires `font_pth`. Print when unset.
+    #[serde(default)]
+    pub conformance: Conformance,
+    /// Letter languages by state and their templates.
+    #[serde(default)]
+    pub languages: LanguageCfg,
+    /// Pads each letter to an even page count for duplex printing.
+    ///
+    /// Letter chunk names end with `ltr_duplex`. Off when unset.
+    #[serde(default)]
+    pub duplex: bool,
+    /// Roles of recipients for a partial mailing, such as `["Political"]`.
+    ///
+    /// Sources of other roles are not loaded. Every role when empty.
+    #[serde(default)]
+    pub recipient_filter: Vec<Role>,
+    /// Also merges the chunks of each tray into `{tray}_all_env.pdf` and `{tray}_all_ltr.pdf`.
+    ///
+    /// Chunks are kept for machine loading. Off when unset.
+    #[serde(default)]
+    pub combine_tray_pdfs: bool,
+    /// Envelope size, `number10`, `number9` or `sixbynine`.
+    ///
+    /// Number 10 when unset.
+    #[serde(default)]
+    pub envelope_size: EnvelopeSize,
+    /// Base of the response url of each mailpiece, such as `https://example.org/r`.
+    ///
+    /// The zero-padded mailpiece id is appended, such as `https://example.org/r/000123`.
+    /// Envelopes carry it as a QR code and letters in the footer. Neither when unset.
+    #[serde(default)]
+    pub qr_base_url: Option<String>,
+    /// Characters of a delivery line merged with address2, such as `1919 SMITH ST STE 9047`.
+    ///
+    /// Longer lines keep address2 on its own line. `ADDRESS1_MAX` when unset.
+    #[serde(default)]
+    pub address1_max: Option<usize>,
+}
+
+impl MailingCfg {
+    /// Reads a mailing configuration, such as `FLE_PTH_CFG`.
+    pub fn load(pth: &str) -> Result<MailingCfg> {
+        read_from_file::<MailingCfg>(pth)
+    }
+
+    /// Ids of the next mailpieces after the last mailpiece id, without reserving them.
+    pub fn mailpiece_ids(&self, cnt: u32) -> Result<Range<u32>> {
+        let start = self.last_mailpiece_id + 1;
+        let end = start
+            .checked_add(cnt)
+            .filter(|end| end - 1 <= IMB_SERIAL_MAX)
+            .ok_or_else(|| {
+                anyhow!(
+                    "{cnt} mailpiece ids after {} exceed the IMb serial {IMB_SERIAL_MAX}",
+                    self.last_mailpiece_id
+                )
+            })?;
+        Ok(start..end)
+    }
+
+    /// Whether recipients of a role are mailed.
+    pub fn is_role_allowed(&self, role: &Role) -> bool {
+        self.recipient_filter.is_empty() || self.recipient_filter.contains(role)
+    }
+
+    /// Errors on a person whose role is filtered out.
+    pub fn check_roles(&self, pers: &[Person]) -> Result<()> {
+        match pers.iter().find(|per| !self.is_role_allowed(&per.role)) {
+            Some(per) => Err(anyhow!(
+                "{} role {} is filtered out by recipient_filter",
+                per.name,
+                per.role
+            )),
+            None => Ok(()),
+        }
+    }
 }
 
 #[derive(Debug, Default, Serialize, Deserialize, Clone)]
@@ -513,6 +1446,58 @@ pub struct PostageStatementCfg {
     pub post_office_mailing: String,
     pub mailing_date: String,
     pub last_statement_id: u16,
+    /// Fills in PS Form 3602-N for each mailing.
+    ///
+    /// Off when unset.
+    #[serde(default)]
+    pub enabled: bool,
+}
+
+/// Largest IMb serial of a nine digit mailer id.
+pub const IMB_SERIAL_MAX: u32 = 999_999;
+
+/// Reserves mailpiece and statement ids of a mailing config.
+///
+/// Reservations are persisted by `commit`, so an abandoned run reuses them.
+#[derive(Debug, Clone)]
+pub struct IdAllocator {
+    pth: String,
+    pub cfg: MailingCfg,
+}
+
+impl IdAllocator {
+    /// Reads the current high-water marks from a mailing config file.
+    pub fn load(pth: &str) -> Result<Self> {
+        Ok(Self {
+            pth: pth.into(),
+            cfg: read_from_file::<MailingCfg>(pth)?,
+        })
+    }
+
+    /// Reserves a contiguous block of mailpiece ids.
+    pub fn reserve_mailpiece_ids(&mut self, cnt: u32) -> Result<Range<u32>> {
+        let ids = self.cfg.mailpiece_ids(cnt)?;
+        self.cfg.last_mailpiece_id = ids.end - 1;
+        Ok(ids)
+    }
+
+    /// Reserves the next postage statement id.
+    pub fn reserve_statement_id(&mut self) -> Result<u16> {
+        let id = self
+            .cfg
+            .ps
+            .last_statement_id
+            .checked_add(1)
+            .ok_or_else(|| anyhow!("postage statement ids exhausted"))?;
+        self.cfg.ps.last_statement_id = id;
+        Ok(id)
+    }
+
+    /// Writes the reserved high-water marks to the config file atomically.
+    pub fn commit(&self) -> Result<()> {
+        debug!("Writing file: {}", self.pth);
+        write_atomic(&data_pth(&self.pth), &serde_json::to_vec_pretty(&self.cfg)?)
+    }
 }
 
 /// USPS barcode identifier.
@@ -546,8 +1531,19 @@ pub enum TraySize {
     TwoFoot,
 }
 
+impl TraySize {
+    /// Mailpiece capacity of the tray.
+    pub fn cap(&self) -> usize {
+        match self {
+            OneFoot => CAP_1FOOT,
+            TwoFoot => CAP_2FOOT,
+        }
+    }
+}
+
+/// Sortation levels of USPS labeling lists.
 #[derive(Debug)]
-enum SortLvl {
+pub enum SortLvl {
     FiveDigit,                     // 5DIG - 5 Digit
     ThreeDigitColA,                // L002A - 3 Digit Col A
     ThreeDigitColB1,               // L002B1 - 3 Digit Col B
@@ -574,3 +1570,1290 @@ enum SortLvl {
     AADCLetterSizeMailingsB, // L801B - AADCs - Letter-Size Mailings
     None,                 // No value selected
 }
+
+/// A recipient of two mailings whose address changed.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct MailpieceChange {
+    pub old: Mailpiece,
+    pub new: Mailpiece,
+    /// Names of the changed address fields, such as `zip4`.
+    pub fields: Vec<String>,
+}
+
+/// Recipients added, removed, or with address changes between two mailings.
+#[derive(Debug, Default, Serialize, Deserialize, Clone)]
+pub struct MailingDiff {
+    pub old: String,
+    pub new: String,
+    pub added: Vec<Mailpiece>,
+    pub removed: Vec<Mailpiece>,
+    pub changed: Vec<MailpieceChange>,
+    pub added_cnt: usize,
+    pub removed_cnt: usize,
+    pub changed_cnt: usize,
+    pub unchanged_cnt: usize,
+}
+
+impl MailingDiff {
+    /// Summary lines, such as `2 added`.
+    pub fn lnes(&self) -> Vec<String> {
+        vec![
+            format!("{} to {}", self.old, self.new),
+            format!("{} added", self.added_cnt),
+            format!("{} removed", self.removed_cnt),
+            format!("{} changed", self.changed_cnt),
+            format!("{} unchanged", self.unchanged_cnt),
+        ]
+    }
+
+    /// A text table of the differences, one row per mailpiece.
+    pub fn table(&self) -> String {
+        let mut rows: Vec<[String; 5]> = vec![[
+            "CHANGE".into(),
+            "OLD ID".into(),
+            "NEW ID".into(),
+            "NAME".into(),
+            "ADDRESS".into(),
+        ]];
+        for mp in self.added.iter() {
+            rows.push([
+                "added".into(),
+                String::new(),
+                format!("{:06}", mp.id),
+                mp.name.clone(),
+                diff_adr(mp),
+            ]);
+        }
+        for mp in self.removed.iter() {
+            rows.push([
+                "removed".into(),
+                format!("{:06}", mp.id),
+                String::new(),
+                mp.name.clone(),
+                diff_adr(mp),
+            ]);
+        }
+        for chg in self.changed.iter() {
+            rows.push([
+                format!("changed ({})", chg.fields.join(", ")),
+                format!("{:06}", chg.old.id),
+                format!("{:06}", chg.new.id),
+                chg.new.name.clone(),
+                format!("{} -> {}", diff_adr(&chg.old), diff_adr(&chg.new)),
+            ]);
+        }
+        let widths: Vec<usize> = (0..4)
+            .map(|col| {
+                rows.iter()
+                    .map(|row| row[col].len())
+                    .max()
+                    .unwrap_or_default()
+            })
+            .collect();
+        let mut ret = String::new();
+        for row in rows {
+            for (col, width) in widths.iter().enumerate() {
+                ret += &format!("{:width$}  ", row[col]);
+            }
+            ret += &row[4];
+            ret += "\n";
+        }
+        ret
+    }
+
+    /// Writes the differences as JSON, and as a text table beside it, such as `mailing_diff.txt`.
+    pub fn write(&self, pth: &str) -> Result<()> {
+        write_to_file(self, pth)?;
+        let pth_txt = data_pth(pth).with_extension("txt");
+        debug!("Writing file: {}", pth_txt.display());
+        write_atomic(&pth_txt, self.table().as_bytes())
+    }
+}
+
+/// Address fields of a mailpiece compared between mailings.
+fn diff_fields(mp: &Mailpiece) -> [(&'static str, String); 10] {
+    [
+        ("firm", mp.firm.clone().unwrap_or_default()),
+        ("address1", mp.address1.clone()),
+        ("address2", mp.address2.clone().unwrap_or_default()),
+        ("city", mp.city.clone()),
+        ("state", mp.state.clone()),
+        ("zip5", format!("{:05}", mp.zip5)),
+        ("zip4", format!("{:04}", mp.zip4)),
+        (
+            "delivery_point",
+            mp.delivery_point.clone().unwrap_or_default(),
+        ),
+        ("country", mp.country.clone().unwrap_or_default()),
+        ("postal_code", mp.postal_code.clone().unwrap_or_default()),
+    ]
+}
+
+/// Single line address of a mailpiece, without its name and titles.
+fn diff_adr(mp: &Mailpiece) -> String {
+    let skip = 1 + mp.title1.is_some() as usize + mp.title2.is_some() as usize;
+    mp.to_lines().into_iter().skip(skip).join(", ")
+}
+
+/// Compares the mailpieces of two mailings by recipient.
+///
+/// Mailpiece ids differ between mailings, so recipients match by `name_key`.
+/// A recipient with several addresses keeps addresses in both mailings unchanged,
+/// and pairs the rest in order as changed, added, or removed.
+pub fn mailing_diff(old: &Mailing, new: &Mailing) -> MailingDiff {
+    let by_name = |mailing: &Mailing| {
+        let mut ret: BTreeMap<String, Vec<Mailpiece>> = BTreeMap::new();
+        for mp in mailing
+            .trays
+            .iter()
+            .flat_map(|tray| tray.mailpieces.iter())
+            .chain(mailing.intl.iter())
+        {
+            ret.entry(name_key(&mp.name)).or_default().push(mp.clone());
+        }
+        ret
+    };
+    let mut olds = by_name(old);
+    let mut news = by_name(new);
+    let keys: Vec<String> = olds
+        .keys()
+        .chain(news.keys())
+        .cloned()
+        .sorted()
+        .dedup()
+        .collect();
+
+    let mut diff = MailingDiff {
+        old: old.name.clone(),
+        new: new.name.clone(),
+        ..Default::default()
+    };
+    for key in keys {
+        let mut mps_old = olds.remove(&key).unwrap_or_default();
+        let mut mps_new = news.remove(&key).unwrap_or_default();
+
+        // Addresses in both mailings are unchanged.
+        mps_old.retain(|mp_old| {
+            match mps_new
+                .iter()
+                .position(|mp_new| diff_fields(mp_new) == diff_fields(mp_old))
+            {
+                Some(idx) => {
+                    mps_new.remove(idx);
+                    diff.unchanged_cnt += 1;
+                    false
+                }
+                None => true,
+            }
+        });
+
+        // Remaining addresses pair as changed.
+        let mut mps_old = mps_old.into_iter();
+        let mut mps_new = mps_new.into_iter();
+        loop {
+            match (mps_old.next(), mps_new.next()) {
+                (Some(mp_old), Some(mp_new)) => {
+                    let fields = diff_fields(&mp_old)
+                        .into_iter()
+                        .zip(diff_fields(&mp_new))
+                        .filter(|(a, b)| a != b)
+                        .map(|(a, _)| a.0.to_string())
+                        .collect();
+                    diff.changed.push(MailpieceChange {
+                        old: mp_old,
+                        new: mp_new,
+                        fields,
+                    });
+                }
+                (Some(mp_old), None) => diff.removed.push(mp_old),
+                (None, Some(mp_new)) => diff.added.push(mp_new),
+                (None, None) => break,
+            }
+        }
+    }
+    diff.added_cnt = diff.added.len();
+    diff.removed_cnt = diff.removed.len();
+    diff.changed_cnt = diff.changed.len();
+    diff
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::prsr::*;
+    use crate::test_support::*;
+    use std::cell::RefCell;
+    use std::collections::BTreeMap;
+
+    /// A standardizer returning a fixed zip4 and recording calls.
+    struct MockStdzr {
+        zip4: u16,
+        calls: RefCell<Vec<String>>,
+    }
+
+    impl Standardizer for MockStdzr {
+        async fn standardize(&self, adr: &mut Address) -> Result<()> {
+            self.calls.borrow_mut().push(adr.address1.clone());
+            adr.zip4 = self.zip4;
+            adr.delivery_point = Some("01".into());
+            Ok(())
+        }
+    }
+
+    fn mp(name: &str, address1: &str, zip4: u16) -> Mailpiece {
+        Mailpiece {
+            name: name.into(),
+            address1: address1.into(),
+            city: "ALBANY".into(),
+            state: "NY".into(),
+            zip5: 12224,
+            zip4,
+            barcode: "FADT".into(),
+            ..Default::default()
+        }
+    }
+
+    fn per(mp: &Mailpiece) -> Person {
+        Person {
+            name: mp.name.clone(),
+            adrs: Some(vec![Address {
+                address1: mp.address1.clone(),
+                city: mp.city.clone(),
+                state: mp.state.clone(),
+                zip5: mp.zip5,
+                zip4: mp.zip4,
+                ..Default::default()
+            }]),
+            ..Default::default()
+        }
+    }
+
+    #[tokio::test]
+    async fn test_foreign_address() {
+        let lnes: Vec<String> = include_str!("../tests/fixtures/observer/uk_institute.txt")
+            .lines()
+            .map(String::from)
+            .collect();
+        let adrs = PRSR.prs_adrs(&lnes).unwrap();
+        assert_eq!(adrs.len(), 1);
+        assert!(adrs[0].is_foreign());
+        assert_eq!(adrs[0].postal_code.as_deref(), Some("SN2 1SZ"));
+
+        // Standardization is skipped, without calling the USPS.
+        let adrs = standardize_addresses(adrs.clone()).await.unwrap();
+        assert_eq!(adrs[0].zip5, 0);
+        assert_eq!(adrs[0].city, "SWINDON");
+
+        let per = Person {
+            name: "Dr. Jane Doe".into(),
+            title1: "UK Space Agency".into(),
+            ..Default::default()
+        };
+        let intl = new_mailpiece(&per, &adrs[0]);
+        assert!(intl.is_foreign());
+        assert_eq!(
+            intl.to_lines(),
+            vec![
+                "DR JANE DOE",
+                "UK SPACE AGENCY",
+                "POLARIS HOUSE",
+                "NORTH STAR AVENUE",
+                "SWINDON  SN2 1SZ",
+                "UNITED KINGDOM",
+            ]
+        );
+
+        // Domestic rendering unchanged.
+        let domestic = mp("A", "1 STATE ST", 1234);
+        assert!(!domestic.is_foreign());
+        assert_eq!(
+            domestic.to_lines(),
+            vec!["A", "1 STATE ST", "ALBANY  NY  12224-1234"]
+        );
+    }
+
+    #[test]
+    fn test_address2() {
+        let per = Person {
+            name: "Jerry L. Carl".into(),
+            ..Default::default()
+        };
+        let adr = Address {
+            address1: "41 W I-65 SERVICE RD N".into(),
+            address2: Some("STE 2300-A".into()),
+            city: "MOBILE".into(),
+            state: "AL".into(),
+            zip5: 36608,
+            zip4: 4402,
+            delivery_point: Some("25".into()),
+            ..Default::default()
+        };
+        let mut mp = new_mailpiece(&per, &adr);
+        assert_eq!(mp.address2.as_deref(), Some("STE 2300-A"));
+        assert_eq!(
+            mp.to_lines(),
+            vec![
+                "JERRY L CARL",
+                "41 W I-65 SERVICE RD N",
+                "STE 2300-A",
+                "MOBILE  AL  36608-4402",
+            ]
+        );
+
+        // Without a zip4, only the zip5 is printed.
+        mp.zip4 = 0;
+        assert_eq!(mp.to_lines()[3], "MOBILE  AL  36608");
+
+        // A firm is printed above address1.
+        let adr = Address {
+            firm: Some("WELLS FARGO CENTER".into()),
+            ..adr
+        };
+        let mp = new_mailpiece(&per, &adr);
+        assert_eq!(
+            mp.to_lines()[1..3],
+            ["WELLS FARGO CENTER", "41 W I-65 SERVICE RD N"]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_upgrade_zip4() {
+        let mps = [
+            mp("A", "1 STATE ST", 0),
+            mp("B", "2 STATE ST", 1234),
+            mp("C", "3 STATE ST", 0),
+        ];
+        let mut pers: Vec<Person> = mps.iter().map(per).collect();
+        let mut trays = vec![
+            MailTray {
+                name: "A".into(),
+                size: OneFoot,
+                barcode_id: BarcodeId::FiveDigit,
+                mailpieces: mps[..2].to_vec(),
+            },
+            MailTray {
+                name: "B".into(),
+                size: OneFoot,
+                barcode_id: BarcodeId::MixedAadc,
+                mailpieces: mps[2..].to_vec(),
+            },
+        ];
+        let stdzr = MockStdzr {
+            zip4: 5678,
+            calls: RefCell::new(Vec::new()),
+        };
+
+        let upgrade = upgrade_zip4(&mut trays, &mut pers, &stdzr, ZIP4_BUDGET).await;
+        assert_eq!(
+            upgrade,
+            Zip4Upgrade {
+                eligible: 1,
+                tried: 1,
+                upgraded: 1
+            }
+        );
+        assert_eq!(*stdzr.calls.borrow(), vec!["1 STATE ST".to_string()]);
+
+        // Mailpiece and source address upgraded.
+        assert_eq!(trays[0].mailpieces[0].zip4, 5678);
+        assert!(trays[0].mailpieces[0].barcode.is_empty());
+        let adrs = pers[0].adrs.as_ref().unwrap();
+        assert_eq!(adrs[0].zip4, 5678);
+        assert_eq!(adrs[0].delivery_point.as_deref(), Some("01"));
+
+        // Ineligible pieces untouched.
+        assert_eq!(trays[0].mailpieces[1].zip4, 1234);
+        assert_eq!(trays[1].mailpieces[0].zip4, 0);
+        assert_eq!(pers[2].adrs.as_ref().unwrap()[0].zip4, 0);
+
+        // Idempotent.
+        let upgrade = upgrade_zip4(&mut trays, &mut pers, &stdzr, ZIP4_BUDGET).await;
+        assert_eq!(upgrade.eligible, 0);
+        assert_eq!(stdzr.calls.borrow().len(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_upgrade_zip4_budget() {
+        let mps: Vec<Mailpiece> = (0..3)
+            .map(|idx| mp(&idx.to_string(), &format!("{idx} STATE ST"), 0))
+            .collect();
+        let mut pers: Vec<Person> = mps.iter().map(per).collect();
+        let mut trays = vec![MailTray {
+            name: "A".into(),
+            size: OneFoot,
+            barcode_id: BarcodeId::FiveDigit,
+            mailpieces: mps,
+        }];
+        let stdzr = MockStdzr {
+            zip4: 5678,
+            calls: RefCell::new(Vec::new()),
+        };
+        let budget = CallBudget {
+            max_calls: 2,
+            max_failures: 5,
+        };
+
+        let upgrade = upgrade_zip4(&mut trays, &mut pers, &stdzr, budget).await;
+        assert_eq!(upgrade.eligible, 3);
+        assert_eq!(upgrade.tried, 2);
+        assert_eq!(trays[0].mailpieces[2].zip4, 0);
+    }
+
+    #[tokio::test]
+    async fn test_write_zip4_files() {
+        let dir = TempDir::new("zip4");
+        let pth = dir.pth("house.json");
+        let missing = dir.pth("state.json");
+        let mps = [mp("A", "1 STATE ST", 0), mp("C", "3 STATE ST", 0)];
+        let per_file = serde_json::json!({
+            "persons": mps.iter().map(per).collect::<Vec<_>>(),
+            "office": "HOUSE",
+        });
+        write_to_file(&per_file, &pth).unwrap();
+
+        // Upgrades of merged persons are written to their person file.
+        let mut pers: Vec<Person> = mps.iter().map(per).collect();
+        let mut trays = vec![MailTray {
+            name: "A".into(),
+            size: OneFoot,
+            barcode_id: BarcodeId::FiveDigit,
+            mailpieces: mps[..1].to_vec(),
+        }];
+        let stdzr = MockStdzr {
+            zip4: 5678,
+            calls: RefCell::new(Vec::new()),
+        };
+        upgrade_zip4(&mut trays, &mut pers, &stdzr, ZIP4_BUDGET).await;
+        assert_eq!(write_zip4_files(&[&pth, &missing], &pers).unwrap(), 1);
+        let upgraded: serde_json::Value = read_from_file(&pth).unwrap();
+        assert_eq!(upgraded["office"], "HOUSE");
+        assert_eq!(upgraded["persons"][0]["adrs"][0]["zip4"], 5678);
+        assert_eq!(upgraded["persons"][0]["adrs"][0]["delivery_point"], "01");
+        assert_eq!(upgraded["persons"][1]["adrs"][0]["zip4"], 0);
+
+        // Written upgrades are not written again.
+        assert_eq!(write_zip4_files(&[&pth], &pers).unwrap(), 0);
+    }
+
+    #[tokio::test]
+    async fn test_upgrade_zip4_merged_suite() {
+        let dir = TempDir::new("zip4_suite");
+        let pth = dir.pth("house.json");
+        let mut pers = vec![per(&mp("A", "1 STATE ST", 0))];
+        pers[0].adrs.as_mut().unwrap()[0].address2 = Some("STE 9".into());
+        write_to_file(&serde_json::json!({ "persons": &pers }), &pth).unwrap();
+
+        // The mailpiece delivery line has the suite merged.
+        let mut trays = vec![MailTray {
+            name: "A".into(),
+            size: OneFoot,
+            barcode_id: BarcodeId::FiveDigit,
+            mailpieces: vec![mp("A", "1 STATE ST STE 9", 0)],
+        }];
+        let stdzr = MockStdzr {
+            zip4: 5678,
+            calls: RefCell::new(Vec::new()),
+        };
+        let upgrade = upgrade_zip4(&mut trays, &mut pers, &stdzr, ZIP4_BUDGET).await;
+        assert_eq!(upgrade.upgraded, 1);
+        assert_eq!(pers[0].adrs.as_ref().unwrap()[0].zip4, 5678);
+
+        assert_eq!(write_zip4_files(&[&pth], &pers).unwrap(), 1);
+        let upgraded: serde_json::Value = read_from_file(&pth).unwrap();
+        assert_eq!(upgraded["persons"][0]["adrs"][0]["zip4"], 5678);
+        assert_eq!(upgraded["persons"][0]["adrs"][0]["address2"], "STE 9");
+    }
+
+    #[test]
+    fn test_chunk_letter_languages() {
+        let tmpl = |txts: &[&str]| {
+            let mut doc = Doc::default();
+            for txt in txts {
+                doc.add_par(par(txt));
+            }
+            doc
+        };
+        let tmpls = LetterTemplates {
+            default: tmpl(&["Dear {{name}},", "Please hold hearings."]),
+            langs: BTreeMap::from([(
+                "es".to_string(),
+                tmpl(&["Estimado/a {{name}}:", "Por favor, celebre audiencias."]),
+            )]),
+        };
+        let mut mp_en = mp("Jane Doe", "1 MAIN ST", 1);
+        mp_en.language = None;
+        let mut mp_es = mp("Jenniffer González-Colón", "2 MAIN ST", 2);
+        mp_es.language = Some("es".into());
+
+        // Letters pair with envelopes in chunk order.
+        let mut manifest = Manifest::new("test");
+        let ltr = chunk_letter(&tmpls, &[&mp_en, &mp_es], false, None, &mut manifest).unwrap();
+        let ltrs: Vec<Vec<String>> = ltr
+            .elms
+            .split(|elm| matches!(elm, Elm::PagBrk))
+            .filter(|elms| !elms.is_empty())
+            .map(|elms| {
+                elms.iter()
+                    .filter_map(|elm| match elm {
+                        Elm::Par(par) => Some(par.txt.clone()),
+                        Elm::PagBrk => None,
+                    })
+                    .collect()
+            })
+            .collect();
+        assert_eq!(
+            ltrs,
+            vec![
+                vec!["Dear Jane Doe,", "Please hold hearings."],
+                vec![
+                    "Estimado/a Jenniffer González-Colón:",
+                    "Por favor, celebre audiencias."
+                ],
+            ]
+        );
+        assert_eq!(manifest.pieces, 2);
+        assert_eq!(
+            manifest.languages,
+            BTreeMap::from([("en".to_string(), 1), ("es".to_string(), 1)])
+        );
+        assert_eq!(manifest.fallbacks, 0);
+
+        // A language without a template falls back to the default.
+        let mut mp_fr = mp("Jean Dupont", "3 MAIN ST", 3);
+        mp_fr.language = Some("fr".into());
+        let ltr = chunk_letter(&tmpls, &[&mp_fr], false, None, &mut manifest).unwrap();
+        assert!(matches!(&ltr.elms[0], Elm::Par(par) if par.txt == "Dear Jean Dupont,"));
+        assert_eq!(manifest.languages["fr"], 1);
+        assert_eq!(manifest.fallbacks, 1);
+
+        // A response url adds a footer to each letter.
+        mp_en.id = 1;
+        mp_es.id = 123;
+        let base = Some("https://example.org/r/");
+        let ltr = chunk_letter(&tmpls, &[&mp_en, &mp_es], false, base, &mut manifest).unwrap();
+        let footers: Vec<String> = ltr
+            .seg_pags()
+            .into_iter()
+            .map(|pag| pag.last().unwrap().txt.clone())
+            .collect();
+        assert_eq!(
+            footers,
+            vec![
+                "Ref 000001  https://example.org/r/000001",
+                "Ref 000123  https://example.org/r/000123"
+            ]
+        );
+    }
+
+    #[test]
+    fn test_chunk_letter_duplex() {
+        let mut one_pag = Doc::default();
+        one_pag.add_par(par("Dear {{name}},"));
+        let mut two_pag = one_pag.clone();
+        two_pag.add_pag_brk();
+        two_pag.add_par(par("Sincerely,"));
+        let tmpls = LetterTemplates {
+            default: one_pag,
+            langs: BTreeMap::from([("es".to_string(), two_pag)]),
+        };
+        let mut mp_es = mp("Jenniffer González-Colón", "2 MAIN ST", 2);
+        mp_es.language = Some("es".into());
+        let mp_en = mp("Jane Doe", "1 MAIN ST", 1);
+        let mps = [&mp_en, &mp_es, &mp_en];
+
+        // Each letter has an even page count.
+        let mut manifest = Manifest::new("test");
+        let pags = chunk_letter(&tmpls, &mps, true, None, &mut manifest)
+            .unwrap()
+            .seg_pags();
+        let txts: Vec<&str> = pags.iter().map(|pag| pag[0].txt.as_str()).collect();
+        assert_eq!(
+            txts,
+            vec![
+                "Dear Jane Doe,",
+                " ",
+                "Dear Jenniffer González-Colón,",
+                "Sincerely,",
+                "Dear Jane Doe,",
+                " ",
+            ]
+        );
+        assert_eq!(manifest.pieces, 3);
+
+        // Simplex letters are unpadded.
+        let ltr = chunk_letter(&tmpls, &mps, false, None, &mut manifest).unwrap();
+        assert_eq!(ltr.seg_pags().len(), 4);
+        assert_eq!(ltr_kind(true), "ltr_duplex");
+        assert_eq!(
+            chunk_name("A", 0, 3, 50, ltr_kind(false)),
+            "A_1of03_cnt50_ltr"
+        );
+    }
+
+    /// A consistent mailing of a five digit tray and a mixed AADC tray.
+    fn valid_mailing() -> Mailing {
+        let tray = |barcode_id: BarcodeId, ids: std::ops::Range<u32>| MailTray {
+            name: format!("{barcode_id:?}"),
+            size: OneFoot,
+            barcode_id,
+            mailpieces: ids
+                .map(|id| Mailpiece {
+                    id,
+                    barcode: placeholder_barcode(),
+                    ..mp("A", "1 STATE ST", 0)
+                })
+                .collect(),
+        };
+        let mut mailing = Mailing::new();
+        mailing.trays = vec![
+            tray(BarcodeId::FiveDigit, 1..4),
+            tray(BarcodeId::MixedAadc, 4..6),
+        ];
+        mailing.mailpiece_cnt = 5;
+        mailing.tray_1ft_cnt = 2;
+        mailing.five_dig_cnt = 3;
+        mailing.mixed_aadc_cnt = 2;
+        mailing.postage_subtotal_five_dig = PRC_FIVE_DIG.times(3);
+        mailing.postage_subtotal_mixed_aadc = PRC_MIXED_AADC.times(2);
+        mailing.part_a_subtotal = (PRC_FIVE_DIG.times(3) + PRC_MIXED_AADC.times(2)).round_cents();
+        mailing
+    }
+
+    #[test]
+    fn test_validate() {
+        valid_mailing().validate().unwrap();
+
+        // Barcodes may be empty before fetching.
+        let mut mailing = valid_mailing();
+        mailing.trays[0].mailpieces[0].barcode.clear();
+        mailing.validate().unwrap();
+
+        type Violation = fn(&mut Mailing);
+        let cases: Vec<(Violation, &str)> = vec![
+            (
+                |mailing| mailing.mailpiece_cnt = 6,
+                "mailpiece_cnt 6 != 5 tray pieces",
+            ),
+            (
+                |mailing| mailing.intl_cnt = 1,
+                "intl_cnt 1 != 0 intl pieces",
+            ),
+            (
+                |mailing| mailing.tray_2ft_cnt = 1,
+                "tray_1ft_cnt 2 + tray_2ft_cnt 1 != 2 trays",
+            ),
+            (
+                |mailing| {
+                    let mps = &mut mailing.trays[1].mailpieces;
+                    for id in 0..CAP_1FOOT as u32 {
+                        mps.push(Mailpiece {
+                            id: 1_000 + id,
+                            ..mps[0].clone()
+                        });
+                    }
+                    mailing.mailpiece_cnt += CAP_1FOOT as u16;
+                    mailing.mixed_aadc_cnt += CAP_1FOOT as u16;
+                    mailing.part_a_subtotal = (PRC_FIVE_DIG.times(3)
+                        + PRC_MIXED_AADC.times(2 + CAP_1FOOT as u64))
+                    .round_cents();
+                },
+                "tray MixedAadc has 602 pieces over capacity 600",
+            ),
+            (
+                |mailing| mailing.trays[1].mailpieces[0].id = 1,
+                "duplicate mailpiece id 1",
+            ),
+            (
+                |mailing| mailing.trays[0].mailpieces[1].barcode = "FADT".into(),
+                "mailpiece 2 barcode has 4 bars",
+            ),
+            (
+                |mailing| mailing.trays[1].barcode_id = BarcodeId::Default,
+                "2 tray pieces without a price category",
+            ),
+            (
+                |mailing| mailing.five_dig_cnt = 4,
+                "five_dig_cnt 4 != 3 tray pieces",
+            ),
+            (
+                |mailing| mailing.part_a_subtotal = mailing.part_a_subtotal + Money(10),
+                "part_a_subtotal 0.95 != 0.94 recomputed",
+            ),
+        ];
+        for (violation, expected) in cases {
+            let mut mailing = valid_mailing();
+            violation(&mut mailing);
+            let err = mailing.validate().unwrap_err().to_string();
+            assert!(
+                err.contains(expected),
+                "Failed to match: {} {}",
+                expected,
+                err
+            );
+        }
+    }
+
+    #[test]
+    fn test_write_manifest() {
+        let dir = TempDir::new("manifest_csv");
+        let pth = dir.join(FLE_NAME_MANIFEST_CSV);
+
+        let mut mailing = valid_mailing();
+        mailing.trays.insert(
+            1,
+            MailTray {
+                name: "ThreeDigit".into(),
+                size: TwoFoot,
+                barcode_id: BarcodeId::ThreeDigit,
+                mailpieces: vec![Mailpiece {
+                    id: 9,
+                    zip4: 42,
+                    barcode: placeholder_barcode(),
+                    ..mp("B", "2 STATE ST", 0)
+                }],
+            },
+        );
+        mailing.write_manifest(&pth).unwrap();
+
+        let mut rdr = csv::Reader::from_path(&pth).unwrap();
+        assert_eq!(
+            rdr.headers().unwrap(),
+            vec![
+                "id",
+                "tray",
+                "tray_size",
+                "barcode_id",
+                "presort",
+                "tray_label",
+                "name",
+                "address1",
+                "address2",
+                "city",
+                "state",
+                "zip5",
+                "zip4",
+                "delivery_point",
+                "imb"
+            ]
+        );
+        let rows: Vec<ManifestRow> = rdr.deserialize().map(|row| row.unwrap()).collect();
+        assert_eq!(rows.len(), 6);
+
+        // Pieces follow tray order.
+        assert_eq!(
+            rows.iter().map(|row| row.id).collect::<Vec<_>>(),
+            vec![1, 2, 3, 9, 4, 5]
+        );
+        assert_eq!(
+            rows.iter()
+                .map(|row| row.tray.as_str())
+                .dedup()
+                .collect::<Vec<_>>(),
+            vec!["FiveDigit", "ThreeDigit", "MixedAadc"]
+        );
+        assert_eq!(rows[3].tray_size, TwoFoot);
+        assert_eq!(rows[5].tray_label, "LTRS MXD AADC");
+        assert_eq!(rows[3].barcode_id, "30");
+        assert_eq!(rows[3].zip4, "0042");
+        assert!(rows.iter().all(|row| row.imb.len() == IMB_BAR_CNT));
+    }
+
+    #[test]
+    fn test_recipient_filter() {
+        let cfg = MailingCfg {
+            recipient_filter: vec![Role::Political],
+            ..Default::default()
+        };
+        let cases = [
+            (Source::Military, false),
+            (Source::Nasa, false),
+            (Source::Executive, true),
+            (Source::Senate, true),
+            (Source::House, true),
+            (Source::Committee, true),
+            (Source::State, true),
+            (Source::Observer, false),
+        ];
+        for (src, expected) in cases {
+            assert_eq!(
+                cfg.is_role_allowed(&src.role()),
+                expected,
+                "Failed to match: {}",
+                src
+            );
+        }
+        assert!(MailingCfg::default().is_role_allowed(&Role::Military));
+
+        // Persons of older files are observers.
+        let json = r#"{"name": "Jane Doe", "title1": "", "title2": "", "url": "", "adrs": null}"#;
+        let mut per: Person = serde_json::from_str(json).unwrap();
+        assert_eq!(per.role, Role::Observer);
+        assert!(cfg.check_roles(std::slice::from_ref(&per)).is_err());
+        per.role = Role::Political;
+        assert!(cfg.check_roles(&[per]).is_ok());
+    }
+
+    #[test]
+    fn test_id_allocator() {
+        let dir = TempDir::new("ids");
+        let pth = dir.pth("mailing_cfg.json");
+        let mut cfg = MailingCfg {
+            last_mailpiece_id: 100,
+            ..Default::default()
+        };
+        cfg.ps.last_statement_id = 7;
+        write_to_file(&cfg, &pth).unwrap();
+
+        // Consecutive mailings get non-overlapping ids.
+        let mut id_alloc = IdAllocator::load(&pth).unwrap();
+        let ids1 = id_alloc.reserve_mailpiece_ids(5).unwrap();
+        assert_eq!(ids1, 101..106);
+        assert_eq!(id_alloc.reserve_statement_id().unwrap(), 8);
+        id_alloc.commit().unwrap();
+
+        let mut id_alloc = IdAllocator::load(&pth).unwrap();
+        let ids2 = id_alloc.reserve_mailpiece_ids(3).unwrap();
+        assert_eq!(ids2, 106..109);
+        assert!(ids2.clone().all(|id| !ids1.contains(&id)));
+        assert_eq!(id_alloc.reserve_statement_id().unwrap(), 9);
+
+        // Uncommitted reservations are reused.
+        let mut id_alloc = IdAllocator::load(&pth).unwrap();
+        assert_eq!(id_alloc.reserve_mailpiece_ids(3).unwrap(), ids2);
+        let cfg: MailingCfg = read_from_file(&pth).unwrap();
+        assert_eq!(cfg.last_mailpiece_id, 105);
+        assert_eq!(cfg.ps.last_statement_id, 8);
+
+        // Ids fit the IMb serial.
+        id_alloc.cfg.last_mailpiece_id = IMB_SERIAL_MAX - 2;
+        assert!(id_alloc.reserve_mailpiece_ids(3).is_err());
+        assert_eq!(
+            id_alloc.reserve_mailpiece_ids(2).unwrap(),
+            IMB_SERIAL_MAX - 1..IMB_SERIAL_MAX + 1
+        );
+    }
+
+    #[test]
+    fn test_reserve_statement_id() {
+        let mut id_alloc = IdAllocator {
+            pth: String::new(),
+            cfg: MailingCfg::default(),
+        };
+        id_alloc.cfg.ps.last_statement_id = 7;
+
+        // A new mailing reserves the next statement id.
+        let mut mailing = Mailing::new();
+        assert!(mailing.reserve_statement_id(&mut id_alloc).unwrap());
+        assert_eq!(mailing.statement_id, Some(8));
+
+        // A rerun of the written mailing keeps its id.
+        let json = serde_json::to_string(&mailing).unwrap();
+        let mut rerun: Mailing = serde_json::from_str(&json).unwrap();
+        assert!(!rerun.reserve_statement_id(&mut id_alloc).unwrap());
+        assert_eq!(rerun.statement_id, Some(8));
+        assert_eq!(id_alloc.cfg.ps.last_statement_id, 8);
+    }
+
+    /// Mailpieces spread over zip5s, none reaching a FiveDigit tray.
+    fn mps_of(zip5s: &[u32], cnt: usize) -> Vec<Mailpiece> {
+        (0..cnt)
+            .map(|idx| Mailpiece {
+                id: idx as u32,
+                zip5: zip5s[idx % zip5s.len()],
+                ..mp("A", "1 STATE ST", 0)
+            })
+            .collect()
+    }
+
+    /// Barcode ids and piece counts of trays.
+    fn tray_ids(trays: &[MailTray]) -> Vec<(BarcodeId, usize)> {
+        trays
+            .iter()
+            .map(|tray| (tray.barcode_id, tray.mailpieces.len()))
+            .collect()
+    }
+
+    #[test]
+    fn test_presort_three_digit() {
+        use BarcodeId::*;
+        // Zip3 124 is outside the L801 excerpt.
+        let zip5s = [12401, 12402, 12403];
+        let cases = [
+            (149, vec![(MixedAadc, 149)]),
+            (150, vec![(ThreeDigit, 150)]),
+            (151, vec![(ThreeDigit, 151)]),
+        ];
+        for (cnt, expected) in cases {
+            let trays = presort_mailpieces(mps_of(&zip5s, cnt));
+            assert_eq!(tray_ids(&trays), expected, "Failed to match: {}", cnt);
+        }
+
+        // FiveDigit groups are taken first.
+        let mut mps = mps_of(&[12401], PRESORT_MIN_FIVE_DIG);
+        mps.extend(mps_of(&[12402, 12403], 150));
+        let trays = presort_mailpieces(mps);
+        assert_eq!(
+            tray_ids(&trays),
+            vec![(FiveDigit, PRESORT_MIN_FIVE_DIG), (ThreeDigit, 150)]
+        );
+        assert_eq!(
+            trays
+                .iter()
+                .map(|tray| tray.name.as_str())
+                .collect::<Vec<_>>(),
+            vec!["A", "B"]
+        );
+    }
+
+    #[test]
+    fn test_presort_aadc() {
+        use BarcodeId::*;
+        // Zip3s 120 and 121 share an AADC, each below a ThreeDigit tray.
+        let zip5s = [12001, 12101];
+        let cases = [
+            (149, vec![(MixedAadc, 149)]),
+            (150, vec![(Aadc, 150)]),
+            (151, vec![(Aadc, 151)]),
+        ];
+        for (cnt, expected) in cases {
+            let trays = presort_mailpieces(mps_of(&zip5s, cnt));
+            assert_eq!(tray_ids(&trays), expected, "Failed to match: {}", cnt);
+        }
+
+        // Short AADC groups join zip3s outside the excerpt in mixed AADC.
+        let mut mps = mps_of(&zip5s, 100);
+        mps.extend(mps_of(&[12401], 20));
+        let trays = presort_mailpieces(mps);
+        assert_eq!(tray_ids(&trays), vec![(MixedAadc, 120)]);
+
+        assert_eq!(aadc_of(122), Some("AADC ALBANY NY 120"));
+        assert_eq!(aadc_of(124), None);
+    }
+
+    #[test]
+    fn test_build() {
+        use BarcodeId::*;
+        // A ThreeDigit zip3, and an AADC too small for its own tray.
+        let mut pers: Vec<Person> = mps_of(&[12401, 12402, 12403], 150)
+            .iter()
+            .chain(mps_of(&[12001], 3).iter())
+            .map(per)
+            .collect();
+        let mut per_intl = per(&mp("Jean Dupont", "1 RUE DE RIVOLI", 0));
+        per_intl.adrs.as_mut().unwrap()[0].country = Some("FRANCE".into());
+        pers.push(per_intl);
+        let cfg = MailingCfg {
+            last_mailpiece_id: 100,
+            ..MailingCfg::default()
+        };
+
+        let mailing = Mailing::build(&pers, &cfg).unwrap();
+        mailing.validate().unwrap();
+
+        // Counts.
+        assert_eq!(mailing.mailpiece_cnt, 153);
+        assert_eq!(mailing.intl_cnt, 1);
+        assert_eq!(mailing.intl[0].name, "Jean Dupont");
+        assert_eq!((mailing.tray_1ft_cnt, mailing.tray_2ft_cnt), (2, 0));
+
+        // Trays, with ids after the config in zip order.
+        assert_eq!(
+            tray_ids(&mailing.trays),
+            vec![(ThreeDigit, 150), (MixedAadc, 3)]
+        );
+        let ids: Vec<u32> = mailing.trays[1].mailpieces.iter().map(|mp| mp.id).collect();
+        assert_eq!(ids, vec![101, 102, 103]);
+        assert!(mailing.trays[0].mailpieces.iter().all(|mp| mp.id > 103));
+        assert_eq!(mailing.trays[0].name, tray_name(0));
+
+        // Pricing.
+        assert_eq!((mailing.three_dig_cnt, mailing.mixed_aadc_cnt), (150, 3));
+        assert_eq!((mailing.five_dig_cnt, mailing.aadc_cnt), (0, 0));
+        assert_eq!(
+            mailing.postage_subtotal_three_dig,
+            Money::from_mills(28_350)
+        );
+        assert_eq!(mailing.postage_subtotal_mixed_aadc, Money::from_mills(624));
+        assert_eq!(mailing.part_a_subtotal, Money::from_mills(28_970));
+
+        // Persons without addresses and filtered roles are errors.
+        let mut per_none = per(&mp("Jane Doe", "1 MAIN ST", 0));
+        per_none.adrs = None;
+        assert!(Mailing::build(&[per_none], &cfg).is_err());
+        let cfg = MailingCfg {
+            recipient_filter: vec![Role::Political],
+            ..MailingCfg::default()
+        };
+        assert!(Mailing::build(&pers, &cfg).is_err());
+    }
+
+    #[test]
+    fn test_build_address2() {
+        let mut per = per(&mp("Jane Doe", "1919 SMITH ST", 1234));
+        let adr = &mut per.adrs.as_mut().unwrap()[0];
+        adr.address2 = Some("STE 9047".into());
+        adr.delivery_point = Some("47".into());
+        adr.standardized = true;
+        let unmerged = new_mailpiece(&per, &per.adrs.as_ref().unwrap()[0]);
+        let cases = [
+            (None, "1919 SMITH ST STE 9047", None),
+            (Some(20), "1919 SMITH ST", Some("STE 9047")),
+        ];
+        for (address1_max, address1, address2) in cases {
+            let cfg = MailingCfg {
+                address1_max,
+                ..Default::default()
+            };
+            let mailing = Mailing::build(std::slice::from_ref(&per), &cfg).unwrap();
+            let mp = &mailing.trays[0].mailpieces[0];
+            assert_eq!(mp.address1, address1, "Failed to match: {}", address1);
+            assert_eq!(
+                mp.address2.as_deref(),
+                address2,
+                "Failed to match: {}",
+                address1
+            );
+            assert_eq!(mp.routing_code(), unmerged.routing_code());
+        }
+    }
+
+    #[test]
+    fn test_mailed_persons() {
+        let pers = vec![
+            per(&mp("Jane Doe", "1 MAIN ST", 0)),
+            per(&mp("John Roe", "2 MAIN ST", 0)),
+        ];
+        let overrides = LanguageOverrides {
+            persons: BTreeMap::from([("John Roe".to_string(), "es".to_string())]),
+        };
+        let suppressions = SuppressionList {
+            suppressions: vec![Suppression {
+                name: "Jane Doe".into(),
+                zip5s: Vec::new(),
+            }],
+        };
+        let (mailed, suppressed) = mailed_persons(&pers, &overrides, &suppressions);
+        assert_eq!(suppressed, vec!["Jane Doe 12224"]);
+        assert_eq!(mailed[0].adr_len(), 0);
+        assert_eq!(mailed[1].language.as_deref(), Some("es"));
+        assert_eq!(mailed[1].adr_len(), 1);
+    }
+
+    #[test]
+    fn test_segment_trays() {
+        let cases = [
+            (1199, vec![(TwoFoot, 1199)]),
+            (1200, vec![(TwoFoot, 1200)]),
+            (1201, vec![(TwoFoot, 601), (OneFoot, 600)]),
+            (1300, vec![(TwoFoot, 650), (TwoFoot, 650)]),
+            (2500, vec![(TwoFoot, 1200), (TwoFoot, 650), (TwoFoot, 650)]),
+        ];
+        for (cnt, expected) in cases {
+            let mut mps = mps_of(&[12401, 12402, 12403], cnt);
+            mps.sort_by_key(|mp| mp.zip5);
+            let trays = segment_trays(BarcodeId::MixedAadc, mps.clone());
+            assert_eq!(
+                trays
+                    .iter()
+                    .map(|tray| (tray.size.clone(), tray.mailpieces.len()))
+                    .collect::<Vec<_>>(),
+                expected,
+                "Failed to match: {}",
+                cnt
+            );
+
+            // Zip order is kept within and across trays.
+            let ids: Vec<u32> = trays
+                .iter()
+                .flat_map(|tray| tray.mailpieces.iter().map(|mp| mp.id))
+                .collect();
+            assert_eq!(
+                ids,
+                mps.iter().map(|mp| mp.id).collect::<Vec<_>>(),
+                "Failed to match: {}",
+                cnt
+            );
+        }
+    }
+
+    #[test]
+    fn test_tray_name() {
+        let cases = [
+            (0, "A"),
+            (25, "Z"),
+            (26, "AA"),
+            (27, "AB"),
+            (51, "AZ"),
+            (52, "BA"),
+            (702, "AAA"),
+        ];
+        for (idx, expected) in cases {
+            assert_eq!(tray_name(idx), expected, "Failed to match: {}", idx);
+        }
+
+        // A mailing with more than 26 trays is named past "Z".
+        let mps = mps_of(&[12401], 27 * CAP_2FOOT);
+        let trays = presort_mailpieces(mps);
+        assert_eq!(trays.len(), 27);
+        assert_eq!(trays[25].name, "Z");
+        assert_eq!(trays[26].name, "AA");
+    }
+
+    #[test]
+    fn test_create_placard() {
+        use BarcodeId::*;
+        let tray = |barcode_id, zip5s: &[u32]| MailTray {
+            name: "A".into(),
+            size: OneFoot,
+            barcode_id,
+            mailpieces: mps_of(zip5s, 3),
+        };
+        let cases = [
+            (FiveDigit, &[47547][..], (47547, 47547), "LTRS 5D 47547"),
+            (
+                FiveDigit,
+                &[47547, 47546],
+                (47546, 47547),
+                "LTRS 5D 47546-47547",
+            ),
+            (FiveDigit, &[2109], (2109, 2109), "LTRS 5D 02109"),
+            (ThreeDigit, &[12401], (12401, 12401), "LTRS 3D 124"),
+            (Aadc, &[12101], (12101, 12101), "LTRS AADC 120"),
+            (Aadc, &[12401], (12401, 12401), "LTRS MXD AADC"),
+            (MixedAadc, &[47547, 12401], (12401, 47547), "LTRS MXD AADC"),
+        ];
+        for (barcode_id, zip5s, zip_range, expected) in cases {
+            let tray = tray(barcode_id, zip5s);
+            assert_eq!(tray.zip_range(), zip_range, "Failed to match: {:?}", zip5s);
+            assert_eq!(tray.label_line(), expected, "Failed to match: {:?}", zip5s);
+        }
+        let empty = MailTray {
+            mailpieces: Vec::new(),
+            ..tray(MixedAadc, &[12401])
+        };
+        assert_eq!(empty.zip_range(), (0, 0));
+
+        let dir = TempDir::new("placard");
+        let mut cfg = MailingCfg::default();
+        cfg.from.name = "UAP Awareness".into();
+        cfg.indicia.permit_id = "1234".into();
+        tray(FiveDigit, &[47547])
+            .create_placard(&cfg, &dir)
+            .unwrap();
+        // Text operators of the placard page.
+        let doc = lopdf::Document::load(dir.join("A_placard.pdf")).unwrap();
+        let txts = page_txts(&doc, 0);
+        for lne in ["LTRS 5D 47547", "Pieces: 3", "Mailer: UAP Awareness"] {
+            assert!(txts.contains(&lne.to_string()), "Failed to match: {}", lne);
+        }
+    }
+
+    #[test]
+    fn test_prs_letter_md() {
+        let md = "# UAP Disclosure\n\
+            \n\
+            Dear {{name}},\n\
+            \n\
+            Please hold *public* hearings\n\
+            on **UAP** this session.\n\
+            ---\n\
+            Sincerely, {{name}}'s constituents\n";
+        let mut doc = prs_letter_md(md, &new_ansi_letter());
+        let pars: Vec<&Par> = doc
+            .elms
+            .iter()
+            .filter_map(|elm| match elm {
+                Elm::Par(par) => Some(par),
+                Elm::PagBrk => None,
+            })
+            .collect();
+        assert_eq!(pars.len(), 4);
+        assert_eq!(pars[0].txt, "UAP Disclosure");
+        assert_eq!(pars[0].fnt_sty, Some(Style::Bold));
+        assert_eq!(
+            pars[2].txt,
+            "Please hold *public* hearings on **UAP** this session."
+        );
+        assert!(matches!(doc.elms[3], Elm::PagBrk));
+
+        // A paragraph mixing styles renders each run in its style.
+        validate_markup(&doc).unwrap();
+        let runs = prs_runs(&pars[2].txt).unwrap();
+        let run = |txt: &str, sty: Style| Run {
+            txt: txt.into(),
+            sty,
+        };
+        assert_eq!(
+            runs,
+            vec![
+                run("Please hold ", Style::Normal),
+                run("public", Style::Italic),
+                run(" hearings on ", Style::Normal),
+                run("UAP", Style::Bold),
+                run(" this session.", Style::Normal),
+            ]
+        );
+
+        doc.replace_par_at(1, FLD_NAME, "Jane Doe");
+        doc.replace_par_at(4, FLD_NAME, "Jane Doe");
+        let txt = |elm: &Elm| match elm {
+            Elm::Par(par) => par.txt.clone(),
+            Elm::PagBrk => String::new(),
+        };
+        assert_eq!(txt(&doc.elms[1]), "Dear Jane Doe,");
+        assert_eq!(txt(&doc.elms[4]), "Sincerely, Jane Doe's constituents");
+    }
+
+    #[test]
+    fn test_mailing_diff() {
+        let mailing = |name: &str, mps: Vec<Mailpiece>| {
+            let mut mailing = Mailing::new();
+            mailing.name = name.into();
+            mailing.trays = vec![MailTray {
+                name: "FiveDigit".into(),
+                size: OneFoot,
+                barcode_id: BarcodeId::Default,
+                mailpieces: mps,
+            }];
+            mailing
+        };
+        let mp_id = |id: u32, name: &str, address1: &str, zip4: u16| Mailpiece {
+            id,
+            ..mp(name, address1, zip4)
+        };
+        let old = mailing(
+            "old",
+            vec![
+                mp_id(1, "Jane Doe", "1 STATE ST", 1),
+                mp_id(2, "John Roe", "2 STATE ST", 2),
+                mp_id(3, "Ann Poe", "3 STATE ST", 3),
+            ],
+        );
+        let new = mailing(
+            "new",
+            vec![
+                mp_id(7, "Jane Doe", "1 STATE ST", 1),
+                mp_id(8, "JOHN ROE", "2 STATE ST", 9),
+                mp_id(9, "Bo Loe", "4 STATE ST", 4),
+            ],
+        );
+
+        let diff = mailing_diff(&old, &new);
+        assert_eq!(diff.unchanged_cnt, 1);
+        assert_eq!(diff.added_cnt, 1);
+        assert_eq!(diff.added[0].name, "Bo Loe");
+        assert_eq!(diff.removed_cnt, 1);
+        assert_eq!(diff.removed[0].name, "Ann Poe");
+
+        // Recipients match by name across differing ids.
+        assert_eq!(diff.changed_cnt, 1);
+        assert_eq!(diff.changed[0].old.id, 2);
+        assert_eq!(diff.changed[0].new.id, 8);
+        assert_eq!(diff.changed[0].fields, vec!["zip4"]);
+
+        let table = diff.table();
+        assert_eq!(table.lines().count(), 4);
+        assert!(table.contains("changed (zip4)"));
+        assert_eq!(mailing_diff(&old, &old).unchanged_cnt, 3);
+    }
+}
@@ -1,60 +1,331 @@
 #![allow(unused)]
 
-#[macro_use]
-extern crate lazy_static;
-
+use adr::acs::*;
+use adr::audit::*;
+use adr::cli::*;
+use adr::committee::*;
+use adr::coverage::*;
+use adr::diff::*;
+use adr::executive::*;
+use adr::export::*;
+use adr::fixture::*;
+use adr::house::*;
+use adr::io::*;
+use adr::letter::*;
+use adr::mailing::{self, *};
+use adr::manual::*;
+use adr::military::*;
+use adr::models::*;
+use adr::nasa::*;
+use adr::observer::*;
+use adr::packing::*;
+use adr::prsr::*;
+use adr::refresh::*;
+use adr::report::*;
+use adr::senate::*;
+use adr::state::*;
+use adr::tracking::*;
+use adr::usps::*;
+use adr::verify::*;
 use anyhow::{anyhow, Result};
-mod core;
-mod envelope;
-mod executive;
-mod house;
-mod mailing;
-mod military;
-mod models;
-mod nasa;
-mod observer;
-mod prsr;
-mod senate;
-mod state;
-mod usps;
-mod postage_statement;
-use core::*;
-use executive::*;
-use house::*;
-use mailing::*;
-use military::*;
-use models::*;
-use nasa::*;
-use observer::*;
-use prsr::*;
-use senate::*;
-use state::*;
-use usps::*;
+use std::env;
+use std::path::Path;
+use tracing_subscriber::EnvFilter;
 
 #[tokio::main]
 pub async fn main() -> Result<()> {
+    // Log levels are set by RUST_LOG, such as `RUST_LOG=trace`.
+    tracing_subscriber::fmt()
+        .with_env_filter(
+            EnvFilter::try_from_default_env().unwrap_or_else(|_| EnvFilter::new("info")),
+        )
+        .with_writer(std::io::stderr)
+        .init();
+
+    // Set the data directory before any data file is read.
+    let args: Vec<String> = env::args().skip(1).collect();
+    let (data_dir, args) = prs_data_dir(&args)?;
+    *DATA_DIR.lock().unwrap() = data_dir;
+    // A malformed known bad list fails the run before parsing.
+    *KNOWN_BAD.lock().unwrap() = Some(KnownBad::load(FLE_PTH_KNOWN_BAD)?);
+
+    // Run developer commands.
+    let (only, person) = match prs_args(&args)? {
+        Cmd::Run {
+            is_dry_run,
+            is_barcode_cache_bypass,
+            only,
+            person,
+            is_refresh_members,
+            members_max_days,
+        } => {
+            REFRESH_MEMBERS.store(is_refresh_members, std::sync::atomic::Ordering::Relaxed);
+            MEMBERS_MAX_DAYS.store(
+                members_max_days.unwrap_or_default(),
+                std::sync::atomic::Ordering::Relaxed,
+            );
+            BARCODE_CACHE_BYPASS.store(
+                is_barcode_cache_bypass,
+                std::sync::atomic::Ordering::Relaxed,
+            );
+            if is_dry_run {
+                DRY_RUN.store(true, std::sync::atomic::Ordering::Relaxed);
+                // Writes land in a scratch copy, so ids and person files are untouched.
+                let dir = redirect_dry_run()?;
+                summary_add(
+                    "Dry run",
+                    vec![
+                        "addresses unstandardized".into(),
+                        "placeholder barcodes, refetched by the next run".into(),
+                        format!("files written to {}", dir.display()),
+                    ],
+                    true,
+                );
+            }
+            (only, person)
+        }
+        Cmd::RecordFixture {
+            url,
+            name,
+            source,
+            force,
+        } => {
+            record_fixture(&url, &name, source, force).await?;
+            return Ok(());
+        }
+        Cmd::Refresh {
+            notify,
+            sources,
+            is_exit_code,
+            force,
+        } => {
+            if force {
+                FORCE_SOURCES
+                    .lock()
+                    .unwrap()
+                    .extend(sources.iter().copied());
+            }
+            let rpt = refresh(&sources).await;
+            write_report(&rpt, &notify)?;
+            if is_exit_code && !rpt.is_empty() {
+                std::process::exit(EXIT_CHANGES);
+            }
+            return Ok(());
+        }
+        Cmd::VerifyArtifacts { dir } => {
+            let artifacts = verify_artifacts(&data_pth(&dir))?;
+            let is_warn = artifacts
+                .iter()
+                .any(|(_, status)| *status != ArtifactStatus::Verified);
+            summary_add("Artifacts", artifact_lnes(&artifacts), is_warn);
+            std::process::exit(summary_print());
+        }
+        Cmd::Track { events, days } => {
+            let mailing = read_from_file_checked::<Mailing>(mailing::FLE_PTH)?;
+            let mut tracking = Tracking::load(&mailing)?;
+            let cnt = tracking.ingest(&mailing, &std::fs::read_to_string(&events)?)?;
+            tracking.save(&mailing)?;
+            eprintln!("{cnt} new tracking events");
+            let now = chrono::Local::now().naive_local();
+            let rpt = reconcile(&mailing, &tracking, now, days);
+            summary_add("Tracking", rpt.lnes(), !rpt.is_empty());
+            if !tracking.unmatched.is_empty() {
+                summary_add("Unmatched tracking events", tracking.unmatched, true);
+            }
+            std::process::exit(summary_print());
+        }
+        Cmd::Acs { corrections } => {
+            let mailing = read_from_file_checked::<Mailing>(mailing::FLE_PTH)?;
+            let stats =
+                apply_correction_files(&mailing, &AUDIT_PER_PTHS, Path::new(&corrections)).await?;
+            summary_add("ACS", stats.lnes(), !stats.unmatched.is_empty());
+            std::process::exit(summary_print());
+        }
+        Cmd::Diff { old, new } => {
+            let old = read_from_file_checked::<Mailing>(&old)?;
+            let new = read_from_file_checked::<Mailing>(&new)?;
+            let diff = mailing_diff(&old, &new);
+            diff.write(mailing::FLE_PTH_DIFF)?;
+            print!("{}", diff.table());
+            summary_add("Mailing diff", diff.lnes(), false);
+            std::process::exit(summary_print());
+        }
+        Cmd::PurgeUspsCache => {
+            let cnt = UspsCache::purge(FLE_PTH_USPS_CACHE)?;
+            eprintln!("{cnt} cached USPS results purged");
+            return Ok(());
+        }
+        Cmd::InvalidateCache { url_prefix } => {
+            let cnt = invalidate_cache(&url_prefix)?;
+            eprintln!("{cnt} cached pages invalidated");
+            return Ok(());
+        }
+        Cmd::Verify { is_apply } => {
+            let diffs = verify_files(&AUDIT_PER_PTHS, is_apply, &UspsFallback::default()).await?;
+            write_to_file(&diffs, FLE_PTH_VERIFY)?;
+            summary_add("Verify", verify_lnes(&diffs), !diffs.is_empty());
+            std::process::exit(summary_print());
+        }
+        Cmd::Audit { mailing: name } => {
+            let mailing_pth = data_pth(mailing::FLE_PTH);
+            let mailing = read_from_file::<Mailing>(mailing::FLE_PTH)?;
+            let name = name.unwrap_or(mailing.name);
+            let dir = mailings_dir().join(&name);
+            let pers = read_persons(&audit_per_pths(&CFG), &CFG)?;
+            let mut rpt = audit(&mailing_pth, &pers, &dir)?;
+            if rpt.mailing != name {
+                rpt.discrepancies
+                    .push(format!("{} is mailing {}", mailing::FLE_PTH, rpt.mailing));
+            }
+            summary_add("Audit", rpt.lnes(), !rpt.is_pass());
+            std::process::exit(summary_print());
+        }
+    };
+    let is_selected = !only.is_empty() || person.is_some();
+
     // Load addresses from disk or network.
-    let mut military = Military::load().await?;
-    let mut nasa = Nasa::load().await?;
-    let mut executive = Executive::load().await?;
-    let mut senate = Senate::load().await?;
-    let mut house = House::load().await?;
-    let mut state = State::load().await?;
-    let mut observer = Observer::load().await?;
+    // Sources of roles filtered out, or not selected, are skipped.
+    let is_loaded =
+        |src: Source| CFG.is_role_allowed(&src.role()) && (only.is_empty() || only.contains(&src));
+
+    // Clear the cached addresses and pages of a person to refetch them.
+    if let Some(name) = &person {
+        let mut urls = Vec::new();
+        for src in SOURCES.into_iter().filter(|src| is_loaded(*src)) {
+            urls.extend(clear_per_adrs(checkpoint_pth(src)?, name)?);
+        }
+        if urls.is_empty() {
+            return Err(anyhow!("no person named '{name}' in the selected sources"));
+        }
+        for url in urls.iter().filter(|url| !url.is_empty()) {
+            invalidate_cache(url)?;
+        }
+        summary_add(
+            "Refetch",
+            vec![format!("{name}: {} persons", urls.len())],
+            false,
+        );
+    }
+
+    let mut military = if is_loaded(Source::Military) {
+        Military::load().await?
+    } else {
+        Military::new()
+    };
+    let mut nasa = if is_loaded(Source::Nasa) {
+        Nasa::load().await?
+    } else {
+        Nasa::new()
+    };
+    let mut executive = if is_loaded(Source::Executive) {
+        Executive::load().await?
+    } else {
+        Executive::new()
+    };
+    let mut senate = if is_loaded(Source::Senate) {
+        Senate::load().await?
+    } else {
+        Senate::new()
+    };
+    let mut house = if is_loaded(Source::House) {
+        House::load().await?
+    } else {
+        House::new()
+    };
+    let mut committee = if is_loaded(Source::Committee) {
+        Committee::load().await?
+    } else {
+        Committee::new()
+    };
+    let mut state = if is_loaded(Source::State) {
+        State::load().await?
+    } else {
+        State::new()
+    };
+    let mut observer = if is_loaded(Source::Observer) {
+        Observer::load().await?
+    } else {
+        Observer::new()
+    };
+    if !CFG.recipient_filter.is_empty() {
+        let roles: Vec<String> = CFG.recipient_filter.iter().map(|r| r.to_string()).collect();
+        let skipped: Vec<String> = SOURCES
+            .into_iter()
+            .filter(|src| !CFG.is_role_allowed(&src.role()))
+            .map(|src| src.to_string())
+            .collect();
+        summary_add(
+            "Recipient filter",
+            vec![
+                format!("roles {}", roles.join(", ")),
+                format!("skipped {}", skipped.join(", ")),
+            ],
+            false,
+        );
+    }
+
+    // Selected sources or persons are loaded only, keeping the mailing.
+    if is_selected {
+        std::process::exit(summary_print());
+    }
+
+    // Report coverage gaps before mailing.
+    if CFG.is_role_allowed(&Role::Political) {
+        let coverage = coverage_report(&senate, &house, &state);
+        summary_add("Coverage", coverage.lnes(), !coverage.is_complete());
+    }
 
     // Combine people into single list.
+    // Sources are parallel to persons for the csv export.
     let mut pers = Vec::with_capacity(1_076);
-    pers.extend(military.persons);
-    pers.extend(nasa.persons);
-    pers.extend(executive.persons);
-    pers.extend(senate.persons);
-    pers.extend(house.persons);
-    pers.extend(state.persons);
-    pers.extend(observer.persons);
+    let mut srcs = Vec::with_capacity(1_076);
+    for (src, src_pers) in [
+        (Source::Military, military.persons),
+        (Source::Nasa, nasa.persons),
+        (Source::Executive, executive.persons),
+        (Source::Senate, senate.persons),
+        (Source::House, house.persons),
+        // Members get committee addresses appended on dedup.
+        (Source::Committee, committee.persons),
+        (Source::State, state.persons),
+        (Source::Observer, observer.persons),
+    ] {
+        let mut rpt = REPORT.lock().unwrap();
+        rpt.begin(src);
+        for per in &src_pers {
+            rpt.record_person(per.adrs.as_ref().map_or(0, |adrs| adrs.len()));
+        }
+        srcs.extend(std::iter::repeat_n(src, src_pers.len()));
+        pers.extend(src_pers);
+    }
     eprintln!("{} people", pers.len());
 
+    // Report counts per source.
+    let rpt = REPORT.lock().unwrap().clone();
+    write_to_file(&rpt, FLE_PTH_REPORT)?;
+    summary_add("Sources", rpt.lnes(), false);
+
+    // Export persons alongside the JSON files.
+    export_csv(&pers, &srcs, &data_pth(FLE_PTH_CSV))?;
+
+    // Merge persons listed by several sources.
+    let per_cnt = pers.len();
+    let mut pers = dedup_persons(pers);
+    summary_add(
+        "Duplicate persons",
+        vec![format!("{} merged", per_cnt - pers.len())],
+        false,
+    );
+
     // Create mailing.
     let mut mailing = Mailing::load(&mut pers).await?;
 
+    // Print run summary.
+    summary_add_artifacts();
+    let code = summary_print();
+    if code != 0 {
+        std::process::exit(code);
+    }
+
     Ok(())
 }
@@ -0,0 +1,70 @@
+use crate::io::*;
+use crate::language::*;
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::path::Path;
+
+pub const FLE_NAME_MANIFEST: &str = "manifest.json";
+
+/// Contents of a mailing directory.
+#[derive(Debug, Default, Serialize, Deserialize, Clone, PartialEq, Eq)]
+pub struct Manifest {
+    pub mailing: String,
+    pub pieces: usize,
+    /// Pieces by letter language.
+    pub languages: BTreeMap<String, usize>,
+    /// Pieces of a language without a template, sent with the default template.
+    pub fallbacks: usize,
+    /// Chunk pdf file names, such as `T1_1of02_cnt150_env.pdf`, and merged tray pdfs.
+    #[serde(default)]
+    pub files: Vec<String>,
+}
+
+impl Manifest {
+    pub fn new(mailing: &str) -> Self {
+        Self {
+            mailing: mailing.into(),
+            ..Default::default()
+        }
+    }
+
+    /// Counts a piece of a letter language.
+    pub fn add_piece(&mut self, lang: Option<&str>, is_fallback: bool) {
+        self.pieces += 1;
+        *self
+            .languages
+            .entry(lang.unwrap_or(LANGUAGE_DEFAULT).into())
+            .or_default() += 1;
+        if is_fallback {
+            self.fallbacks += 1;
+        }
+    }
+
+    /// Records the file name of a chunk or merged tray pdf.
+    pub fn add_file(&mut self, pth: &Path) {
+        self.files
+            .push(pth.file_name().unwrap_or_default().to_string_lossy().into());
+    }
+
+    /// Lines of the run summary, such as `es: 12`.
+    pub fn lnes(&self) -> Vec<String> {
+        let mut lnes: Vec<String> = self
+            .languages
+            .iter()
+            .map(|(lang, cnt)| format!("{lang}: {cnt}"))
+            .collect();
+        if self.fallbacks != 0 {
+            lnes.push(format!(
+                "{} pieces without a template of their language",
+                self.fallbacks
+            ));
+        }
+        lnes
+    }
+
+    /// Writes `manifest.json` to a mailing directory.
+    pub fn write(&self, dir: &Path) -> Result<()> {
+        write_to_file_checked(self, &dir.join(FLE_NAME_MANIFEST).to_string_lossy())
+    }
+}
@@ -0,0 +1,244 @@
+use crate::io::*;
+use crate::models::*;
+use crate::usps::*;
+use anyhow::{anyhow, Result};
+use serde::{Deserialize, Serialize};
+use std::fs::File;
+use std::io::BufReader;
+use tracing::{debug, warn};
+
+/// Manual address entries merged ahead of scraping.
+pub const MANUAL_PTH: &str = "manual_addresses.json";
+
+/// Maximum edit distance of a near-match name.
+const NEAR_MATCH_DIST: usize = 3;
+
+/// Known addresses of a person whose site is unparseable.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct ManualEntry {
+    /// Canonical person name, as fetched from the source.
+    pub name: String,
+    pub source: Source,
+    /// Addresses as objects or single lines, such as `1 STATE ST, ALBANY, NY 12224`.
+    #[serde(deserialize_with = "de_adrs")]
+    pub adrs: Vec<Address>,
+    /// Whether scraping and url probing are bypassed.
+    #[serde(default)]
+    pub skip_scrape: bool,
+    /// Whether addresses are used without USPS standardization.
+    #[serde(default)]
+    pub verbatim: bool,
+}
+
+impl ManualEntry {
+    /// Addresses of the entry, standardized unless verbatim.
+    pub async fn std_adrs(&self) -> Result<Vec<Address>> {
+        if self.verbatim {
+            Ok(self.adrs.clone())
+        } else {
+            standardize_addresses(self.adrs.clone()).await
+        }
+    }
+
+    /// Appends addresses of the entry to scraped addresses, then dedups.
+    pub async fn append_to(&self, scraped: Option<Vec<Address>>) -> Result<Vec<Address>> {
+        let mut adrs = scraped.unwrap_or_default();
+        adrs.extend(self.std_adrs().await?);
+        Ok(dedup_addresses(adrs))
+    }
+}
+
+/// Manual address entries of all sources.
+#[derive(Debug, Default, Serialize, Deserialize, Clone)]
+pub struct ManualAddresses {
+    pub entries: Vec<ManualEntry>,
+}
+
+impl ManualAddresses {
+    /// Reads manual entries from a file; a missing file has no entries.
+    pub fn load(pth: &str) -> Result<ManualAddresses> {
+        if !data_pth(pth).exists() {
+            return Ok(ManualAddresses::default());
+        }
+        debug!("Reading file: {}", pth);
+        let file = File::open(data_pth(pth))?;
+        let entries: Vec<ManualEntry> = serde_json::from_reader(BufReader::new(file))
+            .map_err(|err| anyhow!("invalid manual addresses '{pth}': {err}"))?;
+        for entry in entries.iter() {
+            if entry.adrs.is_empty() {
+                return Err(anyhow!(
+                    "manual entry without addresses {} ({})",
+                    entry.name,
+                    entry.source
+                ));
+            }
+        }
+        Ok(ManualAddresses { entries })
+    }
+
+    /// Returns the entry of a person of a source.
+    pub fn get(&self, source: Source, name: &str) -> Option<&ManualEntry> {
+        self.entries
+            .iter()
+            .find(|entry| entry.source == source && entry.name == name)
+    }
+
+    /// Returns warnings for entries of a source naming no known person.
+    pub fn validate(&self, source: Source, pers: &[Person]) -> Vec<String> {
+        let mut lnes = Vec::new();
+        for entry in self.entries.iter().filter(|entry| entry.source == source) {
+            if pers.iter().any(|per| per.name == entry.name) {
+                continue;
+            }
+            let near: Vec<&str> = pers
+                .iter()
+                .map(|per| per.name.as_str())
+                .filter(|name| is_near_match(name, &entry.name))
+                .collect();
+            let mut lne = format!("unknown {source} name '{}'", entry.name);
+            if !near.is_empty() {
+                lne.push_str(&format!(", near matches: {}", near.join(", ")));
+            }
+            lnes.push(lne);
+        }
+        lnes
+    }
+
+    /// Validates entries of a source, adding warnings to the run summary.
+    pub fn validate_summary(&self, source: Source, pers: &[Person]) {
+        let lnes = self.validate(source, pers);
+        if !lnes.is_empty() {
+            for lne in lnes.iter() {
+                warn!("manual addresses: {lne}");
+            }
+            summary_add("Manual addresses", lnes, true);
+        }
+    }
+}
+
+/// Whether two names differ by a few edits or share a last name.
+fn is_near_match(a: &str, b: &str) -> bool {
+    let a = a.to_lowercase();
+    let b = b.to_lowercase();
+    edit_distance(&a, &b) <= NEAR_MATCH_DIST
+        || a.split_whitespace().last() == b.split_whitespace().last()
+}
+
+/// Levenshtein distance of two strings.
+fn edit_distance(a: &str, b: &str) -> usize {
+    let b: Vec<char> = b.chars().collect();
+    let mut row: Vec<usize> = (0..=b.len()).collect();
+    for (i, ca) in a.chars().enumerate() {
+        let mut prev = row[0];
+        row[0] = i + 1;
+        for (j, cb) in b.iter().enumerate() {
+            let cur = row[j + 1];
+            row[j + 1] = if ca == *cb {
+                prev
+            } else {
+                1 + prev.min(row[j]).min(cur)
+            };
+            prev = cur;
+        }
+    }
+    row[b.len()]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn adr(address1: &str, zip5: u32) -> Address {
+        Address {
+            address1: address1.into(),
+            city: "DENVER".into(),
+            state: "CO".into(),
+            zip5,
+            ..Default::default()
+        }
+    }
+
+    fn manual(skip_scrape: bool) -> ManualAddresses {
+        ManualAddresses {
+            entries: vec![ManualEntry {
+                name: "John W. Hickenlooper".into(),
+                source: Source::Senate,
+                adrs: vec![adr("1873 S BELLAIRE ST STE 810", 80222)],
+                skip_scrape,
+                verbatim: true,
+            }],
+        }
+    }
+
+    #[tokio::test]
+    async fn test_manual_skip_scrape() {
+        let manual = manual(true);
+        assert!(manual.get(Source::House, "John W. Hickenlooper").is_none());
+        let entry = manual.get(Source::Senate, "John W. Hickenlooper").unwrap();
+        assert!(entry.skip_scrape);
+        assert_eq!(entry.std_adrs().await.unwrap(), entry.adrs);
+    }
+
+    #[tokio::test]
+    async fn test_manual_append() {
+        let manual = manual(false);
+        let entry = manual.get(Source::Senate, "John W. Hickenlooper").unwrap();
+        assert!(!entry.skip_scrape);
+
+        // Appended after scraped addresses, duplicates removed.
+        let scraped = vec![
+            adr("120 HART SOB", 20510),
+            adr("1873 S BELLAIRE ST STE 810", 80222),
+        ];
+        let adrs = entry.append_to(Some(scraped.clone())).await.unwrap();
+        assert_eq!(adrs, scraped);
+
+        // Used alone when scraping found nothing.
+        let adrs = entry.append_to(None).await.unwrap();
+        assert_eq!(adrs, entry.adrs);
+    }
+
+    #[test]
+    fn test_manual_single_line() {
+        let json = r#"[{"name": "John W. Hickenlooper", "source": "senate", "adrs": [
+            "1873 S BELLAIRE ST STE 810, DENVER, CO 80222",
+            {"address1": "120 HART SOB", "address2": null, "city": "DENVER", "state": "CO",
+                "zip5": 20510, "zip4": 0, "delivery_point": null}
+        ]}]"#;
+        let entries: Vec<ManualEntry> = serde_json::from_str(json).unwrap();
+        assert_eq!(
+            entries[0].adrs,
+            vec![
+                adr("1873 S BELLAIRE ST STE 810", 80222),
+                adr("120 HART SOB", 20510)
+            ]
+        );
+
+        // A malformed line names the line.
+        let json = r#"[{"name": "Jane Doe", "source": "senate", "adrs": ["DENVER, CO"]}]"#;
+        let err = serde_json::from_str::<Vec<ManualEntry>>(json).unwrap_err();
+        assert!(err.to_string().contains("DENVER, CO"), "{err}");
+    }
+
+    #[test]
+    fn test_manual_unknown_name() {
+        let pers: Vec<Person> = ["John Hickenlooper", "Michael F. Bennet"]
+            .into_iter()
+            .map(|name| Person {
+                name: name.into(),
+                ..Default::default()
+            })
+            .collect();
+        let lnes = manual(true).validate(Source::Senate, &pers);
+        assert_eq!(
+            lnes,
+            vec!["unknown senate name 'John W. Hickenlooper', near matches: John Hickenlooper"]
+        );
+
+        // Known names and other sources don't warn.
+        let mut manual = manual(true);
+        manual.entries[0].name = "Michael F. Bennet".into();
+        assert!(manual.validate(Source::Senate, &pers).is_empty());
+        assert!(manual.validate(Source::House, &pers).is_empty());
+    }
+}
@@ -1,23 +1,19 @@
-use crate::core::*;
+use crate::facility::*;
+use crate::io::*;
 use crate::models::*;
 use crate::prsr::*;
+use crate::report::*;
 use crate::usps::*;
 use anyhow::{anyhow, Result};
 use heck::ToTitleCase;
-use reqwest::Client;
 use scraper::{Html, Selector};
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
-use std::fmt;
-use std::fs::File;
-use std::io::{BufReader, BufWriter};
-use std::ops::Add;
-use std::path::Path;
 use strum::EnumIter; // Required to derive EnumIter
-use strum::IntoEnumIterator;
-use Center::*; // Required for iterating over the enum
+use tracing::{debug, info, trace};
+use MilCenter::*; // Required for iterating over the enum
 
-const FLE_PTH: &str = "military.json";
+pub const FLE_PTH: &str = "military.json";
 const FLE_PTH_ADR: &str = "military_adr.json";
 
 #[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Eq, PartialOrd, Ord)]
@@ -26,6 +22,12 @@ pub struct Military {
     pub role: Role,
     pub persons: Vec<Person>,
 }
+impl Default for Military {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 impl Military {
     pub fn new() -> Self {
         Self {
@@ -36,19 +38,27 @@ impl Military {
     }
 
     pub async fn load() -> Result<Military> {
+        REPORT.lock().unwrap().begin(Source::Military);
+
         // Read members file from disk.
 
         let military = match read_from_file::<Military>(FLE_PTH) {
-            Ok(military_from_disk) => military_from_disk,
+            Ok(mut military_from_disk) => {
+                // Backfill roles of older files.
+                set_roles(&mut military_from_disk.persons, &military_from_disk.role);
+                military_from_disk
+            }
             Err(_) => {
                 let mut military = Military::new();
 
-                let adrs = &fetch_adrs().await?;
+                let adrs = &fetch_facility_adrs::<MilCenter>(FLE_PTH_ADR).await?;
 
                 // Fetch members.
                 military.fetch_members_dod().await?;
                 military.fetch_members_oni(adrs).await?;
                 military.fetch_members_usff(adrs).await?;
+                sort_dedup_by_origin(&mut military.persons);
+                set_roles(&mut military.persons, &military.role);
 
                 // Write file to disk.
                 write_to_file(&military, FLE_PTH)?;
@@ -57,25 +67,25 @@ impl Military {
             }
         };
 
-        println!("{} military leaders", military.persons.len());
+        info!("{} military leaders", military.persons.len());
 
         Ok(military)
     }
 
     pub async fn fetch_members_dod(&mut self) -> Result<()> {
         let url = "https://www.defense.gov/Contact/Mailing-Addresses/";
-        let html = fetch_html(url).await?;
+        let html = fetch_html(url, FetchOptions::for_source(Source::Military)).await?;
         let document = Html::parse_document(&html);
 
         let selector = Selector::parse("div.address-each").unwrap();
         for elm in document.select(&selector) {
             // Get lines and filter.
-            let mut cur_lnes = elm
+            let cur_lnes = elm
                 .text()
                 .map(|s| s.trim().to_string())
                 .filter(|s| PRSR.filter(s))
                 .collect::<Vec<String>>();
-            eprintln!("{cur_lnes:?}");
+            trace!("{cur_lnes:?}");
 
             // Parse person.
             let mut per = Person {
@@ -104,16 +114,19 @@ impl Military {
             // Parse address.
             let mut adr = Address::default();
             let mut lne = cur_lnes[2].clone();
-            let lne_zip = &lne[lne.len() - 10..];
-            let is_zip5 = is_zip5(lne_zip);
-            let is_zip10 = if !is_zip5 { is_zip10(lne_zip) } else { false };
-            if is_zip5 {
-                adr.zip5 = lne_zip.parse().unwrap();
-            } else {
-                adr.zip5 = lne_zip[..5].parse().unwrap();
-                adr.zip4 = lne_zip[lne_zip.len() - 4..].parse().unwrap();
-            }
-            adr.state = "DC".into();
+            let lne_zip = lne
+                .get(lne.len().saturating_sub(LEN_ZIP10)..)
+                .unwrap_or_default();
+            adr.set_zip_from_str(lne_zip)
+                .map_err(|err| anyhow!("{err} for {}", per.name))?;
+            // State before the zip, such as ", WASHINGTON, DC 20301-1000".
+            let idx_zip = lne.len().saturating_sub(LEN_ZIP10);
+            let lne_state = lne
+                .get(idx_zip.saturating_sub(3)..idx_zip.saturating_sub(1))
+                .unwrap_or_default();
+            adr.state = normalize_state(lne_state)
+                .ok_or_else(|| anyhow!("invalid state '{lne_state}' for {}", per.name))?
+                .into();
             adr.city = "WASHINGTON".into();
             lne = lne[..lne.len() - 27].into();
             // Set Address2 if necessary.
@@ -129,38 +142,33 @@ impl Military {
             }
             adr.address1.clone_from(&lne);
 
-            // eprintln!("    {lne}");
-            // eprintln!("  {adr:?}");
-
             let mut adrs = vec![adr];
             adrs = standardize_addresses(adrs).await?;
 
             per.adrs = Some(adrs);
+            per.provenance = Some(Provenance::new(Source::Military, url));
             self.persons.push(per);
         }
 
         Ok(())
     }
 
-    pub async fn fetch_members_oni(&mut self, adrs: &HashMap<Center, Address>) -> Result<()> {
+    pub async fn fetch_members_oni(&mut self, adrs: &HashMap<MilCenter, Address>) -> Result<()> {
         // Fetch url.
         let url = "https://www.oni.navy.mil/About/Biographies/";
-        let html = fetch_html(url).await?;
+        let html = fetch_html(url, FetchOptions::for_source(Source::Military)).await?;
         let document = Html::parse_document(&html);
 
         // Define the CSS selector for the members list.
-        let hdr_sel = Selector::parse("h2").unwrap();
         let tbl_sel = Selector::parse("div.BioWrap").unwrap();
         let row_sel = Selector::parse("div.BioSenLead").unwrap();
         let name_sel = Selector::parse("p a").unwrap();
 
-        for (idx, tbl_elm) in document.select(&tbl_sel).enumerate() {
-            // eprintln!("  {}", hdrs[idx]);
+        for tbl_elm in document.select(&tbl_sel) {
             for row_elm in tbl_elm.select(&row_sel) {
                 if let Some(elm) = row_elm.select(&name_sel).next() {
                     // Find name and office.
                     let full_name = elm.text().collect::<String>();
-                    // eprintln!("{}", full_name.trim());
 
                     // Find name.
                     let mut full_name = full_name
@@ -173,16 +181,17 @@ impl Military {
                     }
 
                     // Create person.
-                    let mut per = Person {
+                    let per = Person {
                         name: name_clean(&full_name),
                         adrs: Some(vec![adrs[&Oni].clone()]),
+                        provenance: Some(Oni.provenance()),
                         ..Default::default()
                     };
                     if per.name.is_empty() {
                         return Err(anyhow!("name is empty"));
                     }
 
-                    eprintln!("{}", per);
+                    debug!("{}", per);
                     self.persons.push(per);
                 }
             }
@@ -191,12 +200,12 @@ impl Military {
         Ok(())
     }
 
-    pub async fn fetch_members_usff(&mut self, adrs: &HashMap<Center, Address>) -> Result<()> {
+    pub async fn fetch_members_usff(&mut self, adrs: &HashMap<MilCenter, Address>) -> Result<()> {
         let urls = ["https://www.usff.navy.mil/Leadership/Biographies/Article/2375906/commander-usff/", "https://www.usff.navy.mil/Leadership/Biographies/Article/2728519/deputy-commander-usff/", "https://www.usff.navy.mil/Leadership/Biographies/Article/2728549/fleet-master-chief/"];
 
         for url in urls {
             // Fetch url.
-            let html = fetch_html(url).await?;
+            let html = fetch_html(url, FetchOptions::for_source(Source::Military)).await?;
             let document = Html::parse_document(&html);
 
             // Select name.
@@ -208,15 +217,15 @@ impl Military {
                     full_name = full_name.to_title_case();
                     full_name.insert_str(0, "FLTCM. ");
                 }
-                // eprintln!("{}", full_name.trim());
 
-                let mut per = Person {
+                let per = Person {
                     name: name_clean(&full_name),
                     adrs: Some(vec![adrs[&Usff].clone()]),
+                    provenance: Some(Usff.provenance()),
                     ..Default::default()
                 };
 
-                eprintln!("{}", per);
+                debug!("{}", per);
                 self.persons.push(per);
             }
         }
@@ -225,145 +234,32 @@ impl Military {
     }
 }
 
-/// Fetch, parse, and standardize an address.
-pub async fn fetch_prs_std_adr(ctr: Center, url: &str) -> Result<Option<Address>> {
-    // Fetch html.
-    let html = fetch_html(url).await?;
-
-    // Parse html to address lines.
-    let adr_lnes_o = prs_adr_lnes(ctr, &html);
-
-    // Parse lines to addresses.
-    let adrs_o = match adr_lnes_o {
-        None => None,
-        Some(mut adr_lnes) => match PRSR.prs_adrs(&adr_lnes) {
-            None => None,
-            Some(mut adrs) => {
-                adrs = standardize_addresses(adrs).await?;
-                if adrs.is_empty() {
-                    None
-                } else {
-                    Some(adrs.remove(0))
-                }
-            }
-        },
-    };
-
-    Ok(adrs_o)
-}
-
-pub fn prs_adr_lnes(ctr: Center, html: &str) -> Option<Vec<String>> {
-    let document = Html::parse_document(html);
-    let mut lnes: Vec<String> = Vec::new();
-    for txt in ["h6", "span", "body"] {
-        let selector = Selector::parse(txt).unwrap();
-        for elm in document.select(&selector) {
-            // Extract lines from html.
-            let mut cur_lnes = elm
-                .text()
-                .map(|s| s.trim().trim_end_matches(',').to_uppercase().to_string())
-                .collect::<Vec<String>>();
-
-            // eprintln!("--- pre: {cur_lnes:?}");
-
-            // Filter lines.
-            // Filter separately to allow debugging.
-            cur_lnes = cur_lnes
-                .into_iter()
-                .filter(|s| PRSR.filter(s))
-                .collect::<Vec<String>>();
-
-            if !cur_lnes.is_empty() {
-                eprintln!("{cur_lnes:?}");
-
-                lnes.extend(cur_lnes);
-            }
-        }
+impl Facility for MilCenter {
+    const SOURCE: Source = Source::Military;
+    const SELECTORS: &'static [&'static str] = &["h6", "span", "body"];
 
-        if !lnes.is_empty() {
-            break;
+    fn url(&self) -> &str {
+        match self {
+            Oni => "https://www.oni.navy.mil/Contact-Us/",
+            Usff => "https://www.usa.gov/agencies/u-s-fleet-forces-command",
         }
     }
 
-    // eprintln!("--- pre: {lnes:?}");
-
-    // Edit lines to make it easier to parse.
-    edit_dot(&mut lnes);
-    edit_nbsp_zwsp(&mut lnes);
-    edit_mailing(&mut lnes);
-    edit_mil_lnes(ctr, &mut lnes);
-    PRSR.edit_lnes(&mut lnes);
-    edit_newline(&mut lnes);
-    edit_split_comma(&mut lnes);
-    edit_starting_hash(&mut lnes);
-    edit_char_half(&mut lnes);
-    edit_empty(&mut lnes);
-
-    eprintln!("--- --- --- post: {lnes:?}");
-
-    // Do not check for zip count here.
-
-    Some(lnes)
-}
-
-pub fn edit_mil_lnes(ctr: Center, lnes: &mut [String]) {
-    if ctr == Oni {
-        for idx in (0..lnes.len()).rev() {
-            if let Some(idx_fnd) = lnes[idx].find(", USA") {
-                lnes[idx].truncate(idx_fnd);
-            }
-        }
-    }
-}
-
-pub async fn fetch_adrs() -> Result<HashMap<Center, Address>> {
-    // Read file from disk.
-    let mut map_adrs = match read_from_file::<HashMap<Center, Address>>(FLE_PTH_ADR) {
-        Ok(map_adrs) => map_adrs,
-        Err(_) => {
-            let mut map_adrs = HashMap::new();
-
-            // Iterate through each center.
-            for ctr in Center::iter() {
-                println!("{:?}", ctr);
-
-                // Get url.
-                let url = adr_url(ctr);
-                if url.is_empty() {
-                    continue;
-                }
-
-                // Fetch, parse, and standardize each address.
-                match fetch_prs_std_adr(ctr, &url).await? {
-                    None => {}
-                    Some(mut adr) => {
-                        map_adrs.insert(ctr, adr);
-                    }
+    fn edit_lnes(&self, lnes: &mut Vec<String>) {
+        if *self == Oni {
+            for idx in (0..lnes.len()).rev() {
+                if let Some(idx_fnd) = lnes[idx].find(", USA") {
+                    lnes[idx].truncate(idx_fnd);
                 }
             }
-
-            // Write file to disk.
-            write_to_file(&map_adrs, FLE_PTH_ADR)?;
-
-            map_adrs
         }
-    };
-
-    Ok(map_adrs)
-}
-
-fn adr_url(ctr: Center) -> String {
-    match ctr {
-        Oni => "https://www.oni.navy.mil/Contact-Us/",
-        Usff => "https://www.usa.gov/agencies/u-s-fleet-forces-command",
     }
-    .into()
 }
 
 #[derive(
     Debug, EnumIter, Clone, Copy, Hash, Serialize, Deserialize, PartialEq, Eq, PartialOrd, Ord,
 )]
-pub enum Center {
+pub enum MilCenter {
     Oni,  // Office of Naval Intelligence
     Usff, // U.S. Fleet Forces Command
 }
@@ -1,16 +1,19 @@
-use crate::core::*;
+use crate::io::cached_at;
 use crate::prsr::*;
 use anyhow::{anyhow, Result};
-use serde::{Deserialize, Serialize};
+use chrono::{Local, NaiveDateTime};
+use serde::{Deserialize, Deserializer, Serialize};
 use std::cmp::Ordering;
-use std::default;
+use std::collections::{HashMap, HashSet};
 use std::fmt;
+use std::str::FromStr;
 
-#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Eq, PartialOrd, Ord)]
+#[derive(Debug, Default, Serialize, Deserialize, Clone, PartialEq, Eq, PartialOrd, Ord)]
 pub enum Role {
     Military,
     Scientific,
     Political,
+    #[default]
     Observer,
 }
 impl fmt::Display for Role {
@@ -24,14 +27,99 @@ impl fmt::Display for Role {
     }
 }
 
+/// A source of persons.
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[serde(rename_all = "lowercase")]
+pub enum Source {
+    Military,
+    Nasa,
+    Executive,
+    Senate,
+    House,
+    Committee,
+    State,
+    Observer,
+}
+impl fmt::Display for Source {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            Source::Military => write!(f, "military"),
+            Source::Nasa => write!(f, "nasa"),
+            Source::Executive => write!(f, "executive"),
+            Source::Senate => write!(f, "senate"),
+            Source::House => write!(f, "house"),
+            Source::Committee => write!(f, "committee"),
+            Source::State => write!(f, "state"),
+            Source::Observer => write!(f, "observer"),
+        }
+    }
+}
+/// Sources in load order.
+pub const SOURCES: [Source; 8] = [
+    Source::Military,
+    Source::Nasa,
+    Source::Executive,
+    Source::Senate,
+    Source::House,
+    Source::Committee,
+    Source::State,
+    Source::Observer,
+];
+impl Source {
+    /// Role of persons of the source.
+    pub fn role(&self) -> Role {
+        match self {
+            Source::Military => Role::Military,
+            Source::Nasa => Role::Scientific,
+            Source::Executive
+            | Source::Senate
+            | Source::House
+            | Source::Committee
+            | Source::State => Role::Political,
+            Source::Observer => Role::Observer,
+        }
+    }
+}
+impl FromStr for Source {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s {
+            "military" => Ok(Source::Military),
+            "nasa" => Ok(Source::Nasa),
+            "executive" => Ok(Source::Executive),
+            "senate" => Ok(Source::Senate),
+            "house" => Ok(Source::House),
+            "committee" => Ok(Source::Committee),
+            "state" => Ok(Source::State),
+            "observer" => Ok(Source::Observer),
+            _ => Err(anyhow!("unknown source '{s}'")),
+        }
+    }
+}
+
 /// A person.
 #[derive(Debug, Default, Serialize, Deserialize, Clone)]
 pub struct Person {
     pub name: String,
+    #[serde(default)]
     pub title1: String,
+    #[serde(default)]
     pub title2: String,
     pub url: String,
+    /// USPS state abbreviation of the constituency, when known.
+    #[serde(default)]
+    pub state: String,
+    /// Role of the source of the person; Observer in files written before roles.
+    #[serde(default)]
+    pub role: Role,
     pub adrs: Option<Vec<Address>>,
+    /// Letter language, such as `es`; `None` is the default language.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub language: Option<String>,
+    /// Where and when the addresses were fetched.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub provenance: Option<Provenance>,
 }
 impl fmt::Display for Person {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
@@ -65,10 +153,60 @@ impl Person {
             .map(|adrs| adrs.len()) // Map the Option to the length of the vector if it exists
             .unwrap_or(0) // Return 0 if the Option is None
     }
+
+    /// Merges a duplicate of the person, unioning addresses and keeping the richer title.
+    pub fn merge(&mut self, other: Person) {
+        if other.title1.len() + other.title2.len() > self.title1.len() + self.title2.len() {
+            self.title1 = other.title1;
+            self.title2 = other.title2;
+        }
+        if self.url.is_empty() {
+            self.url = other.url;
+        }
+        if self.state.is_empty() {
+            self.state = other.state;
+        }
+        if self.language.is_none() {
+            self.language = other.language;
+        }
+        if self.provenance.is_none() {
+            self.provenance = other.provenance;
+        }
+        for adr in other.adrs.into_iter().flatten() {
+            let adrs = self.adrs.get_or_insert_with(Vec::new);
+            if !adrs.iter().any(|o| o.is_same(&adr)) {
+                adrs.push(adr);
+            }
+        }
+    }
+}
+
+/// Where and when the addresses of a person were fetched.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Eq)]
+pub struct Provenance {
+    pub source: Source,
+    /// Page the addresses were parsed from, such as a contact page.
+    ///
+    /// The manual address file for manual addresses; empty for addresses in code.
+    pub url: String,
+    pub fetched_at: NaiveDateTime,
+}
+
+impl Provenance {
+    /// Provenance of addresses of a page, fetched when the page was cached, or now.
+    ///
+    /// Files built from the same cached pages are identical across runs.
+    pub fn new(source: Source, url: &str) -> Self {
+        Provenance {
+            source,
+            url: url.into(),
+            fetched_at: cached_at(url).unwrap_or_else(|| Local::now().naive_local()),
+        }
+    }
 }
 
 /// A mailing address.
-#[derive(Debug, Default, Serialize, Deserialize, Clone, PartialEq, Eq, PartialOrd, Ord)]
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Eq, PartialOrd, Ord)]
 pub struct Address {
     pub address1: String,
     pub address2: Option<String>,
@@ -77,7 +215,210 @@ pub struct Address {
     pub zip5: u32,
     pub zip4: u16,
     pub delivery_point: Option<String>,
+    /// Counties served by an office at the address.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub counties: Vec<String>,
+    /// Country of a foreign address in caps; `None` for US addresses.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub country: Option<String>,
+    /// Postal code of a foreign address.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub postal_code: Option<String>,
+    /// Building or firm name above address1, such as `WELLS FARGO CENTER`.
+    ///
+    /// Kept for the envelope; USPS standardization ignores it.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub firm: Option<String>,
+    /// False when USPS standardization failed and the parsed address was kept,
+    /// such as a Guam caller box.
+    #[serde(default = "standardized_default", skip_serializing_if = "is_true")]
+    pub standardized: bool,
+}
+fn standardized_default() -> bool {
+    true
+}
+fn is_true(b: &bool) -> bool {
+    *b
+}
+impl Default for Address {
+    fn default() -> Self {
+        Self {
+            address1: String::new(),
+            address2: None,
+            city: String::new(),
+            state: String::new(),
+            zip5: 0,
+            zip4: 0,
+            delivery_point: None,
+            counties: Vec::new(),
+            country: None,
+            postal_code: None,
+            firm: None,
+            standardized: standardized_default(),
+        }
+    }
+}
+impl Address {
+    /// Whether two addresses deliver to the same place, ignoring zip4 and counties.
+    pub fn is_same(&self, other: &Address) -> bool {
+        self.address1 == other.address1
+            && self.address2 == other.address2
+            && self.city == other.city
+            && self.state == other.state
+            && self.zip5 == other.zip5
+            && self.country == other.country
+    }
+
+    /// Whether two addresses are the same location, ignoring zip4 and delivery point.
+    ///
+    /// Lines, city and state compare case-insensitively, ignoring extra whitespace.
+    pub fn same_location(&self, other: &Address) -> bool {
+        let norm = |s: &str| {
+            s.split_whitespace()
+                .collect::<Vec<_>>()
+                .join(" ")
+                .to_uppercase()
+        };
+        let adr2 = |adr: &Address| norm(adr.address2.as_deref().unwrap_or(""));
+        norm(&self.address1) == norm(&other.address1)
+            && adr2(self) == adr2(other)
+            && norm(&self.city) == norm(&other.city)
+            && norm(&self.state) == norm(&other.state)
+            && self.zip5 == other.zip5
+            && self.country == other.country
+    }
+
+    /// Count of zip4, delivery point and firm populated, preferring the detailed of duplicates.
+    pub fn detail(&self) -> u8 {
+        (self.zip4 != 0) as u8 + self.delivery_point.is_some() as u8 + self.firm.is_some() as u8
+    }
+
+    /// Sets zip5 and zip4 from a zip, zip4 is zero when absent.
+    pub fn set_zip(&mut self, zip: Zip) {
+        self.zip5 = zip.zip5;
+        self.zip4 = zip.zip4.unwrap_or(0);
+    }
+
+    /// Parses and sets a zip of form `12345` or `12345-6789`.
+    pub fn set_zip_from_str(&mut self, s: &str) -> Result<()> {
+        self.set_zip(Zip::parse(s)?);
+        Ok(())
+    }
+
+    /// Foreign addresses bypass USPS standardization and barcodes.
+    pub fn is_foreign(&self) -> bool {
+        self.country.is_some()
+    }
+
+    /// Territories whose addresses the USPS zip lookup often fails to match.
+    pub fn is_territory(&self) -> bool {
+        TERRITORIES.contains(&self.state.as_str())
+    }
+
+    /// Delivery line with address2 appended, such as `1919 SMITH ST STE 9047`.
+    ///
+    /// Address1 alone when there's no address2 or the merged line exceeds `max` chars.
+    pub fn merged_address1(&self, max: usize) -> String {
+        let Some(address2) = self.address2.as_deref().filter(|o| !o.is_empty()) else {
+            return self.address1.clone();
+        };
+        let merged = format!("{} {address2}", self.address1);
+        if merged.chars().count() <= max {
+            merged
+        } else {
+            self.address1.clone()
+        }
+    }
+
+    /// Canonical single line, such as `1 STATE ST, STE 2, ALBANY, NY 12224-1234`.
+    ///
+    /// Address2 and zip4 are omitted when absent; parsed back by `from_str`.
+    pub fn to_single_line(&self) -> String {
+        let zip = Zip {
+            zip5: self.zip5,
+            zip4: (self.zip4 != 0).then_some(self.zip4),
+        };
+        let mut lne = self.address1.clone();
+        if let Some(address2) = &self.address2 {
+            lne.push_str(", ");
+            lne.push_str(address2);
+        }
+        format!("{lne}, {}, {} {zip}", self.city, self.state)
+    }
 }
+impl FromStr for Address {
+    type Err = anyhow::Error;
+
+    /// Parses the canonical single line of `to_single_line`.
+    ///
+    /// Parts between address1 and the city are address2, which may contain commas.
+    fn from_str(s: &str) -> Result<Self> {
+        let prts: Vec<&str> = s.split(',').map(str::trim).collect();
+        let [address1, mid @ .., city, state_zip] = prts.as_slice() else {
+            return Err(anyhow!(
+                "invalid address '{s}': expected 'ADDRESS1, [ADDRESS2, ]CITY, STATE ZIP'"
+            ));
+        };
+        if address1.is_empty() || city.is_empty() {
+            return Err(anyhow!("invalid address '{s}': empty address1 or city"));
+        }
+        let Some((state, zip)) = state_zip.split_once(' ') else {
+            return Err(anyhow!(
+                "invalid address '{s}': expected 'STATE ZIP' after the city"
+            ));
+        };
+        let state = state.trim();
+        if state.len() != 2 || !state.bytes().all(|b| b.is_ascii_uppercase()) {
+            return Err(anyhow!("invalid address '{s}': invalid state '{state}'"));
+        }
+        let zip = Zip::parse(zip.trim()).map_err(|err| anyhow!("invalid address '{s}': {err}"))?;
+        let mut adr = Address {
+            address1: address1.to_string(),
+            address2: (!mid.is_empty()).then(|| mid.join(", ")),
+            city: city.to_string(),
+            state: state.into(),
+            ..Default::default()
+        };
+        adr.set_zip(zip);
+        Ok(adr)
+    }
+}
+
+/// An address of a data file, as an object or a single line of `to_single_line`.
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum AdrOrLine {
+    Line(String),
+    Adr(Address),
+}
+
+impl AdrOrLine {
+    fn into_adr<E: serde::de::Error>(self) -> std::result::Result<Address, E> {
+        match self {
+            AdrOrLine::Line(lne) => lne.parse().map_err(E::custom),
+            AdrOrLine::Adr(adr) => Ok(adr),
+        }
+    }
+}
+
+/// Deserializes an address given as an object or a single line.
+pub fn de_adr<'de, D: Deserializer<'de>>(de: D) -> std::result::Result<Address, D::Error> {
+    AdrOrLine::deserialize(de)?.into_adr()
+}
+
+/// Deserializes addresses given as objects or single lines.
+pub fn de_adrs<'de, D: Deserializer<'de>>(de: D) -> std::result::Result<Vec<Address>, D::Error> {
+    Vec::<AdrOrLine>::deserialize(de)?
+        .into_iter()
+        .map(AdrOrLine::into_adr)
+        .collect()
+}
+
+/// Characters of a delivery line fitting the envelope address block.
+pub const ADDRESS1_MAX: usize = 40;
+
+/// Freely associated states and territories kept unstandardized on USPS failure.
+pub const TERRITORIES: [&str; 7] = ["AS", "GU", "MP", "FM", "MH", "PW", "VI"];
 impl fmt::Display for Address {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         write!(
@@ -94,6 +435,232 @@ impl fmt::Display for Address {
     }
 }
 
+/// A USPS zip code.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Zip {
+    pub zip5: u32,
+    pub zip4: Option<u16>,
+}
+impl Zip {
+    /// Parses a zip of form `12345` or `12345-6789`.
+    ///
+    /// Leading zeros are kept by formatting, "02134" parses to 2134.
+    pub fn parse(s: &str) -> Result<Zip> {
+        let (s5, s4) = match s.split_once(ZIP_DASH) {
+            Some((s5, s4)) => (s5, Some(s4)),
+            None => (s, None),
+        };
+        let is_digits =
+            |s: &str, len: usize| s.len() == len && s.bytes().all(|b| b.is_ascii_digit());
+        if !is_digits(s5, LEN_ZIP5) || !s4.is_none_or(|s4| is_digits(s4, LEN_ZIP4)) {
+            return Err(anyhow!("invalid zip '{s}'"));
+        }
+        Ok(Zip {
+            zip5: s5.parse()?,
+            zip4: s4.map(|s4| s4.parse()).transpose()?,
+        })
+    }
+}
+impl fmt::Display for Zip {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self.zip4 {
+            Some(zip4) => write!(f, "{:05}{}{:04}", self.zip5, ZIP_DASH, zip4),
+            None => write!(f, "{:05}", self.zip5),
+        }
+    }
+}
+
+/// An office location of a WordPress endpoint, such as `wp-json/wp/v2/locations`.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct Location {
+    pub acf: LocationAcf,
+}
+/// Custom fields of an office location; a `~` address has no address.
+#[derive(Debug, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct LocationAcf {
+    pub address: String,
+    pub suite: String,
+    pub city: String,
+    pub state: String,
+    pub zipcode: String,
+}
+
+/// Parses unstandardized addresses of WordPress office locations.
+///
+/// Locations without an address are skipped.
+pub fn prs_locations(json: &str) -> Result<Vec<Address>> {
+    let locations: Vec<Location> = serde_json::from_str(json)?;
+    locations
+        .into_iter()
+        .filter(|loc| !loc.acf.address.is_empty() && loc.acf.address != "~")
+        .map(|loc| {
+            let mut adr = Address {
+                address1: loc.acf.address,
+                address2: Some(loc.acf.suite).filter(|suite| !suite.is_empty()),
+                city: loc.acf.city,
+                state: normalize_state(&loc.acf.state)
+                    .ok_or_else(|| anyhow!("invalid state '{}'", loc.acf.state))?
+                    .into(),
+                ..Default::default()
+            };
+            adr.set_zip_from_str(&loc.acf.zipcode)?;
+            Ok(adr)
+        })
+        .collect()
+}
+
+/// Sets the role of persons to the role of their source.
+pub fn set_roles(pers: &mut [Person], role: &Role) {
+    for per in pers {
+        per.role = role.clone();
+    }
+}
+
+/// Returns persons with an office serving a county.
+/// "Mobile County" and "MOBILE" are equivalent.
+pub fn persons_serving_county<'a>(pers: &'a [Person], county: &str) -> Vec<&'a Person> {
+    let cnty = prs_counties(county);
+    pers.iter()
+        .filter(|per| {
+            per.adrs
+                .iter()
+                .flatten()
+                .any(|adr| adr.counties.iter().any(|c| cnty.contains(c)))
+        })
+        .collect()
+}
+
+/// Normalized name of a person, such as `john smith` of "John A. Smith".
+pub fn name_key(name: &str) -> String {
+    PRSR.re_name_initials
+        .replace_all(name, "")
+        .to_lowercase()
+        .split_whitespace()
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+/// Merges persons with the same normalized name across sources.
+///
+/// Earlier persons take precedence, so sources are passed in order.
+pub fn dedup_persons(pers: Vec<Person>) -> Vec<Person> {
+    let mut ret: Vec<Person> = Vec::with_capacity(pers.len());
+    let mut idxs: HashMap<String, usize> = HashMap::new();
+    for per in pers {
+        match idxs.get(&name_key(&per.name)) {
+            Some(&idx) => ret[idx].merge(per),
+            None => {
+                idxs.insert(name_key(&per.name), ret.len());
+                ret.push(per);
+            }
+        }
+    }
+    ret
+}
+
+/// Sorts persons by source page and name, keeping the first person of each normalized name.
+///
+/// Orders a person file the same regardless of scrape order, so files of runs diff cleanly.
+pub fn sort_dedup_by_origin(pers: &mut Vec<Person>) {
+    pers.sort_by_cached_key(|per| {
+        (
+            per.provenance.as_ref().map(|prov| prov.url.clone()),
+            name_key(&per.name),
+            per.to_string(),
+        )
+    });
+    let mut keys = HashSet::new();
+    pers.retain(|per| keys.insert(name_key(&per.name)));
+}
+
+/// Changes of a fetched member list against its cached person file.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct ReconcileStats {
+    /// Listed persons keeping their cached addresses under the same name.
+    pub kept: usize,
+    /// New persons, whose addresses are fetched.
+    pub added: Vec<String>,
+    /// Persons no longer listed, such as former members.
+    pub removed: Vec<String>,
+    /// Persons listed under another name at the same url, such as `Bob Smith -> Robert Smith`.
+    pub renamed: Vec<String>,
+}
+
+impl ReconcileStats {
+    pub fn is_empty(&self) -> bool {
+        self.added.is_empty() && self.removed.is_empty() && self.renamed.is_empty()
+    }
+
+    /// Summary lines, such as `1 added: Jane Doe`.
+    pub fn lnes(&self) -> Vec<String> {
+        let mut lnes = vec![format!("{} kept", self.kept)];
+        for (kind, names) in [
+            ("added", &self.added),
+            ("removed", &self.removed),
+            ("renamed", &self.renamed),
+        ] {
+            if !names.is_empty() {
+                lnes.push(format!("{} {kind}: {}", names.len(), names.join(", ")));
+            }
+        }
+        lnes
+    }
+}
+
+/// Reconciles a fetched member list with the persons of a cached person file.
+///
+/// Persons match by normalized name, or by url when renamed, such as a nickname change.
+/// Matched persons keep their cached addresses, new persons have none so only they're fetched,
+/// and persons no longer listed are dropped. The fetched order and fields are kept.
+pub fn reconcile_members(cached: Vec<Person>, fresh: Vec<Person>) -> (Vec<Person>, ReconcileStats) {
+    let mut stats = ReconcileStats::default();
+    let mut cached: Vec<Option<Person>> = cached.into_iter().map(Some).collect();
+    let mut matches: Vec<Option<Person>> = vec![None; fresh.len()];
+
+    // Match names first, so a url shared with a renamed person doesn't take another match.
+    let is_eqs: [fn(&Person, &Person) -> bool; 2] = [
+        |a, b| name_key(&a.name) == name_key(&b.name),
+        |a, b| !a.url.is_empty() && a.url == b.url,
+    ];
+    for is_eq in is_eqs {
+        for (per, old) in fresh.iter().zip(matches.iter_mut()) {
+            if old.is_some() {
+                continue;
+            }
+            if let Some(o) = cached
+                .iter_mut()
+                .find(|o| o.as_ref().is_some_and(|o| is_eq(o, per)))
+            {
+                *old = o.take();
+            }
+        }
+    }
+
+    let mut pers = Vec::with_capacity(fresh.len());
+    for (mut per, old) in fresh.into_iter().zip(matches) {
+        match old {
+            Some(old) => {
+                if name_key(&old.name) == name_key(&per.name) {
+                    stats.kept += 1;
+                } else {
+                    stats.renamed.push(format!("{} -> {}", old.name, per.name));
+                }
+                per.adrs = old.adrs;
+                per.provenance = old.provenance;
+            }
+            None => {
+                stats.added.push(per.name.clone());
+                per.adrs = None;
+            }
+        }
+        pers.push(per);
+    }
+    stats.removed = cached.into_iter().flatten().map(|o| o.name).collect();
+
+    (pers, stats)
+}
+
 // AddressList for pretty printing.
 pub struct AddressList(pub Vec<Address>);
 impl fmt::Display for AddressList {
@@ -102,7 +669,7 @@ impl fmt::Display for AddressList {
             if i != 0 {
                 writeln!(f)?;
             }
-            write!(f, "  {}", address)?;
+            write!(f, "  {}", address.to_single_line())?;
         }
         Ok(())
     }
@@ -122,6 +689,89 @@ pub struct Mailpiece {
     pub delivery_point: Option<String>,
     pub barcode: String,
     pub id: u32,
+    /// Address line 2, such as a suite `STE 2300-A`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub address2: Option<String>,
+    /// Country of a foreign mailpiece in caps.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub country: Option<String>,
+    /// Postal code of a foreign mailpiece.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub postal_code: Option<String>,
+    /// Building or firm name printed above address1.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub firm: Option<String>,
+    /// Barcode components before encoding, used to match tracking scans.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub imb: Option<Imb>,
+    /// Letter language, such as `es`; `None` is the default language.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub language: Option<String>,
+}
+impl Mailpiece {
+    /// Foreign mailpieces are mailed international First-Class.
+    pub fn is_foreign(&self) -> bool {
+        self.country.is_some()
+    }
+
+    /// Routing code of the barcode, a zip5, zip+4 or delivery point code.
+    ///
+    /// The Routing Code field is an optional field, which may contain a
+    /// 5-digit ZIP Code, a 9-digit ZIP+4 code, or an 11-digit delivery
+    /// point code. When used on letters for automation-rate eligibility purposes,
+    /// the routing code must contain a delivery point code from CASS-certified
+    /// software that accurately matches the delivery address.
+    /// From "Intelligent Mail Barcode Technical Resource Guide" PDF.
+    /// See https://postalpro.usps.com/node/221.
+    pub fn routing_code(&self) -> String {
+        let mut routing_code = if self.zip4 != 0 {
+            format!("{:05}{:04}", self.zip5, self.zip4)
+        } else {
+            format!("{:05}", self.zip5)
+        };
+        if self.zip4 != 0 {
+            if let Some(delivery_point) = &self.delivery_point {
+                routing_code.push_str(delivery_point);
+            }
+        }
+        routing_code
+    }
+
+    /// Lines of the envelope address block.
+    ///
+    /// Titles are in caps like the name, whatever the case of their source.
+    /// A firm is its own line above address line 1, and address line 2 below it.
+    /// Foreign addresses end with the country in caps per UPU format.
+    pub fn to_lines(&self) -> Vec<String> {
+        let mut lnes = vec![dot_remove(self.name.clone()).to_uppercase()];
+        lnes.extend(self.title1.iter().map(|title| title.to_uppercase()));
+        lnes.extend(self.title2.iter().map(|title| title.to_uppercase()));
+        lnes.extend(self.firm.iter().cloned());
+        lnes.push(self.address1.clone());
+        lnes.extend(self.address2.iter().cloned());
+        match &self.country {
+            None if self.zip4 == 0 => {
+                lnes.push(format!("{}  {}  {:05}", self.city, self.state, self.zip5))
+            }
+            None => lnes.push(format!(
+                "{}  {}  {:05}-{:04}",
+                self.city, self.state, self.zip5, self.zip4
+            )),
+            Some(country) => {
+                lnes.push(
+                    format!(
+                        "{}  {}",
+                        self.city,
+                        self.postal_code.as_deref().unwrap_or("")
+                    )
+                    .trim_end()
+                    .into(),
+                );
+                lnes.push(country.to_uppercase());
+            }
+        }
+        lnes
+    }
 }
 impl fmt::Display for Mailpiece {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
@@ -142,9 +792,220 @@ impl fmt::Display for Mailpiece {
     }
 }
 
+/// Components of an Intelligent Mail barcode before encoding.
+#[derive(Debug, Default, Serialize, Deserialize, Clone, PartialEq, Eq)]
+pub struct Imb {
+    pub barcode_id: String,
+    pub service_type_id: String,
+    pub mailer_id: String,
+    pub serial: u32,
+    pub routing_code: String,
+}
+
 #[derive(Debug, Default, Serialize, Deserialize, Clone)]
 pub struct Letter {
     pub to: String,
     pub paragraphs: Vec<String>,
     pub from: String,
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn per(name: &str, title1: &str, zip5s: &[u32]) -> Person {
+        Person {
+            name: name.into(),
+            title1: title1.into(),
+            adrs: Some(
+                zip5s
+                    .iter()
+                    .map(|zip5| Address {
+                        address1: "300 E ST SW".into(),
+                        city: "WASHINGTON".into(),
+                        state: "DC".into(),
+                        zip5: *zip5,
+                        ..Default::default()
+                    })
+                    .collect(),
+            ),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_prs_locations() {
+        let json = r#"[
+            {"acf": {"address": "1 Main St", "suite": "Suite 200", "city": "Denver",
+                "state": "CO", "zipcode": "80202"}},
+            {"acf": {"address": "2 Main St", "suite": "", "city": "Pueblo",
+                "state": "CO", "zipcode": "81003-1234"}},
+            {"acf": {"address": "~", "city": "", "state": "", "zipcode": ""}}
+        ]"#;
+        let adrs = prs_locations(json).unwrap();
+        assert_eq!(adrs.len(), 2);
+        assert_eq!(adrs[0].address2.as_deref(), Some("Suite 200"));
+        assert_eq!(adrs[0].zip5, 80202);
+        assert_eq!(adrs[1].address2, None);
+        assert_eq!((adrs[1].zip5, adrs[1].zip4), (81003, 1234));
+
+        // An html page of a site without the endpoint.
+        assert!(prs_locations("<html></html>").is_err());
+    }
+
+    #[test]
+    fn test_merged_address1() {
+        let adr = |address1: &str, address2: Option<&str>| Address {
+            address1: address1.into(),
+            address2: address2.map(String::from),
+            ..Default::default()
+        };
+        let cases = [
+            (
+                adr("1919 SMITH ST", Some("STE 9047")),
+                "1919 SMITH ST STE 9047",
+            ),
+            (adr("1919 SMITH ST", None), "1919 SMITH ST"),
+            (adr("1919 SMITH ST", Some("")), "1919 SMITH ST"),
+            // 40 chars fit.
+            (
+                adr("1600 PENNSYLVANIA AVE NW", Some("RM 1234 STE 123")),
+                "1600 PENNSYLVANIA AVE NW RM 1234 STE 123",
+            ),
+            // Overflow keeps address2 separate.
+            (
+                adr("1600 PENNSYLVANIA AVE NW", Some("EISENHOWER EXEC OFC BLDG")),
+                "1600 PENNSYLVANIA AVE NW",
+            ),
+        ];
+        for (adr, expected) in cases {
+            assert_eq!(
+                adr.merged_address1(ADDRESS1_MAX),
+                expected,
+                "Failed to match: {}",
+                expected
+            );
+        }
+    }
+
+    #[test]
+    fn test_address_single_line() {
+        let adr = |address1: &str, address2: Option<&str>, zip5: u32, zip4: u16| Address {
+            address1: address1.into(),
+            address2: address2.map(String::from),
+            city: "BOSTON".into(),
+            state: "MA".into(),
+            zip5,
+            zip4,
+            ..Default::default()
+        };
+        let cases = [
+            (
+                adr("1 STATE ST", None, 2109, 0),
+                "1 STATE ST, BOSTON, MA 02109",
+            ),
+            (
+                adr("1 STATE ST", None, 2109, 1234),
+                "1 STATE ST, BOSTON, MA 02109-1234",
+            ),
+            (
+                adr("1 STATE ST", Some("STE 2"), 2109, 0),
+                "1 STATE ST, STE 2, BOSTON, MA 02109",
+            ),
+            (
+                adr("1 STATE ST", Some("FL 2, STE 200"), 2109, 45),
+                "1 STATE ST, FL 2, STE 200, BOSTON, MA 02109-0045",
+            ),
+        ];
+        for (adr, expected) in cases {
+            assert_eq!(
+                adr.to_single_line(),
+                expected,
+                "Failed to match: {}",
+                expected
+            );
+            assert_eq!(
+                expected.parse::<Address>().unwrap(),
+                adr,
+                "Failed to match: {}",
+                expected
+            );
+        }
+
+        let cases = [
+            ("", "expected"),
+            ("1 STATE ST, BOSTON", "expected"),
+            ("1 STATE ST, , MA 02109", "empty"),
+            ("1 STATE ST, BOSTON, MA", "STATE ZIP"),
+            ("1 STATE ST, BOSTON, MASS 02109", "invalid state"),
+            ("1 STATE ST, BOSTON, MA 2109", "invalid zip"),
+        ];
+        for (input, expected) in cases {
+            let err = input.parse::<Address>().unwrap_err().to_string();
+            assert!(err.contains(expected), "Failed to match: {}", input);
+        }
+    }
+
+    #[test]
+    fn test_dedup_persons() {
+        // Same address is merged.
+        let pers = dedup_persons(vec![
+            per("John A. Smith", "Administrator", &[20546]),
+            per("John  Smith", "Deputy Administrator", &[20546]),
+            per("Jane Smith", "Director", &[20546]),
+        ]);
+        assert_eq!(pers.len(), 2);
+        assert_eq!(pers[0].name, "John A. Smith");
+        assert_eq!(pers[0].title1, "Deputy Administrator");
+        assert_eq!(pers[0].adr_len(), 1);
+        assert_eq!(pers[1].name, "Jane Smith");
+
+        // Different addresses are unioned.
+        let pers = dedup_persons(vec![
+            per("John Smith", "Administrator", &[20546]),
+            per("JOHN A. SMITH", "", &[20771]),
+        ]);
+        assert_eq!(pers.len(), 1);
+        assert_eq!(pers[0].title1, "Administrator");
+        let zip5s: Vec<u32> = pers[0].adrs.iter().flatten().map(|adr| adr.zip5).collect();
+        assert_eq!(zip5s, vec![20546, 20771]);
+
+        assert_eq!(name_key("John A. Smith"), "john smith");
+    }
+
+    #[test]
+    fn test_reconcile_members() {
+        let with_url = |name: &str, url: &str, zip5s: &[u32]| Person {
+            url: url.into(),
+            ..per(name, "Member of Congress", zip5s)
+        };
+        let cached = vec![
+            with_url("Jane Doe", "https://doe.house.gov", &[20515]),
+            with_url("Bob Smith", "https://smith.house.gov", &[20516]),
+            with_url("John Roe", "https://roe.house.gov", &[20517]),
+        ];
+        let fresh = vec![
+            // Unchanged, with initials.
+            with_url("Jane Q. Doe", "https://doe.house.gov", &[]),
+            // Renamed at the same url.
+            with_url("Robert Smith", "https://smith.house.gov", &[]),
+            // Added, while John Roe is removed.
+            with_url("Ann Lee", "https://lee.house.gov", &[]),
+        ];
+        let (pers, stats) = reconcile_members(cached, fresh);
+
+        let names: Vec<&str> = pers.iter().map(|per| per.name.as_str()).collect();
+        assert_eq!(names, vec!["Jane Q. Doe", "Robert Smith", "Ann Lee"]);
+        let zip5s: Vec<Vec<u32>> = pers
+            .iter()
+            .map(|per| per.adrs.iter().flatten().map(|adr| adr.zip5).collect())
+            .collect();
+        assert_eq!(zip5s, vec![vec![20515], vec![20516], vec![]]);
+        assert!(pers[2].adrs.is_none());
+        assert_eq!(stats.kept, 1);
+        assert_eq!(stats.renamed, vec!["Bob Smith -> Robert Smith"]);
+        assert_eq!(stats.added, vec!["Ann Lee"]);
+        assert_eq!(stats.removed, vec!["John Roe"]);
+        assert!(!stats.is_empty());
+    }
+}
@@ -0,0 +1,137 @@
+use serde::{Deserialize, Deserializer, Serialize};
+use std::fmt;
+use std::iter::Sum;
+use std::ops::Add;
+
+/// Mills per dollar; a mill is a tenth of a cent, the precision of USPS prices.
+const MILLS_PER_DOLLAR: u64 = 1_000;
+/// Mills per cent.
+const MILLS_PER_CENT: u64 = 10;
+
+/// An amount of money in mills, such as 173 for a 17.3¢ price.
+///
+/// Serialized as mills; dollars of older files, such as `34.599999999999994`,
+/// deserialize rounded to the mill.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize)]
+pub struct Money(pub u64);
+
+impl Money {
+    pub const fn from_mills(mills: u64) -> Money {
+        Money(mills)
+    }
+
+    /// Price of a count of pieces at a price per piece, exact to the mill.
+    pub fn times(self, cnt: u64) -> Money {
+        Money(self.0 * cnt)
+    }
+
+    /// Rounds to the nearest cent, halves up, such as a postage total.
+    pub fn round_cents(self) -> Money {
+        Money((self.0 + MILLS_PER_CENT / 2) / MILLS_PER_CENT * MILLS_PER_CENT)
+    }
+}
+
+impl fmt::Display for Money {
+    /// Dollars with cents, and tenths of a cent when any: "26.47", "26.469".
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let dollars = self.0 / MILLS_PER_DOLLAR;
+        let mills = self.0 % MILLS_PER_DOLLAR;
+        if mills.is_multiple_of(MILLS_PER_CENT) {
+            write!(f, "{dollars}.{:02}", mills / MILLS_PER_CENT)
+        } else {
+            write!(f, "{dollars}.{mills:03}")
+        }
+    }
+}
+
+impl Add for Money {
+    type Output = Money;
+
+    fn add(self, other: Money) -> Money {
+        Money(self.0 + other.0)
+    }
+}
+
+impl Sum for Money {
+    fn sum<I: Iterator<Item = Money>>(iter: I) -> Money {
+        iter.fold(Money::default(), Add::add)
+    }
+}
+
+impl<'de> Deserialize<'de> for Money {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Money, D::Error> {
+        #[derive(Deserialize)]
+        #[serde(untagged)]
+        enum Raw {
+            Mills(u64),
+            Dollars(f64),
+        }
+        match Raw::deserialize(deserializer)? {
+            Raw::Mills(mills) => Ok(Money(mills)),
+            Raw::Dollars(dollars) if dollars >= 0.0 => {
+                Ok(Money((dollars * MILLS_PER_DOLLAR as f64).round() as u64))
+            }
+            Raw::Dollars(dollars) => Err(serde::de::Error::custom(format!(
+                "negative money {dollars}"
+            ))),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_money() {
+        let prc = Money::from_mills(173);
+        let cases = [
+            // Subtotals are exact to the mill, totals round to the cent.
+            (153, "26.469", "26.47"),
+            (200, "34.60", "34.60"),
+            (1_200, "207.60", "207.60"),
+            (5, "0.865", "0.87"),
+            (1, "0.173", "0.17"),
+            (0, "0.00", "0.00"),
+        ];
+        for (cnt, subtotal, total) in cases {
+            let amt = prc.times(cnt);
+            assert_eq!(amt.to_string(), subtotal, "Failed to match: {}", cnt);
+            assert_eq!(
+                amt.round_cents().to_string(),
+                total,
+                "Failed to match: {}",
+                cnt
+            );
+        }
+
+        // Halves round up.
+        assert_eq!(Money(26_465).round_cents(), Money(26_470));
+        assert_eq!(Money(26_464).round_cents(), Money(26_460));
+        assert_eq!(
+            [Money(207_600), Money(52_000)].into_iter().sum::<Money>(),
+            Money(259_600)
+        );
+    }
+
+    #[test]
+    fn test_money_serde() {
+        assert_eq!(serde_json::to_string(&Money(34_600)).unwrap(), "34600");
+        let cases = [
+            ("34600", Money(34_600)),
+            // Dollars of older files.
+            ("34.599999999999994", Money(34_600)),
+            ("0.0", Money(0)),
+            ("259.6", Money(259_600)),
+        ];
+        for (json, expected) in cases {
+            assert_eq!(
+                serde_json::from_str::<Money>(json).unwrap(),
+                expected,
+                "Failed to match: {}",
+                json
+            );
+        }
+        assert!(serde_json::from_str::<Money>("-1.5").is_err());
+    }
+}
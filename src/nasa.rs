@@ -1,22 +1,17 @@
-use crate::core::*;
+use crate::facility::*;
+use crate::io::*;
 use crate::models::*;
 use crate::prsr::*;
-use crate::usps::*;
-use anyhow::{anyhow, Result};
-use reqwest::Client;
+use crate::report::*;
+use anyhow::Result;
 use scraper::{Html, Selector};
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
-use std::fmt::Display;
-use std::fs::File;
-use std::io::{BufReader, BufWriter};
-use std::ops::Add;
-use std::path::Path;
 use strum::EnumIter; // Required to derive EnumIter
-use strum::IntoEnumIterator; // Required for iterating over the enum
+use tracing::{debug, info, trace};
 use Center::*;
 
-const FLE_PTH: &str = "nasa.json";
+pub const FLE_PTH: &str = "nasa.json";
 const FLE_PTH_ADR: &str = "nasa_adr.json";
 
 #[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Eq, PartialOrd, Ord)]
@@ -26,6 +21,12 @@ pub struct Nasa {
     pub persons: Vec<Person>,
 }
 
+impl Default for Nasa {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 impl Nasa {
     pub fn new() -> Self {
         Self {
@@ -36,36 +37,25 @@ impl Nasa {
     }
 
     pub async fn load() -> Result<Nasa> {
+        REPORT.lock().unwrap().begin(Source::Nasa);
+
         // Read file from disk.
-        let mut nasa = match read_from_file::<Nasa>(FLE_PTH) {
-            Ok(nasa_from_disk) => nasa_from_disk,
+        let nasa = match read_from_file::<Nasa>(FLE_PTH) {
+            Ok(mut nasa_from_disk) => {
+                // Backfill roles of older files.
+                set_roles(&mut nasa_from_disk.persons, &nasa_from_disk.role);
+                nasa_from_disk
+            }
             Err(_) => {
                 let mut nasa = Nasa::new();
 
-                let adrs = &fetch_adrs().await?;
+                let adrs = &fetch_facility_adrs::<Center>(FLE_PTH_ADR).await?;
 
                 // Fetch members.
-                nasa.persons.extend(nasa.fetch_members_hq(adrs).await?);
-
-                // Directorates
-                nasa.persons.extend(nasa.fetch_members_armd(adrs).await?);
-                nasa.persons.extend(nasa.fetch_members_esdmd(adrs).await?);
-                nasa.persons.extend(nasa.fetch_members_stmd(adrs).await?);
-                nasa.persons.extend(nasa.fetch_members_somd(adrs).await?);
-
-                // Centers
-                nasa.persons.extend(nasa.fetch_members_ames_1(adrs).await?);
-                nasa.persons.extend(nasa.fetch_members_ames_2(adrs).await?);
-                nasa.persons
-                    .extend(nasa.fetch_members_ames_science_staff(adrs).await?);
-                nasa.persons
-                    .extend(nasa.fetch_members_armstrong(adrs).await?);
-                nasa.persons.extend(nasa.fetch_members_glenn(adrs).await?);
-                nasa.persons.extend(nasa.fetch_members_goddard(adrs).await?);
-                nasa.persons.extend(nasa.fetch_members_johnson(adrs).await?);
-
-                // nasa.persons.sort_unstable();
-                nasa.persons.dedup_by(|a, b| a == b);
+                nasa.persons = nasa.fetch_members_all(adrs).await?;
+
+                sort_dedup_by_origin(&mut nasa.persons);
+                set_roles(&mut nasa.persons, &nasa.role);
 
                 // Write file to disk.
                 write_to_file(&nasa, FLE_PTH)?;
@@ -74,14 +64,43 @@ impl Nasa {
             }
         };
 
-        println!("{} scientific leaders", nasa.persons.len());
+        info!("{} scientific leaders", nasa.persons.len());
 
         Ok(nasa)
     }
 
+    /// Fetch members of headquarters, directorates and centers.
+    #[rustfmt::skip]
+    pub async fn fetch_members_all(&self, adrs: &HashMap<Center, Address>) -> Result<Vec<Person>> {
+        let mut pers = Vec::with_capacity(100);
+        pers.extend(self.fetch_members_hq(adrs).await?);
+
+        // Directorates
+        pers.extend(self.fetch_members_armd(adrs).await?);
+        pers.extend(self.fetch_members_esdmd(adrs).await?);
+        pers.extend(self.fetch_members_stmd(adrs).await?);
+        pers.extend(self.fetch_members_somd(adrs).await?);
+
+        // Centers
+        pers.extend(self.fetch_members_ames_1(adrs).await?);
+        pers.extend(self.fetch_members_ames_2(adrs).await?);
+        pers.extend(self.fetch_members_ames_science_staff(adrs).await?);
+        pers.extend(self.fetch_members_armstrong(adrs).await?);
+        pers.extend(self.fetch_members_glenn(adrs).await?);
+        pers.extend(self.fetch_members_goddard(adrs).await?);
+        pers.extend(self.fetch_members_johnson(adrs).await?);
+        pers.extend(self.fetch_members_kennedy(adrs).await?);
+        pers.extend(self.fetch_members_jpl(adrs).await?);
+        pers.extend(self.fetch_members_langley(adrs).await?);
+        pers.extend(self.fetch_members_marshall(adrs).await?);
+        pers.extend(self.fetch_members_safety(adrs).await?);
+
+        Ok(pers)
+    }
+
     pub async fn fetch_members_hq(&self, adrs: &HashMap<Center, Address>) -> Result<Vec<Person>> {
         let url = "https://www.nasa.gov/organization";
-        let html = fetch_html(url).await?;
+        let html = fetch_html(url, FetchOptions::for_source(Source::Nasa)).await?;
         let document = Html::parse_document(&html);
 
         // Define the CSS selector for the members list.
@@ -89,15 +108,12 @@ impl Nasa {
         let tbl_sel = Selector::parse("table").unwrap();
         let row_sel = Selector::parse("tr").unwrap();
         let name_sel = Selector::parse("td:nth-of-type(1)").unwrap();
-        let title_sel = Selector::parse("td:nth-of-type(2)").unwrap();
-        let office_sel = Selector::parse("td:nth-of-type(3)").unwrap();
 
         // Select all headers.
         let hdrs = document
             .select(&hdr_sel)
             .map(|elm| elm.text().collect::<String>().to_uppercase())
             .collect::<Vec<_>>();
-        // eprintln!("{hdrs:?}");
 
         // Iterate over each member entry.
         let mut pers = Vec::new();
@@ -105,21 +121,21 @@ impl Nasa {
             if hdrs[idx] == "CENTERS AND FACILITIES" {
                 continue;
             }
-            eprintln!("  {}", hdrs[idx]);
+            debug!("  {}", hdrs[idx]);
             for row_elm in tbl_elm.select(&row_sel) {
                 if let Some(elm) = row_elm.select(&name_sel).next() {
                     let full_name = elm.text().collect::<String>();
                     if full_name.trim().contains("(Vacant)") {
                         continue;
                     }
-                    //eprintln!("{}", full_name.trim());
-                    let mut per = Person {
+                    let per = Person {
                         name: name_clean(&full_name),
                         adrs: Some(vec![adrs[&HQ].clone()]),
+                        provenance: Some(HQ.provenance()),
                         ..Default::default()
                     };
 
-                    eprintln!("{}", per);
+                    debug!("{}", per);
                     pers.push(per);
                 }
             }
@@ -130,7 +146,7 @@ impl Nasa {
 
     pub async fn fetch_members_armd(&self, adrs: &HashMap<Center, Address>) -> Result<Vec<Person>> {
         let url = "https://www.nasa.gov/directorates/armd/aeronautics-leadership/";
-        let html = fetch_html(url).await?;
+        let html = fetch_html(url, FetchOptions::for_source(Source::Nasa)).await?;
         let document = Html::parse_document(&html);
 
         // Define the CSS selector for the members list.
@@ -144,12 +160,11 @@ impl Nasa {
             .select(&hdr_sel)
             .map(|elm| elm.text().collect::<String>().to_uppercase())
             .collect::<Vec<_>>();
-        // eprintln!("{hdrs:?}");
 
         // Iterate over each member entry.
         let mut pers = Vec::new();
         for (idx, tbl_elm) in document.select(&tbl_sel).enumerate() {
-            eprintln!("  {}", hdrs[idx]);
+            debug!("  {}", hdrs[idx]);
             for row_elm in tbl_elm.select(&row_sel) {
                 if hdrs[idx] != "OFFICE OF THE ASSOCIATE ADMINISTRATOR" && hdrs[idx] != "OFFICES" {
                     continue;
@@ -157,14 +172,14 @@ impl Nasa {
 
                 if let Some(elm) = row_elm.select(&name_sel).next() {
                     let full_name = elm.text().collect::<String>();
-                    //eprintln!("{}", full_name.trim());
-                    let mut per = Person {
+                    let per = Person {
                         name: name_clean(&full_name),
                         adrs: Some(vec![adrs[&HQ].clone()]),
+                        provenance: Some(HQ.provenance()),
                         ..Default::default()
                     };
 
-                    eprintln!("{}", per);
+                    debug!("{}", per);
                     pers.push(per);
                 }
             }
@@ -178,7 +193,7 @@ impl Nasa {
         adrs: &HashMap<Center, Address>,
     ) -> Result<Vec<Person>> {
         let url = "https://www.nasa.gov/exploration-systems-development-mission-directorate/";
-        let html = fetch_html(url).await?;
+        let html = fetch_html(url, FetchOptions::for_source(Source::Nasa)).await?;
         let document = Html::parse_document(&html);
 
         // Define the CSS selector for the members list.
@@ -192,12 +207,11 @@ impl Nasa {
             .select(&hdr_sel)
             .map(|elm| elm.text().collect::<String>().to_uppercase())
             .collect::<Vec<_>>();
-        // eprintln!("{hdrs:?}");
 
         // Iterate over each member entry.
         let mut pers = Vec::new();
         for (idx, tbl_elm) in document.select(&tbl_sel).enumerate() {
-            eprintln!("  {}", hdrs[idx]);
+            debug!("  {}", hdrs[idx]);
             for row_elm in tbl_elm.select(&row_sel) {
                 if hdrs[idx] != "ESDMD LEADERSHIP" && hdrs[idx] != "MOON TO MARS PROGRAM OFFICE" {
                     continue;
@@ -205,14 +219,14 @@ impl Nasa {
 
                 if let Some(elm) = row_elm.select(&name_sel).next() {
                     let full_name = elm.text().collect::<String>();
-                    //eprintln!("{}", full_name.trim());
-                    let mut per = Person {
+                    let per = Person {
                         name: name_clean(&full_name),
                         adrs: Some(vec![adrs[&HQ].clone()]),
+                        provenance: Some(HQ.provenance()),
                         ..Default::default()
                     };
 
-                    eprintln!("{}", per);
+                    debug!("{}", per);
                     pers.push(per);
                 }
             }
@@ -223,7 +237,7 @@ impl Nasa {
 
     pub async fn fetch_members_stmd(&self, adrs: &HashMap<Center, Address>) -> Result<Vec<Person>> {
         let url = "https://www.nasa.gov/about-stmd/";
-        let html = fetch_html(url).await?;
+        let html = fetch_html(url, FetchOptions::for_source(Source::Nasa)).await?;
         let document = Html::parse_document(&html);
 
         // Define the CSS selector for the members list.
@@ -237,23 +251,22 @@ impl Nasa {
             .select(&hdr_sel)
             .map(|elm| elm.text().collect::<String>().to_uppercase())
             .collect::<Vec<_>>();
-        // eprintln!("{hdrs:?}");
 
         // Iterate over each member entry.
         let mut pers = Vec::new();
         for (idx, tbl_elm) in document.select(&tbl_sel).enumerate() {
-            eprintln!("  {}", hdrs[idx]);
+            debug!("  {}", hdrs[idx]);
             for row_elm in tbl_elm.select(&row_sel) {
                 if let Some(elm) = row_elm.select(&name_sel).next() {
                     let full_name = elm.text().collect::<String>();
-                    //eprintln!("{}", full_name.trim());
-                    let mut per = Person {
+                    let per = Person {
                         name: name_clean(&full_name),
                         adrs: Some(vec![adrs[&HQ].clone()]),
+                        provenance: Some(HQ.provenance()),
                         ..Default::default()
                     };
 
-                    eprintln!("{}", per);
+                    debug!("{}", per);
                     pers.push(per);
                 }
             }
@@ -264,7 +277,7 @@ impl Nasa {
 
     pub async fn fetch_members_somd(&self, adrs: &HashMap<Center, Address>) -> Result<Vec<Person>> {
         let url = "https://www.nasa.gov/directorates/space-operations/";
-        let html = fetch_html(url).await?;
+        let html = fetch_html(url, FetchOptions::for_source(Source::Nasa)).await?;
         let document = Html::parse_document(&html);
 
         // Define the CSS selector for the members list.
@@ -278,12 +291,11 @@ impl Nasa {
             .select(&hdr_sel)
             .map(|elm| elm.text().collect::<String>().to_uppercase())
             .collect::<Vec<_>>();
-        // eprintln!("{hdrs:?}");
 
         // Iterate over each member entry.
         let mut pers = Vec::new();
         for (idx, tbl_elm) in document.select(&tbl_sel).enumerate() {
-            eprintln!("  {}", hdrs[idx]);
+            debug!("  {}", hdrs[idx]);
             for row_elm in tbl_elm.select(&row_sel) {
                 if hdrs[idx] != "SPACE OPERATIONS LEADERSHIP" {
                     continue;
@@ -291,14 +303,14 @@ impl Nasa {
 
                 if let Some(elm) = row_elm.select(&name_sel).next() {
                     let full_name = elm.text().collect::<String>();
-                    //eprintln!("{}", full_name.trim());
-                    let mut per = Person {
+                    let per = Person {
                         name: name_clean(&full_name),
                         adrs: Some(vec![adrs[&HQ].clone()]),
+                        provenance: Some(HQ.provenance()),
                         ..Default::default()
                     };
 
-                    eprintln!("{}", per);
+                    debug!("{}", per);
                     pers.push(per);
                 }
             }
@@ -312,7 +324,7 @@ impl Nasa {
         adrs: &HashMap<Center, Address>,
     ) -> Result<Vec<Person>> {
         let url = "https://www.nasa.gov/ames/ames-leadership-organizations/";
-        let html = fetch_html(url).await?;
+        let html = fetch_html(url, FetchOptions::for_source(Source::Nasa)).await?;
         let document = Html::parse_document(&html);
 
         // Define the CSS selector for the members list.
@@ -325,14 +337,14 @@ impl Nasa {
             if full_name.trim() == "Ames Research Center" {
                 continue;
             }
-            //eprintln!("{}", full_name.trim());
             let mut per = Person {
                 name: name_clean(&full_name),
                 ..Default::default()
             };
             per.adrs = Some(vec![adrs[&Ames].clone()]);
+            per.provenance = Some(Ames.provenance());
 
-            eprintln!("{}", per);
+            debug!("{}", per);
             pers.push(per);
         }
 
@@ -344,7 +356,7 @@ impl Nasa {
         adrs: &HashMap<Center, Address>,
     ) -> Result<Vec<Person>> {
         let url = "https://www.nasa.gov/ames/science/management-support/";
-        let html = fetch_html(url).await?;
+        let html = fetch_html(url, FetchOptions::for_source(Source::Nasa)).await?;
         let document = Html::parse_document(&html);
 
         // Define the CSS selector for the members list.
@@ -358,14 +370,14 @@ impl Nasa {
             for row_elm in tbl_elm.select(&row_sel) {
                 if let Some(elm) = row_elm.select(&name_sel).next() {
                     let full_name = elm.text().collect::<String>();
-                    //eprintln!("{}", full_name.trim());
-                    let mut per = Person {
+                    let per = Person {
                         name: name_clean(&full_name),
                         adrs: Some(vec![adrs[&Ames].clone()]),
+                        provenance: Some(Ames.provenance()),
                         ..Default::default()
                     };
 
-                    eprintln!("{}", per);
+                    debug!("{}", per);
                     pers.push(per);
                 }
             }
@@ -393,7 +405,7 @@ impl Nasa {
             "https://www.nasa.gov/space-science-and-astrobiology-at-ames/who-we-are/members-stx/",
         ];
         for url in urls {
-            let html = fetch_html(url).await?;
+            let html = fetch_html(url, FetchOptions::for_source(Source::Nasa)).await?;
             let document = Html::parse_document(&html);
 
             // Define the CSS selector for the members list.
@@ -406,14 +418,14 @@ impl Nasa {
                 for row_elm in tbl_elm.select(&row_sel) {
                     if let Some(elm) = row_elm.select(&name_sel).next() {
                         let full_name = elm.text().collect::<String>();
-                        //eprintln!("{}", full_name.trim());
-                        let mut per = Person {
+                        let per = Person {
                             name: name_clean(&full_name),
                             adrs: Some(vec![adrs[&Ames].clone()]),
+                            provenance: Some(Ames.provenance()),
                             ..Default::default()
                         };
 
-                        eprintln!("{}", per);
+                        debug!("{}", per);
                         pers.push(per);
                     }
                 }
@@ -428,7 +440,7 @@ impl Nasa {
         adrs: &HashMap<Center, Address>,
     ) -> Result<Vec<Person>> {
         let url = "https://www.nasa.gov/armstrong/people/leadership-organizations/#center-director";
-        let html = fetch_html(url).await?;
+        let html = fetch_html(url, FetchOptions::for_source(Source::Nasa)).await?;
         let document = Html::parse_document(&html);
 
         // Define the CSS selector for the members list.
@@ -441,17 +453,16 @@ impl Nasa {
         for tbl_elm in document.select(&tbl_sel) {
             if let Some(elm) = tbl_elm.select(&title_sel).next() {
                 let title = elm.text().collect::<String>();
-                // eprintln!("title:{title}");
                 if title.trim().ends_with(':') {
                     if let Some(elm) = tbl_elm.select(&name_sel).next() {
                         let full_name = elm.text().collect::<String>();
-                        //eprintln!("{}", full_name.trim());
-                        let mut per = Person {
+                        let per = Person {
                             name: name_clean(&full_name),
                             adrs: Some(vec![adrs[&Armstrong].clone()]),
+                            provenance: Some(Armstrong.provenance()),
                             ..Default::default()
                         };
-                        eprintln!("{}", per);
+                        debug!("{}", per);
                         pers.push(per);
                     }
                 }
@@ -466,7 +477,7 @@ impl Nasa {
         adrs: &HashMap<Center, Address>,
     ) -> Result<Vec<Person>> {
         let url = "https://www.nasa.gov/about-glenn-research-center/nasa-glenn-leadership/";
-        let html = fetch_html(url).await?;
+        let html = fetch_html(url, FetchOptions::for_source(Source::Nasa)).await?;
         let document = Html::parse_document(&html);
 
         // Define the CSS selector for the members list.
@@ -480,14 +491,14 @@ impl Nasa {
             for row_elm in tbl_elm.select(&row_sel) {
                 if let Some(elm) = row_elm.select(&name_sel).next() {
                     let full_name = elm.text().collect::<String>();
-                    //eprintln!("{}", full_name.trim());
-                    let mut per = Person {
+                    let per = Person {
                         name: name_clean(&full_name),
                         adrs: Some(vec![adrs[&Glenn].clone()]),
+                        provenance: Some(Glenn.provenance()),
                         ..Default::default()
                     };
 
-                    eprintln!("{}", per);
+                    debug!("{}", per);
                     pers.push(per);
                 }
             }
@@ -501,7 +512,7 @@ impl Nasa {
         adrs: &HashMap<Center, Address>,
     ) -> Result<Vec<Person>> {
         let url = "https://www.nasa.gov/goddard/about/#leadership";
-        let html = fetch_html(url).await?;
+        let html = fetch_html(url, FetchOptions::for_source(Source::Nasa)).await?;
         let document = Html::parse_document(&html);
 
         // Define the CSS selector for the members list.
@@ -511,14 +522,14 @@ impl Nasa {
         let mut pers = Vec::new();
         for elm in document.select(&a_sel) {
             let full_name = elm.text().collect::<String>();
-            //eprintln!("{}", full_name.trim());
-            let mut per = Person {
+            let per = Person {
                 name: name_clean(&full_name),
                 adrs: Some(vec![adrs[&Goddard].clone()]),
+                provenance: Some(Goddard.provenance()),
                 ..Default::default()
             };
 
-            eprintln!("{}", per);
+            debug!("{}", per);
             pers.push(per);
         }
 
@@ -530,7 +541,7 @@ impl Nasa {
         adrs: &HashMap<Center, Address>,
     ) -> Result<Vec<Person>> {
         let url = "https://www.nasa.gov/johnson/#leadership";
-        let html = fetch_html(url).await?;
+        let html = fetch_html(url, FetchOptions::for_source(Source::Nasa)).await?;
         let document = Html::parse_document(&html);
 
         // Define the CSS selector for the members list.
@@ -545,7 +556,7 @@ impl Nasa {
             // Select current header.
             if let Some(hdr_elm) = tbl_elm.select(&hdr_sel).next() {
                 let hdr = hdr_elm.text().collect::<String>().to_uppercase();
-                eprintln!("{hdr:?}");
+                trace!("{hdr:?}");
                 if &hdr != "JOHNSON LEADERSHIP" {
                     continue;
                 }
@@ -554,15 +565,15 @@ impl Nasa {
             for row_elm in tbl_elm.select(&row_sel) {
                 if let Some(elm) = row_elm.select(&name_sel).next() {
                     let full_name = elm.text().collect::<String>();
-                    //eprintln!("{}", full_name.trim());
                     let full_name = full_name.split_terminator(',').next().unwrap_or_default();
-                    let mut per = Person {
+                    let per = Person {
                         name: name_clean(full_name),
                         adrs: Some(vec![adrs[&Johnson].clone()]),
+                        provenance: Some(Johnson.provenance()),
                         ..Default::default()
                     };
 
-                    eprintln!("{}", per);
+                    debug!("{}", per);
                     pers.push(per);
                 }
             }
@@ -570,189 +581,165 @@ impl Nasa {
 
         Ok(pers)
     }
-}
-
-pub async fn fetch_adrs() -> Result<HashMap<Center, Address>> {
-    // Read file from disk.
-    let mut map_adrs = match read_from_file::<HashMap<Center, Address>>(FLE_PTH_ADR) {
-        Ok(map_adrs) => map_adrs,
-        Err(_) => {
-            let mut map_adrs = HashMap::new();
-
-            // Iterate through each center.
-            for ctr in Center::iter() {
-                println!("{:?}", ctr);
-
-                // Get url.
-                let url = adr_url(ctr);
-                if url.is_empty() {
-                    continue;
-                }
 
-                // Fetch, parse, and standardize each address.
-                match fetch_prs_std_adr(ctr, &url).await? {
-                    None => {}
-                    Some(mut adr) => {
-                        // Easy way to clean address2. Due to "CENTER".
-                        adr.address2 = None;
-                        map_adrs.insert(ctr, adr);
-                    }
-                }
-            }
-
-            // Write file to disk.
-            write_to_file(&map_adrs, FLE_PTH_ADR)?;
+    pub async fn fetch_members_kennedy(
+        &self,
+        adrs: &HashMap<Center, Address>,
+    ) -> Result<Vec<Person>> {
+        let url = "https://www.nasa.gov/kennedy/kennedy-leadership/";
+        let html = fetch_html(url, FetchOptions::for_source(Source::Nasa)).await?;
+        Ok(prs_leaders(&html, SEL_CARD_GRID, Kennedy, adrs))
+    }
 
-            map_adrs
-        }
-    };
+    pub async fn fetch_members_jpl(&self, adrs: &HashMap<Center, Address>) -> Result<Vec<Person>> {
+        let url = "https://www.jpl.nasa.gov/who-we-are/executive-council";
+        let html = fetch_html(url, FetchOptions::for_source(Source::Nasa)).await?;
+        // Council members are headings of the page body.
+        Ok(prs_leaders(&html, "main h3", Jpl, adrs))
+    }
 
-    Ok(map_adrs)
-}
+    pub async fn fetch_members_langley(
+        &self,
+        adrs: &HashMap<Center, Address>,
+    ) -> Result<Vec<Person>> {
+        let url = "https://www.nasa.gov/langley/leadership/";
+        let html = fetch_html(url, FetchOptions::for_source(Source::Nasa)).await?;
+        Ok(prs_leaders(&html, SEL_MEET_THE, Langley, adrs))
+    }
 
-/// Fetch, parse, and standardize an address.
-pub async fn fetch_prs_std_adr(ctr: Center, url: &str) -> Result<Option<Address>> {
-    // Fetch html.
-    let html = fetch_html(url).await?;
-
-    // Parse html to address lines.
-    let adr_lnes_o = prs_adr_lnes(ctr, &html);
-
-    // Parse lines to addresses.
-    let adrs_o = match adr_lnes_o {
-        None => None,
-        Some(mut adr_lnes) => match PRSR.prs_adrs(&adr_lnes) {
-            None => None,
-            Some(mut adrs) => {
-                adrs = standardize_addresses(adrs).await?;
-                if adrs.is_empty() {
-                    None
-                } else {
-                    Some(adrs.remove(0))
-                }
-            }
-        },
-    };
+    pub async fn fetch_members_marshall(
+        &self,
+        adrs: &HashMap<Center, Address>,
+    ) -> Result<Vec<Person>> {
+        let url = "https://www.nasa.gov/marshall/leadership/";
+        let html = fetch_html(url, FetchOptions::for_source(Source::Nasa)).await?;
+        Ok(prs_leaders(&html, SEL_CARD_GRID, Marshall, adrs))
+    }
 
-    Ok(adrs_o)
+    pub async fn fetch_members_safety(
+        &self,
+        adrs: &HashMap<Center, Address>,
+    ) -> Result<Vec<Person>> {
+        let url = "https://www.nasa.gov/nasa-safety-center-leadership/";
+        let html = fetch_html(url, FetchOptions::for_source(Source::Nasa)).await?;
+        Ok(prs_leaders(&html, SEL_CARD_GRID, Safety, adrs))
+    }
 }
 
-pub fn prs_adr_lnes(ctr: Center, html: &str) -> Option<Vec<String>> {
+/// Names of leaders in card grids, such as Glenn leadership.
+const SEL_CARD_GRID: &str = "div.hds-card-grid div.hds-card-inner h3";
+
+/// Names of leaders in meet-the sections, such as Goddard leadership.
+const SEL_MEET_THE: &str = "div.hds-meet-the-content a";
+
+/// Parses leaders of a center from names matching a selector.
+///
+/// Vacant seats are skipped and titles after a comma are trimmed.
+fn prs_leaders(
+    html: &str,
+    sel: &str,
+    center: Center,
+    adrs: &HashMap<Center, Address>,
+) -> Vec<Person> {
     let document = Html::parse_document(html);
-    let mut lnes: Vec<String> = Vec::new();
-    for txt in ["body"] {
-        let selector = Selector::parse(txt).unwrap();
-        for elm in document.select(&selector) {
-            // Extract lines from html.
-            let mut cur_lnes = elm
-                .text()
-                .map(|s| s.trim().trim_end_matches(',').to_uppercase().to_string())
-                .collect::<Vec<String>>();
-
-            // eprintln!("--- pre: {cur_lnes:?}");
-
-            // Filter lines.
-            cur_lnes = cur_lnes
-                .into_iter()
-                .filter(|s| PRSR.filter(s))
-                .collect::<Vec<String>>();
-
-            eprintln!("{cur_lnes:?}");
-
-            lnes.extend(cur_lnes);
+    let name_sel = Selector::parse(sel).unwrap();
+
+    // Iterate over each member entry.
+    let mut pers = Vec::new();
+    for elm in document.select(&name_sel) {
+        let full_name = elm.text().collect::<String>();
+        let full_name = full_name.split_terminator(',').next().unwrap_or_default();
+        if full_name.trim().is_empty() || full_name.contains("Vacant") {
+            continue;
         }
+        let per = Person {
+            name: name_clean(full_name),
+            adrs: Some(vec![adrs[&center].clone()]),
+            provenance: Some(center.provenance()),
+            ..Default::default()
+        };
 
-        if !lnes.is_empty() {
-            break;
-        }
+        debug!("{}", per);
+        pers.push(per);
     }
 
-    // eprintln!("--- pre: {lnes:?}");
-
-    // Edit lines to make it easier to parse.
-    edit_dot(&mut lnes);
-    edit_nbsp_zwsp(&mut lnes);
-    edit_mailing(&mut lnes);
-    edit_nasa_lnes(ctr, &mut lnes);
-    PRSR.edit_lnes(&mut lnes);
-    edit_newline(&mut lnes);
-    edit_split_comma(&mut lnes);
-    edit_starting_hash(&mut lnes);
-    edit_char_half(&mut lnes);
-    edit_empty(&mut lnes);
-
-    eprintln!("--- --- --- post: {lnes:?}");
-
-    // Do not check for zip count here.
-
-    Some(lnes)
+    pers
 }
 
-pub fn edit_nasa_lnes(ctr: Center, lnes: &mut Vec<String>) {
-    match ctr {
-        HQ => {
-            for idx in (0..lnes.len()).rev() {
-                if lnes[idx] == "300 E STREET SW, SUITE 5R30" {
-                    lnes[idx] = "300 E STREET SW".into();
+impl Facility for Center {
+    const SOURCE: Source = Source::Nasa;
+    const SELECTORS: &'static [&'static str] = &["body"];
+
+    fn url(&self) -> &str {
+        match self {
+            Ames => "https://www.nasa.gov/ames-earth-science-contact-us/",
+            Armstrong => "https://www.nasa.gov/armstrong/overview/",
+            Glenn => "https://www.grc.nasa.gov/WWW/K-12/directions.html",
+            Goddard => "https://www.nasa.gov/centers-and-facilities/goddard/driving-directions-to-the-goddard-visitor-center/",
+            HQ => "https://www.nasa.gov/contact/",
+            Johnson => "https://www.nasa.gov/johnson/center-operations-directorate/",
+            Jpl => "https://www.jpl.nasa.gov/jpl-and-the-community/directions-and-maps",
+            Kennedy => "https://www.nasa.gov/kennedy-information/",
+            Langley => "https://www.nasa.gov/centers-and-facilities/langley/contacting-nasas-langley-research-center/",
+            Marshall => "https://www.nasa.gov/marshall/visit-marshall-space-flight-center/",
+            Safety => "https://www.nasa.gov/nasa-safety-center-overview/#contact",
+        }
+    }
+
+    fn edit_lnes(&self, lnes: &mut Vec<String>) {
+        match self {
+            HQ => {
+                for idx in (0..lnes.len()).rev() {
+                    if lnes[idx] == "300 E STREET SW, SUITE 5R30" {
+                        lnes[idx] = "300 E STREET SW".into();
+                    }
                 }
             }
-        }
-        Goddard => {
-            for idx in (0..lnes.len()).rev() {
-                if lnes[idx] == "9432 GREENBELT ROAD" {
-                    lnes.remove(idx + 1);
-                    lnes.remove(idx);
+            Goddard => {
+                for idx in (0..lnes.len()).rev() {
+                    if lnes[idx] == "9432 GREENBELT ROAD" {
+                        lnes.remove(idx + 1);
+                        lnes.remove(idx);
+                    }
                 }
             }
-        }
-        Kennedy => {
-            for idx in (0..lnes.len()).rev() {
-                if lnes[idx] == "JOHN F KENNEDY SPACE CENTER" {
-                    lnes[idx] = "KENNEDY SPACE CENTER".into();
+            Kennedy => {
+                for idx in (0..lnes.len()).rev() {
+                    if lnes[idx] == "JOHN F KENNEDY SPACE CENTER" {
+                        lnes[idx] = "KENNEDY SPACE CENTER".into();
+                    }
                 }
             }
-        }
-        Jpl => {
-            for idx in (0..lnes.len()).rev() {
-                if lnes[idx].starts_with("STREET ADDRESS FOR USE") {
-                    lnes.remove(idx + 2);
-                    lnes.remove(idx + 1);
+            Jpl => {
+                for idx in (0..lnes.len()).rev() {
+                    if lnes[idx].starts_with("STREET ADDRESS FOR USE") {
+                        lnes.remove(idx + 2);
+                        lnes.remove(idx + 1);
+                    }
                 }
             }
-        }
-        Marshall => {
-            for idx in (0..lnes.len()).rev() {
-                if lnes[idx].starts_with("PO BOX") {
-                    lnes[idx] = "MARSHALL SPACE FLIGHT CENTER".into();
+            Marshall => {
+                for idx in (0..lnes.len()).rev() {
+                    if lnes[idx].starts_with("PO BOX") {
+                        lnes[idx] = "MARSHALL SPACE FLIGHT CENTER".into();
+                    }
                 }
             }
-        }
-        Langley => {
-            for idx in (0..lnes.len()).rev() {
-                if lnes[idx].contains("23681-2199") {
-                    lnes[idx] = lnes[idx].replace("23681-2199", "23681")
+            Langley => {
+                for idx in (0..lnes.len()).rev() {
+                    if lnes[idx].contains("23681-2199") {
+                        lnes[idx] = lnes[idx].replace("23681-2199", "23681")
+                    }
                 }
             }
+            _ => {}
         }
-        _ => {}
     }
-}
 
-fn adr_url(ctr: Center) -> String {
-    match ctr {
-        Ames => "https://www.nasa.gov/ames-earth-science-contact-us/",
-        Armstrong => "https://www.nasa.gov/armstrong/overview/",
-        Glenn => "https://www.grc.nasa.gov/WWW/K-12/directions.html",
-        Goddard => "https://www.nasa.gov/centers-and-facilities/goddard/driving-directions-to-the-goddard-visitor-center/",
-        HQ => "https://www.nasa.gov/contact/",
-        Johnson => "https://www.nasa.gov/johnson/center-operations-directorate/",
-        Jpl => "https://www.jpl.nasa.gov/jpl-and-the-community/directions-and-maps",
-        Kennedy => "https://www.nasa.gov/kennedy-information/",
-        Langley => "https://www.nasa.gov/centers-and-facilities/langley/contacting-nasas-langley-research-center/",
-        Marshall => "https://www.nasa.gov/marshall/visit-marshall-space-flight-center/",
-        Safety => "https://www.nasa.gov/nasa-safety-center-overview/#contact",
+    fn edit_adr(&self, adr: &mut Address) {
+        // Easy way to clean address2. Due to "CENTER".
+        adr.address2 = None;
     }
-    .into()
 }
 
 #[derive(
@@ -771,3 +758,100 @@ pub enum Center {
     Marshall,
     Safety, // Safety Center
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::*;
+    use chrono::NaiveDateTime;
+
+    #[test]
+    fn test_prs_leaders() {
+        let adrs = HashMap::from([(
+            Marshall,
+            Address {
+                address1: "MARSHALL SPACE FLIGHT CENTER".into(),
+                city: "HUNTSVILLE".into(),
+                state: "AL".into(),
+                zip5: 35812,
+                ..Default::default()
+            },
+        )]);
+        let html = r#"<html><body><div class="hds-card-grid">
+            <div class="hds-card-inner"><h3>Jane Doe</h3><p>Center Director</p></div>
+            <div class="hds-card-inner"><h3>John Public, Deputy Director</h3></div>
+            <div class="hds-card-inner"><h3>(Vacant)</h3></div>
+            </div></body></html>"#;
+        let pers = prs_leaders(html, SEL_CARD_GRID, Marshall, &adrs);
+        let names: Vec<&str> = pers.iter().map(|per| per.name.as_str()).collect();
+        assert_eq!(names, vec!["Jane Doe", "John Public"]);
+        assert_eq!(pers[0].adrs, Some(vec![adrs[&Marshall].clone()]));
+        assert_eq!(pers[0].provenance.as_ref().unwrap().url, Marshall.url());
+    }
+
+    #[test]
+    fn test_sort_dedup_by_origin() {
+        let adr = Address {
+            address1: "1 NASA PKWY".into(),
+            city: "HOUSTON".into(),
+            state: "TX".into(),
+            zip5: 77058,
+            ..Default::default()
+        };
+        let adrs = HashMap::from([(Johnson, adr.clone()), (Marshall, adr)]);
+        let card = |name: &str| {
+            format!(r#"<div class="hds-card-inner"><h3>{name}</h3><p>Director</p></div>"#)
+        };
+        let page = |names: &[&str]| {
+            let cards: String = names.iter().map(|name| card(name)).collect();
+            format!(r#"<html><body><div class="hds-card-grid">{cards}</div></body></html>"#)
+        };
+
+        // Pages cached by an earlier run.
+        let _lock = data_dir_lock();
+        let dir = TempDir::new("nasa_dedup");
+        std::env::set_var(DATA_DIR_ENV, &*dir);
+        for (center, at) in [
+            (Johnson, "2024-03-01T08:00:00"),
+            (Marshall, "2024-03-02T09:30:00"),
+        ] {
+            let meta = CacheMeta {
+                url: center.url().into(),
+                at: at.parse().unwrap(),
+                final_url: None,
+            };
+            write_to_file(&meta, &cache_meta_pth(center.url()).to_string_lossy()).unwrap();
+        }
+
+        // Scrapes of the same pages in different orders.
+        let scrape = |centers: &[(Center, &[&str])]| {
+            let mut pers: Vec<Person> = centers
+                .iter()
+                .flat_map(|(center, names)| {
+                    prs_leaders(&page(names), SEL_CARD_GRID, *center, &adrs)
+                })
+                .collect();
+            sort_dedup_by_origin(&mut pers);
+            serde_json::to_string_pretty(&pers).unwrap()
+        };
+        let run1 = scrape(&[
+            (Marshall, &["Jane Doe", "John Public"]),
+            (Johnson, &["Sam Roe", "Jane A. Doe"]),
+        ]);
+        let run2 = scrape(&[
+            (Johnson, &["Jane A. Doe", "Sam Roe"]),
+            (Marshall, &["John Public", "Jane Doe"]),
+        ]);
+        std::env::remove_var(DATA_DIR_ENV);
+        assert_eq!(run1, run2);
+
+        // Duplicates apart in scrape order are dropped.
+        let pers: Vec<Person> = serde_json::from_str(&run1).unwrap();
+        let names: Vec<&str> = pers.iter().map(|per| per.name.as_str()).collect();
+        assert_eq!(names, vec!["Jane A. Doe", "Sam Roe", "John Public"]);
+
+        // Fetch times are the cache times of the pages.
+        let at: NaiveDateTime = "2024-03-01T08:00:00".parse().unwrap();
+        assert_eq!(pers[0].provenance.as_ref().unwrap().fetched_at, at);
+    }
+}
@@ -1,17 +1,35 @@
-use crate::core::*;
+use crate::io::*;
 use crate::models::*;
 use crate::prsr::*;
+use crate::report::*;
 use crate::usps::*;
 use anyhow::{anyhow, Result};
-use reqwest::Client;
-use scraper::{Html, Selector};
+use csv::Reader;
 use serde::{Deserialize, Serialize};
 use std::fs::File;
-use std::io::{BufReader, BufWriter};
-use std::ops::Add;
+use std::io::Read;
 use std::path::Path;
+use tracing::{debug, info, warn};
 
-const FLE_PTH: &str = "observer.json";
+pub const FLE_PTH: &str = "observer.json";
+
+/// Recipient input of the observer source.
+///
+/// A csv file with a header row of `name,title1,title2,address`; titles may
+/// be empty. Address lines are separated by `|`, such as
+/// `1 MAIN ST|STE 2|ALBANY, NY 12224`, or `POLARIS HOUSE|SWINDON|SN2 1SZ|UNITED KINGDOM`.
+pub const FLE_PTH_INPUT: &str = "observer_input.csv";
+
+/// A row of the observer input.
+#[derive(Debug, Deserialize, Clone, PartialEq, Eq)]
+pub struct ObserverRow {
+    pub name: String,
+    #[serde(default)]
+    pub title1: String,
+    #[serde(default)]
+    pub title2: String,
+    pub address: String,
+}
 
 #[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Eq, PartialOrd, Ord)]
 pub struct Observer {
@@ -19,6 +37,12 @@ pub struct Observer {
     pub role: Role,
     pub persons: Vec<Person>,
 }
+impl Default for Observer {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 impl Observer {
     pub fn new() -> Self {
         Self {
@@ -27,8 +51,153 @@ impl Observer {
             persons: Vec::new(),
         }
     }
+
     pub async fn load() -> Result<Observer> {
+        REPORT.lock().unwrap().begin(Source::Observer);
+
         // Read file from disk.
-        read_from_file::<Observer>(FLE_PTH)
+        // A malformed file is kept for inspection rather than rebuilt.
+        let observer = if data_pth(FLE_PTH).exists() {
+            read_from_file::<Observer>(FLE_PTH).map_err(|err| {
+                anyhow!("{FLE_PTH}: {err}; delete it to rebuild from {FLE_PTH_INPUT}")
+            })?
+        } else if data_pth(FLE_PTH_INPUT).exists() {
+            let mut observer = Observer::new();
+            observer.persons = read_input(&data_pth(FLE_PTH_INPUT))?;
+            set_roles(&mut observer.persons, &observer.role);
+
+            // Standardize addresses.
+            for (idx, per) in observer.persons.iter_mut().enumerate() {
+                if let Some(adrs) = per.adrs.take() {
+                    let adrs = standardize_addresses(adrs).await.map_err(|err| {
+                        anyhow!("{FLE_PTH_INPUT} row {}: address: {err}", idx + 2)
+                    })?;
+                    per.adrs = Some(adrs);
+                }
+            }
+
+            // Write file to disk.
+            write_to_file(&observer, FLE_PTH)?;
+
+            observer
+        } else {
+            warn!("No {FLE_PTH} or {FLE_PTH_INPUT}: no non-officials");
+            Observer::new()
+        };
+
+        info!("{} non-officials", observer.persons.len());
+
+        Ok(observer)
+    }
+}
+
+/// Reads and validates the observer input file.
+pub fn read_input(pth: &Path) -> Result<Vec<Person>> {
+    debug!("Reading file: {}", pth.display());
+    let file = File::open(pth).map_err(|err| anyhow!("{}: {err}", pth.display()))?;
+    prs_input(file).map_err(|err| anyhow!("{}: {err}", pth.display()))
+}
+
+/// Parses observer input rows into persons.
+///
+/// Errors name the row, counting the header as row 1, and the field.
+pub fn prs_input<R: Read>(rdr: R) -> Result<Vec<Person>> {
+    let mut rdr = Reader::from_reader(rdr);
+    let mut pers = Vec::new();
+    for (idx, row) in rdr.deserialize::<ObserverRow>().enumerate() {
+        let row_num = idx + 2;
+        let row = row.map_err(|err| anyhow!("row {row_num}: {err}"))?;
+        let name = row.name.trim();
+        if name.is_empty() {
+            return Err(anyhow!("row {row_num}: name: missing"));
+        }
+        if row.address.trim().is_empty() {
+            return Err(anyhow!("row {row_num}: address: missing"));
+        }
+        let adrs = prs_input_adrs(&row.address).map_err(|err| {
+            anyhow!(
+                "row {row_num}: address: unable to parse '{}': {err}",
+                row.address
+            )
+        })?;
+        pers.push(Person {
+            name: name_clean(name),
+            title1: row.title1.trim().into(),
+            title2: row.title2.trim().into(),
+            adrs: Some(adrs),
+            ..Default::default()
+        });
+    }
+    Ok(pers)
+}
+
+/// Parses a `|` separated raw address.
+pub fn prs_input_adrs(address: &str) -> Result<Vec<Address>, ParseError> {
+    let mut lnes = vec![address.to_uppercase()];
+    edit_dot(&mut lnes);
+    PRSR.edit_lnes(&mut lnes, Source::Observer);
+    edit_empty(&mut lnes);
+    PRSR.prs_adrs(&lnes)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_prs_input() {
+        let pers =
+            prs_input(include_str!("../tests/fixtures/observer/observer_input.csv").as_bytes())
+                .unwrap();
+        assert_eq!(pers.len(), 3);
+
+        assert_eq!(pers[0].title2, "Institute for Space Studies");
+        let adr = &pers[0].adrs.as_ref().unwrap()[0];
+        assert_eq!(adr.address1, "1 MAIN ST");
+        assert_eq!(adr.address2.as_deref(), Some("STE 200"));
+        assert_eq!(adr.city, "ALBANY");
+        assert_eq!(adr.state, "NY");
+        assert_eq!(adr.zip5, 12224);
+
+        let adr = &pers[1].adrs.as_ref().unwrap()[0];
+        assert_eq!(adr.address1, "2400 JFK FEDERAL BLDG");
+        assert_eq!(adr.city, "BOSTON");
+        assert_eq!(adr.zip5, 2203);
+
+        let adr = &pers[2].adrs.as_ref().unwrap()[0];
+        assert!(adr.is_foreign());
+        assert_eq!(adr.postal_code.as_deref(), Some("SN2 1SZ"));
+    }
+
+    #[test]
+    fn test_prs_input_invalid() {
+        let cases = [
+            (
+                "name,title1,title2,address\n,,,\"1 MAIN ST|ALBANY, NY 12224\"\n",
+                "row 2: name: missing",
+            ),
+            (
+                "name,title1,title2,address\nJane Doe,,,\"1 MAIN ST|ALBANY, NY 12224\"\nJohn Roe,,,\n",
+                "row 3: address: missing",
+            ),
+            (
+                "name,title1,title2,address\nJane Doe,,,MAIN STREET\n",
+                "row 2: address: unable to parse 'MAIN STREET'",
+            ),
+            (
+                "name,title1,title2,address\nJane Doe,,,12224\n",
+                "row 2: address: unable to parse '12224'",
+            ),
+            ("name,title1\nJane Doe,Director\n", "row 2: "),
+        ];
+        for (input, expected) in cases {
+            let err = prs_input(input.as_bytes()).unwrap_err().to_string();
+            assert!(
+                err.starts_with(expected),
+                "Failed to match: {} {}",
+                expected,
+                err
+            );
+        }
     }
 }
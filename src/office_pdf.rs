@@ -0,0 +1,127 @@
+use crate::io::*;
+use crate::prsr::*;
+use anyhow::Result;
+use lopdf::Document;
+use reqwest::Url;
+use scraper::{Html, Selector};
+use std::fs;
+use std::path::Path;
+use tracing::debug;
+
+/// Words of the text of a link to an office PDF, such as "Download our office information".
+const LINK_WORDS: [&str; 3] = ["OFFICE", "CONTACT", "LOCATION"];
+
+/// Follows at most one office PDF of a person.
+///
+/// A few offices publish their addresses only in a linked PDF.
+#[derive(Debug, Default)]
+pub struct PdfFallback {
+    /// Url of the followed PDF, if any.
+    pub url: Option<String>,
+}
+
+impl PdfFallback {
+    /// Fetches the text of the office PDF linked from a page.
+    ///
+    /// Returns `None` without a link, after a PDF was followed, or when the PDF fails to fetch or extract.
+    pub async fn fetch_txt(&mut self, html: &str, url: &str, opts: FetchOptions) -> Option<String> {
+        if self.url.is_some() {
+            return None;
+        }
+        let pdf_url = prs_pdf_link(html, url)?;
+        debug!("{url}: following office PDF {pdf_url}");
+        self.url = Some(pdf_url.clone());
+        match fetch_pdf(&pdf_url, opts)
+            .await
+            .and_then(|pth| pdf_txt(&pth))
+        {
+            Ok(txt) => Some(txt),
+            Err(err) => {
+                debug!("{pdf_url}: {err}");
+                None
+            }
+        }
+    }
+}
+
+/// Parses the url of the first office PDF linked from a page, resolved against the page url.
+pub fn prs_pdf_link(html: &str, url: &str) -> Option<String> {
+    let document = Html::parse_document(html);
+    let link_sel = Selector::parse("a[href]").unwrap();
+    document.select(&link_sel).find_map(|elm| {
+        let href = elm.value().attr("href")?.trim();
+        let txt = elm.text().collect::<String>().to_uppercase();
+        if !href.to_lowercase().ends_with(".pdf") || !LINK_WORDS.iter().any(|w| txt.contains(w)) {
+            return None;
+        }
+        let pdf_url = Url::parse(url).and_then(|base| base.join(href)).ok()?;
+        Some(pdf_url.to_string())
+    })
+}
+
+/// Extracts the text of a PDF file.
+///
+/// The text is cached beside the file, such as `a.txt` for `a.pdf`, until the file changes.
+pub fn pdf_txt(pth: &Path) -> Result<String> {
+    let pth_txt = pth.with_extension("txt");
+    let is_fresh = match (fs::metadata(pth), fs::metadata(&pth_txt)) {
+        (Ok(meta), Ok(meta_txt)) => meta_txt.modified()? >= meta.modified()?,
+        _ => false,
+    };
+    if is_fresh {
+        return Ok(fs::read_to_string(&pth_txt)?);
+    }
+
+    let doc = Document::load(pth)?;
+    let pg_nums: Vec<u32> = doc.get_pages().into_keys().collect();
+    let txt = doc.extract_text(&pg_nums)?;
+    write_atomic(&pth_txt, txt.as_bytes())?;
+    Ok(txt)
+}
+
+/// Extracts filtered, uppercase lines from the text of a PDF.
+pub fn pdf_lnes(txt: &str) -> Vec<String> {
+    txt.lines()
+        .map(|s| s.trim().trim_end_matches(',').to_uppercase())
+        .filter(|s| PRSR.filter(s))
+        .collect()
+}
+
+/// Whether address lines have a zip line.
+pub fn has_zip(lnes: &[String]) -> bool {
+    lnes.iter().any(|lne| is_zip(lne))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_prs_pdf_link() {
+        let url = "https://doe.house.gov/contact";
+        let cases = vec![
+            (
+                r#"<a href="/uploads/offices.pdf">Download our office information</a>"#,
+                Some("https://doe.house.gov/uploads/offices.pdf"),
+            ),
+            (
+                r#"<a href="https://cdn.house.gov/a.PDF">Office Locations</a>"#,
+                Some("https://cdn.house.gov/a.PDF"),
+            ),
+            (
+                r#"<a href="/uploads/report.pdf">Annual report</a><a href="c.pdf">Contact us</a>"#,
+                Some("https://doe.house.gov/c.pdf"),
+            ),
+            (r#"<a href="/contact/offices">Office locations</a>"#, None),
+            (r#"<a href="/uploads/report.pdf">Annual report</a>"#, None),
+        ];
+        for (html, expected) in cases {
+            assert_eq!(
+                prs_pdf_link(html, url).as_deref(),
+                expected,
+                "Failed to match: {}",
+                html
+            );
+        }
+    }
+}
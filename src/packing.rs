@@ -0,0 +1,348 @@
+use crate::io::*;
+use crate::label::*;
+use crate::mailing::*;
+use crate::pdfa::*;
+use anyhow::{anyhow, Result};
+use serde::{Deserialize, Serialize};
+use std::fmt::Write;
+use std::fs;
+use std::path::Path;
+
+const FLE_NAME_PLAN: &str = "packing_plan";
+const FLE_NAME_LABELS: &str = "packing_labels";
+
+/// Tolerance for comparing projected weights.
+const OZ_EPSILON: f64 = 1e-9;
+
+/// Box capacity for transporting stuffed envelopes to the BMEU.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct PackingCfg {
+    /// Maximum mailpieces per box; zero is unlimited.
+    pub box_max_pieces: usize,
+    /// Maximum weight per box in ounces; zero is unlimited.
+    pub box_max_oz: f64,
+    /// Weight of one stuffed envelope in ounces.
+    pub piece_oz: f64,
+}
+
+impl Default for PackingCfg {
+    fn default() -> Self {
+        Self {
+            box_max_pieces: 500,
+            box_max_oz: 0.0,
+            // A #10 envelope with one folded sheet.
+            piece_oz: 0.2,
+        }
+    }
+}
+
+impl PackingCfg {
+    /// Whether a box of pieces and weight is within capacity.
+    fn fits(&self, pieces: usize, oz: f64) -> bool {
+        (self.box_max_pieces == 0 || pieces <= self.box_max_pieces)
+            && (self.box_max_oz == 0.0 || oz <= self.box_max_oz + OZ_EPSILON)
+    }
+}
+
+/// A printing chunk packed in a box.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct PackedChunk {
+    pub tray: String,
+    /// One-based chunk number within the tray.
+    pub chunk: usize,
+    pub chunk_cnt: usize,
+    pub pieces: usize,
+    pub ltr_file: String,
+    pub env_file: String,
+}
+
+/// A numbered box of chunks.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct PackedBox {
+    pub number: usize,
+    pub chunks: Vec<PackedChunk>,
+    pub pieces: usize,
+    pub oz: f64,
+}
+
+impl PackedBox {
+    /// Tray names in packing order.
+    pub fn trays(&self) -> Vec<&str> {
+        let mut trays: Vec<&str> = self.chunks.iter().map(|c| c.tray.as_str()).collect();
+        trays.dedup();
+        trays
+    }
+
+    /// Lines summarizing each tray of the box, such as `Tray A: chunks 1-3 of 12, 150 pcs`.
+    pub fn contents(&self) -> Vec<String> {
+        self.trays()
+            .into_iter()
+            .map(|tray| {
+                let chunks: Vec<&PackedChunk> =
+                    self.chunks.iter().filter(|c| c.tray == tray).collect();
+                let first = chunks[0];
+                let last = chunks[chunks.len() - 1];
+                let pieces: usize = chunks.iter().map(|c| c.pieces).sum();
+                let rng = if first.chunk == last.chunk {
+                    format!("chunk {}", first.chunk)
+                } else {
+                    format!("chunks {}-{}", first.chunk, last.chunk)
+                };
+                format!("Tray {tray}: {rng} of {}, {pieces} pcs", first.chunk_cnt)
+            })
+            .collect()
+    }
+}
+
+/// Assignment of printing chunks to boxes for transport.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct PackingPlan {
+    pub mailing: String,
+    pub boxes: Vec<PackedBox>,
+    pub pieces: usize,
+    pub oz: f64,
+}
+
+/// Assigns chunks to numbered boxes in tray order.
+///
+/// A chunk is never split across boxes. Duplex names letter chunks as `ltr_duplex`.
+/// Errors if a single chunk exceeds box capacity or totals don't reconcile with the mailing.
+pub fn packing_plan(mailing: &Mailing, cfg: &PackingCfg, duplex: bool) -> Result<PackingPlan> {
+    let mut boxes: Vec<PackedBox> = Vec::new();
+    let mut cur: Vec<PackedChunk> = Vec::new();
+
+    for tray in mailing.trays.iter() {
+        let chunk_lens = tray.chunk_lens();
+        for (chunk_idx, chunk_len) in chunk_lens.iter().copied().enumerate() {
+            if !cfg.fits(chunk_len, chunk_len as f64 * cfg.piece_oz) {
+                return Err(anyhow!(
+                    "chunk {} of tray {} ({} pcs) exceeds box capacity",
+                    chunk_idx + 1,
+                    tray.name,
+                    chunk_len
+                ));
+            }
+
+            // Close the current box when the chunk doesn't fit.
+            let pieces = cur.iter().map(|c| c.pieces).sum::<usize>() + chunk_len;
+            if !cur.is_empty() && !cfg.fits(pieces, pieces as f64 * cfg.piece_oz) {
+                push_box(&mut boxes, &mut cur, cfg);
+            }
+
+            cur.push(PackedChunk {
+                tray: tray.name.clone(),
+                chunk: chunk_idx + 1,
+                chunk_cnt: chunk_lens.len(),
+                pieces: chunk_len,
+                ltr_file: chunk_name(
+                    &tray.name,
+                    chunk_idx,
+                    chunk_lens.len(),
+                    chunk_len,
+                    ltr_kind(duplex),
+                ),
+                env_file: chunk_name(&tray.name, chunk_idx, chunk_lens.len(), chunk_len, "env"),
+            });
+        }
+    }
+    if !cur.is_empty() {
+        push_box(&mut boxes, &mut cur, cfg);
+    }
+
+    // Reconcile totals with the mailing.
+    let pieces: usize = boxes.iter().map(|b| b.pieces).sum();
+    let mp_cnt: usize = mailing.trays.iter().map(|t| t.mailpieces.len()).sum();
+    if pieces != mp_cnt {
+        return Err(anyhow!(
+            "packing plan has {pieces} pieces, mailing has {mp_cnt}"
+        ));
+    }
+
+    Ok(PackingPlan {
+        mailing: mailing.name.clone(),
+        oz: boxes.iter().map(|b| b.oz).sum(),
+        boxes,
+        pieces,
+    })
+}
+
+fn push_box(boxes: &mut Vec<PackedBox>, cur: &mut Vec<PackedChunk>, cfg: &PackingCfg) {
+    let pieces: usize = cur.iter().map(|c| c.pieces).sum();
+    boxes.push(PackedBox {
+        number: boxes.len() + 1,
+        chunks: std::mem::take(cur),
+        pieces,
+        oz: pieces as f64 * cfg.piece_oz,
+    });
+}
+
+impl PackingPlan {
+    /// Renders the plan as markdown for the production pack.
+    pub fn to_markdown(&self) -> String {
+        let mut ret = String::new();
+        writeln!(ret, "# Packing plan {}\n", self.mailing).unwrap();
+        writeln!(
+            ret,
+            "{} boxes, {} pieces, {}\n",
+            self.boxes.len(),
+            self.pieces,
+            fmt_oz(self.oz)
+        )
+        .unwrap();
+        for bx in self.boxes.iter() {
+            writeln!(ret, "## Box {} of {}\n", bx.number, self.boxes.len()).unwrap();
+            writeln!(ret, "Trays: {}  ", bx.trays().join(", ")).unwrap();
+            writeln!(ret, "Pieces: {}  ", bx.pieces).unwrap();
+            writeln!(ret, "Weight: {}\n", fmt_oz(bx.oz)).unwrap();
+            writeln!(ret, "| Tray | Chunk | Pieces | Letters | Envelopes |").unwrap();
+            writeln!(ret, "|---|---|---|---|---|").unwrap();
+            for c in bx.chunks.iter() {
+                writeln!(
+                    ret,
+                    "| {} | {} of {} | {} | {} | {} |",
+                    c.tray, c.chunk, c.chunk_cnt, c.pieces, c.ltr_file, c.env_file
+                )
+                .unwrap();
+            }
+            ret.push('\n');
+        }
+        ret
+    }
+}
+
+/// Formats a projected weight in ounces and pounds.
+fn fmt_oz(oz: f64) -> String {
+    format!("{:.1} oz ({:.1} lb)", oz, oz / 16.0)
+}
+
+/// Writes `packing_plan.json` and `packing_plan.md` to a mailing directory.
+pub fn write_packing_plan(plan: &PackingPlan, dir: &Path) -> Result<()> {
+    let pth = dir.join(FLE_NAME_PLAN);
+    write_to_file(plan, &pth.with_extension("json").to_string_lossy())?;
+    fs::write(pth.with_extension("md"), plan.to_markdown())?;
+    Ok(())
+}
+
+/// Creates one label per box with the box number and contents summary.
+pub fn create_box_labels(
+    plan: &PackingPlan,
+    dir: &Path,
+    font_pth: Option<&str>,
+    conformance: Conformance,
+) -> Result<()> {
+    let mut lbl_doc = LabelDocument::new(FLE_NAME_LABELS.into(), font_pth)?;
+    for bx in plan.boxes.iter() {
+        let mut lnes = vec![
+            format!("Mailing {}", plan.mailing),
+            format!("Pieces: {}", bx.pieces),
+            format!("Weight: {}", fmt_oz(bx.oz)),
+            String::new(),
+        ];
+        lnes.extend(bx.contents());
+        lbl_doc.create_page(&format!("BOX {} OF {}", bx.number, plan.boxes.len()), &lnes);
+    }
+    lbl_doc.save(dir, conformance)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::*;
+
+    /// Creates a mailing of trays with the given piece counts.
+    fn mailing(tray_lens: &[usize]) -> Mailing {
+        let mut mailing = Mailing::new();
+        for (chr, len) in ('A'..).zip(tray_lens) {
+            mailing.trays.push(MailTray {
+                name: chr.to_string(),
+                size: TraySize::OneFoot,
+                barcode_id: BarcodeId::MixedAadc,
+                mailpieces: vec![Mailpiece::default(); *len],
+            });
+        }
+        mailing
+    }
+
+    fn cfg(box_max_pieces: usize, box_max_oz: f64) -> PackingCfg {
+        PackingCfg {
+            box_max_pieces,
+            box_max_oz,
+            piece_oz: 0.2,
+        }
+    }
+
+    fn box_lens(plan: &PackingPlan) -> Vec<usize> {
+        plan.boxes.iter().map(|b| b.pieces).collect()
+    }
+
+    #[test]
+    fn test_packing_plan_exact_fill() {
+        // Tray A chunks 50,50,50; tray B chunks 50,20.
+        let plan = packing_plan(&mailing(&[150, 70]), &cfg(100, 0.0), false).unwrap();
+        assert_eq!(box_lens(&plan), vec![100, 100, 20]);
+        assert_eq!(plan.boxes[1].trays(), vec!["A", "B"]);
+        assert_eq!(
+            plan.boxes[1].contents(),
+            vec![
+                "Tray A: chunk 3 of 3, 50 pcs".to_string(),
+                "Tray B: chunk 1 of 2, 50 pcs".to_string()
+            ]
+        );
+        assert_eq!(plan.boxes[2].chunks[0].ltr_file, "B_2of02_cnt20_ltr");
+        assert_eq!(plan.boxes[2].chunks[0].env_file, "B_2of02_cnt20_env");
+        assert_eq!(
+            plan.boxes.iter().map(|b| b.number).collect::<Vec<_>>(),
+            vec![1, 2, 3]
+        );
+    }
+
+    #[test]
+    fn test_packing_plan_partial_box() {
+        // Chunks never split, so a 120 piece box holds two chunks.
+        let plan = packing_plan(&mailing(&[230]), &cfg(120, 0.0), false).unwrap();
+        assert_eq!(box_lens(&plan), vec![100, 100, 30]);
+
+        // Weight capacity of 25 oz holds 125 pieces.
+        let plan = packing_plan(&mailing(&[230]), &cfg(0, 25.0), false).unwrap();
+        assert_eq!(box_lens(&plan), vec![100, 100, 30]);
+
+        // Weight capacity exactly filled.
+        let plan = packing_plan(&mailing(&[230]), &cfg(0, 20.0), false).unwrap();
+        assert_eq!(box_lens(&plan), vec![100, 100, 30]);
+
+        // Empty mailing.
+        let plan = packing_plan(&mailing(&[]), &cfg(100, 0.0), false).unwrap();
+        assert!(plan.boxes.is_empty());
+    }
+
+    #[test]
+    fn test_packing_plan_chunk_exceeds_box() {
+        assert!(packing_plan(&mailing(&[50]), &cfg(40, 0.0), false).is_err());
+        assert!(packing_plan(&mailing(&[50]), &cfg(0, 9.0), false).is_err());
+    }
+
+    #[test]
+    fn test_packing_plan_totals() {
+        let mailing = mailing(&[600, 1200, 37]);
+        let plan = packing_plan(&mailing, &PackingCfg::default(), false).unwrap();
+        assert_eq!(plan.pieces, 1837);
+        assert_eq!(
+            plan.boxes
+                .iter()
+                .flat_map(|b| b.chunks.iter())
+                .map(|c| c.pieces)
+                .sum::<usize>(),
+            1837
+        );
+        assert!((plan.oz - 1837.0 * 0.2).abs() < 1e-6);
+        assert!(plan.boxes.iter().all(|b| b.pieces <= 500));
+
+        let md = plan.to_markdown();
+        assert!(md.contains(&format!(
+            "## Box {} of {}",
+            plan.boxes.len(),
+            plan.boxes.len()
+        )));
+        assert!(md.contains("| C | 1 of 1 | 37 | C_1of01_cnt37_ltr | C_1of01_cnt37_env |"));
+    }
+}
@@ -0,0 +1,373 @@
+use anyhow::{anyhow, Result};
+use chrono::Utc;
+use lopdf::{dictionary, Document, Object, Stream};
+use printpdf::{Mm, PdfConformance, PdfDocument, PdfDocumentReference};
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// Pdf version header required by PDF/A-2.
+pub const PDFA_VERSION: &str = "1.7";
+
+/// Output intent subtype of PDF/A.
+const GTS_PDFA1: &str = "GTS_PDFA1";
+
+lazy_static! {
+    /// ICC profile bundled by printpdf, which doesn't export it.
+    ///
+    /// Extracted from the output intent of an empty PDF/A-2b document.
+    static ref ICC_PROFILE: Result<Stream, String> = icc_profile().map_err(|err| err.to_string());
+}
+
+/// Pdf conformance of envelopes, letters and labels.
+#[derive(Debug, Default, Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum Conformance {
+    /// Production printing, with builtin fonts allowed.
+    #[default]
+    Print,
+    /// PDF/A-2b archival, with embedded fonts, XMP metadata and an output intent.
+    Pdfa2b,
+}
+impl fmt::Display for Conformance {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Conformance::Print => write!(f, "print"),
+            Conformance::Pdfa2b => write!(f, "pdfa2b"),
+        }
+    }
+}
+
+/// Returns the font path of a conformance, which PDF/A requires to embed fonts.
+pub fn conformance_font_pth(
+    conformance: Conformance,
+    font_pth: Option<&str>,
+) -> Result<Option<&str>> {
+    match (conformance, font_pth) {
+        (Conformance::Pdfa2b, None) => Err(anyhow!(
+            "{conformance} requires embedded fonts, set font_pth in the mailing config"
+        )),
+        _ => Ok(font_pth),
+    }
+}
+
+/// Saves a printpdf document to a path, converting it for archival conformance.
+pub fn save_doc(doc: PdfDocumentReference, pth: &Path, conformance: Conformance) -> Result<()> {
+    let bytes = match conformance {
+        Conformance::Print => doc.save_to_bytes()?,
+        Conformance::Pdfa2b => {
+            let title = pth.file_stem().unwrap_or_default().to_string_lossy();
+            to_pdfa(
+                &doc.with_conformance(PdfConformance::A2B_2011_PDF_1_7)
+                    .save_to_bytes()?,
+                &title,
+            )?
+        }
+    };
+    fs::write(pth, bytes)?;
+    Ok(())
+}
+
+/// Converts a saved pdf file in place for archival conformance, such as a pdf_doc letter.
+pub fn convert_file(pth: &Path, conformance: Conformance) -> Result<()> {
+    if conformance == Conformance::Print {
+        return Ok(());
+    }
+    let title = pth.file_stem().unwrap_or_default().to_string_lossy();
+    let bytes = to_pdfa(&fs::read(pth)?, &title)?;
+    fs::write(pth, bytes)?;
+    Ok(())
+}
+
+/// Merges pdf files into one, in order, returning its page count.
+///
+/// Page trees of the files become kids of a new root, keeping inherited page attributes.
+/// Files are read one at a time, so only the merged document is held.
+pub fn merge_files(pths: &[PathBuf], pth: &Path, conformance: Conformance) -> Result<usize> {
+    let mut doc = Document::with_version("1.3");
+    let pages_id = doc.new_object_id();
+    let mut kids = Vec::new();
+    let mut pag_cnt = 0;
+    for (idx, part_pth) in pths.iter().enumerate() {
+        let mut part = Document::load(part_pth)?;
+        if idx == 0 {
+            doc.version.clone_from(&part.version);
+        }
+        part.renumber_objects_with(doc.max_id + 1);
+        doc.max_id = part.max_id;
+        pag_cnt += part.get_pages().len();
+
+        // Hang the page tree of the file under the new root.
+        let catalog_id = part.trailer.get(b"Root")?.as_reference()?;
+        let part_pages_id = part.catalog()?.get(b"Pages")?.as_reference()?;
+        part.get_object_mut(part_pages_id)?
+            .as_dict_mut()?
+            .set("Parent", pages_id);
+        kids.push(Object::Reference(part_pages_id));
+        part.objects.remove(&catalog_id);
+        doc.objects.extend(part.objects);
+    }
+    doc.objects.insert(
+        pages_id,
+        Object::Dictionary(dictionary! {
+            "Type" => "Pages",
+            "Kids" => kids,
+            "Count" => pag_cnt as i64,
+        }),
+    );
+    let catalog_id = doc.add_object(dictionary! {
+        "Type" => "Catalog",
+        "Pages" => pages_id,
+    });
+    doc.trailer.set("Root", catalog_id);
+
+    // Drop metadata and info of the files.
+    doc.prune_objects();
+    doc.save(pth)?;
+    convert_file(pth, conformance)?;
+    Ok(pag_cnt)
+}
+
+/// Converts pdf bytes to PDF/A-2b, then self-checks the result.
+///
+/// Sets the version header, adds an XMP packet and a PDF/A output intent.
+pub fn to_pdfa(bytes: &[u8], title: &str) -> Result<Vec<u8>> {
+    let mut doc = Document::load_mem(bytes)?;
+    doc.version = PDFA_VERSION.into();
+
+    // Metadata.
+    let xmp = Stream::new(
+        dictionary! {
+            "Type" => "Metadata",
+            "Subtype" => "XML",
+        },
+        xmp_packet(title).into_bytes(),
+    )
+    .with_compression(false);
+    let xmp_id = doc.add_object(xmp);
+    doc.catalog_mut()?.set("Metadata", xmp_id);
+
+    // Output intent.
+    // printpdf writes a PDF/X intent with its profile; others get the same profile.
+    let mut intents = match doc.catalog()?.get(b"OutputIntents") {
+        Ok(Object::Array(intents)) => intents.clone(),
+        _ => Vec::new(),
+    };
+    if intents.is_empty() {
+        let icc = ICC_PROFILE
+            .clone()
+            .map_err(|err| anyhow!("icc profile: {err}"))?;
+        let icc_id = doc.add_object(icc);
+        intents.push(Object::Dictionary(dictionary! {
+            "Type" => "OutputIntent",
+            "OutputConditionIdentifier" => Object::string_literal("FOGRA39"),
+            "RegistryName" => Object::string_literal("http://www.color.org"),
+            "DestinationOutputProfile" => icc_id,
+        }));
+    }
+    for intent in intents.iter_mut() {
+        intent
+            .as_dict_mut()?
+            .set("S", Object::Name(GTS_PDFA1.into()));
+    }
+    doc.catalog_mut()?.set("OutputIntents", intents);
+
+    let mut ret = Vec::new();
+    doc.save_to(&mut ret)?;
+    check_pdfa(&ret)?;
+    Ok(ret)
+}
+
+/// Validates the key PDF/A-2b markers of pdf bytes.
+///
+/// Checks the version header, the XMP packet, the output intent and that
+/// every font is embedded.
+pub fn check_pdfa(bytes: &[u8]) -> Result<()> {
+    if !bytes.starts_with(format!("%PDF-{PDFA_VERSION}").as_bytes()) {
+        return Err(anyhow!("pdf/a: version header is not {PDFA_VERSION}"));
+    }
+    let doc = Document::load_mem(bytes)?;
+    let catalog = doc.catalog()?;
+
+    // Metadata.
+    let xmp = catalog
+        .get(b"Metadata")
+        .and_then(Object::as_reference)
+        .and_then(|id| doc.get_object(id))
+        .and_then(Object::as_stream)
+        .map_err(|_| anyhow!("pdf/a: missing XMP metadata"))?;
+    let xmp = String::from_utf8_lossy(&xmp.content);
+    if !xmp.contains("<?xpacket") || !xmp.contains("<pdfaid:part>2</pdfaid:part>") {
+        return Err(anyhow!(
+            "pdf/a: XMP metadata lacks a PDF/A-2 identification"
+        ));
+    }
+
+    // Output intent.
+    let is_intent = catalog
+        .get(b"OutputIntents")
+        .and_then(Object::as_array)
+        .map(|intents| {
+            intents.iter().any(|intent| {
+                intent.as_dict().is_ok_and(|intent| {
+                    intent.get(b"S").and_then(Object::as_name_str).ok() == Some(GTS_PDFA1)
+                        && intent.has(b"DestinationOutputProfile")
+                })
+            })
+        })
+        .unwrap_or(false);
+    if !is_intent {
+        return Err(anyhow!("pdf/a: missing {GTS_PDFA1} output intent"));
+    }
+
+    // Fonts.
+    // Composite fonts embed through their descendant, which is checked itself.
+    for obj in doc.objects.values() {
+        let Ok(dict) = obj.as_dict() else {
+            continue;
+        };
+        if dict.get(b"Type").and_then(Object::as_name_str).ok() != Some("Font") {
+            continue;
+        }
+        let subtype = dict
+            .get(b"Subtype")
+            .and_then(Object::as_name_str)
+            .unwrap_or("");
+        if subtype != "Type0" && subtype != "Type3" && !dict.has(b"FontDescriptor") {
+            let name = dict
+                .get(b"BaseFont")
+                .and_then(Object::as_name_str)
+                .unwrap_or("");
+            return Err(anyhow!("pdf/a: builtin font {name}"));
+        }
+    }
+
+    Ok(())
+}
+
+/// Returns an XMP packet identifying PDF/A-2b.
+fn xmp_packet(title: &str) -> String {
+    let title = title
+        .replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;");
+    let date = Utc::now().format("%Y-%m-%dT%H:%M:%SZ");
+    format!(
+        r#"<?xpacket begin="{bom}" id="W5M0MpCehiHzreSzNTczkc9d"?>
+<x:xmpmeta xmlns:x="adobe:ns:meta/">
+<rdf:RDF xmlns:rdf="http://www.w3.org/1999/02/22-rdf-syntax-ns#">
+<rdf:Description rdf:about=""
+  xmlns:dc="http://purl.org/dc/elements/1.1/"
+  xmlns:xmp="http://ns.adobe.com/xap/1.0/"
+  xmlns:pdfaid="http://www.aiim.org/pdfa/ns/id/">
+<dc:format>application/pdf</dc:format>
+<dc:title><rdf:Alt><rdf:li xml:lang="x-default">{title}</rdf:li></rdf:Alt></dc:title>
+<xmp:CreateDate>{date}</xmp:CreateDate>
+<xmp:ModifyDate>{date}</xmp:ModifyDate>
+<pdfaid:part>2</pdfaid:part>
+<pdfaid:conformance>B</pdfaid:conformance>
+</rdf:Description>
+</rdf:RDF>
+</x:xmpmeta>
+<?xpacket end="w"?>"#,
+        bom = '\u{feff}'
+    )
+}
+
+/// Extracts the ICC profile printpdf embeds in a conformant document.
+fn icc_profile() -> Result<Stream> {
+    let (doc, _, _) = PdfDocument::new("icc", Mm(10.0), Mm(10.0), "ICC");
+    let bytes = doc
+        .with_conformance(PdfConformance::A2B_2011_PDF_1_7)
+        .save_to_bytes()?;
+    let doc = Document::load_mem(&bytes)?;
+    let intents = doc.catalog()?.get(b"OutputIntents")?.as_array()?;
+    let id = intents
+        .first()
+        .ok_or(anyhow!("no output intent"))?
+        .as_dict()?
+        .get(b"DestinationOutputProfile")?
+        .as_reference()?;
+    Ok(doc.get_object(id)?.as_stream()?.clone())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::label::*;
+    use crate::test_support::*;
+
+    /// Saves a label document and reads back its bytes.
+    fn render(font_pth: Option<&str>, conformance: Conformance) -> Vec<u8> {
+        let dir = TempDir::new("pdfa");
+        let mut lbl_doc = LabelDocument::new(format!("test_{conformance}"), font_pth).unwrap();
+        lbl_doc.create_page("BOX 1 OF 1", &["Pieces: 50".to_string()]);
+        let pth = dir.join(format!("test_{conformance}.pdf"));
+        lbl_doc.save(&dir, conformance).unwrap();
+        fs::read(&pth).unwrap()
+    }
+
+    #[test]
+    fn test_pdfa_markers() {
+        let bytes = render(Some(TEXT_FONT_PTH), Conformance::Pdfa2b);
+        check_pdfa(&bytes).unwrap();
+        let pdf = String::from_utf8_lossy(&bytes);
+        assert!(pdf.starts_with("%PDF-1.7"));
+        assert!(pdf.contains("<?xpacket"));
+        assert!(pdf.contains("<pdfaid:part>2</pdfaid:part>"));
+        assert!(pdf.contains("/GTS_PDFA1"));
+        assert!(!pdf.contains("/Helvetica"));
+
+        // Default mode has none of the markers.
+        let bytes = render(Some(TEXT_FONT_PTH), Conformance::Print);
+        assert!(check_pdfa(&bytes).is_err());
+        let pdf = String::from_utf8_lossy(&bytes);
+        assert!(!pdf.starts_with("%PDF-1.7"));
+        assert!(!pdf.contains("pdfaid"));
+        assert!(!pdf.contains("/GTS_PDFA1"));
+    }
+
+    #[test]
+    fn test_merge_files() {
+        let dir = TempDir::new("merge");
+        for conformance in [Conformance::Print, Conformance::Pdfa2b] {
+            // Chunks of two and one pages.
+            let mut pths = Vec::new();
+            for (idx, cnt) in [2, 1].into_iter().enumerate() {
+                let name = format!("T1_{}of02_{conformance}", idx + 1);
+                let mut lbl_doc = LabelDocument::new(name.clone(), Some(TEXT_FONT_PTH)).unwrap();
+                for box_idx in 0..cnt {
+                    lbl_doc.create_page(&format!("BOX {}", box_idx + 1), &[]);
+                }
+                lbl_doc.save(&dir, conformance).unwrap();
+                pths.push(dir.join(format!("{name}.pdf")));
+            }
+
+            let pth = dir.join(format!("T1_all_{conformance}.pdf"));
+            assert_eq!(merge_files(&pths, &pth, conformance).unwrap(), 3);
+            let doc = Document::load(&pth).unwrap();
+            assert_eq!(doc.get_pages().len(), 3);
+            if conformance == Conformance::Pdfa2b {
+                check_pdfa(&fs::read(&pth).unwrap()).unwrap();
+            }
+        }
+    }
+
+    #[test]
+    fn test_pdfa_builtin_font() {
+        // Conformance requires a font to embed.
+        assert!(conformance_font_pth(Conformance::Pdfa2b, None).is_err());
+        assert_eq!(
+            conformance_font_pth(Conformance::Print, None).unwrap(),
+            None
+        );
+
+        // The self-check rejects builtin fonts.
+        let mut lbl_doc = LabelDocument::new("test".into(), None).unwrap();
+        lbl_doc.create_page("BOX 1 OF 1", &["Pieces: 50".to_string()]);
+        let bytes = lbl_doc.doc.save_to_bytes().unwrap();
+        let err = to_pdfa(&bytes, "test").unwrap_err();
+        assert!(err.to_string().contains("builtin font"), "{err}");
+    }
+}
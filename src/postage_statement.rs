@@ -3,7 +3,43 @@ use std::path::{Path, PathBuf};
 use anyhow::{anyhow, Result};
 use lopdf::{dictionary, Document, Object, ObjectId, Stream};
 
-use crate::{fetch_pdf, numfmt, Mailing, CFG};
+use crate::io::{fetch_pdf, numfmt, FETCH_OPTS_FORM};
+use crate::mailing::{Mailing, MailingCfg, CFG};
+
+/// Position and font size of a form field, in points from the bottom left.
+#[derive(Debug, Clone, Copy)]
+pub struct Field {
+    pub x: f32,
+    pub y: f32,
+    pub fnt_sze: f32,
+}
+
+/// Field positions of PS Form 3602-N.
+///
+/// USPS revises the form periodically, so positions are kept together here.
+pub mod layout {
+    use super::Field;
+
+    const fn field(x: f32, y: f32) -> Field {
+        Field { x, y, fnt_sze: 9.0 }
+    }
+
+    /// Page 1 Total Postage, carried from Part A.
+    pub const PG1_TOTAL_POSTAGE: Field = field(529.0, 440.0);
+
+    /// Page 2 Part A columns.
+    const X_PIECES: f32 = 392.0;
+    const X_POSTAGE: f32 = 520.0;
+
+    /// Page 2 Part A rows of pieces and postage, by price category.
+    pub const PG2_FIVE_DIG: [Field; 2] = [field(X_PIECES, 676.0), field(X_POSTAGE, 676.0)];
+    pub const PG2_THREE_DIG: [Field; 2] = [field(X_PIECES, 662.0), field(X_POSTAGE, 662.0)];
+    pub const PG2_AADC: [Field; 2] = [field(X_PIECES, 648.0), field(X_POSTAGE, 648.0)];
+    pub const PG2_MIXED_AADC: [Field; 2] = [field(X_PIECES, 634.0), field(X_POSTAGE, 634.0)];
+
+    /// Page 2 Part A Total Postage.
+    pub const PG2_PART_A_TOTAL: Field = field(X_POSTAGE, 566.0);
+}
 
 /// Struct representing a PDF document.
 pub struct PostageStatement {
@@ -34,12 +70,20 @@ impl PostageStatement {
     /// # Returns
     /// A `PostageStatement` instance if the loading is successful, otherwise an error.
     pub async fn load_new() -> Result<Self> {
-        let pth = fetch_pdf("https://about.usps.com/forms/ps3602n.pdf").await?;
+        let pth = fetch_pdf("https://about.usps.com/forms/ps3602n.pdf", FETCH_OPTS_FORM).await?;
         Self::new(pth)
     }
 
     /// Fill in the postage statement and save the file.
-    pub fn fill_and_save(&mut self, mailing: &Mailing, mut pth: PathBuf) -> Result<()> {
+    ///
+    /// `statement_id` is the Statement Seq. No.
+    pub fn fill_and_save(
+        &mut self,
+        mailing: &Mailing,
+        cfg: &MailingCfg,
+        statement_id: u16,
+        mut pth: PathBuf,
+    ) -> Result<()> {
         // Get page IDs.
         let pg1_id = self.get_page_id(0)?;
         let pg2_id = self.get_page_id(1)?;
@@ -49,15 +93,15 @@ impl PostageStatement {
         let x = 60.0;
         let mut y = 698.0;
         let y_dlt = fnt_sze + (0.2 * fnt_sze);
-        self.add_text_to_pdf(pg1_id, &CFG.ps.adr.name, x, y, fnt_sze)?;
+        self.add_text_to_pdf(pg1_id, &cfg.ps.adr.name, x, y, fnt_sze)?;
         y -= y_dlt;
-        self.add_text_to_pdf(pg1_id, &CFG.ps.adr.address1, x, y, fnt_sze)?;
+        self.add_text_to_pdf(pg1_id, &cfg.ps.adr.address1, x, y, fnt_sze)?;
         y -= y_dlt;
         self.add_text_to_pdf(
             pg1_id,
             &format!(
                 "{}, {} {}-{}",
-                &CFG.ps.adr.city, &CFG.ps.adr.state, &CFG.ps.adr.zip5, &CFG.ps.adr.zip4
+                &cfg.ps.adr.city, &cfg.ps.adr.state, &cfg.ps.adr.zip5, &cfg.ps.adr.zip4
             ),
             x,
             y,
@@ -68,39 +112,39 @@ impl PostageStatement {
         let x = 170.0;
         let mut y = 698.0;
         fnt_sze = 8.0;
-        self.add_text_to_pdf(pg1_id, &CFG.ps.email, x, y, fnt_sze)?;
+        self.add_text_to_pdf(pg1_id, &cfg.ps.email, x, y, fnt_sze)?;
         y -= y_dlt;
-        self.add_text_to_pdf(pg1_id, &CFG.ps.phone, x, y, fnt_sze)?;
+        self.add_text_to_pdf(pg1_id, &cfg.ps.phone, x, y, fnt_sze)?;
 
         // Add nonprofit auth.
         let x = 188.0;
         let y = 666.0;
         fnt_sze = 9.0;
-        self.add_text_to_pdf(pg1_id, &CFG.nonprofit_auth_id, x, y, fnt_sze)?;
+        self.add_text_to_pdf(pg1_id, &cfg.nonprofit_auth_id, x, y, fnt_sze)?;
 
         // Add EPS account number..
         let x = 122.0;
         let y = 648.0;
         fnt_sze = 9.0;
-        self.add_text_to_pdf(pg1_id, &CFG.eps_id, x, y, fnt_sze)?;
+        self.add_text_to_pdf(pg1_id, &cfg.eps_id, x, y, fnt_sze)?;
 
         // Add CRID.
         let x = 210.0;
         let y = 648.0;
         fnt_sze = 9.0;
-        self.add_text_to_pdf(pg1_id, &CFG.crid, x, y, fnt_sze)?;
+        self.add_text_to_pdf(pg1_id, &cfg.crid, x, y, fnt_sze)?;
 
         // Post Office of Mailing.
         let x = 60.0;
         let y = 620.0;
         fnt_sze = 9.0;
-        self.add_text_to_pdf(pg1_id, &CFG.ps.post_office_mailing, x, y, fnt_sze)?;
+        self.add_text_to_pdf(pg1_id, &cfg.ps.post_office_mailing, x, y, fnt_sze)?;
 
         // Mailing Date.
         let x = 185.0;
         let y = 620.0;
         fnt_sze = 9.0;
-        self.add_text_to_pdf(pg1_id, &CFG.ps.mailing_date, x, y, fnt_sze)?;
+        self.add_text_to_pdf(pg1_id, &cfg.ps.mailing_date, x, y, fnt_sze)?;
 
         // Total # of Pieces.
         let x = 310.0;
@@ -118,13 +162,7 @@ impl PostageStatement {
         let x = 365.0;
         let y = 620.0;
         fnt_sze = 9.0;
-        self.add_text_to_pdf(
-            pg1_id,
-            &format!("{:03}", CFG.ps.last_statement_id + 1),
-            x,
-            y,
-            fnt_sze,
-        )?;
+        self.add_text_to_pdf(pg1_id, &format!("{:03}", statement_id), x, y, fnt_sze)?;
 
         // 1 ft. Letter Trays.
         let x = 529.0;
@@ -141,7 +179,7 @@ impl PostageStatement {
         let x = 365.0;
         let y = 571.0;
         fnt_sze = 9.0;
-        self.add_text_to_pdf(pg1_id, &CFG.indicia.permit_id, x, y, fnt_sze)?;
+        self.add_text_to_pdf(pg1_id, &cfg.indicia.permit_id, x, y, fnt_sze)?;
 
         // Type of Postage.
         let x = 56.0;
@@ -183,20 +221,46 @@ impl PostageStatement {
         let y = 485.0;
         self.add_text_to_pdf(pg1_id, "X", x, y, fnt_sze)?;
 
+        // Total Postage.
+        let total = mailing.part_a_subtotal.to_string();
+        self.add_field(pg1_id, &total, layout::PG1_TOTAL_POSTAGE)?;
+
         // Page two.
 
-        // // 5-digit No. of Pieces.
-        // let x = 181.5;
-        // let y = 471.0;
-        // fnt_sze = 12.0;
-        // self.add_text_to_pdf(pg2_id, "X", x, y, fnt_sze)?;
+        // Part A pieces and postage by price category.
+        let rows = [
+            (
+                layout::PG2_FIVE_DIG,
+                mailing.five_dig_cnt,
+                mailing.postage_subtotal_five_dig,
+            ),
+            (
+                layout::PG2_THREE_DIG,
+                mailing.three_dig_cnt,
+                mailing.postage_subtotal_three_dig,
+            ),
+            (
+                layout::PG2_AADC,
+                mailing.aadc_cnt,
+                mailing.postage_subtotal_aadc,
+            ),
+            (
+                layout::PG2_MIXED_AADC,
+                mailing.mixed_aadc_cnt,
+                mailing.postage_subtotal_mixed_aadc,
+            ),
+        ];
+        for ([pieces, postage], cnt, subtotal) in rows {
+            self.add_field(pg2_id, &numfmt(cnt as usize), pieces)?;
+            self.add_field(pg2_id, &subtotal.to_string(), postage)?;
+        }
 
+        // Part A Total Postage.
+        self.add_field(pg2_id, &total, layout::PG2_PART_A_TOTAL)?;
 
         pth.push("_postage_statement");
         pth.set_extension("pdf");
-        self.save(pth);
-
-        Ok(())
+        self.save(pth).map_err(|err| anyhow!("{err}"))
     }
 
     /// Gets the page ID of the page at the specified index.
@@ -215,6 +279,11 @@ impl PostageStatement {
         Ok(page_id)
     }
 
+    /// Adds text to a page at a form field.
+    pub fn add_field(&mut self, page_id: ObjectId, text: &str, field: Field) -> Result<()> {
+        self.add_text_to_pdf(page_id, text, field.x, field.y, field.fnt_sze)
+    }
+
     /// Adds text to the specified page of the PDF document at the given coordinates with the specified font size.
     ///
     /// # Arguments
@@ -298,3 +367,95 @@ impl PostageStatement {
         Ok(())
     }
 }
+
+/// Fills in the postage statement of a mailing with its reserved statement id.
+pub async fn create_postage_statement(mailing: &Mailing, pth: PathBuf) -> Result<()> {
+    let statement_id = mailing
+        .statement_id
+        .ok_or_else(|| anyhow!("mailing {} has no postage statement id", mailing.name))?;
+    let mut ps = PostageStatement::load_new().await?;
+    ps.fill_and_save(mailing, &CFG, statement_id, pth)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::money::Money;
+    use crate::test_support::*;
+
+    /// Saves a blank two page form, standing in for a cached ps3602n.pdf.
+    fn blank_form(pth: &Path) {
+        let mut doc = Document::with_version("1.5");
+        let pages_id = doc.new_object_id();
+        let kids: Vec<Object> = (0..2)
+            .map(|_| {
+                let content_id = doc.add_object(Stream::new(dictionary! {}, Vec::new()));
+                doc.add_object(dictionary! {
+                    "Type" => "Page",
+                    "Parent" => pages_id,
+                    "Contents" => content_id,
+                    "Resources" => dictionary! {},
+                    "MediaBox" => vec![0.into(), 0.into(), 612.into(), 792.into()],
+                })
+                .into()
+            })
+            .collect();
+        doc.objects.insert(
+            pages_id,
+            Object::Dictionary(dictionary! {
+                "Type" => "Pages",
+                "Kids" => kids,
+                "Count" => 2,
+            }),
+        );
+        let catalog_id = doc.add_object(dictionary! {
+            "Type" => "Catalog",
+            "Pages" => pages_id,
+        });
+        doc.trailer.set("Root", catalog_id);
+        doc.save(pth).unwrap();
+    }
+
+    /// A mailing of 1,450 pieces in two price categories.
+    fn mailing() -> Mailing {
+        let mut mailing = Mailing::new();
+        mailing.mailpiece_cnt = 1_450;
+        mailing.five_dig_cnt = 1_200;
+        mailing.mixed_aadc_cnt = 250;
+        mailing.postage_subtotal_five_dig = Money(207_600);
+        mailing.postage_subtotal_mixed_aadc = Money(52_000);
+        mailing.part_a_subtotal = Money(259_600);
+        mailing
+    }
+
+    /// Fills a form in a temporary directory and asserts the formatted numbers of both pages.
+    fn assert_filled(mut ps: PostageStatement, name: &str) {
+        let dir = TempDir::new(name);
+        ps.fill_and_save(&mailing(), &MailingCfg::default(), 3, dir.to_path_buf())
+            .unwrap();
+
+        let doc = Document::load(dir.join("_postage_statement.pdf")).unwrap();
+        let txts = page_txts(&doc, 0);
+        for txt in ["1,450", "003", "259.60"] {
+            assert!(txts.contains(&txt.to_string()), "Failed to match: {}", txt);
+        }
+        let txts = page_txts(&doc, 1);
+        for txt in ["1,200", "207.60", "250", "52.00", "0", "0.00", "259.60"] {
+            assert!(txts.contains(&txt.to_string()), "Failed to match: {}", txt);
+        }
+    }
+
+    #[test]
+    fn test_fill_and_save() {
+        let dir = TempDir::new("ps_form");
+        let form_pth = dir.join("ps3602n.pdf");
+        blank_form(&form_pth);
+        assert_filled(PostageStatement::new(&form_pth).unwrap(), "ps_blank");
+    }
+
+    #[tokio::test]
+    #[ignore = "fetches ps3602n.pdf from usps.com once, then fills the cached copy"]
+    async fn test_fill_and_save_cached_form() {
+        assert_filled(PostageStatement::load_new().await.unwrap(), "ps_cached");
+    }
+}
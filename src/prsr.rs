@@ -1,12 +1,25 @@
+use crate::io::*;
 use crate::models::*;
-use crate::usps::*;
 use anyhow::{anyhow, Result};
 use regex::Regex;
+use serde::{Deserialize, Serialize};
 use std::char;
-use std::clone;
+use std::fmt;
+use std::sync::Mutex;
+use tracing::{debug, trace, warn};
 
 lazy_static! {
-    pub static ref PRSR: Prsr = Prsr::new();
+    /// Known bad addresses of the shared `PRSR`, loaded at startup.
+    ///
+    /// Unset, the shared parser has the defaults.
+    pub static ref KNOWN_BAD: Mutex<Option<Vec<KnownBad>>> = Mutex::new(None);
+    pub static ref PRSR: Prsr = Prsr::new().with_known_bad(
+        KNOWN_BAD
+            .lock()
+            .unwrap()
+            .clone()
+            .unwrap_or_else(KnownBad::defaults)
+    );
 }
 
 pub struct Prsr {
@@ -14,6 +27,9 @@ pub struct Prsr {
     pub re_state: Regex,
     /// A regex matching US phone numbers.
     pub re_phone: Regex,
+    /// A regex matching a fax or telephone line, but not FAIRFAX:
+    /// "FAX: 202-225-1234", "TEL (202) 225-1234", "FAX".
+    pub re_fax: Regex,
     /// A regex matching an address1.
     pub re_address1: Regex,
     /// A regex matching an address1 suffix such as `Street`.
@@ -22,6 +38,11 @@ pub struct Prsr {
     pub re_po_box: Regex,
     /// A regex matching clock time.
     pub re_time: Regex,
+    /// A regex matching days of the week at the start of a line:
+    /// "MONDAY – FRIDAY", "MON-THURS", "TUE, WED & FRI".
+    pub re_dow: Regex,
+    /// A regex matching a range of colon-style times: "9:00 – 5:00", "8:30 TO 17:00 ET".
+    pub re_hours: Regex,
     /// A regex matching parentheses.
     pub re_parens: Regex,
     /// A regex matching a floating point number:
@@ -31,9 +52,89 @@ pub struct Prsr {
     pub re_name_initials: Regex,
     /// A regex matching name affectations.
     pub re_name_affectation: Regex,
+    /// A regex matching a list of counties served by an office:
+    /// "SERVING BALDWIN, ESCAMBIA, AND MOBILE COUNTIES", "COUNTIES SERVED: BALDWIN".
+    pub re_counties: Regex,
+    /// A regex matching a city line of a foreign address with a postal code:
+    /// "SWINDON SN2 1SZ", "OTTAWA ON K1A 0B1", "10115 BERLIN", "SN2 1SZ".
+    pub re_postal_code: Regex,
+    /// Zips of addresses known to fail, skipped while parsing.
+    pub known_bad: Vec<KnownBad>,
+}
+
+/// Why address lines fail to parse.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ParseError {
+    /// No zip code, such as a page without addresses.
+    NoZipFound,
+    /// A zip without address line 1 above or below it.
+    MissingAddress1 { lnes: Vec<String> },
+    /// A zip without city and state lines above it.
+    MissingCityState { lnes: Vec<String> },
+    /// A line above a zip that isn't a state.
+    InvalidState { lne: String },
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ParseError::NoZipFound => write!(f, "no zip code found"),
+            ParseError::MissingAddress1 { lnes } => {
+                write!(f, "missing address line 1 near {lnes:?}")
+            }
+            ParseError::MissingCityState { lnes } => {
+                write!(f, "missing city and state near {lnes:?}")
+            }
+            ParseError::InvalidState { lne } => write!(f, "invalid state '{lne}'"),
+        }
+    }
+}
+
+impl std::error::Error for ParseError {}
+
+/// Addresses parsed from a candidate url, or `None` for the caller to try the next url.
+///
+/// A page without a zip has no addresses; a block that fails to parse is logged.
+pub fn adrs_or_next(url: &str, res: Result<Vec<Address>, ParseError>) -> Option<Vec<Address>> {
+    match res {
+        Ok(adrs) => Some(adrs),
+        Err(ParseError::NoZipFound) => {
+            trace!("{url}: no addresses");
+            None
+        }
+        Err(err @ ParseError::MissingAddress1 { .. }) => {
+            warn!("{url}: {err}, trying the next url");
+            None
+        }
+        Err(err) => {
+            debug!("{url}: {err}");
+            None
+        }
+    }
+}
+
+/// Number of lines above a zip scanned for address line 1.
+pub const LEN_ADR1_WINDOW: usize = 6;
+
+/// Number of lines on each side of a failure kept for debugging.
+pub const LEN_ERR_CONTEXT: usize = 3;
+
+/// Lines around an index, for parse errors.
+fn context_lnes(lnes: &[String], idx: usize) -> Vec<String> {
+    let end = lnes.len().min(idx + LEN_ERR_CONTEXT + 1);
+    lnes[idx.saturating_sub(LEN_ERR_CONTEXT)..end].to_vec()
+}
+
+impl Default for Prsr {
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
 impl Prsr {
+    /// A parser independent of the shared `PRSR`, with the default known bad addresses.
+    ///
+    /// Reads no files; see `with_known_bad`.
     pub fn new() -> Self {
         Prsr {
             re_state:Regex::new(r"(?xi)  # Case-insensitive and extended modes
@@ -59,6 +160,15 @@ impl Prsr {
                 \d{4}                    # Last four digits
                 $                        # End of string
             ").unwrap(),
+            re_fax: Regex::new(r"(?xi)
+                \b(?:FAX|TEL|TELEPHONE)\b  # Label as a word
+                \s*[:.\-]?\s*            # Optional punctuation
+                (?:\+1[-.\s]?)?           # Optional country code
+                \(?\d{3}\)?[-.\s]?        # Area code
+                \d{3}[-.\s]?\d{4}\b       # Number
+                |                        # OR
+                ^(?:FAX|TEL|TELEPHONE)\s*:?$  # Label alone
+            ").unwrap(),
             re_address1: Regex::new(r"(?xi)
                 ^                # Start of string
                 (
@@ -94,6 +204,25 @@ impl Prsr {
                 $                # End of string
             ").unwrap(),
             re_time: Regex::new(r"(?i)\b\d{1,2}\s*(?:AM|PM|A\.M\.|P\.M\.)").unwrap(),
+            re_dow: Regex::new(r"(?xi)
+                ^
+                (?:MON(?:DAY)?|TUE(?:S(?:DAY)?)?|WED(?:NESDAY)?|THU(?:R(?:S(?:DAY)?)?)?  # A day
+                    |FRI(?:DAY)?|SAT(?:URDAY)?|SUN(?:DAY)?)\b\.?
+                (?:
+                    \s*(?:-|–|—|TO|THROUGH|THRU|,|&|AND)?\s*  # Optional separator
+                    (?:MON(?:DAY)?|TUE(?:S(?:DAY)?)?|WED(?:NESDAY)?|THU(?:R(?:S(?:DAY)?)?)?  # Another day
+                        |FRI(?:DAY)?|SAT(?:URDAY)?|SUN(?:DAY)?)\b\.?
+                )*
+                \s*[:,]?                  # Optional colon
+            ").unwrap(),
+            re_hours: Regex::new(r"(?xi)
+                ^
+                \d{1,2}:\d{2}\s*(?:AM|PM)?  # Start time
+                \s*(?:-|–|—|TO)\s*         # Separator
+                \d{1,2}:\d{2}\s*(?:AM|PM)?  # End time
+                (?:\s*[ECMP][SD]?T)?       # Optional time zone
+                $
+            ").unwrap(),
             re_parens: Regex::new(r"\(.*?\)").unwrap(),
             re_flt: Regex::new(r"^-?\d+\.\d+$").unwrap(),
             re_name_initials: Regex::new(r"\b[A-Z]\.\s+").unwrap(), // Allow: A.C. Quincy, r"\b[A-Z]\.([A-Z]\.)*\s+"
@@ -127,9 +256,39 @@ impl Prsr {
                     \b                  # Word boundry
                 )
             "#).unwrap(), 
+            re_counties: Regex::new(r"(?xi)
+                ^
+                (?:
+                    SERVING\s+(?:THE\s+)?(?P<lst>.+?)\s+COUNT(?:Y|IES)   # 'SERVING ... COUNTIES'
+                    |                                                  # OR
+                    COUNTIES(?:\s+SERVED)?\s*:\s*(?P<lst2>.+?)           # 'COUNTIES SERVED: ...'
+                )
+                \W*$
+            ").unwrap(),
+            re_postal_code: Regex::new(r"(?x)
+                ^
+                (?:
+                    (?P<code1>\d{4,6})\s+(?P<city1>.+)                    # '10115 BERLIN'
+                    |                                                   # OR
+                    (?P<city2>.*?)[\s,]*
+                    (?P<code2>
+                        [A-Z]{1,2}\d[A-Z\d]?\s?\d[A-Z]{2}                # UK 'SN2 1SZ'
+                        |[A-Z]\d[A-Z]\s?\d[A-Z]\d                        # Canada 'K1A 0B1'
+                        |\d{4,6}                                        # Numeric 'CANBERRA 2600'
+                    )
+                )
+                $
+            ").unwrap(),
+            known_bad: KnownBad::defaults(),
         }
     }
 
+    /// Replaces the known bad addresses, such as those of `KnownBad::load`.
+    pub fn with_known_bad(mut self, known_bad: Vec<KnownBad>) -> Self {
+        self.known_bad = known_bad;
+        self
+    }
+
     pub fn filter(&self, s: &str) -> bool {
         !s.is_empty()
             && !s.contains("IFRAME")
@@ -143,56 +302,167 @@ impl Prsr {
             && !s.contains("DIRECTIONS")
             && !s.contains("ENTRANCE")
             && !self.re_phone.is_match(s)
+            && !self.re_fax.is_match(s)
             && !self.re_flt.is_match(s)
             && !s.contains("PHONE")
-            // && !s.contains("FAX") // Invalid case: FAIRFAX
             // && !s.contains("OFFICE OF") // Invalid case: "OFFICE OF GOVERNOR PO BOX 001"
             && !s.starts_with("P: ")
             && !s.starts_with("F: ")
             && !s.starts_with("MAIN:")
             && !contains_time(s)
+            && !self.is_hours(s)
+    }
+
+    /// Whether a line is opening hours without a meridiem,
+    /// such as "MONDAY – FRIDAY" or "9:00 – 5:00".
+    ///
+    /// Streets named after days, such as "TUESDAY LANE", are kept.
+    pub fn is_hours(&self, lne: &str) -> bool {
+        match self.re_dow.find(lne) {
+            Some(mat) => {
+                let rest = lne[mat.end()..].trim();
+                rest.is_empty() || self.re_hours.is_match(rest)
+            }
+            None => self.re_hours.is_match(lne),
+        }
     }
 
-    pub fn edit_lnes(&self, lnes: &mut Vec<String>) {
+    /// Edits lines of a source to make them easier to parse.
+    ///
+    /// DC zip corrections apply only to their source.
+    pub fn edit_lnes(&self, lnes: &mut Vec<String>, source: Source) {
         // Edit lines to make it easier to parse.
 
         edit_split_bar(lnes);
         // eprintln!("(1) {lnes:?}");
         self.edit_concat_zip(lnes);
         // eprintln!("(2) {lnes:?}");
-        edit_zip_disjoint(lnes);
+        self.edit_zip_disjoint(lnes);
         // eprintln!("(3) {lnes:?}");
         self.edit_split_city_state_zip(lnes);
         // eprintln!("(4) {lnes:?}");
-        edit_drain_after_last_zip(lnes);
+        self.edit_drain_after_last_adr(lnes);
         // eprintln!("(5) {lnes:?}");
         edit_single_comma(lnes);
-        edit_zip_20003(lnes);
+        edit_zip_corrections(lnes, source);
+        for warning in self.edit_infer_state(lnes) {
+            warn!("{warning}");
+        }
+    }
+
+    /// Inserts the state of a zip whose city line has no state, such as "ANCHORAGE 99501".
+    ///
+    /// The state is inferred from the zip prefix. Returns warnings of an inferred state
+    /// disagreeing with a state elsewhere in the block.
+    pub fn edit_infer_state(&self, lnes: &mut Vec<String>) -> Vec<String> {
+        let mut warnings = Vec::new();
+        let mut idx_blk = 0;
+        let mut idx = 0;
+        while idx < lnes.len() {
+            if !is_zip(&lnes[idx]) || self.is_known_bad(lnes, idx) {
+                idx += 1;
+                continue;
+            }
+
+            // The city line above has no digits, unlike a suite line.
+            let idx_city = lnes[idx_blk..idx].iter().rposition(|lne| !lne.is_empty());
+            let state = lnes[idx][..LEN_ZIP5].parse().ok().and_then(zip_state);
+            if let (Some(idx_city), Some(state)) = (idx_city, state) {
+                let city = &lnes[idx_blk + idx_city];
+                if !self.is_state(city) && !city.chars().any(|c| c.is_ascii_digit()) {
+                    if let Some(other) = lnes[idx_blk..idx]
+                        .iter()
+                        .find(|lne| self.is_state(lne) && lne.trim() != state)
+                    {
+                        warnings.push(format!(
+                            "inferred state {state} of zip {} disagrees with {other}",
+                            lnes[idx]
+                        ));
+                    }
+                    lnes.insert(idx_blk + idx_city + 1, state.into());
+                    idx += 1;
+                }
+            }
+            idx += 1;
+            idx_blk = idx;
+        }
+        warnings
     }
 
-    pub fn prs_adrs(&self, lnes: &[String]) -> Option<Vec<Address>> {
+    /// Parses address lines into addresses.
+    ///
+    /// Errors distinguish lines without a zip from a zip whose block fails to parse.
+    pub fn prs_adrs(&self, lnes: &[String]) -> Result<Vec<Address>, ParseError> {
         // eprintln!("--- parse_addresses: {lnes:?}");
 
+        // Foreign addresses end with a country line.
+        if let Some(adr) = self.prs_foreign_adr(lnes) {
+            return Ok(vec![adr]);
+        }
+
+        // Some pages list city, state and zip before the street.
+        // ["WASHINGTON","DC","20515","1234 LONGWORTH HOB"]
+        let is_inverted = self.is_inverted(lnes);
+
         // Start from the bottom.
         // Search for a five digit zip code.
         let mut adrs: Vec<Address> = Vec::new();
+        let mut idx_zips: Vec<usize> = Vec::new();
+        let mut err_skip: Option<ParseError> = None;
         for (idx, lne) in lnes.iter().enumerate().rev() {
-            let is_zip5 = is_zip5(lne);
-            let is_zip10 = if !is_zip5 { is_zip10(lne) } else { false };
-            if (is_zip5 || is_zip10) && !is_invalid_zip(lne) {
+            let zip = Zip::parse(lne)
+                .ok()
+                .filter(|_| !self.is_known_bad(lnes, idx));
+            if let Some(zip) = zip {
                 // eprintln!("-- parse_addresses: idx:{idx}");
                 // Start of an address.
                 let mut adr = Address::default();
-                if is_zip5 {
-                    adr.zip5 = lne.parse().unwrap();
-                } else {
-                    adr.zip5 = lne[..5].parse().unwrap();
-                    adr.zip4 = lne[lne.len() - 4..].parse().unwrap();
+                adr.set_zip(zip);
+
+                // Lines of the address start below the zip of the previous address.
+                // A zip without city and state lines is skipped, such as a footer zip.
+                let idx_blk = (0..idx)
+                    .rev()
+                    .find(|idx_prev| self.is_adr_zip(lnes, *idx_prev))
+                    .map_or(0, |idx_prev| idx_prev + 1);
+                if idx - idx_blk < 2 && !is_inverted {
+                    debug!("Skipping zip without city and state: {lne}");
+                    err_skip.get_or_insert(ParseError::MissingCityState {
+                        lnes: context_lnes(lnes, idx),
+                    });
+                    continue;
+                }
+                if idx < 2 {
+                    return Err(ParseError::MissingCityState {
+                        lnes: context_lnes(lnes, idx),
+                    });
                 }
-                adr.state.clone_from(&lnes[idx - 1]);
+                let Some(state) = normalize_state(&lnes[idx - 1]) else {
+                    return Err(ParseError::InvalidState {
+                        lne: lnes[idx - 1].clone(),
+                    });
+                };
+                adr.state = state.into();
                 let idx_city = idx - 2;
                 adr.city.clone_from(&lnes[idx_city]);
 
+                // Address1 below the zip.
+                if is_inverted {
+                    match self.idx_adr1_below(lnes, idx) {
+                        Some(idx_adr1) => adr.address1.clone_from(&lnes[idx_adr1]),
+                        None => {
+                            adr.address1 = synth_dc_adr1(lnes, adr.zip5).ok_or_else(|| {
+                                ParseError::MissingAddress1 {
+                                    lnes: context_lnes(lnes, idx),
+                                }
+                            })?;
+                        }
+                    }
+                    adrs.push(adr);
+                    idx_zips.push(idx);
+                    continue;
+                }
+
                 // Address1.
                 // Starts with digit and contains letter.
                 // Next line could be address1 or address2.
@@ -200,19 +470,26 @@ impl Prsr {
                 // 1710 ALABAMA AVENUE,247 CARL ELLIOTT BUILDING,JASPER,AL,35501
                 // PO BOX 729,SUITE # I-10,BELTON,TX,76513
                 // "300 EAST 8TH ST, 7TH FLOOR", "AUSTIN", "TX",
-                let mut idx_adr1 = idx.saturating_sub(3);
-                while idx_adr1 != usize::MAX
-                    && !(self.re_address1.is_match(&lnes[idx_adr1])
-                        || self.re_po_box.is_match(&lnes[idx_adr1]))
-                {
-                    idx_adr1 = idx_adr1.wrapping_sub(1);
-                }
-                if idx_adr1 == usize::MAX {
-                    eprintln!("Unable to find address line 1 {}", adr);
-                    return None;
-                }
+                // Scan up to a window above the zip, within the address.
+                let idx_min = idx.saturating_sub(LEN_ADR1_WINDOW).max(idx_blk);
+                let idx_adr1_o = (idx_min..idx_city).rev().find(|idx_adr1| {
+                    self.re_address1.is_match(&lnes[*idx_adr1])
+                        || self.re_po_box.is_match(&lnes[*idx_adr1])
+                });
+                let Some(mut idx_adr1) = idx_adr1_o else {
+                    // Building and room lines apart, such as a room in a sidebar.
+                    let Some(adr1) = synth_dc_adr1(lnes, adr.zip5) else {
+                        return Err(ParseError::MissingAddress1 {
+                            lnes: context_lnes(lnes, idx),
+                        });
+                    };
+                    adr.address1 = adr1;
+                    adrs.push(adr);
+                    idx_zips.push(idx);
+                    continue;
+                };
                 // Check if address2 looks like address1.
-                if idx_adr1 != 0
+                if idx_adr1 > idx_min
                     && !self.re_po_box.is_match(&lnes[idx_adr1])
                     && self.re_address1.is_match(&lnes[idx_adr1 - 1])
                 {
@@ -220,30 +497,219 @@ impl Prsr {
                 }
                 adr.address1.clone_from(&lnes[idx_adr1]);
 
+                // Building name directly above address1, if any.
+                // ["WELLS FARGO PLAZA","221 N. KANSAS STREET","SUITE 1500","EL PASO","TX","79901"]
+                if idx_adr1 != 0 && is_firm(&lnes[idx_adr1 - 1]) {
+                    adr.firm = Some(lnes[idx_adr1 - 1].clone());
+                }
+
                 // Address2, if any.
                 // If multiple lines, concatenate.
-                let mut idx_adr2 = idx_adr1 + 1;
-                if idx_adr2 != idx_city {
-                    let mut address2 = lnes[idx_adr2].clone();
-                    idx_adr2 += 1;
-                    while idx_adr2 != idx_city {
-                        address2.push(' ');
-                        address2.push_str(&lnes[idx_adr2]);
-                        idx_adr2 += 1;
-                    }
-                    adr.address2 = Some(address2);
+                // Skip counties lines.
+                let adr2_lnes: Vec<&str> = lnes[idx_adr1 + 1..idx_city]
+                    .iter()
+                    .filter(|lne| !is_counties(lne))
+                    .map(|lne| lne.as_str())
+                    .collect();
+                if !adr2_lnes.is_empty() {
+                    adr.address2 = Some(adr2_lnes.join(" "));
                 }
                 adrs.push(adr);
+                idx_zips.push(idx);
             }
         }
 
-        // Deduplicate extracted addresses.
-        adrs.sort_unstable();
-        adrs.dedup_by(|a, b| a == b);
-
-        eprintln!("{} addresses parsed.", adrs.len());
+        // Attach counties to the address with the nearest zip.
+        // Ties go to the address above.
+        for (idx, lne) in lnes.iter().enumerate().filter(|(_, lne)| is_counties(lne)) {
+            if let Some((idx_adr, _)) = idx_zips
+                .iter()
+                .enumerate()
+                .min_by_key(|(_, idx_zip)| (idx.abs_diff(**idx_zip), **idx_zip > idx))
+            {
+                adrs[idx_adr]
+                    .counties
+                    .extend(prs_counties(&lne[COUNTIES.len()..]));
+            }
+        }
 
-        Some(adrs)
+        // Deduplicate extracted addresses, keeping the detailed duplicate.
+        adrs.sort_unstable();
+        adrs.dedup_by(|a, b| {
+            let is_dup = a.same_location(b);
+            if is_dup && a.detail() > b.detail() {
+                std::mem::swap(a, b);
+            }
+            is_dup
+        });
+
+        // Offices listing a street and a mailing PO Box keep one.
+        self.edit_po_box_pairs(&mut adrs, PO_BOX_PREFERENCE);
+
+        if adrs.is_empty() {
+            return Err(err_skip.unwrap_or(ParseError::NoZipFound));
+        }
+
+        debug!("{} addresses parsed.", adrs.len());
+
+        Ok(adrs)
+    }
+
+    /// Whether a line is the zip of an address, excluding known bad zips.
+    fn is_adr_zip(&self, lnes: &[String], idx: usize) -> bool {
+        Zip::parse(&lnes[idx]).is_ok() && !self.is_known_bad(lnes, idx)
+    }
+
+    /// Whether the zip at a line, with the lines above it, is a known bad address.
+    pub fn is_known_bad(&self, lnes: &[String], idx: usize) -> bool {
+        let Ok(zip) = Zip::parse(&lnes[idx]) else {
+            return false;
+        };
+        let lnes_blk = &lnes[idx.saturating_sub(LEN_ADR1_WINDOW)..idx];
+        self.known_bad.iter().any(|bad| {
+            bad.zip5 == zip.zip5
+                && bad
+                    .address1
+                    .as_ref()
+                    .is_none_or(|adr1| lnes_blk.iter().any(|lne| lne.contains(adr1.as_str())))
+        })
+    }
+
+    /// Keeps one address of each street and PO Box pair sharing a city, state and zip5.
+    ///
+    /// ["146 N STATE AVENUE","SOMERTON","AZ","85350","PO BOX 4105","SOMERTON","AZ","85350"]
+    ///
+    /// Only a lone street and a lone PO Box of a city, state and zip5 are a pair;
+    /// several offices sharing a zip are all kept.
+    pub fn edit_po_box_pairs(&self, adrs: &mut Vec<Address>, pref: PoBoxPreference) {
+        let is_po_box = |adr: &Address| self.re_po_box.is_match(&adr.address1);
+        let cnt = |adr: &Address, po_box: bool| {
+            adrs.iter()
+                .filter(|oth| {
+                    oth.city == adr.city
+                        && oth.state == adr.state
+                        && oth.zip5 == adr.zip5
+                        && is_po_box(oth) == po_box
+                })
+                .count()
+        };
+        let is_paired = |adr: &Address| cnt(adr, true) == 1 && cnt(adr, false) == 1;
+        let is_dropped = |adr: &Address| match pref {
+            PoBoxPreference::KeepBoth => false,
+            PoBoxPreference::PreferPoBox => !is_po_box(adr) && is_paired(adr),
+            PoBoxPreference::PreferStreet => is_po_box(adr) && is_paired(adr),
+        };
+        let drops: Vec<bool> = adrs.iter().map(is_dropped).collect();
+        let mut drops = drops.into_iter();
+        adrs.retain(|_| !drops.next().unwrap());
+    }
+
+    /// Trims lines after the last address.
+    ///
+    /// Inverted pages keep the street below the last zip.
+    pub fn edit_drain_after_last_adr(&self, lnes: &mut Vec<String>) {
+        if !self.is_inverted(lnes) {
+            edit_drain_after_last_zip(lnes);
+            return;
+        }
+        if let Some(idx_zip) = lnes.iter().rposition(|lne| is_zip(lne)) {
+            let idx = self.idx_adr1_below(lnes, idx_zip).unwrap_or(idx_zip);
+            drain_after(lnes, idx);
+        }
+    }
+
+    /// Whether a line is a whole state name or abbreviation.
+    fn is_state(&self, lne: &str) -> bool {
+        self.re_state
+            .find(lne)
+            .is_some_and(|mat| mat.as_str() == lne.trim())
+    }
+
+    /// Whether a line reads like address line 1.
+    fn is_adr1(&self, lne: &str) -> bool {
+        self.re_address1.is_match(lne) || self.re_po_box.is_match(lne)
+    }
+
+    /// Index of address line 1 within the two lines below a zip.
+    fn idx_adr1_below(&self, lnes: &[String], idx_zip: usize) -> Option<usize> {
+        (idx_zip + 1..lnes.len().min(idx_zip + 3)).find(|idx| self.is_adr1(&lnes[*idx]))
+    }
+
+    /// Whether addresses list city, state and zip before the street.
+    ///
+    /// Decided by the first address, which has no street above its city.
+    fn is_inverted(&self, lnes: &[String]) -> bool {
+        let Some(idx_zip) = (0..lnes.len()).find(|idx| self.is_adr_zip(lnes, *idx)) else {
+            return false;
+        };
+        idx_zip >= 2
+            && !lnes[..idx_zip - 2].iter().any(|lne| self.is_adr1(lne))
+            && self.idx_adr1_below(lnes, idx_zip).is_some()
+    }
+
+    /// Parses a foreign address ending with a country line.
+    ///
+    /// ["POLARIS HOUSE","NORTH STAR AVENUE","SWINDON","SN2 1SZ","UNITED KINGDOM"]
+    pub fn prs_foreign_adr(&self, lnes: &[String]) -> Option<Address> {
+        let (country, lnes) = lnes.split_last()?;
+        let country = country.trim().to_uppercase();
+        if !COUNTRIES.contains(&country.as_str()) {
+            return None;
+        }
+
+        // City and postal code.
+        let (lne, mut lnes) = lnes.split_last()?;
+        let caps = self.re_postal_code.captures(lne.trim())?;
+        let (mut city, postal_code) = match caps.name("code1") {
+            Some(code) => (caps["city1"].to_string(), code.as_str()),
+            None => (caps["city2"].to_string(), caps.name("code2")?.as_str()),
+        };
+        if city.is_empty() {
+            let (lne, rest) = lnes.split_last()?;
+            city.clone_from(lne);
+            lnes = rest;
+        }
+
+        // Address lines in order.
+        let (address1, rest) = lnes.split_first()?;
+        Some(Address {
+            address1: address1.clone(),
+            address2: if rest.is_empty() {
+                None
+            } else {
+                Some(rest.join(", "))
+            },
+            city,
+            postal_code: Some(postal_code.into()),
+            country: Some(country),
+            ..Default::default()
+        })
+    }
+
+    /// Combines a zip split across lines, such as "Vidalia, GA 304", "74".
+    ///
+    /// Merges only when the digits end a city line, with a state or a comma, and
+    /// complete a zip5, so a suite number "204" or a phone fragment is kept.
+    pub fn edit_zip_disjoint(&self, lnes: &mut Vec<String>) {
+        for idx in (1..lnes.len()).rev() {
+            let lne = &lnes[idx];
+            if lne.is_empty() || lne.len() >= LEN_ZIP5 || !lne.chars().all(|c| c.is_ascii_digit()) {
+                continue;
+            }
+            let prv = &lnes[idx - 1];
+            let head = prv.trim_end_matches(|c: char| c.is_ascii_digit());
+            let tail = &prv[head.len()..];
+            let is_city = self.re_state.is_match(head) || head.contains(',');
+            if tail.is_empty()
+                || !head.ends_with(char::is_whitespace)
+                || !is_zip5(&format!("{tail}{lne}"))
+                || !is_city
+            {
+                continue;
+            }
+            let lne = lnes.remove(idx);
+            lnes[idx - 1] += &lne;
+        }
     }
 
     pub fn edit_concat_zip(&self, lnes: &mut Vec<String>) {
@@ -276,7 +742,7 @@ impl Prsr {
         // Invalid split: "P.O. BOX 9023958", "SAN JUAN", "PR", "00902-3958"
 
         for idx in (0..lnes.len()).rev() {
-            let mut lne = lnes[idx].clone();
+            let mut lne = trim_zip_end(&lnes[idx]).to_string();
             if let Some(zip) = ends_with_zip(&lne) {
                 // Remove current line.
                 lnes.remove(idx);
@@ -296,8 +762,8 @@ impl Prsr {
                 }
 
                 if lne.contains(',') {
-                    for mut prt in lne.split_terminator(',').rev() {
-                        lnes.insert(idx, prt.trim().into());
+                    for prt in lne.split_terminator(',').rev() {
+                        lnes.insert(idx, clean_city(prt, EXPAND_INSTALLATIONS));
                     }
                 } else {
                     // Check if street and city not delimited.
@@ -305,11 +771,120 @@ impl Prsr {
                     // 430 NORTH FRANKLIN ST FORT BRAGG, CA 95437
                     // "GLEN ALLEN, VA 23060"
                     // "SAN LUIS OBISPO, CA 93401"
-                    lnes.insert(idx, lne);
+                    lnes.insert(idx, clean_city(&lne, EXPAND_INSTALLATIONS));
                 }
             }
         }
     }
+
+    pub fn edit_counties(&self, lnes: &mut [String]) {
+        // Mark a list of counties for parsing with the nearest address.
+        // "SERVING BALDWIN, ESCAMBIA, AND MOBILE COUNTIES" -> "COUNTIES:BALDWIN;ESCAMBIA;MOBILE"
+        for lne in lnes.iter_mut() {
+            if let Some(caps) = self.re_counties.captures(lne) {
+                let lst = caps
+                    .name("lst")
+                    .or_else(|| caps.name("lst2"))
+                    .map_or("", |mat| mat.as_str());
+                let cntys = prs_counties(lst);
+                if !cntys.is_empty() {
+                    *lne = format!("{COUNTIES}{}", cntys.join(";"));
+                }
+            }
+        }
+    }
+}
+
+/// Military installation abbreviations in city names and their expansions.
+pub const INSTALLATION_ABBRS: [(&str, &str); 5] = [
+    ("AFB", "AIR FORCE BASE"),
+    ("ANGB", "AIR NATIONAL GUARD BASE"),
+    ("ARB", "AIR RESERVE BASE"),
+    ("JB", "JOINT BASE"),
+    ("NAS", "NAVAL AIR STATION"),
+];
+
+/// Whether city cleanup expands installation abbreviations.
+///
+/// Off since the USPS accepts "AFB".
+pub const EXPAND_INSTALLATIONS: bool = false;
+
+/// Cleans a city split from its state.
+///
+/// Trims trailing punctuation, collapses internal spaces, and optionally
+/// expands installation abbreviations. Hyphenated names are kept.
+/// "WRIGHT-PATTERSON  AFB -" -> "WRIGHT-PATTERSON AFB"
+pub fn clean_city(city: &str, is_expand: bool) -> String {
+    let mut ret = city
+        .split_whitespace()
+        .map(|tkn| {
+            INSTALLATION_ABBRS
+                .iter()
+                .find(|(abbr, _)| is_expand && *abbr == tkn)
+                .map_or(tkn, |(_, name)| name)
+        })
+        .collect::<Vec<&str>>()
+        .join(" ");
+    trim_end_spc_pnc(&mut ret);
+    ret
+}
+
+/// Which address an office listing a street and a PO Box in one city keeps.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PoBoxPreference {
+    PreferPoBox,
+    PreferStreet,
+    KeepBoth,
+}
+
+/// Preference of `edit_po_box_pairs`.
+///
+/// Return service to PO Boxes of district offices is more reliable.
+pub const PO_BOX_PREFERENCE: PoBoxPreference = PoBoxPreference::PreferPoBox;
+
+/// Prefix of a line listing counties served by an office.
+pub const COUNTIES: &str = "COUNTIES:";
+
+/// Checks whether a line is a list of counties marked by `edit_counties`.
+pub fn is_counties(lne: &str) -> bool {
+    lne.starts_with(COUNTIES)
+}
+
+/// Parses a list of county names.
+/// "BALDWIN COUNTY, ESCAMBIA, AND MOBILE" -> ["BALDWIN", "ESCAMBIA", "MOBILE"]
+pub fn prs_counties(lst: &str) -> Vec<String> {
+    let mut ret: Vec<String> = Vec::new();
+    for prt in lst
+        .to_uppercase()
+        .replace(" AND ", ",")
+        .replace('&', ",")
+        .split([',', ';'])
+    {
+        let cnty = prt
+            .split_whitespace()
+            .filter(|wrd| !matches!(*wrd, "AND" | "COUNTY" | "COUNTIES"))
+            .collect::<Vec<_>>()
+            .join(" ");
+        if !cnty.is_empty() && !ret.contains(&cnty) {
+            ret.push(cnty);
+        }
+    }
+    ret
+}
+
+/// Last words of a building name kept above address1, such as `SILVER BOW CENTER`.
+pub const FIRM_SUFFIXES: [&str; 8] = [
+    "BUILDING", "CENTER", "CENTRE", "COMPLEX", "PLAZA", "SQUARE", "TOWER", "TOWERS",
+];
+
+/// Checks whether a line is a building name of letters ending with a firm suffix.
+pub fn is_firm(lne: &str) -> bool {
+    lne.chars()
+        .all(|c| c.is_ascii_uppercase() || matches!(c, ' ' | '&' | '\'' | '-'))
+        && lne
+            .split_whitespace()
+            .last()
+            .is_some_and(|wrd| FIRM_SUFFIXES.contains(&wrd))
 }
 
 pub fn edit_split_bar(lnes: &mut Vec<String>) {
@@ -330,18 +905,27 @@ pub fn edit_split_bar(lnes: &mut Vec<String>) {
 pub fn edit_drain_after_last_zip(lnes: &mut Vec<String>) {
     // Trim the list after the last zip code.
     // Search for the last zip code.
-    for idx in (0..lnes.len()).rev() {
-        if is_zip(&lnes[idx]) {
-            lnes.drain(idx + 1..);
-            break;
-        }
+    if let Some(idx) = lnes.iter().rposition(|lne| is_zip(lne)) {
+        drain_after(lnes, idx);
     }
 }
 
+/// Trims lines after an index, keeping counties lines of the last address.
+pub fn drain_after(lnes: &mut Vec<String>, idx: usize) {
+    let cntys: Vec<String> = lnes
+        .drain(idx + 1..)
+        .filter(|lne| is_counties(lne))
+        .collect();
+    lnes.extend(cntys);
+}
+
 pub fn edit_sob(lnes: &mut Vec<String>) {
     // Trim list prefix prior to "Senate Office Building"
     // Reverse indexes to allow for room line removal.
     for idx in (0..lnes.len()).rev() {
+        if is_counties(&lnes[idx]) {
+            continue;
+        }
         if lnes[idx].starts_with("2 CONSTITUTION AVE")
             || lnes[idx].starts_with("50 CONSTITUTION AVE")
             || lnes[idx].starts_with("120 CONSTITUTION AVE")
@@ -412,35 +996,67 @@ pub fn edit_sob(lnes: &mut Vec<String>) {
         // "SUITE 455"
         // "SUITE SR-374"
         // "SUITE 479A"
+        // "SUITE SD-G12"
         if idx + 1 != lnes.len()
             && (lnes[idx + 1].contains("ROOM") || lnes[idx + 1].contains("SUITE"))
             && lnes[idx].trim().ends_with("SOB")
         {
-            // Filter digits.
-            let mut adr1: String = lnes[idx + 1]
-                .chars()
-                .filter(|c| c.is_ascii_digit())
-                .collect();
-            adr1.push(' ');
-            adr1.push_str(&lnes[idx]);
-            lnes[idx] = adr1;
+            lnes[idx] = format!("{} {}", sob_room(&lnes[idx + 1]), lnes[idx]);
             lnes.remove(idx + 1);
         }
 
-        if lnes[idx].contains(HART) {
-            lnes[idx] = lnes[idx].replace("HART SOB", "HSOB");
-        } else if lnes[idx].contains(DIRKSEN) {
-            lnes[idx] = lnes[idx].replace("DIRKSEN SOB", "DSOB");
-        } else if lnes[idx].contains(RUSSELL) {
-            lnes[idx] = lnes[idx].replace("RUSSELL SOB", "RSOB");
+        // "SUITE SD-G12", "DIRKSEN SOB"
+        let mut cur = idx;
+        if idx != 0
+            && (lnes[idx - 1].starts_with("ROOM ") || lnes[idx - 1].starts_with("SUITE "))
+            && lnes[idx].trim().ends_with("SOB")
+            && !lnes[idx].starts_with(|c: char| c.is_ascii_digit())
+        {
+            lnes[idx] = format!("{} {}", sob_room(&lnes[idx - 1]), lnes[idx]);
+            lnes.remove(idx - 1);
+            cur = idx - 1;
+        }
+
+        if lnes[cur].contains(HART) {
+            lnes[cur] = lnes[cur].replace("HART SOB", "HSOB");
+        } else if lnes[cur].contains(DIRKSEN) {
+            lnes[cur] = lnes[cur].replace("DIRKSEN SOB", "DSOB");
+        } else if lnes[cur].contains(RUSSELL) {
+            lnes[cur] = lnes[cur].replace("RUSSELL SOB", "RSOB");
         }
     }
 }
 
+/// Room of a Senate office building room line, such as `G12` of `SUITE SD-G12`.
+///
+/// Keeps a leading G (ground) or B (basement) and a trailing suite letter,
+/// falling back to the digits of the line.
+fn sob_room(lne: &str) -> String {
+    let room = lne.split_whitespace().last().unwrap_or_default();
+    let room = match room.split_once('-') {
+        Some((prefix, room)) if prefix.len() == 2 && prefix.starts_with('S') => room,
+        _ => room,
+    };
+    let body = room.strip_prefix(['G', 'B']).unwrap_or(room);
+    let digits = body.trim_end_matches(|c: char| c.is_ascii_uppercase());
+    if !digits.is_empty()
+        && digits.chars().all(|c| c.is_ascii_digit())
+        && body.len() - digits.len() <= 1
+    {
+        return room.into();
+    }
+
+    // Filter digits.
+    lne.chars().filter(|c| c.is_ascii_digit()).collect()
+}
+
 pub fn edit_hob(lnes: &mut Vec<String>) {
     // Trim list prefix prior to "House Office Building"
     // Reverse indexes to allow for room line removal.
     for idx in (0..lnes.len()).rev() {
+        if is_counties(&lnes[idx]) {
+            continue;
+        }
         if lnes[idx].starts_with("45 INDEPENDENCE AVE")
             || lnes[idx].starts_with("15 INDEPENDENCE AVE")
             || lnes[idx].starts_with("27 INDEPENDENCE AVE")
@@ -514,6 +1130,41 @@ pub fn edit_hob(lnes: &mut Vec<String>) {
     }
 }
 
+/// Office buildings of DC congressional zips, with tokens and abbreviations.
+const DC_BUILDINGS: [(u32, &str, &str); 6] = [
+    (20515, "CANNON", "CHOB"),
+    (20515, "LONGWORTH", "LHOB"),
+    (20515, "RAYBURN", "RHOB"),
+    (20510, "HART", "HSOB"),
+    (20510, "DIRKSEN", "DSOB"),
+    (20510, "RUSSELL", "RSOB"),
+];
+
+/// Synthesizes address line 1 of a DC office building from separate building and room lines.
+///
+/// ["ROOM 1027", "CONTACT", "LONGWORTH", "WASHINGTON", "DC", "20515"] -> "1027 LHOB"
+/// The room is the room line nearest the building line; ties go to the line below.
+pub fn synth_dc_adr1(lnes: &[String], zip5: u32) -> Option<String> {
+    let (idx_bld, abbr) = lnes.iter().enumerate().find_map(|(idx, lne)| {
+        DC_BUILDINGS
+            .iter()
+            .filter(|(bld_zip5, _, _)| *bld_zip5 == zip5)
+            .find(|(_, tkn, abbr)| {
+                lne.split_whitespace()
+                    .any(|word| word == *tkn || word == *abbr)
+            })
+            .map(|(_, _, abbr)| (idx, abbr))
+    })?;
+    let room = lnes
+        .iter()
+        .enumerate()
+        .filter(|(_, lne)| lne.starts_with("ROOM ") || lne.starts_with("SUITE "))
+        .min_by_key(|(idx, _)| (idx.abs_diff(idx_bld), *idx < idx_bld))
+        .map(|(_, lne)| sob_room(lne))
+        .filter(|room| !room.is_empty())?;
+    Some(format!("{room} {abbr}"))
+}
+
 pub fn edit_dot(lnes: &mut [String]) {
     // Remove dots.
     // "D.C." -> "DC"
@@ -535,16 +1186,96 @@ pub fn edit_single_comma(lnes: &mut Vec<String>) {
     }
 }
 
-pub fn edit_zip_20003(lnes: &mut [String]) {
-    // Change DC zip code.
+/// Tokens marking a House office building line.
+pub const HOB_TKNS: [&str; 7] = [
+    "HOB",
+    "CHOB",
+    "LHOB",
+    "RHOB",
+    "CANNON",
+    "LONGWORTH",
+    "RAYBURN",
+];
+
+/// Tokens marking a Senate office building line.
+pub const SOB_TKNS: [&str; 7] = ["SOB", "HSOB", "DSOB", "RSOB", "HART", "DIRKSEN", "RUSSELL"];
+
+/// Number of lines above a zip searched for an office building token.
+pub const LEN_DC_WINDOW: usize = 3;
+
+/// A DC zip rewritten within office blocks of a source.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ZipCorrection {
+    pub source: Source,
+    pub zip_from: &'static str,
+    pub zip_to: &'static str,
+    /// Office building tokens of the block.
+    pub tkns: &'static [&'static str],
+}
+
+/// DC zip corrections of congressional office blocks.
+pub const ZIP_CORRECTIONS: [ZipCorrection; 3] = [
     // 143 CHOB,,WASHINGTON,DC,20003
+    ZipCorrection {
+        source: Source::House,
+        zip_from: "20003",
+        zip_to: "20515",
+        tkns: &HOB_TKNS,
+    },
+    // Roger Marshall, Bill Hagerty: "WASHINGTON, DC 20002"
+    ZipCorrection {
+        source: Source::Senate,
+        zip_from: "20002",
+        zip_to: "20510",
+        tkns: &SOB_TKNS,
+    },
+    ZipCorrection {
+        source: Source::Senate,
+        zip_from: "20003",
+        zip_to: "20510",
+        tkns: &SOB_TKNS,
+    },
+];
+
+/// Applies the DC zip corrections of a source.
+pub fn edit_zip_corrections(lnes: &mut [String], source: Source) {
+    for zc in ZIP_CORRECTIONS.iter().filter(|zc| zc.source == source) {
+        edit_zip_dc(lnes, zc.zip_from, zc.zip_to, zc.tkns);
+    }
+}
+
+/// Rewrites `zip_from` to `zip_to` only within a congressional office block.
+///
+/// A block is recognized by an office building token within `LEN_DC_WINDOW`
+/// lines above the zip, or "WASHINGTON" immediately above it.
+/// Residential DC zips outside an office block are left alone.
+pub fn edit_zip_dc(lnes: &mut [String], zip_from: &str, zip_to: &str, tkns: &[&str]) {
+    let suffix = format!(" {zip_from}");
     for idx in (0..lnes.len()).rev() {
-        if lnes[idx] == "20003" {
-            lnes[idx] = "20515".into();
+        if lnes[idx] != zip_from && !lnes[idx].ends_with(&suffix) {
+            continue;
+        }
+
+        let is_office = lnes[idx.saturating_sub(LEN_DC_WINDOW)..=idx]
+            .iter()
+            .any(|lne| contains_tkn(lne, tkns))
+            || (idx != 0 && lnes[idx - 1] == "WASHINGTON");
+        if !is_office {
+            continue;
         }
+
+        let len = lnes[idx].len() - zip_from.len();
+        lnes[idx].truncate(len);
+        lnes[idx].push_str(zip_to);
     }
 }
 
+/// Returns true when a line contains one of the tokens as a whole word.
+pub fn contains_tkn(lne: &str, tkns: &[&str]) -> bool {
+    lne.split(|c: char| !c.is_ascii_alphanumeric())
+        .any(|wrd| tkns.contains(&wrd))
+}
+
 pub fn edit_split_comma(lnes: &mut Vec<String>) {
     // Remove dots.
     // "U.S. FEDERAL BUILDING, 220 E ROSSER AVENUE" ->
@@ -552,7 +1283,7 @@ pub fn edit_split_comma(lnes: &mut Vec<String>) {
     for idx in (0..lnes.len()).rev() {
         if lnes[idx].contains(',') {
             let lne = lnes[idx].clone();
-            for s in lne.split(|c: char| c == ',').rev() {
+            for s in lne.split(',').rev() {
                 lnes.insert(idx + 1, s.trim().to_string());
             }
             lnes.remove(idx);
@@ -560,20 +1291,6 @@ pub fn edit_split_comma(lnes: &mut Vec<String>) {
     }
 }
 
-// TODO: MOVE TO edit_person_lnes.
-//  FIND PERSON
-pub fn edit_zip_disjoint(lnes: &mut Vec<String>) {
-    // Combine disjointed zip code.
-    // "Vidalia, GA 304", "74"
-    for idx in (1..lnes.len()).rev() {
-        if lnes[idx].len() < 5 && lnes[idx].chars().all(|c| c.is_ascii_digit()) {
-            let lne = lnes.remove(idx);
-            lnes[idx - 1] += &lne;
-            break;
-        }
-    }
-}
-
 pub fn edit_mailing(lnes: &mut [String]) {
     // Remove "MAILING ADDRESS:".
     // "MAILING ADDRESS: PO BOX4105" -> "PO BOX4105"
@@ -647,25 +1364,276 @@ pub fn edit_newline(lnes: &mut Vec<String>) {
     }
 }
 
-/// Zip codes associated with addresses the USPS does not recognize.
-pub fn is_invalid_zip(zip: &str) -> bool {
-    matches!(
-        zip,
-        "89801"
-            | "49854"
-            | "78702"
-            | "29142"
-            | "85139"
-            | "78071"
-            | "07410"
-            | "85353"
-            | "12451"
-            | "28562"
-            | "00802"
-            | "96952"
-    )
+/// USPS abbreviations and names of states, DC and inhabited territories.
+pub const STATE_NAMES: [(&str, &str); 56] = [
+    ("AL", "ALABAMA"),
+    ("AK", "ALASKA"),
+    ("AZ", "ARIZONA"),
+    ("AR", "ARKANSAS"),
+    ("CA", "CALIFORNIA"),
+    ("CO", "COLORADO"),
+    ("CT", "CONNECTICUT"),
+    ("DE", "DELAWARE"),
+    ("FL", "FLORIDA"),
+    ("GA", "GEORGIA"),
+    ("HI", "HAWAII"),
+    ("ID", "IDAHO"),
+    ("IL", "ILLINOIS"),
+    ("IN", "INDIANA"),
+    ("IA", "IOWA"),
+    ("KS", "KANSAS"),
+    ("KY", "KENTUCKY"),
+    ("LA", "LOUISIANA"),
+    ("ME", "MAINE"),
+    ("MD", "MARYLAND"),
+    ("MA", "MASSACHUSETTS"),
+    ("MI", "MICHIGAN"),
+    ("MN", "MINNESOTA"),
+    ("MS", "MISSISSIPPI"),
+    ("MO", "MISSOURI"),
+    ("MT", "MONTANA"),
+    ("NE", "NEBRASKA"),
+    ("NV", "NEVADA"),
+    ("NH", "NEW HAMPSHIRE"),
+    ("NJ", "NEW JERSEY"),
+    ("NM", "NEW MEXICO"),
+    ("NY", "NEW YORK"),
+    ("NC", "NORTH CAROLINA"),
+    ("ND", "NORTH DAKOTA"),
+    ("OH", "OHIO"),
+    ("OK", "OKLAHOMA"),
+    ("OR", "OREGON"),
+    ("PA", "PENNSYLVANIA"),
+    ("RI", "RHODE ISLAND"),
+    ("SC", "SOUTH CAROLINA"),
+    ("SD", "SOUTH DAKOTA"),
+    ("TN", "TENNESSEE"),
+    ("TX", "TEXAS"),
+    ("UT", "UTAH"),
+    ("VT", "VERMONT"),
+    ("VA", "VIRGINIA"),
+    ("WA", "WASHINGTON"),
+    ("WV", "WEST VIRGINIA"),
+    ("WI", "WISCONSIN"),
+    ("WY", "WYOMING"),
+    ("DC", "DISTRICT OF COLUMBIA"),
+    ("AS", "AMERICAN SAMOA"),
+    ("GU", "GUAM"),
+    ("MP", "NORTHERN MARIANA ISLANDS"),
+    ("PR", "PUERTO RICO"),
+    ("VI", "VIRGIN ISLANDS"),
+];
+
+/// Countries of foreign addresses, as written on the last line per UPU format.
+pub const COUNTRIES: [&str; 20] = [
+    "AUSTRALIA",
+    "BELGIUM",
+    "BRAZIL",
+    "CANADA",
+    "CHILE",
+    "DENMARK",
+    "FRANCE",
+    "GERMANY",
+    "INDIA",
+    "IRELAND",
+    "ISRAEL",
+    "ITALY",
+    "JAPAN",
+    "MEXICO",
+    "NETHERLANDS",
+    "NEW ZEALAND",
+    "NORWAY",
+    "SPAIN",
+    "SWEDEN",
+    "UNITED KINGDOM",
+];
+
+/// USPS abbreviations and names of freely associated states and armed forces codes.
+pub const STATE_NAMES_OTHER: [(&str, &str); 6] = [
+    ("FM", "FEDERATED STATES OF MICRONESIA"),
+    ("MH", "MARSHALL ISLANDS"),
+    ("PW", "PALAU"),
+    ("AA", "ARMED FORCES AMERICAS"),
+    ("AE", "ARMED FORCES EUROPE"),
+    ("AP", "ARMED FORCES PACIFIC"),
+];
+
+/// Returns the USPS abbreviation of a state token of an address.
+///
+/// Accepts abbreviations and full names in any case: "Wisconsin", "d.c.", "TX".
+/// `None` when the token isn't a state, territory or armed forces code.
+pub fn normalize_state(s: &str) -> Option<&'static str> {
+    let s = s
+        .replace('.', "")
+        .split_whitespace()
+        .collect::<Vec<_>>()
+        .join(" ")
+        .to_uppercase();
+    STATE_NAMES
+        .iter()
+        .chain(STATE_NAMES_OTHER.iter())
+        .find(|(abbr, name)| *abbr == s || *name == s)
+        .map(|(abbr, _)| *abbr)
+}
+
+/// Returns the USPS abbreviation of a state or territory name.
+/// Accepts names and slugs: "New Hampshire", "new-hampshire".
+pub fn state_abbr(name: &str) -> Option<&'static str> {
+    let name = name.trim().replace('-', " ").to_uppercase();
+    let name = name.strip_prefix("U S ").unwrap_or(&name);
+    STATE_NAMES
+        .iter()
+        .find(|(_, state_name)| *state_name == name)
+        .map(|(abbr, _)| *abbr)
+}
+
+/// Returns the name of a state or territory abbreviation in title case.
+/// "NH" is "New Hampshire", "DC" is "District of Columbia".
+pub fn state_name(abbr: &str) -> Option<String> {
+    let (_, name) = STATE_NAMES.iter().find(|(state, _)| *state == abbr)?;
+    let words: Vec<String> = name
+        .split(' ')
+        .map(|word| match word {
+            "OF" => "of".into(),
+            _ => {
+                let (fst, rest) = word.split_at(1);
+                format!("{fst}{}", rest.to_lowercase())
+            }
+        })
+        .collect();
+    Some(words.join(" "))
 }
 
+/// Ranges of three digit zip prefixes and their states, in order.
+///
+/// Military prefixes, such as 090 to 099, have no state.
+const ZIP3_STATES: [(u32, u32, &str); 66] = [
+    (5, 5, "NY"),
+    (6, 7, "PR"),
+    (8, 8, "VI"),
+    (9, 9, "PR"),
+    (10, 27, "MA"),
+    (28, 29, "RI"),
+    (30, 38, "NH"),
+    (39, 49, "ME"),
+    (50, 54, "VT"),
+    (55, 55, "MA"),
+    (56, 59, "VT"),
+    (60, 69, "CT"),
+    (70, 89, "NJ"),
+    (100, 149, "NY"),
+    (150, 196, "PA"),
+    (197, 199, "DE"),
+    (200, 200, "DC"),
+    (201, 201, "VA"),
+    (202, 205, "DC"),
+    (206, 219, "MD"),
+    (220, 246, "VA"),
+    (247, 268, "WV"),
+    (270, 289, "NC"),
+    (290, 299, "SC"),
+    (300, 319, "GA"),
+    (320, 339, "FL"),
+    (341, 349, "FL"),
+    (350, 369, "AL"),
+    (370, 385, "TN"),
+    (386, 397, "MS"),
+    (398, 399, "GA"),
+    (400, 427, "KY"),
+    (430, 459, "OH"),
+    (460, 479, "IN"),
+    (480, 499, "MI"),
+    (500, 528, "IA"),
+    (530, 549, "WI"),
+    (550, 567, "MN"),
+    (569, 569, "DC"),
+    (570, 577, "SD"),
+    (580, 588, "ND"),
+    (590, 599, "MT"),
+    (600, 629, "IL"),
+    (630, 658, "MO"),
+    (660, 679, "KS"),
+    (680, 693, "NE"),
+    (700, 714, "LA"),
+    (716, 729, "AR"),
+    (730, 732, "OK"),
+    (733, 733, "TX"),
+    (734, 749, "OK"),
+    (750, 799, "TX"),
+    (800, 816, "CO"),
+    (820, 831, "WY"),
+    (832, 838, "ID"),
+    (840, 847, "UT"),
+    (850, 865, "AZ"),
+    (870, 884, "NM"),
+    (885, 885, "TX"),
+    (889, 898, "NV"),
+    (900, 961, "CA"),
+    (967, 968, "HI"),
+    (969, 969, "GU"),
+    (970, 979, "OR"),
+    (980, 994, "WA"),
+    (995, 999, "AK"),
+];
+
+/// State of a zip5 by its three digit prefix, such as `AK` of 99501.
+pub fn zip_state(zip5: u32) -> Option<&'static str> {
+    let zip3 = zip5 / 100;
+    ZIP3_STATES
+        .iter()
+        .find(|(lo, hi, _)| (*lo..=*hi).contains(&zip3))
+        .map(|(_, _, state)| *state)
+}
+
+/// Known bad addresses file.
+pub const FLE_PTH_KNOWN_BAD: &str = "known_bad.json";
+
+/// An address known to fail, such as `{"zip5": 78702, "reason": "..."}`.
+///
+/// Its zip is skipped while parsing, only in blocks containing `address1` if given.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Eq)]
+pub struct KnownBad {
+    pub zip5: u32,
+    /// Substring of a line above the zip.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub address1: Option<String>,
+    pub reason: String,
+}
+
+impl KnownBad {
+    /// Known bad addresses of previous runs.
+    ///
+    /// Most failing address1s weren't kept, so their whole zip is skipped.
+    /// St. Thomas has other offices in its zip, so only its failing address is skipped.
+    pub fn defaults() -> Vec<KnownBad> {
+        let mut known_bad: Vec<KnownBad> = [
+            89801, 49854, 78702, 29142, 85139, 78071, 7410, 85353, 12451, 28562, 96952,
+        ]
+        .into_iter()
+        .map(|zip5| KnownBad {
+            zip5,
+            address1: None,
+            reason: "failed USPS standardization".into(),
+        })
+        .collect();
+        known_bad.push(KnownBad {
+            zip5: 802,
+            address1: Some("KONGENS GADE".into()),
+            reason: "failed USPS standardization".into(),
+        });
+        known_bad
+    }
+
+    /// Reads known bad addresses from a file; a missing file has the defaults.
+    pub fn load(pth: &str) -> Result<Vec<KnownBad>> {
+        if !data_pth(pth).exists() {
+            return Ok(KnownBad::defaults());
+        }
+        read_from_file(pth).map_err(|err| anyhow!("invalid known bad list '{pth}': {err}"))
+    }
+}
+
+pub const LEN_ZIP4: usize = 4;
 pub const LEN_ZIP5: usize = 5;
 pub const LEN_ZIP10: usize = 10;
 pub const ZIP_DASH: char = '-';
@@ -705,43 +1673,48 @@ pub fn is_zip10(lne: &str) -> bool {
     })
 }
 
+/// Trims trailing whitespace and punctuation after a zip, such as "SANTA FE, NM 87501.".
+pub fn trim_zip_end(lne: &str) -> &str {
+    lne.trim_end_matches(|c: char| c.is_whitespace() || matches!(c, '.' | ',' | ';' | ':' | ')'))
+}
+
 /// Checks whether a string ends with a USPS zip with 5 characters.
 ///
 /// Specified string expected to be longer than 5 characters.
+/// Trailing punctuation is ignored.
 pub fn ends_with_zip5(lne: &str) -> Option<String> {
+    let chrs: Vec<char> = trim_zip_end(lne).chars().collect();
+
     // Disallow exact match.
-    if lne.len() > LEN_ZIP5 {
-        // Check 5 digit zip.
-        let zip: String = lne.chars().skip(lne.chars().count() - LEN_ZIP5).collect();
-        if is_zip5(&zip) {
-            // Check for invalid cases.
-            //  - Too many digits: "123456".
-            //  - 10 char zip: "12345-67890".
-            //  - Unit number: "#20127".
-            //  - Room number: "ROOM 20100".
-            //  - Suite number: "SUITE 20350".
-            //  - Box number: "BOX 22201".
-            const IDX_ROOM: usize = 10;
-            if lne.len() >= IDX_ROOM && lne[lne.len() - IDX_ROOM..].starts_with("ROOM") {
-                return None;
-            }
-            const IDX_SUITE: usize = 11;
-            if lne.len() >= IDX_SUITE && lne[lne.len() - IDX_SUITE..].starts_with("SUITE") {
-                return None;
-            }
-            const IDX_BOX: usize = 9;
-            if lne.len() >= IDX_BOX && lne[lne.len() - IDX_BOX..].starts_with("BOX") {
-                return None;
-            }
-            if let Some(c) = lne.chars().rev().nth(LEN_ZIP5) {
-                if !c.is_ascii_digit() && c != ZIP_DASH && c != '#' {
-                    return Some(zip);
-                }
-            }
-        }
+    if chrs.len() <= LEN_ZIP5 {
+        return None;
+    }
+
+    // Check 5 digit zip.
+    let (pre, zip) = chrs.split_at(chrs.len() - LEN_ZIP5);
+    let zip: String = zip.iter().collect();
+    if !is_zip5(&zip) {
+        return None;
+    }
+
+    // Check for invalid cases.
+    //  - Too many digits: "123456".
+    //  - 10 char zip: "12345-67890".
+    //  - Unit number: "#20127".
+    //  - Room number: "ROOM 20100".
+    //  - Suite number: "SUITE 20350".
+    //  - Box number: "BOX 22201".
+    let pre: String = pre.iter().collect();
+    if ["ROOM ", "SUITE ", "BOX "]
+        .iter()
+        .any(|unit| pre.ends_with(unit))
+    {
+        return None;
+    }
+    match pre.chars().last() {
+        Some(c) if !c.is_ascii_digit() && c != ZIP_DASH && c != '#' => Some(zip),
+        _ => None,
     }
-
-    None
 }
 
 /// Checks whether a string ends with a USPS zip with 10 characters.
@@ -771,8 +1744,6 @@ pub fn ends_with_zip(lne: &str) -> Option<String> {
 
 /// Checks whether the string contains clock time, 9AM, 5 p.m.
 pub fn contains_time(lne: &str) -> bool {
-    let mut lft: usize = 0;
-
     let mut saw_fst_chr = false;
     let mut cnt_dig: u8 = 0;
     for c in lne.chars() {
@@ -875,7 +1846,7 @@ pub fn dot_remove(mut s: String) -> String {
 
 pub fn name_clean(full_name: &str) -> String {
     // Replace name affectations with an empty string
-    let mut s = PRSR.re_name_affectation.replace_all(full_name, "");
+    let s = PRSR.re_name_affectation.replace_all(full_name, "");
 
     // Replace non-breaking space
     let mut s = nbsp_replace(s.to_string());
@@ -1096,6 +2067,80 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_filter_fax() {
+        let prsr = Prsr::new();
+        let cases = vec![
+            // Fax and telephone lines.
+            ("FAX: 202-225-1234", false),
+            ("FAX 202-225-1234", false),
+            ("FAX (202) 225-1234", false),
+            ("FAX:(202)225-1234", false),
+            ("FAX", false),
+            ("FAX:", false),
+            ("TEL: 202.225.1234", false),
+            ("TEL", false),
+            ("TELEPHONE: 202-225-1234", false),
+            ("SUITE 200 FAX: 202-225-1234", false),
+            // Fairfax and other words.
+            ("FAIRFAX", true),
+            ("FAIRFAX DRIVE", true),
+            ("4000 FAIRFAX DR", true),
+            ("FAIRFAX, VA 22030", true),
+            ("SUITE 200", true),
+            ("1 HOTEL CIR", true),
+            ("10 TELEGRAPH RD", true),
+        ];
+        for (input, expected) in cases {
+            assert_eq!(prsr.filter(input), expected, "Failed to match: {}", input);
+        }
+    }
+
+    #[test]
+    fn test_is_hours() {
+        let cases = vec![
+            // Hours.
+            ("MONDAY – FRIDAY", true),
+            ("MONDAY - FRIDAY:", true),
+            ("MON-FRI", true),
+            ("MON. – THURS.", true),
+            ("TUE, WED & FRI", true),
+            ("MONDAY THROUGH FRIDAY", true),
+            ("SATURDAY", true),
+            ("9:00 – 5:00", true),
+            ("8:30 TO 17:00 ET", true),
+            ("MONDAY – FRIDAY 9:00 – 5:00", true),
+            // Addresses.
+            ("TUESDAY LANE", false),
+            ("100 TUESDAY LANE", false),
+            ("MONROE ST", false),
+            ("SUNSET BLVD", false),
+            ("WEDGEWOOD DR", false),
+            ("FRIENDSHIP HEIGHTS", false),
+            ("SUITE 200", false),
+        ];
+        for (input, expected) in cases {
+            assert_eq!(PRSR.is_hours(input), expected, "Failed to match: {}", input);
+        }
+
+        // A district office page with hours over two lines.
+        let lnes: Vec<&str> = [
+            "DISTRICT OFFICE",
+            "100 N MAIN ST",
+            "SUITE 200",
+            "MONDAY – FRIDAY",
+            "9:00 – 5:00",
+            "SPRINGFIELD, IL 62701",
+        ]
+        .into_iter()
+        .filter(|lne| PRSR.filter(lne))
+        .collect();
+        let adrs = prs_house_lnes(&lnes).unwrap();
+        assert_eq!(adrs.len(), 1);
+        assert_eq!(adrs[0].address1, "100 N MAIN ST");
+        assert_eq!(adrs[0].address2.as_deref(), Some("SUITE 200"));
+    }
+
     #[test]
     fn test_is_zip5_valid() {
         let valid_cases = vec![
@@ -1207,6 +2252,63 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_zip_parse_valid() {
+        // Round trip zip5 and zip4 across digit widths, including leading zeros.
+        let zip5s = [0, 802, 2134, 7410, 20301, 99999];
+        let zip4s = [None, Some(0), Some(1), Some(42), Some(1000), Some(9999)];
+        for zip5 in zip5s {
+            for zip4 in zip4s {
+                let zip = Zip { zip5, zip4 };
+                let s = zip.to_string();
+                assert!(is_zip(&s), "Failed to format: {}", s);
+                assert_eq!(Zip::parse(&s).unwrap(), zip, "Failed to match: {}", s);
+
+                let mut adr = Address::default();
+                adr.set_zip_from_str(&s).unwrap();
+                assert_eq!(adr.zip5, zip5, "Failed to match: {}", s);
+                assert_eq!(adr.zip4, zip4.unwrap_or(0), "Failed to match: {}", s);
+            }
+        }
+    }
+
+    #[test]
+    fn test_zip_parse_invalid() {
+        let invalid_cases = vec![
+            "",             // Empty
+            "1234",         // Less than five digits
+            "123456",       // More than five digits without hyphen
+            "12-567",       // Less than five digits before hyphen
+            "1234-5678",    // Less than five digits before hyphen
+            "ABCDE",        // Leters
+            "12345-",       // Missing extended part
+            "12345-678",    // Less than four digits after hyphen
+            "12345-67890",  // More than four digits after hyphen
+            "12345 6789",   // Space instead of hyphen
+            "12a45-6789",   // Alphabetic character in zip code
+            "12345-678a",   // Alphabetic character in extended part
+            "123456789",    // No hyphen in extended zip code
+            "202-225-4735", // Phone number
+            "+1234",        // Sign
+            "12345-+678",   // Sign in extended part
+            " 12345",       // Leading space
+        ];
+
+        for case in invalid_cases {
+            assert!(
+                Zip::parse(case).is_err(),
+                "Incorrectly parsed invalid zip code: {}",
+                case
+            );
+            let mut adr = Address::default();
+            assert!(
+                adr.set_zip_from_str(case).is_err(),
+                "Incorrectly set invalid zip code: {}",
+                case
+            );
+        }
+    }
+
     #[test]
     fn test_ends_with_zip5_valid() {
         let cases = vec![
@@ -1214,6 +2316,12 @@ mod tests {
             ("End with 54321", "54321".into()),
             ("Starts with zip 98765", "98765".into()),
             ("Zip in the middle 12345", "12345".into()),
+            // Non-ASCII characters.
+            ("Teléfono: 505-555-0100, SANTA FE NM 87501", "87501".into()),
+            ("Señor 87501", "87501".into()),
+            // Trailing punctuation.
+            ("SANTA FE, NM 87501.", "87501".into()),
+            ("SANTA FE, NM 87501 ", "87501".into()),
         ];
 
         for (input, expected) in cases {
@@ -1240,6 +2348,9 @@ mod tests {
             "BOX 22201",                        // Box number
             "ROOM 20100",                       // Room number
             "SUITE 20350",                      // Suite number
+            "SUITE 20350.",                     // Suite number with a period
+            "Teléfono 12345-6789",              // Valid 9-digit zip with non-ASCII
+            "é2345",                            // Too short with non-ASCII
         ];
 
         for input in cases {
@@ -1720,7 +2831,6 @@ mod tests {
             .find_iter(text3)
             .map(|m| m.as_str())
             .collect();
-        eprintln!("{matches:?}");
         assert!(matches.is_empty());
     }
 
@@ -1975,6 +3085,57 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_sob_ground_floor_suite() {
+        let mut lines = vec![
+            "SUITE SD-G12".to_string(),
+            "DIRKSEN SENATE OFFICE BUILDING".to_string(),
+        ];
+        edit_sob(&mut lines);
+        assert_eq!(lines, vec!["G12 DSOB".to_string()]);
+
+        let mut lines = vec![
+            "DIRKSEN SENATE OFFICE BUILDING".to_string(),
+            "SUITE SD-G12".to_string(),
+        ];
+        edit_sob(&mut lines);
+        assert_eq!(lines, vec!["G12 DSOB".to_string()]);
+    }
+
+    #[test]
+    fn test_sob_suite_letter() {
+        let mut lines = vec!["RUSSELL SOB".to_string(), "SUITE SR-374A".to_string()];
+        edit_sob(&mut lines);
+        assert_eq!(lines, vec!["374A RSOB".to_string()]);
+
+        let mut lines = vec![
+            "SUITE SR-374A".to_string(),
+            "RUSSELL SOB".to_string(),
+            "WASHINGTON, DC 20510".to_string(),
+        ];
+        edit_sob(&mut lines);
+        assert_eq!(
+            lines,
+            vec!["374A RSOB".to_string(), "WASHINGTON, DC 20510".to_string()]
+        );
+    }
+
+    #[test]
+    fn test_sob_room() {
+        let cases = [
+            ("ROOM 521", "521"),
+            ("SUITE 479A", "479A"),
+            ("SUITE SR-374", "374"),
+            ("SUITE SR-374A", "374A"),
+            ("SUITE SD-G12", "G12"),
+            ("ROOM B40C", "B40C"),
+            ("SUITE #455", "455"),
+        ];
+        for (input, expected) in cases {
+            assert_eq!(sob_room(input), expected, "Failed to match: {}", input);
+        }
+    }
+
     #[test]
     fn test_single_split() {
         let mut lines = vec![
@@ -2130,4 +3291,722 @@ mod tests {
             );
         }
     }
+
+    #[test]
+    fn test_edit_zip_disjoint() {
+        let prsr = Prsr::new();
+        let cases = [
+            // Split zip.
+            (
+                vec!["100 MAIN ST", "Vidalia, GA 304", "74"],
+                vec!["100 MAIN ST", "Vidalia, GA 30474"],
+            ),
+            // Standalone suite number.
+            (
+                vec!["100 MAIN ST", "SUITE", "204", "Vidalia, GA 30474"],
+                vec!["100 MAIN ST", "SUITE", "204", "Vidalia, GA 30474"],
+            ),
+            // Phone fragment after a whole zip.
+            (
+                vec!["Vidalia, GA 30474", "912", "555-0100"],
+                vec!["Vidalia, GA 30474", "912", "555-0100"],
+            ),
+            // Digits not completing a zip5.
+            (
+                vec!["Vidalia, GA 304", "7412"],
+                vec!["Vidalia, GA 304", "7412"],
+            ),
+            // Digits without a city line.
+            (vec!["ROOM 30", "474"], vec!["ROOM 30", "474"]),
+            // Two split zips.
+            (
+                vec![
+                    "100 MAIN ST",
+                    "Vidalia, GA 304",
+                    "74",
+                    "2 OAK AVE",
+                    "MACON GA 31",
+                    "201",
+                ],
+                vec![
+                    "100 MAIN ST",
+                    "Vidalia, GA 30474",
+                    "2 OAK AVE",
+                    "MACON GA 31201",
+                ],
+            ),
+        ];
+        for (input, expected) in cases {
+            let mut lnes: Vec<String> = input.iter().map(|s| s.to_string()).collect();
+            prsr.edit_zip_disjoint(&mut lnes);
+            assert_eq!(lnes, expected, "Failed to match: {:?}", input);
+        }
+    }
+
+    #[test]
+    fn test_edit_zip_20003_office() {
+        let mut lines = vec![
+            "143 CHOB".to_string(),
+            "WASHINGTON".to_string(),
+            "DC".to_string(),
+            "20003".to_string(),
+        ];
+        edit_zip_corrections(&mut lines, Source::House);
+        assert_eq!(lines[3], "20515");
+    }
+
+    #[test]
+    fn test_edit_zip_20003_residential() {
+        let mut lines = vec![
+            "143 CHOB".to_string(),
+            "WASHINGTON".to_string(),
+            "DC".to_string(),
+            "20515".to_string(),
+            "600 PENNSYLVANIA AVE SE".to_string(),
+            "SUITE 210".to_string(),
+            "WASHINGTON".to_string(),
+            "DC".to_string(),
+            "20003".to_string(),
+        ];
+        edit_zip_corrections(&mut lines, Source::House);
+        assert_eq!(lines[3], "20515");
+        assert_eq!(lines[8], "20003");
+    }
+
+    #[test]
+    fn test_edit_zip_corrections_by_source() {
+        let lnes = || vec!["1 CANNON PL SE, WASHINGTON, DC 20003".to_string()];
+
+        // A residential 20003 of a governor page is left untouched.
+        let mut lines = lnes();
+        PRSR.edit_lnes(&mut lines, Source::State);
+        assert_eq!(lines, vec!["1 CANNON PL SE", "WASHINGTON", "DC", "20003"]);
+
+        // The same lines of a House page read as an office block.
+        let mut lines = lnes();
+        PRSR.edit_lnes(&mut lines, Source::House);
+        assert_eq!(lines[3], "20515");
+
+        // Senate blocks correct both 20002 and 20003.
+        for zip in ["20002", "20003"] {
+            let mut lines = vec![
+                "167 RUSSELL SOB".to_string(),
+                format!("WASHINGTON, DC {zip}"),
+            ];
+            PRSR.edit_lnes(&mut lines, Source::Senate);
+            assert_eq!(
+                lines.last().unwrap(),
+                "20510",
+                "Failed to match: {:?}",
+                lines
+            );
+        }
+    }
+
+    #[test]
+    fn test_edit_zip_dc_senate() {
+        let cases = vec![
+            (
+                vec![
+                    "479A RUSSELL SENATE OFFICE BUILDING",
+                    "WASHINGTON, DC 20002",
+                ],
+                "WASHINGTON, DC 20510",
+            ),
+            (
+                vec!["167 RUSSELL SOB", "WASHINGTON", "DC", "20002"],
+                "20510",
+            ),
+            (
+                vec!["1200 FIRST ST NE", "WASHINGTON, DC 20002"],
+                "WASHINGTON, DC 20002",
+            ),
+        ];
+
+        for (input, expected) in cases {
+            let mut lines: Vec<String> = input.into_iter().map(String::from).collect();
+            edit_zip_dc(&mut lines, "20002", "20510", &SOB_TKNS);
+            assert_eq!(
+                lines.last().unwrap(),
+                expected,
+                "Failed to match: {:?}",
+                lines
+            );
+        }
+    }
+
+    #[test]
+    fn test_normalize_state() {
+        let cases = vec![
+            ("WI", Some("WI")),
+            ("Wisconsin", Some("WI")),
+            ("WISCONSIN", Some("WI")),
+            ("new  hampshire", Some("NH")),
+            ("D.C.", Some("DC")),
+            ("District of Columbia", Some("DC")),
+            ("Puerto Rico", Some("PR")),
+            ("Palau", Some("PW")),
+            ("Armed Forces Europe", Some("AE")),
+            ("AP", Some("AP")),
+            ("ZZ", None),
+            ("Atlantis", None),
+            ("WASHINGTON DC", None),
+            ("", None),
+        ];
+        for (input, expected) in cases {
+            assert_eq!(
+                normalize_state(input),
+                expected,
+                "Failed to match: {}",
+                input
+            );
+        }
+
+        let cases = [
+            ("WI", Some("Wisconsin")),
+            ("NH", Some("New Hampshire")),
+            ("DC", Some("District of Columbia")),
+            ("Wisconsin", None),
+            ("", None),
+        ];
+        for (abbr, expected) in cases {
+            assert_eq!(
+                state_name(abbr).as_deref(),
+                expected,
+                "Failed to match: {}",
+                abbr
+            );
+        }
+
+        // Parsed addresses store abbreviations.
+        let lnes: Vec<String> = ["1 E MAIN ST", "MADISON", "WISCONSIN", "53703"]
+            .iter()
+            .map(|lne| lne.to_string())
+            .collect();
+        assert_eq!(PRSR.prs_adrs(&lnes).unwrap()[0].state, "WI");
+        let lnes: Vec<String> = ["1 E MAIN ST", "MADISON", "WISC", "53703"]
+            .iter()
+            .map(|lne| lne.to_string())
+            .collect();
+        assert!(matches!(
+            PRSR.prs_adrs(&lnes),
+            Err(ParseError::InvalidState { .. })
+        ));
+    }
+
+    #[test]
+    fn test_state_abbr() {
+        let cases = vec![
+            ("Alabama", Some("AL")),
+            ("new-hampshire", Some("NH")),
+            ("NORTHERN MARIANA ISLANDS", Some("MP")),
+            ("u-s-virgin-islands", Some("VI")),
+            ("Atlantis", None),
+        ];
+        for (input, expected) in cases {
+            assert_eq!(state_abbr(input), expected, "Failed to match: {}", input);
+        }
+    }
+
+    #[test]
+    fn test_edit_counties() {
+        let cases = vec![
+            (
+                "SERVING BALDWIN, ESCAMBIA, AND MOBILE COUNTIES",
+                "COUNTIES:BALDWIN;ESCAMBIA;MOBILE",
+            ),
+            (
+                "SERVING THE ST CLAIR COUNTY AREA",
+                "SERVING THE ST CLAIR COUNTY AREA",
+            ),
+            ("COUNTIES SERVED: ADA & CANYON", "COUNTIES:ADA;CANYON"),
+            ("SERVING JEFFERSON COUNTY.", "COUNTIES:JEFFERSON"),
+            ("123 COUNTY ROAD", "123 COUNTY ROAD"),
+        ];
+        for (input, expected) in cases {
+            let mut lines = vec![input.to_string()];
+            PRSR.edit_counties(&mut lines);
+            assert_eq!(lines[0], expected, "Failed to match: {}", input);
+        }
+    }
+
+    #[test]
+    fn test_prs_foreign_adr() {
+        let cases = vec![
+            (
+                vec![
+                    "LIAISON OFFICE",
+                    "350 SPARKS STREET",
+                    "OTTAWA ON K1A 0B1",
+                    "CANADA",
+                ],
+                Some(("LIAISON OFFICE", "OTTAWA ON", "K1A 0B1", "CANADA")),
+            ),
+            (
+                vec!["INVALIDENSTRASSE 43", "10115 BERLIN", "Germany"],
+                Some(("INVALIDENSTRASSE 43", "BERLIN", "10115", "GERMANY")),
+            ),
+            (vec!["1 MAIN ST", "DAYTON", "OH", "45402"], None),
+            (vec!["1 MAIN ST", "SWINDON", "UNITED KINGDOM"], None),
+        ];
+        for (input, expected) in cases {
+            let lnes: Vec<String> = input.iter().map(|s| s.to_string()).collect();
+            let actual = PRSR.prs_foreign_adr(&lnes).map(|adr| {
+                (
+                    adr.address1,
+                    adr.city,
+                    adr.postal_code.unwrap(),
+                    adr.country.unwrap(),
+                )
+            });
+            let expected = expected
+                .map(|(a, b, c, d)| (a.to_string(), b.to_string(), c.to_string(), d.to_string()));
+            assert_eq!(actual, expected, "Failed to match: {:?}", input);
+        }
+    }
+
+    #[test]
+    fn test_clean_city() {
+        let cases = vec![
+            ("WRIGHT-PATTERSON AFB -", false, "WRIGHT-PATTERSON AFB"),
+            (
+                "WRIGHT-PATTERSON  AFB",
+                true,
+                "WRIGHT-PATTERSON AIR FORCE BASE",
+            ),
+            ("WINSTON-SALEM", true, "WINSTON-SALEM"),
+            ("WINSTON-SALEM,", false, "WINSTON-SALEM"),
+            ("SAFB CITY", true, "SAFB CITY"),
+        ];
+        for (input, is_expand, expected) in cases {
+            assert_eq!(
+                clean_city(input, is_expand),
+                expected,
+                "Failed to match: {}",
+                input
+            );
+        }
+
+        // Split city, state, and zip.
+        let mut lnes = vec!["WRIGHT-PATTERSON  AFB - OH 45433".to_string()];
+        PRSR.edit_split_city_state_zip(&mut lnes);
+        assert_eq!(lnes, vec!["WRIGHT-PATTERSON AFB", "OH", "45433"]);
+
+        let mut lnes = vec!["WINSTON-SALEM, NC 27101".to_string()];
+        PRSR.edit_split_city_state_zip(&mut lnes);
+        assert_eq!(lnes, vec!["WINSTON-SALEM", "NC", "27101"]);
+    }
+
+    /// Edits and parses raw House page lines.
+    fn prs_house_lnes(raw: &[&str]) -> Result<Vec<Address>, ParseError> {
+        let mut lnes: Vec<String> = raw.iter().map(|lne| lne.to_string()).collect();
+        PRSR.edit_lnes(&mut lnes, Source::House);
+        PRSR.prs_adrs(&lnes)
+    }
+
+    #[test]
+    fn test_prs_adrs_inverted() {
+        let adrs = prs_house_lnes(&["WASHINGTON, DC 20515", "1234 LONGWORTH HOB"]).unwrap();
+        assert_eq!(adrs.len(), 1);
+        assert_eq!(adrs[0].address1, "1234 LONGWORTH HOB");
+        assert_eq!(adrs[0].city, "WASHINGTON");
+        assert_eq!(adrs[0].zip5, 20515);
+
+        // Each street goes with the zip above it.
+        let adrs = prs_house_lnes(&[
+            "WASHINGTON, DC 20515",
+            "1234 LONGWORTH HOB",
+            "SPRINGFIELD, IL 62701",
+            "OFFICE OF THE REPRESENTATIVE",
+            "100 N MAIN ST",
+        ])
+        .unwrap();
+        assert_eq!(adrs.len(), 2);
+        let springfield = adrs.iter().find(|adr| adr.city == "SPRINGFIELD").unwrap();
+        assert_eq!(springfield.address1, "100 N MAIN ST");
+        let washington = adrs.iter().find(|adr| adr.city == "WASHINGTON").unwrap();
+        assert_eq!(washington.address1, "1234 LONGWORTH HOB");
+    }
+
+    #[test]
+    fn test_prs_adrs_normal_order() {
+        let adrs = prs_house_lnes(&[
+            "1234 LONGWORTH HOB",
+            "WASHINGTON, DC 20515",
+            "100 N MAIN ST",
+            "SUITE 2",
+            "SPRINGFIELD, IL 62701",
+        ])
+        .unwrap();
+        assert_eq!(adrs.len(), 2);
+        let springfield = adrs.iter().find(|adr| adr.city == "SPRINGFIELD").unwrap();
+        assert_eq!(springfield.address1, "100 N MAIN ST");
+        assert_eq!(springfield.address2.as_deref(), Some("SUITE 2"));
+        let washington = adrs.iter().find(|adr| adr.city == "WASHINGTON").unwrap();
+        assert_eq!(washington.address1, "1234 LONGWORTH HOB");
+
+        // A street above the first zip keeps the usual order.
+        assert!(!PRSR.is_inverted(&[
+            "1234 LONGWORTH HOB".to_string(),
+            "WASHINGTON".to_string(),
+            "DC".to_string(),
+            "20515".to_string(),
+            "100 N MAIN ST".to_string(),
+        ]));
+    }
+
+    #[test]
+    fn test_prs_adrs_dedup_zip4() {
+        // A contact page block with zip4 and a footer block without.
+        let adrs = prs_house_lnes(&[
+            "100 N MAIN ST",
+            "SPRINGFIELD, IL 62701-6789",
+            "100 N MAIN ST",
+            "SPRINGFIELD, IL 62701",
+        ])
+        .unwrap();
+        assert_eq!(adrs.len(), 1);
+        assert_eq!(adrs[0].zip4, 6789);
+    }
+
+    #[test]
+    fn test_zip_state() {
+        let cases = [
+            (99501, Some("AK")),
+            (99701, Some("AK")),
+            (20515, Some("DC")),
+            (22201, Some("VA")),
+            (901, Some("PR")),
+            (73301, Some("TX")),
+            (79936, Some("TX")),
+            (9012, None),
+        ];
+        for (zip5, expected) in cases {
+            assert_eq!(zip_state(zip5), expected, "Failed to match: {}", zip5);
+        }
+    }
+
+    #[test]
+    fn test_edit_infer_state() {
+        // City and zip without a state.
+        let per = Person::default();
+        let html = "<address>101 W 4th Ave<br>Anchorage 99501</address>";
+        let adrs = crate::house::prs_html_adrs(&per, html).unwrap();
+        assert_eq!(adrs[0].to_string(), "101 W 4TH AVE,,ANCHORAGE,AK,99501,0,");
+        let html = "<address>101 College Rd<br>Fairbanks, 99701</address>";
+        let adrs = crate::house::prs_html_adrs(&per, html).unwrap();
+        assert_eq!(adrs[0].to_string(), "101 COLLEGE RD,,FAIRBANKS,AK,99701,0,");
+
+        // Lines with a state are unchanged.
+        let lnes = |raw: &[&str]| raw.iter().map(|lne| lne.to_string()).collect::<Vec<_>>();
+        let mut cur = lnes(&["100 N MAIN ST", "SPRINGFIELD", "IL", "62701"]);
+        assert!(PRSR.edit_infer_state(&mut cur).is_empty());
+        assert_eq!(cur, lnes(&["100 N MAIN ST", "SPRINGFIELD", "IL", "62701"]));
+
+        // A suite line above the zip isn't a city.
+        let mut cur = lnes(&["100 N MAIN ST", "SPRINGFIELD", "SUITE 2", "62701"]);
+        PRSR.edit_infer_state(&mut cur);
+        assert_eq!(cur.len(), 4);
+
+        // A state elsewhere in the block disagrees.
+        let mut cur = lnes(&[
+            "100 MAIN ST",
+            "WA",
+            "ANCHORAGE",
+            "99501",
+            "1 STATE ST",
+            "JUNEAU",
+        ]);
+        let warnings = PRSR.edit_infer_state(&mut cur);
+        assert_eq!(
+            cur,
+            lnes(&[
+                "100 MAIN ST",
+                "WA",
+                "ANCHORAGE",
+                "AK",
+                "99501",
+                "1 STATE ST",
+                "JUNEAU"
+            ])
+        );
+        assert_eq!(
+            warnings,
+            vec!["inferred state AK of zip 99501 disagrees with WA"]
+        );
+    }
+
+    #[test]
+    fn test_prs_adrs_firm() {
+        // A building name above address1 is kept.
+        let per = Person {
+            name: "Andy Biggs".into(),
+            ..Default::default()
+        };
+        let html = "<address>Superstition Plaza<br>2051 W Warner Rd, Suite 1<br>\
+            Chandler, AZ 85224</address>";
+        let adrs = crate::house::prs_html_adrs(&per, html).unwrap();
+        assert_eq!(adrs[0].firm.as_deref(), Some("SUPERSTITION PLAZA"));
+        assert_eq!(
+            adrs[0].to_string(),
+            "2051 W WARNER RD,SUITE 1,CHANDLER,AZ,85224,0,"
+        );
+
+        let cases = [
+            ("WELLS FARGO CENTER", true),
+            ("SILVER BOW CENTER", true),
+            ("O'NEILL FEDERAL BUILDING", true),
+            ("DISTRICT OFFICE", false),
+            ("SUITE 1500", false),
+            ("100 MAIN PLAZA", false),
+        ];
+        for (lne, expected) in cases {
+            assert_eq!(is_firm(lne), expected, "Failed to match: {}", lne);
+        }
+    }
+
+    #[test]
+    fn test_edit_po_box_pairs() {
+        let prsr = Prsr::new();
+        let lnes = |raw: &[&str]| raw.iter().map(|lne| lne.to_string()).collect::<Vec<_>>();
+        let adr1s = |adrs: &[Address]| {
+            adrs.iter()
+                .map(|adr| adr.address1.clone())
+                .collect::<Vec<_>>()
+        };
+
+        // A street and a mailing PO Box of one office keep the PO Box.
+        let pair = lnes(&[
+            "146 N STATE AVENUE",
+            "SOMERTON",
+            "AZ",
+            "85350",
+            "PO BOX 4105",
+            "SOMERTON",
+            "AZ",
+            "85350",
+        ]);
+        let adrs = prsr.prs_adrs(&pair).unwrap();
+        assert_eq!(adr1s(&adrs), vec!["PO BOX 4105"]);
+
+        // The preference is configurable.
+        let mut adrs = vec![adrs[0].clone(), adrs[0].clone()];
+        adrs[1].address1 = "146 N STATE AVENUE".into();
+        let mut street = adrs.clone();
+        prsr.edit_po_box_pairs(&mut street, PoBoxPreference::PreferStreet);
+        assert_eq!(adr1s(&street), vec!["146 N STATE AVENUE"]);
+        let mut both = adrs.clone();
+        prsr.edit_po_box_pairs(&mut both, PoBoxPreference::KeepBoth);
+        assert_eq!(both.len(), 2);
+
+        // Distinct offices in one city are kept.
+        let distinct = lnes(&[
+            "100 MAIN ST",
+            "TUCSON",
+            "AZ",
+            "85701",
+            "200 OAK AVE",
+            "TUCSON",
+            "AZ",
+            "85701",
+            "PO BOX 12",
+            "TUCSON",
+            "AZ",
+            "85702",
+        ]);
+        let adrs = prsr.prs_adrs(&distinct).unwrap();
+        assert_eq!(
+            adr1s(&adrs),
+            vec!["100 MAIN ST", "200 OAK AVE", "PO BOX 12"]
+        );
+
+        // Two offices sharing a zip with a PO Box are all kept.
+        let same_zip = lnes(&[
+            "100 MAIN ST",
+            "TUCSON",
+            "AZ",
+            "85701",
+            "200 OAK AVE",
+            "TUCSON",
+            "AZ",
+            "85701",
+            "PO BOX 12",
+            "TUCSON",
+            "AZ",
+            "85701",
+        ]);
+        let adrs = prsr.prs_adrs(&same_zip).unwrap();
+        assert_eq!(
+            adr1s(&adrs),
+            vec!["100 MAIN ST", "200 OAK AVE", "PO BOX 12"]
+        );
+    }
+
+    #[test]
+    fn test_known_bad() {
+        let lnes = |raw: &[&str]| raw.iter().map(|lne| lne.to_string()).collect::<Vec<_>>();
+        let ok = lnes(&["9100 PORT OF SALE MALL", "ST THOMAS", "VI", "00802"]);
+        let bad = lnes(&["5049 KONGENS GADE", "ST THOMAS", "VI", "00802"]);
+
+        // By default, only the failing St. Thomas address is skipped.
+        assert_eq!(
+            KnownBad::load("missing_known_bad.json").unwrap(),
+            KnownBad::defaults()
+        );
+        let adrs = PRSR.prs_adrs(&ok).unwrap();
+        assert_eq!(
+            adrs[0].to_string(),
+            "9100 PORT OF SALE MALL,,ST THOMAS,VI,802,0,"
+        );
+        assert_eq!(PRSR.prs_adrs(&bad), Err(ParseError::NoZipFound));
+
+        // A replaced list skips only its own addresses.
+        let prsr = Prsr::new().with_known_bad(vec![KnownBad {
+            zip5: 802,
+            address1: Some("PORT OF SALE".into()),
+            reason: "no such address".into(),
+        }]);
+        assert_eq!(prsr.prs_adrs(&ok), Err(ParseError::NoZipFound));
+        assert_eq!(
+            prsr.prs_adrs(&bad).unwrap()[0].address1,
+            "5049 KONGENS GADE"
+        );
+
+        // Entries without address1 skip every block of the zip.
+        let defaults = Prsr::new();
+        let lnes_78702 = lnes(&["1000 E 11TH ST", "AUSTIN", "TX", "78702"]);
+        assert!(defaults.is_known_bad(&lnes_78702, 3));
+        assert!(!defaults.is_known_bad(&ok, 3));
+    }
+
+    #[test]
+    fn test_synth_dc_adr1() {
+        let lnes = |raw: &[&str]| raw.iter().map(|lne| lne.to_string()).collect::<Vec<_>>();
+        let cases = [
+            (vec!["LONGWORTH", "ROOM 1027"], 20515, Some("1027 LHOB")),
+            (
+                vec!["ROOM 1027", "CONTACT US", "LONGWORTH"],
+                20515,
+                Some("1027 LHOB"),
+            ),
+            (
+                vec!["SUITE 2", "CANNON", "ROOM 143"],
+                20515,
+                Some("143 CHOB"),
+            ),
+            (vec!["SUITE SD-G12", "DIRKSEN"], 20510, Some("G12 DSOB")),
+            (vec!["HART", "SUITE 509"], 20510, Some("509 HSOB")),
+            // Building of the other chamber.
+            (vec!["HART", "SUITE 509"], 20515, None),
+            // Missing room.
+            (vec!["RAYBURN"], 20515, None),
+            (vec!["ROOM 5", "RAYBURN"], 62701, None),
+        ];
+        for (input, zip5, expected) in cases {
+            assert_eq!(
+                synth_dc_adr1(&lnes(&input), zip5).as_deref(),
+                expected,
+                "Failed to match: {:?}",
+                input
+            );
+        }
+
+        // Building and room lines apart from the city line.
+        let adrs = prs_house_lnes(&[
+            "ROOM 1027",
+            "OFFICE OF THE REPRESENTATIVE",
+            "LONGWORTH",
+            "WASHINGTON, DC 20515",
+        ])
+        .unwrap();
+        assert_eq!(adrs.len(), 1);
+        assert_eq!(adrs[0].address1, "1027 LHOB");
+        assert_eq!(adrs[0].zip5, 20515);
+        let mut lnes = lnes(&["SUITE SR-374A", "RUSSELL", "WASHINGTON", "DC", "20510"]);
+        PRSR.edit_lnes(&mut lnes, Source::Senate);
+        assert_eq!(PRSR.prs_adrs(&lnes).unwrap()[0].address1, "374A RSOB");
+    }
+
+    #[test]
+    fn test_prs_adrs_errors() {
+        let lnes = |raw: &[&str]| raw.iter().map(|lne| lne.to_string()).collect::<Vec<_>>();
+        let cases = vec![
+            (lnes(&["CONTACT US", "PHONE"]), ParseError::NoZipFound),
+            (
+                lnes(&["OFFICE HOURS", "SPRINGFIELD", "IL", "62701"]),
+                ParseError::MissingAddress1 {
+                    lnes: lnes(&["OFFICE HOURS", "SPRINGFIELD", "IL", "62701"]),
+                },
+            ),
+            (
+                lnes(&["IL", "62701", "100 N MAIN ST"]),
+                ParseError::MissingCityState {
+                    lnes: lnes(&["IL", "62701", "100 N MAIN ST"]),
+                },
+            ),
+            (
+                lnes(&["100 N MAIN ST", "SPRINGFIELD", "SUITE 2", "62701"]),
+                ParseError::InvalidState {
+                    lne: "SUITE 2".into(),
+                },
+            ),
+        ];
+        for (input, expected) in cases {
+            assert_eq!(
+                PRSR.prs_adrs(&input),
+                Err(expected.clone()),
+                "Failed to match: {:?}",
+                input
+            );
+        }
+
+        // Zips without city and state lines don't panic.
+        assert!(matches!(
+            PRSR.prs_adrs(&lnes(&["20515"])),
+            Err(ParseError::MissingCityState { .. })
+        ));
+        assert!(matches!(
+            PRSR.prs_adrs(&lnes(&["DC", "20515"])),
+            Err(ParseError::MissingCityState { .. })
+        ));
+        assert!(PRSR
+            .prs_adrs(&lnes(&["100 MAIN ST", "DC", "20515"]))
+            .is_err());
+
+        // A footer zip is skipped.
+        let mut page = lnes(&["100 N MAIN ST", "SPRINGFIELD", "IL", "62701", "62702"]);
+        let adrs = PRSR.prs_adrs(&page).unwrap();
+        assert_eq!(adrs.len(), 1);
+        assert_eq!(
+            adrs[0].to_string(),
+            "100 N MAIN ST,,SPRINGFIELD,IL,62701,0,"
+        );
+
+        // Address lines don't cross into the previous address.
+        page.extend(lnes(&["OFFICE HOURS", "DECATUR", "IL", "62523"]));
+        assert_eq!(
+            PRSR.prs_adrs(&page),
+            Err(ParseError::MissingAddress1 {
+                lnes: lnes(&["OFFICE HOURS", "DECATUR", "IL", "62523"]),
+            })
+        );
+
+        // Every error moves on to the next candidate url.
+        let url = "https://doe.house.gov/contact";
+        assert_eq!(adrs_or_next(url, PRSR.prs_adrs(&page)), None);
+        assert_eq!(adrs_or_next(url, Err(ParseError::NoZipFound)), None);
+        assert_eq!(
+            adrs_or_next(url, PRSR.prs_adrs(&page[..5])).map(|adrs| adrs.len()),
+            Some(1)
+        );
+
+        // Errors carry the lines around the failure.
+        assert_eq!(
+            ParseError::MissingAddress1 {
+                lnes: lnes(&["SPRINGFIELD", "IL", "62701"])
+            }
+            .to_string(),
+            r#"missing address line 1 near ["SPRINGFIELD", "IL", "62701"]"#
+        );
+    }
 }
@@ -0,0 +1,212 @@
+use anyhow::{anyhow, Result};
+use printpdf::*;
+use qrcode::{Color as QrColor, EcLevel, QrCode};
+
+/// Module size, 0.6 mm for phone cameras at reading distance.
+pub const QR_MODULE: Mm = Mm(0.6);
+
+/// Returns the response url of a mailpiece, such as `https://example.org/r/000123`.
+pub fn qr_url(base: &str, id: u32) -> String {
+    format!("{}/{id:06}", base.trim_end_matches('/'))
+}
+
+/// Encodes a payload as QR modules, returning the width and dark modules by row.
+///
+/// Medium error correction tolerates ink spread and smudges on envelopes.
+pub fn qr_modules(payload: &str) -> Result<(usize, Vec<bool>)> {
+    let code = QrCode::with_error_correction_level(payload, EcLevel::M)
+        .map_err(|err| anyhow!("QR {payload}: {err}"))?;
+    let modules = code
+        .to_colors()
+        .into_iter()
+        .map(|c| c == QrColor::Dark)
+        .collect();
+    Ok((code.width(), modules))
+}
+
+/// Returns the side length of the QR code of a payload.
+pub fn qr_size(payload: &str) -> Result<Mm> {
+    let (width, _) = qr_modules(payload)?;
+    Ok(QR_MODULE * width as f32)
+}
+
+/// Draws the dark modules of a QR code as filled rectangles from a lower left corner.
+pub fn draw_qr(lyr: &PdfLayerReference, payload: &str, x: Mm, y: Mm) -> Result<()> {
+    let (width, modules) = qr_modules(payload)?;
+    lyr.set_fill_color(Color::Greyscale(Greyscale::new(0.0, None)));
+    for (idx, _) in modules.iter().enumerate().filter(|(_, is_dark)| **is_dark) {
+        // Rows run down from the top edge.
+        let ll_x = x + QR_MODULE * (idx % width) as f32;
+        let ll_y = y + QR_MODULE * (width - 1 - idx / width) as f32;
+        let rect = Rect::new(ll_x, ll_y, ll_x + QR_MODULE, ll_y + QR_MODULE)
+            .with_mode(path::PaintMode::Fill);
+        lyr.add_rect(rect);
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Format information coordinates beside the top left finder, most significant bit first.
+    const FORMAT_COORDS: [(usize, usize); 15] = [
+        (0, 8),
+        (1, 8),
+        (2, 8),
+        (3, 8),
+        (4, 8),
+        (5, 8),
+        (7, 8),
+        (8, 8),
+        (8, 7),
+        (8, 5),
+        (8, 4),
+        (8, 3),
+        (8, 2),
+        (8, 1),
+        (8, 0),
+    ];
+
+    /// Characters of alphanumeric mode by value.
+    const ALPHANUMERIC: &[u8; 45] = b"0123456789ABCDEFGHIJKLMNOPQRSTUVWXYZ $%*+-./:";
+
+    /// Data bits of versions 1 to 3 at medium error correction.
+    const DATA_BITS_M: [usize; 3] = [16 * 8, 28 * 8, 44 * 8];
+
+    /// Whether a module of a version 1 to 6 code is a finder, timing, format or alignment module.
+    fn is_functional(width: usize, x: usize, y: usize) -> bool {
+        let is_align = width > 21 && x.abs_diff(width - 7) <= 2 && y.abs_diff(width - 7) <= 2;
+        x == 6
+            || y == 6
+            || (x < 9 && y < 9)
+            || (x < 9 && y >= width - 8)
+            || (x >= width - 8 && y < 9)
+            || is_align
+    }
+
+    /// Whether a mask pattern inverts the module at row `i` and column `j`.
+    fn is_masked(mask: u16, i: usize, j: usize) -> bool {
+        match mask {
+            0 => (i + j).is_multiple_of(2),
+            1 => i.is_multiple_of(2),
+            2 => j.is_multiple_of(3),
+            3 => (i + j).is_multiple_of(3),
+            4 => (i / 2 + j / 3).is_multiple_of(2),
+            5 => (i * j) % 2 + (i * j) % 3 == 0,
+            6 => ((i * j) % 2 + (i * j) % 3).is_multiple_of(2),
+            _ => ((i + j) % 2 + (i * j) % 3).is_multiple_of(2),
+        }
+    }
+
+    /// Decodes the payload of a single block QR bitmap, versions 1 to 3 at medium error correction.
+    fn decode(width: usize, modules: &[bool]) -> String {
+        let is_dark = |x: usize, y: usize| modules[y * width + x];
+
+        // Format information, unmasked, holds the error correction level and mask.
+        let format = FORMAT_COORDS
+            .iter()
+            .fold(0u16, |acc, &(x, y)| acc << 1 | is_dark(x, y) as u16)
+            ^ 0x5412;
+        assert_eq!(format >> 13, 0b00, "medium error correction");
+        let mask = (format >> 10) & 0b111;
+
+        // Data bits zigzag up and down column pairs from the bottom right, skipping the timing column.
+        let mut bits: Vec<bool> = Vec::new();
+        let mut is_up = true;
+        let mut x = width - 1;
+        while x > 0 {
+            if x == 6 {
+                x -= 1;
+            }
+            for k in 0..width {
+                let y = if is_up { width - 1 - k } else { k };
+                for cx in [x, x - 1] {
+                    if !is_functional(width, cx, y) {
+                        bits.push(is_dark(cx, y) ^ is_masked(mask, y, cx));
+                    }
+                }
+            }
+            is_up = !is_up;
+            x = x.saturating_sub(2);
+        }
+
+        // Segments of numeric, alphanumeric and byte modes, until the terminator.
+        // A full code truncates the terminator, read as light past the data capacity.
+        let cap = DATA_BITS_M[(width - 17) / 4 - 1];
+        bits.truncate(cap);
+        let mut pos = 0;
+        let mut read = |len: usize| {
+            let val = (pos..pos + len).fold(0usize, |acc, idx| {
+                acc << 1 | bits.get(idx).copied().unwrap_or_default() as usize
+            });
+            pos += len;
+            val
+        };
+        let mut txt = String::new();
+        loop {
+            match read(4) {
+                0b0001 => {
+                    let mut len = read(10);
+                    while len > 0 {
+                        let (digits, bit_len) = match len {
+                            1 => (1, 4),
+                            2 => (2, 7),
+                            _ => (3, 10),
+                        };
+                        txt.push_str(&format!("{:0digits$}", read(bit_len)));
+                        len -= digits;
+                    }
+                }
+                0b0010 => {
+                    let mut len = read(9);
+                    while len > 0 {
+                        if len == 1 {
+                            txt.push(ALPHANUMERIC[read(6)] as char);
+                            break;
+                        }
+                        let val = read(11);
+                        txt.push(ALPHANUMERIC[val / 45] as char);
+                        txt.push(ALPHANUMERIC[val % 45] as char);
+                        len -= 2;
+                    }
+                }
+                0b0100 => {
+                    let len = read(8);
+                    let bytes: Vec<u8> = (0..len).map(|_| read(8) as u8).collect();
+                    txt.push_str(&String::from_utf8(bytes).unwrap());
+                }
+                _ => return txt,
+            }
+        }
+    }
+
+    #[test]
+    fn test_qr_url() {
+        assert_eq!(
+            qr_url("https://example.org/r", 123),
+            "https://example.org/r/000123"
+        );
+        assert_eq!(
+            qr_url("https://example.org/r/", 1234567),
+            "https://example.org/r/1234567"
+        );
+    }
+
+    #[test]
+    fn test_qr_modules_decode() {
+        for id in [1, 123, 999_999] {
+            let url = qr_url("https://example.org/r", id);
+            let (width, modules) = qr_modules(&url).unwrap();
+            assert_eq!(modules.len(), width * width);
+            // Single block versions keep codewords in order.
+            assert!(width <= 29, "Failed to match: {}", url);
+            assert_eq!(decode(width, &modules), url, "Failed to match: {}", url);
+            assert!(decode(width, &modules).ends_with(&format!("{id:06}")));
+        }
+        assert_eq!(
+            qr_size("https://example.org/r/000123").unwrap(),
+            QR_MODULE * 25.0
+        );
+    }
+}
@@ -0,0 +1,301 @@
+use crate::committee::*;
+use crate::diff::*;
+use crate::executive::*;
+use crate::house::*;
+use crate::io::{data_pth, read_from_file, write_to_file};
+use crate::military::*;
+use crate::models::*;
+use crate::nasa::*;
+use crate::observer::*;
+use crate::senate::*;
+use crate::state::*;
+use anyhow::Result;
+use serde::Deserialize;
+use std::fmt;
+use std::fs;
+use std::fs::File;
+use std::future::Future;
+use std::io::BufReader;
+use std::path::{Path, PathBuf};
+
+/// Sources refreshed by default.
+pub const REFRESH_SOURCES: [Source; 3] = [Source::Senate, Source::House, Source::State];
+
+/// Persons of a source checkpoint file.
+#[derive(Debug, Deserialize)]
+struct Checkpoint {
+    persons: Vec<Person>,
+}
+
+/// Changes found by a refresh, per source.
+#[derive(Debug, Default)]
+pub struct RefreshReport {
+    pub changes: Vec<(Source, Vec<Change>)>,
+    pub failures: Vec<(Source, String)>,
+}
+
+impl RefreshReport {
+    /// Whether nothing changed and nothing failed.
+    pub fn is_empty(&self) -> bool {
+        self.changes.iter().all(|(_, changes)| changes.is_empty()) && self.failures.is_empty()
+    }
+}
+
+impl fmt::Display for RefreshReport {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for (source, changes) in self.changes.iter().filter(|(_, c)| !c.is_empty()) {
+            writeln!(f, "{source}")?;
+            for change in changes {
+                for lne in change.to_string().lines() {
+                    writeln!(f, "  {lne}")?;
+                }
+            }
+        }
+        for (source, err) in self.failures.iter() {
+            writeln!(f, "{source}")?;
+            writeln!(f, "  refresh failed: {err}")?;
+        }
+        Ok(())
+    }
+}
+
+/// Refetches and standardizes sources, reporting changes against previous checkpoints.
+///
+/// A failed source keeps its previous checkpoint and is reported.
+/// No mailing artifacts are created.
+pub async fn refresh(sources: &[Source]) -> RefreshReport {
+    let mut rpt = RefreshReport::default();
+    for source in sources.iter().copied() {
+        let res = match checkpoint_pth(source) {
+            Ok(pth) => refresh_checkpoint(&data_pth(pth), || load_source(source)).await,
+            Err(err) => Err(err),
+        };
+        match res {
+            Ok(changes) => rpt.changes.push((source, changes)),
+            Err(err) => rpt.failures.push((source, err.to_string())),
+        }
+    }
+    rpt
+}
+
+/// Checkpoint file of a source.
+pub fn checkpoint_pth(source: Source) -> Result<&'static str> {
+    match source {
+        Source::Military => Ok(crate::military::FLE_PTH),
+        Source::Nasa => Ok(crate::nasa::FLE_PTH),
+        Source::Executive => Ok(crate::executive::FLE_PTH),
+        Source::Senate => Ok(crate::senate::FLE_PTH),
+        Source::House => Ok(crate::house::FLE_PTH),
+        Source::Committee => Ok(crate::committee::FLE_PTH),
+        Source::State => Ok(crate::state::FLE_PTH),
+        Source::Observer => Ok(crate::observer::FLE_PTH),
+    }
+}
+
+/// Fetches, standardizes, and checkpoints persons of a source.
+async fn load_source(source: Source) -> Result<Vec<Person>> {
+    match source {
+        Source::Military => Ok(Military::load().await?.persons),
+        Source::Nasa => Ok(Nasa::load().await?.persons),
+        Source::Executive => Ok(Executive::load().await?.persons),
+        Source::Senate => Ok(Senate::load().await?.persons),
+        Source::House => Ok(House::load().await?.persons),
+        Source::Committee => Ok(Committee::load().await?.persons),
+        Source::State => Ok(State::load().await?.persons),
+        Source::Observer => Ok(Observer::load().await?.persons),
+    }
+}
+
+/// Clears the addresses of persons named `name` in a person file, so the next load refetches them.
+///
+/// Names match by `name_key`; other persons and fields are untouched.
+/// Returns the urls of the cleared persons; a missing file clears nothing.
+pub fn clear_per_adrs(pth: &str, name: &str) -> Result<Vec<String>> {
+    if !data_pth(pth).exists() {
+        return Ok(Vec::new());
+    }
+    let mut per_file: serde_json::Value = read_from_file(pth)?;
+    let key = name_key(name);
+    let mut urls = Vec::new();
+    for per in per_file["persons"].as_array_mut().into_iter().flatten() {
+        if per["name"].as_str().is_some_and(|n| name_key(n) == key) {
+            per["adrs"] = serde_json::Value::Null;
+            urls.push(per["url"].as_str().unwrap_or_default().to_string());
+        }
+    }
+    if !urls.is_empty() {
+        write_to_file(&per_file, pth)?;
+    }
+    Ok(urls)
+}
+
+/// Reads persons of a checkpoint file.
+fn read_checkpoint(pth: &Path) -> Result<Vec<Person>> {
+    let file = File::open(pth)?;
+    let checkpoint: Checkpoint = serde_json::from_reader(BufReader::new(file))?;
+    Ok(checkpoint.persons)
+}
+
+/// Reloads a checkpoint and returns changes against the previous checkpoint.
+///
+/// The previous checkpoint is set aside while `load` writes a new one,
+/// then dropped on success or restored on failure.
+pub async fn refresh_checkpoint<F, Fut>(pth: &Path, load: F) -> Result<Vec<Change>>
+where
+    F: FnOnce() -> Fut,
+    Fut: Future<Output = Result<Vec<Person>>>,
+{
+    let mut pth_prev = pth.as_os_str().to_owned();
+    pth_prev.push(".prev");
+    let pth_prev = PathBuf::from(pth_prev);
+
+    let prev = if pth.exists() {
+        let prev = read_checkpoint(pth)?;
+        fs::rename(pth, &pth_prev)?;
+        prev
+    } else {
+        Vec::new()
+    };
+
+    match load().await {
+        Ok(cur) => {
+            // Promote the new checkpoint.
+            if pth_prev.exists() {
+                fs::remove_file(&pth_prev)?;
+            }
+            Ok(diff_persons(&prev, &cur))
+        }
+        Err(err) => {
+            // Restore the previous checkpoint over any partial write.
+            if pth_prev.exists() {
+                fs::rename(&pth_prev, pth)?;
+            } else if pth.exists() {
+                fs::remove_file(pth)?;
+            }
+            Err(err)
+        }
+    }
+}
+
+/// Writes a refresh report to a file, or to stdout.
+pub fn write_report(rpt: &RefreshReport, notify: &str) -> Result<()> {
+    if notify == "stdout" {
+        print!("{rpt}");
+    } else {
+        fs::write(notify, rpt.to_string())?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::*;
+    use anyhow::anyhow;
+
+    const CHECKPOINT: &str = include_str!("../tests/fixtures/refresh/senate.json");
+
+    /// Copies the fixture checkpoint to a temporary directory.
+    fn checkpoint(name: &str) -> (TempDir, PathBuf) {
+        let dir = TempDir::new(&format!("refresh_{name}"));
+        let pth = dir.join("senate.json");
+        fs::write(&pth, CHECKPOINT).unwrap();
+        (dir, pth)
+    }
+
+    /// Writes persons as a new checkpoint, as a source load does.
+    async fn load(pth: &Path, pers: Vec<Person>) -> Result<Vec<Person>> {
+        let mut senate = Senate::new();
+        senate.persons = pers;
+        write_to_file(&senate, &pth.to_string_lossy())?;
+        Ok(senate.persons)
+    }
+
+    #[test]
+    fn test_clear_per_adrs() {
+        let (dir, pth) = checkpoint("clear");
+        let pth = pth.to_string_lossy().to_string();
+
+        // Names match normalized, clearing only that person.
+        let urls = clear_per_adrs(&pth, "ANN  B. SMITH").unwrap();
+        assert_eq!(urls, vec!["https://smith.senate.gov"]);
+        let pers = read_checkpoint(Path::new(&pth)).unwrap();
+        assert!(pers[0].adrs.is_none());
+        assert!(pers[1].adrs.is_some());
+        let per_file: serde_json::Value = read_from_file(&pth).unwrap();
+        assert_eq!(per_file["name"], "U.S. Senate");
+
+        // Unknown names and missing files clear nothing.
+        assert!(clear_per_adrs(&pth, "Carl Roe").unwrap().is_empty());
+        let missing = dir.pth("house.json");
+        assert!(clear_per_adrs(&missing, "Ann Smith").unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_refresh_unchanged() {
+        let (_dir, pth) = checkpoint("unchanged");
+        let pers = read_checkpoint(&pth).unwrap();
+
+        let changes = refresh_checkpoint(&pth, || load(&pth, pers)).await.unwrap();
+        assert!(changes.is_empty());
+        let rpt = RefreshReport {
+            changes: vec![(Source::Senate, changes)],
+            failures: Vec::new(),
+        };
+        assert!(rpt.is_empty());
+        assert_eq!(rpt.to_string(), "");
+    }
+
+    #[tokio::test]
+    async fn test_refresh_changed() {
+        let (dir, pth) = checkpoint("changed");
+        let mut pers = read_checkpoint(&pth).unwrap();
+        // Moved office.
+        pers[0].adrs.as_mut().unwrap()[0].address1 = "200 MAIN ST".into();
+        // Parse failing.
+        pers[1].adrs = None;
+        // Vacancy filled.
+        pers.push(Person {
+            name: "New Member".into(),
+            ..Default::default()
+        });
+
+        let changes = refresh_checkpoint(&pth, || load(&pth, pers)).await.unwrap();
+        let rpt = RefreshReport {
+            changes: vec![(Source::Senate, changes)],
+            failures: Vec::new(),
+        };
+        assert!(!rpt.is_empty());
+        assert_eq!(
+            rpt.to_string(),
+            "senate
+  addresses changed Ann Smith: +1 -1
+    + 200 MAIN ST,,DAYTON,OH,45402,0,
+    - 100 MAIN ST,,DAYTON,OH,45402,0,
+  parse failing Bob Jones
+  added New Member
+"
+        );
+
+        // New checkpoint promoted.
+        assert_eq!(read_checkpoint(&pth).unwrap().len(), 3);
+        assert!(!dir.join("senate.json.prev").exists());
+    }
+
+    #[tokio::test]
+    async fn test_refresh_failed() {
+        let (dir, pth) = checkpoint("failed");
+
+        let res = refresh_checkpoint(&pth, || async {
+            // Partial write before failing.
+            fs::write(&pth, "{\"persons\": [").unwrap();
+            Err(anyhow!("fetch failed"))
+        })
+        .await;
+        assert!(res.is_err());
+
+        // Previous checkpoint kept.
+        assert_eq!(fs::read_to_string(&pth).unwrap(), CHECKPOINT);
+        assert!(!dir.join("senate.json.prev").exists());
+    }
+}
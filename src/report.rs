@@ -0,0 +1,165 @@
+use crate::models::*;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::sync::Mutex;
+
+/// Report file of a run.
+pub const FLE_PTH_REPORT: &str = "report.json";
+
+lazy_static! {
+    /// Per-source counts of a run, populated as sources load.
+    pub static ref REPORT: Mutex<Report> = Mutex::new(Report::default());
+}
+
+/// Counts of a source in a run.
+#[derive(Debug, Default, Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+pub struct SourceReport {
+    pub persons: usize,
+    /// Persons by address count: none, one, and two or more.
+    pub adrs_0: usize,
+    pub adrs_1: usize,
+    pub adrs_2_plus: usize,
+    /// Addresses standardized only after dropping the zip.
+    pub fallbacks: usize,
+    /// Special-case line edits changing the lines of a person or facility.
+    pub line_edits: usize,
+}
+
+impl SourceReport {
+    fn add(&mut self, other: &SourceReport) {
+        self.persons += other.persons;
+        self.adrs_0 += other.adrs_0;
+        self.adrs_1 += other.adrs_1;
+        self.adrs_2_plus += other.adrs_2_plus;
+        self.fallbacks += other.fallbacks;
+        self.line_edits += other.line_edits;
+    }
+}
+
+/// Per-source counts of a run.
+#[derive(Debug, Default, Serialize, Deserialize, Clone, PartialEq, Eq)]
+pub struct Report {
+    /// Source loading, counted by `inc_fallback` and `inc_line_edit`.
+    #[serde(skip)]
+    pub cur: Option<Source>,
+    pub sources: BTreeMap<Source, SourceReport>,
+}
+
+impl Report {
+    /// Starts counting a source.
+    pub fn begin(&mut self, source: Source) {
+        self.cur = Some(source);
+        self.sources.entry(source).or_default();
+    }
+
+    fn cur_mut(&mut self) -> Option<&mut SourceReport> {
+        let source = self.cur?;
+        Some(self.sources.entry(source).or_default())
+    }
+
+    /// Counts a fallback standardization of the current source.
+    pub fn inc_fallback(&mut self) {
+        if let Some(rpt) = self.cur_mut() {
+            rpt.fallbacks += 1;
+        }
+    }
+
+    /// Counts a line edit of the current source.
+    pub fn inc_line_edit(&mut self) {
+        if let Some(rpt) = self.cur_mut() {
+            rpt.line_edits += 1;
+        }
+    }
+
+    /// Counts a person of the current source with its address count.
+    pub fn record_person(&mut self, adr_cnt: usize) {
+        if let Some(rpt) = self.cur_mut() {
+            rpt.persons += 1;
+            match adr_cnt {
+                0 => rpt.adrs_0 += 1,
+                1 => rpt.adrs_1 += 1,
+                _ => rpt.adrs_2_plus += 1,
+            }
+        }
+    }
+
+    /// Counts of all sources.
+    pub fn total(&self) -> SourceReport {
+        let mut total = SourceReport::default();
+        for rpt in self.sources.values() {
+            total.add(rpt);
+        }
+        total
+    }
+
+    /// Lines of the run summary, a table with a total row.
+    pub fn lnes(&self) -> Vec<String> {
+        let row = |name: &str, rpt: &SourceReport| {
+            format!(
+                "{name:<10}{:>8}{:>6}{:>6}{:>6}{:>10}{:>7}",
+                rpt.persons, rpt.adrs_0, rpt.adrs_1, rpt.adrs_2_plus, rpt.fallbacks, rpt.line_edits
+            )
+        };
+        let mut lnes = vec![format!(
+            "{:<10}{:>8}{:>6}{:>6}{:>6}{:>10}{:>7}",
+            "source", "persons", "0", "1", "2+", "fallbacks", "edits"
+        )];
+        for (source, rpt) in &self.sources {
+            lnes.push(row(&source.to_string(), rpt));
+        }
+        lnes.push(row("total", &self.total()));
+        lnes
+    }
+}
+
+/// Applies a special-case line edit, counting it when the lines change.
+pub fn edit_counted(lnes: &mut Vec<String>, edit: impl FnOnce(&mut Vec<String>)) {
+    let pre = lnes.clone();
+    edit(lnes);
+    if *lnes != pre {
+        REPORT.lock().unwrap().inc_line_edit();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_report() {
+        let mut rpt = Report::default();
+
+        // Counts without a current source are dropped.
+        rpt.inc_fallback();
+        rpt.record_person(1);
+        assert!(rpt.sources.is_empty());
+
+        rpt.begin(Source::Senate);
+        rpt.record_person(1);
+        rpt.record_person(2);
+        rpt.inc_line_edit();
+        rpt.begin(Source::House);
+        rpt.record_person(0);
+        rpt.record_person(3);
+        rpt.record_person(1);
+        rpt.inc_fallback();
+        rpt.begin(Source::Nasa);
+
+        // Per-source persons add up to the total.
+        let total = rpt.total();
+        assert_eq!(total.persons, 5);
+        assert_eq!(total.adrs_0 + total.adrs_1 + total.adrs_2_plus, 5);
+        assert_eq!(rpt.sources[&Source::House].adrs_2_plus, 1);
+        assert_eq!(rpt.sources[&Source::House].fallbacks, 1);
+        assert_eq!(rpt.sources[&Source::Senate].line_edits, 1);
+        assert_eq!(rpt.sources[&Source::Nasa], SourceReport::default());
+
+        let lnes = rpt.lnes();
+        assert_eq!(lnes.len(), 5);
+        assert!(lnes[0].starts_with("source"), "{lnes:?}");
+        assert_eq!(
+            lnes[4],
+            "total            5     1     2     2         1      1"
+        );
+    }
+}
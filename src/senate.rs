@@ -1,25 +1,50 @@
-use crate::core::*;
+use crate::io::*;
+use crate::manual::*;
 use crate::models::*;
+use crate::office_pdf::*;
 use crate::prsr::*;
+use crate::report::*;
 use crate::usps::*;
 use anyhow::{anyhow, Result};
-use scraper::{Html, Selector};
+use chrono::{Local, NaiveDate};
+use scraper::{ElementRef, Html, Selector};
 use serde::{Deserialize, Serialize};
-use std::fs::File;
-use std::io::{BufReader, BufWriter};
-use std::ops::Add;
-use std::path::Path;
+use std::time::Instant;
+use tracing::{debug, info, trace, warn};
 
-const FLE_PTH: &str = "senate.json";
+pub const FLE_PTH: &str = "senate.json";
 
 /// The U.S. Senate consists of 100 members, with each of the 50 states represented by two senators regardless of population size.
 const CAP_PER: usize = 100;
 
+/// States represented in the Senate, in fetch order.
+pub const STATES: [&str; 50] = [
+    "AL", "AK", "AZ", "AR", "CA", "CO", "CT", "DE", "FL", "GA", "HI", "ID", "IL", "IN", "IA", "KS",
+    "KY", "LA", "ME", "MD", "MA", "MI", "MN", "MS", "MO", "MT", "NE", "NV", "NH", "NJ", "NM", "NY",
+    "NC", "ND", "OH", "OK", "OR", "PA", "RI", "SC", "SD", "TN", "TX", "UT", "VT", "VA", "WA", "WV",
+    "WI", "WY",
+];
+
+/// Title line of a senator on the envelope.
+pub const TITLE_SENATOR: &str = "United States Senator";
+
 #[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Eq, PartialOrd, Ord)]
 pub struct Senate {
     pub name: String,
     pub role: Role,
     pub persons: Vec<Person>,
+    /// States with fewer than two senators, such as after a resignation.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub vacancies: Vec<String>,
+    /// Date the member list was fetched; `None` in older files.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub members_on: Option<NaiveDate>,
+}
+
+impl Default for Senate {
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
 impl Senate {
@@ -28,27 +53,47 @@ impl Senate {
             name: "U.S. Senate".into(),
             role: Role::Political,
             persons: Vec::with_capacity(CAP_PER),
+            vacancies: Vec::new(),
+            members_on: None,
         }
     }
 
     pub async fn load() -> Result<Senate> {
+        REPORT.lock().unwrap().begin(Source::Senate);
+
         // Read file from disk.
         let mut senate = match read_from_file::<Senate>(FLE_PTH) {
-            Ok(senate_from_disk) => senate_from_disk,
+            Ok(mut senate_from_disk) => {
+                // Backfill states of older files.
+                // Members were fetched two per state in `STATES` order.
+                for (idx, per) in senate_from_disk.persons.iter_mut().enumerate() {
+                    if per.state.is_empty() && idx / 2 < STATES.len() {
+                        per.state = STATES[idx / 2].into();
+                    }
+                }
+                // Backfill titles of older files.
+                for per in senate_from_disk
+                    .persons
+                    .iter_mut()
+                    .filter(|per| per.title1.is_empty())
+                {
+                    set_senator_titles(per);
+                }
+                // Backfill roles of older files.
+                set_roles(&mut senate_from_disk.persons, &senate_from_disk.role);
+                // Refetch members, dropping former members.
+                if is_members_refresh(senate_from_disk.members_on) {
+                    senate_from_disk.refresh_members().await?;
+                }
+                senate_from_disk
+            }
             Err(_) => {
                 let mut senate = Senate::new();
 
                 // Fetch members.
-                let states = vec![
-                    "AL", "AK", "AZ", "AR", "CA", "CO", "CT", "DE", "FL", "GA", "HI", "ID", "IL",
-                    "IN", "IA", "KS", "KY", "LA", "ME", "MD", "MA", "MI", "MN", "MS", "MO", "MT",
-                    "NE", "NV", "NH", "NJ", "NM", "NY", "NC", "ND", "OH", "OK", "OR", "PA", "RI",
-                    "SC", "SD", "TN", "TX", "UT", "VT", "VA", "WA", "WV", "WI", "WY",
-                ];
-                for state in states {
-                    let pers = senate.fetch_members(state).await?;
-                    senate.persons.extend(pers);
-                }
+                (senate.persons, senate.vacancies) = senate.fetch_all_members().await?;
+                senate.members_on = Some(Local::now().date_naive());
+                set_roles(&mut senate.persons, &senate.role);
 
                 // Write file to disk.
                 write_to_file(&senate, FLE_PTH)?;
@@ -57,7 +102,12 @@ impl Senate {
             }
         };
 
-        println!("{} senators", senate.persons.len());
+        info!("{} senators", senate.persons.len());
+        if !senate.vacancies.is_empty() {
+            let mut lnes = vec![format!("{} senate vacancies", senate.vacancies.len())];
+            lnes.extend(senate.vacancies.iter().cloned());
+            summary_add("Senate vacancies", lnes, true);
+        }
 
         // Fetch addresses.
         senate.fetch_adrs().await?;
@@ -65,57 +115,78 @@ impl Senate {
         Ok(senate)
     }
 
-    /// Fetch member from network.
-    pub async fn fetch_members(&self, state: &str) -> Result<Vec<Person>> {
-        let url = format!("https://www.senate.gov/states/{state}/intro.htm");
-        let html = fetch_html(&url).await?;
-        let document = Html::parse_document(&html);
-
-        let mut pers = Vec::new();
-
-        // Select name and url.
-        let name_sel = Selector::parse("div.state-column").expect("Invalid selector");
-        let url_sel = Selector::parse("a").expect("Invalid selector");
-        for elm_doc in document.select(&name_sel) {
-            if let Some(elm_url) = elm_doc.select(&url_sel).next() {
-                let mut per = Person::default();
-                let full_name = elm_url.text().collect::<Vec<_>>().concat();
-                eprintln!("{}", full_name.trim());
-                per.name = name_clean(&full_name);
-                per.url = elm_url
-                    .value()
-                    .attr("href")
-                    .unwrap_or_default()
-                    .replace("www.", "")
-                    .trim_end_matches('/')
-                    .to_string();
-
-                // Validate fields.
-                if per.name.is_empty() {
-                    return Err(anyhow!("name is empty {:?}", per));
-                }
-                if per.url.is_empty() {
-                    return Err(anyhow!("url is empty {:?}", per));
-                }
-                if !per.url.ends_with(".senate.gov") {
-                    return Err(anyhow!("url doesn't end with '.senate.gov' {:?}", per));
+    /// Fetch members of each state and the states with vacant seats.
+    ///
+    /// States whose page fails fall back to the XML member list.
+    pub async fn fetch_all_members(&self) -> Result<(Vec<Person>, Vec<String>)> {
+        let mut pers_all = Vec::with_capacity(CAP_PER);
+        let mut vacancies = Vec::new();
+        let mut xml_pers: Option<Vec<Person>> = None;
+        let mut fallbacks: Vec<String> = Vec::new();
+        for state in STATES {
+            let pers = match self.fetch_members(state).await {
+                Ok(pers) => pers,
+                Err(err) => {
+                    warn!("{err}: falling back to {URL_CONTACT_XML}");
+                    if xml_pers.is_none() {
+                        xml_pers = Some(self.fetch_members_xml().await?);
+                    }
+                    let pers = members_of_state(xml_pers.as_deref().unwrap(), state)?;
+                    fallbacks.push(format!("{state}: {err}"));
+                    pers
                 }
-
-                pers.push(per);
+            };
+            if pers.len() < 2 {
+                warn!("vacant senate seat for {state}");
+                vacancies.push(state.into());
             }
+            pers_all.extend(pers);
         }
-
-        if pers.len() != 2 {
-            return Err(anyhow!("missing two senators for {state}"));
+        if !fallbacks.is_empty() {
+            summary_add("Senate XML member fallbacks", fallbacks, true);
         }
+        Ok((pers_all, vacancies))
+    }
+
+    /// Refetch members and vacancies, keeping the addresses of cached members, and write the file.
+    ///
+    /// New members have no addresses, so only they're fetched.
+    pub async fn refresh_members(&mut self) -> Result<()> {
+        let (fresh, vacancies) = self.fetch_all_members().await?;
+        let (pers, stats) = reconcile_members(std::mem::take(&mut self.persons), fresh);
+        info!("senate members: {}", stats.lnes().join("; "));
+        summary_add("Senate members", stats.lnes(), false);
+        self.persons = pers;
+        self.vacancies = vacancies;
+        self.members_on = Some(Local::now().date_naive());
+        set_roles(&mut self.persons, &self.role);
+        write_to_file(self, FLE_PTH)
+    }
+
+    /// Fetch member from network.
+    pub async fn fetch_members(&self, state: &str) -> Result<Vec<Person>> {
+        let url = format!("https://www.senate.gov/states/{state}/intro.htm");
+        let html = fetch_html(&url, FetchOptions::for_source(Source::Senate)).await?;
+        prs_members(&html, state)
+    }
 
-        Ok(pers)
+    /// Fetch members of all states from the senate.gov XML member list.
+    pub async fn fetch_members_xml(&self) -> Result<Vec<Person>> {
+        let xml = fetch_html(URL_CONTACT_XML, FetchOptions::for_source(Source::Senate)).await?;
+        prs_members_xml(&xml)
     }
 
     pub async fn fetch_adrs(&mut self) -> Result<()> {
         // Clone self for file writing.
-        let mut self_clone = self.clone();
-        let per_len = self.persons.len() as f64;
+        let self_clone = self.clone();
+        let manual = ManualAddresses::load(MANUAL_PTH)?;
+        manual.validate_summary(Source::Senate, &self.persons);
+        let offices = fetch_offices().await?;
+        let mut mismatches: Vec<String> = Vec::new();
+
+        let mut ckpt = Checkpoint::new(CheckpointPolicy::load(FLE_PTH_CHECKPOINT)?, Instant::now());
+        let todo = self.persons.iter().filter(|per| per.adrs.is_none()).count();
+        let mut progress = Progress::new(Some(todo));
 
         for (idx, per) in self_clone
             .persons
@@ -124,102 +195,114 @@ impl Senate {
             .filter(|(_, per)| per.adrs.is_none())
         // .take(1)
         {
-            let pct = (((idx as f64 + 1.0) / per_len) * 100.0) as u8;
-            eprintln!("  {}% {} {} {}", pct, idx, per.name, per.url);
+            progress.tick(&format!("{} {} {}", idx, per.name, per.url));
 
-            match self.fetch_prs_per(idx, per).await? {
-                Some(adrs) => {
-                    self.persons[idx].adrs = Some(adrs);
+            // Flush completed persons before returning an error.
+            match self.fetch_adrs_per(idx, per, &manual, &offices).await {
+                Ok(mismatch) => mismatches.extend(mismatch),
+                Err(err) => {
+                    ckpt.flush(&self, FLE_PTH, Instant::now())?;
+                    return Err(err);
                 }
-                None => {
-                    // Fetch from single unknown url.
-                    let url_paths = [
-                        "contact",
-                        "contact/offices",
-                        "",
-                        "public",
-                        "public/index.cfm/office-locations",
-                        "contact/office-locations",
-                    ];
-                    for url_path in url_paths {
-                        // Create url.
-                        let mut url = per.url.clone();
-                        if !url_path.is_empty() {
-                            url.push('/');
-                            url.push_str(url_path);
-                        }
-                        // Fetch, parse, standardize.
-                        if let Some(adrs) = fetch_prs_std_adrs(per, &url).await? {
-                            self.persons[idx].adrs = Some(adrs);
-                            break;
-                        }
-                    }
-                }
-            }
-
-            // Check for address parsing error.
-            if self.persons[idx].adrs.is_none() {
-                return Err(anyhow!("no addresses for {}", self.persons[idx]));
             }
 
             // Checkpoint save.
             // Write intermediate file to disk.
-            write_to_file(&self, FLE_PTH)?;
+            ckpt.done(&self, FLE_PTH, Instant::now())?;
+        }
+        ckpt.flush(&self, FLE_PTH, Instant::now())?;
+
+        if !mismatches.is_empty() {
+            summary_add("Senate DC office mismatches", mismatches, true);
         }
 
         Ok(())
     }
 
-    pub async fn fetch_prs_per(&self, idx: usize, per: &Person) -> Result<Option<Vec<Address>>> {
+    /// Fetches addresses of a person.
+    ///
+    /// Returns a note when a scraped DC address mismatches the senate.gov directory,
+    /// or when the directory has no office of the person.
+    async fn fetch_adrs_per(
+        &mut self,
+        idx: usize,
+        per: &Person,
+        manual: &ManualAddresses,
+        offices: &[SenateOffice],
+    ) -> Result<Option<String>> {
+        // Manual addresses bypass scraping.
+        let entry = manual.get(Source::Senate, &per.name);
+        if let Some(entry) = entry.filter(|entry| entry.skip_scrape) {
+            self.persons[idx].adrs = Some(entry.std_adrs().await?);
+            self.persons[idx].provenance = Some(Provenance::new(Source::Senate, MANUAL_PTH));
+            return Ok(None);
+        }
+
+        match self.fetch_prs_per(per).await? {
+            Some((url, adrs)) => {
+                self.persons[idx].adrs = Some(adrs);
+                self.persons[idx].provenance = Some(Provenance::new(Source::Senate, url));
+            }
+            None => {
+                // Fetch from single unknown url.
+                let url_paths = [
+                    "contact",
+                    "contact/offices",
+                    "",
+                    "public",
+                    "public/index.cfm/office-locations",
+                    "contact/office-locations",
+                ];
+                let mut pdf = PdfFallback::default();
+                for url_path in url_paths {
+                    // Create url.
+                    let mut url = per.url.clone();
+                    if !url_path.is_empty() {
+                        url.push('/');
+                        url.push_str(url_path);
+                    }
+                    // Fetch, parse, standardize.
+                    if let Some(adrs) = fetch_prs_std_adrs(per, &url, &mut pdf).await? {
+                        self.persons[idx].adrs = Some(adrs);
+                        self.persons[idx].provenance = Some(Provenance::new(Source::Senate, &url));
+                        break;
+                    }
+                }
+            }
+        }
+
+        // Manual addresses extend scraped addresses.
+        if let Some(entry) = entry {
+            let adrs = self.persons[idx].adrs.take();
+            self.persons[idx].adrs = Some(entry.append_to(adrs).await?);
+            let provenance = &mut self.persons[idx].provenance;
+            provenance.get_or_insert_with(|| Provenance::new(Source::Senate, MANUAL_PTH));
+        }
+
+        // Check for address parsing error.
+        if self.persons[idx].adrs.is_none() {
+            return Err(anyhow!("no addresses for {}", self.persons[idx]));
+        }
+
+        // DC office from the senate.gov directory.
+        let adrs = self.persons[idx].adrs.take().unwrap_or_default();
+        let (adrs, mismatch) = merge_office(per, adrs, offices);
+        self.persons[idx].adrs = Some(adrs);
+        Ok(mismatch.map(|lne| format!("{}: {lne}", per.name)))
+    }
+
+    /// Fetches addresses of a person with a known page, returning the page and addresses.
+    pub async fn fetch_prs_per(
+        &self,
+        per: &Person,
+    ) -> Result<Option<(&'static str, Vec<Address>)>> {
         match per.name.as_str() {
             "John W. Hickenlooper" => {
                 let url = "https://hickenlooper.senate.gov/wp-json/wp/v2/locations";
                 let response = reqwest::get(url).await?.text().await?;
-                let locations: Vec<Location> = serde_json::from_str(&response)?;
-                let mut adrs: Vec<Address> = locations
-                    .into_iter()
-                    .map(|loc| {
-                        let mut adr = Address {
-                            address1: loc.acf.address,
-                            address2: if loc.acf.suite.is_empty() {
-                                None
-                            } else {
-                                Some(loc.acf.suite)
-                            },
-                            city: loc.acf.city,
-                            state: loc.acf.state,
-                            ..Default::default()
-                        };
-                        let lne_zip = &loc.acf.zipcode;
-                        let is_zip5 = is_zip5(lne_zip);
-                        let is_zip10 = if !is_zip5 { is_zip10(lne_zip) } else { false };
-                        if is_zip5 {
-                            adr.zip5 = lne_zip.parse().unwrap();
-                        } else {
-                            adr.zip5 = lne_zip[..5].parse().unwrap();
-                            adr.zip4 = lne_zip[lne_zip.len() - 4..].parse().unwrap();
-                        }
-
-                        adr
-                    })
-                    .collect();
-                for idx in (0..adrs.len()).rev() {
-                    if adrs[idx].address1 == "~" {
-                        adrs.remove(idx);
-                    } else if adrs[idx].address1.starts_with("2 Constitution Ave") {
-                        // Russell Senate Office Building
-                        // 2 Constitution Ave NE,Suite SR-374
-                        if let Some(adr2) = adrs[idx].address2.clone() {
-                            if let Some(idx_fnd) = adr2.find("SR-") {
-                                let mut adr1 = adr2[idx_fnd + 3..].to_string();
-                                adr1.push_str(" RUSSELL SOB");
-                                adrs[idx].address1 = adr1;
-                                adrs[idx].address2 = None;
-                            }
-                        }
-                    }
-                }
-                return Ok(Some(standardize_addresses(adrs).await?));
+                // The DC office is checked against the senate.gov directory.
+                let adrs = prs_locations(&response)?;
+                return Ok(Some((url, standardize_addresses(adrs).await?)));
             }
             "" => {}
             _ => {}
@@ -229,36 +312,399 @@ impl Senate {
     }
 }
 
+/// Contact information of senators, including DC office rooms.
+pub const URL_CONTACT_XML: &str =
+    "https://www.senate.gov/general/contact_information/senators_cfm.xml";
+
+/// A senator's DC office from the senate.gov contact information.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SenateOffice {
+    pub last_name: String,
+    pub state: String,
+    /// Website host, such as `baldwin.senate.gov`.
+    pub website: String,
+    /// Room, such as `141` or `127A`.
+    pub room: String,
+    /// Building, such as `HART`.
+    pub building: String,
+}
+
+impl SenateOffice {
+    /// Whether the office is of a person, by website or by state and last name.
+    pub fn is_person(&self, per: &Person) -> bool {
+        url_host(&per.url) == self.website
+            || (per.state == self.state && per.name.to_uppercase().ends_with(&self.last_name))
+    }
+
+    /// DC address of the office, standardized locally.
+    pub fn adr(&self) -> Address {
+        let mut adr = Address {
+            address1: format!("{} {} SOB", self.room, self.building),
+            ..Default::default()
+        };
+        std_sob(&mut adr);
+        adr
+    }
+
+    /// Replaces scraped DC addresses with the office address.
+    ///
+    /// Returns a note when a scraped DC address names another room or building.
+    /// Scraped DC addresses outside the office buildings aren't compared.
+    pub fn merge_dc(&self, adrs: Vec<Address>) -> (Vec<Address>, Option<String>) {
+        let dc = self.adr();
+        let mut mismatch = None;
+        let mut ret = vec![dc.clone()];
+        for adr in adrs {
+            if adr.state != "DC" {
+                ret.push(adr);
+                continue;
+            }
+            let sob = prs_sob(&adr.address1);
+            if sob.is_some() && sob != prs_sob(&dc.address1) {
+                mismatch = Some(format!(
+                    "scraped '{}', senate.gov '{}'",
+                    adr.address1, dc.address1
+                ));
+            }
+        }
+        order_addresses(&mut ret);
+        (ret, mismatch)
+    }
+}
+
+/// Merges the senate.gov DC office of a person into scraped addresses.
+///
+/// Without an office of the person, scraped DC addresses are kept, and a note is returned.
+pub fn merge_office(
+    per: &Person,
+    adrs: Vec<Address>,
+    offices: &[SenateOffice],
+) -> (Vec<Address>, Option<String>) {
+    let Some(office) = offices.iter().find(|office| office.is_person(per)) else {
+        let note = if adrs.iter().any(|adr| adr.state == "DC") {
+            "no senate.gov office, kept the scraped DC address"
+        } else {
+            "no senate.gov office or scraped DC address"
+        };
+        warn!("{}: {note}", per.name);
+        return (adrs, Some(note.into()));
+    };
+    office.merge_dc(adrs)
+}
+
+/// Fetches DC offices of all senators.
+pub async fn fetch_offices() -> Result<Vec<SenateOffice>> {
+    let xml = fetch_html(URL_CONTACT_XML, FetchOptions::for_source(Source::Senate)).await?;
+    prs_contact_xml(&xml)
+}
+
+/// Parses DC offices from the senate.gov contact information XML.
+///
+/// Addresses read like `141 Hart Senate Office Building Washington DC 20510`.
+pub fn prs_contact_xml(xml: &str) -> Result<Vec<SenateOffice>> {
+    let document = Html::parse_document(xml);
+    let member_sel = Selector::parse("member").expect("Invalid selector");
+    let txt = |elm: ElementRef, sel: &str| {
+        let selector = Selector::parse(sel).expect("Invalid selector");
+        elm.select(&selector)
+            .next()
+            .map(|elm| elm.text().collect::<String>().trim().to_string())
+            .unwrap_or_default()
+    };
+
+    let mut ret = Vec::with_capacity(CAP_PER);
+    for elm in document.select(&member_sel) {
+        let adr = txt(elm, "address").to_uppercase();
+        let mut tkns = adr.split_whitespace();
+        let room = tkns.next().unwrap_or_default();
+        let building = tkns.find(|tkn| ["HART", "DIRKSEN", "RUSSELL"].contains(tkn));
+        let (Some((room, _)), Some(building)) = (prs_sob(&format!("{room} HSOB")), building) else {
+            return Err(anyhow!(
+                "invalid senate.gov address '{adr}' of {}",
+                txt(elm, "member_full")
+            ));
+        };
+        ret.push(SenateOffice {
+            last_name: txt(elm, "last_name").to_uppercase(),
+            state: txt(elm, "state"),
+            website: url_host(&txt(elm, "website")),
+            room,
+            building: building.into(),
+        });
+    }
+
+    if ret.is_empty() {
+        return Err(anyhow!("no members in senate.gov contact information"));
+    }
+
+    Ok(ret)
+}
+
+/// Parses members from the senate.gov contact information XML.
+pub fn prs_members_xml(xml: &str) -> Result<Vec<Person>> {
+    let document = Html::parse_document(xml);
+    let member_sel = Selector::parse("member").expect("Invalid selector");
+    let txt = |elm: ElementRef, sel: &str| {
+        let selector = Selector::parse(sel).expect("Invalid selector");
+        elm.select(&selector)
+            .next()
+            .map(|elm| elm.text().collect::<String>().trim().to_string())
+            .unwrap_or_default()
+    };
+
+    let mut pers = Vec::with_capacity(CAP_PER);
+    for elm in document.select(&member_sel) {
+        let full_name = format!("{} {}", txt(elm, "first_name"), txt(elm, "last_name"));
+        let mut per = Person {
+            name: name_clean(&full_name),
+            url: txt(elm, "website")
+                .replace("www.", "")
+                .trim_end_matches('/')
+                .to_string(),
+            state: txt(elm, "state"),
+            ..Default::default()
+        };
+        set_senator_titles(&mut per);
+
+        // Validate fields.
+        validate_member(&per)?;
+
+        pers.push(per);
+    }
+
+    if pers.is_empty() {
+        return Err(anyhow!("no members in senate.gov contact information"));
+    }
+
+    Ok(pers)
+}
+
+/// Parses members of a state page.
+pub fn prs_members(html: &str, state: &str) -> Result<Vec<Person>> {
+    let document = Html::parse_document(html);
+
+    let mut pers = Vec::new();
+
+    // Select name and url.
+    let name_sel = Selector::parse("div.state-column").expect("Invalid selector");
+    let url_sel = Selector::parse("a").expect("Invalid selector");
+    for elm_doc in document.select(&name_sel) {
+        if let Some(elm_url) = elm_doc.select(&url_sel).next() {
+            let mut per = Person {
+                state: state.into(),
+                ..Default::default()
+            };
+            let full_name = elm_url.text().collect::<Vec<_>>().concat();
+            debug!("{}", full_name.trim());
+            per.name = name_clean(&full_name);
+            per.url = elm_url
+                .value()
+                .attr("href")
+                .unwrap_or_default()
+                .replace("www.", "")
+                .trim_end_matches('/')
+                .to_string();
+
+            set_senator_titles(&mut per);
+
+            // Validate fields.
+            validate_member(&per)?;
+
+            pers.push(per);
+        }
+    }
+
+    check_member_cnt(&pers, state)?;
+
+    Ok(pers)
+}
+
+/// Sets the titles of a senator by state, such as "State of Wisconsin".
+pub fn set_senator_titles(per: &mut Person) {
+    per.title1 = TITLE_SENATOR.into();
+    if let Some(name) = state_name(&per.state) {
+        per.title2 = format!("State of {name}");
+    }
+}
+
+/// Members of a state.
+pub fn members_of_state(pers: &[Person], state: &str) -> Result<Vec<Person>> {
+    let pers: Vec<Person> = pers
+        .iter()
+        .filter(|per| per.state == state)
+        .cloned()
+        .collect();
+    check_member_cnt(&pers, state)?;
+    Ok(pers)
+}
+
+/// Checks a state has one or two members.
+///
+/// One member is a vacancy; none is a scraper break.
+fn check_member_cnt(pers: &[Person], state: &str) -> Result<()> {
+    match pers.len() {
+        1 | 2 => Ok(()),
+        0 => Err(anyhow!("no senators for {state}")),
+        cnt => Err(anyhow!("{cnt} senators for {state}")),
+    }
+}
+
+/// Validates the name and url of a member.
+fn validate_member(per: &Person) -> Result<()> {
+    if per.name.is_empty() {
+        return Err(anyhow!("name is empty {:?}", per));
+    }
+    if per.url.is_empty() {
+        return Err(anyhow!("url is empty {:?}", per));
+    }
+    if !per.url.ends_with(".senate.gov") {
+        return Err(anyhow!("url doesn't end with '.senate.gov' {:?}", per));
+    }
+    Ok(())
+}
+
+/// Host of a url without `www.`, such as `baldwin.senate.gov`.
+fn url_host(url: &str) -> String {
+    let host = url.split("://").last().unwrap_or_default();
+    let host = host.split('/').next().unwrap_or_default();
+    host.trim_start_matches("www.").to_lowercase()
+}
+
 /// Fetch and parse addresses and standardize with the USPS.
-pub async fn fetch_prs_std_adrs(per: &Person, url: &str) -> Result<Option<Vec<Address>>> {
+pub async fn fetch_prs_std_adrs(
+    per: &Person,
+    url: &str,
+    pdf: &mut PdfFallback,
+) -> Result<Option<Vec<Address>>> {
     // Fetch html.
-    let html = fetch_html(url).await?;
-
-    // Parse html to address lines.
-    let adr_lnes_o = prs_adr_lnes(per, &html);
+    // A missing page, a 404 or 410, is a candidate url without addresses.
+    // Other failed fetches, such as a 403 block page, are errors.
+    let html = match fetch_html(url, FetchOptions::for_source(Source::Senate)).await {
+        Ok(html) => html,
+        Err(err) if is_page_missing(&err) => {
+            debug!("{err}");
+            return Ok(None);
+        }
+        Err(err) => return Err(err),
+    };
 
-    // Parse lines to addresses.
-    let adrs_o = match adr_lnes_o {
+    // Parse html to addresses.
+    // Without zips, offices may be in a linked PDF.
+    let adr_grps = prs_adr_grps(per, &html);
+    let mut adrs_res = prs_grps_adrs(&adr_grps);
+    if !adr_grps.iter().any(|grp| has_zip(grp)) {
+        let opts = FetchOptions::for_source(Source::Senate);
+        if let Some(txt) = pdf.fetch_txt(&html, url, opts).await {
+            adrs_res = prs_pdf_adrs(per, &txt);
+        }
+    }
+    let adrs_o = match adrs_or_next(url, adrs_res) {
         None => None,
-        Some(mut adr_lnes) => match PRSR.prs_adrs(&adr_lnes) {
-            None => None,
-            Some(mut adrs) => {
-                adrs = standardize_addresses(adrs).await?;
-                if adrs.len() < 2 {
-                    None
-                } else {
-                    Some(adrs)
-                }
+        Some(mut adrs) => {
+            adrs = standardize_addresses(adrs).await?;
+            if adrs.len() < 2 {
+                None
+            } else {
+                Some(adrs)
             }
-        },
+        }
     };
 
     Ok(adrs_o)
 }
 
+/// Parses addresses of a person from html, before standardization.
+pub fn prs_html_adrs(per: &Person, html: &str) -> Result<Vec<Address>, ParseError> {
+    prs_grps_adrs(&prs_adr_grps(per, html))
+}
+
+/// Parses addresses of line groups, one group at a time.
+///
+/// Groups without a zip are skipped. Groups failing to parse are skipped
+/// with a warning, and the first failure is returned when no group parses.
+pub fn prs_grps_adrs(grps: &[Vec<String>]) -> Result<Vec<Address>, ParseError> {
+    let mut adrs: Vec<Address> = Vec::new();
+    let mut err_fst: Option<ParseError> = None;
+    for grp in grps {
+        match PRSR.prs_adrs(grp) {
+            Ok(grp_adrs) => adrs.extend(grp_adrs),
+            Err(ParseError::NoZipFound) => {}
+            Err(err) => {
+                warn!("skipping address group {grp:?}: {err}");
+                err_fst.get_or_insert(err);
+            }
+        }
+    }
+    if adrs.is_empty() {
+        return Err(err_fst.unwrap_or(ParseError::NoZipFound));
+    }
+    adrs.sort_unstable();
+    Ok(dedup_addresses(adrs))
+}
+
+/// Address attribute names of list items, such as Marco Rubio's `data-addr`.
+///
+/// Each set is a street attribute and the attributes of the city line.
+const ATRS_ADR: [(&str, &[&str]); 2] = [
+    ("data-addr", &["data-city"]),
+    ("data-address", &["data-location", "data-state", "data-zip"]),
+];
+
+/// Extracts the address lines of a list item from its attributes.
+///
+/// Returns `None` without address attributes, or when the city line is
+/// missing so that one office's city isn't attached to another's street.
+pub fn prs_atr_lnes(elm: &ElementRef) -> Option<Vec<String>> {
+    let atr = |name: &str| {
+        elm.value()
+            .attr(name)
+            .unwrap_or_default()
+            .trim()
+            .trim_end_matches(',')
+            .to_uppercase()
+    };
+    let (atr_street, atrs_city) = ATRS_ADR
+        .iter()
+        .find(|(atr_street, _)| elm.value().attr(atr_street).is_some())?;
+    let street = atr(atr_street);
+    let mut city = atr(atrs_city[0]);
+    if street.is_empty() || city.is_empty() {
+        debug!("skipping incomplete address attributes: {street:?} {city:?}");
+        return None;
+    }
+
+    // City line, such as `MIAMI, FL 33166`.
+    let state_zip = atrs_city[1..]
+        .iter()
+        .map(|name| atr(name))
+        .filter(|val| !val.is_empty())
+        .collect::<Vec<String>>()
+        .join(" ");
+    if !state_zip.is_empty() {
+        city.push_str(", ");
+        city.push_str(&state_zip);
+    }
+    Some(vec![street, city])
+}
+
+/// Parses addresses of a person from the text of an office PDF, before standardization.
+pub fn prs_pdf_adrs(per: &Person, txt: &str) -> Result<Vec<Address>, ParseError> {
+    let mut adr_lnes = pdf_lnes(txt);
+    edit_adr_lnes(per, &mut adr_lnes);
+    PRSR.prs_adrs(&adr_lnes)
+}
+
+/// Extracts the address lines of a person from html, joined from the groups of `prs_adr_grps`.
 pub fn prs_adr_lnes(per: &Person, html: &str) -> Option<Vec<String>> {
+    Some(prs_adr_grps(per, html).concat())
+}
+
+/// Extracts the address lines of a person from html, one group per element.
+///
+/// Groups are parsed separately so that one office's city isn't attached to another's street.
+pub fn prs_adr_grps(per: &Person, html: &str) -> Vec<Vec<String>> {
     let document = Html::parse_document(html);
-    let mut lnes: Vec<String> = Vec::new();
+    let mut grps: Vec<Vec<String>> = Vec::new();
     for txt in [
         "li",
         "div.et_pb_blurb_description",
@@ -284,23 +730,11 @@ pub fn prs_adr_lnes(per: &Person, html: &str) -> Option<Vec<String>> {
 
             // Extract lines from html.
             if txt == "li" {
-                // For Marco Rubio
-                cur_lnes = vec![
-                    elm.value()
-                        .attr("data-addr")
-                        .unwrap_or_default()
-                        .trim()
-                        .trim_end_matches(',')
-                        .to_uppercase()
-                        .to_string(),
-                    elm.value()
-                        .attr("data-city")
-                        .unwrap_or_default()
-                        .trim()
-                        .trim_end_matches(',')
-                        .to_uppercase()
-                        .to_string(),
-                ];
+                // Only list items with address attributes, such as for Marco Rubio.
+                match prs_atr_lnes(&elm) {
+                    Some(atr_lnes) => cur_lnes = atr_lnes,
+                    None => continue,
+                }
             } else {
                 cur_lnes = elm
                     .text()
@@ -318,37 +752,42 @@ pub fn prs_adr_lnes(per: &Person, html: &str) -> Option<Vec<String>> {
                 .collect::<Vec<String>>();
 
             if !cur_lnes.is_empty() {
-                eprintln!("{cur_lnes:?}");
+                trace!("{cur_lnes:?}");
 
-                lnes.extend(cur_lnes);
+                edit_adr_lnes(per, &mut cur_lnes);
+                grps.push(cur_lnes);
             }
         }
 
-        if !lnes.is_empty() {
+        if !grps.is_empty() {
             break;
         }
     }
 
+    // Do not check for zip count here.
+
+    grps
+}
+
+/// Edits extracted address lines of a person to make them easier to parse.
+pub fn edit_adr_lnes(per: &Person, lnes: &mut Vec<String>) {
     // eprintln!("--- pre: {lnes:?}");
 
     // Edit lines to make it easier to parse.
-    edit_dot(&mut lnes);
-    edit_nbsp_zwsp(&mut lnes);
-    edit_mailing(&mut lnes);
-    edit_person_senate_lnes(per, &mut lnes);
-    PRSR.edit_lnes(&mut lnes);
-    edit_newline(&mut lnes);
-    edit_sob(&mut lnes);
-    edit_split_comma(&mut lnes);
-    edit_starting_hash(&mut lnes);
-    edit_char_half(&mut lnes);
-    edit_empty(&mut lnes);
-
-    eprintln!("--- --- --- post: {lnes:?}");
-
-    // Do not check for zip count here.
+    edit_dot(lnes);
+    edit_nbsp_zwsp(lnes);
+    PRSR.edit_counties(lnes);
+    edit_mailing(lnes);
+    edit_counted(lnes, |lnes| edit_person_senate_lnes(per, lnes));
+    PRSR.edit_lnes(lnes, Source::Senate);
+    edit_newline(lnes);
+    edit_sob(lnes);
+    edit_split_comma(lnes);
+    edit_starting_hash(lnes);
+    edit_char_half(lnes);
+    edit_empty(lnes);
 
-    Some(lnes)
+    trace!("--- --- --- post: {lnes:?}");
 }
 
 pub fn edit_person_senate_lnes(per: &Person, lnes: &mut Vec<String>) {
@@ -379,13 +818,6 @@ pub fn edit_person_senate_lnes(per: &Person, lnes: &mut Vec<String>) {
                 }
             }
         }
-        "Roger Marshall" => {
-            for idx in (0..lnes.len()).rev() {
-                if lnes[idx].contains("20002") {
-                    lnes[idx] = lnes[idx].replace("20002", "20510");
-                }
-            }
-        }
         "Benjamin L. Cardin" => {
             for idx in (0..lnes.len()).rev() {
                 if lnes[idx] == "TOWER 1, SUITE 1710" {
@@ -407,15 +839,6 @@ pub fn edit_person_senate_lnes(per: &Person, lnes: &mut Vec<String>) {
                 }
             }
         }
-        "Martin Heinrich" => {
-            // "709 HART SENATE OFFICE BUILDING WASHINGTON, D.C. 20510"
-            for idx in (0..lnes.len()).rev() {
-                if lnes[idx].starts_with("709 HART") {
-                    lnes[idx] = "709 HART SOB, WASHINGTON, DC 20510".into();
-                }
-            }
-        }
-
         "Charles E. Schumer" => {
             // "LEO O'BRIEN BUILDING, ROOM 827"
             for idx in (0..lnes.len()).rev() {
@@ -436,30 +859,6 @@ pub fn edit_person_senate_lnes(per: &Person, lnes: &mut Vec<String>) {
                 }
             }
         }
-        "Sheldon Whitehouse" => {
-            // "HART SENATE OFFICE BLDG., RM. 530"
-            for idx in (0..lnes.len()).rev() {
-                if lnes[idx].starts_with("HART SENATE") {
-                    lnes[idx] = "530 HART SOB".into();
-                }
-            }
-        }
-        "John Thune" => {
-            // "UNITED STATES SENATE SD-511"
-            for idx in (0..lnes.len()).rev() {
-                if lnes[idx] == "UNITED STATES SENATE SD-511" {
-                    lnes[idx] = "511 DIRKSEN SOB".into();
-                }
-            }
-        }
-        "Mike Rounds" => {
-            // "HART SENATE OFFICE BLDG., SUITE 716"
-            for idx in (0..lnes.len()).rev() {
-                if lnes[idx].starts_with("HART SENATE") {
-                    lnes[idx] = "716 HART SOB".into();
-                }
-            }
-        }
         "Marsha Blackburn" => {
             // "10 WEST M. L. KING BLVD"
             for idx in (0..lnes.len()).rev() {
@@ -473,8 +872,6 @@ pub fn edit_person_senate_lnes(per: &Person, lnes: &mut Vec<String>) {
             for idx in (0..lnes.len()).rev() {
                 if lnes[idx].starts_with("109 S") {
                     lnes[idx] = "109 S HIGHLAND AVE".into();
-                } else if lnes[idx] == "20002" {
-                    lnes[idx] = "20510".into();
                 }
             }
         }
@@ -483,16 +880,6 @@ pub fn edit_person_senate_lnes(per: &Person, lnes: &mut Vec<String>) {
             for idx in (0..lnes.len()).rev() {
                 if lnes[idx].starts_with("MICKEY LELAND FEDERAL") {
                     lnes[idx] = "1919 SMITH ST STE 9047".into();
-                } else if lnes[idx] == "167 RUSSELL" {
-                    lnes[idx].push_str(" SOB");
-                }
-            }
-        }
-        "Peter Welch" => {
-            // SR-124 RUSSELL
-            for idx in (0..lnes.len()).rev() {
-                if lnes[idx].starts_with("SR-124 RUSSELL") {
-                    lnes[idx] = lnes[idx][3..].into();
                 }
             }
         }
@@ -506,45 +893,384 @@ pub fn edit_person_senate_lnes(per: &Person, lnes: &mut Vec<String>) {
         }
         "Cynthia M. Lummis" => {
             for idx in (0..lnes.len()).rev() {
-                if lnes[idx].starts_with("RUSSELL SENATE") {
-                    // "RUSSELL SENATE OFFICE BUILDING SUITE SR-127A WASHINGTON, DC 20510"
-                    lnes[idx] = "127 RUSSELL SOB".into();
-                    lnes.insert(idx + 1, "WASHINGTON, DC 20510".into());
-                } else if lnes[idx].starts_with("FEDERAL CENTER") {
+                if lnes[idx].starts_with("FEDERAL CENTER") {
                     // "FEDERAL CENTER 2120 CAPITOL AVENUE SUITE 2007 CHEYENNE, WY 82001"
                     lnes[idx] = "2120 CAPITOL AVE STE 2007".into();
                     lnes.insert(idx + 1, "CHEYENNE, WY 82001".into());
                 }
             }
         }
-        "Jon Tester" => {
-            for idx in (0..lnes.len()).rev() {
-                if lnes[idx] == "SILVER BOW CENTER" {
-                    lnes.remove(idx);
-                }
-            }
-        }
-        "John Cornyn" => {
-            for idx in (0..lnes.len()).rev() {
-                if lnes[idx] == "WELLS FARGO CENTER" {
-                    lnes.remove(idx);
-                }
-            }
-        }
         "" => {}
         _ => {}
     }
 }
 
-#[derive(Debug, Serialize, Deserialize)]
-struct Location {
-    acf: LocationAcf,
-}
-#[derive(Debug, Serialize, Deserialize)]
-struct LocationAcf {
-    address: String,
-    suite: String,
-    city: String,
-    state: String,
-    zipcode: String,
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::*;
+
+    const HTML_COUNTIES: &str = include_str!("../tests/fixtures/senate/counties_listing.html");
+    const XML_CONTACT: &str = include_str!("../tests/fixtures/senate/contact_information.xml");
+    const XML_SENATORS_WI: &str = include_str!("../tests/fixtures/senate/senators_wi.xml");
+    const HTML_LI_DATA_ADDR: &str = include_str!("../tests/fixtures/senate/li_data_addr.html");
+    const HTML_LI_DATA_ADDRESS: &str =
+        include_str!("../tests/fixtures/senate/li_data_address.html");
+    const HTML_LI_PLAIN: &str = include_str!("../tests/fixtures/senate/li_plain.html");
+    const HTML_STATE_ONE: &str = include_str!("../tests/fixtures/senate/state_one.html");
+    const HTML_STATE_ZERO: &str = include_str!("../tests/fixtures/senate/state_zero.html");
+
+    fn prs_fixture(html: &str) -> Vec<Address> {
+        let per = Person {
+            name: "Test Senator".into(),
+            ..Default::default()
+        };
+        prs_html_adrs(&per, html).unwrap()
+    }
+
+    #[test]
+    fn test_prs_counties_listing() {
+        let adrs = prs_fixture(HTML_COUNTIES);
+        assert_eq!(adrs.len(), 3, "Failed to match: {:?}", adrs);
+
+        let huntsville = adrs.iter().find(|adr| adr.city == "HUNTSVILLE").unwrap();
+        assert_eq!(huntsville.address1, "200 CLINTON AVENUE WEST");
+        assert_eq!(huntsville.address2.as_deref(), Some("SUITE 802"));
+        assert_eq!(huntsville.counties, vec!["MADISON", "LIMESTONE", "JACKSON"]);
+
+        let mobile = adrs.iter().find(|adr| adr.city == "MOBILE").unwrap();
+        assert_eq!(mobile.address1, "41 WEST I-65 SERVICE ROAD NORTH");
+        assert_eq!(mobile.address2.as_deref(), Some("SUITE 2300-A"));
+        assert_eq!(mobile.counties, vec!["BALDWIN", "ESCAMBIA", "MOBILE"]);
+
+        let dc = adrs.iter().find(|adr| adr.state == "DC").unwrap();
+        assert_eq!(dc.address1, "455 DSOB");
+        assert!(dc.counties.is_empty());
+
+        // Addresses are unchanged without the counties lines.
+        let html = HTML_COUNTIES
+            .replace("<p>Serving Baldwin, Escambia, and Mobile Counties</p>", "")
+            .replace(
+                "<p>Counties served: Madison, Limestone &amp; Jackson County</p>",
+                "",
+            );
+        let mut adrs_cntys = adrs.clone();
+        for adr in adrs_cntys.iter_mut() {
+            adr.counties.clear();
+        }
+        assert_eq!(prs_fixture(&html), adrs_cntys);
+
+        // Query persons by county.
+        let pers = vec![Person {
+            name: "Test Senator".into(),
+            adrs: Some(adrs),
+            ..Default::default()
+        }];
+        assert_eq!(persons_serving_county(&pers, "Mobile County").len(), 1);
+        assert!(persons_serving_county(&pers, "Autauga").is_empty());
+    }
+
+    #[test]
+    fn test_prs_li_data_addr() {
+        let adrs = prs_fixture(HTML_LI_DATA_ADDR);
+        let cases = vec![
+            ("284 RSOB", None, "WASHINGTON", "DC"),
+            ("8669 NW 36TH STREET", Some("SUITE 110"), "DORAL", "FL"),
+            (
+                "201 SOUTH ORANGE AVENUE",
+                Some("SUITE 350"),
+                "ORLANDO",
+                "FL",
+            ),
+        ];
+        assert_eq!(adrs.len(), cases.len(), "Failed to match: {:?}", adrs);
+        for (address1, address2, city, state) in cases {
+            assert!(
+                adrs.iter().any(|adr| adr.address1 == address1
+                    && adr.address2.as_deref() == address2
+                    && adr.city == city
+                    && adr.state == state),
+                "Failed to match: {} {:?}",
+                address1,
+                adrs
+            );
+        }
+    }
+
+    #[test]
+    fn test_prs_li_data_address() {
+        let adrs = prs_fixture(HTML_LI_DATA_ADDRESS);
+        let cases = vec![
+            ("503 HSOB", None, "WASHINGTON", "DC"),
+            ("100 NORTH MAIN STREET", Some("SUITE 200"), "BOISE", "ID"),
+            (
+                "801 EAST SHERMAN STREET",
+                Some("ROOM 193"),
+                "POCATELLO",
+                "ID",
+            ),
+        ];
+        assert_eq!(adrs.len(), cases.len(), "Failed to match: {:?}", adrs);
+        for (address1, address2, city, state) in cases {
+            assert!(
+                adrs.iter().any(|adr| adr.address1 == address1
+                    && adr.address2.as_deref() == address2
+                    && adr.city == city
+                    && adr.state == state),
+                "Failed to match: {} {:?}",
+                address1,
+                adrs
+            );
+        }
+    }
+
+    #[test]
+    fn test_prs_li_plain() {
+        // List items without address attributes fall through to text extraction.
+        let document = Html::parse_document(HTML_LI_PLAIN);
+        let selector = Selector::parse("li").unwrap();
+        assert!(document
+            .select(&selector)
+            .all(|elm| prs_atr_lnes(&elm).is_none()));
+
+        let adrs = prs_fixture(HTML_LI_PLAIN);
+        assert_eq!(adrs.len(), 2, "Failed to match: {:?}", adrs);
+        let dc = adrs.iter().find(|adr| adr.state == "DC").unwrap();
+        assert_eq!(dc.address1, "502 HSOB");
+        let honolulu = adrs.iter().find(|adr| adr.city == "HONOLULU").unwrap();
+        assert_eq!(honolulu.address1, "300 ALA MOANA BOULEVARD");
+        assert_eq!(honolulu.address2.as_deref(), Some("ROOM 3-106"));
+    }
+
+    #[test]
+    fn test_prs_adr_grps() {
+        let per = Person::default();
+        // A city doesn't attach to the street of another element.
+        let html = r#"<div class="address">100 NORTH MAIN STREET</div>
+            <div class="address">BOISE, ID 83702</div>
+            <div class="address">503 HART SENATE OFFICE BUILDING<br>WASHINGTON, DC 20510</div>"#;
+        let grps = prs_adr_grps(&per, html);
+        assert_eq!(grps.len(), 3, "Failed to match: {:?}", grps);
+
+        // The city-only group is skipped, keeping the other offices.
+        let adrs = prs_grps_adrs(&grps).unwrap();
+        assert_eq!(adrs.len(), 1);
+        assert_eq!(adrs[0].state, "DC");
+
+        // Without another office, the group's failure is returned.
+        assert!(matches!(
+            prs_grps_adrs(&grps[..2]),
+            Err(ParseError::MissingAddress1 { .. })
+        ));
+    }
+
+    #[test]
+    fn test_prs_contact_xml() {
+        let offices = prs_contact_xml(XML_CONTACT).unwrap();
+        assert_eq!(offices.len(), 3);
+        assert_eq!(
+            offices[1],
+            SenateOffice {
+                last_name: "THUNE".into(),
+                state: "SD".into(),
+                website: "thune.senate.gov".into(),
+                room: "511".into(),
+                building: "DIRKSEN".into(),
+            }
+        );
+
+        // Synthesized addresses are locally standardized and round-trip.
+        let adrs: Vec<Address> = offices.iter().map(|office| office.adr()).collect();
+        assert_eq!(
+            adrs.iter()
+                .map(|adr| adr.address1.as_str())
+                .collect::<Vec<_>>(),
+            vec!["141 HSOB", "511 DSOB", "127A RSOB"]
+        );
+        for adr in adrs {
+            assert_eq!(
+                (adr.city.as_str(), adr.state.as_str()),
+                ("WASHINGTON", "DC")
+            );
+            assert_eq!(adr.zip5, ZIP_SOB);
+            let mut again = adr.clone();
+            assert!(std_sob(&mut again));
+            assert_eq!(again, adr);
+        }
+
+        // Persons match by website, or state and last name.
+        let per = Person {
+            name: "Tammy Baldwin".into(),
+            state: "WI".into(),
+            url: "https://baldwin.senate.gov".into(),
+            ..Default::default()
+        };
+        assert!(offices[0].is_person(&per));
+        assert!(!offices[1].is_person(&per));
+        let per = Person {
+            name: "Cynthia M. Lummis".into(),
+            state: "WY".into(),
+            ..Default::default()
+        };
+        assert!(offices[2].is_person(&per));
+
+        assert!(prs_contact_xml("<contact_information></contact_information>").is_err());
+        let xml = XML_CONTACT.replace("SD-511 Dirksen", "Dirksen");
+        assert!(prs_contact_xml(&xml).is_err());
+    }
+
+    #[test]
+    fn test_prs_members_vacancy() {
+        // A vacant seat leaves one member.
+        let pers = prs_members(HTML_STATE_ONE, "OH").unwrap();
+        assert_eq!(pers.len(), 1);
+        assert_eq!(pers[0].name, "Jon Husted");
+        assert_eq!(pers[0].url, "https://husted.senate.gov");
+        assert_eq!(pers[0].state, "OH");
+        assert_eq!(pers[0].title1, "United States Senator");
+        assert_eq!(pers[0].title2, "State of Ohio");
+
+        // No members is a scraper break.
+        let err = prs_members(HTML_STATE_ZERO, "OH").unwrap_err();
+        assert_eq!(err.to_string(), "no senators for OH");
+
+        // Vacancies of older files default to none.
+        let senate: Senate =
+            serde_json::from_str(r#"{"name": "U.S. Senate", "role": "Political", "persons": []}"#)
+                .unwrap();
+        assert!(senate.vacancies.is_empty());
+    }
+
+    #[test]
+    fn test_prs_members_xml() {
+        let pers = prs_members_xml(XML_SENATORS_WI).unwrap();
+        assert_eq!(pers.len(), 3);
+        assert_eq!(pers[0].name, "Tammy Baldwin");
+        assert_eq!(pers[0].url, "https://baldwin.senate.gov");
+        assert_eq!(pers[1].url, "https://ronjohnson.senate.gov");
+        assert_eq!(pers[2].state, "SD");
+        assert_eq!(pers[2].title1, TITLE_SENATOR);
+        assert_eq!(pers[2].title2, "State of South Dakota");
+
+        // A state falls back with one or two members.
+        let wi = members_of_state(&pers, "WI").unwrap();
+        assert_eq!(
+            wi.iter().map(|per| per.name.as_str()).collect::<Vec<_>>(),
+            vec!["Tammy Baldwin", "Ron Johnson"]
+        );
+        assert_eq!(members_of_state(&pers, "SD").unwrap().len(), 1);
+        assert!(members_of_state(&pers, "WY").is_err());
+
+        // Urls are validated like scraped members.
+        let xml = XML_SENATORS_WI.replace("www.thune.senate.gov", "thune.example.com");
+        assert!(prs_members_xml(&xml).is_err());
+        assert!(prs_members_xml("<contact_information></contact_information>").is_err());
+    }
+
+    #[test]
+    fn test_merge_dc_mismatch() {
+        let offices = prs_contact_xml(XML_CONTACT).unwrap();
+        let thune = &offices[1];
+        let district = Address {
+            address1: "320 N MAIN AVE STE B".into(),
+            city: "SIOUX FALLS".into(),
+            state: "SD".into(),
+            zip5: 57104,
+            ..Default::default()
+        };
+        let dc = |address1: &str| Address {
+            address1: address1.into(),
+            city: "WASHINGTON".into(),
+            state: "DC".into(),
+            zip5: ZIP_SOB,
+            ..Default::default()
+        };
+
+        // A matching scraped DC address is replaced without a flag.
+        let (adrs, mismatch) = thune.merge_dc(vec![district.clone(), dc("511 DIRKSEN SOB")]);
+        assert_eq!(adrs, vec![dc("511 DSOB"), district.clone()]);
+        assert!(mismatch.is_none());
+
+        // A scraped DC address of another room is flagged and replaced.
+        let (adrs, mismatch) = thune.merge_dc(vec![dc("SD-512 DIRKSEN SOB"), district.clone()]);
+        assert_eq!(adrs, vec![dc("511 DSOB"), district.clone()]);
+        assert_eq!(
+            mismatch.as_deref(),
+            Some("scraped 'SD-512 DIRKSEN SOB', senate.gov '511 DSOB'")
+        );
+
+        // Without a scraped DC address the office is added.
+        let (adrs, mismatch) = thune.merge_dc(vec![district.clone()]);
+        assert_eq!(adrs, vec![dc("511 DSOB"), district.clone()]);
+        assert!(mismatch.is_none());
+
+        // Without an office of the person, the scraped DC address is kept and noted.
+        let per = Person {
+            name: "Jane Doe".into(),
+            state: "SD".into(),
+            url: "https://doe.senate.gov".into(),
+            ..Default::default()
+        };
+        let scraped = vec![dc("SD-512 DIRKSEN SOB"), district.clone()];
+        let (adrs, note) = merge_office(&per, scraped.clone(), &offices);
+        assert_eq!(adrs, scraped);
+        assert_eq!(
+            note.as_deref(),
+            Some("no senate.gov office, kept the scraped DC address")
+        );
+        let (adrs, note) = merge_office(&per, vec![district.clone()], &offices);
+        assert_eq!(adrs, vec![district]);
+        assert_eq!(
+            note.as_deref(),
+            Some("no senate.gov office or scraped DC address")
+        );
+    }
+
+    #[test]
+    fn test_fixture_tuberville() {
+        assert_eq!(
+            fixture_lnes("Tommy Tuberville", "tests/fixtures/senate/tuberville.html"),
+            vec![
+                "200 CLINTON AVENUE NW,SUITE 802,HUNTSVILLE,AL,35801,0,",
+                "41 W I-65 SERVICE RD N STE 2300-A,,MOBILE,AL,36608,0,",
+                "455 RSOB,,WASHINGTON,DC,20510,0,",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_fixture_ernst() {
+        assert_eq!(
+            fixture_lnes("Joni Ernst", "tests/fixtures/senate/ernst.html"),
+            vec![
+                "111 7TH AVENUE SE,SUITE 480,CEDAR RAPIDS,IA,52401,0,",
+                "2146 27TH AVE,SUITE 550,COUNCIL BLUFFS,IA,51501,0,",
+                "730 HSOB,,WASHINGTON,DC,20510,0,",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_fixture_cramer() {
+        assert_eq!(
+            fixture_lnes("Kevin Cramer", "tests/fixtures/senate/cramer.html"),
+            vec![
+                "220 EAST ROSSER AVENUE RM 328,,BISMARCK,ND,58501,0,",
+                "313 HSOB,,WASHINGTON,DC,20510,0,",
+                "3217 FIECHTNER DRIVE,SUITE D,FARGO,ND,58103,0,",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_fixture_lummis() {
+        assert_eq!(
+            fixture_lnes("Cynthia M. Lummis", "tests/fixtures/senate/lummis.html"),
+            vec![
+                "100 EAST B STREET,SUITE 3201,CASPER,WY,82601,0,",
+                "127A RSOB,,WASHINGTON,DC,20510,0,",
+                "2120 CAPITOL AVE STE 2007,,CHEYENNE,WY,82001,0,",
+            ]
+        );
+    }
 }
@@ -1,17 +1,34 @@
-use crate::core::*;
+use crate::io::*;
+use crate::manual::*;
 use crate::models::*;
 use crate::prsr::*;
+use crate::report::*;
 use crate::usps::*;
 use anyhow::{anyhow, Result};
-use reqwest::Client;
 use scraper::{Html, Selector};
 use serde::{Deserialize, Serialize};
-use std::fs::File;
-use std::io::{BufReader, BufWriter};
-use std::ops::Add;
-use std::path::Path;
+use std::collections::BTreeMap;
+use std::time::Instant;
+use tracing::{info, trace, warn};
+
+pub const FLE_PTH: &str = "state.json";
+
+/// Governor address overrides by state slug, such as
+/// `{"indiana": {"rewrites": [{"from": "STATEHOUSE", "to": "200 W WASHINGTON ST"}]}}`.
+pub const OVERRIDES_PTH: &str = "state_overrides.json";
+
+lazy_static! {
+    /// Governor address overrides, read once.
+    ///
+    /// `State::fetch_adrs` reads them first, so a malformed file fails the run before use.
+    static ref STATE_OVERRIDES: Result<StateOverrides, String> =
+        StateOverrides::load(OVERRIDES_PTH).map_err(|err| err.to_string());
+}
 
-const FLE_PTH: &str = "state.json";
+/// Governor address overrides of the run, erroring on a malformed file.
+pub fn state_overrides() -> Result<&'static StateOverrides> {
+    STATE_OVERRIDES.as_ref().map_err(|err| anyhow!("{err}"))
+}
 
 #[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Eq, PartialOrd, Ord)]
 pub struct State {
@@ -20,6 +37,12 @@ pub struct State {
     pub persons: Vec<Person>,
 }
 
+impl Default for State {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 impl State {
     pub fn new() -> Self {
         // In the United States, there are a total of 55 governors. This includes: 50 state governors (one for each of the 50 states). 5 territorial governors for the following U.S. territories: American Samoa, Guam, Northern Mariana Islands, Puerto Rico, U.S. Virgin Islands.
@@ -31,9 +54,21 @@ impl State {
     }
 
     pub async fn load() -> Result<State> {
+        REPORT.lock().unwrap().begin(Source::State);
+
         // Read file from disk.
         let mut state = match read_from_file::<State>(FLE_PTH) {
-            Ok(state_from_disk) => state_from_disk,
+            Ok(mut state_from_disk) => {
+                // Backfill states of older files.
+                for (per, state_name) in state_from_disk.persons.iter_mut().zip(state_names()) {
+                    if per.state.is_empty() {
+                        per.state = state_abbr(state_name).unwrap_or_default().into();
+                    }
+                }
+                // Backfill roles of older files.
+                set_roles(&mut state_from_disk.persons, &state_from_disk.role);
+                state_from_disk
+            }
             Err(_) => {
                 let mut state = State::new();
 
@@ -42,6 +77,7 @@ impl State {
                     let per = state.fetch_member(state_name).await?;
                     state.persons.push(per);
                 }
+                set_roles(&mut state.persons, &state.role);
 
                 // Write file to disk.
                 write_to_file(&state, FLE_PTH)?;
@@ -50,7 +86,7 @@ impl State {
             }
         };
 
-        println!("{} governors", state.persons.len());
+        info!("{} governors", state.persons.len());
 
         // Fetch addresses.
         state.fetch_adrs().await?;
@@ -61,9 +97,12 @@ impl State {
     /// Fetch member from network.
     pub async fn fetch_member(&self, state_name: &str) -> Result<Person> {
         let url = format!("https://www.nga.org/governors/{state_name}/");
-        let html = fetch_html(&url).await?;
+        let html = fetch_html(&url, FetchOptions::for_source(Source::State)).await?;
         let document = Html::parse_document(&html);
-        let mut per = Person::default();
+        let mut per = Person {
+            state: state_abbr(state_name).unwrap_or_default().into(),
+            ..Default::default()
+        };
 
         // Select name.
         let name_sel = Selector::parse("h1.title").expect("Invalid selector");
@@ -97,9 +136,14 @@ impl State {
 
     pub async fn fetch_adrs(&mut self) -> Result<()> {
         // Clone self for file writing.
-        let mut self_clone = self.clone();
-        let per_len = self.persons.len() as f64;
+        let self_clone = self.clone();
+        let manual = ManualAddresses::load(MANUAL_PTH)?;
+        manual.validate_summary(Source::State, &self.persons);
+        state_overrides()?.validate_summary();
         let state_names = state_names();
+        let mut ckpt = Checkpoint::new(CheckpointPolicy::load(FLE_PTH_CHECKPOINT)?, Instant::now());
+        let todo = self.persons.iter().filter(|per| per.adrs.is_none()).count();
+        let mut progress = Progress::new(Some(todo));
 
         for (idx, per) in self_clone
             .persons
@@ -108,51 +152,93 @@ impl State {
             .filter(|(_, per)| per.adrs.is_none())
         // .take(1)
         {
-            let mut state = state_names[idx];
-            if state == "virgin-islands" {
-                state = "u-s-virgin-islands";
-            }
-            let mut url = format!("https://www.usa.gov/states/{}", state);
+            let state = state_names[idx];
+            let url_state = match state {
+                "virgin-islands" => "u-s-virgin-islands",
+                _ => state,
+            };
+            let mut url = format!("https://www.usa.gov/states/{}", url_state);
             if state == "guam" {
                 url.clone_from(&per.url);
             }
 
-            let pct = (((idx as f64 + 1.0) / per_len) * 100.0) as u8;
-            eprintln!("  {}% {} {} {}", pct, idx, state, url);
-
-            if state == "new-york" {
-                let adr = Address {
-                    address1: "NYS STATE CAPITOL BUILDING".into(),
-                    city: "ALBANY".into(),
-                    state: "NY".into(),
-                    zip5: 12224,
-                    delivery_point: None,
-                    ..Default::default()
-                };
-                self.persons[idx].adrs = Some(vec![adr]);
-            } else if state == "american-samoa" {
-                let adr = Address {
-                    address1: "OFFICE OF THE GOVERNOR".into(),
-                    city: "PAGO PAGO".into(),
-                    state: "AS".into(),
-                    zip5: 96799,
-                    delivery_point: None,
-                    ..Default::default()
-                };
-                self.persons[idx].adrs = Some(vec![adr]);
-            } else {
-                // Fetch, parse, standardize.
-                match fetch_prs_std_adrs(state, &url).await? {
-                    None => {}
-                    Some(mut adrs) => {
-                        self.persons[idx].adrs = Some(adrs);
-                    }
-                }
+            progress.tick(&format!("{} {} {}", idx, state, url));
+
+            // Flush completed persons before returning an error.
+            if let Err(err) = self.fetch_adrs_per(idx, per, state, &url, &manual).await {
+                ckpt.flush(&self, FLE_PTH, Instant::now())?;
+                return Err(err);
             }
 
             // Checkpoint save.
             // Write intermediate file to disk.
-            write_to_file(&self, FLE_PTH)?;
+            ckpt.done(&self, FLE_PTH, Instant::now())?;
+        }
+        ckpt.flush(&self, FLE_PTH, Instant::now())?;
+
+        Ok(())
+    }
+
+    /// Fetches addresses of a person of a state.
+    async fn fetch_adrs_per(
+        &mut self,
+        idx: usize,
+        per: &Person,
+        state: &str,
+        url: &str,
+        manual: &ManualAddresses,
+    ) -> Result<()> {
+        // Manual addresses bypass scraping.
+        let entry = manual.get(Source::State, &per.name);
+        if let Some(entry) = entry.filter(|entry| entry.skip_scrape) {
+            self.persons[idx].adrs = Some(entry.std_adrs().await?);
+            self.persons[idx].provenance = Some(Provenance::new(Source::State, MANUAL_PTH));
+            return Ok(());
+        }
+
+        // Override addresses bypass scraping, and are used verbatim.
+        if let Some(adr) = state_overrides()?.address(state) {
+            self.persons[idx].adrs = Some(vec![adr.clone()]);
+            self.persons[idx].provenance = Some(Provenance::new(Source::State, OVERRIDES_PTH));
+        } else if state == "new-york" {
+            let adr = Address {
+                address1: "NYS STATE CAPITOL BUILDING".into(),
+                city: "ALBANY".into(),
+                state: "NY".into(),
+                zip5: 12224,
+                delivery_point: None,
+                ..Default::default()
+            };
+            self.persons[idx].adrs = Some(vec![adr]);
+            self.persons[idx].provenance = Some(Provenance::new(Source::State, ""));
+        } else if state == "american-samoa" {
+            let adr = Address {
+                address1: "OFFICE OF THE GOVERNOR".into(),
+                city: "PAGO PAGO".into(),
+                state: "AS".into(),
+                zip5: 96799,
+                delivery_point: None,
+                ..Default::default()
+            };
+            self.persons[idx].adrs = Some(vec![adr]);
+            self.persons[idx].provenance = Some(Provenance::new(Source::State, ""));
+        } else {
+            // Fetch, parse, standardize.
+            match fetch_prs_std_adrs(state, url).await? {
+                None => {}
+                Some(adrs) => {
+                    self.persons[idx].adrs = Some(adrs);
+                    self.persons[idx].provenance = Some(Provenance::new(Source::State, url));
+                }
+            }
+        }
+
+        // Manual addresses extend scraped addresses.
+        if let Some(entry) = entry {
+            let adrs = self.persons[idx].adrs.take();
+            self.persons[idx].adrs = Some(entry.append_to(adrs).await?);
+            let provenance = &mut self.persons[idx].provenance;
+            provenance.get_or_insert_with(|| Provenance::new(Source::State, MANUAL_PTH));
         }
 
         Ok(())
@@ -162,25 +248,23 @@ impl State {
 /// Fetch and parse addresses and standardize with the USPS.
 pub async fn fetch_prs_std_adrs(state: &str, url: &str) -> Result<Option<Vec<Address>>> {
     // Fetch html.
-    let html = fetch_html(url).await?;
+    let html = fetch_html(url, FetchOptions::for_source(Source::State)).await?;
 
     // Parse html to address lines.
     let adr_lnes_o = prs_adr_lnes(state, &html);
 
     // Parse lines to addresses.
-    let adrs_o = match adr_lnes_o {
+    let adrs_o = adr_lnes_o.and_then(|adr_lnes| adrs_or_next(url, PRSR.prs_adrs(&adr_lnes)));
+    let adrs_o = match adrs_o {
         None => None,
-        Some(mut adr_lnes) => match PRSR.prs_adrs(&adr_lnes) {
-            None => None,
-            Some(mut adrs) => {
-                adrs = standardize_addresses(adrs).await?;
-                if adrs.is_empty() {
-                    None
-                } else {
-                    Some(adrs)
-                }
+        Some(mut adrs) => {
+            adrs = standardize_addresses(adrs).await?;
+            if adrs.is_empty() {
+                None
+            } else {
+                Some(adrs)
             }
-        },
+        }
     };
 
     Ok(adrs_o)
@@ -206,7 +290,7 @@ pub fn prs_adr_lnes(state: &str, html: &str) -> Option<Vec<String>> {
                 .filter(|s| PRSR.filter(s))
                 .collect::<Vec<String>>();
 
-            eprintln!("{cur_lnes:?}");
+            trace!("{cur_lnes:?}");
 
             lnes.extend(cur_lnes);
         }
@@ -222,15 +306,15 @@ pub fn prs_adr_lnes(state: &str, html: &str) -> Option<Vec<String>> {
     edit_dot(&mut lnes);
     edit_nbsp_zwsp(&mut lnes);
     edit_mailing(&mut lnes);
-    edit_person_state_lnes(state, &mut lnes);
-    PRSR.edit_lnes(&mut lnes);
+    edit_counted(&mut lnes, |lnes| edit_person_state_lnes(state, lnes));
+    PRSR.edit_lnes(&mut lnes, Source::State);
     edit_newline(&mut lnes);
     edit_split_comma(&mut lnes);
     edit_starting_hash(&mut lnes);
     edit_char_half(&mut lnes);
     edit_empty(&mut lnes);
 
-    eprintln!("--- --- --- post: {lnes:?}");
+    trace!("--- --- --- post: {lnes:?}");
 
     // Do not check for zip count here.
 
@@ -274,7 +358,7 @@ pub fn edit_person_state_lnes(state: &str, lnes: &mut [String]) {
                 }
             }
         }
-        "u-s-virgin-islands" => {
+        "virgin-islands" => {
             for idx in (0..lnes.len()).rev() {
                 if lnes[idx].contains("(21-22)") {
                     lnes[idx] = lnes[idx].replace("(21-22)", "");
@@ -284,9 +368,99 @@ pub fn edit_person_state_lnes(state: &str, lnes: &mut [String]) {
         "" => {}
         _ => {}
     }
+
+    // File-driven rewrites follow the built-in ones.
+    // A malformed file already failed `State::fetch_adrs`.
+    if let Ok(overrides) = state_overrides() {
+        overrides.edit_lnes(state, lnes);
+    }
 }
 
-fn state_names() -> Vec<&'static str> {
+/// An override of the governor address of a state.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum StateOverride {
+    /// A complete address, bypassing scraping, read as an object or a single line such as
+    /// `{"address": "109 STATE ST, PAVILION BLDG, MONTPELIER, VT 05609"}`, written as an object.
+    Address(#[serde(deserialize_with = "de_adr")] Address),
+    /// Rewrites of scraped address lines.
+    Rewrites(Vec<LneRewrite>),
+}
+
+/// A rewrite of the part of an address line matching `from`.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Eq)]
+pub struct LneRewrite {
+    pub from: String,
+    pub to: String,
+}
+
+/// Governor address overrides by state slug of `state_names`.
+#[derive(Debug, Default, Clone)]
+pub struct StateOverrides {
+    pub overrides: BTreeMap<String, StateOverride>,
+}
+
+impl StateOverrides {
+    /// Reads overrides from a file; a missing file has no overrides.
+    pub fn load(pth: &str) -> Result<StateOverrides> {
+        if !data_pth(pth).exists() {
+            return Ok(StateOverrides::default());
+        }
+        let overrides =
+            read_from_file(pth).map_err(|err| anyhow!("invalid state overrides '{pth}': {err}"))?;
+        Ok(StateOverrides { overrides })
+    }
+
+    /// Complete address of a state, if overridden.
+    pub fn address(&self, state: &str) -> Option<&Address> {
+        match self.overrides.get(state) {
+            Some(StateOverride::Address(adr)) => Some(adr),
+            _ => None,
+        }
+    }
+
+    /// Rewrites address lines of a state.
+    pub fn edit_lnes(&self, state: &str, lnes: &mut [String]) {
+        let Some(StateOverride::Rewrites(rewrites)) = self.overrides.get(state) else {
+            return;
+        };
+        for lne in lnes.iter_mut() {
+            for rewrite in rewrites.iter() {
+                if lne.contains(&rewrite.from) {
+                    *lne = lne.replace(&rewrite.from, &rewrite.to);
+                }
+            }
+        }
+    }
+
+    /// Returns warnings for slugs naming no state.
+    pub fn validate(&self) -> Vec<String> {
+        let state_names = state_names();
+        self.overrides
+            .keys()
+            .filter(|slug| !state_names.contains(&slug.as_str()))
+            .map(|slug| {
+                format!(
+                    "unknown state slug '{slug}', valid slugs: {}",
+                    state_names.join(", ")
+                )
+            })
+            .collect()
+    }
+
+    /// Validates slugs, adding warnings to the run summary.
+    pub fn validate_summary(&self) {
+        let lnes = self.validate();
+        if !lnes.is_empty() {
+            for lne in lnes.iter() {
+                warn!("state overrides: {lne}");
+            }
+            summary_add("State overrides", lnes, true);
+        }
+    }
+}
+
+pub fn state_names() -> Vec<&'static str> {
     vec![
         "alabama",
         "alaska",
@@ -345,3 +519,92 @@ fn state_names() -> Vec<&'static str> {
         "virgin-islands",
     ]
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::*;
+    use std::fs;
+
+    #[test]
+    fn test_state_overrides() {
+        let dir = TempDir::new("state");
+        let pth = dir.pth(OVERRIDES_PTH);
+        fs::write(
+            &pth,
+            r#"{
+                "vermont": {"address": "109 STATE ST, PAVILION BLDG, MONTPELIER, VT 05609"},
+                "maine": {"address": {
+                    "address1": "1 STATE HOUSE STA", "city": "AUGUSTA",
+                    "state": "ME", "zip5": 4333, "zip4": 1, "delivery_point": null
+                }},
+                "indiana": {"rewrites": [
+                    {"from": "STATEHOUSE", "to": "200 W WASHINGTON ST STE 206"},
+                    {"from": " (ROOM 206)", "to": ""}
+                ]},
+                "atlantis": {"rewrites": []}
+            }"#,
+        )
+        .unwrap();
+        let overrides = StateOverrides::load(&pth).unwrap();
+
+        // A full-address override.
+        let adr = overrides.address("vermont").unwrap();
+        assert_eq!(adr.address1, "109 STATE ST");
+        assert_eq!(adr.address2.as_deref(), Some("PAVILION BLDG"));
+        assert_eq!(adr.zip5, 5609);
+        assert_eq!(overrides.address("maine").unwrap().zip4, 1);
+        // Written back as an object, keeping fields a single line lacks.
+        let written = serde_json::to_value(&overrides.overrides["vermont"]).unwrap();
+        assert_eq!(written["address"]["address2"], "PAVILION BLDG");
+        assert_eq!(written["address"]["zip5"], 5609);
+        assert!(written["address"].get("delivery_point").is_some());
+        assert!(overrides.address("indiana").is_none());
+        assert!(overrides.address("ohio").is_none());
+
+        // A rewrite override.
+        let mut lnes = vec![
+            "OFFICE OF THE GOVERNOR".to_string(),
+            "STATEHOUSE (ROOM 206)".to_string(),
+            "INDIANAPOLIS, IN 46204".to_string(),
+        ];
+        overrides.edit_lnes("indiana", &mut lnes);
+        assert_eq!(
+            lnes,
+            vec![
+                "OFFICE OF THE GOVERNOR",
+                "200 W WASHINGTON ST STE 206",
+                "INDIANAPOLIS, IN 46204"
+            ]
+        );
+        let mut ohio = vec!["STATEHOUSE".to_string()];
+        overrides.edit_lnes("ohio", &mut ohio);
+        assert_eq!(ohio, vec!["STATEHOUSE"]);
+
+        // Unknown slugs list valid slugs.
+        let lnes = overrides.validate();
+        assert_eq!(lnes.len(), 1);
+        assert!(lnes[0].starts_with("unknown state slug 'atlantis'"));
+        assert!(lnes[0].contains("new-york"));
+
+        // A malformed file is an error.
+        fs::write(&pth, r#"{"vermont": {"address": "109 STATE ST"}}"#).unwrap();
+        let err = StateOverrides::load(&pth).unwrap_err();
+        assert!(
+            err.to_string().starts_with("invalid state overrides"),
+            "{err}"
+        );
+
+        // A relative path is read under the data directory.
+        let _lock = data_dir_lock();
+        std::env::set_var(DATA_DIR_ENV, &*dir);
+        let res = StateOverrides::load(OVERRIDES_PTH);
+        std::env::remove_var(DATA_DIR_ENV);
+        assert!(res.is_err());
+
+        assert!(StateOverrides::load("missing_state_overrides.json")
+            .unwrap()
+            .overrides
+            .is_empty());
+    }
+}
@@ -0,0 +1,160 @@
+use crate::io::*;
+use crate::models::*;
+use crate::prsr::*;
+use anyhow::{anyhow, Result};
+use serde::{Deserialize, Serialize};
+
+/// Persons not to mail, such as `[{"name": "Jane Doe", "zip5s": [20510]}]`.
+pub const SUPPRESSION_PTH: &str = "suppression.json";
+
+/// A person not to mail.
+#[derive(Debug, Default, Serialize, Deserialize, Clone, PartialEq, Eq)]
+pub struct Suppression {
+    pub name: String,
+    /// Zip5s of suppressed addresses; empty suppresses every address.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub zip5s: Vec<u32>,
+}
+
+/// Persons not to mail, kept outside the refetched person files.
+#[derive(Debug, Default, Clone)]
+pub struct SuppressionList {
+    pub suppressions: Vec<Suppression>,
+}
+
+impl SuppressionList {
+    /// Reads suppressions from a file; a missing file has no suppressions.
+    pub fn load(pth: &str) -> Result<SuppressionList> {
+        if !data_pth(pth).exists() {
+            return Ok(SuppressionList::default());
+        }
+        let suppressions: Vec<Suppression> = read_from_file(pth)
+            .map_err(|err| anyhow!("invalid suppression list '{pth}': {err}"))?;
+        Ok(SuppressionList { suppressions })
+    }
+
+    /// Writes suppressions to a file.
+    pub fn save(&self, pth: &str) -> Result<()> {
+        write_to_file(&self.suppressions, pth)
+    }
+
+    /// Adds a suppression, merging zip5s of a suppressed name.
+    ///
+    /// Returns false when already suppressed.
+    pub fn add(&mut self, sup: &Suppression) -> bool {
+        let key = suppression_key(&sup.name);
+        let Some(cur) = self
+            .suppressions
+            .iter_mut()
+            .find(|cur| suppression_key(&cur.name) == key)
+        else {
+            self.suppressions.push(sup.clone());
+            return true;
+        };
+        if cur.zip5s.is_empty() {
+            return false;
+        }
+        if sup.zip5s.is_empty() {
+            cur.zip5s.clear();
+            return true;
+        }
+        let cnt = cur.zip5s.len();
+        for zip5 in sup.zip5s.iter() {
+            if !cur.zip5s.contains(zip5) {
+                cur.zip5s.push(*zip5);
+            }
+        }
+        cur.zip5s.len() != cnt
+    }
+
+    /// Whether the address of a person is suppressed.
+    ///
+    /// Names match case-insensitively after name_clean.
+    pub fn is_suppressed(&self, name: &str, adr: &Address) -> bool {
+        let name = suppression_key(name);
+        self.suppressions.iter().any(|sup| {
+            suppression_key(&sup.name) == name
+                && (sup.zip5s.is_empty() || sup.zip5s.contains(&adr.zip5))
+        })
+    }
+}
+
+/// Normalized name of a suppression.
+fn suppression_key(name: &str) -> String {
+    name_clean(name).to_lowercase()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_is_suppressed() {
+        let list = SuppressionList {
+            suppressions: vec![
+                Suppression {
+                    name: "Jane Doe".into(),
+                    zip5s: vec![],
+                },
+                Suppression {
+                    name: "John Public".into(),
+                    zip5s: vec![20510],
+                },
+            ],
+        };
+        let adr = |zip5: u32| Address {
+            zip5,
+            ..Default::default()
+        };
+        let cases = [
+            // Exact match.
+            ("Jane Doe", 20515, true),
+            // Case mismatch.
+            ("JANE DOE", 20515, true),
+            ("jane  doe", 20002, true),
+            ("Jane Roe", 20515, false),
+            // Zip-qualified.
+            ("John Public", 20510, true),
+            ("JOHN PUBLIC", 20510, true),
+            ("John Public", 99501, false),
+        ];
+        for (name, zip5, expected) in cases {
+            assert_eq!(
+                list.is_suppressed(name, &adr(zip5)),
+                expected,
+                "Failed to match: {}",
+                name
+            );
+        }
+
+        assert!(SuppressionList::load("missing_suppression.json")
+            .unwrap()
+            .suppressions
+            .is_empty());
+    }
+
+    #[test]
+    fn test_add() {
+        let sup = |name: &str, zip5s: Vec<u32>| Suppression {
+            name: name.into(),
+            zip5s,
+        };
+        let mut list = SuppressionList::default();
+        assert!(list.add(&sup("Jane Doe", vec![20510])));
+        assert!(!list.add(&sup("jane doe", vec![20510])));
+        assert!(list.add(&sup("Jane Doe", vec![20515])));
+        assert!(list.add(&sup("John Public", vec![])));
+        assert!(!list.add(&sup("John Public", vec![20510])));
+        assert_eq!(
+            list.suppressions,
+            vec![
+                sup("Jane Doe", vec![20510, 20515]),
+                sup("John Public", vec![])
+            ]
+        );
+
+        // Every address of a name supersedes its zip5s.
+        assert!(list.add(&sup("Jane Doe", vec![])));
+        assert!(list.suppressions[0].zip5s.is_empty());
+    }
+}
@@ -0,0 +1,111 @@
+use crate::models::*;
+use lopdf::content::Content;
+use lopdf::Document;
+use std::fs;
+use std::ops::Deref;
+use std::path::{Path, PathBuf};
+use std::sync::{Mutex, MutexGuard};
+
+/// A text TTF of the font embedding tests, DejaVu Sans.
+///
+/// Not the IMb font, which has only barcode glyphs.
+pub const TEXT_FONT_PTH: &str = "tests/fixtures/fonts/DejaVuSans.ttf";
+
+/// Lock of tests resolving data files against `ADR_DATA_DIR`.
+static DATA_DIR_LOCK: Mutex<()> = Mutex::new(());
+
+/// Serializes tests which set `ADR_DATA_DIR` or read data files by relative path.
+pub fn data_dir_lock() -> MutexGuard<'static, ()> {
+    DATA_DIR_LOCK.lock().unwrap_or_else(|err| err.into_inner())
+}
+
+/// Parses addresses of a person from an html fixture, stopping before USPS standardization.
+///
+/// The fixture directory is the source, such as `tests/fixtures/senate/ernst.html`.
+pub fn parse_fixture(name: &str, pth: &str) -> Vec<Address> {
+    let pth = Path::new(pth);
+    let source: Source = pth
+        .parent()
+        .and_then(|dir| dir.file_name())
+        .and_then(|dir| dir.to_str())
+        .unwrap_or_default()
+        .parse()
+        .unwrap();
+    let html = fs::read_to_string(pth).unwrap();
+    let per = Person {
+        name: name.into(),
+        ..Default::default()
+    };
+    let adrs = match source {
+        Source::Senate => crate::senate::prs_html_adrs(&per, &html),
+        Source::House => crate::house::prs_html_adrs(&per, &html),
+        _ => panic!("no fixture parser for source '{source}'"),
+    };
+    adrs.unwrap_or_else(|err| panic!("fixture {pth:?} does not parse: {err}"))
+}
+
+/// Parsed addresses of a fixture as display lines.
+pub fn fixture_lnes(name: &str, pth: &str) -> Vec<String> {
+    parse_fixture(name, pth)
+        .iter()
+        .map(|adr| adr.to_string())
+        .collect()
+}
+
+/// Text operands of the `Tj` operators of a page's content streams.
+pub fn page_txts(doc: &Document, pg_idx: usize) -> Vec<String> {
+    let pg_id = *doc.get_pages().values().nth(pg_idx).unwrap();
+    let content = Content::decode(&doc.get_page_content(pg_id).unwrap()).unwrap();
+    content
+        .operations
+        .iter()
+        .filter(|op| op.operator == "Tj")
+        .filter_map(|op| op.operands.first()?.as_str().ok())
+        .map(|txt| String::from_utf8_lossy(txt).into_owned())
+        .collect()
+}
+
+/// A temporary directory of a test, removed when dropped.
+///
+/// Named by test and process, such as `adr_state_1234`, so parallel tests don't collide.
+pub struct TempDir(PathBuf);
+
+impl TempDir {
+    /// Creates an empty directory, clearing one left by an aborted run.
+    pub fn new(name: &str) -> Self {
+        let dir = TempDir(std::env::temp_dir().join(format!("adr_{name}_{}", std::process::id())));
+        dir.clear();
+        dir
+    }
+
+    /// Empties the directory.
+    pub fn clear(&self) {
+        let _ = fs::remove_dir_all(&self.0);
+        fs::create_dir_all(&self.0).unwrap();
+    }
+
+    /// Path of a file in the directory, as taken by `read_from_file` and `write_to_file`.
+    pub fn pth(&self, name: &str) -> String {
+        self.0.join(name).to_string_lossy().to_string()
+    }
+}
+
+impl Deref for TempDir {
+    type Target = Path;
+
+    fn deref(&self) -> &Path {
+        &self.0
+    }
+}
+
+impl AsRef<Path> for TempDir {
+    fn as_ref(&self) -> &Path {
+        &self.0
+    }
+}
+
+impl Drop for TempDir {
+    fn drop(&mut self) {
+        let _ = fs::remove_dir_all(&self.0);
+    }
+}
@@ -0,0 +1,403 @@
+use crate::io::*;
+use crate::mailing::*;
+use anyhow::{anyhow, Result};
+use chrono::{NaiveDate, NaiveDateTime};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fmt;
+use std::path::PathBuf;
+use std::str::FromStr;
+
+/// Days after acceptance before a piece without an induction scan is reported.
+pub const TRACKING_DAYS: i64 = 7;
+
+/// A tracking scan event type.
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum EventKind {
+    /// A tray accepted at the BMEU.
+    Acceptance,
+    /// A piece inducted into the mailstream.
+    Induction,
+}
+impl fmt::Display for EventKind {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            EventKind::Acceptance => write!(f, "acceptance"),
+            EventKind::Induction => write!(f, "induction"),
+        }
+    }
+}
+impl FromStr for EventKind {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s.to_lowercase().as_str() {
+            "acceptance" => Ok(EventKind::Acceptance),
+            "induction" => Ok(EventKind::Induction),
+            _ => Err(anyhow!("unknown event '{s}'")),
+        }
+    }
+}
+
+/// A scan event matched to trays of a mailing.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Eq)]
+pub struct TrackingEvent {
+    /// Tray name or serial range, as ingested.
+    pub target: String,
+    pub kind: EventKind,
+    pub timestamp: NaiveDateTime,
+    /// Names of matched trays.
+    pub trays: Vec<String>,
+    /// Inclusive serial range of a piece event; `None` for a tray event.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub serials: Option<(u32, u32)>,
+}
+
+/// Scan events of a mailing.
+#[derive(Debug, Default, Serialize, Deserialize, Clone)]
+pub struct Tracking {
+    pub mailing: String,
+    pub events: Vec<TrackingEvent>,
+    /// Ingested rows matching no tray or piece.
+    #[serde(default)]
+    pub unmatched: Vec<String>,
+}
+
+/// A row of a scan event CSV.
+#[derive(Debug, Deserialize)]
+struct EventRow {
+    target: String,
+    event: String,
+    timestamp: String,
+}
+
+impl Tracking {
+    pub fn new(mailing: &Mailing) -> Self {
+        Self {
+            mailing: mailing.name.clone(),
+            ..Default::default()
+        }
+    }
+
+    /// Tracking file of a mailing.
+    ///
+    /// Kept beside the mailing directory, which is recreated on each run.
+    pub fn pth(mailing: &Mailing) -> PathBuf {
+        mailings_dir().join(format!("{}.tracking.json", mailing.name))
+    }
+
+    /// Reads the tracking file of a mailing, or starts a new one.
+    pub fn load(mailing: &Mailing) -> Result<Tracking> {
+        let pth = Tracking::pth(mailing);
+        if pth.exists() {
+            read_from_file_checked::<Tracking>(&pth.to_string_lossy())
+        } else {
+            Ok(Tracking::new(mailing))
+        }
+    }
+
+    pub fn save(&self, mailing: &Mailing) -> Result<()> {
+        write_to_file_checked(self, &Tracking::pth(mailing).to_string_lossy())
+    }
+
+    /// Ingests a CSV of `target,event,timestamp` rows, returning the count of new events.
+    ///
+    /// A target is a tray name or a serial range such as `000101-000150`.
+    /// Rows matching nothing are kept in `unmatched`; repeated events are skipped.
+    pub fn ingest(&mut self, mailing: &Mailing, csv: &str) -> Result<usize> {
+        let mut rdr = csv::ReaderBuilder::new()
+            .trim(csv::Trim::All)
+            .from_reader(csv.as_bytes());
+        let mut cnt = 0;
+        for (idx, row) in rdr.deserialize::<EventRow>().enumerate() {
+            let row = row?;
+            match match_event(mailing, &row) {
+                Ok(event) => {
+                    if !self.events.contains(&event) {
+                        self.events.push(event);
+                        cnt += 1;
+                    }
+                }
+                Err(err) => {
+                    // Header is line 1.
+                    let lne = format!("line {} '{}': {err}", idx + 2, row.target);
+                    if !self.unmatched.contains(&lne) {
+                        self.unmatched.push(lne);
+                    }
+                }
+            }
+        }
+        self.events.sort_by_key(|event| event.timestamp);
+        Ok(cnt)
+    }
+}
+
+/// Matches a CSV row to trays of a mailing.
+fn match_event(mailing: &Mailing, row: &EventRow) -> Result<TrackingEvent> {
+    let kind: EventKind = row.event.parse()?;
+    let timestamp = prs_timestamp(&row.timestamp)?;
+
+    // Tray event.
+    if mailing.trays.iter().any(|tray| tray.name == row.target) {
+        return Ok(TrackingEvent {
+            target: row.target.clone(),
+            kind,
+            timestamp,
+            trays: vec![row.target.clone()],
+            serials: None,
+        });
+    }
+
+    // Piece event by serial range.
+    let (start, end) = prs_serials(&row.target)?;
+    let trays: Vec<String> = mailing
+        .trays
+        .iter()
+        .filter(|tray| {
+            tray.mailpieces.iter().any(|mp| {
+                mp.imb
+                    .as_ref()
+                    .is_some_and(|imb| (start..=end).contains(&imb.serial))
+            })
+        })
+        .map(|tray| tray.name.clone())
+        .collect();
+    if trays.is_empty() {
+        return Err(anyhow!("no mailpieces in serial range"));
+    }
+    Ok(TrackingEvent {
+        target: row.target.clone(),
+        kind,
+        timestamp,
+        trays,
+        serials: Some((start, end)),
+    })
+}
+
+/// Parses a timestamp such as `2024-07-01 09:30:00`, `2024-07-01T09:30:00`, or `2024-07-01`.
+fn prs_timestamp(s: &str) -> Result<NaiveDateTime> {
+    for fmt in ["%Y-%m-%d %H:%M:%S", "%Y-%m-%dT%H:%M:%S"] {
+        if let Ok(timestamp) = NaiveDateTime::parse_from_str(s, fmt) {
+            return Ok(timestamp);
+        }
+    }
+    NaiveDate::parse_from_str(s, "%Y-%m-%d")
+        .map(|date| date.and_hms_opt(0, 0, 0).unwrap())
+        .map_err(|_| anyhow!("invalid timestamp '{s}'"))
+}
+
+/// Parses an inclusive serial range such as `000101-000150`, or a single serial.
+fn prs_serials(s: &str) -> Result<(u32, u32)> {
+    let (start, end) = s.split_once('-').unwrap_or((s, s));
+    let is_serial = |s: &str| !s.is_empty() && s.bytes().all(|b| b.is_ascii_digit());
+    if !is_serial(start) || !is_serial(end) {
+        return Err(anyhow!("unknown tray or serial range"));
+    }
+    let (start, end) = (start.parse()?, end.parse()?);
+    if start > end {
+        return Err(anyhow!("reversed serial range"));
+    }
+    Ok((start, end))
+}
+
+/// Gaps between a mailing and its scan events.
+#[derive(Debug, Default)]
+pub struct TrackingReport {
+    /// Trays without an acceptance event.
+    pub unaccepted: Vec<String>,
+    /// Serial ranges per tray without an induction scan after the allowed days.
+    pub uninducted: Vec<(String, u32, u32)>,
+    pub days: i64,
+}
+
+impl TrackingReport {
+    pub fn is_empty(&self) -> bool {
+        self.unaccepted.is_empty() && self.uninducted.is_empty()
+    }
+
+    pub fn lnes(&self) -> Vec<String> {
+        let mut lnes = Vec::new();
+        for tray in self.unaccepted.iter() {
+            lnes.push(format!("tray {tray} without acceptance"));
+        }
+        for (tray, start, end) in self.uninducted.iter() {
+            lnes.push(format!(
+                "tray {tray} pieces {start:06}-{end:06} without induction after {} days",
+                self.days
+            ));
+        }
+        lnes
+    }
+}
+
+/// Reconciles scan events against trays and pieces of a mailing.
+///
+/// Pieces are reported once their tray was accepted `days` or more before `now`.
+/// Pieces without stored barcode components only match tray events.
+pub fn reconcile(
+    mailing: &Mailing,
+    tracking: &Tracking,
+    now: NaiveDateTime,
+    days: i64,
+) -> TrackingReport {
+    let mut rpt = TrackingReport {
+        days,
+        ..Default::default()
+    };
+
+    // Earliest acceptance of each tray.
+    let mut accepted: BTreeMap<&str, NaiveDateTime> = BTreeMap::new();
+    for event in tracking
+        .events
+        .iter()
+        .filter(|event| event.kind == EventKind::Acceptance)
+    {
+        for tray in event.trays.iter() {
+            let timestamp = accepted.entry(tray).or_insert(event.timestamp);
+            *timestamp = (*timestamp).min(event.timestamp);
+        }
+    }
+
+    let inductions: Vec<&TrackingEvent> = tracking
+        .events
+        .iter()
+        .filter(|event| event.kind == EventKind::Induction)
+        .collect();
+    for tray in mailing.trays.iter() {
+        let Some(timestamp) = accepted.get(tray.name.as_str()) else {
+            rpt.unaccepted.push(tray.name.clone());
+            continue;
+        };
+        if (now - *timestamp).num_days() < days {
+            continue;
+        }
+        let is_tray_inducted = inductions
+            .iter()
+            .any(|event| event.serials.is_none() && event.trays.contains(&tray.name));
+        if is_tray_inducted {
+            continue;
+        }
+
+        // Collapse serials without induction into ranges.
+        let mut serials: Vec<u32> = tray
+            .mailpieces
+            .iter()
+            .filter_map(|mp| mp.imb.as_ref().map(|imb| imb.serial))
+            .filter(|serial| {
+                !inductions.iter().any(|event| {
+                    event
+                        .serials
+                        .is_some_and(|(start, end)| (start..=end).contains(serial))
+                })
+            })
+            .collect();
+        serials.sort_unstable();
+        for serial in serials {
+            match rpt.uninducted.last_mut() {
+                Some((name, _, end)) if *name == tray.name && *end + 1 == serial => *end = serial,
+                _ => rpt.uninducted.push((tray.name.clone(), serial, serial)),
+            }
+        }
+    }
+
+    rpt
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::*;
+
+    const EVENTS: &str = include_str!("../tests/fixtures/tracking/events.csv");
+
+    /// A mailing of trays A, B, and C with pieces of serials 101 through 107.
+    fn mailing() -> Mailing {
+        let mut mailing = Mailing::new();
+        for (name, serials) in [("A", 101..=104), ("B", 105..=106), ("C", 107..=107)] {
+            mailing.trays.push(MailTray {
+                name: name.into(),
+                size: TraySize::OneFoot,
+                barcode_id: BarcodeId::MixedAadc,
+                mailpieces: serials
+                    .map(|serial| Mailpiece {
+                        id: serial,
+                        imb: Some(Imb {
+                            barcode_id: "50".into(),
+                            service_type_id: STID_RSR.into(),
+                            mailer_id: "123456".into(),
+                            serial,
+                            routing_code: "12224".into(),
+                        }),
+                        ..Default::default()
+                    })
+                    .collect(),
+            });
+        }
+        mailing
+    }
+
+    fn ts(s: &str) -> NaiveDateTime {
+        prs_timestamp(s).unwrap()
+    }
+
+    #[test]
+    fn test_tracking_ingest() {
+        let mailing = mailing();
+        let mut tracking = Tracking::new(&mailing);
+        assert_eq!(tracking.ingest(&mailing, EVENTS).unwrap(), 4);
+
+        let events: Vec<String> = tracking
+            .events
+            .iter()
+            .map(|event| {
+                format!(
+                    "{} {} {:?} {:?}",
+                    event.target, event.kind, event.trays, event.serials
+                )
+            })
+            .collect();
+        assert_eq!(
+            events,
+            vec![
+                "A acceptance [\"A\"] None",
+                "B acceptance [\"B\"] None",
+                "B induction [\"B\"] None",
+                "000101-000102 induction [\"A\"] Some((101, 102))",
+            ]
+        );
+        assert_eq!(
+            tracking.unmatched,
+            vec![
+                "line 6 '000900-000910': no mailpieces in serial range",
+                "line 7 'Z': unknown tray or serial range",
+                "line 8 'A': unknown event 'delivered'",
+            ]
+        );
+
+        // Reingesting adds nothing.
+        assert_eq!(tracking.ingest(&mailing, EVENTS).unwrap(), 0);
+        assert_eq!(tracking.unmatched.len(), 3);
+    }
+
+    #[test]
+    fn test_tracking_reconcile() {
+        let mailing = mailing();
+        let mut tracking = Tracking::new(&mailing);
+        tracking.ingest(&mailing, EVENTS).unwrap();
+
+        // Within the allowed days only acceptance gaps are reported.
+        let rpt = reconcile(&mailing, &tracking, ts("2026-01-08"), TRACKING_DAYS);
+        assert_eq!(rpt.lnes(), vec!["tray C without acceptance"]);
+
+        let rpt = reconcile(&mailing, &tracking, ts("2026-01-20"), TRACKING_DAYS);
+        assert!(!rpt.is_empty());
+        assert_eq!(
+            rpt.lnes(),
+            vec![
+                "tray C without acceptance",
+                "tray A pieces 000103-000104 without induction after 7 days",
+            ]
+        );
+    }
+}
@@ -1,54 +1,509 @@
-use crate::core::*;
+use crate::imb::IMB_BAR_CNT;
+use crate::io::*;
 use crate::models::*;
+use crate::report::*;
 use anyhow::{anyhow, Result};
-use reqwest::Client;
-use reqwest::StatusCode;
+use chrono::{Duration, Local, NaiveDateTime};
+use itertools::Itertools;
+use reqwest::{Client, RequestBuilder};
 use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fmt;
+use std::fs;
+use std::future::Future;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use tokio::sync::Semaphore;
+use tokio::time::Instant;
+use tracing::{debug, trace, warn};
 use StdAdr::*;
 
+/// Cache file of USPS standardization results.
+pub const FLE_PTH_USPS_CACHE: &str = "usps_cache.json";
+
+/// Days before a cached USPS result is refetched.
+pub const USPS_CACHE_TTL_DAYS: i64 = 90;
+
+/// Cache file of IMb encodings.
+pub const FLE_PTH_BARCODE_CACHE: &str = "barcode_cache.json";
+
+/// Entries above which encodings are no longer cached.
+pub const BARCODE_CACHE_MAX: usize = 100_000;
+
+/// Skips reading cached IMb encodings, set by `--no-barcode-cache`.
+pub static BARCODE_CACHE_BYPASS: AtomicBool = AtomicBool::new(false);
+
+lazy_static! {
+    /// USPS standardization results shared by fetch loops.
+    pub static ref USPS_CACHE: Mutex<UspsCache> = Mutex::new(
+        UspsCache::load(FLE_PTH_USPS_CACHE).unwrap_or_else(|err| {
+            warn!("ignoring usps cache: {err}");
+            UspsCache::default()
+        })
+    );
+    /// IMb encodings shared by barcode loops.
+    pub static ref BARCODE_CACHE: Mutex<BarcodeCache> = Mutex::new(
+        BarcodeCache::load(FLE_PTH_BARCODE_CACHE).unwrap_or_else(|err| {
+            warn!("ignoring barcode cache: {err}");
+            BarcodeCache::default()
+        })
+    );
+    /// USPS client shared by standardization and barcode encoding.
+    pub static ref USPS_CLI: Arc<UspsClient> =
+        Arc::new(UspsClient::new(CLI.clone(), USPS_PERMITS, USPS_INTERVAL));
+}
+
 pub async fn standardize_addresses(mut adrs: Vec<Address>) -> Result<Vec<Address>> {
     // The USPS prefers that secondary address designators such as "APT" (Apartment) or "STE" (Suite) appear on the same line as the street address when there is enough space. However, it is also acceptable for these designators to appear on a separate line if needed, typically as Address Line 2.
-    eprintln!("{}", AddressList(adrs.clone()));
+    trace!("{}", AddressList(adrs.clone()));
+
+    // Dry runs return addresses untouched.
+    if is_dry_run() {
+        debug!("dry run: skipping address standardization");
+        return Ok(adrs);
+    }
+
+    // Foreign addresses are not standardized by the USPS.
+    let now = Local::now().naive_local();
+    for adr in adrs.iter_mut().filter(|adr| !adr.is_foreign()) {
+        // Senate office buildings are standardized locally.
+        if std_sob(adr) {
+            continue;
+        }
+        // Known addresses are standardized from the cache.
+        std_or_keep(&USPS_CACHE, adr, &UspsFallback::default(), now).await?;
+    }
+    UspsCache::save_dirty(&USPS_CACHE, FLE_PTH_USPS_CACHE)?;
+
+    // Deduplicate and order standardized addresses.
+    let mut adrs = dedup_addresses(adrs);
+    order_addresses(&mut adrs);
+
+    trace!("{}", AddressList(adrs.clone()));
+
+    Ok(adrs)
+}
+
+/// Removes duplicate addresses, keeping the first of each in input order.
+///
+/// Addresses are duplicates when they are the same location, and the duplicate
+/// with zip4 and delivery point populated is kept.
+/// Counties of a removed duplicate are merged into the kept address.
+pub fn dedup_addresses(adrs: Vec<Address>) -> Vec<Address> {
+    let mut ret: Vec<Address> = Vec::with_capacity(adrs.len());
+    for adr in adrs {
+        match ret.iter().position(|kept| kept.same_location(&adr)) {
+            Some(idx) => {
+                let mut counties = std::mem::take(&mut ret[idx].counties);
+                for cnty in adr.counties.iter() {
+                    if !counties.contains(cnty) {
+                        counties.push(cnty.clone());
+                    }
+                }
+                if adr.detail() > ret[idx].detail() {
+                    ret[idx] = adr;
+                }
+                ret[idx].counties = counties;
+            }
+            None => ret.push(adr),
+        }
+    }
+    ret
+}
+
+/// A normalized key identifying an address.
+fn adr_key(adr: &Address) -> String {
+    let norm = |s: &str| s.split_whitespace().join(" ").to_uppercase();
+    format!(
+        "{}|{}|{}|{}|{:05}|{:04}",
+        norm(&adr.address1),
+        norm(adr.address2.as_deref().unwrap_or("")),
+        norm(&adr.city),
+        norm(&adr.state),
+        adr.zip5,
+        adr.zip4
+    )
+}
+
+/// Orders a person's addresses for mailing.
+///
+/// The DC office is first, followed by district offices by state then city.
+/// Offices in the same city keep their input order.
+pub fn order_addresses(adrs: &mut [Address]) {
+    adrs.sort_by(|a, b| {
+        (a.state != "DC", &a.state, &a.city).cmp(&(b.state != "DC", &b.state, &b.city))
+    });
+}
+
+/// Zip of the Senate office buildings.
+pub const ZIP_SOB: u32 = 20510;
+
+/// Standardizes a Senate office building address locally, such as `141 HART SOB` to `141 HSOB`.
+///
+/// Returns false for other addresses, which are standardized by the USPS.
+pub fn std_sob(adr: &mut Address) -> bool {
+    if !adr.state.is_empty() && adr.state != "DC" {
+        return false;
+    }
+    let Some((room, bldg)) = prs_sob(&adr.address1) else {
+        return false;
+    };
+    adr.address1 = format!("{room} {bldg}SOB");
+    adr.city = "WASHINGTON".into();
+    adr.state = "DC".into();
+    adr.zip5 = ZIP_SOB;
+    true
+}
+
+/// Parses the room and building initial of a Senate office building line.
+///
+/// Accepts forms such as `141 HSOB`, `SH-141 HART SOB` and `141 HART SENATE OFFICE BUILDING`.
+pub fn prs_sob(lne: &str) -> Option<(String, char)> {
+    let lne = lne.to_uppercase();
+    let (room, bldg) = lne.trim().split_once(' ')?;
+    let room = match room.split_once('-') {
+        Some((prefix, room)) if prefix.len() == 2 && prefix.starts_with('S') => room,
+        _ => room,
+    };
+    if !room.chars().any(|c| c.is_ascii_digit()) || !room.chars().all(|c| c.is_ascii_alphanumeric())
+    {
+        return None;
+    }
+    let bldg = bldg.split_whitespace().join(" ");
+    let initial = ["HART", "DIRKSEN", "RUSSELL"]
+        .into_iter()
+        .find_map(|name| {
+            let initial = name.chars().next().unwrap();
+            let is_match = bldg == format!("{initial}SOB")
+                || [
+                    "SOB",
+                    "SENATE OFFICE BUILDING",
+                    "SENATE OFFICE BLDG",
+                    "SENATE OFFICE BLDG.",
+                ]
+                .iter()
+                .any(|sfx| bldg == format!("{name} {sfx}"));
+            is_match.then_some(initial)
+        })?;
+    Some((room.into(), initial))
+}
+
+/// USPS zip code lookup by address.
+const URL_ZIP_BY_ADR: &str = "https://tools.usps.com/tools/app/ziplookup/zipByAddress";
+
+/// Standardizes a single address.
+#[allow(async_fn_in_trait)]
+pub trait Standardizer {
+    async fn standardize(&self, adr: &mut Address) -> Result<()>;
+}
 
-    for adr in adrs.iter_mut() {
-        eprintln!("Attempting to standardize by combining address lines.");
-        match standardize_address(adr, AsIs, false).await {
+/// Concurrent requests of a USPS client.
+pub const USPS_PERMITS: usize = 2;
+
+/// Minimum interval between requests of a USPS client, 2 requests per second.
+pub const USPS_INTERVAL: std::time::Duration = std::time::Duration::from_millis(500);
+
+/// Sends USPS requests with limited concurrency and a minimum interval between requests.
+pub struct UspsClient {
+    pub cli: Client,
+    /// Zip lookup url, `URL_ZIP_BY_ADR` but for tests.
+    pub url: String,
+    permits: Semaphore,
+    interval: std::time::Duration,
+    /// Earliest start of the next request.
+    next_at: tokio::sync::Mutex<Instant>,
+}
+
+impl UspsClient {
+    /// A client sending at most `permits` concurrent requests, at least `interval` apart.
+    pub fn new(cli: Client, permits: usize, interval: std::time::Duration) -> Self {
+        UspsClient {
+            cli,
+            url: URL_ZIP_BY_ADR.into(),
+            permits: Semaphore::new(permits),
+            interval,
+            next_at: tokio::sync::Mutex::new(Instant::now()),
+        }
+    }
+
+    /// Sends an idempotent request once permitted, returning the response text.
+    ///
+    /// The permit is held until the response is read, including retries.
+    pub async fn send<F>(&self, url: &str, req: F) -> Result<String>
+    where
+        F: Fn(&Client) -> RequestBuilder,
+    {
+        let _permit = self.permits.acquire().await?;
+        self.wait_interval().await;
+        let res = send_retry(url, RETRY, || req(&self.cli)).await?;
+        Ok(res.text().await?)
+    }
+
+    /// Waits until the interval since the previous request has passed.
+    async fn wait_interval(&self) {
+        let mut next_at = self.next_at.lock().await;
+        tokio::time::sleep_until(*next_at).await;
+        *next_at = Instant::now() + self.interval;
+    }
+}
+
+/// Standardizes addresses with the USPS zip lookup.
+pub struct Usps {
+    pub cli: Arc<UspsClient>,
+}
+
+impl Usps {
+    /// A standardizer sending lookups with a client, such as one from `new_client`.
+    ///
+    /// The client is limited apart from the shared `USPS_CLI`.
+    pub fn new(cli: Client) -> Self {
+        Usps {
+            cli: Arc::new(UspsClient::new(cli, USPS_PERMITS, USPS_INTERVAL)),
+        }
+    }
+}
+
+impl Default for Usps {
+    fn default() -> Self {
+        Usps {
+            cli: USPS_CLI.clone(),
+        }
+    }
+}
+
+impl Standardizer for Usps {
+    async fn standardize(&self, adr: &mut Address) -> Result<()> {
+        self.cli.standardize_address(adr, AsIs, false).await
+    }
+}
+
+/// Standardizes addresses with the USPS, falling back through address line approaches.
+pub struct UspsFallback {
+    pub cli: Arc<UspsClient>,
+}
+
+impl UspsFallback {
+    /// A standardizer sending lookups with a client, such as one from `new_client`.
+    ///
+    /// The client is limited apart from the shared `USPS_CLI`.
+    pub fn new(cli: Client) -> Self {
+        UspsFallback {
+            cli: Arc::new(UspsClient::new(cli, USPS_PERMITS, USPS_INTERVAL)),
+        }
+    }
+}
+
+impl Default for UspsFallback {
+    fn default() -> Self {
+        UspsFallback {
+            cli: USPS_CLI.clone(),
+        }
+    }
+}
+
+impl Standardizer for UspsFallback {
+    async fn standardize(&self, adr: &mut Address) -> Result<()> {
+        let cli = &self.cli;
+        debug!("Attempting to standardize by combining address lines.");
+        match cli.standardize_address(adr, AsIs, false).await {
             Ok(_) => {}
             Err(err) => {
-                eprintln!("standardize_addresses: err1: {}", err);
+                debug!("standardize_addresses: err1: {}", err);
 
-                eprintln!("Attempting to standardize without combining address lines.");
-                match standardize_address(adr, CombineAdr1Adr2, false).await {
+                debug!("Attempting to standardize without combining address lines.");
+                match cli.standardize_address(adr, CombineAdr1Adr2, false).await {
                     Ok(_) => {}
                     Err(err) => {
-                        eprintln!("standardize_addresses: err2: {}", err);
+                        debug!("standardize_addresses: err2: {}", err);
 
-                        eprintln!("Attempting to standardize by swapping address lines.");
-                        match standardize_address(adr, SwapAdr1Adr2, false).await {
+                        debug!("Attempting to standardize by swapping address lines.");
+                        match cli.standardize_address(adr, SwapAdr1Adr2, false).await {
                             Ok(_) => {}
                             Err(err) => {
-                                eprintln!("standardize_addresses: err3: {}", err);
+                                debug!("standardize_addresses: err3: {}", err);
 
                                 // Mitigate failed address standardization.
-                                eprintln!("Attempting to standardize address without zip.");
+                                warn!("Attempting to standardize address without zip: {adr}");
                                 adr.zip5 = 0;
-                                eprintln!("  {}", adr);
-                                standardize_address(adr, AsIs, true).await?;
+                                cli.standardize_address(adr, AsIs, true).await?;
+                                REPORT.lock().unwrap().inc_fallback();
                             }
                         }
                     }
                 }
             }
         }
+        Ok(())
     }
+}
 
-    // Deduplicate extracted addresses.
-    adrs.sort_unstable();
-    adrs.dedup_by(|a, b| a == b);
+/// Persistent USPS standardization results, including failures.
+///
+/// Keyed by the normalized input address.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct UspsCache {
+    /// Days before an entry is refetched.
+    #[serde(default = "usps_cache_ttl_days")]
+    pub ttl_days: i64,
+    pub entries: BTreeMap<String, UspsCacheEntry>,
+    /// Whether entries were added since the cache was loaded or saved.
+    #[serde(skip)]
+    pub is_dirty: bool,
+}
 
-    eprintln!("{}", AddressList(adrs.clone()));
+fn usps_cache_ttl_days() -> i64 {
+    USPS_CACHE_TTL_DAYS
+}
 
-    Ok(adrs)
+impl Default for UspsCache {
+    fn default() -> Self {
+        Self {
+            ttl_days: USPS_CACHE_TTL_DAYS,
+            entries: BTreeMap::new(),
+            is_dirty: false,
+        }
+    }
+}
+
+/// A cached USPS standardization of an address.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct UspsCacheEntry {
+    pub at: NaiveDateTime,
+    /// Standardized address, `None` for a failure.
+    pub adr: Option<Address>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub err: Option<String>,
+}
+
+impl UspsCache {
+    /// Reads the cache from a file; a missing file is an empty cache.
+    pub fn load(pth: &str) -> Result<UspsCache> {
+        if !data_pth(pth).exists() {
+            return Ok(UspsCache::default());
+        }
+        read_from_file(pth)
+    }
+
+    pub fn save(&self, pth: &str) -> Result<()> {
+        write_to_file(self, pth)
+    }
+
+    /// Saves a shared cache when entries were added since its last save.
+    ///
+    /// The cache is copied under the lock and written after releasing it.
+    pub fn save_dirty(cache: &Mutex<UspsCache>, pth: &str) -> Result<()> {
+        let cache = {
+            let mut cache = cache.lock().unwrap();
+            if !cache.is_dirty {
+                return Ok(());
+            }
+            cache.is_dirty = false;
+            cache.clone()
+        };
+        cache.save(pth)
+    }
+
+    /// Returns the entry of an input address, unless expired.
+    pub fn get(&self, adr: &Address, now: NaiveDateTime) -> Option<&UspsCacheEntry> {
+        self.entries
+            .get(&adr_key(adr))
+            .filter(|entry| now - entry.at < Duration::days(self.ttl_days))
+    }
+
+    /// Deletes the cache file and clears the shared cache.
+    ///
+    /// Returns the count of purged entries.
+    pub fn purge(pth: &str) -> Result<usize> {
+        let cnt = UspsCache::load(pth)?.entries.len();
+        if data_pth(pth).exists() {
+            fs::remove_file(data_pth(pth))?;
+        }
+        USPS_CACHE.lock().unwrap().entries.clear();
+        Ok(cnt)
+    }
+}
+
+/// Standardizes an address from a cache, standardizing and caching on a miss.
+///
+/// Addresses the USPS doesn't find are cached too, so known-bad addresses aren't requeried.
+/// Failed requests, such as a connection error or a 5xx after retries, aren't cached.
+/// A miss marks the cache dirty for `UspsCache::save_dirty`.
+pub async fn std_cached<S: Standardizer>(
+    cache: &Mutex<UspsCache>,
+    adr: &mut Address,
+    stdzr: &S,
+    now: NaiveDateTime,
+) -> Result<()> {
+    let hit = cache.lock().unwrap().get(adr, now).cloned();
+    if let Some(entry) = hit {
+        return match entry.adr {
+            Some(std_adr) => {
+                adr_std_from(adr, &std_adr);
+                Ok(())
+            }
+            None => Err(anyhow!(
+                "cached USPS failure for {adr}: {}",
+                entry.err.unwrap_or_default()
+            )),
+        };
+    }
+
+    let key = adr_key(adr);
+    let res = stdzr.standardize(adr).await;
+    if res.as_ref().is_err_and(|err| !is_not_found(err)) {
+        return res;
+    }
+    let entry = UspsCacheEntry {
+        at: now,
+        adr: res.is_ok().then(|| adr.clone()),
+        err: res.as_ref().err().map(|err| err.to_string()),
+    };
+    let mut cache = cache.lock().unwrap();
+    cache.entries.insert(key, entry);
+    cache.is_dirty = true;
+    res
+}
+
+/// Standardizes an address through the cache, keeping a territory address on failure.
+///
+/// The kept address is the parsed address, undoing any fallback edits such as a dropped zip,
+/// and is marked unstandardized.
+pub async fn std_or_keep<S: Standardizer>(
+    cache: &Mutex<UspsCache>,
+    adr: &mut Address,
+    stdzr: &S,
+    now: NaiveDateTime,
+) -> Result<()> {
+    let parsed = adr.clone();
+    match std_cached(cache, adr, stdzr, now).await {
+        Err(err) if parsed.is_territory() => {
+            warn!("keeping unstandardized address {parsed}: {err}");
+            *adr = parsed;
+            adr.standardized = false;
+            Ok(())
+        }
+        res => res,
+    }
+}
+
+/// Copies the standardized fields of an address, keeping others such as counties.
+fn adr_std_from(adr: &mut Address, std_adr: &Address) {
+    adr.address1.clone_from(&std_adr.address1);
+    adr.address2.clone_from(&std_adr.address2);
+    adr.city.clone_from(&std_adr.city);
+    adr.state.clone_from(&std_adr.state);
+    adr.zip5 = std_adr.zip5;
+    adr.zip4 = std_adr.zip4;
+    adr.delivery_point.clone_from(&std_adr.delivery_point);
+}
+
+/// Limits calls to the USPS.
+///
+/// Stops after `max_calls` calls, or after `max_failures` consecutive failures.
+#[derive(Debug, Clone, Copy)]
+pub struct CallBudget {
+    pub max_calls: usize,
+    pub max_failures: usize,
 }
 
 #[derive(PartialEq)]
@@ -57,92 +512,159 @@ pub enum StdAdr {
     CombineAdr1Adr2,
     SwapAdr1Adr2,
 }
+/// A USPS response that an address was not found, rather than a failed request.
+#[derive(Debug)]
+pub struct AddressNotFound {
+    pub reason: String,
+}
+impl fmt::Display for AddressNotFound {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.reason)
+    }
+}
+impl std::error::Error for AddressNotFound {}
+
+/// Returns an error that an address was not found.
+pub fn not_found(reason: impl Into<String>) -> anyhow::Error {
+    AddressNotFound {
+        reason: reason.into(),
+    }
+    .into()
+}
+
+/// Whether an error is a USPS response that an address was not found.
+pub fn is_not_found(err: &anyhow::Error) -> bool {
+    err.chain()
+        .any(|cause| cause.downcast_ref::<AddressNotFound>().is_some())
+}
+
+/// Standardizes an address with the USPS default client.
 pub async fn standardize_address(
     adr: &mut Address,
     approach: StdAdr,
     drop_zip: bool,
 ) -> Result<()> {
-    let mut prms: Vec<(&str, String)> = Vec::with_capacity(5);
-    match approach {
-        AsIs => {
-            if !adr.address1.is_empty() {
-                prms.push(("address1", adr.address1.clone()));
-            }
-            if adr.address2.is_some() {
-                let address2 = adr.address2.clone().unwrap();
-                prms.push(("address2", address2));
-            }
+    USPS_CLI.standardize_address(adr, approach, drop_zip).await
+}
+
+impl UspsClient {
+    /// Standardizes an address with the USPS zip lookup.
+    pub async fn standardize_address(
+        &self,
+        adr: &mut Address,
+        approach: StdAdr,
+        drop_zip: bool,
+    ) -> Result<()> {
+        // Dry runs leave the address untouched.
+        if is_dry_run() {
+            return Ok(());
         }
-        CombineAdr1Adr2 => {
-            let mut address1 = adr.address1.clone();
-            if let Some(address2) = adr.address2.clone() {
-                address1.push(' ');
-                address1.push_str(&address2);
+
+        let mut prms: Vec<(&str, String)> = Vec::with_capacity(5);
+        match approach {
+            AsIs => {
+                if !adr.address1.is_empty() {
+                    prms.push(("address1", adr.address1.clone()));
+                }
+                if adr.address2.is_some() {
+                    let address2 = adr.address2.clone().unwrap();
+                    prms.push(("address2", address2));
+                }
             }
-            prms.push(("address1", address1));
-        }
-        SwapAdr1Adr2 => {
-            if adr.address2.is_some() {
-                let address2 = adr.address2.clone().unwrap();
-                prms.push(("address1", address2));
-            } else {
-                return Err(anyhow!("No address2 to swap to address1."));
+            CombineAdr1Adr2 => {
+                let mut address1 = adr.address1.clone();
+                if let Some(address2) = adr.address2.clone() {
+                    address1.push(' ');
+                    address1.push_str(&address2);
+                }
+                prms.push(("address1", address1));
+            }
+            SwapAdr1Adr2 => {
+                if adr.address2.is_some() {
+                    let address2 = adr.address2.clone().unwrap();
+                    prms.push(("address1", address2));
+                } else {
+                    return Err(not_found("No address2 to swap to address1."));
+                }
             }
         }
-    }
 
-    if !adr.city.is_empty() {
-        prms.push(("city", adr.city.clone()));
-    }
-    if !adr.state.is_empty() {
-        prms.push(("state", adr.state.clone()));
-    }
-    if !drop_zip && adr.zip5 != 0 {
-        prms.push(("zip", format!("{:05}", adr.zip5)));
-    }
+        if !adr.city.is_empty() {
+            prms.push(("city", adr.city.clone()));
+        }
+        if !adr.state.is_empty() {
+            prms.push(("state", adr.state.clone()));
+        }
+        if !drop_zip && adr.zip5 != 0 {
+            prms.push(("zip", format!("{:05}", adr.zip5)));
+        }
 
-    let response = CLI
-        .post("https://tools.usps.com/tools/app/ziplookup/zipByAddress")
-        .form(&prms)
-        .send()
-        .await?;
-    let response_text = response.text().await?;
-    eprintln!("{}", response_text);
-    let response_json: USPSResponse = serde_json::from_str(&response_text)?;
+        // The lookup is idempotent, so it's retried.
+        let url = self.url.as_str();
+        let response_text = self.send(url, |cli| cli.post(url).form(&prms)).await?;
+        // Responses can be large, so they're never logged above debug.
+        trace!("{}", response_text);
+        let response_json: USPSResponse = serde_json::from_str(&response_text)?;
 
-    if response_json.result_status == "SUCCESS" {
-        if !response_json.address_list.is_empty() {
-            let usps_adrs: Vec<USPSAddress> = response_json
-                .address_list
-                .into_iter()
-                .filter(|v| !v.address_line1.contains("Range"))
-                .collect();
+        if response_json.result_status == "SUCCESS" {
+            if !response_json.address_list.is_empty() {
+                let usps_adrs: Vec<USPSAddress> = response_json
+                    .address_list
+                    .into_iter()
+                    .filter(|v| !v.address_line1.contains("Range"))
+                    .collect();
 
-            match usps_adrs.len() {
-                1 => {
-                    from(adr, usps_adrs[0].clone());
-                    Ok(())
-                }
-                n if n > 1 => {
-                    if let Some(new_adr) = usps_adrs.iter().find(|v| v.address_line2.is_none()) {
-                        from(adr, new_adr.clone());
-                    } else {
-                        from(adr, usps_adrs[0].clone());
+                let new_adr = match usps_adrs.len() {
+                    1 => usps_adrs[0].clone(),
+                    n if n > 1 => usps_adrs
+                        .iter()
+                        .find(|v| v.address_line2.is_none())
+                        .unwrap_or(&usps_adrs[0])
+                        .clone(),
+                    _ => {
+                        return Err(not_found(
+                            "Over filtered response. No address found in the USPS response.",
+                        ))
                     }
-                    Ok(())
-                }
-                _ => Err(anyhow!(
-                    "Over filtered response. No address found in the USPS response."
-                )),
+                };
+                let mut std_adr = adr.clone();
+                from(&mut std_adr, new_adr);
+                check_std_state(adr, &std_adr)?;
+                *adr = std_adr;
+                Ok(())
+            } else {
+                Err(not_found("No address found in the USPS response."))
             }
         } else {
-            Err(anyhow!("No address found in the USPS response."))
+            Err(not_found("Failed to standardize address."))
         }
-    } else {
-        Err(anyhow!("Failed to standardize address."))
     }
 }
 
+/// State changes accepted from standardization, as parsed and as standardized.
+///
+/// Such as the former `CM` of the Northern Mariana Islands.
+pub const STD_STATE_CHANGES: [(&str, &str); 1] = [("CM", "MP")];
+
+/// Checks a standardized address is in the state it was parsed in.
+///
+/// A fuzzy match of a badly parsed line can latch onto a similar street in another state.
+/// A changed city is logged, as the USPS prefers its own city names.
+pub fn check_std_state(adr: &Address, std_adr: &Address) -> Result<()> {
+    if !adr.city.is_empty() && adr.city != std_adr.city {
+        debug!("standardized city {} of {adr}", std_adr.city);
+    }
+    if adr.state.is_empty()
+        || adr.state == std_adr.state
+        || STD_STATE_CHANGES.contains(&(adr.state.as_str(), std_adr.state.as_str()))
+    {
+        return Ok(());
+    }
+    Err(not_found(format!(
+        "standardized {adr} to {std_adr} in another state"
+    )))
+}
+
 #[derive(Debug, Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct USPSResponse {
@@ -180,6 +702,8 @@ fn from(adr: &mut Address, usps: USPSAddress) {
 /// Encodes mailing information to characters
 /// `F`,`A`,`D`,`T`
 /// for use with a barcode font.
+///
+/// Encodings are deterministic, so they're reused from the barcode cache.
 pub async fn encode_barcode(
     barcode_id: &str,
     service_id: &str, // STID
@@ -187,7 +711,155 @@ pub async fn encode_barcode(
     serial_id: &str,
     routing_code: &str,
 ) -> Result<String> {
-    // Validate input.
+    USPS_CLI
+        .encode_barcode_cached(barcode_id, service_id, mailer_id, serial_id, routing_code)
+        .await
+}
+
+/// Persistent IMb encodings, keyed by the encoder query
+/// `barcode_id+stid+mailer_id+serial_id+routing_code`.
+#[derive(Debug, Default, Serialize, Deserialize, Clone)]
+pub struct BarcodeCache {
+    pub entries: BTreeMap<String, String>,
+    /// Whether entries were added since the cache was loaded or saved.
+    #[serde(skip)]
+    pub is_dirty: bool,
+}
+
+impl BarcodeCache {
+    /// Reads the cache from a file; a missing file is an empty cache.
+    pub fn load(pth: &str) -> Result<BarcodeCache> {
+        if !data_pth(pth).exists() {
+            return Ok(BarcodeCache::default());
+        }
+        read_from_file(pth)
+    }
+
+    pub fn save(&self, pth: &str) -> Result<()> {
+        write_to_file(self, pth)
+    }
+
+    /// Saves a shared cache when entries were added since its last save.
+    ///
+    /// The cache is copied under the lock and written after releasing it.
+    pub fn save_dirty(cache: &Mutex<BarcodeCache>, pth: &str) -> Result<()> {
+        let cache = {
+            let mut cache = cache.lock().unwrap();
+            if !cache.is_dirty {
+                return Ok(());
+            }
+            cache.is_dirty = false;
+            cache.clone()
+        };
+        cache.save(pth)
+    }
+
+    /// Adds an encoding, returning false when the cache is full.
+    pub fn insert(&mut self, qry: &str, imb: &str) -> bool {
+        if self.entries.len() >= BARCODE_CACHE_MAX && !self.entries.contains_key(qry) {
+            return false;
+        }
+        self.entries.insert(qry.into(), imb.into());
+        self.is_dirty = true;
+        true
+    }
+}
+
+/// Returns the cached encoding of a query, encoding and caching on a miss.
+///
+/// Bypassing skips the cached encoding, replacing it with a fresh one.
+/// A miss marks the cache dirty for `BarcodeCache::save_dirty`.
+pub async fn encode_cached<F, Fut>(
+    cache: &Mutex<BarcodeCache>,
+    qry: &str,
+    is_bypass: bool,
+    encode: F,
+) -> Result<String>
+where
+    F: FnOnce() -> Fut,
+    Fut: Future<Output = Result<String>>,
+{
+    if !is_bypass {
+        if let Some(imb) = cache.lock().unwrap().entries.get(qry).cloned() {
+            return Ok(imb);
+        }
+    }
+
+    let imb = encode().await?;
+    if !cache.lock().unwrap().insert(qry, &imb) {
+        warn!("barcode cache full at {BARCODE_CACHE_MAX} entries");
+    }
+    Ok(imb)
+}
+
+impl UspsClient {
+    /// Encodes mailing information, reusing the barcode cache before the USPS IMb encoder.
+    pub async fn encode_barcode_cached(
+        &self,
+        barcode_id: &str,
+        service_id: &str, // STID
+        mailer_id: &str,
+        serial_id: &str,
+        routing_code: &str,
+    ) -> Result<String> {
+        let qry = format!("{barcode_id}{service_id}{mailer_id}{serial_id}{routing_code}");
+        encode_cached(
+            &BARCODE_CACHE,
+            &qry,
+            BARCODE_CACHE_BYPASS.load(Ordering::Relaxed),
+            || self.encode_barcode(barcode_id, service_id, mailer_id, serial_id, routing_code),
+        )
+        .await
+    }
+
+    /// Encodes mailing information with the USPS IMb encoder.
+    pub async fn encode_barcode(
+        &self,
+        barcode_id: &str,
+        service_id: &str, // STID
+        mailer_id: &str,
+        serial_id: &str,
+        routing_code: &str,
+    ) -> Result<String> {
+        // Validate input.
+        validate_imb_fields(barcode_id, service_id, mailer_id, serial_id, routing_code)?;
+
+        // Encode information.
+        let qry = format!(
+            "{}{}{}{}{}",
+            barcode_id, service_id, mailer_id, serial_id, routing_code
+        );
+        // eprintln!("qry:{qry}");
+        let url = format!(
+            "https://postalpro.usps.com/ppro-tools-api/imb/encode?imb={}",
+            qry
+        );
+        debug!("url:{url}");
+
+        let res: ImbResponse = serde_json::from_str(&self.send(&url, |cli| cli.get(&url)).await?)?;
+
+        if res.code != "00" {
+            return Err(anyhow!("Error from API: {}", res.code));
+        }
+
+        // Return the encoding.
+        Ok(res.imb)
+    }
+}
+#[derive(Deserialize)]
+struct ImbResponse {
+    code: String,
+    imb: String,
+}
+
+/// Validates mailing information before encoding.
+fn validate_imb_fields(
+    barcode_id: &str,
+    service_id: &str,
+    mailer_id: &str,
+    serial_id: &str,
+    routing_code: &str,
+) -> Result<()> {
     if barcode_id.len() != 2
         || !barcode_id.chars().all(|c| c.is_ascii_digit())
         || barcode_id.chars().nth(1).unwrap() > '4'
@@ -211,37 +883,622 @@ pub async fn encode_barcode(
     {
         return Err(anyhow!("Invalid zip_code"));
     }
+    Ok(())
+}
 
-    // Encode information.
-    let qry = format!(
-        "{}{}{}{}{}",
-        barcode_id, service_id, mailer_id, serial_id, routing_code
-    );
-    // eprintln!("qry:{qry}");
-    let url = format!(
-        "https://postalpro.usps.com/ppro-tools-api/imb/encode?imb={}",
-        qry
-    );
-    eprintln!("url:{url}");
+/// Checks an encoded IMb is 65 bars of `F`,`A`,`D`,`T`.
+pub fn validate_imb(encoded: &str) -> Result<()> {
+    let cnt = encoded.chars().count();
+    if cnt != IMB_BAR_CNT {
+        return Err(anyhow!("IMb has {cnt} bars, expected {IMB_BAR_CNT}"));
+    }
+    if let Some(c) = encoded.chars().find(|c| !"FADT".contains(*c)) {
+        return Err(anyhow!("invalid IMb bar '{c}'"));
+    }
+    Ok(())
+}
+
+/// Generator polynomial of the IMb frame check sequence.
+const IMB_CRC_POLY: u16 = 0x0F35;
+
+/// Character bit of each descender, then each ascender, numbered from 1 (USPS-B-3200 Appendix D).
+#[rustfmt::skip]
+const IMB_BAR_MAP: [u8; 130] = [
+    67, 6, 78, 16, 86, 95, 34, 40, 45, 113, 117, 121, 62,
+    87, 18, 104, 41, 76, 57, 119, 115, 72, 97, 2, 127, 26,
+    105, 35, 122, 52, 114, 7, 24, 82, 68, 63, 94, 44, 77,
+    112, 70, 100, 39, 30, 107, 15, 125, 85, 10, 65, 54, 88,
+    20, 106, 46, 66, 8, 116, 29, 61, 99, 80, 90, 37, 123,
+    51, 25, 84, 129, 56, 4, 109, 96, 28, 36, 47, 11, 71,
+    33, 102, 21, 9, 17, 49, 124, 79, 64, 91, 42, 69, 53,
+    60, 14, 1, 27, 103, 126, 75, 89, 50, 120, 19, 32, 110,
+    92, 111, 130, 59, 31, 12, 81, 43, 55, 5, 74, 22, 101,
+    128, 58, 118, 48, 108, 38, 98, 93, 23, 83, 13, 73, 3,
+];
+
+lazy_static! {
+    /// Characters of codewords 0 to 1286.
+    static ref IMB_5OF13: Vec<u16> = nof13_table(5, 1287);
+    /// Characters of codewords 1287 to 1364.
+    static ref IMB_2OF13: Vec<u16> = nof13_table(2, 78);
+}
 
-    let res = CLI.get(&url).send().await?.json::<ImbResponse>().await?;
+/// Encodes mailing information to characters
+/// `F`,`A`,`D`,`T`
+/// without the USPS encoder, per USPS-B-3200.
+pub fn encode_barcode_local(
+    barcode_id: &str,
+    service_id: &str, // STID
+    mailer_id: &str,
+    serial_id: &str,
+    routing_code: &str,
+) -> Result<String> {
+    // Validate input.
+    validate_imb_fields(barcode_id, service_id, mailer_id, serial_id, routing_code)?;
+
+    // Convert the routing code and tracking code to a binary value.
+    let mut val: u128 = match routing_code.len() {
+        0 => 0,
+        5 => routing_code.parse::<u128>()? + 1,
+        9 => routing_code.parse::<u128>()? + 100_001,
+        _ => routing_code.parse::<u128>()? + 1_000_100_001,
+    };
+    let trk = format!("{barcode_id}{service_id}{mailer_id}{serial_id}");
+    let dgts: Vec<u128> = trk.bytes().map(|b| (b - b'0') as u128).collect();
+    val = val * 10 + dgts[0];
+    val = val * 5 + dgts[1];
+    for dgt in &dgts[2..] {
+        val = val * 10 + dgt;
+    }
+    let fcs = imb_crc11(val);
 
-    if res.code != "00" {
-        return Err(anyhow!("Error from API: {}", res.code));
+    // Convert the binary value to codewords.
+    let mut cws = [0usize; 10];
+    cws[9] = (val % 636) as usize;
+    val /= 636;
+    for cw in cws[1..9].iter_mut().rev() {
+        *cw = (val % 1365) as usize;
+        val /= 1365;
+    }
+    cws[0] = val as usize;
+    cws[9] *= 2;
+    if fcs & 0x400 != 0 {
+        cws[0] += 659;
+    }
+
+    // Convert codewords to characters, flipping those marked by the frame check sequence.
+    let mut chrs = cws.map(|cw| {
+        if cw < 1287 {
+            IMB_5OF13[cw]
+        } else {
+            IMB_2OF13[cw - 1287]
+        }
+    });
+    for (idx, chr) in chrs.iter_mut().enumerate() {
+        if fcs >> idx & 1 == 1 {
+            *chr ^= 0x1FFF;
+        }
+    }
+
+    // Map character bits to descenders and ascenders.
+    let mut bars = [(false, false); IMB_BAR_CNT];
+    for (idx, chr) in chrs.iter().enumerate() {
+        for bit in 0..13 {
+            let is_set = chr >> bit & 1 == 1;
+            let slot = IMB_BAR_MAP[13 * idx + bit] as usize - 1;
+            if slot < IMB_BAR_CNT {
+                bars[slot].0 = is_set;
+            } else {
+                bars[slot - IMB_BAR_CNT].1 = is_set;
+            }
+        }
     }
+    Ok(bars
+        .iter()
+        .map(|bar| match bar {
+            (true, true) => 'F',
+            (false, true) => 'A',
+            (true, false) => 'D',
+            (false, false) => 'T',
+        })
+        .collect())
+}
 
-    // Return the encoding.
-    Ok(res.imb)
+/// Frame check sequence of the 102 bit binary value of an IMb.
+fn imb_crc11(val: u128) -> u16 {
+    let bytes = val.to_be_bytes();
+    let mut fcs: u16 = 0x07FF;
+    for (idx, byte) in bytes[3..].iter().enumerate() {
+        // The two most significant bits of the first byte are unused.
+        let (mut data, bits) = if idx == 0 {
+            ((*byte as u16) << 5, 2)
+        } else {
+            ((*byte as u16) << 3, 0)
+        };
+        for _ in bits..8 {
+            fcs = if (fcs ^ data) & 0x400 != 0 {
+                (fcs << 1) ^ IMB_CRC_POLY
+            } else {
+                fcs << 1
+            } & 0x7FF;
+            data <<= 1;
+        }
+    }
+    fcs
 }
-#[derive(Deserialize)]
-struct ImbResponse {
-    code: String,
-    imb: String,
+
+/// Characters of 13 bits with `n` set, pairing each with its reverse.
+///
+/// Palindromes fill the table from the end.
+fn nof13_table(n: u32, len: usize) -> Vec<u16> {
+    let mut tbl = vec![0u16; len];
+    let (mut lo, mut hi) = (0, len - 1);
+    for chr in 0u16..8192 {
+        if chr.count_ones() != n {
+            continue;
+        }
+        let rev = chr.reverse_bits() >> 3;
+        if rev < chr {
+            continue;
+        }
+        if rev == chr {
+            tbl[hi] = chr;
+            hi -= 1;
+        } else {
+            tbl[lo] = chr;
+            tbl[lo + 1] = rev;
+            lo += 2;
+        }
+    }
+    tbl
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::test_support::*;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    fn adr(address1: &str, city: &str, state: &str, zip5: u32) -> Address {
+        Address {
+            address1: address1.into(),
+            city: city.into(),
+            state: state.into(),
+            zip5,
+            ..Default::default()
+        }
+    }
+
+    /// A standardizer uppercasing streets, failing on `BAD`, and counting calls.
+    struct MockStdzr {
+        calls: std::cell::Cell<usize>,
+    }
+
+    impl Standardizer for MockStdzr {
+        async fn standardize(&self, adr: &mut Address) -> Result<()> {
+            self.calls.set(self.calls.get() + 1);
+            if adr.address1.contains("BAD") {
+                return Err(not_found("No address found in the USPS response."));
+            }
+            if adr.address1.contains("DOWN") {
+                return Err(anyhow!("failed after 3 attempts: status 503"));
+            }
+            adr.address1 = adr.address1.to_uppercase();
+            adr.zip4 = 1234;
+            Ok(())
+        }
+    }
+
+    /// A standardizer dropping the zip before failing, as the USPS fallback does.
+    struct DropZipStdzr;
+
+    impl Standardizer for DropZipStdzr {
+        async fn standardize(&self, adr: &mut Address) -> Result<()> {
+            adr.zip5 = 0;
+            Err(not_found("No address found in the USPS response."))
+        }
+    }
+
+    /// Serves requests slowly, recording the most requests in flight at once.
+    fn serve_slow(cnt: usize) -> (String, Arc<AtomicUsize>) {
+        use std::io::{Read, Write};
+        let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        let url = format!("http://{}/zip", listener.local_addr().unwrap());
+        let in_flight = Arc::new(AtomicUsize::new(0));
+        let max_in_flight = Arc::new(AtomicUsize::new(0));
+        let max_srv = max_in_flight.clone();
+        std::thread::spawn(move || {
+            for _ in 0..cnt {
+                let (mut stream, _) = listener.accept().unwrap();
+                let in_flight = in_flight.clone();
+                let max_in_flight = max_srv.clone();
+                std::thread::spawn(move || {
+                    let mut buf = [0u8; 4096];
+                    let _ = stream.read(&mut buf);
+                    let cur = in_flight.fetch_add(1, Ordering::SeqCst) + 1;
+                    max_in_flight.fetch_max(cur, Ordering::SeqCst);
+                    std::thread::sleep(std::time::Duration::from_millis(50));
+                    in_flight.fetch_sub(1, Ordering::SeqCst);
+                    let resp =
+                        "HTTP/1.1 200 OK\r\nContent-Length: 2\r\nConnection: close\r\n\r\nok";
+                    stream.write_all(resp.as_bytes()).unwrap();
+                });
+            }
+        });
+        (url, max_in_flight)
+    }
+
+    /// Serves canned USPS replies in order, one per connection.
+    fn serve_replies(replies: Vec<String>) -> String {
+        use std::io::{Read, Write};
+        let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        let url = format!("http://{}/zip", listener.local_addr().unwrap());
+        std::thread::spawn(move || {
+            for reply in replies {
+                let (mut stream, _) = listener.accept().unwrap();
+                let mut buf = [0u8; 4096];
+                let _ = stream.read(&mut buf);
+                let resp = format!(
+                    "HTTP/1.1 200 OK\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{reply}",
+                    reply.len()
+                );
+                stream.write_all(resp.as_bytes()).unwrap();
+            }
+        });
+        url
+    }
+
+    /// A USPS reply of a single address.
+    fn reply(address1: &str, city: &str, state: &str, zip5: &str) -> String {
+        format!(
+            r#"{{"resultStatus":"SUCCESS","addressList":[{{"addressLine1":"{address1}","city":"{city}","state":"{state}","zip5":"{zip5}","zip4":"1234","deliveryPoint":"01"}}]}}"#
+        )
+    }
+
+    #[tokio::test]
+    async fn test_std_state_changed() {
+        let fallback = |url: String| UspsFallback {
+            cli: Arc::new(UspsClient {
+                url,
+                ..UspsClient::new(CLI.clone(), 1, std::time::Duration::ZERO)
+            }),
+        };
+        let divergent = reply("100 MAIN ST", "MAIN", "ME", "04401");
+
+        // A reply in another state falls through to the next approach.
+        let url = serve_replies(vec![
+            divergent.clone(),
+            reply("100 MAIN ST STE 2", "AKRON", "OH", "44308"),
+        ]);
+        let mut ohio = adr("100 MAIN ST", "AKRON", "OH", 44308);
+        ohio.address2 = Some("STE 2".into());
+        fallback(url).standardize(&mut ohio).await.unwrap();
+        assert_eq!(ohio.state, "OH");
+        assert_eq!(ohio.address1, "100 MAIN ST STE 2");
+
+        // Replies in another state from every approach name both addresses.
+        let url = serve_replies(vec![divergent.clone(); 3]);
+        let mut ohio = adr("100 MAIN ST", "AKRON", "OH", 44308);
+        let err = fallback(url)
+            .standardize(&mut ohio)
+            .await
+            .unwrap_err()
+            .to_string();
+        assert!(err.contains("AKRON,OH"), "{err}");
+        assert!(err.contains("MAIN,ME"), "{err}");
+        assert_eq!(ohio.state, "OH");
+
+        // Allowed changes are kept.
+        let cases = [
+            ("OH", "OH", true),
+            ("", "OH", true),
+            ("CM", "MP", true),
+            ("OH", "ME", false),
+        ];
+        for (state, std_state, expected) in cases {
+            let parsed = adr("100 MAIN ST", "AKRON", state, 44308);
+            let std_adr = adr("100 MAIN ST", "AKRON", std_state, 44308);
+            assert_eq!(
+                check_std_state(&parsed, &std_adr).is_ok(),
+                expected,
+                "Failed to match: {}",
+                state
+            );
+        }
+    }
+
+    #[tokio::test]
+    async fn test_usps_client_permits() {
+        let cnt = 8;
+        let (url, max_in_flight) = serve_slow(cnt);
+        let usps = UspsClient::new(CLI.clone(), 2, std::time::Duration::ZERO);
+        let resps =
+            futures_util::future::join_all((0..cnt).map(|_| usps.send(&url, |cli| cli.get(&url))))
+                .await;
+        assert!(resps.iter().all(|res| res.as_deref().ok() == Some("ok")));
+        assert_eq!(max_in_flight.load(Ordering::SeqCst), 2);
+    }
+
+    #[tokio::test]
+    async fn test_usps_client_interval() {
+        let cnt = 4;
+        let (url, max_in_flight) = serve_slow(cnt);
+        let interval = std::time::Duration::from_millis(100);
+        let usps = UspsClient::new(CLI.clone(), cnt, interval);
+        let start = Instant::now();
+        let resps =
+            futures_util::future::join_all((0..cnt).map(|_| usps.send(&url, |cli| cli.get(&url))))
+                .await;
+        assert!(resps.iter().all(|res| res.is_ok()));
+
+        // Requests start an interval apart, so fewer overlap than permitted.
+        assert!(start.elapsed() >= interval * (cnt as u32 - 1));
+        assert!(max_in_flight.load(Ordering::SeqCst) < cnt);
+    }
+
+    #[tokio::test]
+    async fn test_std_or_keep_territory() {
+        let cache = Mutex::new(UspsCache::default());
+        let now = NaiveDateTime::parse_from_str("2026-10-01 12:00", "%Y-%m-%d %H:%M").unwrap();
+
+        // A Guam caller box is kept as parsed, from the USPS and then the cache.
+        for _ in 0..2 {
+            let mut guam = adr("CALLER BOX 25001", "HAGATNA", "GU", 96932);
+            std_or_keep(&cache, &mut guam, &DropZipStdzr, now)
+                .await
+                .unwrap();
+            assert_eq!(guam.zip5, 96932);
+            assert!(!guam.standardized);
+        }
+
+        // Other states still fail.
+        let mut ohio = adr("9 BAD ST", "AKRON", "OH", 44308);
+        assert!(std_or_keep(&cache, &mut ohio, &DropZipStdzr, now)
+            .await
+            .is_err());
+
+        // The mailpiece is routed by zip5 alone.
+        let mut guam = adr("CALLER BOX 25001", "HAGATNA", "GU", 96932);
+        std_or_keep(&cache, &mut guam, &DropZipStdzr, now)
+            .await
+            .unwrap();
+        guam.zip4 = 5001;
+        let per = Person {
+            name: "Jane Doe".into(),
+            ..Default::default()
+        };
+        let mp = crate::mailing::new_mailpiece(&per, &guam);
+        assert_eq!(mp.delivery_point, None);
+        assert_eq!(mp.routing_code(), "96932");
+
+        let mut dc = adr("1 FIRST ST SE", "WASHINGTON", "DC", 20515);
+        dc.zip4 = 6301;
+        dc.delivery_point = Some("01".into());
+        let mp = crate::mailing::new_mailpiece(&per, &dc);
+        assert_eq!(mp.routing_code(), "20515630101");
+
+        // Only unstandardized addresses serialize the flag.
+        let json = serde_json::to_string(&guam).unwrap();
+        assert!(json.contains(r#""standardized":false"#));
+        assert!(!serde_json::from_str::<Address>(&json).unwrap().standardized);
+        let json = serde_json::to_string(&dc).unwrap();
+        assert!(!json.contains("standardized"));
+        assert!(serde_json::from_str::<Address>(&json).unwrap().standardized);
+    }
+
+    #[tokio::test]
+    async fn test_std_cached() {
+        let cache = Mutex::new(UspsCache::default());
+        let stdzr = MockStdzr {
+            calls: std::cell::Cell::new(0),
+        };
+        let now = NaiveDateTime::parse_from_str("2026-10-01 12:00", "%Y-%m-%d %H:%M").unwrap();
+        let adrs = vec![
+            adr("100 Main St", "DAYTON", "OH", 45402),
+            adr("9 BAD ST", "AKRON", "OH", 44308),
+        ];
+        let run = |now: NaiveDateTime| {
+            let (cache, stdzr, adrs) = (&cache, &stdzr, adrs.clone());
+            async move {
+                let mut ret = Vec::new();
+                for mut adr in adrs {
+                    let res = std_cached(cache, &mut adr, stdzr, now).await;
+                    ret.push(res.map(|_| adr));
+                }
+                ret
+            }
+        };
+
+        // A cold cache calls the USPS for each address.
+        let cold = run(now).await;
+        assert_eq!(stdzr.calls.get(), 2);
+        assert_eq!(cold[0].as_ref().unwrap().address1, "100 MAIN ST");
+        assert!(cold[1].is_err());
+
+        // A firm is ignored by the USPS and kept.
+        let mut firm = adr("100 Main St", "DAYTON", "OH", 45402);
+        firm.firm = Some("SILVER BOW CENTER".into());
+        std_cached(&cache, &mut firm, &stdzr, now).await.unwrap();
+        assert_eq!(stdzr.calls.get(), 2);
+        assert_eq!(firm.firm.as_deref(), Some("SILVER BOW CENTER"));
+        assert_eq!(firm.address1, "100 MAIN ST");
+
+        // A warm cache calls nothing, returning the same results and failures.
+        let warm = run(now + Duration::days(1)).await;
+        assert_eq!(stdzr.calls.get(), 2);
+        assert_eq!(warm[0].as_ref().unwrap(), cold[0].as_ref().unwrap());
+        let err = warm[1].as_ref().unwrap_err().to_string();
+        assert!(err.starts_with("cached USPS failure"), "{err}");
+
+        // Expired entries are refetched.
+        run(now + Duration::days(USPS_CACHE_TTL_DAYS)).await;
+        assert_eq!(stdzr.calls.get(), 4);
+
+        // Failed requests aren't cached.
+        let mut down = adr("1 DOWN ST", "AKRON", "OH", 44308);
+        for _ in 0..2 {
+            assert!(std_cached(&cache, &mut down, &stdzr, now).await.is_err());
+        }
+        assert_eq!(stdzr.calls.get(), 6);
+        assert!(cache.lock().unwrap().get(&down, now).is_none());
+        assert!(cache.lock().unwrap().is_dirty);
+    }
+
+    #[test]
+    fn test_usps_cache_purge() {
+        let dir = TempDir::new("usps_cache");
+        let pth = dir.pth("usps_cache.json");
+
+        let mut cache = UspsCache::default();
+        cache.entries.insert(
+            "100 MAIN ST||DAYTON|OH|45402|0000".into(),
+            UspsCacheEntry {
+                at: Local::now().naive_local(),
+                adr: None,
+                err: Some("No address found in the USPS response.".into()),
+            },
+        );
+        cache.save(&pth).unwrap();
+        assert_eq!(UspsCache::load(&pth).unwrap().entries, cache.entries);
+
+        assert_eq!(UspsCache::purge(&pth).unwrap(), 1);
+        assert!(!data_pth(&pth).exists());
+        assert!(UspsCache::load(&pth).unwrap().entries.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_encode_cached() {
+        let dir = TempDir::new("barcode_cache");
+        let pth = dir.pth("barcode_cache.json");
+        let calls = AtomicUsize::new(0);
+        let qry = "0030112345678900000136608440225";
+        let encode = || async {
+            calls.fetch_add(1, Ordering::SeqCst);
+            encode_barcode_local("00", "301", "123456789", "000001", "36608440225")
+        };
+
+        // A cold cache encodes, saving once when asked.
+        let cache = Mutex::new(BarcodeCache::load(&pth).unwrap());
+        let imb = encode_cached(&cache, qry, false, encode).await.unwrap();
+        assert_eq!(calls.load(Ordering::SeqCst), 1);
+        assert!(!data_pth(&pth).exists());
+        BarcodeCache::save_dirty(&cache, &pth).unwrap();
+        assert!(!cache.lock().unwrap().is_dirty);
+
+        // A rerun from the saved cache encodes nothing and stays clean.
+        let cache = Mutex::new(BarcodeCache::load(&pth).unwrap());
+        let warm = encode_cached(&cache, qry, false, encode).await.unwrap();
+        assert_eq!(warm, imb);
+        assert_eq!(calls.load(Ordering::SeqCst), 1);
+        assert!(!cache.lock().unwrap().is_dirty);
+
+        // Bypassing encodes again.
+        encode_cached(&cache, qry, true, encode).await.unwrap();
+        assert_eq!(calls.load(Ordering::SeqCst), 2);
+
+        // A full cache stops growing.
+        let mut full = BarcodeCache::default();
+        for idx in 0..BARCODE_CACHE_MAX {
+            assert!(full.insert(&idx.to_string(), "F"));
+        }
+        assert!(!full.insert(qry, &imb));
+        assert!(full.insert("0", "A"));
+    }
+
+    #[test]
+    fn test_dedup_order_addresses() {
+        let adrs = vec![
+            adr("100 MAIN ST", "DAYTON", "OH", 45402),
+            adr("9 ELM ST", "AKRON", "OH", 44308),
+            adr("2312 RHOB", "WASHINGTON", "DC", 20515),
+            adr("100  Main St", "Dayton", "OH", 45402),
+            adr("1 CENTRAL AVE", "AKRON", "OH", 44308),
+            adr("2312 RHOB", "WASHINGTON", "DC", 20515),
+        ];
+        let mut adrs = dedup_addresses(adrs);
+        assert_eq!(adrs.len(), 4);
+        // Input order preserved.
+        assert_eq!(adrs[0].address1, "100 MAIN ST");
+        assert_eq!(adrs[2].address1, "2312 RHOB");
+
+        order_addresses(&mut adrs);
+        assert_eq!(
+            adrs.iter().map(|a| a.address1.as_str()).collect::<Vec<_>>(),
+            vec!["2312 RHOB", "9 ELM ST", "1 CENTRAL AVE", "100 MAIN ST"]
+        );
+
+        // Same result regardless of input order.
+        let mut rev = dedup_addresses(adrs.iter().rev().cloned().collect());
+        order_addresses(&mut rev);
+        assert_eq!(rev[0].address1, "2312 RHOB");
+        assert_eq!(rev[3].address1, "100 MAIN ST");
+    }
+
+    #[test]
+    fn test_dedup_addresses_counties() {
+        let mut a = adr("100 MAIN ST", "DAYTON", "OH", 45402);
+        a.counties = vec!["MONTGOMERY".into()];
+        let mut b = a.clone();
+        b.counties = vec!["GREENE".into(), "MONTGOMERY".into()];
+        let mut c = a.clone();
+        c.zip4 = 1234;
+        let adrs = dedup_addresses(vec![a, b, c]);
+        assert_eq!(adrs.len(), 1);
+        assert_eq!(adrs[0].zip4, 1234);
+        assert_eq!(adrs[0].counties, vec!["MONTGOMERY", "GREENE"]);
+    }
+
+    #[test]
+    fn test_dedup_addresses_zip4() {
+        // Identical but for zip4 and delivery point, keeping the detailed one.
+        let a = adr("100 MAIN ST", "DAYTON", "OH", 45402);
+        let mut b = adr("100 Main St", "Dayton", "OH", 45402);
+        b.zip4 = 6789;
+        b.delivery_point = Some("01".into());
+        let adrs = dedup_addresses(vec![a.clone(), b.clone()]);
+        assert_eq!(adrs, vec![b.clone()]);
+        let adrs = dedup_addresses(vec![b.clone(), a.clone()]);
+        assert_eq!(adrs, vec![b.clone()]);
+
+        // Different suites at the same street survive.
+        let mut ste1 = a.clone();
+        ste1.address2 = Some("STE 100".into());
+        let mut ste2 = b.clone();
+        ste2.address2 = Some("STE 200".into());
+        let adrs = dedup_addresses(vec![ste1.clone(), ste2.clone(), a.clone()]);
+        assert_eq!(adrs, vec![ste1, ste2, a]);
+    }
+
+    #[test]
+    fn test_std_sob() {
+        let cases = vec![
+            ("141 HSOB", "141 HSOB"),
+            ("141 HART SOB", "141 HSOB"),
+            ("SD-G12 DIRKSEN SOB", "G12 DSOB"),
+            ("SR-127A RUSSELL SENATE OFFICE BUILDING", "127A RSOB"),
+            ("530 Hart Senate Office Bldg.", "530 HSOB"),
+        ];
+        for (address1, expected) in cases {
+            let mut std = adr(address1, "", "", 0);
+            assert!(std_sob(&mut std), "Failed to match: {}", address1);
+            assert_eq!(std, adr(expected, "WASHINGTON", "DC", ZIP_SOB));
+            // Standardized addresses round-trip.
+            let mut again = std.clone();
+            assert!(std_sob(&mut again));
+            assert_eq!(again, std, "Failed to match: {}", address1);
+        }
+
+        // Other addresses go to the USPS.
+        for (address1, state) in [
+            ("2312 RHOB", "DC"),
+            ("HART SOB", "DC"),
+            ("141 HSOB", "OH"),
+            ("100 MAIN ST", ""),
+        ] {
+            let mut other = adr(address1, "", state, 0);
+            assert!(!std_sob(&mut other), "Failed to match: {}", address1);
+        }
+    }
 
     #[tokio::test]
     async fn test_valid_barcode() {
@@ -315,4 +1572,46 @@ mod tests {
         let result = encode_barcode(barcode_id, service_id, mailer_id, serial_id, zip_code).await;
         assert!(result.is_err());
     }
+
+    #[test]
+    fn test_encode_barcode_local() {
+        // USPS-B-3200 Appendix C test vectors.
+        let cases = [
+            (
+                "",
+                "ATTFATTDTTADTAATTDTDTATTDAFDDFADFDFTFFFFFTATFAAAATDFFTDAADFTFDTDT",
+            ),
+            (
+                "01234",
+                "DTTAFADDTTFTDTFTFDTDDADADAFADFATDDFTAAAFDTTADFAAATDFDTDFADDDTDFFT",
+            ),
+            (
+                "012345678",
+                "ADFTTAFDTTTTFATTADTAAATFTFTATDAAAFDDADATATDTDTTDFDTDATADADTDFFTFA",
+            ),
+            (
+                "01234567891",
+                "AADTFFDFTDADTAADAATFDTDDAAADDTDTTDAFADADDDTFFFDDTTTADFAAADFTDAADA",
+            ),
+        ];
+        for (routing_code, expected) in cases {
+            let encoded =
+                encode_barcode_local("01", "234", "567094987", "654321", routing_code).unwrap();
+            assert_eq!(encoded, expected, "Failed to match: {}", routing_code);
+            assert!(validate_imb(&encoded).is_ok());
+        }
+
+        assert!(encode_barcode_local("01", "234", "567094987", "654321", "0123").is_err());
+    }
+
+    #[test]
+    fn test_validate_imb() {
+        let valid = "ATTFATTDTTADTAATTDTDTATTDAFDDFADFDFTFFFFFTATFAAAATDFFTDAADFTFDTDT";
+        assert!(validate_imb(valid).is_ok());
+        assert!(validate_imb(&valid[1..]).is_err());
+        assert!(validate_imb(&format!("{valid}T")).is_err());
+        assert!(validate_imb(&valid.replace('F', "X")).is_err());
+        assert!(validate_imb(&valid.to_lowercase()).is_err());
+        assert!(validate_imb("").is_err());
+    }
 }
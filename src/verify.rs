@@ -0,0 +1,288 @@
+use crate::io::*;
+use crate::models::*;
+use crate::usps::*;
+use anyhow::{anyhow, Result};
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use tracing::info;
+
+/// Report file of the verify command.
+pub const FLE_PTH_VERIFY: &str = "verify_report.json";
+
+/// How a cached address differs from its USPS standardization now.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub enum AdrChange {
+    /// Standardized fields changed, such as `zip4`.
+    Changed { fields: Vec<String> },
+    /// Standardization now fails.
+    Failing { err: String },
+}
+
+/// A cached address of a person and its standardization now.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct AddressDiff {
+    pub name: String,
+    pub old: Address,
+    /// Same as `old` for a failing address.
+    pub new: Address,
+    pub change: AdrChange,
+}
+impl fmt::Display for AddressDiff {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match &self.change {
+            AdrChange::Changed { fields } => {
+                write!(f, "changed {} ({}):", self.name, fields.join(", "))?;
+                write!(f, "\n  - {}\n  + {}", self.old, self.new)
+            }
+            AdrChange::Failing { err } => {
+                write!(f, "failing {}: {err}\n  {}", self.name, self.old)
+            }
+        }
+    }
+}
+
+/// Re-standardizes cached addresses of persons with the USPS, bypassing the USPS cache.
+pub async fn verify_addresses(pers: &[Person]) -> Vec<AddressDiff> {
+    verify_addresses_with(pers, &UspsFallback::default()).await
+}
+
+/// Re-standardizes cached addresses of persons, returning the differences.
+///
+/// Foreign addresses are skipped and Senate office buildings are verified locally.
+/// An unstandardized address still failing is unchanged.
+pub async fn verify_addresses_with<S: Standardizer>(
+    pers: &[Person],
+    stdzr: &S,
+) -> Vec<AddressDiff> {
+    let mut diffs = Vec::new();
+    for per in pers {
+        for old in per.adrs.iter().flatten().filter(|adr| !adr.is_foreign()) {
+            let mut new = old.clone();
+            if !std_sob(&mut new) {
+                if let Err(err) = stdzr.standardize(&mut new).await {
+                    if old.standardized {
+                        diffs.push(AddressDiff {
+                            name: per.name.clone(),
+                            old: old.clone(),
+                            new: old.clone(),
+                            change: AdrChange::Failing {
+                                err: err.to_string(),
+                            },
+                        });
+                    }
+                    continue;
+                }
+                new.standardized = true;
+            }
+            let fields = changed_fields(old, &new);
+            if !fields.is_empty() {
+                diffs.push(AddressDiff {
+                    name: per.name.clone(),
+                    old: old.clone(),
+                    new,
+                    change: AdrChange::Changed { fields },
+                });
+            }
+        }
+    }
+    diffs
+}
+
+/// Names of the standardized fields differing between addresses.
+fn changed_fields(old: &Address, new: &Address) -> Vec<String> {
+    [
+        ("address1", old.address1 != new.address1),
+        ("address2", old.address2 != new.address2),
+        ("city", old.city != new.city),
+        ("state", old.state != new.state),
+        ("zip5", old.zip5 != new.zip5),
+        ("zip4", old.zip4 != new.zip4),
+        ("delivery_point", old.delivery_point != new.delivery_point),
+        ("standardized", old.standardized != new.standardized),
+    ]
+    .into_iter()
+    .filter(|(_, is_changed)| *is_changed)
+    .map(|(field, _)| field.to_string())
+    .collect()
+}
+
+/// Replaces changed addresses of persons; failing addresses are kept.
+///
+/// Returns the count of replaced addresses.
+pub fn apply_diffs(pers: &mut [Person], diffs: &[AddressDiff]) -> usize {
+    let mut cnt = 0;
+    for diff in diffs
+        .iter()
+        .filter(|diff| matches!(diff.change, AdrChange::Changed { .. }))
+    {
+        for per in pers.iter_mut().filter(|per| per.name == diff.name) {
+            for adr in per.adrs.iter_mut().flatten() {
+                if *adr == diff.old {
+                    *adr = diff.new.clone();
+                    cnt += 1;
+                }
+            }
+        }
+    }
+    cnt
+}
+
+/// Verifies the addresses of person files, such as `house.json`.
+///
+/// Applying rewrites the persons of a file with changes, keeping its other fields.
+/// Missing files are skipped.
+pub async fn verify_files<S: Standardizer>(
+    pths: &[&str],
+    is_apply: bool,
+    stdzr: &S,
+) -> Result<Vec<AddressDiff>> {
+    let mut diffs = Vec::new();
+    for pth in pths.iter().filter(|pth| data_pth(pth).exists()) {
+        info!("verify {pth}");
+        let mut per_file: serde_json::Value = read_from_file(pth)?;
+        let mut pers: Vec<Person> = serde_json::from_value(per_file["persons"].take())
+            .map_err(|err| anyhow!("verify: person file '{pth}': {err}"))?;
+        let file_diffs = verify_addresses_with(&pers, stdzr).await;
+        if is_apply && apply_diffs(&mut pers, &file_diffs) > 0 {
+            per_file["persons"] = serde_json::to_value(&pers)?;
+            write_to_file(&per_file, pth)?;
+        }
+        diffs.extend(file_diffs);
+    }
+    Ok(diffs)
+}
+
+/// Lines of the run summary.
+pub fn verify_lnes(diffs: &[AddressDiff]) -> Vec<String> {
+    let mut lnes = vec![format!("{} changes", diffs.len())];
+    for diff in diffs {
+        lnes.extend(diff.to_string().lines().map(String::from));
+    }
+    lnes
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::*;
+
+    /// A standardizer adding a zip4, failing for addresses containing "BAD".
+    struct MockStdzr {
+        zip4: u16,
+    }
+
+    impl Standardizer for MockStdzr {
+        async fn standardize(&self, adr: &mut Address) -> Result<()> {
+            if adr.address1.contains("BAD") {
+                return Err(anyhow!("No address found in the USPS response."));
+            }
+            adr.zip4 = self.zip4;
+            Ok(())
+        }
+    }
+
+    fn per(name: &str, address1: &str, zip4: u16) -> Person {
+        Person {
+            name: name.into(),
+            adrs: Some(vec![Address {
+                address1: address1.into(),
+                city: "ALBANY".into(),
+                state: "NY".into(),
+                zip5: 12224,
+                zip4,
+                ..Default::default()
+            }]),
+            ..Default::default()
+        }
+    }
+
+    #[tokio::test]
+    async fn test_verify_addresses() {
+        // Unchanged addresses.
+        let pers = vec![
+            per("Jane Doe", "1 STATE ST", 1234),
+            per("John Public", "2 STATE ST", 1234),
+        ];
+        let diffs = verify_addresses_with(&pers, &MockStdzr { zip4: 1234 }).await;
+        assert!(diffs.is_empty());
+        assert_eq!(verify_lnes(&diffs), vec!["0 changes"]);
+
+        // A seeded zip4 change and a failing address.
+        let mut pers = vec![
+            per("Jane Doe", "1 STATE ST", 1111),
+            per("John Public", "2 BAD ST", 1234),
+            per("Sam Roe", "3 STATE ST", 1234),
+        ];
+        let diffs = verify_addresses_with(&pers, &MockStdzr { zip4: 1234 }).await;
+        assert_eq!(diffs.len(), 2);
+        assert_eq!(diffs[0].name, "Jane Doe");
+        assert_eq!(
+            diffs[0].change,
+            AdrChange::Changed {
+                fields: vec!["zip4".into()]
+            }
+        );
+        assert_eq!(diffs[0].new.zip4, 1234);
+        assert_eq!(diffs[1].name, "John Public");
+        assert!(matches!(diffs[1].change, AdrChange::Failing { .. }));
+        assert_eq!(verify_lnes(&diffs)[0], "2 changes");
+
+        // Applying replaces changed addresses only.
+        assert_eq!(apply_diffs(&mut pers, &diffs), 1);
+        assert_eq!(pers[0].adrs.as_ref().unwrap()[0].zip4, 1234);
+        assert_eq!(pers[1].adrs.as_ref().unwrap()[0].address1, "2 BAD ST");
+        let diffs = verify_addresses_with(&pers[..1], &MockStdzr { zip4: 1234 }).await;
+        assert!(diffs.is_empty());
+
+        // An unstandardized address still failing is unchanged.
+        pers[1].adrs.as_mut().unwrap()[0].standardized = false;
+        let diffs = verify_addresses_with(&pers[1..2], &MockStdzr { zip4: 1234 }).await;
+        assert!(diffs.is_empty());
+
+        // Senate office buildings are verified locally.
+        let mut sob = per("Sam Roe", "141 HSOB", 0);
+        let adr = &mut sob.adrs.as_mut().unwrap()[0];
+        adr.state = "DC".into();
+        assert!(std_sob(adr));
+        let diffs = verify_addresses_with(&[sob], &MockStdzr { zip4: 1234 }).await;
+        assert!(diffs.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_verify_files() {
+        let dir = TempDir::new("verify");
+        let pth = dir.pth("house.json");
+        let missing = dir.pth("state.json");
+        let per_file = serde_json::json!({
+            "persons": [per("Jane Doe", "1 STATE ST", 1111)],
+            "office": "HOUSE",
+        });
+        write_to_file(&per_file, &pth).unwrap();
+        let stdzr = MockStdzr { zip4: 1234 };
+
+        // Without applying, the file is unchanged.
+        let diffs = verify_files(&[&pth, &missing], false, &stdzr)
+            .await
+            .unwrap();
+        assert_eq!(diffs.len(), 1);
+        assert_eq!(
+            verify_lnes(&diffs),
+            vec![
+                "1 changes",
+                "changed Jane Doe (zip4):",
+                "  - 1 STATE ST,,ALBANY,NY,12224,1111,",
+                "  + 1 STATE ST,,ALBANY,NY,12224,1234,",
+            ]
+        );
+        assert_eq!(read_from_file::<serde_json::Value>(&pth).unwrap(), per_file);
+
+        // Applying rewrites persons, keeping other fields.
+        let diffs = verify_files(&[&pth], true, &stdzr).await.unwrap();
+        assert_eq!(diffs.len(), 1);
+        let applied: serde_json::Value = read_from_file(&pth).unwrap();
+        assert_eq!(applied["office"], "HOUSE");
+        assert_eq!(applied["persons"][0]["adrs"][0]["zip4"], 1234);
+        let diffs = verify_files(&[&pth], true, &stdzr).await.unwrap();
+        assert_eq!(verify_lnes(&diffs), vec!["0 changes"]);
+    }
+}